@@ -0,0 +1,259 @@
+//! Members of uncompressed tar archives and of zip archives, addressed as
+//! `archive.tar::inner/path`.
+//!
+//! Members are read in place from the archive, so they can be checked and checksummed without
+//! extracting them first. Zip archives are recognized by their content, not their extension.
+
+use crate::scan;
+use crate::zip;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Separates the path of an archive from the path of a member inside it.
+pub const SEPARATOR: &str = "::";
+
+/// Splits `path` into the archive and the member path, if it refers to a member of an archive.
+///
+/// Paths containing the separator are only treated as archive members if the part before it is
+/// an existing file, so that plain files with `::` in their name can still be checked.
+pub fn split(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let path = path.to_str()?;
+    path.match_indices(SEPARATOR).find_map(|(i, _)| {
+        let archive = Path::new(&path[..i]);
+        let member = &path[i + SEPARATOR.len()..];
+        (archive.is_file() && !member.is_empty())
+            .then(|| (archive.to_path_buf(), PathBuf::from(member)))
+    })
+}
+
+/// Joins the path of an archive and a member into a job path.
+fn join(archive: &Path, member: &Path) -> PathBuf {
+    PathBuf::from(format!(
+        "{}{SEPARATOR}{}",
+        archive.display(),
+        member.display()
+    ))
+}
+
+/// Normalizes member paths, which are often stored with a leading `./`.
+fn normalize(member: &Path) -> &Path {
+    member.strip_prefix(".").unwrap_or(member)
+}
+
+fn not_found(archive: &Path, member: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "{} is not a regular file in archive {}",
+            member.display(),
+            archive.display()
+        ),
+    )
+}
+
+/// Returns the offset and size of the data of a regular file in a tar archive.
+fn locate(archive: &Path, member: &Path) -> io::Result<(u64, u64)> {
+    let mut tar = tar::Archive::new(fs::File::open(archive)?);
+    for entry in tar.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() && normalize(&entry.path()?) == normalize(member) {
+            return Ok((entry.raw_file_position(), entry.size()));
+        }
+    }
+    Err(not_found(archive, member))
+}
+
+/// Returns the entry of a regular file in a zip archive.
+fn locate_zip(archive: &Path, member: &Path) -> io::Result<zip::Entry> {
+    zip::entries(archive)?
+        .into_iter()
+        .find(|entry| entry.is_file() && normalize(Path::new(&entry.name)) == normalize(member))
+        .ok_or_else(|| not_found(archive, member))
+}
+
+/// Opens a file or a member of an archive for reading.
+pub fn open(path: &Path) -> io::Result<Box<dyn Read + Send>> {
+    match split(path) {
+        Some((archive, member)) if zip::is_zip(&archive)? => {
+            zip::open(&archive, &locate_zip(&archive, &member)?)
+        }
+        Some((archive, member)) => {
+            let (offset, size) = locate(&archive, &member)?;
+            let mut file = fs::File::open(&archive)?;
+            file.seek(SeekFrom::Start(offset))?;
+            Ok(Box::new(file.take(size)))
+        }
+        None => Ok(Box::new(open_scanned(path)?)),
+    }
+}
+
+/// Opens a file, verifying that it is still the file found there by `--scan`.
+fn open_scanned(path: &Path) -> io::Result<fs::File> {
+    let file = fs::File::open(path)?;
+    scan::verify_identity(path, &file)?;
+    Ok(file)
+}
+
+/// A file or a member of an archive opened for random access, e.g. to read a footer.
+pub struct Section {
+    file: fs::File,
+    start: u64,
+    size: u64,
+    position: u64,
+}
+
+impl Section {
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Reads `len` bytes at `offset`, failing if they extend beyond the end of the section.
+    pub fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        self.seek(SeekFrom::Start(offset))?;
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+impl Read for Section {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.size.saturating_sub(self.position);
+        let len = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        self.file
+            .seek(SeekFrom::Start(self.start + self.position))?;
+        let n = self.file.read(&mut buf[..len])?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for Section {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.size.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before the start"))?;
+        Ok(self.position)
+    }
+}
+
+/// Opens a file or a member of an archive for random access.
+pub fn open_section(path: &Path) -> io::Result<Section> {
+    let (file, start, size) = match split(path) {
+        Some((archive, member)) if zip::is_zip(&archive)? => {
+            let (offset, size) = zip::stored_range(&archive, &locate_zip(&archive, &member)?)?;
+            (fs::File::open(&archive)?, offset, size)
+        }
+        Some((archive, member)) => {
+            let (offset, size) = locate(&archive, &member)?;
+            (fs::File::open(&archive)?, offset, size)
+        }
+        None => {
+            let file = open_scanned(path)?;
+            let size = file.metadata()?.len();
+            (file, 0, size)
+        }
+    };
+    Ok(Section {
+        file,
+        start,
+        size,
+        position: 0,
+    })
+}
+
+/// Returns the size of a file or a member of an archive.
+pub fn size(path: &Path) -> io::Result<u64> {
+    match split(path) {
+        Some((archive, member)) if zip::is_zip(&archive)? => {
+            locate_zip(&archive, &member).map(|entry| entry.size)
+        }
+        Some((archive, member)) => locate(&archive, &member).map(|(_, size)| size),
+        None => fs::metadata(path).map(|metadata| metadata.len()),
+    }
+}
+
+/// Returns whether a path is a stream whose size is unknown until it is read, such as a pipe from
+/// stdin or from a download, instead of a regular file or a member of an archive.
+pub fn is_stream(path: &Path) -> bool {
+    split(path).is_none()
+        && fs::metadata(path).is_ok_and(|metadata| !metadata.is_file() && !metadata.is_dir())
+}
+
+/// Lists the regular files of an archive as job paths.
+pub fn members(archive: &Path) -> io::Result<Vec<PathBuf>> {
+    if zip::is_zip(archive)? {
+        return Ok(zip::entries(archive)?
+            .iter()
+            .filter(|entry| entry.is_file())
+            .map(|entry| join(archive, normalize(Path::new(&entry.name))))
+            .collect());
+    }
+    let mut tar = tar::Archive::new(fs::File::open(archive)?);
+    let mut members = Vec::new();
+    for entry in tar.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() {
+            members.push(join(archive, normalize(&entry.path()?)));
+        }
+    }
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_members_are_read_in_place() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let archive = dir.path().join("submission.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive)?);
+        for (name, content) in [("./files/a.txt", "first\n"), ("files/b.txt", "second\n")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, content.as_bytes())?;
+        }
+        builder.into_inner()?;
+
+        let members = members(&archive)?;
+        assert_eq!(
+            members,
+            vec![
+                join(&archive, Path::new("files/a.txt")),
+                join(&archive, Path::new("files/b.txt")),
+            ]
+        );
+
+        let mut content = String::new();
+        open(&members[1])?.read_to_string(&mut content)?;
+        assert_eq!(content, "second\n");
+        assert_eq!(size(&members[0])?, 6);
+        assert_eq!(size(&archive)?, fs::metadata(&archive)?.len());
+
+        let mut section = open_section(&members[1])?;
+        assert_eq!(section.size(), 7);
+        assert_eq!(section.read_at(2, 4)?, b"cond");
+        assert!(section.read_at(4, 4).is_err());
+
+        let missing = join(&archive, Path::new("files/c.txt"));
+        assert_eq!(
+            open(&missing).err().map(|e| e.kind()),
+            Some(io::ErrorKind::NotFound)
+        );
+        Ok(())
+    }
+}
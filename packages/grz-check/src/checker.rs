@@ -1,25 +1,89 @@
+use crate::archive;
 use crate::checks::bam::BamCheckJob;
-use crate::checks::common;
-use crate::checks::fastq::{PairedFastqJob, SingleFastqJob};
+use crate::checks::bed::BedCheckJob;
+use crate::checks::common::{self, CheckSettings, Compression, InflateBackend};
+use crate::checks::dependencies::{self, NotEvaluated};
+use crate::checks::fasta::FastaCheckJob;
+use crate::checks::fastq::{
+    AdapterScreening, BaseComposition, BaseCounts, InterleavedFastqJob, PairedFastqJob,
+    PolyGScreening, QualityStats, SingleFastqJob, TripleFastqJob,
+};
+use crate::checks::gzi::GziCheckJob;
 use crate::checks::raw::RawJob;
-use crate::checks::{bam, fastq, raw};
+use crate::checks::sam::SamCheckJob;
+use crate::checks::signal::SignalJob;
+use crate::checks::tabix::TabixCheckJob;
+use crate::checks::vcf::{GvcfStats, VcfCheckJob};
+use crate::checks::{bam, bed, fasta, fastq, gzi, raw, reference, sam, signal, tabix, vcf};
+use crate::checksum_db::ChecksumDb;
+use crate::checksum_registry::{self, ChecksumRegistry, Registry};
+use crate::consent::{self, DonorConsent};
+use crate::control::{ControlServer, ControlState, JobEntry, JobState};
+use crate::deadline::{Deadline, PendingJob};
+use crate::duplicates::{self, Aliases, DigestIndex};
+use crate::entity::{Entity, EntityKind};
+use crate::external::ExternalCheck;
+use crate::findings::{self, Finding, Severity};
+use crate::hooks::{PostCheckHook, Staged, StagingHook};
+use crate::lab_data::{FastqPair, LabDataTotals, LabDatum, ReadGroupMapping};
+use crate::line_format::ParseLeniency;
+use crate::logging::{self, LogFormat};
+use crate::md5_sidecar;
+use crate::merkle::MerkleDigests;
+use crate::mounts::{MountLimit, Mounts};
+use crate::path_constraints::PathConstraints;
+use crate::progress::{self, RecordProgress};
+use crate::quarantine::Quarantine;
+use crate::report;
+use crate::scan;
+use crate::schedule::{Queue, Schedule};
+use crate::sha256::{ChunkDigests, DigestEncoding, FileDigests, FileHasher};
+use crate::suppress::{self, Suppression};
+use crate::systemd::{self, Heartbeat};
+use crate::timing::Timings;
+use crate::umi::UmiCheck;
 use anyhow::Context;
+use chrono::{DateTime, SecondsFormat, Utc};
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use serde::Serialize;
-use sha2::{Digest, Sha256};
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::error::Error as StdError;
 use std::fmt;
 use std::fs;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Write};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Copy, Clone, PartialEq, Serialize)]
 pub struct Stats {
     pub num_records: u64,
     pub total_read_length: Option<u64>,
+    pub modal_read_length: Option<u64>,
+    /// Shortest and longest read, for FASTQ files only.
+    pub min_read_length: Option<u64>,
+    pub max_read_length: Option<u64>,
+    /// Block statistics, for gVCF files only.
+    pub gvcf: Option<GvcfStats>,
+    /// Number of reads containing an adapter sequence, for FASTQ files with `--adapter-screening`.
+    pub adapter_reads: Option<u64>,
+    /// Number of reads ending in a poly-G tail, for FASTQ files with `--poly-g-screening`.
+    pub poly_g_reads: Option<u64>,
+    /// Number of reads with a valid UMI, for FASTQ files with `--umi`.
+    pub umi_reads: Option<u64>,
+    /// Number of records flagged as PCR or optical duplicates, for BAM and SAM files only.
+    pub duplicate_records: Option<u64>,
+    /// Number of records not flagged as unmapped, for BAM and SAM files only.
+    pub mapped_records: Option<u64>,
+    /// Number of bases of each kind, for FASTQ files only.
+    pub bases: Option<BaseCounts>,
+    /// Mean and median base quality, for FASTQ files only.
+    pub quality: Option<QualityStats>,
 }
 
 impl Stats {
@@ -27,6 +91,71 @@ impl Stats {
         self.total_read_length
             .map(|total_read_length| (total_read_length as f64) / (self.num_records as f64))
     }
+
+    pub fn n_fraction(self) -> Option<f64> {
+        self.bases
+            .zip(self.total_read_length)
+            .map(|(bases, total)| bases.n as f64 / total as f64)
+    }
+
+    pub fn base_composition(self) -> Option<BaseComposition> {
+        self.bases
+            .zip(self.total_read_length)
+            .filter(|&(_, total)| total > 0)
+            .map(|(bases, total)| bases.composition(total))
+    }
+
+    /// Percentage of `G` and `C` bases.
+    pub fn gc_content(self) -> Option<f64> {
+        self.base_composition()
+            .map(|composition| composition.g + composition.c)
+    }
+
+    pub fn adapter_fraction(self) -> Option<f64> {
+        self.adapter_reads
+            .map(|adapter_reads| (adapter_reads as f64) / (self.num_records as f64))
+    }
+
+    pub fn poly_g_fraction(self) -> Option<f64> {
+        self.poly_g_reads
+            .map(|poly_g_reads| (poly_g_reads as f64) / (self.num_records as f64))
+    }
+
+    pub fn umi_fraction(self) -> Option<f64> {
+        self.umi_reads
+            .map(|umi_reads| (umi_reads as f64) / (self.num_records as f64))
+    }
+
+    pub fn duplicate_fraction(self) -> Option<f64> {
+        self.duplicate_records
+            .map(|duplicates| (duplicates as f64) / (self.num_records as f64))
+    }
+
+    pub fn mapped_fraction(self) -> Option<f64> {
+        self.mapped_records
+            .map(|mapped| (mapped as f64) / (self.num_records as f64))
+    }
+
+    pub fn alignment_status(self) -> Option<AlignmentStatus> {
+        self.mapped_records.map(|mapped| match mapped {
+            0 => AlignmentStatus::Unaligned,
+            mapped if mapped == self.num_records => AlignmentStatus::Aligned,
+            _ => AlignmentStatus::Mixed,
+        })
+    }
+}
+
+/// Whether the records of an alignment file are mapped, to be cross-checked against the
+/// submission metadata, which distinguishes aligned and unaligned files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlignmentStatus {
+    /// All records are mapped.
+    Aligned,
+    /// No record is mapped, as in unaligned BAM files.
+    Unaligned,
+    /// Some records are mapped, e.g. an aligned file including the unmapped reads.
+    Mixed,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -34,8 +163,29 @@ pub struct FileReport {
     pub path: PathBuf,
     pub stats: Option<Stats>,
     pub sha256: Option<String>,
+    /// Detected compression format, if the file was decompressed for checking.
+    pub compression: Option<Compression>,
     pub errors: Vec<String>,
     pub warnings: Vec<String>,
+    pub skipped_checks: Vec<&'static str>,
+    pub not_evaluated: Vec<NotEvaluated>,
+    pub timings: Option<Timings>,
+    /// Time spent waiting for the staging hook before the file was opened.
+    pub staging_seconds: Option<f64>,
+    /// Errors and warnings removed by `--suppress` rules.
+    pub suppressed_errors: Vec<String>,
+    pub suppressed_warnings: Vec<String>,
+    /// Whether the file was expected, e.g. from a checksum manifest, but does not exist.
+    pub missing: bool,
+    /// SHA-256 digest the file is expected to have, e.g. from a checksum manifest.
+    pub expected_sha256: Option<String>,
+    /// Digests of fixed-size chunks, given via `--chunk-size`.
+    pub chunk_sha256: Option<ChunkDigests>,
+    /// Merkle-tree digests, given via `--merkle`.
+    pub merkle: Option<MerkleDigests>,
+    /// Other paths reaching the same file, e.g. through symlinks or bind mounts, which were not
+    /// checked again.
+    pub aliases: Vec<PathBuf>,
 }
 
 impl FileReport {
@@ -49,8 +199,20 @@ impl FileReport {
             path: path.to_path_buf(),
             stats,
             sha256: None,
+            compression: None,
             errors,
             warnings,
+            skipped_checks: vec![],
+            not_evaluated: vec![],
+            timings: None,
+            staging_seconds: None,
+            suppressed_errors: vec![],
+            suppressed_warnings: vec![],
+            missing: false,
+            expected_sha256: None,
+            chunk_sha256: None,
+            merkle: None,
+            aliases: vec![],
         }
     }
 
@@ -59,19 +221,161 @@ impl FileReport {
             path: path.to_path_buf(),
             stats: None,
             sha256: None,
+            compression: None,
             errors: vec![error],
             warnings: vec![],
+            skipped_checks: vec![],
+            not_evaluated: vec![],
+            timings: None,
+            staging_seconds: None,
+            suppressed_errors: vec![],
+            suppressed_warnings: vec![],
+            missing: false,
+            expected_sha256: None,
+            chunk_sha256: None,
+            merkle: None,
+            aliases: vec![],
+        }
+    }
+
+    /// Report of an expected file that does not exist.
+    pub fn new_missing(path: &Path, error: String) -> Self {
+        Self {
+            missing: true,
+            ..Self::new_with_error(path, error)
+        }
+    }
+
+    pub fn with_digests(mut self, digests: Option<FileDigests>) -> Self {
+        if let Some(digests) = digests {
+            self.sha256 = Some(digests.sha256);
+            self.chunk_sha256 = digests.chunks;
+            self.merkle = digests.merkle;
         }
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Option<Compression>) -> Self {
+        self.compression = compression;
+        self
     }
 
-    pub fn with_sha256(mut self, sha256: Option<String>) -> Self {
-        self.sha256 = sha256;
+    pub fn with_skipped_checks(mut self, skipped_checks: Vec<&'static str>) -> Self {
+        self.skipped_checks = skipped_checks;
+        self
+    }
+
+    pub fn with_not_evaluated(mut self, not_evaluated: Vec<NotEvaluated>) -> Self {
+        self.not_evaluated = not_evaluated;
+        self
+    }
+
+    pub fn with_timings(mut self, timings: Timings) -> Self {
+        self.timings = Some(timings);
         self
     }
 
     pub fn is_ok(&self) -> bool {
         self.errors.is_empty()
     }
+
+    /// Status in the report: `OK`, `ERROR`, or `MISSING` for expected files that do not exist.
+    pub fn status(&self) -> &'static str {
+        if self.missing {
+            "MISSING"
+        } else if self.is_ok() {
+            "OK"
+        } else {
+            "ERROR"
+        }
+    }
+
+    /// Adds an error if the fraction of `N` bases of a FASTQ file exceeds `max_n_fraction`.
+    fn check_n_fraction(&mut self, max_n_fraction: f64) {
+        let Some(stats) = self.stats else {
+            return;
+        };
+        if let (Some(n_bases), Some(total), Some(n_fraction)) = (
+            stats.bases.map(|b| b.n),
+            stats.total_read_length,
+            stats.n_fraction(),
+        ) && n_fraction > max_n_fraction
+        {
+            self.errors.push(format!(
+                "Fraction of N bases ({n_fraction:.4}, {n_bases} of {total} bases) exceeds the maximum of {max_n_fraction}; failed sequencing cycles may have produced all-N reads or tails."
+            ));
+        }
+    }
+
+    /// Adds a warning if the fraction of records of an alignment file flagged as duplicates
+    /// exceeds `max_duplicate_fraction`.
+    fn check_duplicate_fraction(&mut self, max_duplicate_fraction: f64) {
+        let Some(stats) = self.stats else {
+            return;
+        };
+        if let (Some(duplicates), Some(fraction)) =
+            (stats.duplicate_records, stats.duplicate_fraction())
+            && fraction > max_duplicate_fraction
+        {
+            self.warnings.push(format!(
+                "{:.2}% of records ({duplicates} of {}) are flagged as PCR or optical duplicates, above the maximum of {:.2}%. The library may have had low complexity or been over-amplified.",
+                fraction * 100.0,
+                stats.num_records,
+                max_duplicate_fraction * 100.0
+            ));
+        }
+    }
+
+    /// Adds an error if the number of records of a FASTQ file differs from `expected`.
+    fn check_record_count(&mut self, expected: u64) {
+        if let Some(stats) = self.stats
+            && stats.num_records != expected
+        {
+            self.errors.push(format!(
+                "Number of records ({}) does not match the expected number of records ({expected}); the file may be truncated or incomplete.",
+                stats.num_records
+            ));
+        }
+    }
+
+    /// Returns an error if the number of bases of the files `what` deviates from `expected` by
+    /// more than the fraction `tolerance`.
+    fn base_yield_error(what: &str, total: u64, expected: u64, tolerance: f64) -> Option<String> {
+        let deviation = total.abs_diff(expected) as f64 / expected as f64;
+        (deviation > tolerance).then(|| {
+            format!(
+                "Total number of bases of {what} ({total}) deviates from the expected yield ({expected}) by {:.2}%, more than the tolerance of {:.2}%; the file may be truncated or incomplete.",
+                deviation * 100.0,
+                tolerance * 100.0
+            )
+        })
+    }
+
+    fn demote_threshold_violations(&mut self) {
+        findings::demote_threshold_violations(&mut self.errors, &mut self.warnings);
+    }
+
+    fn demote(&mut self, codes: &[&str]) {
+        findings::demote(codes, &mut self.errors, &mut self.warnings);
+    }
+
+    /// Applies `rules` to the errors and warnings, returning the codes of suppressed findings.
+    fn suppress(&mut self, rules: &[Suppression]) -> Vec<&'static str> {
+        let paths = [self.path.as_path()];
+        let mut codes =
+            suppress::apply(rules, &paths, &mut self.errors, &mut self.suppressed_errors);
+        codes.extend(suppress::apply(
+            rules,
+            &paths,
+            &mut self.warnings,
+            &mut self.suppressed_warnings,
+        ));
+        codes
+    }
+
+    fn suppressed_findings(&self) -> Vec<Finding<'_>> {
+        findings::collect(&self.suppressed_errors, &self.suppressed_warnings)
+    }
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -79,36 +383,321 @@ pub struct PairReport {
     pub fq1_report: FileReport,
     pub fq2_report: FileReport,
     pub pair_errors: Vec<String>,
+    /// Pair errors suppressed by `--suppress` rules.
+    pub suppressed_pair_errors: Vec<String>,
+    /// Report of the index reads of a `--fastq-triple` input.
+    pub index_report: Option<FileReport>,
 }
 
 impl PairReport {
     fn is_ok(&self) -> bool {
-        self.fq1_report.is_ok() && self.fq2_report.is_ok() && self.pair_errors.is_empty()
+        self.fq1_report.is_ok()
+            && self.fq2_report.is_ok()
+            && self.index_report.as_ref().is_none_or(FileReport::is_ok)
+            && self.pair_errors.is_empty()
+    }
+
+    fn file_reports(&self) -> impl Iterator<Item = &FileReport> {
+        [&self.fq1_report, &self.fq2_report]
+            .into_iter()
+            .chain(&self.index_report)
+    }
+
+    /// The pair as an entity with the findings concerning both files, identified by the path of
+    /// its first read like the pairs of lab data.
+    fn entity(&self) -> Entity {
+        let mut entity = Entity::new(EntityKind::Pair, self.fq1_report.path.display().to_string());
+        for file_report in self.file_reports() {
+            entity.add_member(file_report.path.display().to_string(), file_report.is_ok());
+        }
+        entity.errors = self.pair_errors.clone();
+        entity
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "check_type", rename_all = "snake_case")]
 pub enum Job {
     SingleFastq(SingleFastqJob),
     PairedFastq(PairedFastqJob),
+    TripleFastq(TripleFastqJob),
+    InterleavedFastq(InterleavedFastqJob),
     Bam(BamCheckJob),
+    Sam(SamCheckJob),
+    Vcf(VcfCheckJob),
+    Fasta(FastaCheckJob),
+    Bed(BedCheckJob),
+    Tabix(TabixCheckJob),
+    Gzi(GziCheckJob),
+    Pod5(SignalJob),
+    Fast5(SignalJob),
     Raw(RawJob),
 }
 
-#[derive(Debug)]
+impl Job {
+    pub fn check_type(&self) -> &'static str {
+        match self {
+            Job::SingleFastq(_)
+            | Job::PairedFastq(_)
+            | Job::TripleFastq(_)
+            | Job::InterleavedFastq(_) => "fastq",
+            Job::Bam(_) => "bam",
+            Job::Sam(_) => "sam",
+            Job::Vcf(_) => "vcf",
+            Job::Fasta(_) => "fasta",
+            Job::Bed(_) => "bed",
+            Job::Tabix(_) => "tabix",
+            Job::Gzi(_) => "gzi",
+            Job::Pod5(_) => "pod5",
+            Job::Fast5(_) => "fast5",
+            Job::Raw(_) => "raw",
+        }
+    }
+
+    /// Size on disk of the files of the job.
+    pub fn size(&self) -> u64 {
+        match self {
+            Job::SingleFastq(job) => job.size,
+            Job::PairedFastq(job) => job.fq1_size + job.fq2_size,
+            Job::TripleFastq(job) => job.pair.fq1_size + job.pair.fq2_size + job.index_size,
+            Job::InterleavedFastq(job) => job.size,
+            Job::Bam(job) => job.size,
+            Job::Sam(job) => job.size,
+            Job::Vcf(job) => job.size,
+            Job::Fasta(job) => job.size,
+            Job::Bed(job) => job.size,
+            Job::Tabix(job) => job.size,
+            Job::Gzi(job) => job.size,
+            Job::Pod5(job) | Job::Fast5(job) => job.size,
+            Job::Raw(job) => job.size,
+        }
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        match self {
+            Job::SingleFastq(job) => vec![job.path.clone()],
+            Job::PairedFastq(job) => vec![job.fq1_path.clone(), job.fq2_path.clone()],
+            Job::TripleFastq(job) => vec![
+                job.pair.fq1_path.clone(),
+                job.pair.fq2_path.clone(),
+                job.index_path.clone(),
+            ],
+            Job::InterleavedFastq(job) => vec![job.path.clone()],
+            Job::Bam(job) => vec![job.path.clone()],
+            Job::Sam(job) => vec![job.path.clone()],
+            Job::Vcf(job) => vec![job.path.clone()],
+            Job::Fasta(job) => vec![job.path.clone()],
+            Job::Bed(job) => vec![job.path.clone()],
+            Job::Tabix(job) => vec![job.path.clone()],
+            Job::Gzi(job) => vec![job.path.clone()],
+            Job::Pod5(job) | Job::Fast5(job) => vec![job.path.clone()],
+            Job::Raw(job) => vec![job.path.clone()],
+        }
+    }
+}
+
+/// Level of detail of the statistics included in the report.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsLevel {
+    /// Record-level statistics only.
+    #[default]
+    Basic,
+    /// Additionally include a breakdown of the time spent reading, decompressing, hashing
+    /// and checking each file.
+    Full,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RunOptions {
+    pub continue_on_error: bool,
+    /// Order in which the jobs are started, given via `--schedule`.
+    pub schedule: Schedule,
+    pub show_progress: Option<bool>,
+    pub stats: StatsLevel,
+    pub control_socket: Option<PathBuf>,
+    pub suppressions: Vec<Suppression>,
+    pub strict_extensions: bool,
+    /// Verify files against their MD5 sidecars, given via `--verify-md5`.
+    pub verify_md5: bool,
+    pub checksum_db: Option<PathBuf>,
+    pub submission_id: Option<String>,
+    /// External registry of the expected digests of files, from the config file.
+    pub checksum_registry: Option<ChecksumRegistry>,
+    pub lab_data: Vec<LabDatum>,
+    /// IDs of the lab data assigned to BAM files, given via `--lab-datum-bam`.
+    pub lab_datum_bams: BTreeMap<PathBuf, Vec<String>>,
+    /// Number of records expected in FASTQ files, given via `--expected-records`.
+    pub expected_records: BTreeMap<PathBuf, u64>,
+    /// Expected number of bases of FASTQ files, or of pairs by the path of R1, given via
+    /// `--expected-bases`.
+    pub expected_bases: BTreeMap<PathBuf, u64>,
+    /// Fraction by which the number of bases may deviate from `expected_bases`.
+    pub base_yield_tolerance: f64,
+    /// Report threshold violations as warnings, given via `--assess`.
+    pub assess: bool,
+    /// Severity of findings about Phred+64 or Solexa encoded FASTQ files.
+    pub quality_encoding_severity: Severity,
+    /// Severity of findings about FASTQ records with an empty sequence or quality string.
+    pub empty_record_severity: Severity,
+    /// Severity of findings about primary alignment records without base quality scores.
+    pub missing_quality_severity: Severity,
+    /// Only allow `ACGTN` in FASTQ sequences, given via `--strict-bases`.
+    pub strict_bases: bool,
+    /// Require FASTQ read names in Casava 1.8+ format, given via `--strict-read-names`.
+    pub strict_read_names: bool,
+    /// Whether recoverable FASTQ defects are errors or warnings, given via `--parse-leniency`.
+    pub parse_leniency: ParseLeniency,
+    /// Validate the auxiliary fields of alignment records, given via `--validate-tags`.
+    pub validate_tags: bool,
+    /// FASTA file whose sequences the @SQ lines of alignment files must match, given via
+    /// `--bam-reference`.
+    pub bam_reference: Option<PathBuf>,
+    /// Encoding of the digests in the report.
+    pub report_digest_encoding: DigestEncoding,
+    /// Size of the chunks that are digested in addition to whole files, given via `--chunk-size`.
+    pub chunk_size: Option<u64>,
+    /// Maximum fraction of `N` bases of FASTQ files, given via `--max-n-fraction`.
+    pub max_n_fraction: Option<f64>,
+    /// Fraction of records of an alignment file flagged as duplicates above which a warning is
+    /// reported.
+    pub max_duplicate_fraction: Option<f64>,
+    /// Screening of FASTQ reads for adapters, given via `--adapter-screening`.
+    pub adapter_screening: Option<AdapterScreening>,
+    /// Screening of FASTQ reads for poly-G tails, given via `--poly-g-screening`.
+    pub poly_g_screening: Option<PolyGScreening>,
+    /// UMI check of FASTQ reads, given via `--umi`.
+    pub umi: Option<UmiCheck>,
+    /// Memory budget per file of the duplicate read-name check, given via
+    /// `--duplicate-read-names`.
+    pub duplicate_names_memory: Option<u64>,
+    /// Height of the Merkle subtrees to report if Merkle-tree digests are enabled via
+    /// `--merkle`.
+    pub merkle: Option<Option<u32>>,
+    /// Concurrency limits per storage mount from the config file.
+    pub mount_limits: Vec<MountLimit>,
+    /// Command run before the files of a job are opened, from the config file.
+    pub staging_hook: Option<StagingHook>,
+    /// Command run after each file has been checked, from the config file.
+    pub post_check_hook: Option<PostCheckHook>,
+    /// Directory failed files are preserved in, given via `--quarantine`.
+    pub quarantine: Option<Quarantine>,
+    /// `--inflate-backend`.
+    pub inflate_backend: InflateBackend,
+    /// Worker threads per file of `--inflate-backend parallel-bgzf`.
+    pub inflate_threads: usize,
+    /// External validators run on each checked file, from the config file.
+    pub external_checks: Vec<ExternalCheck>,
+    /// Time after which no further jobs are started, given via `--deadline`. Not part of rerun
+    /// bundles, since it only applies to the original run.
+    #[serde(skip)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Time after the deadline after which running jobs are cancelled, given via
+    /// `--deadline-grace`.
+    #[serde(skip)]
+    pub deadline_grace: Option<Duration>,
+    /// Liveness signal of the workers for the systemd watchdog.
+    #[serde(skip)]
+    pub heartbeat: Heartbeat,
+    /// Constraints of the inbox on file paths, from the config file.
+    pub path_constraints: Option<PathConstraints>,
+    /// Consent scopes of the donors, given via `--consent-scope`.
+    pub donor_consents: Vec<DonorConsent>,
+    /// File categories depending on the consent scope, from the config file.
+    pub consent_categories: Vec<consent::Category>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
 enum CheckResult {
     PairedFastq(PairReport),
     SingleFastq(FileReport),
+    InterleavedFastq(FileReport),
     Bam(FileReport),
+    Sam(FileReport),
+    Vcf(FileReport),
+    Fasta(FileReport),
+    Bed(FileReport),
+    Tabix(FileReport),
+    Gzi(FileReport),
+    Pod5(FileReport),
+    Fast5(FileReport),
     Raw(FileReport),
 }
 
+/// Error of an input file that does not exist.
+const MISSING_INPUT_ERROR: &str = "File is given as an input, but does not exist.";
+const MISSING_LISTED_ERROR: &str = "File is listed in the manifest, but does not exist.";
+/// Error of a file that was not checked, because another file of its job does not exist.
+const MISSING_MATE_ERROR: &str =
+    "File was not checked, because another file of its job does not exist.";
+
+fn is_missing(path: &Path) -> bool {
+    archive::size(path).is_err_and(|e| e.kind() == io::ErrorKind::NotFound)
+}
+
 impl CheckResult {
+    /// Result of a job with files that do not exist, which are reported as missing instead of
+    /// being checked. Returns `None` if all files of the job exist.
+    fn missing(job: &Job) -> Option<Self> {
+        if !job.paths().iter().any(|path| is_missing(path)) {
+            return None;
+        }
+        let missing = |path: &Path| FileReport::new_missing(path, MISSING_INPUT_ERROR.to_string());
+        let member = |path: &Path| {
+            if is_missing(path) {
+                missing(path)
+            } else {
+                FileReport::new_with_error(path, MISSING_MATE_ERROR.to_string())
+            }
+        };
+        let pair = |job: &PairedFastqJob, index_report| {
+            CheckResult::PairedFastq(PairReport {
+                fq1_report: member(&job.fq1_path),
+                fq2_report: member(&job.fq2_path),
+                pair_errors: vec![],
+                suppressed_pair_errors: vec![],
+                index_report,
+            })
+        };
+        Some(match job {
+            Job::PairedFastq(job) => pair(job, None),
+            Job::TripleFastq(job) => pair(&job.pair, Some(member(&job.index_path))),
+            Job::SingleFastq(job) => CheckResult::SingleFastq(missing(&job.path)),
+            Job::InterleavedFastq(job) => CheckResult::InterleavedFastq(missing(&job.path)),
+            Job::Bam(job) => CheckResult::Bam(missing(&job.path)),
+            Job::Sam(job) => CheckResult::Sam(missing(&job.path)),
+            Job::Vcf(job) => CheckResult::Vcf(missing(&job.path)),
+            Job::Fasta(job) => CheckResult::Fasta(missing(&job.path)),
+            Job::Bed(job) => CheckResult::Bed(missing(&job.path)),
+            Job::Tabix(job) => CheckResult::Tabix(missing(&job.path)),
+            Job::Gzi(job) => CheckResult::Gzi(missing(&job.path)),
+            Job::Pod5(job) => CheckResult::Pod5(missing(&job.path)),
+            Job::Fast5(job) => CheckResult::Fast5(missing(&job.path)),
+            Job::Raw(job) => CheckResult::Raw(match &job.expected_sha256 {
+                Some(expected_sha256) => FileReport {
+                    expected_sha256: Some(expected_sha256.clone()),
+                    ..FileReport::new_missing(&job.path, MISSING_LISTED_ERROR.to_string())
+                },
+                None => missing(&job.path),
+            }),
+        })
+    }
+
     fn is_error(&self) -> bool {
         match self {
             CheckResult::PairedFastq(r) => !r.is_ok(),
             CheckResult::SingleFastq(r) => !r.is_ok(),
+            CheckResult::InterleavedFastq(r) => !r.is_ok(),
             CheckResult::Bam(r) => !r.is_ok(),
+            CheckResult::Sam(r) => !r.is_ok(),
+            CheckResult::Vcf(r) => !r.is_ok(),
+            CheckResult::Fasta(r) => !r.is_ok(),
+            CheckResult::Bed(r) => !r.is_ok(),
+            CheckResult::Tabix(r) => !r.is_ok(),
+            CheckResult::Gzi(r) => !r.is_ok(),
+            CheckResult::Pod5(r) => !r.is_ok(),
+            CheckResult::Fast5(r) => !r.is_ok(),
             CheckResult::Raw(r) => !r.is_ok(),
         }
     }
@@ -116,10 +705,239 @@ impl CheckResult {
         match self {
             CheckResult::PairedFastq(r) => &r.fq1_report.path,
             CheckResult::SingleFastq(r) => &r.path,
+            CheckResult::InterleavedFastq(r) => &r.path,
             CheckResult::Bam(r) => &r.path,
+            CheckResult::Sam(r) => &r.path,
+            CheckResult::Vcf(r) => &r.path,
+            CheckResult::Fasta(r) => &r.path,
+            CheckResult::Bed(r) => &r.path,
+            CheckResult::Tabix(r) => &r.path,
+            CheckResult::Gzi(r) => &r.path,
+            CheckResult::Pod5(r) => &r.path,
+            CheckResult::Fast5(r) => &r.path,
             CheckResult::Raw(r) => &r.path,
         }
     }
+
+    fn file_reports_mut(&mut self) -> Vec<&mut FileReport> {
+        match self {
+            CheckResult::PairedFastq(r) => [&mut r.fq1_report, &mut r.fq2_report]
+                .into_iter()
+                .chain(&mut r.index_report)
+                .collect(),
+            CheckResult::SingleFastq(r)
+            | CheckResult::InterleavedFastq(r)
+            | CheckResult::Bam(r)
+            | CheckResult::Sam(r)
+            | CheckResult::Vcf(r)
+            | CheckResult::Fasta(r)
+            | CheckResult::Bed(r)
+            | CheckResult::Tabix(r)
+            | CheckResult::Gzi(r)
+            | CheckResult::Pod5(r)
+            | CheckResult::Fast5(r)
+            | CheckResult::Raw(r) => vec![r],
+        }
+    }
+
+    /// Records the staging time of each file, and an error for each file that failed to stage.
+    fn record_staging(&mut self, staged: Vec<Staged>) {
+        for (path, result) in staged {
+            for report in self.file_reports_mut() {
+                if report.path != path {
+                    continue;
+                }
+                match &result {
+                    Ok(duration) => report.staging_seconds = Some(duration.as_secs_f64()),
+                    Err(error) => report.errors.push(error.clone()),
+                }
+            }
+        }
+    }
+
+    /// Re-encodes the hex-encoded digests of all files for the report.
+    fn encode_digests(&mut self, encoding: DigestEncoding) {
+        for report in self.file_reports_mut() {
+            let chunks = report
+                .chunk_sha256
+                .iter_mut()
+                .flat_map(|chunks| chunks.sha256.iter_mut());
+            let merkle = report.merkle.iter_mut().flat_map(|merkle| {
+                std::iter::once(&mut merkle.root).chain(merkle.subtrees.iter_mut())
+            });
+            for digest in [&mut report.sha256, &mut report.expected_sha256]
+                .into_iter()
+                .flatten()
+                .chain(chunks)
+                .chain(merkle)
+            {
+                *digest = encoding.encode(digest);
+            }
+        }
+    }
+
+    /// Runs the external checks that apply to `check_type` on every file that exists.
+    fn run_external_checks(&mut self, checks: &[ExternalCheck], check_type: &str) {
+        for report in self.file_reports_mut() {
+            if report.missing {
+                continue;
+            }
+            for check in checks
+                .iter()
+                .filter(|check| check.applies_to(check_type, &report.path))
+            {
+                let findings = check.run(&report.path);
+                report.errors.extend(findings.errors);
+                report.warnings.extend(findings.warnings);
+            }
+        }
+    }
+
+    /// Preserves every failed file with the report entries of the job in the quarantine
+    /// directory, adding a warning for each file that could not be preserved. The digests of the
+    /// entries are encoded like those of the report, while the post-check hook, which runs
+    /// later, still receives hex digests.
+    fn quarantine_failed(
+        &mut self,
+        quarantine: &Quarantine,
+        stats: StatsLevel,
+        encoding: DigestEncoding,
+        times: EntryTimes,
+    ) {
+        if !self.is_error() {
+            return;
+        }
+        let pair_ok = match self {
+            CheckResult::PairedFastq(r) => r.pair_errors.is_empty(),
+            _ => true,
+        };
+        let mut entries = Vec::new();
+        let written = if encoding == DigestEncoding::Hex {
+            write_jsonl_report_entry(self, stats, times, &mut entries)
+        } else {
+            let mut encoded = self.clone();
+            encoded.encode_digests(encoding);
+            write_jsonl_report_entry(&encoded, stats, times, &mut entries)
+        };
+        if let Err(e) = written {
+            logging::error(format!(
+                "Failed to write the report entry of {} for quarantine: {e}",
+                self.primary_path().display()
+            ));
+        }
+        for report in self.file_reports_mut() {
+            if report.is_ok() && pair_ok {
+                continue;
+            }
+            match quarantine.preserve(&report.path, &entries) {
+                Ok(target) => logging::info(format!(
+                    "Quarantined {} as {}",
+                    report.path.display(),
+                    target.display()
+                )),
+                Err(warning) => report.warnings.push(warning),
+            }
+        }
+    }
+
+    /// Runs the post-check hook for every file, adding a warning for each failed run.
+    fn run_post_check_hook(&mut self, hook: &PostCheckHook) {
+        let pair_ok = match self {
+            CheckResult::PairedFastq(r) => r.pair_errors.is_empty(),
+            _ => true,
+        };
+        for report in self.file_reports_mut() {
+            let status = if report.is_ok() && pair_ok {
+                "OK"
+            } else {
+                "ERROR"
+            };
+            if let Err(warning) = hook.run(&report.path, status, report.sha256.as_deref()) {
+                report.warnings.push(warning);
+            }
+        }
+    }
+
+    /// Adds an error to every file whose extension is not accepted in GRZ submissions.
+    fn check_extensions(&mut self) {
+        for report in self.file_reports_mut() {
+            if let Some(error) = scan::check_extension(&report.path) {
+                report.errors.push(error);
+            }
+        }
+    }
+
+    /// Adds an error to every file whose path violates the constraints of the inbox.
+    fn check_paths(&mut self, constraints: &PathConstraints) {
+        for report in self.file_reports_mut() {
+            let errors = constraints.check(&report.path);
+            report.errors.extend(errors);
+        }
+    }
+
+    /// Adds an error to every FASTQ file or pair whose number of bases deviates from the
+    /// expected yield. Pairs are looked up by the path of R1, and their error is a pair error.
+    fn check_base_yield(&mut self, expected_bases: &BTreeMap<PathBuf, u64>, tolerance: f64) {
+        if let CheckResult::PairedFastq(r) = self {
+            let Some(&expected) = expected_bases.get(&r.fq1_report.path) else {
+                return;
+            };
+            let total = r
+                .fq1_report
+                .stats
+                .zip(r.fq2_report.stats)
+                .and_then(|(fq1, fq2)| Some(fq1.total_read_length? + fq2.total_read_length?));
+            if let Some(error) = total.and_then(|total| {
+                FileReport::base_yield_error("R1 and R2", total, expected, tolerance)
+            }) {
+                r.pair_errors.push(error);
+            }
+            return;
+        }
+        for report in self.file_reports_mut() {
+            if let Some(&expected) = expected_bases.get(&report.path)
+                && let Some(total) = report.stats.and_then(|stats| stats.total_read_length)
+                && let Some(error) =
+                    FileReport::base_yield_error("the file", total, expected, tolerance)
+            {
+                report.errors.push(error);
+            }
+        }
+    }
+
+    /// Applies `rules` to all findings, returning the codes of suppressed findings.
+    fn suppress(&mut self, rules: &[Suppression]) -> Vec<&'static str> {
+        match self {
+            CheckResult::PairedFastq(r) => {
+                let mut codes = r.fq1_report.suppress(rules);
+                codes.extend(r.fq2_report.suppress(rules));
+                if let Some(index_report) = &mut r.index_report {
+                    codes.extend(index_report.suppress(rules));
+                }
+                let paths: Vec<PathBuf> = r.file_reports().map(|r| r.path.clone()).collect();
+                let paths: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+                codes.extend(suppress::apply(
+                    rules,
+                    &paths,
+                    &mut r.pair_errors,
+                    &mut r.suppressed_pair_errors,
+                ));
+                codes
+            }
+            CheckResult::SingleFastq(r)
+            | CheckResult::InterleavedFastq(r)
+            | CheckResult::Bam(r)
+            | CheckResult::Sam(r)
+            | CheckResult::Vcf(r)
+            | CheckResult::Fasta(r)
+            | CheckResult::Bed(r)
+            | CheckResult::Tabix(r)
+            | CheckResult::Gzi(r)
+            | CheckResult::Pod5(r)
+            | CheckResult::Fast5(r)
+            | CheckResult::Raw(r) => r.suppress(rules),
+        }
+    }
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -153,14 +971,21 @@ fn filename(path: impl AsRef<Path>) -> String {
 
 fn process_job(
     (m, main_pb, style): &mut (MultiProgress, ProgressBar, ProgressStyle),
+    settings: &CheckSettings,
     job: Job,
 ) -> CheckResult {
     match job {
         Job::SingleFastq(job) => {
-            let pb = m.add(ProgressBar::new(job.size));
-            pb.set_style(style.clone());
+            let pb = m.add(progress::file_bar(job.size, &job.path, style));
             pb.set_prefix("FASTQ");
-            let report = fastq::check_single_fastq(&job.path, job.length_check, &pb, main_pb);
+            let report = fastq::check_single_fastq(
+                &job.path,
+                job.length_check,
+                job.declared_read_length,
+                settings,
+                &pb,
+                main_pb,
+            );
             if report.is_ok() {
                 pb.finish_with_message(format!("✓ OK    {}", filename(&job.path)));
             } else {
@@ -168,58 +993,140 @@ fn process_job(
             }
             CheckResult::SingleFastq(report)
         }
+        Job::InterleavedFastq(job) => {
+            let pb = m.add(progress::file_bar(job.size, &job.path, style));
+            pb.set_prefix("FASTQ");
+            let filename = filename(&job.path);
+            let report = fastq::check_interleaved_fastq(
+                &job.path,
+                job.length_check,
+                job.declared_read_length,
+                settings,
+                &pb,
+                main_pb,
+            );
+            finish_pb(pb, filename, &report);
+            CheckResult::InterleavedFastq(report)
+        }
         Job::PairedFastq(job) => {
-            let fq1_pb = m.add(ProgressBar::new(job.fq1_size));
-            fq1_pb.set_style(style.clone());
+            let fq1_pb = m.add(progress::file_bar(job.fq1_size, &job.fq1_path, style));
             fq1_pb.set_prefix("FASTQ R1");
 
-            let fq2_pb = m.add(ProgressBar::new(job.fq2_size));
-            fq2_pb.set_style(style.clone());
+            let fq2_pb = m.add(progress::file_bar(job.fq2_size, &job.fq2_path, style));
             fq2_pb.set_prefix("FASTQ R2");
 
-            let fq1_setup = common::setup_file_reader(&job.fq1_path, &fq1_pb, main_pb, true);
-            let fq2_setup = common::setup_file_reader(&job.fq2_path, &fq2_pb, main_pb, true);
+            let started = Instant::now();
+            let fq1_setup = common::setup_file_reader(
+                &job.fq1_path,
+                settings,
+                &fq1_pb,
+                main_pb,
+                common::Decompression::Auto,
+            );
+            let fq2_setup = common::setup_file_reader(
+                &job.fq2_path,
+                settings,
+                &fq2_pb,
+                main_pb,
+                common::Decompression::Auto,
+            );
 
+            let read_failure =
+                || dependencies::propagate_failures(fastq::CHECKS, &[dependencies::READ_CHECK]);
             let report = match (fq1_setup, fq2_setup) {
-                (Ok((reader1, hasher1)), Ok((reader2, hasher2))) => {
+                (
+                    Ok((reader1, hasher1, timers1, compression1, truncation1)),
+                    Ok((reader2, hasher2, timers2, compression2, truncation2)),
+                ) => {
                     let (fq1_outcome, fq2_outcome, pair_errors) =
-                        match fastq::process_paired_readers(reader1, reader2, job.length_check) {
+                        match fastq::process_paired_readers(
+                            reader1,
+                            reader2,
+                            job.length_check,
+                            (job.fq1_declared_read_length, job.fq2_declared_read_length),
+                            settings,
+                            (
+                                RecordProgress::of(&fq1_pb, &job.fq1_path),
+                                RecordProgress::of(&fq2_pb, &job.fq2_path),
+                            ),
+                        ) {
                             Ok(result) => result,
                             Err(e) => {
+                                // A truncated stream is reported for its file instead of the
+                                // parse error it causes.
                                 let outcome1 = common::CheckOutcome {
-                                    errors: vec![e.clone()],
+                                    errors: vec![truncation1.error().unwrap_or_else(|| e.clone())],
                                     ..Default::default()
                                 };
                                 let outcome2 = common::CheckOutcome {
-                                    errors: vec![e],
+                                    errors: vec![truncation2.error().unwrap_or(e)],
                                     ..Default::default()
                                 };
+                                let not_evaluated = || {
+                                    dependencies::propagate_failures(
+                                        fastq::CHECKS,
+                                        &[fastq::RECORDS_CHECK],
+                                    )
+                                };
                                 return CheckResult::PairedFastq(PairReport {
                                     fq1_report: FileReport::new(
                                         &job.fq1_path,
                                         None,
                                         outcome1.errors,
                                         outcome1.warnings,
-                                    ),
+                                    )
+                                    .with_not_evaluated(not_evaluated()),
                                     fq2_report: FileReport::new(
                                         &job.fq2_path,
                                         None,
                                         outcome2.errors,
                                         outcome2.warnings,
-                                    ),
+                                    )
+                                    .with_not_evaluated(not_evaluated()),
                                     pair_errors: vec![
                                         "Parsing error during paired fastq check.".to_string(),
                                     ],
+                                    suppressed_pair_errors: vec![],
+                                    index_report: None,
                                 });
                             }
                         };
 
-                    let finalize = |hasher: Arc<Mutex<Sha256>>| match Arc::try_unwrap(hasher) {
-                        Ok(mutex) => Some(format!("{:x}", mutex.into_inner().unwrap().finalize())),
-                        Err(_) => None,
-                    };
-                    let cs1 = finalize(hasher1);
-                    let cs2 = finalize(hasher2);
+                    let finalize =
+                        |hasher: Arc<Mutex<FileHasher>>, path: &Path, errors: &mut Vec<String>| {
+                            let digests = Arc::try_unwrap(hasher)
+                                .ok()?
+                                .into_inner()
+                                .unwrap()
+                                .finalize();
+                            if let Some(md5) = &digests.md5
+                                && let Some(error) = md5_sidecar::verify(path, md5)
+                            {
+                                errors.push(error);
+                            }
+                            Some(digests)
+                        };
+                    let mut fq1_outcome = fq1_outcome;
+                    let mut fq2_outcome = fq2_outcome;
+                    let cs1 = finalize(hasher1, &job.fq1_path, &mut fq1_outcome.errors);
+                    let cs2 = finalize(hasher2, &job.fq2_path, &mut fq2_outcome.errors);
+                    let mut pair_errors = pair_errors;
+                    if let (Some(digests1), Some(digests2)) = (&cs1, &cs2)
+                        && digests1.sha256 == digests2.sha256
+                    {
+                        // Reported instead of identical reads, which byte-identical files
+                        // trivially have.
+                        pair_errors.retain(|error| !error.starts_with(fastq::IDENTICAL_MATES));
+                        pair_errors.push(format!(
+                            "R1 and R2 are byte-identical (SHA-256 {}). The same file was submitted as both mates.",
+                            digests1.sha256
+                        ));
+                    }
+
+                    let wall_time = started.elapsed();
+                    let read_time = timers1.total.elapsed() + timers2.total.elapsed();
+                    let timings1 = Timings::with_check_time(&timers1, wall_time, read_time);
+                    let timings2 = Timings::with_check_time(&timers2, wall_time, read_time);
 
                     let fq1_report = FileReport::new(
                         &job.fq1_path,
@@ -227,23 +1134,32 @@ fn process_job(
                         fq1_outcome.errors,
                         fq1_outcome.warnings,
                     )
-                    .with_sha256(cs1);
+                    .with_digests(cs1)
+                    .with_compression(compression1)
+                    .with_skipped_checks(fq1_outcome.skipped_checks)
+                    .with_timings(timings1);
                     let fq2_report = FileReport::new(
                         &job.fq2_path,
                         fq2_outcome.stats,
                         fq2_outcome.errors,
                         fq2_outcome.warnings,
                     )
-                    .with_sha256(cs2);
+                    .with_digests(cs2)
+                    .with_compression(compression2)
+                    .with_skipped_checks(fq2_outcome.skipped_checks)
+                    .with_timings(timings2);
 
                     PairReport {
                         fq1_report,
                         fq2_report,
                         pair_errors,
+                        suppressed_pair_errors: vec![],
+                        index_report: None,
                     }
                 }
-                (Err(e1), Ok((_r2, _h2))) => {
-                    let fq1_report = FileReport::new_with_error(&job.fq1_path, e1.to_string());
+                (Err(e1), Ok(_)) => {
+                    let fq1_report = FileReport::new_with_error(&job.fq1_path, e1.to_string())
+                        .with_not_evaluated(read_failure());
                     let fq2_report = FileReport::new(
                         &job.fq2_path,
                         None,
@@ -257,9 +1173,11 @@ fn process_job(
                         fq1_report,
                         fq2_report,
                         pair_errors: vec![],
+                        suppressed_pair_errors: vec![],
+                        index_report: None,
                     }
                 }
-                (Ok((_r1, _h1)), Err(e2)) => {
+                (Ok(_), Err(e2)) => {
                     let fq1_report = FileReport::new(
                         &job.fq1_path,
                         None,
@@ -269,20 +1187,27 @@ fn process_job(
                         )],
                         vec![],
                     );
-                    let fq2_report = FileReport::new_with_error(&job.fq2_path, e2.to_string());
+                    let fq2_report = FileReport::new_with_error(&job.fq2_path, e2.to_string())
+                        .with_not_evaluated(read_failure());
                     PairReport {
                         fq1_report,
                         fq2_report,
                         pair_errors: vec![],
+                        suppressed_pair_errors: vec![],
+                        index_report: None,
                     }
                 }
                 (Err(e1), Err(e2)) => {
-                    let fq1_report = FileReport::new_with_error(&job.fq1_path, e1.to_string());
-                    let fq2_report = FileReport::new_with_error(&job.fq2_path, e2.to_string());
+                    let fq1_report = FileReport::new_with_error(&job.fq1_path, e1.to_string())
+                        .with_not_evaluated(read_failure());
+                    let fq2_report = FileReport::new_with_error(&job.fq2_path, e2.to_string())
+                        .with_not_evaluated(read_failure());
                     PairReport {
                         fq1_report,
                         fq2_report,
                         pair_errors: vec![],
+                        suppressed_pair_errors: vec![],
+                        index_report: None,
                     }
                 }
             };
@@ -294,20 +1219,136 @@ fn process_job(
 
             CheckResult::PairedFastq(report)
         }
+        Job::TripleFastq(job) => {
+            let index_pb = m.add(progress::file_bar(job.index_size, &job.index_path, style));
+            index_pb.set_prefix("FASTQ I1");
+
+            let (pair_result, index_report) = rayon::join(
+                || {
+                    process_job(
+                        &mut (m.clone(), main_pb.clone(), style.clone()),
+                        settings,
+                        Job::PairedFastq(job.pair),
+                    )
+                },
+                || fastq::check_index_fastq(&job.index_path, settings, &index_pb, main_pb),
+            );
+            finish_pb(index_pb, filename(&job.index_path), &index_report);
+
+            let CheckResult::PairedFastq(mut report) = pair_result else {
+                unreachable!("Paired FASTQ jobs yield paired FASTQ results");
+            };
+            if let (Some(pair_stats), Some(index_stats)) =
+                (report.fq1_report.stats, index_report.stats)
+                && pair_stats.num_records != index_stats.num_records
+            {
+                report.pair_errors.push(format!(
+                    "Mismatched read counts: I1 has {} records, but R1 has {}.",
+                    index_stats.num_records, pair_stats.num_records
+                ));
+            }
+            report.index_report = Some(index_report);
+            CheckResult::PairedFastq(report)
+        }
         Job::Bam(job) => {
             let pb = m.add(ProgressBar::new(job.size));
             pb.set_style(style.clone());
-            pb.set_prefix("BAM");
+            pb.set_prefix(if job.unaligned { "uBAM" } else { "BAM" });
             let filename = filename(&job.path);
-            let report = bam::check_bam(&job.path, &pb, main_pb);
+            let report = bam::check_bam(
+                &job.path,
+                job.species,
+                job.unaligned,
+                job.index_path.as_deref(),
+                settings,
+                &pb,
+                main_pb,
+            );
             finish_pb(pb, filename, &report);
             CheckResult::Bam(report)
         }
+        Job::Sam(job) => {
+            let pb = m.add(ProgressBar::new(job.size));
+            pb.set_style(style.clone());
+            pb.set_prefix("SAM");
+            let filename = filename(&job.path);
+            let report = sam::check_sam(&job.path, job.species, settings, &pb, main_pb);
+            finish_pb(pb, filename, &report);
+            CheckResult::Sam(report)
+        }
+        Job::Vcf(job) => {
+            let pb = m.add(ProgressBar::new(job.size));
+            pb.set_style(style.clone());
+            pb.set_prefix(if job.gvcf { "gVCF" } else { "VCF" });
+            let filename = filename(&job.path);
+            let report = vcf::check_vcf(&job.path, job.gvcf, settings, &pb, main_pb);
+            finish_pb(pb, filename, &report);
+            CheckResult::Vcf(report)
+        }
+        Job::Fasta(job) => {
+            let pb = m.add(ProgressBar::new(job.size));
+            pb.set_style(style.clone());
+            pb.set_prefix("FASTA");
+            let filename = filename(&job.path);
+            let report =
+                fasta::check_fasta(&job.path, job.index_path.as_deref(), settings, &pb, main_pb);
+            finish_pb(pb, filename, &report);
+            CheckResult::Fasta(report)
+        }
+        Job::Bed(job) => {
+            let pb = m.add(ProgressBar::new(job.size));
+            pb.set_style(style.clone());
+            pb.set_prefix("BED");
+            let filename = filename(&job.path);
+            let report =
+                bed::check_bed(&job.path, job.reference.as_deref(), settings, &pb, main_pb);
+            finish_pb(pb, filename, &report);
+            CheckResult::Bed(report)
+        }
+        Job::Tabix(job) => {
+            let pb = m.add(ProgressBar::new(job.size));
+            pb.set_style(style.clone());
+            pb.set_prefix("TABIX");
+            let filename = filename(&job.path);
+            let report = tabix::check_tabix(&job.path, &job.data_path, settings, &pb, main_pb);
+            finish_pb(pb, filename, &report);
+            CheckResult::Tabix(report)
+        }
+        Job::Gzi(job) => {
+            let pb = m.add(ProgressBar::new(job.size));
+            pb.set_style(style.clone());
+            pb.set_prefix("GZI");
+            let filename = filename(&job.path);
+            let report = gzi::check_gzi(&job.path, &job.data_path, settings, &pb, main_pb);
+            finish_pb(pb, filename, &report);
+            CheckResult::Gzi(report)
+        }
+        Job::Pod5(job) => {
+            let pb = m.add(ProgressBar::new(job.size));
+            pb.set_style(style.clone());
+            pb.set_prefix("POD5");
+            let report = signal::check_pod5(&job.path, settings, &pb, main_pb);
+            let filename = filename(&job.path);
+            finish_pb(pb, filename, &report);
+            CheckResult::Pod5(report)
+        }
+        Job::Fast5(job) => {
+            let pb = m.add(ProgressBar::new(job.size));
+            pb.set_style(style.clone());
+            pb.set_prefix("FAST5");
+            let report = signal::check_fast5(&job.path, settings, &pb, main_pb);
+            let filename = filename(&job.path);
+            finish_pb(pb, filename, &report);
+            CheckResult::Fast5(report)
+        }
         Job::Raw(job) => {
             let pb = m.add(ProgressBar::new(job.size));
             pb.set_style(style.clone());
             pb.set_prefix("OTHER");
-            let report = raw::check_raw(&job.path, &pb, main_pb);
+            let report = match &job.expected_sha256 {
+                Some(expected) => raw::check_listed(&job.path, expected, settings, &pb, main_pb),
+                None => raw::check_raw(&job.path, settings, &pb, main_pb),
+            };
             let filename = filename(&job.path);
             finish_pb(pb, filename, &report);
             CheckResult::Raw(report)
@@ -323,80 +1364,519 @@ fn finish_pb(pb: ProgressBar, filename: String, report: &FileReport) {
     }
 }
 
-#[allow(clippy::result_large_err)]
-fn process_jobs(
-    jobs: Vec<Job>,
-    continue_on_error: bool,
-    shutdown_flag: Arc<AtomicBool>,
-    mpb: MultiProgress,
-    main_pb: ProgressBar,
-    file_style: ProgressStyle,
-    writer: Arc<Mutex<BufWriter<fs::File>>>,
-) -> Result<(), EarlyExitError> {
-    if continue_on_error {
-        let num_failed_jobs = Arc::new(AtomicUsize::new(0));
+fn write_report(
+    report: &CheckResult,
+    stats: StatsLevel,
+    times: EntryTimes,
+    writer: &mut impl Write,
+    control: Option<&ControlState>,
+) {
+    let mut lines = Vec::new();
+    let result = write_jsonl_report_entry(report, stats, times, &mut lines)
+        .and_then(|()| writer.write_all(&lines).map_err(anyhow::Error::from));
+    if let Err(e) = result {
+        logging::error(format!(
+            "Failed to write report line for {:?}: {}",
+            report.primary_path(),
+            e
+        ));
+    }
+    if let Some(control) = control {
+        control.add_report_lines(&lines);
+    }
+}
 
-        jobs.into_par_iter().for_each_with(
-            (
-                mpb,
-                main_pb.clone(),
-                file_style,
-                writer,
-                num_failed_jobs.clone(),
-            ),
-            |(mpb, main_pb, style, writer, num_failed), job| {
-                if shutdown_flag.load(Ordering::Relaxed) {
-                    return;
-                }
+fn log_result(report: &CheckResult) {
+    let status = if report.is_error() { "ERROR" } else { "OK" };
+    logging::info(format!(
+        "Checked {}: {status}",
+        report.primary_path().display()
+    ));
+}
 
-                let report = process_job(&mut (mpb.clone(), main_pb.clone(), style.clone()), job);
+/// State collected over all jobs of a run for run-level findings and summaries.
+#[derive(Debug, Default)]
+struct RunState {
+    settings: CheckSettings,
+    suppressed: suppress::Summary,
+    digests: DigestIndex,
+    checksum_db: Option<ChecksumDb>,
+    checksum_registry: Option<Box<dyn Registry>>,
+    lab_data: LabDataTotals,
+    mounts: Mounts,
+    aliases: Aliases,
+    consent: consent::Findings,
+    deadline: Option<Deadline>,
+    /// Start of the run, used as the creation time of the run-level entries.
+    started: Clock,
+    /// Entries of the submission so far, by their path or the ID of their entity, with whether
+    /// their status is OK.
+    members: Mutex<Vec<(String, bool)>>,
+}
 
-                if report.is_error() {
+impl RunState {
+    /// Errors concerning the submission as a whole rather than a single file.
+    fn errors(&self) -> Vec<String> {
+        self.consent.errors.clone()
+    }
+
+    /// Findings concerning the run as a whole rather than a single file.
+    fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.digests.duplicates();
+        warnings.extend(self.consent.warnings.iter().cloned());
+        warnings
+    }
+
+    /// Adds an entry of the submission, given by its path or the ID of its entity.
+    fn add_member(&self, member: String, is_ok: bool) {
+        self.members.lock().unwrap().push((member, is_ok));
+    }
+
+    /// The submission with the run-level findings and all entries added so far as members.
+    fn submission(&self) -> Entity {
+        let mut submission = Entity::new(EntityKind::Submission, "");
+        for (member, is_ok) in self.members.lock().unwrap().iter() {
+            submission.add_member(member.clone(), *is_ok);
+        }
+        submission.errors = self.errors();
+        submission.warnings = self.warnings();
+        submission
+    }
+}
+
+/// Applies the run-wide options to the result of a job and records it in `run_state`.
+fn finish_job(report: &mut CheckResult, options: &RunOptions, run_state: &RunState) {
+    for file_report in report.file_reports_mut() {
+        file_report.aliases = run_state.aliases.of(&file_report.path).to_vec();
+    }
+    if options.strict_extensions {
+        report.check_extensions();
+    }
+    if let Some(constraints) = &options.path_constraints {
+        report.check_paths(constraints);
+    }
+    if let Some(max_n_fraction) = options.max_n_fraction {
+        for file_report in report.file_reports_mut() {
+            file_report.check_n_fraction(max_n_fraction);
+        }
+    }
+    if let Some(max_duplicate_fraction) = options.max_duplicate_fraction {
+        for file_report in report.file_reports_mut() {
+            file_report.check_duplicate_fraction(max_duplicate_fraction);
+        }
+    }
+    if !options.expected_records.is_empty() {
+        for file_report in report.file_reports_mut() {
+            if let Some(&expected) = options.expected_records.get(&file_report.path) {
+                file_report.check_record_count(expected);
+            }
+        }
+    }
+    if !options.expected_bases.is_empty() {
+        report.check_base_yield(&options.expected_bases, options.base_yield_tolerance);
+    }
+    if options.assess {
+        for file_report in report.file_reports_mut() {
+            file_report.demote_threshold_violations();
+        }
+    }
+    if options.quality_encoding_severity == Severity::Warning {
+        for file_report in report.file_reports_mut() {
+            file_report.demote(&[findings::QUALITY_ENCODING_CODE]);
+        }
+    }
+    if options.empty_record_severity == Severity::Warning {
+        for file_report in report.file_reports_mut() {
+            file_report.demote(&[findings::EMPTY_RECORD_CODE]);
+        }
+    }
+    if options.missing_quality_severity == Severity::Warning {
+        for file_report in report.file_reports_mut() {
+            file_report.demote(&[findings::MISSING_QUALITY_CODE]);
+        }
+    }
+    for file_report in report.file_reports_mut() {
+        if let (Some(registry), Some(config)) =
+            (&run_state.checksum_registry, &options.checksum_registry)
+            && let Some(id) = config.logical_id(&file_report.path)
+        {
+            checksum_registry::verify(registry.as_ref(), &id, file_report);
+        }
+        let Some(checksum) = &file_report.sha256 else {
+            continue;
+        };
+        run_state.digests.add(&file_report.path, checksum);
+        if let Some(db) = &run_state.checksum_db {
+            match db.check_and_record(&file_report.path, checksum) {
+                Ok(Some(warning)) => file_report.warnings.push(warning),
+                Ok(None) => {}
+                Err(e) => logging::warn(format!(
+                    "Failed to look up {} in the checksum database: {e}",
+                    file_report.path.display()
+                )),
+            }
+        }
+    }
+    run_state
+        .suppressed
+        .add(&report.suppress(&options.suppressions));
+    if let CheckResult::PairedFastq(pair_report) = report {
+        run_state.lab_data.add(pair_report);
+    }
+    run_state.add_member(
+        report.primary_path().display().to_string(),
+        !report.is_error(),
+    );
+}
+
+/// Writes the run-level entry with the status of the submission, which rolls up all other
+/// entries, returning whether there are run-level errors.
+fn write_run_report(
+    run_state: &RunState,
+    writer: &mut impl Write,
+    control: Option<&ControlState>,
+) -> anyhow::Result<bool> {
+    let submission = run_state.submission();
+    for error in &submission.errors {
+        logging::error(error);
+    }
+    for warning in &submission.warnings {
+        logging::warn(warning);
+    }
+    let mut lines = Vec::new();
+    write_json_report(
+        JsonReport::Run(RunReport {
+            status: submission.status(),
+            failed_members: &submission.failed_members,
+            errors: &submission.errors,
+            warnings: &submission.warnings,
+            findings: findings::collect(&submission.errors, &submission.warnings),
+        }),
+        run_state.started.stop(),
+        &mut lines,
+    )?;
+    writer.write_all(&lines)?;
+    if let Some(control) = control {
+        control.add_report_lines(&lines);
+    }
+    Ok(!submission.errors.is_empty())
+}
+
+/// Writes an entry with the aggregated statistics of each lab datum, whose status rolls up its
+/// pairs, returning whether any of them has errors of its own.
+fn write_lab_data_report(
+    run_state: &RunState,
+    options: &RunOptions,
+    writer: &mut impl Write,
+    control: Option<&ControlState>,
+) -> anyhow::Result<bool> {
+    let mut has_errors = false;
+    let mut lines = Vec::new();
+    let submission = run_state.submission();
+    for mut summary in run_state.lab_data.summaries() {
+        if options.assess {
+            findings::demote_threshold_violations(&mut summary.errors, &mut summary.warnings);
+        }
+        let lab_datum = summary.lab_datum;
+        logging::info(format!(
+            "Lab datum {}: {} read(s) with {} base(s) in {} FASTQ pair(s)",
+            lab_datum.id,
+            summary
+                .num_records
+                .map_or_else(|| "?".to_string(), |n| n.to_string()),
+            summary
+                .total_bases
+                .map_or_else(|| "?".to_string(), |n| n.to_string()),
+            lab_datum.pairs.len()
+        ));
+        for error in &summary.errors {
+            logging::error(format!("Lab datum {}: {error}", lab_datum.id));
+        }
+        for warning in &summary.warnings {
+            logging::warn(format!("Lab datum {}: {warning}", lab_datum.id));
+        }
+        has_errors |= !summary.errors.is_empty();
+        let mut sample = Entity::new(EntityKind::Sample, &lab_datum.id);
+        for pair in &lab_datum.pairs {
+            let id = pair.fq1.display().to_string();
+            // Pairs that were not checked at all, e.g. after an error, are not OK either.
+            let is_ok = submission.members.contains(&id) && !submission.has_failed(&id);
+            sample.add_member(id, is_ok);
+        }
+        sample.errors = summary.errors.clone();
+        run_state.add_member(sample.id.clone(), sample.is_ok());
+        write_json_report(
+            JsonReport::LabDatum(LabDatumReport {
+                id: &lab_datum.id,
+                status: sample.status(),
+                pairs: &lab_datum.pairs,
+                failed_members: &sample.failed_members,
+                num_records: summary.num_records,
+                total_bases: summary.total_bases,
+                declared_num_records: lab_datum.declared_num_records,
+                declared_total_bases: lab_datum.declared_total_bases,
+                errors: &summary.errors,
+                warnings: &summary.warnings,
+                findings: findings::collect(&summary.errors, &summary.warnings),
+            }),
+            run_state.started.stop(),
+            &mut lines,
+        )?;
+    }
+    writer.write_all(&lines)?;
+    if let Some(control) = control {
+        control.add_report_lines(&lines);
+    }
+    Ok(has_errors)
+}
+
+fn mark_job(control: Option<&ControlState>, id: usize, state: JobState) {
+    if let Some(control) = control {
+        control.set_job_state(id, state);
+    }
+}
+
+/// Writes the entry of a job that was not checked, because the deadline has passed.
+fn write_not_checked(
+    id: usize,
+    (check_type, paths): &PendingJob,
+    reason: &str,
+    writer: &mut impl Write,
+    control: Option<&ControlState>,
+) {
+    let mut lines = Vec::new();
+    let result = write_json_report(
+        JsonReport::NotChecked(NotCheckedReport {
+            check: check_type,
+            paths,
+            status: "NOT_CHECKED",
+            reason,
+            errors: &[],
+            warnings: &[],
+            findings: vec![],
+        }),
+        Clock::start().stop(),
+        &mut lines,
+    )
+    .and_then(|()| Ok(writer.write_all(&lines)?));
+    if let Err(e) = result {
+        logging::error(format!("Failed to write report entry: {e}"));
+    }
+    if let Some(control) = control {
+        control.add_report_lines(&lines);
+    }
+    mark_job(control, id, JobState::NotChecked);
+}
+
+/// Returns whether a job is skipped because the deadline has passed, in which case it is
+/// reported as not checked.
+fn skip_at_deadline(
+    id: usize,
+    run_state: &RunState,
+    writer: &Mutex<BufWriter<fs::File>>,
+    control: Option<&ControlState>,
+) -> bool {
+    let Some(deadline) = &run_state.deadline else {
+        return false;
+    };
+    if !deadline.is_reached() {
+        return false;
+    }
+    let mut writer_guard = writer.lock().unwrap();
+    if let Some(job) = deadline.skip(id) {
+        let (_, paths) = &job;
+        if let Some(path) = paths.first() {
+            run_state.add_member(path.display().to_string(), false);
+        }
+        write_not_checked(id, &job, DEADLINE_REASON, &mut *writer_guard, control);
+    }
+    true
+}
+
+const DEADLINE_REASON: &str = "The deadline of the run passed before the job was started.";
+const GRACE_REASON: &str =
+    "The job did not finish within the grace period after the deadline of the run.";
+
+/// Stages and checks the files of a job, runs the external checks, then applies the run-wide
+/// options and the post-check hook to the result.
+///
+/// Returns the result with the times at which the job started and its checks completed.
+fn run_job(
+    progress: &mut (MultiProgress, ProgressBar, ProgressStyle),
+    id: usize,
+    job: Job,
+    options: &RunOptions,
+    run_state: &RunState,
+    control: Option<&ControlState>,
+) -> (CheckResult, EntryTimes) {
+    let clock = Clock::start();
+    run_state.settings.heartbeat.beat();
+    let paths = job.paths();
+    let check_type = job.check_type();
+    let permit = run_state.mounts.acquire(&paths);
+    let staged = options
+        .staging_hook
+        .as_ref()
+        .map(|hook| hook.stage_all(&paths));
+    mark_job(control, id, JobState::Running);
+    let mut report = match CheckResult::missing(&job) {
+        Some(report) => {
+            progress.1.inc(job.size());
+            report
+        }
+        None => process_job(progress, &run_state.settings, job),
+    };
+    drop(permit);
+    if let Some(staged) = staged {
+        report.record_staging(staged);
+    }
+    if !options.external_checks.is_empty() {
+        report.run_external_checks(&options.external_checks, check_type);
+    }
+    finish_job(&mut report, options, run_state);
+    let times = clock.stop();
+    // Failed files are preserved before the post-check hook may move them away.
+    if let Some(quarantine) = &options.quarantine {
+        report.quarantine_failed(
+            quarantine,
+            options.stats,
+            options.report_digest_encoding,
+            times,
+        );
+    }
+    if let Some(hook) = &options.post_check_hook {
+        report.run_post_check_hook(hook);
+    }
+    if options.report_digest_encoding != DigestEncoding::Hex {
+        report.encode_digests(options.report_digest_encoding);
+    }
+    run_state.settings.heartbeat.beat();
+    (report, times)
+}
+
+#[allow(clippy::result_large_err)]
+#[allow(clippy::too_many_arguments)]
+fn process_jobs(
+    jobs: Vec<Job>,
+    discovered: Option<Box<dyn Iterator<Item = Job> + Send + '_>>,
+    options: &RunOptions,
+    shutdown_flag: Arc<AtomicBool>,
+    mpb: MultiProgress,
+    main_pb: ProgressBar,
+    file_style: ProgressStyle,
+    writer: Arc<Mutex<BufWriter<fs::File>>>,
+    control: Option<&ControlState>,
+    run_state: &RunState,
+) -> Result<(), EarlyExitError> {
+    let result_state = |report: &CheckResult| {
+        if report.is_error() {
+            JobState::Error
+        } else {
+            JobState::Ok
+        }
+    };
+
+    let queue = Queue::new(
+        options.schedule,
+        jobs,
+        Job::size,
+        rayon::current_num_threads(),
+    )
+    .followed_by(discovered.into_iter().flatten());
+    if options.continue_on_error {
+        let num_failed_jobs = Arc::new(AtomicUsize::new(0));
+
+        queue.par_bridge().for_each_with(
+            (
+                mpb,
+                main_pb.clone(),
+                file_style,
+                writer,
+                num_failed_jobs.clone(),
+            ),
+            |(mpb, main_pb, style, writer, num_failed), (id, job, _huge_slot)| {
+                if shutdown_flag.load(Ordering::Relaxed)
+                    || skip_at_deadline(id, run_state, writer, control)
+                {
+                    return;
+                }
+
+                let (report, times) = run_job(
+                    &mut (mpb.clone(), main_pb.clone(), style.clone()),
+                    id,
+                    job,
+                    options,
+                    run_state,
+                    control,
+                );
+
+                if report.is_error() {
                     num_failed.fetch_add(1, Ordering::SeqCst);
                 }
 
                 let mut writer_guard = writer.lock().unwrap();
-                if let Err(e) = write_jsonl_report_entry(&report, &mut *writer_guard) {
-                    eprintln!(
-                        "Failed to write report line for {:?}: {}",
-                        report.primary_path(),
-                        e
-                    );
+                write_report(&report, options.stats, times, &mut *writer_guard, control);
+                if let Some(deadline) = &run_state.deadline {
+                    deadline.finish(id);
                 }
+                drop(writer_guard);
+                log_result(&report);
+                mark_job(control, id, result_state(&report));
             },
         );
 
         let final_fail_count = num_failed_jobs.load(Ordering::SeqCst);
+        let num_not_checked = run_state
+            .deadline
+            .as_ref()
+            .map_or(0, Deadline::num_not_checked);
         if shutdown_flag.load(Ordering::SeqCst) {
             main_pb.abandon_with_message("✗ Operation cancelled by user.");
+        } else if num_not_checked > 0 {
+            main_pb.abandon_with_message(format!(
+                "✗ Deadline reached. {final_fail_count} pairs/files failed, {num_not_checked} job(s) not checked."
+            ));
         } else if final_fail_count > 0 {
             main_pb.abandon_with_message(format!(
                 "✗ Processing complete. {final_fail_count} pairs/files failed."
             ));
+            logging::info(format!(
+                "Processing complete. {final_fail_count} pairs/files failed."
+            ));
         } else {
             main_pb.finish_with_message("✓ All checks passed!");
+            logging::info("All checks passed!");
         }
 
         Ok(())
     } else {
-        jobs.into_par_iter().try_for_each_with(
+        queue.par_bridge().try_for_each_with(
             (mpb, main_pb, file_style, writer),
-            |(mpb, main_pb, style, writer), job| {
+            |(mpb, main_pb, style, writer), (id, job, _huge_slot)| {
                 if shutdown_flag.load(Ordering::Relaxed) {
                     return Err(EarlyExitError(StopReason::Interrupted));
                 }
-                let report = process_job(&mut (mpb.clone(), main_pb.clone(), style.clone()), job);
+                if skip_at_deadline(id, run_state, writer, control) {
+                    return Ok(());
+                }
+                let (report, times) = run_job(
+                    &mut (mpb.clone(), main_pb.clone(), style.clone()),
+                    id,
+                    job,
+                    options,
+                    run_state,
+                    control,
+                );
 
                 let mut writer_guard = writer.lock().unwrap();
-                if let Err(e) = write_jsonl_report_entry(&report, &mut *writer_guard) {
-                    eprintln!(
-                        "Failed to write report line for {:?}: {}",
-                        report.primary_path(),
-                        e
-                    );
+                write_report(&report, options.stats, times, &mut *writer_guard, control);
+                if let Some(deadline) = &run_state.deadline {
+                    deadline.finish(id);
                 }
                 writer_guard.flush().ok();
                 drop(writer_guard);
+                log_result(&report);
+                mark_job(control, id, result_state(&report));
 
                 if report.is_error() {
                     Err(EarlyExitError(StopReason::Error(report)))
@@ -421,10 +1901,10 @@ fn setup_signal_handler() -> anyhow::Result<()> {
         let handler_flag = SHUTDOWN_FLAG.clone();
         let set_handler_result = ctrlc::set_handler(move || {
             if handler_flag.swap(true, Ordering::SeqCst) {
-                eprintln!("\nSecond interrupt received, exiting immediately.");
+                logging::warn("\nSecond interrupt received, exiting immediately.");
                 std::process::exit(130);
             }
-            eprintln!("\nCtrl+C received, shutting down gracefully…");
+            logging::warn("\nCtrl+C received, shutting down gracefully…");
         });
 
         if let Err(e) = set_handler_result {
@@ -435,17 +1915,121 @@ fn setup_signal_handler() -> anyhow::Result<()> {
     result
 }
 
+/// Returns the flag set by Ctrl+C, e.g. to stop waiting for further submissions.
+pub fn shutdown_flag() -> anyhow::Result<Arc<AtomicBool>> {
+    setup_signal_handler()?;
+    Ok(SHUTDOWN_FLAG.clone())
+}
+
 pub fn run_check(
     jobs: Vec<Job>,
     total_bytes: u64,
     output: &Path,
-    continue_on_error: bool,
-    show_progress: Option<bool>,
+    options: &RunOptions,
+) -> anyhow::Result<()> {
+    check_jobs(jobs, total_bytes, None, output, options)
+}
+
+/// Like [`run_check`], but also checks the jobs received from `discovered` until all of its
+/// senders are dropped, e.g. the jobs of a directory scan that is still in progress. Files of
+/// discovered jobs that are reached through other paths are skipped without being listed as
+/// aliases, as the entries of their first paths may already have been written.
+pub fn run_check_streaming(
+    jobs: Vec<Job>,
+    total_bytes: u64,
+    discovered: Receiver<Job>,
+    output: &Path,
+    options: &RunOptions,
+) -> anyhow::Result<()> {
+    check_jobs(jobs, total_bytes, Some(discovered), output, options)
+}
+
+/// Settings of the checks of every file of a run.
+fn check_settings(options: &RunOptions) -> CheckSettings {
+    let read_group_mapping = (!options.lab_datum_bams.is_empty()).then(|| {
+        let mut declared: Vec<String> = options
+            .lab_data
+            .iter()
+            .map(|lab_datum| lab_datum.id.clone())
+            .chain(options.lab_datum_bams.values().flatten().cloned())
+            .collect();
+        declared.sort();
+        declared.dedup();
+        Arc::new(ReadGroupMapping {
+            assigned: options.lab_datum_bams.clone(),
+            declared,
+        })
+    });
+    CheckSettings {
+        verify_md5: options.verify_md5,
+        chunk_size: options.chunk_size,
+        merkle: options.merkle,
+        parallel_bgzf: (options.inflate_backend == InflateBackend::ParallelBgzf).then(|| {
+            NonZeroUsize::new(options.inflate_threads).unwrap_or(common::DEFAULT_INFLATE_THREADS)
+        }),
+        parse_leniency: options.parse_leniency,
+        strict_bases: options.strict_bases,
+        strict_read_names: options.strict_read_names,
+        adapter_screening: options.adapter_screening.clone(),
+        poly_g_screening: options.poly_g_screening.clone(),
+        umi: options.umi.clone(),
+        duplicate_names_memory: options.duplicate_names_memory,
+        validate_tags: options.validate_tags,
+        md5_reference: options
+            .bam_reference
+            .clone()
+            .map(|path| Arc::new(reference::Md5Reference::new(path))),
+        read_group_mapping,
+        heartbeat: options.heartbeat.clone(),
+    }
+}
+
+fn check_jobs(
+    jobs: Vec<Job>,
+    total_bytes: u64,
+    discovered: Option<Receiver<Job>>,
+    output: &Path,
+    options: &RunOptions,
 ) -> anyhow::Result<()> {
     setup_signal_handler()?;
+    let (jobs, aliased_bytes, aliases, mut deduper) = duplicates::dedupe_jobs(jobs);
+    let total_bytes = total_bytes.saturating_sub(aliased_bytes);
+    if !aliases.is_empty() {
+        logging::info(format!(
+            "Skipping {} path(s) reaching files that are already checked under other paths",
+            aliases.len()
+        ));
+    }
+    logging::info(format!(
+        "Inflating with {} ({})",
+        match options.inflate_backend {
+            InflateBackend::Flate2 => "flate2",
+            InflateBackend::ParallelBgzf => "parallel BGZF workers, flate2 for other gzip files",
+        },
+        common::ZLIB_IMPLEMENTATION
+    ));
+    // Connected before the report is created and the service manager is notified, so that an
+    // unusable database or registry fails the run without side effects.
+    debug_assert!(
+        options.checksum_db.is_none() || options.submission_id.is_some(),
+        "--checksum-db requires --submission-id"
+    );
+    let checksum_db = match (&options.checksum_db, &options.submission_id) {
+        (Some(path), Some(submission_id)) => Some(ChecksumDb::open(path, submission_id)?),
+        _ => None,
+    };
+    let checksum_registry = options
+        .checksum_registry
+        .as_ref()
+        .map(ChecksumRegistry::connect)
+        .transpose()?;
     let shutdown_flag = SHUTDOWN_FLAG.clone();
 
     let mpb = MultiProgress::new();
+    let show_progress = match logging::format() {
+        LogFormat::Text => options.show_progress,
+        LogFormat::Json => Some(false),
+    };
     match show_progress {
         Some(true) => {
             mpb.set_draw_target(ProgressDrawTarget::stderr());
@@ -468,15 +2052,183 @@ pub fn run_check(
             .with_context(|| format!("Failed to create report file at {}", output.display()))?,
     )));
 
-    let processing_result = process_jobs(
-        jobs,
-        continue_on_error,
-        shutdown_flag.clone(),
-        mpb.clone(),
-        main_pb.clone(),
-        file_style,
-        writer.clone(),
-    );
+    let control = options.control_socket.as_deref().map(|socket_path| {
+        let job_entries = jobs
+            .iter()
+            .enumerate()
+            .map(|(id, job)| JobEntry {
+                id,
+                check_type: job.check_type(),
+                paths: job.paths(),
+                state: JobState::Pending,
+            })
+            .collect();
+        let state = Arc::new(ControlState::new(
+            job_entries,
+            main_pb.clone(),
+            shutdown_flag.clone(),
+        ));
+        (socket_path, state)
+    });
+    let control_server = match &control {
+        Some((socket_path, state)) => Some(ControlServer::start(socket_path, state.clone())?),
+        None => None,
+    };
+
+    if let Err(e) = systemd::notify(&format!("STATUS=Checking {} job(s)", jobs.len())) {
+        logging::warn(format!("Failed to notify service manager: {e}"));
+    }
+    let run = options.heartbeat.run();
+    logging::info(format!(
+        "Checking {} job(s) with a total of {total_bytes} bytes",
+        jobs.len()
+    ));
+
+    let consent = if options.donor_consents.is_empty() {
+        consent::Findings::default()
+    } else {
+        let paths: Vec<PathBuf> = jobs.iter().flat_map(Job::paths).collect();
+        let paths: Vec<&Path> = paths
+            .iter()
+            .flat_map(|path| {
+                std::iter::once(path.as_path()).chain(aliases.of(path).iter().map(PathBuf::as_path))
+            })
+            .collect();
+        consent::check(&options.donor_consents, &options.consent_categories, &paths)
+    };
+    let run_state = RunState {
+        settings: check_settings(options),
+        checksum_db,
+        checksum_registry,
+        lab_data: LabDataTotals::new(&options.lab_data),
+        mounts: Mounts::new(&options.mount_limits),
+        aliases,
+        consent,
+        deadline: options.deadline.map(|at| {
+            let jobs = jobs
+                .iter()
+                .enumerate()
+                .map(|(id, job)| (id, (job.check_type(), job.paths())))
+                .collect();
+            Deadline::new(at, options.deadline_grace, jobs)
+        }),
+        ..Default::default()
+    };
+    #[allow(clippy::result_large_err)]
+    let processing_result = thread::scope(|scope| {
+        let (stop_grace_watcher, stopped) = mpsc::channel::<()>();
+        if let Some(until_cancellation) = run_state
+            .deadline
+            .as_ref()
+            .and_then(Deadline::until_cancellation)
+        {
+            let writer = writer.clone();
+            let socket_path = control.as_ref().map(|(socket_path, _)| *socket_path);
+            let control = control.as_ref().map(|(_, state)| state.clone());
+            let run_state = &run_state;
+            scope.spawn(move || {
+                if stopped.recv_timeout(until_cancellation) != Err(RecvTimeoutError::Timeout) {
+                    return;
+                }
+                let Some(deadline) = &run_state.deadline else {
+                    return;
+                };
+                let mut writer_guard = writer.lock().unwrap();
+                let jobs = deadline.skip_all();
+                for (id, job) in &jobs {
+                    write_not_checked(
+                        *id,
+                        job,
+                        GRACE_REASON,
+                        &mut *writer_guard,
+                        control.as_deref(),
+                    );
+                }
+                // The process exits without unwinding, so the run-level entries are written and
+                // the control socket is removed here rather than after the jobs.
+                let result = write_lab_data_report(
+                    run_state,
+                    options,
+                    &mut *writer_guard,
+                    control.as_deref(),
+                )
+                .and_then(|_| write_run_report(run_state, &mut *writer_guard, control.as_deref()))
+                .and_then(|_| Ok(writer_guard.flush()?));
+                if let Err(e) = result {
+                    logging::error(format!("Failed to write run-level report entries: {e}"));
+                }
+                if let Some(socket_path) = socket_path {
+                    let _ = fs::remove_file(socket_path);
+                }
+                if let Err(e) = systemd::notify("STOPPING=1") {
+                    logging::warn(format!("Failed to notify service manager: {e}"));
+                }
+                logging::error(format!(
+                    "Grace period after the deadline passed; cancelled {} unfinished job(s)",
+                    jobs.len()
+                ));
+                std::process::exit(1);
+            });
+        }
+        let discovered = discovered.map(|receiver| {
+            let main_pb = main_pb.clone();
+            Box::new(
+                receiver
+                    .into_iter()
+                    .filter_map(move |job| match deduper.keep(job) {
+                        Ok(job) => {
+                            main_pb.inc_length(job.size());
+                            Some(job)
+                        }
+                        Err(dropped) => {
+                            for (checked_path, path) in dropped {
+                                logging::info(format!(
+                                    "Skipping {}, which reaches the file checked as {}",
+                                    path.display(),
+                                    checked_path.display()
+                                ));
+                            }
+                            None
+                        }
+                    }),
+            ) as Box<dyn Iterator<Item = Job> + Send>
+        });
+        let result = process_jobs(
+            jobs,
+            discovered,
+            options,
+            shutdown_flag.clone(),
+            mpb.clone(),
+            main_pb.clone(),
+            file_style,
+            writer.clone(),
+            control.as_ref().map(|(_, state)| state.as_ref()),
+            &run_state,
+        );
+        drop(stop_grace_watcher);
+        result
+    });
+    if let Some(summary) = run_state.suppressed.describe() {
+        logging::warn(summary);
+    }
+    let lab_data_errors = write_lab_data_report(
+        &run_state,
+        options,
+        &mut *writer.lock().unwrap(),
+        control.as_ref().map(|(_, state)| state.as_ref()),
+    )
+    .context("Failed to write lab datum report entries")?;
+    let run_errors = write_run_report(
+        &run_state,
+        &mut *writer.lock().unwrap(),
+        control.as_ref().map(|(_, state)| state.as_ref()),
+    )
+    .context("Failed to write run-level report entry")?;
+
+    drop(run);
+    if let Some(server) = control_server {
+        server.stop();
+    }
 
     if let Ok(mutex) = Arc::try_unwrap(writer)
         && let Ok(mut writer_guard) = mutex.into_inner()
@@ -491,9 +2243,32 @@ pub fn run_check(
         Ok(()) => {
             if shutdown_flag.load(Ordering::Relaxed) {
                 main_pb.abandon_with_message("✗ Operation cancelled by user.");
+                logging::warn("Operation cancelled by user.");
                 std::process::exit(130);
-            } else if !continue_on_error {
+            } else if let Some(deadline) = &run_state.deadline
+                && deadline.num_not_checked() > 0
+            {
+                anyhow::bail!(
+                    "The deadline was reached; {} job(s) were not checked.",
+                    deadline.num_not_checked()
+                );
+            } else if !options.continue_on_error && run_errors {
+                main_pb.abandon_with_message(format!(
+                    "✗ The submitted files violate the consent of a donor. See report: {}",
+                    output.display()
+                ));
+                anyhow::bail!(
+                    "The submitted files violate the consent scope of at least one donor."
+                );
+            } else if !options.continue_on_error && lab_data_errors {
+                main_pb.abandon_with_message(format!(
+                    "✗ Totals of lab data do not match the metadata. See report: {}",
+                    output.display()
+                ));
+                anyhow::bail!("The totals of at least one lab datum do not match the metadata.");
+            } else if !options.continue_on_error {
                 main_pb.finish_with_message("✓ All checks passed!");
+                logging::info("All checks passed!");
             }
         }
         Err(EarlyExitError(reason)) => match reason {
@@ -511,6 +2286,7 @@ pub fn run_check(
             }
             StopReason::Interrupted => {
                 main_pb.abandon_with_message("✗ Operation cancelled by user.");
+                logging::warn("Operation cancelled by user.");
                 std::process::exit(130);
             }
         },
@@ -519,37 +2295,200 @@ pub fn run_check(
     Ok(())
 }
 
+/// Fields of the report entries of all checked files, followed by those of their check type.
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
-struct FastqReport<'a> {
+struct FileFields<'a> {
     path: &'a Path,
     status: &'a str,
-    num_records: Option<u64>,
-    mean_read_length: Option<f64>,
     checksum: Option<&'a String>,
-    errors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chunk_checksums: Option<&'a ChunkDigests>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merkle: Option<&'a MerkleDigests>,
+    #[serde(skip_serializing_if = "<[PathBuf]>::is_empty")]
+    aliases: &'a [PathBuf],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<Compression>,
+    errors: &'a [String],
     warnings: &'a [String],
+    skipped_checks: &'a [&'static str],
+    not_evaluated: &'a [NotEvaluated],
+    findings: Vec<Finding<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suppressed_findings: Vec<Finding<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    staging_seconds: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timings: Option<Timings>,
+}
+
+impl<'a> FileFields<'a> {
+    fn new(report: &'a FileReport, stats: StatsLevel) -> Self {
+        FileFields {
+            path: &report.path,
+            status: report.status(),
+            checksum: report.sha256.as_ref(),
+            chunk_checksums: report.chunk_sha256.as_ref(),
+            merkle: report.merkle.as_ref(),
+            aliases: &report.aliases,
+            compression: report.compression,
+            errors: &report.errors,
+            warnings: &report.warnings,
+            skipped_checks: &report.skipped_checks,
+            not_evaluated: &report.not_evaluated,
+            findings: findings::collect(&report.errors, &report.warnings),
+            suppressed_findings: report.suppressed_findings(),
+            staging_seconds: report.staging_seconds,
+            timings: match stats {
+                StatsLevel::Basic => None,
+                StatsLevel::Full => report.timings,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct FastqReport<'a> {
+    #[serde(flatten)]
+    file: FileFields<'a>,
+    num_records: Option<u64>,
+    /// Number of mate pairs, for interleaved files only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_pairs: Option<u64>,
+    mean_read_length: Option<f64>,
+    modal_read_length: Option<u64>,
+    min_read_length: Option<u64>,
+    max_read_length: Option<u64>,
+    n_fraction: Option<f64>,
+    /// Percentage of `G` and `C` bases.
+    gc_content: Option<f64>,
+    /// Percentages of `A`, `C`, `G`, `T` and `N` bases.
+    base_composition: Option<BaseComposition>,
+    /// Mean Phred quality of all bases.
+    mean_quality: Option<f64>,
+    /// Median Phred quality of all bases, from a histogram of the quality characters.
+    median_quality: Option<u8>,
+    /// Fraction of reads containing an adapter sequence, with `--adapter-screening` only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    adapter_fraction: Option<f64>,
+    /// Fraction of reads ending in a poly-G tail, with `--poly-g-screening` only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poly_g_fraction: Option<f64>,
+    /// Fraction of reads with a valid UMI, with `--umi` only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    umi_fraction: Option<f64>,
+}
+
+impl<'a> FastqReport<'a> {
+    fn new(report: &'a FileReport, stats: StatsLevel, interleaved: bool) -> Self {
+        FastqReport {
+            file: FileFields::new(report, stats),
+            num_records: report.stats.map(|s| s.num_records),
+            num_pairs: report
+                .stats
+                .filter(|_| interleaved)
+                .map(|s| s.num_records / 2),
+            mean_read_length: report.stats.and_then(|s| s.mean_read_length()),
+            modal_read_length: report.stats.and_then(|s| s.modal_read_length),
+            min_read_length: report.stats.and_then(|s| s.min_read_length),
+            max_read_length: report.stats.and_then(|s| s.max_read_length),
+            n_fraction: report.stats.and_then(|s| s.n_fraction()),
+            gc_content: report.stats.and_then(|s| s.gc_content()),
+            base_composition: report.stats.and_then(|s| s.base_composition()),
+            mean_quality: report.stats.and_then(|s| s.quality).map(|q| q.mean),
+            median_quality: report.stats.and_then(|s| s.quality).map(|q| q.median),
+            adapter_fraction: report.stats.and_then(|s| s.adapter_fraction()),
+            poly_g_fraction: report.stats.and_then(|s| s.poly_g_fraction()),
+            umi_fraction: report.stats.and_then(|s| s.umi_fraction()),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 struct BamReport<'a> {
-    path: &'a Path,
-    status: &'a str,
+    #[serde(flatten)]
+    file: FileFields<'a>,
+    num_records: Option<u64>,
+    /// Number of records flagged as PCR or optical duplicates.
+    num_duplicates: Option<u64>,
+    /// Fraction of all records flagged as duplicates, as reported by `samtools flagstat`.
+    duplicate_fraction: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_mapped: Option<u64>,
+    /// Fraction of all records that are mapped, as reported by `samtools flagstat`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mapped_fraction: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alignment_status: Option<AlignmentStatus>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct VcfReport<'a> {
+    #[serde(flatten)]
+    file: FileFields<'a>,
+    num_records: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gvcf_blocks: Option<GvcfStats>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct FastaReport<'a> {
+    #[serde(flatten)]
+    file: FileFields<'a>,
+    num_records: Option<u64>,
+    total_length: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct BedReport<'a> {
+    #[serde(flatten)]
+    file: FileFields<'a>,
+    num_records: Option<u64>,
+    /// Number of bases covered by the regions, counting overlapping regions repeatedly.
+    total_length: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct TabixReport<'a> {
+    #[serde(flatten)]
+    file: FileFields<'a>,
+    /// Number of reference sequences in the index.
+    num_sequences: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct GziReport<'a> {
+    #[serde(flatten)]
+    file: FileFields<'a>,
+    /// Number of entries in the index.
+    num_entries: Option<u64>,
+}
+
+/// Report of a nanopore raw signal file (POD5 or FAST5).
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct SignalReport<'a> {
+    #[serde(flatten)]
+    file: FileFields<'a>,
+    /// Number of reads, for POD5 files only.
     num_records: Option<u64>,
-    checksum: Option<&'a String>,
-    errors: &'a [String],
-    warnings: &'a [String],
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 struct RawReport<'a> {
-    path: &'a Path,
-    status: &'a str,
-    checksum: Option<&'a String>,
-    errors: &'a [String],
-    warnings: &'a [String],
+    #[serde(flatten)]
+    file: FileFields<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected_checksum: Option<&'a String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -557,77 +2496,260 @@ struct RawReport<'a> {
 enum JsonReport<'a> {
     Fastq(FastqReport<'a>),
     Bam(BamReport<'a>),
+    Sam(BamReport<'a>),
+    Vcf(VcfReport<'a>),
+    Fasta(FastaReport<'a>),
+    Bed(BedReport<'a>),
+    Tabix(TabixReport<'a>),
+    Gzi(GziReport<'a>),
+    Pod5(SignalReport<'a>),
+    Fast5(SignalReport<'a>),
     Raw(RawReport<'a>),
+    Run(RunReport<'a>),
+    LabDatum(LabDatumReport<'a>),
+    Entity(EntityReport<'a>),
+    NotChecked(NotCheckedReport<'a>),
 }
 
-fn write_jsonl_report_entry<W: Write>(result: &CheckResult, writer: &mut W) -> anyhow::Result<()> {
-    match result {
-        CheckResult::PairedFastq(pair_report) => {
-            let is_pair_error = !pair_report.pair_errors.is_empty();
+/// Findings concerning an entity of several files, e.g. a FASTQ pair, rather than any single one
+/// of them.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct EntityReport<'a> {
+    kind: EntityKind,
+    id: &'a str,
+    status: &'a str,
+    members: &'a [String],
+    failed_members: &'a [String],
+    errors: &'a [String],
+    warnings: &'a [String],
+    findings: Vec<Finding<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suppressed_findings: Vec<Finding<'a>>,
+}
 
-            let r1 = &pair_report.fq1_report;
-            let r2 = &pair_report.fq2_report;
-            let file_reports = [r1, r2];
+/// A job that was not checked, e.g. because the deadline of the run had passed.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct NotCheckedReport<'a> {
+    /// Check type of the job.
+    check: &'static str,
+    paths: &'a [PathBuf],
+    status: &'a str,
+    reason: &'a str,
+    errors: &'a [String],
+    warnings: &'a [String],
+    findings: Vec<Finding<'a>>,
+}
 
-            for file_report in file_reports {
-                let mut errors = file_report.errors.clone();
-                if is_pair_error {
-                    errors.extend(pair_report.pair_errors.clone());
-                }
-                let status = if file_report.is_ok() && !is_pair_error {
-                    "OK"
-                } else {
-                    "ERROR"
-                };
+/// Findings concerning the run as a whole, e.g. files with identical content, and the status of
+/// the submission.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct RunReport<'a> {
+    status: &'a str,
+    /// Entries whose status is not OK, by their path or the ID of their entity.
+    failed_members: &'a [String],
+    errors: &'a [String],
+    warnings: &'a [String],
+    findings: Vec<Finding<'a>>,
+}
 
-                let report = JsonReport::Fastq(FastqReport {
-                    path: &file_report.path,
-                    status,
-                    num_records: file_report.stats.map(|s| s.num_records),
-                    mean_read_length: file_report.stats.and_then(|s| s.mean_read_length()),
-                    checksum: file_report.sha256.as_ref(),
-                    errors,
-                    warnings: &file_report.warnings,
-                });
-                serde_json::to_writer(&mut *writer, &report)?;
-                writer.write_all(b"\n")?;
-            }
-        }
-        CheckResult::SingleFastq(report) => {
-            let json_report = JsonReport::Fastq(FastqReport {
-                path: &report.path,
-                status: if report.is_ok() { "OK" } else { "ERROR" },
+/// Aggregated statistics of the FASTQ pairs of a lab datum, e.g. of several flowcells.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+struct LabDatumReport<'a> {
+    id: &'a str,
+    status: &'a str,
+    pairs: &'a [FastqPair],
+    /// First reads of the pairs whose status is not OK.
+    failed_members: &'a [String],
+    /// Total number of reads of all pairs.
+    num_records: Option<u64>,
+    /// Combined yield of all pairs in bases.
+    total_bases: Option<u64>,
+    declared_num_records: Option<u64>,
+    declared_total_bases: Option<u64>,
+    errors: &'a [String],
+    warnings: &'a [String],
+    findings: Vec<Finding<'a>>,
+}
+
+/// The start of the work behind a report entry on the wall clock, for ordering entries across
+/// machines, and on the monotonic clock, for measuring its duration.
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    wall: DateTime<Utc>,
+    monotonic: Instant,
+}
+
+impl Clock {
+    fn start() -> Self {
+        Clock {
+            wall: Utc::now(),
+            monotonic: Instant::now(),
+        }
+    }
+
+    fn stop(self) -> EntryTimes {
+        EntryTimes {
+            created_at: self.wall,
+            completed_at: Utc::now(),
+            duration: self.monotonic.elapsed(),
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::start()
+    }
+}
+
+/// When the work behind a report entry started and completed.
+#[derive(Debug, Clone, Copy)]
+struct EntryTimes {
+    created_at: DateTime<Utc>,
+    completed_at: DateTime<Utc>,
+    /// Measured on the monotonic clock, so unlike the difference of the timestamps it is not
+    /// affected by adjustments of the system time.
+    duration: Duration,
+}
+
+/// A line of the JSONL report.
+#[derive(Debug, Serialize)]
+struct ReportEntry<'a> {
+    schema_version: u64,
+    created_at: String,
+    completed_at: String,
+    duration_seconds: f64,
+    #[serde(flatten)]
+    report: JsonReport<'a>,
+}
+
+fn write_json_report<W: Write>(
+    report: JsonReport,
+    times: EntryTimes,
+    writer: &mut W,
+) -> anyhow::Result<()> {
+    let entry = ReportEntry {
+        schema_version: report::CURRENT_SCHEMA_VERSION,
+        created_at: times
+            .created_at
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        completed_at: times
+            .completed_at
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        duration_seconds: times.duration.as_secs_f64(),
+        report,
+    };
+    serde_json::to_writer(&mut *writer, &entry)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+fn write_jsonl_report_entry<W: Write>(
+    result: &CheckResult,
+    stats: StatsLevel,
+    times: EntryTimes,
+    writer: &mut W,
+) -> anyhow::Result<()> {
+    match result {
+        CheckResult::PairedFastq(pair_report) => {
+            for file_report in pair_report.file_reports() {
+                let report = JsonReport::Fastq(FastqReport::new(file_report, stats, false));
+                write_json_report(report, times, writer)?;
+            }
+            let pair = pair_report.entity();
+            let report = JsonReport::Entity(EntityReport {
+                kind: pair.kind,
+                id: &pair.id,
+                status: pair.status(),
+                members: &pair.members,
+                failed_members: &pair.failed_members,
+                errors: &pair.errors,
+                warnings: &pair.warnings,
+                findings: findings::collect(&pair.errors, &pair.warnings),
+                suppressed_findings: findings::collect(&pair_report.suppressed_pair_errors, &[]),
+            });
+            write_json_report(report, times, writer)?;
+        }
+        CheckResult::SingleFastq(report) | CheckResult::InterleavedFastq(report) => {
+            let is_interleaved = matches!(result, CheckResult::InterleavedFastq(_));
+            let json_report = JsonReport::Fastq(FastqReport::new(report, stats, is_interleaved));
+            write_json_report(json_report, times, writer)?;
+        }
+        CheckResult::Bam(report) | CheckResult::Sam(report) => {
+            let bam_report = BamReport {
+                file: FileFields::new(report, stats),
+                num_records: report.stats.map(|s| s.num_records),
+                num_duplicates: report.stats.and_then(|s| s.duplicate_records),
+                duplicate_fraction: report.stats.and_then(|s| s.duplicate_fraction()),
+                num_mapped: report.stats.and_then(|s| s.mapped_records),
+                mapped_fraction: report.stats.and_then(|s| s.mapped_fraction()),
+                alignment_status: report.stats.and_then(|s| s.alignment_status()),
+            };
+            let json_report = match result {
+                CheckResult::Sam(_) => JsonReport::Sam(bam_report),
+                _ => JsonReport::Bam(bam_report),
+            };
+            write_json_report(json_report, times, writer)?;
+        }
+        CheckResult::Vcf(report) => {
+            let json_report = JsonReport::Vcf(VcfReport {
+                file: FileFields::new(report, stats),
                 num_records: report.stats.map(|s| s.num_records),
-                mean_read_length: report.stats.and_then(|s| s.mean_read_length()),
-                checksum: report.sha256.as_ref(),
-                errors: report.errors.clone(),
-                warnings: &report.warnings,
+                gvcf_blocks: report.stats.and_then(|s| s.gvcf),
             });
-            serde_json::to_writer(&mut *writer, &json_report)?;
-            writer.write_all(b"\n")?;
+            write_json_report(json_report, times, writer)?;
         }
-        CheckResult::Bam(report) => {
-            let json_report = JsonReport::Bam(BamReport {
-                path: &report.path,
-                status: if report.is_ok() { "OK" } else { "ERROR" },
+        CheckResult::Fasta(report) => {
+            let json_report = JsonReport::Fasta(FastaReport {
+                file: FileFields::new(report, stats),
                 num_records: report.stats.map(|s| s.num_records),
-                checksum: report.sha256.as_ref(),
-                errors: &report.errors,
-                warnings: &report.warnings,
+                total_length: report.stats.and_then(|s| s.total_read_length),
             });
-            serde_json::to_writer(&mut *writer, &json_report)?;
-            writer.write_all(b"\n")?;
+            write_json_report(json_report, times, writer)?;
+        }
+        CheckResult::Bed(report) => {
+            let json_report = JsonReport::Bed(BedReport {
+                file: FileFields::new(report, stats),
+                num_records: report.stats.map(|s| s.num_records),
+                total_length: report.stats.and_then(|s| s.total_read_length),
+            });
+            write_json_report(json_report, times, writer)?;
+        }
+        CheckResult::Tabix(report) => {
+            let json_report = JsonReport::Tabix(TabixReport {
+                file: FileFields::new(report, stats),
+                num_sequences: report.stats.map(|s| s.num_records),
+            });
+            write_json_report(json_report, times, writer)?;
+        }
+        CheckResult::Gzi(report) => {
+            let json_report = JsonReport::Gzi(GziReport {
+                file: FileFields::new(report, stats),
+                num_entries: report.stats.map(|s| s.num_records),
+            });
+            write_json_report(json_report, times, writer)?;
+        }
+        CheckResult::Pod5(report) | CheckResult::Fast5(report) => {
+            let signal_report = SignalReport {
+                file: FileFields::new(report, stats),
+                num_records: report.stats.map(|s| s.num_records),
+            };
+            let json_report = match result {
+                CheckResult::Fast5(_) => JsonReport::Fast5(signal_report),
+                _ => JsonReport::Pod5(signal_report),
+            };
+            write_json_report(json_report, times, writer)?;
         }
         CheckResult::Raw(report) => {
             let json_report = JsonReport::Raw(RawReport {
-                path: &report.path,
-                status: if report.is_ok() { "OK" } else { "ERROR" },
-                checksum: report.sha256.as_ref(),
-                errors: &report.errors,
-                warnings: &report.warnings,
+                file: FileFields::new(report, stats),
+                expected_checksum: report.expected_sha256.as_ref(),
             });
-            serde_json::to_writer(&mut *writer, &json_report)?;
-            writer.write_all(b"\n")?;
+            write_json_report(json_report, times, writer)?;
         }
     }
     Ok(())
@@ -640,20 +2762,24 @@ mod tests {
     use flate2::Compression;
     use flate2::write::GzEncoder;
     use noodles::bam;
+    use noodles::bgzf::VirtualPosition;
+    use noodles::core::Position;
 
-    use crate::checks::fastq::ReadLengthCheck;
+    use crate::checks::fastq::{DeclaredReadLength, InterleavedFastqJob, ReadLengthCheck};
+    use crate::checks::reference::Species;
     use noodles::sam::alignment::io::Write as SamWrite;
     use noodles::sam::alignment::record::Flags;
     use noodles::sam::alignment::record::cigar::op::{Kind, Op};
     use noodles::sam::alignment::record_buf;
     use noodles::sam::alignment::record_buf::QualityScores;
-    use noodles::sam::header::record::value::map::ReadGroup;
+    use noodles::sam::header::record::value::map::{ReadGroup, ReferenceSequence};
     use noodles::sam::{Header, header::record::value::Map};
     use serde::Deserialize;
     use std::io::{BufRead, BufReader, Write};
+    use std::num::NonZeroUsize;
     use tempfile::tempdir;
 
-    fn create_gzipped_fastq(path: &Path, content: &str) -> Result<()> {
+    fn write_gzipped(path: &Path, content: &str) -> Result<()> {
         let file = fs::File::create(path)?;
         let mut writer = GzEncoder::new(file, Compression::default());
         writer.write_all(content.as_bytes())?;
@@ -672,36 +2798,36 @@ mod tests {
             let dir = tempdir.path().to_path_buf();
 
             // Case 1: Valid pair
-            create_gzipped_fastq(
+            write_gzipped(
                 &dir.join("ok_r1.fastq.gz"),
                 "@SEQ1\nACGT\n+\nFFFF\n@SEQ2\nTGCA\n+\nFFFF\n",
             )?;
-            create_gzipped_fastq(
+            write_gzipped(
                 &dir.join("ok_r2.fastq.gz"),
                 "@SEQ1\nAAAA\n+\nFFFF\n@SEQ2\nTTTT\n+\nFFFF\n",
             )?;
 
             // Case 2: Inconsistent length in a single file
-            create_gzipped_fastq(
+            write_gzipped(
                 &dir.join("badlen.fastq.gz"),
                 "@SEQ1\nACGT\n+\nFFFF\n@SEQ2\nTCG\n+\nFFF\n",
             )?;
 
             // Case 3: Mismatched read counts in a pair
-            create_gzipped_fastq(
+            write_gzipped(
                 &dir.join("counts1.fastq.gz"),
                 "@SEQ1\nACGT\n+\nFFFF\n@SEQ2\nTGCA\n+\nFFFF\n",
             )?;
-            create_gzipped_fastq(&dir.join("counts2.fastq.gz"), "@SEQ1\nACGT\n+\nFFFF\n")?;
+            write_gzipped(&dir.join("counts2.fastq.gz"), "@SEQ1\nACGT\n+\nFFFF\n")?;
 
             // Case 4: Malformed file (seq len != qual len)
-            create_gzipped_fastq(&dir.join("malformed.fastq.gz"), "@SEQ1\nACGT\n+\nFF\n")?;
+            write_gzipped(&dir.join("malformed.fastq.gz"), "@SEQ1\nACGT\n+\nFF\n")?;
 
             // Case 5: Empty file
-            create_gzipped_fastq(&dir.join("empty.fastq.gz"), "")?;
+            write_gzipped(&dir.join("empty.fastq.gz"), "")?;
 
             // Case 6: Read length 5 for R2 (for checking different read lengths in the same pair)
-            create_gzipped_fastq(
+            write_gzipped(
                 &dir.join("ok_r2_len5.fastq.gz"),
                 "@SEQ1\nAAAAA\n+\nFFFFF\n@SEQ2\nTTTTA\n+\nFFFFF\n",
             )?;
@@ -720,10 +2846,22 @@ mod tests {
         path: PathBuf,
         status: String,
         num_records: Option<u64>,
+        num_pairs: Option<u64>,
         mean_read_length: Option<f64>,
+        modal_read_length: Option<u64>,
+        min_read_length: Option<u64>,
+        max_read_length: Option<u64>,
+        #[serde(default)]
+        n_fraction: Option<f64>,
+        gc_content: Option<f64>,
+        base_composition: Option<serde_json::Value>,
+        mean_quality: Option<f64>,
+        median_quality: Option<u8>,
         checksum: Option<String>,
+        compression: Option<String>,
         errors: Vec<String>,
         warnings: Vec<String>,
+        skipped_checks: Vec<String>,
     }
 
     #[allow(dead_code)]
@@ -733,9 +2871,119 @@ mod tests {
         path: PathBuf,
         status: String,
         num_records: Option<u64>,
+        num_duplicates: Option<u64>,
+        duplicate_fraction: Option<f64>,
+        num_mapped: Option<u64>,
+        mapped_fraction: Option<f64>,
+        alignment_status: Option<String>,
+        checksum: Option<String>,
+        errors: Vec<String>,
+        warnings: Vec<String>,
+        skipped_checks: Vec<String>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "snake_case")]
+    struct TestVcfReportData {
+        path: PathBuf,
+        status: String,
+        num_records: Option<u64>,
+        checksum: Option<String>,
+        errors: Vec<String>,
+        warnings: Vec<String>,
+        not_evaluated: Vec<serde_json::Value>,
+        gvcf_blocks: Option<serde_json::Value>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "snake_case")]
+    struct TestFastaReportData {
+        path: PathBuf,
+        status: String,
+        num_records: Option<u64>,
+        total_length: Option<u64>,
         checksum: Option<String>,
         errors: Vec<String>,
         warnings: Vec<String>,
+        #[serde(default)]
+        suppressed_findings: Vec<serde_json::Value>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "snake_case")]
+    struct TestTabixReportData {
+        path: PathBuf,
+        status: String,
+        num_sequences: Option<u64>,
+        errors: Vec<String>,
+        skipped_checks: Vec<String>,
+        findings: Vec<serde_json::Value>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "snake_case")]
+    struct TestGziReportData {
+        path: PathBuf,
+        status: String,
+        num_entries: Option<u64>,
+        errors: Vec<String>,
+        findings: Vec<serde_json::Value>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "snake_case")]
+    struct TestBedReportData {
+        path: PathBuf,
+        status: String,
+        num_records: Option<u64>,
+        total_length: Option<u64>,
+        errors: Vec<String>,
+        warnings: Vec<String>,
+        skipped_checks: Vec<String>,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "snake_case")]
+    struct TestRunReportData {
+        status: String,
+        failed_members: Vec<String>,
+        errors: Vec<String>,
+        warnings: Vec<String>,
+        findings: Vec<serde_json::Value>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "snake_case")]
+    struct TestLabDatumReportData {
+        id: String,
+        status: String,
+        pairs: Vec<serde_json::Value>,
+        failed_members: Vec<String>,
+        num_records: Option<u64>,
+        total_bases: Option<u64>,
+        errors: Vec<String>,
+        warnings: Vec<String>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "snake_case")]
+    struct TestEntityReportData {
+        kind: String,
+        id: String,
+        status: String,
+        members: Vec<String>,
+        failed_members: Vec<String>,
+        errors: Vec<String>,
+        warnings: Vec<String>,
+        #[serde(default)]
+        suppressed_findings: Vec<serde_json::Value>,
     }
 
     #[allow(dead_code)]
@@ -745,8 +2993,14 @@ mod tests {
         path: PathBuf,
         status: String,
         checksum: Option<String>,
+        #[serde(default)]
+        expected_checksum: Option<String>,
         errors: Vec<String>,
         warnings: Vec<String>,
+        timings: Option<serde_json::Value>,
+        staging_seconds: Option<f64>,
+        #[serde(default)]
+        aliases: Vec<PathBuf>,
     }
 
     #[derive(Deserialize, Debug, Clone)]
@@ -754,10 +3008,37 @@ mod tests {
     enum TestReport {
         Fastq(TestFastqReportData),
         Bam(TestBamReportData),
+        Sam(TestBamReportData),
+        Vcf(TestVcfReportData),
+        Fasta(TestFastaReportData),
+        Bed(TestBedReportData),
+        Tabix(TestTabixReportData),
+        Gzi(TestGziReportData),
+        Run(TestRunReportData),
+        LabDatum(TestLabDatumReportData),
+        Entity(TestEntityReportData),
         Raw(TestRawReportData),
+        NotChecked(TestNotCheckedReportData),
     }
 
-    fn read_jsonl_report(report_path: &Path) -> Result<Vec<TestReport>> {
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "snake_case")]
+    struct TestNotCheckedReportData {
+        check: String,
+        paths: Vec<PathBuf>,
+        status: String,
+        reason: String,
+    }
+
+    fn test_options(continue_on_error: bool) -> RunOptions {
+        RunOptions {
+            continue_on_error,
+            show_progress: Some(false),
+            ..Default::default()
+        }
+    }
+
+    fn read_all_report_entries(report_path: &Path) -> Result<Vec<TestReport>> {
         let file = fs::File::open(report_path)?;
         let reader = BufReader::new(file);
         reader
@@ -769,6 +3050,109 @@ mod tests {
             .collect()
     }
 
+    /// Reads the per-file entries of a report, skipping the entries of entities and the
+    /// run-level entry.
+    fn read_jsonl_report(report_path: &Path) -> Result<Vec<TestReport>> {
+        Ok(read_all_report_entries(report_path)?
+            .into_iter()
+            .filter(|entry| {
+                !matches!(
+                    entry,
+                    TestReport::Run(_) | TestReport::LabDatum(_) | TestReport::Entity(_)
+                )
+            })
+            .collect())
+    }
+
+    /// Report data of one check type, taken from its [`TestReport`].
+    trait FromTestReport: Sized {
+        fn from_report(report: &TestReport) -> Option<Self>;
+    }
+
+    impl FromTestReport for TestBamReportData {
+        fn from_report(report: &TestReport) -> Option<Self> {
+            match report {
+                TestReport::Bam(data) | TestReport::Sam(data) => Some(data.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    impl FromTestReport for TestVcfReportData {
+        fn from_report(report: &TestReport) -> Option<Self> {
+            match report {
+                TestReport::Vcf(data) => Some(data.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    impl FromTestReport for TestFastaReportData {
+        fn from_report(report: &TestReport) -> Option<Self> {
+            match report {
+                TestReport::Fasta(data) => Some(data.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    impl FromTestReport for TestBedReportData {
+        fn from_report(report: &TestReport) -> Option<Self> {
+            match report {
+                TestReport::Bed(data) => Some(data.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    impl FromTestReport for TestTabixReportData {
+        fn from_report(report: &TestReport) -> Option<Self> {
+            match report {
+                TestReport::Tabix(data) => Some(data.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    impl FromTestReport for TestGziReportData {
+        fn from_report(report: &TestReport) -> Option<Self> {
+            match report {
+                TestReport::Gzi(data) => Some(data.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    /// Checks `job` on its own and returns the entry of its file, whose report is written next
+    /// to the file.
+    fn check_single_job<T: FromTestReport>(job: Job) -> Result<T> {
+        check_single_job_with_options(job, &test_options(true))
+    }
+
+    fn check_single_job_with_options<T: FromTestReport>(
+        job: Job,
+        options: &RunOptions,
+    ) -> Result<T> {
+        let output = job.paths()[0].with_extension("report.jsonl");
+        let size = job.size();
+        run_check(vec![job], size, &output, options)?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 1);
+        T::from_report(&records[0]).ok_or_else(|| anyhow!("Unexpected report {:?}", records[0]))
+    }
+
+    /// Reads the entries of the entities of a report, e.g. of FASTQ pairs.
+    fn read_entity_reports(report_path: &Path) -> Result<Vec<TestEntityReportData>> {
+        Ok(read_all_report_entries(report_path)?
+            .into_iter()
+            .filter_map(|entry| match entry {
+                TestReport::Entity(data) => Some(data),
+                _ => None,
+            })
+            .collect())
+    }
+
     #[test]
     fn test_valid_pair_with_different_lengths() -> Result<()> {
         let fixture = TestFiles::new()?;
@@ -784,11 +3168,13 @@ mod tests {
             fq1_path,
             fq2_path,
             length_check: ReadLengthCheck::Fixed(3),
+            fq1_declared_read_length: None,
+            fq2_declared_read_length: None,
             fq1_size,
             fq2_size,
         })];
 
-        run_check(jobs, total_bytes, &output, false, Some(false))?;
+        run_check(jobs, total_bytes, &output, &test_options(false))?;
 
         let mut records = read_jsonl_report(&output)?;
         records.sort_by(|a, b| match (a, b) {
@@ -800,6 +3186,7 @@ mod tests {
         if let TestReport::Fastq(data) = &records[0] {
             assert!(data.path.ends_with("ok_r1.fastq.gz"));
             assert_eq!(data.status, "OK");
+            assert_eq!(data.skipped_checks, vec!["fastq.declared_read_length"]);
         } else {
             panic!("Expected a Fastq report for R1");
         }
@@ -814,80 +3201,594 @@ mod tests {
     }
 
     #[test]
-    fn test_multiple_inputs_with_continue_on_error() -> Result<()> {
-        let fixture = TestFiles::new()?;
-        let output = fixture.dir.join("report.jsonl");
+    fn test_phred64_quality_encoding() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("legacy.fastq.gz");
+        write_gzipped(
+            &path,
+            "@SEQ1\nACGT\n+\nFFFF\n@SEQ2\nACGT\n+\n@Bhh\n@SEQ3\nACGT\n+\nhhhh\n",
+        )?;
+        let size = fs::metadata(&path)?.len();
+        let job = || {
+            Job::SingleFastq(SingleFastqJob {
+                path: path.clone(),
+                length_check: ReadLengthCheck::Skip,
+                declared_read_length: None,
+                size,
+            })
+        };
+        let expected = "Quality scores appear to be Phred+64 encoded (quality characters range from '@' to 'h'), first detected at record #2 ('SEQ2'). Expected Phred+33.";
 
-        let mut jobs = Vec::new();
-        let mut total_bytes = 0;
+        let output = dir.path().join("report.jsonl");
+        run_check(vec![job()], size, &output, &test_options(true))?;
+        let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Fastq report");
+        };
+        assert_eq!(data.errors, vec![expected]);
 
-        let p1f1_path = fixture.dir.join("counts1.fastq.gz");
-        let p1f2_path = fixture.dir.join("counts2.fastq.gz");
-        let p1f1_size = fs::metadata(&p1f1_path)?.len();
-        let p1f2_size = fs::metadata(&p1f2_path)?.len();
-        total_bytes += p1f1_size + p1f2_size;
-        jobs.push(Job::PairedFastq(PairedFastqJob {
-            fq1_path: p1f1_path,
-            fq2_path: p1f2_path,
-            length_check: ReadLengthCheck::Fixed(4),
-            fq1_size: p1f1_size,
-            fq2_size: p1f2_size,
-        }));
+        let options = RunOptions {
+            quality_encoding_severity: Severity::Warning,
+            ..test_options(true)
+        };
+        let output = dir.path().join("report_warning.jsonl");
+        run_check(vec![job()], size, &output, &options)?;
+        let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Fastq report");
+        };
+        assert_eq!(data.status, "OK");
+        assert_eq!(data.warnings, vec![expected]);
+        Ok(())
+    }
 
-        let p2f1_path = fixture.dir.join("ok_r1.fastq.gz");
-        let p2f2_path = fixture.dir.join("ok_r2.fastq.gz");
-        let p2f1_size = fs::metadata(&p2f1_path)?.len();
-        let p2f2_size = fs::metadata(&p2f2_path)?.len();
-        total_bytes += p2f1_size + p2f2_size;
-        jobs.push(Job::PairedFastq(PairedFastqJob {
-            fq1_path: p2f1_path,
-            fq2_path: p2f2_path,
-            length_check: ReadLengthCheck::Fixed(3),
-            fq1_size: p2f1_size,
-            fq2_size: p2f2_size,
-        }));
+    #[test]
+    fn test_empty_records() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("trimmed.fastq.gz");
+        write_gzipped(
+            &path,
+            "@SEQ1\nACGT\n+\nFFFF\n@SEQ2\n\n+\n\n@SEQ3\nACGT\n+\nFFFF\n@SEQ4\n\n+\n\n",
+        )?;
+        let size = fs::metadata(&path)?.len();
+        let job = || {
+            Job::SingleFastq(SingleFastqJob {
+                path: path.clone(),
+                length_check: ReadLengthCheck::Skip,
+                declared_read_length: None,
+                size,
+            })
+        };
+        let expected = "File contains 2 record(s) with an empty sequence or quality string. First detected at record #2 ('SEQ2').";
 
-        let s1_path = fixture.dir.join("badlen.fastq.gz");
-        let s1_size = fs::metadata(&s1_path)?.len();
-        total_bytes += s1_size;
-        jobs.push(Job::SingleFastq(SingleFastqJob {
-            path: s1_path,
-            length_check: ReadLengthCheck::Fixed(4),
-            size: s1_size,
-        }));
+        let output = dir.path().join("report.jsonl");
+        run_check(vec![job()], size, &output, &test_options(true))?;
+        let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Fastq report");
+        };
+        assert_eq!(data.errors, vec![expected]);
 
-        run_check(jobs, total_bytes, &output, true, Some(false))?;
+        let options = RunOptions {
+            empty_record_severity: Severity::Warning,
+            ..test_options(true)
+        };
+        let output = dir.path().join("report_warning.jsonl");
+        run_check(vec![job()], size, &output, &options)?;
+        let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Fastq report");
+        };
+        assert_eq!(data.status, "OK");
+        assert_eq!(data.warnings, vec![expected]);
+        Ok(())
+    }
 
-        let records = read_jsonl_report(&output)?;
-        assert_eq!(records.len(), 5);
+    #[test]
+    fn test_illegal_quality_character() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("garbage.fastq.gz");
+        write_gzipped(
+            &path,
+            "@SEQ1\nACGT\n+\nFFFF\n@SEQ2\nACGT\n+\nFF\x07F\n@SEQ3\nACGT\n+\n\x7fFFF\n",
+        )?;
+        let size = fs::metadata(&path)?.len();
+        let job = Job::SingleFastq(SingleFastqJob {
+            path: path.clone(),
+            length_check: ReadLengthCheck::Skip,
+            declared_read_length: None,
+            size,
+        });
 
-        let find_report = |recs: &[TestReport], suffix: &str| -> TestReport {
-            recs.iter()
-                .find(|r| match r {
-                    TestReport::Fastq(d) => d.path.ends_with(suffix),
-                    _ => false,
-                })
-                .unwrap_or_else(|| panic!("Report for file ending in '{suffix}' not found"))
-                .clone()
+        let output = dir.path().join("report.jsonl");
+        run_check(vec![job], size, &output, &test_options(true))?;
+        let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Fastq report");
         };
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec![
+                "Illegal quality character 0x07 at position 3 of record #2 ('SEQ2'). Quality characters must be printable ASCII from '!' to '~' (Phred+33)."
+            ]
+        );
+        Ok(())
+    }
 
-        if let TestReport::Fastq(data) = find_report(&records, "counts1.fastq.gz") {
-            assert_eq!(data.status, "ERROR");
-            assert!(
-                data.errors
-                    .iter()
-                    .any(|e| e.contains("Mismatched read counts"))
-            );
-        }
+    #[test]
+    fn test_expected_record_count() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("truncated.fastq.gz");
+        write_gzipped(&path, "@SEQ1\nACGT\n+\nFFFF\n@SEQ2\nACGT\n+\nFFFF\n")?;
+        let size = fs::metadata(&path)?.len();
+        let job = || {
+            Job::SingleFastq(SingleFastqJob {
+                path: path.clone(),
+                length_check: ReadLengthCheck::Skip,
+                declared_read_length: None,
+                size,
+            })
+        };
 
-        if let TestReport::Fastq(data) = find_report(&records, "counts2.fastq.gz") {
-            assert_eq!(data.status, "ERROR");
-            assert!(
-                data.errors
-                    .iter()
-                    .any(|e| e.contains("Mismatched read counts"))
-            );
+        let output = dir.path().join("report.jsonl");
+        for (expected, errors) in [
+            (2, vec![]),
+            (
+                3,
+                vec![
+                    "Number of records (2) does not match the expected number of records (3); the file may be truncated or incomplete.",
+                ],
+            ),
+        ] {
+            let options = RunOptions {
+                expected_records: BTreeMap::from([(path.clone(), expected)]),
+                ..test_options(true)
+            };
+            run_check(vec![job()], size, &output, &options)?;
+            let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+                panic!("Expected a Fastq report");
+            };
+            assert_eq!(data.errors, errors);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_expected_base_yield_of_pair() -> Result<()> {
+        let dir = tempdir()?;
+        let fq1_path = dir.path().join("sample_R1.fastq.gz");
+        let fq2_path = dir.path().join("sample_R2.fastq.gz");
+        write_gzipped(&fq1_path, "@SEQ1/1\nACGTACGT\n+\nFFFFFFFF\n")?;
+        write_gzipped(&fq2_path, "@SEQ1/2\nACGTAC\n+\nFFFFFF\n")?;
+        let fq1_size = fs::metadata(&fq1_path)?.len();
+        let fq2_size = fs::metadata(&fq2_path)?.len();
+        let job = || {
+            Job::PairedFastq(PairedFastqJob {
+                fq1_path: fq1_path.clone(),
+                fq2_path: fq2_path.clone(),
+                length_check: ReadLengthCheck::Skip,
+                fq1_declared_read_length: None,
+                fq2_declared_read_length: None,
+                fq1_size,
+                fq2_size,
+            })
+        };
+
+        let output = dir.path().join("report.jsonl");
+        for (expected, errors) in [
+            (15, vec![]),
+            (
+                20,
+                vec![
+                    "Total number of bases of R1 and R2 (14) deviates from the expected yield (20) by 30.00%, more than the tolerance of 10.00%; the file may be truncated or incomplete.",
+                ],
+            ),
+        ] {
+            let options = RunOptions {
+                expected_bases: BTreeMap::from([(fq1_path.clone(), expected)]),
+                base_yield_tolerance: 0.1,
+                ..test_options(true)
+            };
+            run_check(vec![job()], fq1_size + fq2_size, &output, &options)?;
+            for entry in read_jsonl_report(&output)? {
+                let TestReport::Fastq(data) = entry else {
+                    panic!("Expected a Fastq report");
+                };
+                assert!(data.errors.is_empty(), "{:?}", data.errors);
+            }
+            let entities = read_entity_reports(&output)?;
+            assert_eq!(entities.len(), 1);
+            assert_eq!(entities[0].errors, errors);
+        }
+
+        // A suppressed yield error is listed once, for the pair.
+        let options = RunOptions {
+            expected_bases: BTreeMap::from([(fq1_path.clone(), 20)]),
+            base_yield_tolerance: 0.1,
+            suppressions: vec![
+                suppress::parse_suppression("fastq.base_yield_mismatch:*")
+                    .map_err(anyhow::Error::msg)?,
+            ],
+            ..test_options(true)
+        };
+        run_check(vec![job()], fq1_size + fq2_size, &output, &options)?;
+        let entities = read_entity_reports(&output)?;
+        assert_eq!(entities[0].status, "OK", "{:?}", entities[0].errors);
+        assert_eq!(entities[0].suppressed_findings.len(), 1);
+        assert_eq!(
+            entities[0].suppressed_findings[0]["code"],
+            "fastq.base_yield_mismatch"
+        );
+        for entry in read_all_report_entries(&output)? {
+            if let TestReport::Fastq(data) = entry {
+                assert!(data.errors.is_empty(), "{:?}", data.errors);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_length_range() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("trimmed.fastq.gz");
+        write_gzipped(
+            &path,
+            "@SEQ1\nACG\n+\nFFF\n@SEQ2\nACGTA\n+\nFFFFF\n@SEQ3\nACGTA\n+\nFFFFF\n@SEQ4\nACGTACGT\n+\nFFFFFFFF\n",
+        )?;
+        let size = fs::metadata(&path)?.len();
+        let job = Job::SingleFastq(SingleFastqJob {
+            path: path.clone(),
+            length_check: ReadLengthCheck::Skip,
+            declared_read_length: None,
+            size,
+        });
+
+        let output = dir.path().join("report.jsonl");
+        run_check(vec![job], size, &output, &test_options(true))?;
+        let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Fastq report");
+        };
+        assert_eq!(data.status, "OK", "{:?}", data.errors);
+        assert_eq!(
+            (
+                data.min_read_length,
+                data.modal_read_length,
+                data.max_read_length
+            ),
+            (Some(3), Some(5), Some(8))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_n_fraction_threshold() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("failed_cycles.fastq.gz");
+        write_gzipped(
+            &path,
+            "@SEQ1\nACGTACNNNN\n+\nFFFFFFFFFF\n@SEQ2\nACGTACGTAC\n+\nFFFFFFFFFF\n",
+        )?;
+        let size = fs::metadata(&path)?.len();
+        let job = || {
+            Job::SingleFastq(SingleFastqJob {
+                path: path.clone(),
+                length_check: ReadLengthCheck::Skip,
+                declared_read_length: None,
+                size,
+            })
+        };
+        let expected = "Fraction of N bases (0.2000, 4 of 20 bases) exceeds the maximum of 0.1; failed sequencing cycles may have produced all-N reads or tails.";
+
+        let output = dir.path().join("report.jsonl");
+        run_check(vec![job()], size, &output, &test_options(true))?;
+        let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Fastq report");
+        };
+        assert_eq!(data.n_fraction, Some(0.2));
+        assert_eq!(data.gc_content, Some(40.0));
+        assert_eq!(
+            (data.mean_quality, data.median_quality),
+            (Some(37.0), Some(37))
+        );
+        assert_eq!(
+            data.base_composition,
+            Some(serde_json::json!({"A": 25.0, "C": 25.0, "G": 15.0, "T": 15.0, "N": 20.0}))
+        );
+        assert!(data.errors.is_empty());
+
+        for assess in [false, true] {
+            let options = RunOptions {
+                max_n_fraction: Some(0.1),
+                assess,
+                ..test_options(true)
+            };
+            let output = dir.path().join(format!("report_{assess}.jsonl"));
+            run_check(vec![job()], size, &output, &options)?;
+            let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+                panic!("Expected a Fastq report");
+            };
+            let (errors, warnings) = if assess {
+                (&data.warnings, &data.errors)
+            } else {
+                (&data.errors, &data.warnings)
+            };
+            assert_eq!(errors, &vec![expected]);
+            assert!(warnings.is_empty());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_sequence_characters() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("artifacts.fastq.gz");
+        write_gzipped(
+            &path,
+            "@SEQ1\nACGTRY\n+\nFFFFFF\n@SEQ2\nACgTAA\n+\nFFFFFF\n@SEQ3\nAC\0TAA\n+\nFFFFFF\n",
+        )?;
+        let size = fs::metadata(&path)?.len();
+        let job = Job::SingleFastq(SingleFastqJob {
+            path: path.clone(),
+            length_check: ReadLengthCheck::Skip,
+            declared_read_length: None,
+            size,
+        });
+
+        let output = dir.path().join("report.jsonl");
+        run_check(vec![job], size, &output, &test_options(true))?;
+        let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Fastq report");
+        };
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 2 record(s) with invalid sequence characters. First detected at position 3 of record #2 ('SEQ2'): 'g'. Sequences must only contain upper-case IUPAC nucleotide codes."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pair_with_mismatched_mate_names() -> Result<()> {
+        let dir = tempdir()?;
+        let output = dir.path().join("report.jsonl");
+        let fq1_path = dir.path().join("r1.fastq.gz");
+        let fq2_path = dir.path().join("r2.fastq.gz");
+        write_gzipped(
+            &fq1_path,
+            "@SEQ1/1 1:N:0:ACGT\nACGT\n+\nFFFF\n@SEQ2/1\nACGT\n+\nFFFF\n",
+        )?;
+        write_gzipped(
+            &fq2_path,
+            "@SEQ1/2 2:N:0:ACGT\nACGT\n+\nFFFF\n@SEQ3/2\nACGT\n+\nFFFF\n",
+        )?;
+        let fq1_size = fs::metadata(&fq1_path)?.len();
+        let fq2_size = fs::metadata(&fq2_path)?.len();
+        let jobs = vec![Job::PairedFastq(PairedFastqJob {
+            fq1_path,
+            fq2_path,
+            length_check: ReadLengthCheck::Skip,
+            fq1_declared_read_length: None,
+            fq2_declared_read_length: None,
+            fq1_size,
+            fq2_size,
+        })];
+        run_check(jobs, fq1_size + fq2_size, &output, &test_options(true))?;
+
+        // The mismatch concerns the pair, not either of its files.
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 2);
+        for record in records {
+            let TestReport::Fastq(data) = record else {
+                panic!("Expected a Fastq report");
+            };
+            assert_eq!(data.status, "OK");
+            assert!(data.errors.is_empty());
+        }
+        let pairs = read_entity_reports(&output)?;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(
+            (pairs[0].kind.as_str(), pairs[0].status.as_str()),
+            ("pair", "ERROR")
+        );
+        assert_eq!(pairs[0].members.len(), 2);
+        assert!(pairs[0].failed_members.is_empty());
+        assert_eq!(
+            pairs[0].errors,
+            vec![
+                "R1 and R2 have mismatched mate names at record #2 ('SEQ2/1' vs 'SEQ3/2'); the files may be shuffled or not belong together."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_declared_read_length_mismatch() -> Result<()> {
+        let fixture = TestFiles::new()?;
+        let output = fixture.dir.join("report.jsonl");
+
+        let fq1_path = fixture.dir.join("ok_r1.fastq.gz");
+        let fq2_path = fixture.dir.join("ok_r2_len5.fastq.gz");
+        let fq1_size = fs::metadata(&fq1_path)?.len();
+        let fq2_size = fs::metadata(&fq2_path)?.len();
+        let declared = DeclaredReadLength {
+            length: 4,
+            tolerance: 0,
+        };
+
+        let jobs = vec![Job::PairedFastq(PairedFastqJob {
+            fq1_path,
+            fq2_path,
+            length_check: ReadLengthCheck::Skip,
+            fq1_declared_read_length: Some(declared),
+            fq2_declared_read_length: Some(declared),
+            fq1_size,
+            fq2_size,
+        })];
+
+        run_check(jobs, fq1_size + fq2_size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 2);
+        for record in records {
+            let TestReport::Fastq(data) = record else {
+                panic!("Expected a Fastq report");
+            };
+            if data.path.ends_with("ok_r1.fastq.gz") {
+                assert_eq!(data.status, "OK");
+                assert_eq!(data.modal_read_length, Some(4));
+            } else {
+                assert_eq!(data.status, "ERROR");
+                assert_eq!(data.modal_read_length, Some(5));
+                assert_eq!(
+                    data.errors,
+                    vec![
+                        "Modal read length (5) deviates from declared read length (4) by more than the tolerance of 0 base(s)"
+                    ]
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_declared_read_length_of_interleaved_fastq() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("interleaved.fastq.gz");
+        write_gzipped(
+            &path,
+            "@SEQ1/1\nACGTA\n+\nFFFFF\n@SEQ1/2\nTTTTT\n+\nFFFFF\n",
+        )?;
+        let size = fs::metadata(&path)?.len();
+        let output = dir.path().join("report.jsonl");
+        for (length, errors) in [
+            (5, vec![]),
+            (
+                4,
+                vec![
+                    "Modal read length (5) deviates from declared read length (4) by more than the tolerance of 0 base(s)",
+                ],
+            ),
+        ] {
+            let jobs = vec![Job::InterleavedFastq(InterleavedFastqJob {
+                path: path.clone(),
+                length_check: ReadLengthCheck::Skip,
+                declared_read_length: Some(DeclaredReadLength {
+                    length,
+                    tolerance: 0,
+                }),
+                size,
+            })];
+            run_check(jobs, size, &output, &test_options(true))?;
+            let records = read_jsonl_report(&output)?;
+            let [TestReport::Fastq(data)] = records.as_slice() else {
+                panic!("Expected a Fastq report");
+            };
+            assert_eq!(data.errors, errors);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_inputs_with_continue_on_error() -> Result<()> {
+        let fixture = TestFiles::new()?;
+        let output = fixture.dir.join("report.jsonl");
+
+        let mut jobs = Vec::new();
+        let mut total_bytes = 0;
+
+        let p1f1_path = fixture.dir.join("counts1.fastq.gz");
+        let p1f2_path = fixture.dir.join("counts2.fastq.gz");
+        let p1f1_size = fs::metadata(&p1f1_path)?.len();
+        let p1f2_size = fs::metadata(&p1f2_path)?.len();
+        total_bytes += p1f1_size + p1f2_size;
+        jobs.push(Job::PairedFastq(PairedFastqJob {
+            fq1_path: p1f1_path,
+            fq2_path: p1f2_path,
+            length_check: ReadLengthCheck::Fixed(4),
+            fq1_declared_read_length: None,
+            fq2_declared_read_length: None,
+            fq1_size: p1f1_size,
+            fq2_size: p1f2_size,
+        }));
+
+        let p2f1_path = fixture.dir.join("ok_r1.fastq.gz");
+        let p2f2_path = fixture.dir.join("ok_r2.fastq.gz");
+        let p2f1_size = fs::metadata(&p2f1_path)?.len();
+        let p2f2_size = fs::metadata(&p2f2_path)?.len();
+        total_bytes += p2f1_size + p2f2_size;
+        jobs.push(Job::PairedFastq(PairedFastqJob {
+            fq1_path: p2f1_path,
+            fq2_path: p2f2_path,
+            length_check: ReadLengthCheck::Fixed(3),
+            fq1_declared_read_length: None,
+            fq2_declared_read_length: None,
+            fq1_size: p2f1_size,
+            fq2_size: p2f2_size,
+        }));
+
+        let s1_path = fixture.dir.join("badlen.fastq.gz");
+        let s1_size = fs::metadata(&s1_path)?.len();
+        total_bytes += s1_size;
+        jobs.push(Job::SingleFastq(SingleFastqJob {
+            path: s1_path,
+            length_check: ReadLengthCheck::Fixed(4),
+            declared_read_length: None,
+            size: s1_size,
+        }));
+
+        run_check(jobs, total_bytes, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 5);
+
+        let find_report = |recs: &[TestReport], suffix: &str| -> TestReport {
+            recs.iter()
+                .find(|r| match r {
+                    TestReport::Fastq(d) => d.path.ends_with(suffix),
+                    _ => false,
+                })
+                .unwrap_or_else(|| panic!("Report for file ending in '{suffix}' not found"))
+                .clone()
+        };
+
+        // The mismatch is reported on the pair only.
+        for suffix in ["counts1.fastq.gz", "counts2.fastq.gz"] {
+            if let TestReport::Fastq(data) = find_report(&records, suffix) {
+                assert!(
+                    !data
+                        .errors
+                        .iter()
+                        .any(|e| e.contains("Mismatched read counts"))
+                );
+            }
         }
+        let pairs = read_entity_reports(&output)?;
+        assert_eq!(pairs.len(), 2);
+        let counts_pair = pairs
+            .iter()
+            .find(|pair| pair.id.ends_with("counts1.fastq.gz"))
+            .expect("entity of the pair with mismatched read counts");
+        assert_eq!(counts_pair.status, "ERROR");
+        assert!(
+            counts_pair
+                .errors
+                .iter()
+                .any(|e| e.contains("Mismatched read counts"))
+        );
+        assert!(
+            pairs
+                .iter()
+                .any(|pair| pair.id.ends_with("ok_r1.fastq.gz") && pair.status == "OK")
+        );
+
+        // The submission fails because of the pair and the file with the wrong read length.
+        let run = read_all_report_entries(&output)?
+            .into_iter()
+            .find_map(|entry| match entry {
+                TestReport::Run(run) => Some(run),
+                _ => None,
+            })
+            .expect("run entry");
+        assert_eq!(run.status, "ERROR");
+        assert_eq!(run.failed_members.len(), 2);
 
         if let TestReport::Fastq(data) = find_report(&records, "badlen.fastq.gz") {
             assert_eq!(data.status, "ERROR");
@@ -905,6 +3806,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_bam_without_eof_marker() -> Result<()> {
+        let dir = tempdir()?;
+        let bam_path = dir.path().join("partial.bam");
+        let header = Header::default();
+        let mut writer = bam::io::Writer::new(Vec::new());
+        writer.write_header(&header)?;
+        let record = record_buf::Builder::default()
+            .set_name("r0")
+            .set_flags(Flags::UNMAPPED)
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(QualityScores::from(vec![1, 1, 1, 1]))
+            .build();
+        writer.write_alignment_record(&header, &record)?;
+        let content = writer.into_inner().finish()?;
+        fs::write(&bam_path, &content[..content.len() - 28])?;
+
+        let output = dir.path().join("report.jsonl");
+        let bam_size = fs::metadata(&bam_path)?.len();
+        let jobs = vec![Job::Bam(BamCheckJob {
+            path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: None,
+            size: bam_size,
+        })];
+        run_check(jobs, bam_size, &output, &test_options(true))?;
+        let TestReport::Bam(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Bam report");
+        };
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec![
+                "File does not end with the BGZF EOF marker block (file likely incomplete). The transfer was probably interrupted at a block boundary; transfer it again."
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_valid_bam_check() -> Result<()> {
         let dir = tempdir()?;
@@ -929,10 +3870,13 @@ mod tests {
         let bam_size = fs::metadata(&bam_path)?.len();
         let jobs = vec![Job::Bam(BamCheckJob {
             path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: None,
             size: bam_size,
         })];
 
-        run_check(jobs, bam_size, &output, true, Some(false))?;
+        run_check(jobs, bam_size, &output, &test_options(true))?;
 
         let records = read_jsonl_report(&output)?;
         assert_eq!(records.len(), 1);
@@ -947,6 +3891,7 @@ mod tests {
                 )),
                 "Expected to find BAM header warning"
             );
+            assert_eq!(data.skipped_checks, vec!["reference.plausibility"]);
         } else {
             panic!("Expected a Bam report");
         }
@@ -968,9 +3913,10 @@ mod tests {
         let jobs = vec![Job::Raw(RawJob {
             path: file_path,
             size: file_size,
+            expected_sha256: None,
         })];
 
-        run_check(jobs, file_size, &output, true, Some(false))?;
+        run_check(jobs, file_size, &output, &test_options(true))?;
 
         let records = read_jsonl_report(&output)?;
         assert_eq!(records.len(), 1);
@@ -985,40 +3931,287 @@ mod tests {
     }
 
     #[test]
-    fn test_bam_with_multiple_secondary_alignments() -> Result<()> {
+    fn test_unusable_checksum_registry_fails_before_the_report() -> Result<()> {
         let dir = tempdir()?;
-        let bam_path = dir.path().join("secondary.bam");
-        let header = Header::default();
-        let mut writer = bam::io::Writer::new(fs::File::create(&bam_path)?);
-        writer.write_header(&header)?;
-
-        let rec1 = record_buf::Builder::default()
-            .set_name("rec1")
-            .set_flags(Flags::empty())
-            .build();
-        let rec2 = record_buf::Builder::default()
-            .set_name("rec2_secondary")
-            .set_flags(Flags::SECONDARY)
-            .build();
-        let rec3 = record_buf::Builder::default()
-            .set_name("rec3_secondary")
-            .set_flags(Flags::SECONDARY)
-            .build();
-
-        writer.write_alignment_record(&header, &rec1)?;
-        writer.write_alignment_record(&header, &rec2)?;
-        writer.write_alignment_record(&header, &rec3)?;
-        drop(writer);
-
+        let file_path = dir.path().join("raw.txt");
+        fs::write(&file_path, "some file contents")?;
         let output = dir.path().join("report.jsonl");
-        let bam_size = fs::metadata(&bam_path)?.len();
-        let jobs = vec![Job::Bam(BamCheckJob {
-            path: bam_path,
-            size: bam_size,
+        let options = RunOptions {
+            checksum_registry: Some(ChecksumRegistry {
+                backend: checksum_registry::Backend::Database {
+                    path: dir.path().join("missing.sqlite"),
+                    query: checksum_registry::DEFAULT_QUERY.to_string(),
+                },
+                root: dir.path().to_path_buf(),
+            }),
+            ..test_options(true)
+        };
+        let jobs = vec![Job::Raw(RawJob {
+            path: file_path,
+            size: 18,
+            expected_sha256: None,
         })];
-        run_check(jobs, bam_size, &output, true, Some(false))?;
 
-        let records = read_jsonl_report(&output)?;
+        let error = run_check(jobs, 18, &output, &options).unwrap_err();
+        assert!(
+            format!("{error:#}").starts_with("Failed to open checksum registry"),
+            "{error:#}"
+        );
+        assert!(!output.exists());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_check_streaming() -> Result<()> {
+        let dir = tempdir()?;
+        let raw_job = |name: &str| -> Result<Job> {
+            let path = dir.path().join(name);
+            if !path.exists() {
+                fs::write(&path, name)?;
+            }
+            Ok(Job::Raw(RawJob {
+                size: fs::metadata(&path)?.len(),
+                path,
+                expected_sha256: None,
+            }))
+        };
+        std::os::unix::fs::symlink(dir.path().join("a.txt"), dir.path().join("link.txt"))?;
+        let initial = raw_job("a.txt")?;
+        let total_bytes = initial.size();
+
+        let output = dir.path().join("report.jsonl");
+        let (sender, discovered) = mpsc::channel();
+        std::thread::scope(|scope| -> Result<()> {
+            let sender_thread = scope.spawn(move || -> Result<()> {
+                // Discovered after the checks have started
+                std::thread::sleep(Duration::from_millis(50));
+                sender.send(raw_job("b.txt")?)?;
+                sender.send(raw_job("link.txt")?)?;
+                Ok(())
+            });
+            run_check_streaming(
+                vec![initial],
+                total_bytes,
+                discovered,
+                &output,
+                &test_options(true),
+            )?;
+            sender_thread.join().unwrap()
+        })?;
+
+        let mut paths: Vec<PathBuf> = read_jsonl_report(&output)?
+            .into_iter()
+            .map(|record| match record {
+                TestReport::Raw(data) => data.path,
+                other => panic!("Expected a raw report, got {other:?}"),
+            })
+            .collect();
+        paths.sort();
+        // The link reaches a file that is already checked.
+        assert_eq!(
+            paths,
+            vec![dir.path().join("a.txt"), dir.path().join("b.txt")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_manifest() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("intact.txt"), "some file contents")?;
+        fs::write(dir.path().join("corrupt.txt"), "some file content")?;
+        let digest = "cf57fcf9d6d7fb8fd7d8c30527c8f51026aa1d99ad77cc769dd0c757d4fe8667";
+        let manifest = dir.path().join("SHA256SUMS");
+        fs::write(
+            &manifest,
+            format!("{digest}  intact.txt\n{digest}  corrupt.txt\n{digest} *missing.txt\n"),
+        )?;
+
+        let output = dir.path().join("report.jsonl");
+        let (jobs, total_bytes) = crate::manifest::create_jobs(&manifest)?;
+        run_check(jobs, total_bytes, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        let report = |name: &str| {
+            records
+                .iter()
+                .find_map(|record| match record {
+                    TestReport::Raw(data) if data.path.ends_with(name) => Some(data.clone()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        let intact = report("intact.txt");
+        assert_eq!(intact.status, "OK");
+        assert_eq!(intact.checksum.as_deref(), Some(digest));
+
+        let corrupt = report("corrupt.txt");
+        assert_eq!(corrupt.status, "ERROR");
+        assert!(corrupt.errors[0].contains("differs from the digest"));
+
+        assert_eq!(corrupt.expected_checksum.as_deref(), Some(digest));
+
+        let missing = report("missing.txt");
+        assert_eq!(missing.status, "MISSING");
+        assert_eq!(missing.checksum, None);
+        assert_eq!(missing.expected_checksum.as_deref(), Some(digest));
+        assert_eq!(
+            missing.errors,
+            vec!["File is listed in the manifest, but does not exist."]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_input_files() -> Result<()> {
+        let fixture = TestFiles::new()?;
+        let output = fixture.dir.join("report.jsonl");
+
+        let fq1_path = fixture.dir.join("ok_r1.fastq.gz");
+        let fq2_path = fixture.dir.join("ok_r2.fastq.gz");
+        let fq1_size = fs::metadata(&fq1_path)?.len();
+        let fq2_size = fs::metadata(&fq2_path)?.len();
+        let bam_path = fixture.dir.join("not_uploaded.bam");
+        let jobs = vec![
+            Job::PairedFastq(PairedFastqJob {
+                fq1_path,
+                fq2_path,
+                length_check: ReadLengthCheck::Skip,
+                fq1_declared_read_length: None,
+                fq2_declared_read_length: None,
+                fq1_size,
+                fq2_size,
+            }),
+            Job::Bam(BamCheckJob {
+                path: bam_path.clone(),
+                species: None,
+                unaligned: false,
+                index_path: None,
+                size: 0,
+            }),
+        ];
+        run_check(jobs, fq1_size + fq2_size, &output, &test_options(true))?;
+
+        let missing: Vec<serde_json::Value> = fs::read_to_string(&output)?
+            .lines()
+            .map(serde_json::from_str)
+            .filter(|entry: &serde_json::Result<serde_json::Value>| {
+                entry
+                    .as_ref()
+                    .is_ok_and(|entry| entry["data"]["status"] == "MISSING")
+            })
+            .collect::<serde_json::Result<_>>()?;
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0]["data"]["path"], bam_path.to_str().unwrap());
+        assert_eq!(missing[0]["data"]["errors"][0], MISSING_INPUT_ERROR);
+
+        // The present mate of a missing file is reported, but not checked.
+        let fq1_path = fixture.dir.join("ok_r1.fastq.gz");
+        let jobs = vec![Job::PairedFastq(PairedFastqJob {
+            fq1_path: fq1_path.clone(),
+            fq2_path: fixture.dir.join("not_uploaded_r2.fastq.gz"),
+            length_check: ReadLengthCheck::Skip,
+            fq1_declared_read_length: None,
+            fq2_declared_read_length: None,
+            fq1_size,
+            fq2_size: 0,
+        })];
+        run_check(jobs, fq1_size, &output, &test_options(true))?;
+        for record in read_jsonl_report(&output)? {
+            let TestReport::Fastq(data) = record else {
+                panic!("Expected a Fastq report");
+            };
+            if data.path == fq1_path {
+                assert_eq!(data.status, "ERROR");
+                assert_eq!(data.errors, vec![MISSING_MATE_ERROR]);
+            } else {
+                assert_eq!(data.status, "MISSING");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_full_stats_include_timings() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("raw.txt");
+        fs::write(&file_path, "some file contents")?;
+        let file_size = fs::metadata(&file_path)?.len();
+
+        for (stats, expect_timings) in [(StatsLevel::Basic, false), (StatsLevel::Full, true)] {
+            let output = dir.path().join("report.jsonl");
+            let jobs = vec![Job::Raw(RawJob {
+                path: file_path.clone(),
+                size: file_size,
+                expected_sha256: None,
+            })];
+            let options = RunOptions {
+                stats,
+                ..test_options(true)
+            };
+            run_check(jobs, file_size, &output, &options)?;
+
+            let records = read_jsonl_report(&output)?;
+            let TestReport::Raw(data) = &records[0] else {
+                panic!("Expected a raw report");
+            };
+            assert_eq!(data.timings.is_some(), expect_timings);
+            if let Some(timings) = &data.timings {
+                for key in [
+                    "read_seconds",
+                    "decompression_seconds",
+                    "hashing_seconds",
+                    "check_seconds",
+                    "total_seconds",
+                ] {
+                    assert!(timings[key].is_f64(), "missing timing '{key}'");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bam_with_multiple_secondary_alignments() -> Result<()> {
+        let dir = tempdir()?;
+        let bam_path = dir.path().join("secondary.bam");
+        let header = Header::default();
+        let mut writer = bam::io::Writer::new(fs::File::create(&bam_path)?);
+        writer.write_header(&header)?;
+
+        let rec1 = record_buf::Builder::default()
+            .set_name("rec1")
+            .set_flags(Flags::empty())
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
+            .build();
+        let rec2 = record_buf::Builder::default()
+            .set_name("rec2_secondary")
+            .set_flags(Flags::SECONDARY)
+            .build();
+        let rec3 = record_buf::Builder::default()
+            .set_name("rec3_secondary")
+            .set_flags(Flags::SECONDARY)
+            .build();
+
+        writer.write_alignment_record(&header, &rec1)?;
+        writer.write_alignment_record(&header, &rec2)?;
+        writer.write_alignment_record(&header, &rec3)?;
+        drop(writer);
+
+        let output = dir.path().join("report.jsonl");
+        let bam_size = fs::metadata(&bam_path)?.len();
+        let jobs = vec![Job::Bam(BamCheckJob {
+            path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: None,
+            size: bam_size,
+        })];
+        run_check(jobs, bam_size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
         assert_eq!(records.len(), 1);
         if let TestReport::Bam(data) = &records[0] {
             assert_eq!(data.status, "OK");
@@ -1051,16 +4244,20 @@ mod tests {
             .set_flags(Flags::empty())
             .set_cigar(cigar_hard_clip.clone())
             .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
             .build();
         let rec2 = record_buf::Builder::default()
             .set_name("rec2_noclip")
             .set_flags(Flags::empty())
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
             .build();
         let rec3 = record_buf::Builder::default()
             .set_name("rec3_hardclip")
             .set_flags(Flags::empty())
             .set_cigar(cigar_hard_clip)
             .set_sequence(b"TGCA".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
             .build();
 
         writer.write_alignment_record(&header, &rec1)?;
@@ -1072,9 +4269,12 @@ mod tests {
         let bam_size = fs::metadata(&bam_path)?.len();
         let jobs = vec![Job::Bam(BamCheckJob {
             path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: None,
             size: bam_size,
         })];
-        run_check(jobs, bam_size, &output, true, Some(false))?;
+        run_check(jobs, bam_size, &output, &test_options(true))?;
 
         let records = read_jsonl_report(&output)?;
         assert_eq!(records.len(), 1);
@@ -1092,6 +4292,62 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_bam_duplicate_fraction() -> Result<()> {
+        let dir = tempdir()?;
+        let bam_path = dir.path().join("duplicates.bam");
+        let header = Header::default();
+        let mut writer = bam::io::Writer::new(fs::File::create(&bam_path)?);
+        writer.write_header(&header)?;
+        for (name, flags) in [
+            ("r1", Flags::UNMAPPED),
+            ("r2", Flags::UNMAPPED | Flags::DUPLICATE),
+            ("r3", Flags::UNMAPPED),
+            ("r4", Flags::UNMAPPED | Flags::DUPLICATE),
+        ] {
+            let record = record_buf::Builder::default()
+                .set_name(name)
+                .set_flags(flags)
+                .set_sequence(b"ACGT".into())
+                .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
+                .build();
+            writer.write_alignment_record(&header, &record)?;
+        }
+        drop(writer);
+
+        let bam_size = fs::metadata(&bam_path)?.len();
+        let run = |max_duplicate_fraction: Option<f64>| -> Result<TestBamReportData> {
+            let job = Job::Bam(BamCheckJob {
+                path: bam_path.clone(),
+                species: None,
+                unaligned: false,
+                index_path: None,
+                size: bam_size,
+            });
+            let options = RunOptions {
+                max_duplicate_fraction,
+                ..test_options(true)
+            };
+            check_single_job_with_options(job, &options)
+        };
+
+        let data = run(None)?;
+        assert_eq!(data.num_duplicates, Some(2));
+        assert_eq!(data.duplicate_fraction, Some(0.5));
+        assert!(data.warnings.is_empty(), "{:?}", data.warnings);
+
+        let data = run(Some(0.3))?;
+        assert_eq!(data.status, "OK");
+        assert_eq!(
+            data.warnings,
+            vec![
+                "50.00% of records (2 of 4) are flagged as PCR or optical duplicates, above the maximum of 30.00%. The library may have had low complexity or been over-amplified."
+            ]
+        );
+        assert!(run(Some(0.5))?.warnings.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_bam_with_mixed_warnings() -> Result<()> {
         let dir = tempdir()?;
@@ -1100,11 +4356,16 @@ mod tests {
         let mut writer = bam::io::Writer::new(fs::File::create(&bam_path)?);
         writer.write_header(&header)?;
 
-        let cigar_hard_clip: record_buf::Cigar = [Op::new(Kind::HardClip, 5)].into_iter().collect();
+        let cigar_hard_clip: record_buf::Cigar =
+            [Op::new(Kind::HardClip, 5), Op::new(Kind::Match, 4)]
+                .into_iter()
+                .collect();
         let rec1 = record_buf::Builder::default()
             .set_name("rec1_hardclip")
             .set_flags(Flags::empty())
             .set_cigar(cigar_hard_clip.clone())
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
             .build();
         let rec2 = record_buf::Builder::default()
             .set_name("rec2_secondary")
@@ -1114,6 +4375,8 @@ mod tests {
             .set_name("rec3_hardclip")
             .set_flags(Flags::empty())
             .set_cigar(cigar_hard_clip)
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
             .build();
         let rec4 = record_buf::Builder::default()
             .set_name("rec4_secondary")
@@ -1130,9 +4393,12 @@ mod tests {
         let bam_size = fs::metadata(&bam_path)?.len();
         let jobs = vec![Job::Bam(BamCheckJob {
             path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: None,
             size: bam_size,
         })];
-        run_check(jobs, bam_size, &output, true, Some(false))?;
+        run_check(jobs, bam_size, &output, &test_options(true))?;
 
         let records = read_jsonl_report(&output)?;
         assert_eq!(records.len(), 1);
@@ -1147,4 +4413,2204 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_bam_with_mouse_reference_for_human_species() -> Result<()> {
+        let dir = tempdir()?;
+        let bam_path = dir.path().join("mouse.bam");
+
+        let mut builder = Header::builder();
+        for i in 1..=19 {
+            let length = if i == 1 { 195_471_971 } else { 100_000_000 };
+            builder = builder.add_reference_sequence(
+                format!("chr{i}"),
+                Map::<ReferenceSequence>::new(NonZeroUsize::try_from(length)?),
+            );
+        }
+        let header = builder.build();
+
+        let mut writer = bam::io::Writer::new(fs::File::create(&bam_path)?);
+        writer.write_header(&header)?;
+        let record = record_buf::Builder::default()
+            .set_name("r0")
+            .set_flags(Flags::UNMAPPED)
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
+            .build();
+        writer.write_alignment_record(&header, &record)?;
+        drop(writer);
+
+        let output = dir.path().join("report.jsonl");
+        let bam_size = fs::metadata(&bam_path)?.len();
+        let jobs = vec![Job::Bam(BamCheckJob {
+            path: bam_path,
+            species: Some(Species::Human),
+            unaligned: false,
+            index_path: None,
+            size: bam_size,
+        })];
+        run_check(jobs, bam_size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 1);
+        if let TestReport::Bam(data) = &records[0] {
+            assert_eq!(data.status, "ERROR");
+            assert!(
+                data.errors
+                    .iter()
+                    .any(|e| e.contains("matching a mouse reference"))
+            );
+            assert!(
+                data.errors
+                    .iter()
+                    .any(|e| e.contains("It matches mouse (GRCm38)"))
+            );
+        } else {
+            panic!("Expected a BAM report");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_ubam_with_alignment_information() -> Result<()> {
+        let dir = tempdir()?;
+        let bam_path = dir.path().join("reads.ubam");
+        let header = Header::builder()
+            .add_reference_sequence(
+                "chr1",
+                Map::<ReferenceSequence>::new(NonZeroUsize::try_from(1000)?),
+            )
+            .build();
+        let mut writer = bam::io::Writer::new(fs::File::create(&bam_path)?);
+        writer.write_header(&header)?;
+
+        let unmapped = record_buf::Builder::default()
+            .set_name("r0_unmapped")
+            .set_flags(Flags::UNMAPPED)
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
+            .build();
+        let mapped = record_buf::Builder::default()
+            .set_name("r1_mapped")
+            .set_flags(Flags::empty())
+            .set_reference_sequence_id(0)
+            .set_alignment_start(Position::try_from(10)?)
+            .set_cigar([Op::new(Kind::Match, 4)].into_iter().collect())
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
+            .build();
+        let placed = record_buf::Builder::default()
+            .set_name("r2_placed")
+            .set_flags(Flags::UNMAPPED)
+            .set_reference_sequence_id(0)
+            .set_alignment_start(Position::try_from(10)?)
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
+            .build();
+        for record in [&unmapped, &mapped, &placed] {
+            writer.write_alignment_record(&header, record)?;
+        }
+        drop(writer);
+
+        let output = dir.path().join("report.jsonl");
+        let bam_size = fs::metadata(&bam_path)?.len();
+        let jobs = vec![Job::Bam(BamCheckJob {
+            path: bam_path,
+            species: None,
+            unaligned: true,
+            index_path: None,
+            size: bam_size,
+        })];
+        run_check(jobs, bam_size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        let TestReport::Bam(data) = &records[0] else {
+            panic!("Expected a BAM report");
+        };
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 1 mapped record(s), which are not allowed in unaligned BAM. First detected at record #2 ('r1_mapped').",
+                "File contains 2 record(s) with reference coordinates, which are not allowed in unaligned BAM. First detected at record #2 ('r1_mapped').",
+                "File contains 1 record(s) with CIGAR operations, which are not allowed in unaligned BAM. First detected at record #2 ('r1_mapped').",
+            ]
+        );
+        Ok(())
+    }
+
+    const VCF_HEADER: &str = "##fileformat=VCFv4.3\n\
+        ##contig=<ID=chr1,length=1000>\n\
+        ##INFO=<ID=DP,Number=1,Type=Integer,Description=\"Depth\">\n\
+        ##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">\n\
+        #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tsample1\n";
+
+    /// A job checking a bgzip-compressed VCF file with `content`.
+    fn vcf_job(dir: &Path, content: &str, gvcf: bool) -> Result<Job> {
+        let path = dir.join("variants.vcf.gz");
+        write_gzipped(&path, content)?;
+        Ok(Job::Vcf(VcfCheckJob {
+            size: fs::metadata(&path)?.len(),
+            path,
+            gvcf,
+        }))
+    }
+
+    #[test]
+    fn test_valid_vcf_check() -> Result<()> {
+        let dir = tempdir()?;
+        let content = format!(
+            "{VCF_HEADER}chr1\t100\t.\tA\tG\t50\tPASS\tDP=10\tGT\t0/1\n\
+             chr1\t200\t.\tC\tT\t50\tPASS\tDP=12\tGT\t1/1\n"
+        );
+        let data: TestVcfReportData = check_single_job(vcf_job(dir.path(), &content, false)?)?;
+
+        assert_eq!(data.status, "OK");
+        assert_eq!(data.num_records, Some(2));
+        assert!(data.errors.is_empty(), "{:?}", data.errors);
+        assert!(data.checksum.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcf_with_inconsistent_records() -> Result<()> {
+        let dir = tempdir()?;
+        let content = format!(
+            "{VCF_HEADER}chr1\t100\t.\tA\tG\t50\tPASS\tDP=10\tGT\t0/1\n\
+             chr2\t200\t.\tC\tT\t50\tPASS\tAF=0.5\tGT\t1/1\n"
+        );
+        let data: TestVcfReportData = check_single_job(vcf_job(dir.path(), &content, false)?)?;
+
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(data.num_records, Some(2));
+        assert!(
+            data.errors
+                .iter()
+                .any(|e| e.contains("contigs not declared") && e.contains("record #2 ('chr2')"))
+        );
+        assert!(
+            data.errors
+                .iter()
+                .any(|e| e.contains("INFO fields not declared") && e.contains("record #2 ('AF')"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcf_with_unparsable_record() -> Result<()> {
+        let dir = tempdir()?;
+        let content = format!(
+            "{VCF_HEADER}chr1\t100\t.\tA\tG\t50\tPASS\tDP=10\tGT\t0/1\n\
+             chr1\tnot_a_position\t.\tC\tT\t50\tPASS\tDP=12\tGT\t1/1\n"
+        );
+        let data: TestVcfReportData = check_single_job(vcf_job(dir.path(), &content, false)?)?;
+
+        assert_eq!(data.status, "ERROR");
+        assert!(
+            data.errors.iter().any(|e| e.contains("record #2")),
+            "{:?}",
+            data.errors
+        );
+        assert_eq!(
+            data.not_evaluated,
+            vec![serde_json::json!({"check": "vcf.contig_consistency", "because": "vcf.records"})]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_gzipped_sam_uses_bam_checks() -> Result<()> {
+        let dir = tempdir()?;
+        let sam_path = dir.path().join("test.sam.gz");
+        write_gzipped(
+            &sam_path,
+            "@HD\tVN:1.6\n\
+             @SQ\tSN:chr1\tLN:1000\n\
+             r0\t0\tchr1\t1\t60\t2H4M\t*\t0\t0\tACGT\tFFFF\n\
+             r1\t256\tchr1\t10\t0\t4M\t*\t0\t0\tACGT\tFFFF\n",
+        )?;
+
+        let output = dir.path().join("report.jsonl");
+        let size = fs::metadata(&sam_path)?.len();
+        let jobs = vec![Job::Sam(SamCheckJob {
+            path: sam_path,
+            species: None,
+            size,
+        })];
+        run_check(jobs, size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 1);
+        let TestReport::Sam(data) = &records[0] else {
+            panic!("Expected a SAM report");
+        };
+        assert_eq!(data.status, "OK");
+        assert_eq!(data.num_records, Some(2));
+        assert!(
+            data.warnings
+                .iter()
+                .any(|w| w.contains("Detected a header in SAM file"))
+        );
+        assert!(data.warnings.iter().any(|w| w.contains(
+            "1 secondary alignment(s). First detected at record #2 ('r1')"
+        )));
+        assert!(data.warnings.iter().any(|w| w.contains(
+            "1 primary alignment(s) with hard-clipped bases. First detected at record #1 ('r0')"
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn test_valid_fasta_with_matching_index() -> Result<()> {
+        let dir = tempdir()?;
+        let fasta_path = dir.path().join("ref.fa");
+        fs::write(&fasta_path, ">chr1 first\nACGTACGT\nACGT\n>chr2\nNNNN\n")?;
+        let index_path = dir.path().join("ref.fa.fai");
+        fs::write(&index_path, "chr1\t12\t12\t8\t9\nchr2\t4\t32\t4\t5\n")?;
+
+        let data: TestFastaReportData = check_single_job(Job::Fasta(FastaCheckJob {
+            size: fs::metadata(&fasta_path)?.len(),
+            path: fasta_path,
+            index_path: Some(index_path),
+        }))?;
+        assert_eq!(data.status, "OK", "{:?}", data.errors);
+        assert_eq!(data.num_records, Some(2));
+        assert_eq!(data.total_length, Some(16));
+        assert!(data.warnings.is_empty(), "{:?}", data.warnings);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fasta_with_duplicate_names_and_stale_index() -> Result<()> {
+        let dir = tempdir()?;
+        let fasta_path = dir.path().join("ref.fa");
+        fs::write(&fasta_path, ">chr1\nACGT\nAC\nACGT\n>chr1\nACGT\n")?;
+        let index_path = dir.path().join("ref.fa.fai");
+        fs::write(&index_path, "chr1\t10\t6\t4\t5\nchrM\t100\t30\t60\t61\n")?;
+
+        let data: TestFastaReportData = check_single_job(Job::Fasta(FastaCheckJob {
+            size: fs::metadata(&fasta_path)?.len(),
+            path: fasta_path,
+            index_path: Some(index_path),
+        }))?;
+        assert_eq!(data.status, "ERROR");
+        assert!(data.errors.iter().any(|e| {
+            e.contains("1 duplicate sequence name(s). First detected at sequence #2 ('chr1')")
+        }));
+        assert!(
+            data.errors.iter().any(
+                |e| e.contains("1 sequence(s) not present in the file") && e.contains("'chrM'")
+            )
+        );
+        assert!(
+            data.warnings
+                .iter()
+                .any(|w| w.contains("inconsistent line lengths"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_interleaved_fastq() -> Result<()> {
+        let dir = tempdir()?;
+        let ok_path = dir.path().join("ok.fastq.gz");
+        write_gzipped(
+            &ok_path,
+            "@SEQ1/1\nACGT\n+\nFFFF\n@SEQ1/2\nTTTT\n+\nFFFF\n\
+             @SEQ2/1\nACGT\n+\nFFFF\n@SEQ2/2\nTTTT\n+\nFFFF\n",
+        )?;
+        let bad_path = dir.path().join("bad.fastq.gz");
+        write_gzipped(
+            &bad_path,
+            "@SEQ1/1\nACGT\n+\nFFFF\n@SEQ2/2\nTTTT\n+\nFFFF\n@SEQ3/1\nACGT\n+\nFFFF\n",
+        )?;
+
+        let output = dir.path().join("report.jsonl");
+        let mut jobs = Vec::new();
+        let mut total_bytes = 0;
+        for path in [ok_path, bad_path] {
+            let size = fs::metadata(&path)?.len();
+            total_bytes += size;
+            jobs.push(Job::InterleavedFastq(InterleavedFastqJob {
+                path,
+                length_check: ReadLengthCheck::Fixed(3),
+                declared_read_length: None,
+                size,
+            }));
+        }
+        run_check(jobs, total_bytes, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 2);
+        for record in &records {
+            let TestReport::Fastq(data) = record else {
+                panic!("Expected a FASTQ report");
+            };
+            if data.path.ends_with("ok.fastq.gz") {
+                assert_eq!(data.status, "OK", "{:?}", data.errors);
+                assert_eq!(data.num_records, Some(4));
+                assert_eq!(data.num_pairs, Some(2));
+            } else {
+                assert_eq!(data.status, "ERROR");
+                assert!(data.errors.iter().any(|e| e.contains(
+                    "1 pair(s) with mismatched mate names. First detected at records #1 and #2 ('SEQ1' vs 'SEQ2')"
+                )));
+                assert!(
+                    data.errors
+                        .iter()
+                        .any(|e| e.contains("last record #3 ('SEQ3') has no mate"))
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_suppressed_findings_are_listed_but_do_not_fail() -> Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join("legacy"))?;
+        let legacy_path = dir.path().join("legacy").join("dup.fa");
+        let other_path = dir.path().join("dup.fa");
+        for path in [&legacy_path, &other_path] {
+            fs::write(path, ">chr1\nACGT\n>chr1\nACGT\n")?;
+        }
+
+        let output = dir.path().join("report.jsonl");
+        let mut jobs = Vec::new();
+        let mut total_bytes = 0;
+        for path in [legacy_path, other_path] {
+            let size = fs::metadata(&path)?.len();
+            total_bytes += size;
+            jobs.push(Job::Fasta(FastaCheckJob {
+                path,
+                index_path: None,
+                size,
+            }));
+        }
+        let options = RunOptions {
+            suppressions: vec![
+                suppress::parse_suppression("fasta.duplicate_*:*/legacy/*.fa")
+                    .map_err(anyhow::Error::msg)?,
+            ],
+            ..test_options(true)
+        };
+        run_check(jobs, total_bytes, &output, &options)?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 2);
+        for record in records {
+            let TestReport::Fasta(data) = record else {
+                panic!("Expected a FASTA report");
+            };
+            if data.path.parent().unwrap().ends_with("legacy") {
+                assert_eq!(data.status, "OK", "{:?}", data.errors);
+                assert!(data.errors.is_empty());
+                assert_eq!(data.suppressed_findings.len(), 1);
+                assert_eq!(data.suppressed_findings[0]["code"], "fasta.duplicate_name");
+            } else {
+                assert_eq!(data.status, "ERROR");
+                assert!(data.suppressed_findings.is_empty());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict_extensions() -> Result<()> {
+        let dir = tempdir()?;
+        let accepted_path = dir.path().join("sample.vcf");
+        let stray_path = dir.path().join("sample.vcf.bak");
+        fs::write(&accepted_path, "")?;
+        fs::write(&stray_path, "")?;
+
+        let output = dir.path().join("report.jsonl");
+        let jobs = vec![
+            Job::Raw(RawJob {
+                path: accepted_path,
+                size: 0,
+                expected_sha256: None,
+            }),
+            Job::Raw(RawJob {
+                path: stray_path,
+                size: 0,
+                expected_sha256: None,
+            }),
+        ];
+        let options = RunOptions {
+            strict_extensions: true,
+            ..test_options(true)
+        };
+        run_check(jobs, 0, &output, &options)?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 2);
+        for record in records {
+            let TestReport::Raw(data) = record else {
+                panic!("Expected a raw report");
+            };
+            if data.path.ends_with("sample.vcf") {
+                assert_eq!(data.status, "OK", "{:?}", data.errors);
+            } else {
+                assert_eq!(data.status, "ERROR");
+                assert!(
+                    data.errors[0].starts_with("File extension is not accepted"),
+                    "{:?}",
+                    data.errors
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_fastq_compression_formats() -> Result<()> {
+        let dir = tempdir()?;
+        let content = "@SEQ1\nACGT\n+\nFFFF\n@SEQ2\nTGCA\n+\nFFFF\n";
+        let formats = [
+            ("reads.fastq", niffler::compression::Format::No, "none"),
+            ("reads.fastq.gz", niffler::compression::Format::Gzip, "gzip"),
+            (
+                "reads.fastq.bz2",
+                niffler::compression::Format::Bzip,
+                "bzip2",
+            ),
+            ("reads.fastq.xz", niffler::compression::Format::Lzma, "xz"),
+            (
+                "reads.fastq.zst",
+                niffler::compression::Format::Zstd,
+                "zstd",
+            ),
+        ];
+
+        let output = dir.path().join("report.jsonl");
+        let mut jobs = Vec::new();
+        let mut total_bytes = 0;
+        for (name, format, _) in formats {
+            let path = dir.path().join(name);
+            let mut writer = niffler::get_writer(
+                Box::new(fs::File::create(&path)?),
+                format,
+                niffler::compression::Level::One,
+            )?;
+            writer.write_all(content.as_bytes())?;
+            drop(writer);
+
+            let size = fs::metadata(&path)?.len();
+            total_bytes += size;
+            jobs.push(Job::SingleFastq(SingleFastqJob {
+                path,
+                length_check: ReadLengthCheck::Fixed(3),
+                declared_read_length: None,
+                size,
+            }));
+        }
+        run_check(jobs, total_bytes, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), formats.len());
+        for record in records {
+            let TestReport::Fastq(data) = record else {
+                panic!("Expected a FASTQ report");
+            };
+            let (_, _, expected) = formats
+                .iter()
+                .find(|(name, _, _)| data.path.ends_with(name))
+                .unwrap();
+            assert_eq!(data.status, "OK", "{:?}", data.errors);
+            assert_eq!(data.num_records, Some(2));
+            assert_eq!(data.compression.as_deref(), Some(*expected));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_valid_bed_with_reference() -> Result<()> {
+        let dir = tempdir()?;
+        let fasta_path = dir.path().join("ref.fa");
+        fs::write(&fasta_path, ">chr1\nACGTACGT\n>chr2 second\nACGT\n")?;
+        let bed_path = dir.path().join("targets.bed");
+        fs::write(
+            &bed_path,
+            "track name=targets\nchr1\t0\t4\tEXON1\nchr1\t2\t8\tEXON2\nchr2\t1\t3\tEXON3\n",
+        )?;
+
+        let data: TestBedReportData = check_single_job(Job::Bed(BedCheckJob {
+            size: fs::metadata(&bed_path)?.len(),
+            path: bed_path.clone(),
+            reference: Some(fasta_path),
+        }))?;
+        assert_eq!(data.status, "OK", "{:?}", data.errors);
+        assert_eq!(data.num_records, Some(3));
+        assert_eq!(data.total_length, Some(12));
+        assert!(data.warnings.is_empty(), "{:?}", data.warnings);
+        assert!(data.skipped_checks.is_empty());
+
+        let data: TestBedReportData = check_single_job(Job::Bed(BedCheckJob {
+            size: fs::metadata(&bed_path)?.len(),
+            path: bed_path,
+            reference: None,
+        }))?;
+        assert_eq!(data.status, "OK", "{:?}", data.errors);
+        assert_eq!(data.skipped_checks, vec!["bed.reference_names"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_bed() -> Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("ref.fa.fai");
+        fs::write(&index_path, "chr1\t8\t6\t8\t9\n")?;
+        let bed_path = dir.path().join("targets.bed");
+        fs::write(
+            &bed_path,
+            "chr1\t10\t20\nchr1\t5\t5\nchr2\t1\t2\nchr1\t-1\t4\nchr1\t30\nchr1\t40\t50\textra\n",
+        )?;
+
+        let data: TestBedReportData = check_single_job(Job::Bed(BedCheckJob {
+            size: fs::metadata(&bed_path)?.len(),
+            path: bed_path,
+            reference: Some(index_path),
+        }))?;
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 1 record(s) with fewer than 3 columns. First detected at line 5.",
+                "File contains 1 record(s) with an inconsistent number of columns. First detected at line 6 (4 instead of 3).",
+                "File contains 1 record(s) with invalid coordinates. First detected at line 4 ('-1', '4').",
+                "File contains 1 record(s) whose start is not less than their end. First detected at line 2 (5-5).",
+                "File contains 1 record(s) on chromosomes not present in the reference. First detected at line 3 ('chr2').",
+            ]
+        );
+        assert_eq!(
+            data.warnings,
+            vec![
+                "File is not sorted by chromosome and start position (2 record(s) out of order). First detected at line 2."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_content_across_files() -> Result<()> {
+        let dir = tempdir()?;
+        let paths: Vec<PathBuf> = ["a.bin", "b.bin", "c.bin"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        fs::write(&paths[0], "same")?;
+        fs::write(&paths[1], "same")?;
+        fs::write(&paths[2], "different")?;
+
+        let output = dir.path().join("report.jsonl");
+        let mut jobs = Vec::new();
+        let mut total_bytes = 0;
+        // The same path given twice is not a duplicate.
+        for path in [&paths[0], &paths[1], &paths[2], &paths[2]] {
+            let size = fs::metadata(path)?.len();
+            total_bytes += size;
+            jobs.push(Job::Raw(RawJob {
+                path: path.clone(),
+                size,
+                expected_sha256: None,
+            }));
+        }
+        run_check(jobs, total_bytes, &output, &test_options(true))?;
+
+        let entries = read_all_report_entries(&output)?;
+        assert_eq!(entries.len(), 5);
+        let TestReport::Run(run) = &entries[4] else {
+            panic!("Expected the run-level entry last, got {:?}", entries[4]);
+        };
+        assert_eq!(run.warnings.len(), 1);
+        assert!(run.warnings[0].starts_with("Files with identical content (SHA256 "));
+        assert!(run.warnings[0].ends_with(&format!(
+            "{}, {}",
+            paths[0].display(),
+            paths[1].display()
+        )));
+        assert_eq!(run.findings[0]["code"], "run.duplicate_content");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_aliased_paths_checked_once() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("a.bin");
+        let link = dir.path().join("link.bin");
+        fs::write(&path, "content")?;
+        std::os::unix::fs::symlink(&path, &link)?;
+
+        let output = dir.path().join("report.jsonl");
+        let jobs = [&path, &link]
+            .into_iter()
+            .map(|path| {
+                Job::Raw(RawJob {
+                    path: path.clone(),
+                    size: 7,
+                    expected_sha256: None,
+                })
+            })
+            .collect();
+        run_check(jobs, 14, &output, &test_options(true))?;
+
+        // A single entry, without the run entry reporting identical content.
+        let entries = read_all_report_entries(&output)?;
+        let [TestReport::Raw(data), TestReport::Run(run)] = entries.as_slice() else {
+            panic!("Expected a raw report and the run entry, got {entries:?}");
+        };
+        assert_eq!(data.path, path);
+        assert_eq!(data.aliases, vec![link]);
+        assert_eq!(run.status, "OK");
+        assert!(run.errors.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_deadline_marks_remaining_jobs_not_checked() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("a.bin");
+        fs::write(&path, "content")?;
+        let options = RunOptions {
+            deadline: Some(Utc::now() - chrono::Duration::seconds(1)),
+            ..test_options(true)
+        };
+        let job = Job::Raw(RawJob {
+            path: path.clone(),
+            size: 7,
+            expected_sha256: None,
+        });
+        let output = dir.path().join("report.jsonl");
+        let error = run_check(vec![job], 7, &output, &options).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The deadline was reached; 1 job(s) were not checked."
+        );
+
+        let entries = read_all_report_entries(&output)?;
+        let [TestReport::NotChecked(data), TestReport::Run(run)] = entries.as_slice() else {
+            panic!("Expected a not-checked entry and the run entry, got {entries:?}");
+        };
+        assert_eq!(run.status, "ERROR");
+        assert_eq!(run.failed_members, vec![path.display().to_string()]);
+        assert_eq!(data.check, "raw");
+        assert_eq!(data.paths, vec![path]);
+        assert_eq!(data.status, "NOT_CHECKED");
+        assert_eq!(data.reason, DEADLINE_REASON);
+        assert!(report::lint_report(BufReader::new(fs::File::open(&output)?))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_consent_scope_violation() -> Result<()> {
+        let dir = tempdir()?;
+        let research = dir.path().join("research");
+        fs::create_dir(&research)?;
+        let path = research.join("calls.bin");
+        fs::write(&path, "content")?;
+
+        let pattern = |pattern: &str| {
+            glob::Pattern::new(&format!(
+                "{}/{pattern}",
+                glob::Pattern::escape(&dir.path().to_string_lossy())
+            ))
+            .unwrap()
+        };
+        let options = RunOptions {
+            donor_consents: vec![DonorConsent {
+                donor: "index".to_string(),
+                path: pattern("**"),
+                scope: consent::Scope::Care,
+            }],
+            consent_categories: vec![consent::Category {
+                name: "research files".to_string(),
+                path: pattern("research/*"),
+                requires: consent::Scope::Research,
+                expected: false,
+            }],
+            ..test_options(false)
+        };
+        let job = Job::Raw(RawJob {
+            path: path.clone(),
+            size: 7,
+            expected_sha256: None,
+        });
+        let output = dir.path().join("report.jsonl");
+        assert!(run_check(vec![job], 7, &output, &options).is_err());
+
+        let entries = read_all_report_entries(&output)?;
+        let TestReport::Run(run) = &entries[1] else {
+            panic!("Expected a run-level entry, got {:?}", entries[1]);
+        };
+        assert_eq!(
+            run.errors,
+            vec![format!(
+                "Consent scope of donor index is care, but 1 file(s) of category 'research files' requiring research consent are submitted: {}",
+                path.display()
+            )]
+        );
+        assert_eq!(run.findings[0]["code"], "consent.scope_violation");
+        Ok(())
+    }
+
+    const GVCF_HEADER: &str = "##fileformat=VCFv4.2\n\
+        ##ALT=<ID=NON_REF,Description=\"Any other allele\">\n\
+        ##contig=<ID=chr1,length=1000>\n\
+        ##contig=<ID=chr2,length=1000>\n\
+        ##INFO=<ID=END,Number=1,Type=Integer,Description=\"End of the block\">\n\
+        ##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">\n\
+        #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tsample1\n";
+
+    #[test]
+    fn test_valid_gvcf_check() -> Result<()> {
+        let dir = tempdir()?;
+        let content = format!(
+            "{GVCF_HEADER}chr1\t1\t.\tA\t<NON_REF>\t.\t.\tEND=99\tGT\t0/0\n\
+             chr1\t100\t.\tA\tG,<NON_REF>\t50\tPASS\t.\tGT\t0/1\n\
+             chr1\t101\t.\tC\t<NON_REF>\t.\t.\tEND=200\tGT\t0/0\n\
+             chr2\t1\t.\tG\t<NON_REF>\t.\t.\tEND=10\tGT\t0/0\n"
+        );
+        let data: TestVcfReportData = check_single_job(vcf_job(dir.path(), &content, true)?)?;
+        assert_eq!(data.status, "OK", "{:?}", data.errors);
+        assert!(data.warnings.is_empty(), "{:?}", data.warnings);
+        assert_eq!(
+            data.gvcf_blocks,
+            Some(serde_json::json!({
+                "reference_blocks": 3,
+                "variant_records": 1,
+                "reference_block_bases": 209,
+                "max_reference_block_length": 100,
+            }))
+        );
+
+        // Without --gvcf, no block statistics are reported.
+        let data: TestVcfReportData = check_single_job(vcf_job(dir.path(), &content, false)?)?;
+        assert_eq!(data.gvcf_blocks, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_gvcf_blocks() -> Result<()> {
+        let dir = tempdir()?;
+        let content = format!(
+            "{GVCF_HEADER}chr1\t1\t.\tA\t<NON_REF>\t.\t.\t.\tGT\t0/0\n\
+             chr1\t2\t.\tA\t<NON_REF>\t.\t.\tEND=50\tGT\t0/0\n\
+             chr1\t40\t.\tA\tG,<NON_REF>\t50\tPASS\t.\tGT\t0/1\n\
+             chr1\t60\t.\tC\t<*>\t.\t.\tEND=55\tGT\t0/0\n"
+        );
+        let data: TestVcfReportData = check_single_job(vcf_job(dir.path(), &content, true)?)?;
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 1 record(s) that are reference blocks without an END tag. First detected at record #1 ('chr1:1').",
+                "File contains 1 record(s) with an END before their position. First detected at record #4 ('chr1:60' with END=55).",
+                "File contains 1 record(s) overlapping the preceding block. First detected at record #3 ('chr1:40' overlaps a block ending at 50).",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lab_datum_totals_across_flowcells() -> Result<()> {
+        let fixture = TestFiles::new()?;
+        let output = fixture.dir.join("report.jsonl");
+
+        let pairs = [
+            ("ok_r1.fastq.gz", "ok_r2.fastq.gz"),
+            ("counts1.fastq.gz", "ok_r2_len5.fastq.gz"),
+        ];
+        let mut jobs = Vec::new();
+        let mut total_bytes = 0;
+        for (fq1, fq2) in pairs {
+            let fq1_path = fixture.dir.join(fq1);
+            let fq2_path = fixture.dir.join(fq2);
+            let fq1_size = fs::metadata(&fq1_path)?.len();
+            let fq2_size = fs::metadata(&fq2_path)?.len();
+            total_bytes += fq1_size + fq2_size;
+            jobs.push(Job::PairedFastq(PairedFastqJob {
+                fq1_path,
+                fq2_path,
+                length_check: ReadLengthCheck::Fixed(3),
+                fq1_declared_read_length: None,
+                fq2_declared_read_length: None,
+                fq1_size,
+                fq2_size,
+            }));
+        }
+        let lab_datum = LabDatum {
+            id: "sample1".to_string(),
+            pairs: pairs
+                .iter()
+                .map(|(fq1, fq2)| FastqPair {
+                    fq1: fixture.dir.join(fq1),
+                    fq2: fixture.dir.join(fq2),
+                })
+                .collect(),
+            declared_num_records: Some(8),
+            declared_total_bases: Some(40),
+        };
+        let options = RunOptions {
+            lab_data: vec![lab_datum],
+            ..test_options(true)
+        };
+
+        run_check(jobs, total_bytes, &output, &options)?;
+
+        let lab_data: Vec<TestLabDatumReportData> = read_all_report_entries(&output)?
+            .into_iter()
+            .filter_map(|entry| match entry {
+                TestReport::LabDatum(data) => Some(data),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lab_data.len(), 1);
+        let data = &lab_data[0];
+        assert_eq!(data.id, "sample1");
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(data.pairs.len(), 2);
+        assert_eq!(data.num_records, Some(8));
+        assert_eq!(data.total_bases, Some(34));
+        assert_eq!(
+            data.errors,
+            vec!["Combined yield across 2 FASTQ pair(s) is 34 bases, but 40 are declared."]
+        );
+        Ok(())
+    }
+
+    fn write_bgzf(path: &Path, content: &str) -> Result<()> {
+        let mut writer = noodles::bgzf::io::Writer::new(fs::File::create(path)?);
+        writer.write_all(content.as_bytes())?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    const TABIX_VCF: &str = "##fileformat=VCFv4.2\n\
+        ##contig=<ID=chr1,length=1000>\n\
+        ##contig=<ID=chr2,length=1000>\n\
+        ##contig=<ID=chr3,length=1000>\n\
+        #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+        chr1\t10\t.\tA\tG\t.\tPASS\t.\n\
+        chr1\t20\t.\tC\tT\t.\tPASS\t.\n\
+        chr2\t30\t.\tG\tA\t.\tPASS\t.\n";
+
+    #[test]
+    fn test_valid_tabix_index() -> Result<()> {
+        let dir = tempdir()?;
+        let data_path = dir.path().join("variants.vcf.gz");
+        let index_path = dir.path().join("variants.vcf.gz.tbi");
+        write_bgzf(&data_path, TABIX_VCF)?;
+        noodles::tabix::fs::write(&index_path, &noodles::vcf::fs::index(&data_path)?)?;
+
+        let data: TestTabixReportData = check_single_job(Job::Tabix(TabixCheckJob {
+            size: fs::metadata(&index_path)?.len(),
+            path: index_path,
+            data_path,
+        }))?;
+        assert_eq!(data.status, "OK", "{:?}", data.errors);
+        assert_eq!(data.num_sequences, Some(2));
+        assert!(data.skipped_checks.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_tabix_index() -> Result<()> {
+        let dir = tempdir()?;
+        let data_path = dir.path().join("variants.vcf.gz");
+        let index_path = dir.path().join("variants.vcf.gz.tbi");
+        write_bgzf(&data_path, TABIX_VCF)?;
+        noodles::tabix::fs::write(&index_path, &noodles::vcf::fs::index(&data_path)?)?;
+
+        // Rewrite the data file after indexing, moving the records of chr2 to chr3.
+        let rewritten = TABIX_VCF
+            .replace("chr1\t10\t", "chr1\t5\t.\tT\tC\t.\tPASS\t.\nchr1\t10\t")
+            .replace("chr2\t30", "chr3\t30");
+        write_bgzf(&data_path, &rewritten)?;
+
+        let data: TestTabixReportData = check_single_job(Job::Tabix(TabixCheckJob {
+            size: fs::metadata(&index_path)?.len(),
+            path: index_path,
+            data_path,
+        }))?;
+        assert_eq!(data.status, "ERROR");
+        let codes: Vec<&str> = data.errors.iter().map(|e| findings::code_for(e)).collect();
+        assert_eq!(
+            codes,
+            vec![
+                "tabix.extra_sequence",
+                "tabix.missing_sequence",
+                "tabix.unresolved_offset"
+            ],
+            "{:?}",
+            data.errors
+        );
+        assert!(
+            data.findings
+                .iter()
+                .all(|finding| finding["hint"] == "Regenerate the index with `tabix`.")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_tabix_magic() -> Result<()> {
+        let dir = tempdir()?;
+        let data_path = dir.path().join("variants.vcf.gz");
+        let index_path = dir.path().join("variants.vcf.gz.tbi");
+        write_bgzf(&data_path, TABIX_VCF)?;
+        write_bgzf(&index_path, "not an index")?;
+
+        let data: TestTabixReportData = check_single_job(Job::Tabix(TabixCheckJob {
+            size: fs::metadata(&index_path)?.len(),
+            path: index_path,
+            data_path,
+        }))?;
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec!["File is not a tabix or CSI index (magic number \"not \")."]
+        );
+        Ok(())
+    }
+
+    /// Writes a bgzipped file with one BGZF stream per part and returns the compressed and
+    /// uncompressed offset of the start of each part after the first, as listed in a GZI index.
+    fn write_bgzf_parts(path: &Path, parts: &[&str]) -> Result<Vec<(u64, u64)>> {
+        let mut data = Vec::new();
+        let mut offsets = Vec::new();
+        let mut uncompressed = 0;
+        for part in parts {
+            if !data.is_empty() {
+                offsets.push((data.len() as u64, uncompressed));
+            }
+            let mut writer = noodles::bgzf::io::Writer::new(&mut data);
+            writer.write_all(part.as_bytes())?;
+            writer.finish()?;
+            uncompressed += part.len() as u64;
+        }
+        fs::write(path, data)?;
+        Ok(offsets)
+    }
+
+    fn write_gzi(path: &Path, entries: &[(u64, u64)]) -> Result<()> {
+        let mut index = (entries.len() as u64).to_le_bytes().to_vec();
+        for (compressed, uncompressed) in entries {
+            index.extend(compressed.to_le_bytes());
+            index.extend(uncompressed.to_le_bytes());
+        }
+        fs::write(path, index)?;
+        Ok(())
+    }
+
+    const GZI_FASTA_PARTS: &[&str] = &[">chr1\nACGTACGT\n", ">chr2\nGGCC\n", ">chr3\nTTAA\n"];
+
+    #[test]
+    fn test_valid_gzi_index() -> Result<()> {
+        let dir = tempdir()?;
+        let data_path = dir.path().join("genome.fa.gz");
+        let index_path = dir.path().join("genome.fa.gz.gzi");
+        let offsets = write_bgzf_parts(&data_path, GZI_FASTA_PARTS)?;
+        write_gzi(&index_path, &offsets)?;
+
+        let data: TestGziReportData = check_single_job(Job::Gzi(GziCheckJob {
+            size: fs::metadata(&index_path)?.len(),
+            path: index_path,
+            data_path,
+        }))?;
+        assert_eq!(data.status, "OK", "{:?}", data.errors);
+        assert_eq!(data.num_entries, Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_gzi_offsets() -> Result<()> {
+        let dir = tempdir()?;
+        let data_path = dir.path().join("genome.fa.gz");
+        let index_path = dir.path().join("genome.fa.gz.gzi");
+        let offsets = write_bgzf_parts(&data_path, GZI_FASTA_PARTS)?;
+        let size = fs::metadata(&data_path)?.len();
+        let (c1, u1) = offsets[0];
+        let (c2, u2) = offsets[1];
+        write_gzi(
+            &index_path,
+            &[(c1 + 1, u1), (c2, u2 + 1), (size + 10, u2 + 20)],
+        )?;
+
+        let data: TestGziReportData = check_single_job(Job::Gzi(GziCheckJob {
+            size: fs::metadata(&index_path)?.len(),
+            path: index_path.clone(),
+            data_path: data_path.clone(),
+        }))?;
+        assert_eq!(data.status, "ERROR");
+        let codes: Vec<&str> = data.errors.iter().map(|e| findings::code_for(e)).collect();
+        assert_eq!(
+            codes,
+            vec![
+                "gzi.out_of_bounds",
+                "gzi.misaligned_offset",
+                "gzi.uncompressed_offset"
+            ],
+            "{:?}",
+            data.errors
+        );
+        assert!(
+            data.findings
+                .iter()
+                .all(|finding| finding["hint"] == "Regenerate the index with `bgzip -r`.")
+        );
+
+        write_gzi(&index_path, &[(c2, u2), (c1, u1)])?;
+        let data: TestGziReportData = check_single_job(Job::Gzi(GziCheckJob {
+            size: fs::metadata(&index_path)?.len(),
+            path: index_path.clone(),
+            data_path: data_path.clone(),
+        }))?;
+        assert_eq!(
+            data.errors,
+            vec![format!(
+                "Index contains 1 entry(ies) that are not in increasing order of offsets. First detected at entry #2 ({c1}:{u1} after {c2}:{u2})."
+            )]
+        );
+
+        fs::write(&index_path, [2, 0, 0, 0, 0, 0, 0, 0])?;
+        let data: TestGziReportData = check_single_job(Job::Gzi(GziCheckJob {
+            size: fs::metadata(&index_path)?.len(),
+            path: index_path,
+            data_path,
+        }))?;
+        assert_eq!(
+            data.errors,
+            vec![
+                "GZI index declares 2 entries but has 0 bytes of entries, so it is truncated or has trailing data."
+            ]
+        );
+        Ok(())
+    }
+
+    /// Writes a BAM file with two reference sequences and a single mapped record on the given
+    /// one, and returns the virtual offsets of the start and end of the record.
+    fn write_indexable_bam(
+        bam_path: &Path,
+        reference_sequence_id: usize,
+    ) -> Result<(VirtualPosition, VirtualPosition)> {
+        let header = Header::builder()
+            .add_reference_sequence(
+                "chr1",
+                Map::<ReferenceSequence>::new(NonZeroUsize::try_from(100_000)?),
+            )
+            .add_reference_sequence(
+                "chr2",
+                Map::<ReferenceSequence>::new(NonZeroUsize::try_from(100_000)?),
+            )
+            .build();
+        let mut writer = bam::io::Writer::new(fs::File::create(bam_path)?);
+        writer.write_header(&header)?;
+        let record = record_buf::Builder::default()
+            .set_name("r0")
+            .set_reference_sequence_id(reference_sequence_id)
+            .set_alignment_start(Position::try_from(1)?)
+            .set_cigar([Op::new(Kind::Match, 4)].into_iter().collect())
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(QualityScores::from(vec![1, 1, 1, 1]))
+            .build();
+        let start = writer.get_ref().virtual_position();
+        writer.write_alignment_record(&header, &record)?;
+        let end = writer.get_ref().virtual_position();
+        writer.try_finish()?;
+        Ok((start, end))
+    }
+
+    /// Writes a BAI index with one record on the first reference sequence, stored in the chunk
+    /// from `start` to `end`.
+    fn write_bai(
+        index_path: &Path,
+        num_reference_sequences: usize,
+        (start, end): (VirtualPosition, VirtualPosition),
+    ) -> Result<()> {
+        use noodles::csi::binning_index::Indexer;
+        use noodles::csi::binning_index::index::reference_sequence::bin::Chunk;
+        use noodles::csi::binning_index::index::reference_sequence::index::LinearIndex;
+
+        let mut indexer = Indexer::<LinearIndex>::default();
+        indexer.add_record(
+            Some((0, Position::try_from(1)?, Position::try_from(4)?, true)),
+            Chunk::new(start, end),
+        )?;
+        bam::bai::fs::write(index_path, &indexer.build(num_reference_sequences))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_bam_with_consistent_index() -> Result<()> {
+        let dir = tempdir()?;
+        let bam_path = dir.path().join("sample.bam");
+        let index_path = dir.path().join("sample.bam.bai");
+        let chunk = write_indexable_bam(&bam_path, 0)?;
+        write_bai(&index_path, 2, chunk)?;
+        assert_eq!(
+            crate::checks::bam::sibling_index(&bam_path),
+            Some(index_path.clone())
+        );
+
+        let data: TestBamReportData = check_single_job(Job::Bam(BamCheckJob {
+            size: fs::metadata(&bam_path)?.len(),
+            path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: Some(index_path),
+        }))?;
+        assert_eq!(data.status, "OK", "{:?}", data.errors);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bam_with_inconsistent_index() -> Result<()> {
+        let dir = tempdir()?;
+        let bam_path = dir.path().join("sample.bam");
+        let index_path = dir.path().join("sample.bai");
+        write_indexable_bam(&bam_path, 0)?;
+        let bam_size = fs::metadata(&bam_path)?.len();
+        // An index of a larger BAM file with a third reference sequence.
+        write_bai(
+            &index_path,
+            3,
+            (
+                VirtualPosition::default(),
+                VirtualPosition::from((bam_size + 1000) << 16),
+            ),
+        )?;
+
+        let data: TestBamReportData = check_single_job(Job::Bam(BamCheckJob {
+            size: fs::metadata(&bam_path)?.len(),
+            path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: Some(index_path),
+        }))?;
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec![
+                "Index contains 3 reference sequence(s), but the BAM header declares 2."
+                    .to_string(),
+                format!(
+                    "Index refers to offset {} beyond the end of the file ({bam_size} bytes); it may belong to a different or truncated BAM file.",
+                    bam_size + 1000
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bam_with_stale_index() -> Result<()> {
+        let dir = tempdir()?;
+        let bam_path = dir.path().join("sample.bam");
+        let index_path = dir.path().join("sample.bam.bai");
+        let chunk = write_indexable_bam(&bam_path, 0)?;
+        write_bai(&index_path, 2, chunk)?;
+        // The file is regenerated with its record on chr2 at the same offset.
+        write_indexable_bam(&bam_path, 1)?;
+
+        let data: TestBamReportData = check_single_job(Job::Bam(BamCheckJob {
+            size: fs::metadata(&bam_path)?.len(),
+            path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: Some(index_path),
+        }))?;
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec![format!(
+                "Index points to 1 of 1 sampled offset(s) without a matching record; it was likely generated for a different version of the file. First detected: offset {}/{} of bin 4681 of reference sequence #1 points to a record ('r0') at position 1 of reference sequence #2.",
+                chunk.0.compressed(),
+                chunk.0.uncompressed()
+            )]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_alignment_tag_validation() -> Result<()> {
+        let dir = tempdir()?;
+        let sam_path = dir.path().join("tags.sam.gz");
+        write_gzipped(
+            &sam_path,
+            "r1\t4\t*\t0\t0\t*\t*\t0\t0\tACGTACGT\tFFFFFFFF\tOQ:Z:????????\tXH:H:1AE3\n\
+             r2\t4\t*\t0\t0\t*\t*\t0\t0\tACGTACGT\tFFFFFFFF\tOQ:Z:????????\tXH:H:1AE\n\
+             r3\t4\t*\t0\t0\t*\t*\t0\t0\tACGTACGT\tFFFFFFFF\tOQ:Z:????????\tXA:B:c,1,x\n",
+        )?;
+
+        let output = dir.path().join("report.jsonl");
+        let size = fs::metadata(&sam_path)?.len();
+        let jobs = vec![Job::Sam(SamCheckJob {
+            path: sam_path,
+            species: None,
+            size,
+        })];
+        let options = RunOptions {
+            validate_tags: true,
+            ..test_options(true)
+        };
+        run_check(jobs, size, &output, &options)?;
+
+        let records = read_jsonl_report(&output)?;
+        let TestReport::Sam(data) = &records[0] else {
+            panic!("Expected a SAM report");
+        };
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 2 record(s) with malformed auxiliary fields (tags). First detected at record #2 ('r2'): hex string is not an even number of hexadecimal digits."
+            ]
+        );
+        assert_eq!(
+            data.warnings,
+            vec![
+                "Auxiliary tag(s) OQ (12 bytes per record) are as large as the reads themselves. Tags such as original qualities (OQ) or indel qualities (BI/BD) left by base recalibration inflate the submission."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_base_qualities() -> Result<()> {
+        let dir = tempdir()?;
+        let sam_path = dir.path().join("quals.sam.gz");
+        write_gzipped(
+            &sam_path,
+            "r1\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n\
+             r2\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\t*\n\
+             r3\t260\t*\t0\t0\t*\t*\t0\t0\tACGT\t*\n\
+             r4\t2052\t*\t0\t0\t*\t*\t0\t0\t*\t*\n\
+             r5\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\t*\n",
+        )?;
+        let size = fs::metadata(&sam_path)?.len();
+        let run = |missing_quality_severity: Severity| -> Result<TestBamReportData> {
+            let job = Job::Sam(SamCheckJob {
+                path: sam_path.clone(),
+                species: None,
+                size,
+            });
+            let options = RunOptions {
+                missing_quality_severity,
+                ..test_options(true)
+            };
+            check_single_job_with_options(job, &options)
+        };
+        let expected = "File contains 2 primary record(s) without base quality scores (QUAL '*'). First detected at record #2 ('r2'). The original base qualities are required; the file was likely written with qualities omitted, e.g. `--omit-quals`.";
+
+        let data = run(Severity::Error)?;
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(data.errors, vec![expected]);
+
+        let data = run(Severity::Warning)?;
+        assert_eq!(data.status, "OK");
+        assert!(data.warnings.iter().any(|warning| warning == expected));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_sequences() -> Result<()> {
+        let dir = tempdir()?;
+        let sam_path = dir.path().join("sequences.sam.gz");
+        write_gzipped(
+            &sam_path,
+            "r1\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n\
+             r2\t4\t*\t0\t0\t*\t*\t0\t0\t*\t*\n\
+             r3\t260\t*\t0\t0\t*\t*\t0\t0\t*\t*\n\
+             r4\t2052\t*\t0\t0\t*\t*\t0\t0\t*\t*\n\
+             r5\t4\t*\t0\t0\t*\t*\t0\t0\t*\t*\n",
+        )?;
+
+        let output = dir.path().join("report.jsonl");
+        let size = fs::metadata(&sam_path)?.len();
+        let jobs = vec![Job::Sam(SamCheckJob {
+            path: sam_path,
+            species: None,
+            size,
+        })];
+        run_check(jobs, size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        let TestReport::Sam(data) = &records[0] else {
+            panic!("Expected a SAM report");
+        };
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 2 primary record(s) without a sequence (SEQ '*'). First detected at record #2 ('r2'). Only secondary and supplementary records may omit the sequence; the bases of the reads cannot be recovered from the file."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_alignments_beyond_reference_sequence() -> Result<()> {
+        let dir = tempdir()?;
+        let sam_path = dir.path().join("bounds.sam.gz");
+        write_gzipped(
+            &sam_path,
+            "@SQ\tSN:chr1\tLN:100\n\
+             @SQ\tSN:chr2\tLN:50\n\
+             ok\t0\tchr1\t97\t60\t4M\t*\t0\t0\tACGT\tFFFF\n\
+             deleted\t0\tchr1\t95\t60\t2M4D2M\t*\t0\t0\tACGT\tFFFF\n\
+             clipped\t0\tchr2\t49\t60\t2M2S\t*\t0\t0\tACGT\tFFFF\n\
+             beyond\t0\tchr2\t60\t60\t4M\t*\t0\t0\tACGT\tFFFF\n\
+             placed\t4\tchr2\t50\t0\t*\t*\t0\t0\tACGT\tFFFF\n",
+        )?;
+
+        let output = dir.path().join("report.jsonl");
+        let size = fs::metadata(&sam_path)?.len();
+        let jobs = vec![Job::Sam(SamCheckJob {
+            path: sam_path,
+            species: None,
+            size,
+        })];
+        run_check(jobs, size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        let TestReport::Sam(data) = &records[0] else {
+            panic!("Expected a SAM report");
+        };
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 2 mapped record(s) extending beyond the end of their reference sequence. First detected at record #2 ('deleted'), aligned to chr1:95-102, although the @SQ line declares a length of 100. The file was likely corrupted or edited by hand."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_alignment_status() -> Result<()> {
+        let dir = tempdir()?;
+        let check = |name: &str, records: &str| -> Result<TestBamReportData> {
+            let sam_path = dir.path().join(name);
+            write_gzipped(&sam_path, &format!("@SQ\tSN:chr1\tLN:1000\n{records}"))?;
+            check_single_job(Job::Sam(SamCheckJob {
+                size: fs::metadata(&sam_path)?.len(),
+                path: sam_path,
+                species: None,
+            }))
+        };
+        let mapped = "m1\t0\tchr1\t1\t60\t4M\t*\t0\t0\tACGT\tFFFF\n";
+        let unmapped = "u1\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n\
+                        u2\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n\
+                        u3\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n";
+
+        let data = check("aligned.sam.gz", mapped)?;
+        assert_eq!(data.alignment_status.as_deref(), Some("aligned"));
+        assert_eq!(data.mapped_fraction, Some(1.0));
+
+        let data = check("unaligned.sam.gz", unmapped)?;
+        assert_eq!(data.alignment_status.as_deref(), Some("unaligned"));
+        assert_eq!(
+            (data.num_mapped, data.mapped_fraction),
+            (Some(0), Some(0.0))
+        );
+
+        let data = check("mixed.sam.gz", &format!("{mapped}{unmapped}"))?;
+        assert_eq!(data.status, "OK");
+        assert_eq!(data.alignment_status.as_deref(), Some("mixed"));
+        assert_eq!(
+            (data.num_mapped, data.mapped_fraction),
+            (Some(1), Some(0.25))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cigar_not_matching_sequence() -> Result<()> {
+        let dir = tempdir()?;
+        let sam_path = dir.path().join("cigar.sam.gz");
+        write_gzipped(
+            &sam_path,
+            "@SQ\tSN:chr1\tLN:1000\n\
+             ok\t0\tchr1\t1\t60\t1S2M1I2D\t*\t0\t0\tACGT\tFFFF\n\
+             clipped\t0\tchr1\t2\t60\t3H4M\t*\t0\t0\tACGT\tFFFF\n\
+             long\t0\tchr1\t3\t60\t6M\t*\t0\t0\tACGT\tFFFF\n\
+             secondary\t256\tchr1\t4\t60\t6M\t*\t0\t0\t*\t*\n\
+             short\t0\tchr1\t5\t60\t2M\t*\t0\t0\tACGT\tFFFF\n",
+        )?;
+
+        let output = dir.path().join("report.jsonl");
+        let size = fs::metadata(&sam_path)?.len();
+        let jobs = vec![Job::Sam(SamCheckJob {
+            path: sam_path,
+            species: None,
+            size,
+        })];
+        run_check(jobs, size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        let TestReport::Sam(data) = &records[0] else {
+            panic!("Expected a SAM report");
+        };
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 2 record(s) whose CIGAR does not match the length of their sequence. First detected at record #3 ('long'): the CIGAR consumes 6 base(s) of the read, but SEQ has 4. The file was likely corrupted or edited by hand."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_inconsistent_mate_flags() -> Result<()> {
+        let dir = tempdir()?;
+        let sam_path = dir.path().join("mates.sam.gz");
+        write_gzipped(
+            &sam_path,
+            "@SQ\tSN:chr1\tLN:1000\n\
+             ok1\t99\tchr1\t1\t60\t4M\t=\t100\t103\tACGT\tFFFF\n\
+             ok2\t77\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n\
+             no_mate\t1\tchr1\t5\t60\t4M\t*\t0\t0\tACGT\tFFFF\n\
+             unpaired\t8\tchr1\t6\t60\t4M\t*\t0\t0\tACGT\tFFFF\n\
+             both\t205\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n\
+             proper1\t79\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n\
+             proper2\t2\tchr1\t7\t60\t4M\t*\t0\t0\tACGT\tFFFF\n",
+        )?;
+
+        let output = dir.path().join("report.jsonl");
+        let size = fs::metadata(&sam_path)?.len();
+        let jobs = vec![Job::Sam(SamCheckJob {
+            path: sam_path,
+            species: None,
+            size,
+        })];
+        run_check(jobs, size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        let TestReport::Sam(data) = &records[0] else {
+            panic!("Expected a SAM report");
+        };
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 1 record(s) flagged as paired with a mapped mate, but without the reference sequence or position of the mate. First detected at record(s) #3 ('no_mate').",
+                "File contains 1 record(s) with flags about their mate (mate unmapped or mate reverse strand), but not flagged as paired. First detected at record(s) #4 ('unpaired').",
+                "File contains 1 record(s) flagged as both the first and the last segment of their template (read 1 and read 2). First detected at record(s) #5 ('both').",
+                "File contains 2 record(s) flagged as properly paired, although they are unpaired or they or their mate are unmapped. First detected at record(s) #6 ('proper1'), #7 ('proper2').",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_groups_of_records() -> Result<()> {
+        let dir = tempdir()?;
+        let sam_path = dir.path().join("read_groups.sam.gz");
+        write_gzipped(
+            &sam_path,
+            "@RG\tID:rg1\tSM:s1\n\
+             r1\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\tRG:Z:rg1\n\
+             r2\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\tRG:Z:rg2\n\
+             r3\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n\
+             r4\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\tRG:Z:rg2\n\
+             r5\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\tRG:Z:rg3\n",
+        )?;
+
+        let output = dir.path().join("report.jsonl");
+        let size = fs::metadata(&sam_path)?.len();
+        let jobs = vec![Job::Sam(SamCheckJob {
+            path: sam_path,
+            species: None,
+            size,
+        })];
+        run_check(jobs, size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        let TestReport::Sam(data) = &records[0] else {
+            panic!("Expected a SAM report");
+        };
+        assert_eq!(
+            data.errors,
+            vec![
+                "File contains 3 record(s) with a read group that is not declared by an @RG header line: 'rg2' (2 record(s)), 'rg3' (1 record(s)). First detected at record #2 ('r2'). The @RG lines were likely dropped when the file was merged or reheadered."
+            ]
+        );
+        assert!(data.warnings.iter().any(|warning| warning
+            == "File contains 1 record(s) without a read group (RG tag), although the file uses read groups. First detected at record #3 ('r3')."));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_groups_of_lab_data() -> Result<()> {
+        use noodles::sam::header::record::value::map::read_group::tag;
+
+        let dir = tempdir()?;
+        let bam_path = dir.path().join("merged.bam");
+        let read_group = |sample: &str, library: &str| {
+            Map::<ReadGroup>::builder()
+                .insert(tag::SAMPLE, sample)
+                .insert(tag::LIBRARY, library)
+                .build()
+        };
+        let header = Header::builder()
+            .add_read_group("rg1", read_group("tumor", "lib1")?)
+            .add_read_group("rg2", read_group("normal", "lib2")?)
+            .add_read_group("rg3", read_group("unknown", "lib3")?)
+            .build();
+        let mut writer = bam::io::Writer::new(fs::File::create(&bam_path)?);
+        writer.write_header(&header)?;
+        let record = record_buf::Builder::default()
+            .set_name("r1")
+            .set_flags(Flags::UNMAPPED)
+            .set_sequence(b"ACGT".into())
+            .set_quality_scores(record_buf::QualityScores::from(vec![30; 4]))
+            .build();
+        writer.write_alignment_record(&header, &record)?;
+        writer.into_inner().finish()?;
+
+        let output = dir.path().join("report.jsonl");
+        let size = fs::metadata(&bam_path)?.len();
+        let options = RunOptions {
+            lab_datum_bams: BTreeMap::from([
+                (
+                    bam_path.clone(),
+                    vec!["tumor".to_string(), "germline".to_string()],
+                ),
+                (dir.path().join("normal.bam"), vec!["normal".to_string()]),
+            ]),
+            ..test_options(true)
+        };
+        let jobs = vec![Job::Bam(BamCheckJob {
+            path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: None,
+            size,
+        })];
+        run_check(jobs, size, &output, &options)?;
+
+        let records = read_jsonl_report(&output)?;
+        let TestReport::Bam(data) = &records[0] else {
+            panic!("Expected a BAM report");
+        };
+        assert_eq!(
+            data.errors,
+            vec![
+                "Read group 'rg2' (SM 'normal', LB 'lib2') belongs to lab datum 'normal', which is not assigned to this file. The file may have been merged with the reads of another sample.",
+                "Read group 'rg3' (SM 'unknown', LB 'lib3') maps to no declared lab datum; its ID, SM or LB must be one of: tumor, germline.",
+                "Lab datum 'germline' is assigned to this file, but no read group has it as ID, SM or LB.",
+            ]
+        );
+        Ok(())
+    }
+
+    /// A job checking a BAM file declaring `sort_order` with records of the given names and
+    /// placements on chr1 or chr2 (reference sequence ID and 1-based start).
+    fn sorted_bam_job(
+        dir: &Path,
+        sort_order: &str,
+        records: &[(&str, Option<(usize, usize)>)],
+    ) -> Result<Job> {
+        use noodles::sam::header::record::value::map::header::tag;
+
+        let bam_path = dir.join(format!("{sort_order}.bam"));
+        let header = Header::builder()
+            .set_header(
+                Map::<noodles::sam::header::record::value::map::Header>::builder()
+                    .insert(tag::SORT_ORDER, sort_order)
+                    .build()?,
+            )
+            .add_reference_sequence(
+                "chr1",
+                Map::<ReferenceSequence>::new(NonZeroUsize::try_from(100_000)?),
+            )
+            .add_reference_sequence(
+                "chr2",
+                Map::<ReferenceSequence>::new(NonZeroUsize::try_from(100_000)?),
+            )
+            .build();
+        let mut writer = bam::io::Writer::new(fs::File::create(&bam_path)?);
+        writer.write_header(&header)?;
+        for &(name, placement) in records {
+            let mut builder = record_buf::Builder::default()
+                .set_name(name)
+                .set_sequence(b"ACGT".into())
+                .set_quality_scores(QualityScores::from(vec![30; 4]));
+            match placement {
+                Some((id, start)) => {
+                    builder = builder
+                        .set_reference_sequence_id(id)
+                        .set_alignment_start(Position::try_from(start)?)
+                        .set_cigar([Op::new(Kind::Match, 4)].into_iter().collect());
+                }
+                None => builder = builder.set_flags(Flags::UNMAPPED),
+            }
+            writer.write_alignment_record(&header, &builder.build())?;
+        }
+        writer.into_inner().finish()?;
+        Ok(Job::Bam(BamCheckJob {
+            size: fs::metadata(&bam_path)?.len(),
+            path: bam_path,
+            species: None,
+            unaligned: false,
+            index_path: None,
+        }))
+    }
+
+    #[test]
+    fn test_bam_sort_order() -> Result<()> {
+        let dir = tempdir()?;
+        let data: TestBamReportData = check_single_job(sorted_bam_job(
+            dir.path(),
+            "coordinate",
+            &[
+                ("r1", Some((0, 100))),
+                ("r2", Some((0, 100))),
+                ("r3", Some((1, 5))),
+                ("r4", None),
+            ],
+        )?)?;
+        assert!(data.errors.is_empty(), "{:?}", data.errors);
+
+        let data: TestBamReportData = check_single_job(sorted_bam_job(
+            dir.path(),
+            "coordinate",
+            &[
+                ("r1", Some((1, 5))),
+                ("r2", Some((0, 100))),
+                ("r3", None),
+                ("r4", Some((0, 200))),
+            ],
+        )?)?;
+        assert_eq!(data.status, "ERROR");
+        assert_eq!(
+            data.errors,
+            vec![
+                "Header declares SO:coordinate, but record #2 ('r2') at chr1:100 comes after a record at chr2:5. The file is not sorted by coordinate, so it cannot be indexed."
+            ]
+        );
+
+        // Natural order, as written by `samtools sort -n`.
+        let data: TestBamReportData = check_single_job(sorted_bam_job(
+            dir.path(),
+            "queryname",
+            &[("read2", None), ("read2", None), ("read10", None)],
+        )?)?;
+        assert!(data.errors.is_empty(), "{:?}", data.errors);
+
+        let data: TestBamReportData = check_single_job(sorted_bam_job(
+            dir.path(),
+            "queryname",
+            &[("read2", None), ("read10", None), ("read2", None)],
+        )?)?;
+        assert_eq!(
+            data.errors,
+            vec![
+                "Header declares SO:queryname, but record #3 ('read2') comes after 'read10'. The file is not sorted by read name, so the records of a template may not be grouped."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_assess_reports_threshold_violations_as_warnings() -> Result<()> {
+        let fixture = TestFiles::new()?;
+        let output = fixture.dir.join("report.jsonl");
+
+        write_gzipped(&fixture.dir.join("truncated.fastq.gz"), "@SEQ1\nACGT\n")?;
+
+        let mut jobs = Vec::new();
+        let mut total_bytes = 0;
+        for name in ["ok_r1.fastq.gz", "truncated.fastq.gz"] {
+            let path = fixture.dir.join(name);
+            let size = fs::metadata(&path)?.len();
+            total_bytes += size;
+            jobs.push(Job::SingleFastq(SingleFastqJob {
+                path,
+                length_check: ReadLengthCheck::Fixed(10),
+                declared_read_length: None,
+                size,
+            }));
+        }
+        let options = RunOptions {
+            assess: true,
+            ..test_options(true)
+        };
+        run_check(jobs, total_bytes, &output, &options)?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 2);
+        let TestReport::Fastq(ok) = &records[0] else {
+            panic!("Expected a FASTQ report");
+        };
+        assert_eq!(ok.status, "OK");
+        assert_eq!(ok.num_records, Some(2));
+        assert_eq!(
+            ok.warnings,
+            vec!["Mean read length (4) is not greater than minimum required (10)"]
+        );
+        let TestReport::Fastq(truncated) = &records[1] else {
+            panic!("Expected a FASTQ report");
+        };
+        assert_eq!(truncated.status, "ERROR");
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_gzip_stream() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("partial.fastq.gz");
+        write_gzipped(&path, &"@SEQ1\nACGTACGTAC\n+\nFFFFFFFFFF\n".repeat(1000))?;
+        let content = fs::read(&path)?;
+        fs::write(&path, &content[..content.len() / 2])?;
+        let size = fs::metadata(&path)?.len();
+
+        let output = dir.path().join("report.jsonl");
+        let job = Job::SingleFastq(SingleFastqJob {
+            path,
+            length_check: ReadLengthCheck::Skip,
+            declared_read_length: None,
+            size,
+        });
+        run_check(vec![job], size, &output, &test_options(true))?;
+        let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+            panic!("Expected a Fastq report");
+        };
+        assert_eq!(
+            data.errors,
+            vec![format!(
+                "Compressed stream truncated at byte {size} (file likely incomplete). The file ends inside a compressed block; transfer it again."
+            )]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_identical_mates() -> Result<()> {
+        let dir = tempdir()?;
+        let content = "@SEQ1/1\nACGTACGTAC\n+\nFFFFFFFFFF\n@SEQ2/1\nTTGCATTGCA\n+\nFFFFFFFFFF\n";
+        let fq1_path = dir.path().join("r1.fastq.gz");
+        write_gzipped(&fq1_path, content)?;
+        let copy_path = dir.path().join("r2.fastq.gz");
+        fs::copy(&fq1_path, &copy_path)?;
+        // The same reads, compressed differently.
+        let recompressed_path = dir.path().join("r2_recompressed.fastq.gz");
+        let mut writer = GzEncoder::new(fs::File::create(&recompressed_path)?, Compression::fast());
+        writer.write_all(content.replace("/1", "/2").as_bytes())?;
+        writer.finish()?;
+
+        for (fq2_path, expected) in [
+            (&copy_path, "R1 and R2 are byte-identical"),
+            (
+                &recompressed_path,
+                "R1 and R2 contain the same reads: all 2 pair(s)",
+            ),
+        ] {
+            let output = dir.path().join("report.jsonl");
+            let fq1_size = fs::metadata(&fq1_path)?.len();
+            let fq2_size = fs::metadata(fq2_path)?.len();
+            let job = Job::PairedFastq(PairedFastqJob {
+                fq1_path: fq1_path.clone(),
+                fq2_path: fq2_path.clone(),
+                length_check: ReadLengthCheck::Skip,
+                fq1_declared_read_length: None,
+                fq2_declared_read_length: None,
+                fq1_size,
+                fq2_size,
+            });
+            run_check(vec![job], fq1_size + fq2_size, &output, &test_options(true))?;
+            let [pair] = read_entity_reports(&output)?
+                .try_into()
+                .expect("a single pair");
+            assert_eq!(pair.status, "ERROR");
+            assert_eq!(pair.errors.len(), 1);
+            assert!(pair.errors[0].starts_with(expected), "{:?}", pair.errors);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_windows_edited_fastq() -> Result<()> {
+        let dir = tempdir()?;
+        let check = |name: &str, content: &str| -> Result<(Vec<String>, Vec<String>)> {
+            let path = dir.path().join(name);
+            write_gzipped(&path, content)?;
+            let size = fs::metadata(&path)?.len();
+            let output = dir.path().join(format!("{name}.report.jsonl"));
+            let job = Job::SingleFastq(SingleFastqJob {
+                path,
+                length_check: ReadLengthCheck::Skip,
+                declared_read_length: None,
+                size,
+            });
+            run_check(vec![job], size, &output, &test_options(true))?;
+            let TestReport::Fastq(data) = &read_jsonl_report(&output)?[0] else {
+                panic!("Expected a Fastq report");
+            };
+            Ok((data.errors.clone(), data.warnings.clone()))
+        };
+
+        let (errors, warnings) = check(
+            "crlf.fastq.gz",
+            "@SEQ1\r\nACGTACGTAC\r\n+\r\nFFFFFFFFFF\r\n@SEQ2\r\nACGTACGTAC \r\n+\r\nFFFFFFFFFF\r\n",
+        )?;
+        assert_eq!(
+            errors,
+            vec![
+                "Trailing whitespace (space) at the end of line 6, the sequence line of record #2. Sequence and quality lines must not contain whitespace."
+            ]
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                "File has Windows (CRLF) line endings, first at line 1 (record #1). Many tools expect Unix (LF) line endings."
+            ]
+        );
+
+        let (errors, _) = check(
+            "blank.fastq.gz",
+            "@SEQ1\nACGTACGTAC\n+\nFFFFFFFFFF\n\n@SEQ2\nACGTACGTAC\n+\nFFFFFFFFFF\n",
+        )?;
+        assert_eq!(
+            errors,
+            vec![
+                "Failed to parse record record #2: blank line at line 5, where the record should start. FASTQ records consist of exactly four lines, without blank lines between or after them."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_members_of_tar_archive() -> Result<()> {
+        let fixture = TestFiles::new()?;
+        let output = fixture.dir.join("report.jsonl");
+
+        let archive_path = fixture.dir.join("submission.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive_path)?);
+        builder.append_path_with_name(fixture.dir.join("ok_r1.fastq.gz"), "files/r1.fastq.gz")?;
+        let content = "some file contents";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "files/raw.txt", content.as_bytes())?;
+        builder.into_inner()?;
+
+        let fastq_path = PathBuf::from(format!("{}::files/r1.fastq.gz", archive_path.display()));
+        let raw_path = PathBuf::from(format!("{}::files/raw.txt", archive_path.display()));
+        let fastq_size = fs::metadata(fixture.dir.join("ok_r1.fastq.gz"))?.len();
+        let raw_size = content.len() as u64;
+        let jobs = vec![
+            Job::SingleFastq(SingleFastqJob {
+                path: fastq_path.clone(),
+                length_check: ReadLengthCheck::Skip,
+                declared_read_length: None,
+                size: fastq_size,
+            }),
+            Job::Raw(RawJob {
+                path: raw_path.clone(),
+                size: raw_size,
+                expected_sha256: None,
+            }),
+        ];
+        run_check(jobs, fastq_size + raw_size, &output, &test_options(true))?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 2);
+        let Some(fastq) = records.iter().find_map(|record| match record {
+            TestReport::Fastq(data) => Some(data),
+            _ => None,
+        }) else {
+            panic!("Expected a FASTQ report");
+        };
+        assert_eq!(fastq.path, fastq_path);
+        assert_eq!(fastq.status, "OK");
+        assert_eq!(fastq.num_records, Some(2));
+        let Some(raw) = records.iter().find_map(|record| match record {
+            TestReport::Raw(data) => Some(data),
+            _ => None,
+        }) else {
+            panic!("Expected a Checksum report");
+        };
+        assert_eq!(raw.path, raw_path);
+        assert_eq!(
+            raw.checksum.as_deref(),
+            Some("cf57fcf9d6d7fb8fd7d8c30527c8f51026aa1d99ad77cc769dd0c757d4fe8667")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_triple_fastq_with_index_reads() -> Result<()> {
+        let fixture = TestFiles::new()?;
+        write_gzipped(
+            &fixture.dir.join("ok_i1.fastq.gz"),
+            "@SEQ1\nAC\n+\nFF\n@SEQ2\nGT\n+\nFF\n",
+        )?;
+        write_gzipped(&fixture.dir.join("short_i1.fastq.gz"), "@SEQ1\nAC\n+\nFF\n")?;
+
+        let run_triple =
+            |index_name: &str| -> Result<(Vec<TestFastqReportData>, TestEntityReportData)> {
+                let output = fixture.dir.join(format!("{index_name}.jsonl"));
+                let fq1_path = fixture.dir.join("ok_r1.fastq.gz");
+                let fq2_path = fixture.dir.join("ok_r2.fastq.gz");
+                let index_path = fixture.dir.join(index_name);
+                let fq1_size = fs::metadata(&fq1_path)?.len();
+                let fq2_size = fs::metadata(&fq2_path)?.len();
+                let index_size = fs::metadata(&index_path)?.len();
+                let jobs = vec![Job::TripleFastq(TripleFastqJob {
+                    pair: PairedFastqJob {
+                        fq1_path,
+                        fq2_path,
+                        length_check: ReadLengthCheck::Fixed(3),
+                        fq1_declared_read_length: None,
+                        fq2_declared_read_length: None,
+                        fq1_size,
+                        fq2_size,
+                    },
+                    index_path,
+                    index_size,
+                })];
+                run_check(
+                    jobs,
+                    fq1_size + fq2_size + index_size,
+                    &output,
+                    &test_options(true),
+                )?;
+                let reports = read_jsonl_report(&output)?
+                    .into_iter()
+                    .map(|record| match record {
+                        TestReport::Fastq(data) => Ok(data),
+                        other => Err(anyhow!("Expected a FASTQ report, got {other:?}")),
+                    })
+                    .collect::<Result<_>>()?;
+                let [pair] = read_entity_reports(&output)?
+                    .try_into()
+                    .map_err(|pairs| anyhow!("Expected a single pair, got {pairs:?}"))?;
+                Ok((reports, pair))
+            };
+
+        let (reports, pair) = run_triple("ok_i1.fastq.gz")?;
+        assert_eq!(reports.len(), 3);
+        assert!(reports.iter().all(|data| data.status == "OK"));
+        assert_eq!(reports[2].num_records, Some(2));
+        assert_eq!(reports[2].mean_read_length, Some(2.0));
+        assert_eq!(pair.status, "OK");
+        assert_eq!(pair.members.len(), 3);
+
+        let (reports, pair) = run_triple("short_i1.fastq.gz")?;
+        assert_eq!(reports.len(), 3);
+        assert!(reports.iter().all(|data| data.status == "OK"));
+        assert_eq!(pair.status, "ERROR");
+        assert_eq!(
+            pair.errors,
+            vec!["Mismatched read counts: I1 has 1 records, but R1 has 2."]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_md5_sidecars() -> Result<()> {
+        use md5::{Digest, Md5};
+
+        let fixture = TestFiles::new()?;
+        let output = fixture.dir.join("report.jsonl");
+        let fq1_path = fixture.dir.join("ok_r1.fastq.gz");
+        let fq2_path = fixture.dir.join("ok_r2.fastq.gz");
+        let raw_path = fixture.dir.join("raw.txt");
+        fs::write(&raw_path, "some file contents")?;
+
+        let md5_of =
+            |path: &Path| -> Result<String> { Ok(format!("{:x}", Md5::digest(fs::read(path)?))) };
+        fs::write(
+            fixture.dir.join("ok_r1.fastq.gz.md5"),
+            format!("{}  ok_r1.fastq.gz\n", md5_of(&fq1_path)?.to_uppercase()),
+        )?;
+        fs::write(
+            fixture.dir.join("ok_r2.fastq.gz.md5"),
+            format!("{}  ok_r2.fastq.gz\n", md5_of(&fq1_path)?),
+        )?;
+        fs::write(fixture.dir.join("raw.txt.md5"), "not a digest\n")?;
+
+        let fq1_size = fs::metadata(&fq1_path)?.len();
+        let fq2_size = fs::metadata(&fq2_path)?.len();
+        let raw_size = fs::metadata(&raw_path)?.len();
+        let jobs = vec![
+            Job::PairedFastq(PairedFastqJob {
+                fq1_path,
+                fq2_path,
+                length_check: ReadLengthCheck::Skip,
+                fq1_declared_read_length: None,
+                fq2_declared_read_length: None,
+                fq1_size,
+                fq2_size,
+            }),
+            Job::Raw(RawJob {
+                path: raw_path,
+                size: raw_size,
+                expected_sha256: None,
+            }),
+        ];
+        let options = RunOptions {
+            verify_md5: true,
+            ..test_options(true)
+        };
+        run_check(jobs, fq1_size + fq2_size + raw_size, &output, &options)?;
+
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 3);
+        let fastq_reports: Vec<&TestFastqReportData> = records
+            .iter()
+            .filter_map(|record| match record {
+                TestReport::Fastq(data) => Some(data),
+                _ => None,
+            })
+            .collect();
+        let [fq1, fq2] = fastq_reports[..] else {
+            panic!("Expected two FASTQ reports: {records:?}");
+        };
+        let Some(TestReport::Raw(raw)) = records
+            .iter()
+            .find(|record| matches!(record, TestReport::Raw(_)))
+        else {
+            panic!("Expected a Checksum report: {records:?}");
+        };
+        assert_eq!(fq1.status, "OK");
+        assert_eq!(fq2.status, "ERROR");
+        assert_eq!(
+            fq2.errors
+                .iter()
+                .map(|e| findings::code_for(e))
+                .collect::<Vec<_>>(),
+            vec!["checksum.md5_mismatch"]
+        );
+        assert_eq!(raw.status, "ERROR");
+        assert_eq!(
+            raw.errors
+                .iter()
+                .map(|e| findings::code_for(e))
+                .collect::<Vec<_>>(),
+            vec!["checksum.md5_sidecar_unreadable"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_staging_hook_runs_before_check() -> Result<()> {
+        let dir = tempdir()?;
+        let staged_path = dir.path().join("staged.txt");
+        let offline_path = dir.path().join("offline.txt");
+        fs::write(&staged_path, "some file contents")?;
+        fs::write(&offline_path, "some file contents")?;
+
+        let options = RunOptions {
+            staging_hook: Some(StagingHook {
+                command: "case {path} in *offline*) echo 'not on tape' >&2; exit 1;; esac; touch {path}.staged"
+                    .to_string(),
+            }),
+            ..test_options(true)
+        };
+        let jobs = [&staged_path, &offline_path]
+            .into_iter()
+            .map(|path| {
+                Job::Raw(RawJob {
+                    path: path.clone(),
+                    size: 18,
+                    expected_sha256: None,
+                })
+            })
+            .collect();
+        let output = dir.path().join("report.jsonl");
+        run_check(jobs, 36, &output, &options)?;
+
+        assert!(dir.path().join("staged.txt.staged").exists());
+        let records = read_jsonl_report(&output)?;
+        assert_eq!(records.len(), 2);
+        for record in records {
+            let TestReport::Raw(data) = record else {
+                panic!("Expected a Checksum report");
+            };
+            if data.path == staged_path {
+                assert_eq!(data.status, "OK");
+                assert!(data.staging_seconds.is_some());
+            } else {
+                assert_eq!(data.status, "ERROR");
+                assert_eq!(data.staging_seconds, None);
+                assert_eq!(
+                    data.errors
+                        .iter()
+                        .map(|e| findings::code_for(e))
+                        .collect::<Vec<_>>(),
+                    vec!["io.staging"],
+                    "{:?}",
+                    data.errors
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_post_check_hook_moves_passed_files() -> Result<()> {
+        let dir = tempdir()?;
+        let ready = dir.path().join("ready");
+        fs::create_dir(&ready)?;
+        let passed_path = dir.path().join("passed.vcf");
+        let failed_path = dir.path().join("failed.txt");
+        fs::write(&passed_path, "some file contents")?;
+        fs::write(&failed_path, "some file contents")?;
+
+        let options = RunOptions {
+            strict_extensions: true,
+            post_check_hook: Some(PostCheckHook {
+                command: format!(
+                    "if [ {{status}} = OK ]; then mv {{path}} {ready} && echo {{digest}} > {ready}/digest; fi",
+                    ready = ready.display()
+                ),
+                digest_encoding: DigestEncoding::S3,
+            }),
+            report_digest_encoding: DigestEncoding::Base64,
+            quarantine: Some(Quarantine {
+                dir: dir.path().join("quarantine"),
+                mode: crate::quarantine::QuarantineMode::Copy,
+            }),
+            ..test_options(true)
+        };
+        let jobs = [&passed_path, &failed_path]
+            .into_iter()
+            .map(|path| {
+                Job::Raw(RawJob {
+                    path: path.clone(),
+                    size: 18,
+                    expected_sha256: None,
+                })
+            })
+            .collect();
+        let output = dir.path().join("report.jsonl");
+        run_check(jobs, 36, &output, &options)?;
+
+        assert!(!passed_path.exists());
+        assert!(ready.join("passed.vcf").exists());
+        assert!(failed_path.exists());
+        assert_eq!(
+            fs::read_to_string(ready.join("digest"))?,
+            "z1f8+dbX+4/X2MMFJ8j1ECaqHZmtd8x2ndDHV9T+hmc=\n"
+        );
+        let quarantined = dir
+            .path()
+            .join("quarantine")
+            .join(failed_path.strip_prefix("/")?);
+        let mut entries = quarantined.into_os_string();
+        entries.push(".report.jsonl");
+        let records = read_jsonl_report(Path::new(&entries))?
+            .into_iter()
+            .chain(read_jsonl_report(&output)?);
+        for record in records {
+            let TestReport::Raw(data) = record else {
+                panic!("Expected a Raw report");
+            };
+            assert_eq!(
+                data.checksum.as_deref(),
+                Some("z1f8-dbX-4_X2MMFJ8j1ECaqHZmtd8x2ndDHV9T-hmc")
+            );
+        }
+        Ok(())
+    }
 }
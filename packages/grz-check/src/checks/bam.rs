@@ -1,102 +1,1043 @@
+use crate::archive;
 use crate::checker::{FileReport, Stats};
-use crate::checks::common::{CheckOutcome, check_file};
+use crate::checks::common::{
+    CheckFailure, CheckOutcome, CheckSettings, Decompression, Occurrences, check_file,
+};
+use crate::checks::dependencies::Check;
+use crate::checks::reference::{self, Species};
+use crate::lab_data;
 use indicatif::ProgressBar;
-use noodles::bam;
+use noodles::csi::BinningIndex;
+use noodles::csi::binning_index::Index;
+use noodles::csi::binning_index::ReferenceSequence as _;
+use noodles::csi::binning_index::index::reference_sequence::{self, Bin};
+use noodles::sam::alignment::record::Flags;
 use noodles::sam::alignment::record::cigar::op::Kind;
-use std::io::BufReader;
+use noodles::sam::alignment::record::data::field::value::Array;
+use noodles::sam::alignment::record::data::field::{Tag, Value};
+use noodles::sam::header::record::value::map::header::{sort_order, tag};
+use noodles::{bam, bgzf, csi, sam};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::io;
 use std::path::{Path, PathBuf};
 
-pub fn check_bam(path: &Path, file_pb: &ProgressBar, global_pb: &ProgressBar) -> FileReport {
-    check_file(path, file_pb, global_pb, false, |reader| {
-        let mut bam_reader = bam::io::Reader::new(BufReader::new(reader));
-        let header = match bam_reader.read_header() {
-            Ok(h) => h,
-            Err(e) => return Err(format!("Failed to read BAM header: {e}")),
+pub const HEADER_CHECK: &str = "alignment.header";
+pub const RECORDS_CHECK: &str = "alignment.records";
+const UNALIGNED_CHECK: &str = "alignment.unaligned";
+const INDEX_CHECK: &str = "alignment.index";
+
+/// Checks of BAM and SAM files.
+pub const CHECKS: &[Check] = &[
+    Check::new(HEADER_CHECK, &[]),
+    Check::new(reference::PLAUSIBILITY_CHECK, &[HEADER_CHECK]),
+    Check::new(RECORDS_CHECK, &[HEADER_CHECK]),
+];
+
+/// Checks of unaligned BAM files, which must not contain any alignment information.
+pub const UNALIGNED_CHECKS: &[Check] = &[
+    Check::new(HEADER_CHECK, &[]),
+    Check::new(reference::PLAUSIBILITY_CHECK, &[HEADER_CHECK]),
+    Check::new(RECORDS_CHECK, &[HEADER_CHECK]),
+    Check::new(UNALIGNED_CHECK, &[RECORDS_CHECK]),
+];
+
+/// Checks of BAM files with an index, given via `--check-index`.
+pub const INDEXED_CHECKS: &[Check] = &[
+    Check::new(HEADER_CHECK, &[]),
+    Check::new(reference::PLAUSIBILITY_CHECK, &[HEADER_CHECK]),
+    Check::new(RECORDS_CHECK, &[HEADER_CHECK]),
+    Check::new(INDEX_CHECK, &[HEADER_CHECK]),
+];
+
+/// Records violating a requirement of unaligned BAM files.
+#[derive(Default)]
+struct Violations {
+    count: u64,
+    first: Option<(u64, String)>,
+}
+
+impl Violations {
+    fn add(&mut self, rec_num: u64, read_name: impl FnOnce() -> String) {
+        self.count += 1;
+        if self.first.is_none() {
+            self.first = Some((rec_num, read_name()));
+        }
+    }
+
+    fn report(self, description: &str, errors: &mut Vec<String>) {
+        if let Some((rec_num, read_name)) = self.first {
+            errors.push(format!(
+                "File contains {} {description}, which are not allowed in unaligned BAM. First detected at record #{rec_num} ('{read_name}').",
+                self.count
+            ));
+        }
+    }
+}
+
+/// Maximum number of records reported for each inconsistency of flags.
+const MAX_FLAG_EXAMPLES: usize = 3;
+
+/// Inconsistencies of the flags of paired records and their mate fields.
+const FLAG_RULES: [&str; 4] = [
+    "flagged as paired with a mapped mate, but without the reference sequence or position of the mate",
+    "with flags about their mate (mate unmapped or mate reverse strand), but not flagged as paired",
+    "flagged as both the first and the last segment of their template (read 1 and read 2)",
+    "flagged as properly paired, although they are unpaired or they or their mate are unmapped",
+];
+
+/// Records violating each of [`FLAG_RULES`], with the first few record numbers and names.
+#[derive(Default)]
+struct FlagChecks {
+    violations: [(u64, Vec<(u64, String)>); FLAG_RULES.len()],
+}
+
+impl FlagChecks {
+    fn add<R: sam::alignment::Record>(
+        &mut self,
+        header: &sam::Header,
+        rec_num: u64,
+        record: &R,
+        flags: Flags,
+    ) {
+        let paired = flags.is_segmented();
+        let mate_fields_missing = record.mate_reference_sequence_id(header).is_none()
+            || record.mate_alignment_start().is_none();
+        let violated = [
+            paired && !flags.is_mate_unmapped() && mate_fields_missing,
+            !paired && (flags.is_mate_unmapped() || flags.is_mate_reverse_complemented()),
+            flags.is_first_segment() && flags.is_last_segment(),
+            flags.is_properly_segmented()
+                && (!paired || flags.is_unmapped() || flags.is_mate_unmapped()),
+        ];
+        for (is_violated, (count, examples)) in violated.into_iter().zip(&mut self.violations) {
+            if is_violated {
+                *count += 1;
+                if examples.len() < MAX_FLAG_EXAMPLES {
+                    let name = record.name().map(|n| n.to_string()).unwrap_or_default();
+                    examples.push((rec_num, name));
+                }
+            }
+        }
+    }
+
+    fn report(self, errors: &mut Vec<String>) {
+        for (description, (count, examples)) in FLAG_RULES.iter().zip(self.violations) {
+            if count > 0 {
+                let examples: Vec<_> = examples
+                    .iter()
+                    .map(|(rec_num, name)| format!("#{rec_num} ('{name}')"))
+                    .collect();
+                errors.push(format!(
+                    "File contains {count} record(s) {description}. First detected at record(s) {}.",
+                    examples.join(", ")
+                ));
+            }
+        }
+    }
+}
+
+/// Read groups of the records compared to the `@RG` lines of the header.
+#[derive(Default)]
+struct ReadGroupCheck {
+    /// Read groups that are not declared in the header, with their number of records.
+    undeclared: BTreeMap<String, u64>,
+    first_undeclared: Option<(u64, String)>,
+    with_read_group: u64,
+    without_read_group: Violations,
+}
+
+impl ReadGroupCheck {
+    fn add<R: sam::alignment::Record>(&mut self, header: &sam::Header, rec_num: u64, record: &R) {
+        let name = || record.name().map(|n| n.to_string()).unwrap_or_default();
+        let data = record.data();
+        // Malformed fields are reported by the tag validation.
+        let id = match data.get(&Tag::READ_GROUP) {
+            None => {
+                self.without_read_group.add(rec_num, name);
+                return;
+            }
+            Some(Ok(Value::String(id))) => id,
+            Some(_) => return,
         };
+        self.with_read_group += 1;
+        if !header.read_groups().contains_key(id) {
+            *self.undeclared.entry(id.to_string()).or_default() += 1;
+            if self.first_undeclared.is_none() {
+                self.first_undeclared = Some((rec_num, name()));
+            }
+        }
+    }
 
-        let mut warnings = Vec::new();
-        if !header.reference_sequences().is_empty()
-            || !header.read_groups().is_empty()
-            || (header.programs().roots().count() != 0)
-            || !header.comments().is_empty()
+    fn report(self, header: &sam::Header, errors: &mut Vec<String>, warnings: &mut Vec<String>) {
+        if let Some((rec_num, read_name)) = self.first_undeclared {
+            let count: u64 = self.undeclared.values().sum();
+            let mut ids: Vec<_> = self
+                .undeclared
+                .iter()
+                .take(MAX_FLAG_EXAMPLES)
+                .map(|(id, count)| format!("'{id}' ({count} record(s))"))
+                .collect();
+            if self.undeclared.len() > MAX_FLAG_EXAMPLES {
+                ids.push(format!(
+                    "and {} more",
+                    self.undeclared.len() - MAX_FLAG_EXAMPLES
+                ));
+            }
+            errors.push(format!(
+                "File contains {count} record(s) with a read group that is not declared by an @RG header line: {}. First detected at record #{rec_num} ('{read_name}'). The @RG lines were likely dropped when the file was merged or reheadered.",
+                ids.join(", ")
+            ));
+        }
+        // Files without any read groups are not expected to tag their records.
+        if self.with_read_group == 0 && header.read_groups().is_empty() {
+            return;
+        }
+        if let Some((rec_num, read_name)) = self.without_read_group.first {
+            warnings.push(format!(
+                "File contains {} record(s) without a read group (RG tag), although the file uses read groups. First detected at record #{rec_num} ('{read_name}').",
+                self.without_read_group.count
+            ));
+        }
+    }
+}
+
+/// Mapped records whose alignment extends beyond the length of their reference sequence declared
+/// by its `@SQ` line.
+#[derive(Default)]
+struct BoundsCheck {
+    out_of_bounds: Violations,
+    /// Alignment of the first record out of bounds, e.g. `chr1:95-104`, and the declared length of
+    /// its reference sequence.
+    first_alignment: Option<(String, usize)>,
+}
+
+impl BoundsCheck {
+    fn add<R: sam::alignment::Record>(
+        &mut self,
+        header: &sam::Header,
+        rec_num: u64,
+        record: &R,
+        flags: Flags,
+    ) {
+        // Unmapped records may be placed at the position of their mate without an alignment.
+        if flags.is_unmapped() {
+            return;
+        }
+        let Some(Ok(id)) = record.reference_sequence_id(header) else {
+            return;
+        };
+        let Some((name, reference_sequence)) = header.reference_sequences().get_index(id) else {
+            return;
+        };
+        let (Some(Ok(start)), Some(Ok(end))) = (record.alignment_start(), record.alignment_end())
+        else {
+            return;
+        };
+        let length = reference_sequence.length().get();
+        if usize::from(end) <= length {
+            return;
+        }
+        self.out_of_bounds.add(rec_num, || {
+            record.name().map(|n| n.to_string()).unwrap_or_default()
+        });
+        if self.first_alignment.is_none() {
+            self.first_alignment = Some((format!("{name}:{start}-{end}"), length));
+        }
+    }
+
+    fn report(self, errors: &mut Vec<String>) {
+        if let (Some((rec_num, read_name)), Some((alignment, length))) =
+            (self.out_of_bounds.first, self.first_alignment)
         {
-            warnings.push(
-                "Detected a header in BAM file, ensure it contains no private information!"
-                    .to_string(),
-            );
-        }
-
-        let mut num_records = 0;
-        let mut secondary_alignment_count: u64 = 0;
-        let mut first_secondary_warning_details: Option<(u64, String)> = None;
-        let mut hard_clip_count: u64 = 0;
-        let mut first_hard_clip_warning_details: Option<(u64, String)> = None;
-
-        for (i, result) in bam_reader.records().enumerate() {
-            let record = match result {
-                Ok(rec) => rec,
-                Err(e) => return Err(format!("Failed to parse record #{}: {}", i + 1, e)),
-            };
-            num_records += 1;
+            errors.push(format!(
+                "File contains {} mapped record(s) extending beyond the end of their reference sequence. First detected at record #{rec_num} ('{read_name}'), aligned to {alignment}, although the @SQ line declares a length of {length}. The file was likely corrupted or edited by hand.",
+                self.out_of_bounds.count
+            ));
+        }
+    }
+}
 
-            if record.flags().is_secondary() {
-                secondary_alignment_count += 1;
-                if first_secondary_warning_details.is_none() {
-                    first_secondary_warning_details = Some((
-                        num_records,
-                        record.name().map(|n| n.to_string()).unwrap_or_default(),
+/// Sort order declared by the `SO` tag of the header.
+#[derive(Clone, Copy)]
+enum SortOrder {
+    Coordinate,
+    QueryName,
+}
+
+fn trim_zeros(s: &[u8]) -> &[u8] {
+    &s[s.iter().take_while(|&&c| c == b'0').count()..]
+}
+
+/// Compares read names with runs of digits as numbers, like `samtools sort -n`.
+fn natural_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let digits_end = |s: &[u8], start: usize| {
+        s[start..]
+            .iter()
+            .position(|c| !c.is_ascii_digit())
+            .map_or(s.len(), |p| start + p)
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let (end_a, end_b) = (digits_end(a, i), digits_end(b, j));
+            let (x, y) = (trim_zeros(&a[i..end_a]), trim_zeros(&b[j..end_b]));
+            match x.len().cmp(&y.len()).then_with(|| x.cmp(y)) {
+                Ordering::Equal => (i, j) = (end_a, end_b),
+                ordering => return ordering,
+            }
+        } else {
+            match a[i].cmp(&b[j]) {
+                Ordering::Equal => (i, j) = (i + 1, j + 1),
+                ordering => return ordering,
+            }
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j))
+}
+
+/// Verifies that the records follow the sort order declared in the header, reporting the first
+/// record out of order.
+///
+/// Coordinate order places unplaced records after all placed ones. For query name order, the
+/// names must be sorted lexicographically (Picard, `samtools sort -N`) or naturally
+/// (`samtools sort -n`), which groups the records of a template.
+struct SortOrderCheck {
+    order: SortOrder,
+    /// Reference sequence ID and 0-based start of the previous record.
+    last_coordinate: (usize, usize),
+    last_name: Vec<u8>,
+    lexicographic: bool,
+    natural: bool,
+    error: Option<String>,
+}
+
+impl SortOrderCheck {
+    fn new(header: &sam::Header) -> Option<Self> {
+        let declared = header.header()?.other_fields().get(&tag::SORT_ORDER)?;
+        let order = match declared.as_ref() {
+            sort_order::COORDINATE => SortOrder::Coordinate,
+            sort_order::QUERY_NAME => SortOrder::QueryName,
+            _ => return None,
+        };
+        Some(Self {
+            order,
+            last_coordinate: (0, 0),
+            last_name: Vec::new(),
+            lexicographic: true,
+            natural: true,
+            error: None,
+        })
+    }
+
+    fn describe_coordinate(header: &sam::Header, (id, start): (usize, usize)) -> String {
+        match header.reference_sequences().get_index(id) {
+            Some((name, _)) => format!("{name}:{}", start + 1),
+            None => "an unplaced position".to_string(),
+        }
+    }
+
+    fn add<R: sam::alignment::Record>(&mut self, header: &sam::Header, rec_num: u64, record: &R) {
+        if self.error.is_some() {
+            return;
+        }
+        let read_name = || record.name().map(|n| n.to_string()).unwrap_or_default();
+        match self.order {
+            SortOrder::Coordinate => {
+                let id = record
+                    .reference_sequence_id(header)
+                    .and_then(Result::ok)
+                    .unwrap_or(usize::MAX);
+                let start = record
+                    .alignment_start()
+                    .and_then(Result::ok)
+                    .map_or(0, |position| usize::from(position) - 1);
+                let coordinate = (id, start);
+                if coordinate < self.last_coordinate {
+                    self.error = Some(format!(
+                        "Header declares SO:coordinate, but record #{rec_num} ('{}') at {} comes after a record at {}. The file is not sorted by coordinate, so it cannot be indexed.",
+                        read_name(),
+                        Self::describe_coordinate(header, coordinate),
+                        Self::describe_coordinate(header, self.last_coordinate)
                     ));
                 }
+                self.last_coordinate = coordinate;
             }
+            SortOrder::QueryName => {
+                let name: &[u8] = record.name().map(|n| n.as_ref()).unwrap_or_default();
+                self.lexicographic &= self.last_name.as_slice() <= name;
+                self.natural &= natural_cmp(&self.last_name, name) != Ordering::Greater;
+                if !self.lexicographic && !self.natural {
+                    self.error = Some(format!(
+                        "Header declares SO:queryname, but record #{rec_num} ('{}') comes after '{}'. The file is not sorted by read name, so the records of a template may not be grouped.",
+                        read_name(),
+                        String::from_utf8_lossy(&self.last_name)
+                    ));
+                }
+                self.last_name.clear();
+                self.last_name.extend_from_slice(name);
+            }
+        }
+    }
+}
 
-            if !record.flags().is_secondary()
-                && record
-                    .cigar()
-                    .iter()
-                    .any(|op| op.is_ok_and(|op| op.kind() == Kind::HardClip))
-            {
-                hard_clip_count += 1;
-                if first_hard_clip_warning_details.is_none() {
-                    first_hard_clip_warning_details = Some((
-                        num_records,
+/// Counts the values of an array, failing on the first malformed one.
+fn array_len<N>(values: impl Iterator<Item = io::Result<N>>) -> io::Result<u64> {
+    let mut len = 0;
+    for value in values {
+        value?;
+        len += 1;
+    }
+    Ok(len)
+}
+
+/// Returns the size of a value in BAM encoding, failing if it is malformed.
+fn encoded_size(value: &Value<'_>) -> io::Result<u64> {
+    let invalid = |message: &str| Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    Ok(match value {
+        Value::Character(c) if !c.is_ascii_graphic() => {
+            return invalid("character value is not printable");
+        }
+        Value::Character(_) | Value::Int8(_) | Value::UInt8(_) => 1,
+        Value::Int16(_) | Value::UInt16(_) => 2,
+        Value::Int32(_) | Value::UInt32(_) | Value::Float(_) => 4,
+        Value::Hex(hex) if hex.len() % 2 != 0 || !hex.iter().all(u8::is_ascii_hexdigit) => {
+            return invalid("hex string is not an even number of hexadecimal digits");
+        }
+        Value::String(s) | Value::Hex(s) => s.len() as u64 + 1,
+        // Subtype and count, followed by the values.
+        Value::Array(array) => {
+            5 + match array {
+                Array::Int8(values) => array_len(values.iter())?,
+                Array::UInt8(values) => array_len(values.iter())?,
+                Array::Int16(values) => array_len(values.iter())? * 2,
+                Array::UInt16(values) => array_len(values.iter())? * 2,
+                Array::Int32(values) => array_len(values.iter())? * 4,
+                Array::UInt32(values) => array_len(values.iter())? * 4,
+                Array::Float(values) => array_len(values.iter())? * 4,
+            }
+        }
+    })
+}
+
+/// Auxiliary fields of the records of a file, validated with `--validate-tags`.
+#[derive(Default)]
+struct TagValidation {
+    num_records: u64,
+    bases: u64,
+    /// Encoded size of the fields of each tag, including the tag and type.
+    bytes: BTreeMap<[u8; 2], u64>,
+    malformed: u64,
+    /// Number and name of the first record with a malformed field, with the reason.
+    first_malformed: Option<(u64, String, String)>,
+}
+
+impl TagValidation {
+    fn add<R: sam::alignment::Record>(&mut self, rec_num: u64, record: &R) {
+        self.num_records += 1;
+        self.bases += record.sequence().len() as u64;
+        let data = record.data();
+        for field in data.iter() {
+            match field.and_then(|(tag, value)| Ok((tag, encoded_size(&value)?))) {
+                Ok((tag, size)) => *self.bytes.entry(*tag.as_ref()).or_default() += 3 + size,
+                Err(e) => {
+                    self.malformed += 1;
+                    if self.first_malformed.is_none() {
+                        let name = record.name().map(|n| n.to_string()).unwrap_or_default();
+                        self.first_malformed = Some((rec_num, name, e.to_string()));
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    fn report(self, errors: &mut Vec<String>, warnings: &mut Vec<String>) {
+        if let Some((rec_num, read_name, reason)) = self.first_malformed {
+            errors.push(format!(
+                "File contains {} record(s) with malformed auxiliary fields (tags). First detected at record #{rec_num} ('{read_name}'): {reason}.",
+                self.malformed
+            ));
+        }
+        let bloated: Vec<_> = self
+            .bytes
+            .iter()
+            .filter(|&(_, &bytes)| self.bases > 0 && bytes >= self.bases)
+            .map(|(tag, bytes)| {
+                format!(
+                    "{} ({} bytes per record)",
+                    String::from_utf8_lossy(tag),
+                    bytes / self.num_records
+                )
+            })
+            .collect();
+        if !bloated.is_empty() {
+            warnings.push(format!(
+                "Auxiliary tag(s) {} are as large as the reads themselves. Tags such as original qualities (OQ) or indel qualities (BI/BD) left by base recalibration inflate the submission.",
+                bloated.join(", ")
+            ));
+        }
+    }
+}
+
+/// Returns the 0-based, half-open region covered by a bin.
+fn bin_region(id: usize, min_shift: u8, depth: u8) -> Option<(u64, u64)> {
+    let mut level_start = 0;
+    for level in 0..=depth {
+        let level_end = level_start + (1 << (3 * level));
+        if id < level_end {
+            let width = 1u64 << (min_shift + 3 * (depth - level));
+            let start = (id - level_start) as u64 * width;
+            return Some((start, start + width));
+        }
+        level_start = level_end;
+    }
+    None
+}
+
+/// Maximum number of indexed offsets that are read to verify that they point to records.
+const MAX_INDEX_SAMPLES: usize = 64;
+
+/// A chunk start of an index, with the reference sequence and region its record must lie in.
+struct IndexedOffset {
+    reference_sequence_id: usize,
+    bin_id: usize,
+    region: (u64, u64),
+    offset: bgzf::VirtualPosition,
+}
+
+/// Seeks to a sample of the chunk starts of an index and checks that each points to a record
+/// within the region of its bin, as an index regenerated against an older version of the file
+/// does not.
+fn check_indexed_offsets(path: &Path, offsets: &[IndexedOffset], errors: &mut Vec<String>) {
+    if offsets.is_empty() || archive::is_stream(path) {
+        return;
+    }
+    let section = match archive::open_section(path) {
+        Ok(section) => section,
+        Err(e) => {
+            errors.push(format!("Failed to read file: {e}"));
+            return;
+        }
+    };
+    let mut reader = bam::io::Reader::new(section);
+    let mut record = bam::Record::default();
+    let mut mismatches = Occurrences::default();
+    let step = offsets.len().div_ceil(MAX_INDEX_SAMPLES);
+    let mut sampled = 0;
+    for indexed in offsets.iter().step_by(step) {
+        sampled += 1;
+        let found = reader
+            .get_mut()
+            .seek(indexed.offset)
+            .and_then(|_| reader.read_record(&mut record));
+        let mismatch = match found {
+            Err(e) => Some(format!("failed to read a record: {e}")),
+            Ok(0) => Some("no record".to_string()),
+            Ok(_) => {
+                let id = record.reference_sequence_id().and_then(Result::ok);
+                let start = record
+                    .alignment_start()
+                    .and_then(Result::ok)
+                    .map(|position| usize::from(position) as u64 - 1);
+                let (region_start, region_end) = indexed.region;
+                let in_region = id == Some(indexed.reference_sequence_id)
+                    && start.is_some_and(|start| (region_start..region_end).contains(&start));
+                (!in_region).then(|| {
+                    format!(
+                        "a record ('{}') at {}",
                         record.name().map(|n| n.to_string()).unwrap_or_default(),
+                        match (id, start) {
+                            (Some(id), Some(start)) => {
+                                format!("position {} of reference sequence #{}", start + 1, id + 1)
+                            }
+                            _ => "no position".to_string(),
+                        }
+                    )
+                })
+            }
+        };
+        if let Some(mismatch) = mismatch {
+            mismatches.add(|| {
+                format!(
+                    "offset {}/{} of bin {} of reference sequence #{} points to {mismatch}",
+                    indexed.offset.compressed(),
+                    indexed.offset.uncompressed(),
+                    indexed.bin_id,
+                    indexed.reference_sequence_id + 1
+                )
+            });
+        }
+    }
+    if let Some(first) = mismatches.first {
+        errors.push(format!(
+            "Index points to {} of {sampled} sampled offset(s) without a matching record; it was likely generated for a different version of the file. First detected: {first}.",
+            mismatches.count
+        ));
+    }
+}
+
+/// Checks that the bins and chunks of an index are consistent with the BAM header and file,
+/// and returns the chunk starts to verify against the records.
+fn check_index_structure<I>(
+    index: &Index<I>,
+    header: &sam::Header,
+    file_size: u64,
+    errors: &mut Vec<String>,
+) -> Vec<IndexedOffset>
+where
+    I: reference_sequence::Index,
+{
+    let num_indexed = index.reference_sequences().len();
+    let num_declared = header.reference_sequences().len();
+    if num_indexed != num_declared {
+        errors.push(format!(
+            "Index contains {num_indexed} reference sequence(s), but the BAM header declares {num_declared}."
+        ));
+    }
+
+    let (min_shift, depth) = (index.min_shift(), index.depth());
+    let mut invalid_bins = Occurrences::default();
+    let mut bins_beyond_length = Occurrences::default();
+    let mut invalid_chunks = Occurrences::default();
+    let mut last_offset = 0;
+    let mut offsets = Vec::new();
+    for (i, reference_sequence) in index.reference_sequences().iter().enumerate() {
+        let length = header
+            .reference_sequences()
+            .get_index(i)
+            .map(|(_, reference_sequence)| reference_sequence.length().get() as u64);
+        for (&id, bin) in reference_sequence.bins() {
+            let describe = || format!("bin {id} of reference sequence #{}", i + 1);
+            let region = bin_region(id, min_shift, depth).filter(|_| id < Bin::max_id(depth));
+            match region {
+                None => invalid_bins.add(describe),
+                Some((start, _)) if length.is_some_and(|length| start >= length) => {
+                    bins_beyond_length.add(describe);
+                }
+                Some(_) => {}
+            }
+            for chunk in bin.chunks() {
+                if chunk.start() > chunk.end() {
+                    invalid_chunks.add(describe);
+                }
+                last_offset = last_offset.max(chunk.end().compressed());
+                if let Some(region) = region {
+                    offsets.push(IndexedOffset {
+                        reference_sequence_id: i,
+                        bin_id: id,
+                        region,
+                        offset: chunk.start(),
+                    });
+                }
+            }
+        }
+        if let Some(metadata) = reference_sequence.metadata() {
+            last_offset = last_offset.max(metadata.end_position().compressed());
+        }
+    }
+
+    if let Some(first) = invalid_bins.first {
+        errors.push(format!(
+            "Index contains {} bin(s) with an ID out of range. First detected: {first}.",
+            invalid_bins.count
+        ));
+    }
+    if let Some(first) = bins_beyond_length.first {
+        errors.push(format!(
+            "Index contains {} bin(s) beyond the length of their reference sequence in the BAM header. First detected: {first}.",
+            bins_beyond_length.count
+        ));
+    }
+    if let Some(first) = invalid_chunks.first {
+        errors.push(format!(
+            "Index contains {} chunk(s) ending before they start. First detected in {first}.",
+            invalid_chunks.count
+        ));
+    }
+    if last_offset > file_size {
+        errors.push(format!(
+            "Index refers to offset {last_offset} beyond the end of the file ({file_size} bytes); it may belong to a different or truncated BAM file."
+        ));
+    }
+    // Bins are stored in a hash map, so their order is not stable across runs.
+    offsets.sort_by_key(|indexed| indexed.offset);
+    offsets
+}
+
+/// Cross-validates a `.bai` or `.csi` index against the BAM header and file size.
+fn check_index(path: &Path, index_path: &Path, header: &sam::Header) -> Vec<String> {
+    let mut errors = Vec::new();
+    let file_size = match archive::size(path) {
+        Ok(size) => size,
+        Err(e) => return vec![format!("Failed to read file: {e}")],
+    };
+    let is_csi = index_path.extension().is_some_and(|ext| ext == "csi");
+    let result = if is_csi {
+        csi::fs::read(index_path)
+            .map(|index| check_index_structure(&index, header, file_size, &mut errors))
+    } else {
+        bam::bai::fs::read(index_path)
+            .map(|index| check_index_structure(&index, header, file_size, &mut errors))
+    };
+    match result {
+        // Offsets are only followed into a file that the index structurally fits.
+        Ok(offsets) if errors.is_empty() => check_indexed_offsets(path, &offsets, &mut errors),
+        Ok(_) => {}
+        Err(e) => errors.push(format!(
+            "Failed to read BAM index {}: {e}",
+            index_path.display()
+        )),
+    }
+    errors
+}
+
+/// Empty block that ends every BGZF file (SAM specification, § 4.1.2).
+const BGZF_EOF: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Returns an error if a BAM file does not end with the BGZF EOF block, as `samtools quickcheck`
+/// does. Without it, a file cut at a block boundary parses without errors.
+///
+/// Files that cannot be read at random positions, such as pipes and compressed members of zip
+/// archives, are not checked.
+fn eof_error(path: &Path) -> Option<String> {
+    if archive::is_stream(path) {
+        return None;
+    }
+    let mut section = archive::open_section(path).ok()?;
+    let size = section.size();
+    let ends_with_eof = size >= BGZF_EOF.len() as u64
+        && section
+            .read_at(size - BGZF_EOF.len() as u64, BGZF_EOF.len())
+            .is_ok_and(|end| end == BGZF_EOF);
+    (!ends_with_eof).then(|| {
+        "File does not end with the BGZF EOF marker block (file likely incomplete). The transfer was probably interrupted at a block boundary; transfer it again.".to_string()
+    })
+}
+
+/// Returns the index next to a BAM file, i.e. `x.bam.bai`, `x.bai` or `x.bam.csi`.
+pub fn sibling_index(path: &Path) -> Option<PathBuf> {
+    let with_suffix = |suffix: &str| {
+        let mut index_path = path.as_os_str().to_owned();
+        index_path.push(suffix);
+        PathBuf::from(index_path)
+    };
+    [
+        with_suffix(".bai"),
+        path.with_extension("bai"),
+        with_suffix(".csi"),
+    ]
+    .into_iter()
+    .find(|index_path| index_path.is_file())
+}
+
+pub fn check_bam(
+    path: &Path,
+    species: Option<Species>,
+    unaligned: bool,
+    index_path: Option<&Path>,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    let checks = match (unaligned, index_path) {
+        (true, _) => UNALIGNED_CHECKS,
+        (false, Some(_)) => INDEXED_CHECKS,
+        (false, None) => CHECKS,
+    };
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::Bgzf,
+        checks,
+        |reader| {
+            let mut bam_reader = bam::io::Reader::from(reader);
+            let header = match bam_reader.read_header() {
+                Ok(h) => h,
+                Err(e) => {
+                    return Err(CheckFailure::new(
+                        HEADER_CHECK,
+                        format!("Failed to read BAM header: {e}"),
                     ));
                 }
+            };
+            let mut outcome = check_alignments(
+                &header,
+                bam_reader.records(),
+                species,
+                unaligned,
+                settings,
+                "BAM",
+            )?;
+            if let Some(error) = eof_error(path) {
+                outcome.errors.push(error);
+            }
+            if let Some(mapping) = &settings.read_group_mapping {
+                outcome
+                    .errors
+                    .extend(lab_data::read_group_errors(path, &header, mapping));
+            }
+            if let Some(index_path) = index_path {
+                outcome
+                    .errors
+                    .extend(check_index(path, index_path, &header));
+            }
+            Ok(outcome)
+        },
+    )
+}
+
+/// Checks the header and the records of an alignment file, independent of its format.
+///
+/// If `unaligned` is set, records that are mapped or carry any alignment information are errors.
+pub fn check_alignments<R, I>(
+    header: &sam::Header,
+    records: I,
+    species: Option<Species>,
+    unaligned: bool,
+    settings: &CheckSettings,
+    format: &str,
+) -> Result<CheckOutcome, CheckFailure>
+where
+    R: sam::alignment::Record,
+    I: Iterator<Item = io::Result<R>>,
+{
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    if !header.reference_sequences().is_empty()
+        || !header.read_groups().is_empty()
+        || (header.programs().roots().count() != 0)
+        || !header.comments().is_empty()
+    {
+        warnings.push(format!(
+            "Detected a header in {format} file, ensure it contains no private information!"
+        ));
+    }
+
+    let mut skipped_checks = Vec::new();
+    match species {
+        Some(species) => {
+            let reference_outcome = reference::check_reference_sequences(header, species);
+            errors.extend(reference_outcome.errors);
+            warnings.extend(reference_outcome.warnings);
+            skipped_checks.extend(reference_outcome.skipped_checks);
+        }
+        None => skipped_checks.push(reference::PLAUSIBILITY_CHECK),
+    }
+    if let Some(md5_reference) = &settings.md5_reference {
+        errors.extend(md5_reference.errors(header));
+    }
+
+    let mut num_records = 0;
+    let mut secondary_alignment_count: u64 = 0;
+    let mut duplicate_count: u64 = 0;
+    let mut mapped_count: u64 = 0;
+    let mut first_secondary_warning_details: Option<(u64, String)> = None;
+    let mut hard_clip_count: u64 = 0;
+    let mut first_hard_clip_warning_details: Option<(u64, String)> = None;
+    let mut mapped = Violations::default();
+    let mut with_coordinates = Violations::default();
+    let mut with_cigar = Violations::default();
+    let mut without_quality = Violations::default();
+    let mut without_sequence = Violations::default();
+    let mut cigar_mismatch = Violations::default();
+    // Bases consumed by the CIGAR and length of the sequence of the first mismatching record.
+    let mut first_cigar_mismatch_lengths: Option<(usize, usize)> = None;
+    let mut sort_order = SortOrderCheck::new(header);
+    let mut tags = settings.validate_tags.then(TagValidation::default);
+    let mut flag_checks = FlagChecks::default();
+    let mut read_groups = ReadGroupCheck::default();
+    let mut bounds = BoundsCheck::default();
+
+    for (i, result) in records.enumerate() {
+        let record = match result {
+            Ok(rec) => rec,
+            Err(e) => {
+                return Err(CheckFailure::new(
+                    RECORDS_CHECK,
+                    format!("Failed to parse record #{}: {}", i + 1, e),
+                ));
+            }
+        };
+        num_records += 1;
+
+        let flags = record.flags().map_err(|e| {
+            CheckFailure::new(
+                RECORDS_CHECK,
+                format!("Failed to parse flags of record #{num_records}: {e}"),
+            )
+        })?;
+
+        flag_checks.add(header, num_records, &record, flags);
+        read_groups.add(header, num_records, &record);
+        bounds.add(header, num_records, &record, flags);
+        if let Some(sort_order) = &mut sort_order {
+            sort_order.add(header, num_records, &record);
+        }
+        if let Some(tags) = &mut tags {
+            tags.add(num_records, &record);
+        }
+
+        if unaligned {
+            let read_name = || record.name().map(|n| n.to_string()).unwrap_or_default();
+            if !flags.is_unmapped() {
+                mapped.add(num_records, read_name);
+            }
+            if record.reference_sequence_id(header).is_some()
+                || record.alignment_start().is_some()
+                || record.mate_reference_sequence_id(header).is_some()
+                || record.mate_alignment_start().is_some()
+            {
+                with_coordinates.add(num_records, read_name);
+            }
+            if !record.cigar().is_empty() {
+                with_cigar.add(num_records, read_name);
             }
         }
 
-        if num_records == 0 {
-            return Ok(CheckOutcome {
-                errors: vec!["File is empty. Expected at least one record.".to_string()],
-                ..Default::default()
+        if flags.is_duplicate() {
+            duplicate_count += 1;
+        }
+        if !flags.is_unmapped() {
+            mapped_count += 1;
+        }
+
+        // Malformed CIGAR operations are reported by the parser.
+        let cigar = record.cigar();
+        let sequence_len = record.sequence().len();
+        if !cigar.is_empty()
+            && sequence_len > 0
+            && let Ok(read_length) = cigar.read_length()
+            && read_length != sequence_len
+        {
+            cigar_mismatch.add(num_records, || {
+                record.name().map(|n| n.to_string()).unwrap_or_default()
             });
+            first_cigar_mismatch_lengths.get_or_insert((read_length, sequence_len));
         }
 
-        if let Some((rec_num, read_name)) = first_secondary_warning_details {
-            warnings.push(format!(
-                "File contains {secondary_alignment_count} secondary alignment(s). First detected at record #{rec_num} ('{read_name}')."
-            ));
+        // Secondary and supplementary records may omit the sequence and qualities of the read.
+        if !flags.is_secondary() && !flags.is_supplementary() {
+            let read_name = || record.name().map(|n| n.to_string()).unwrap_or_default();
+            if record.sequence().is_empty() {
+                without_sequence.add(num_records, read_name);
+            } else if record.quality_scores().is_empty() {
+                without_quality.add(num_records, read_name);
+            }
         }
 
-        if let Some((rec_num, read_name)) = first_hard_clip_warning_details {
-            warnings.push(format!(
-                "File contains {hard_clip_count} primary alignment(s) with hard-clipped bases. First detected at record #{rec_num} ('{read_name}')."
-            ));
+        if flags.is_secondary() {
+            secondary_alignment_count += 1;
+            if first_secondary_warning_details.is_none() {
+                first_secondary_warning_details = Some((
+                    num_records,
+                    record.name().map(|n| n.to_string()).unwrap_or_default(),
+                ));
+            }
         }
 
-        Ok(CheckOutcome {
-            stats: Some(Stats {
-                num_records,
-                total_read_length: None,
-            }),
-            errors: vec![],
-            warnings,
-        })
+        if !flags.is_secondary()
+            && record
+                .cigar()
+                .iter()
+                .any(|op| op.is_ok_and(|op| op.kind() == Kind::HardClip))
+        {
+            hard_clip_count += 1;
+            if first_hard_clip_warning_details.is_none() {
+                first_hard_clip_warning_details = Some((
+                    num_records,
+                    record.name().map(|n| n.to_string()).unwrap_or_default(),
+                ));
+            }
+        }
+    }
+
+    if num_records == 0 {
+        return Ok(CheckOutcome {
+            errors: vec!["File is empty. Expected at least one record.".to_string()],
+            skipped_checks,
+            ..Default::default()
+        });
+    }
+
+    mapped.report("mapped record(s)", &mut errors);
+    with_coordinates.report("record(s) with reference coordinates", &mut errors);
+    with_cigar.report("record(s) with CIGAR operations", &mut errors);
+    flag_checks.report(&mut errors);
+    read_groups.report(header, &mut errors, &mut warnings);
+    bounds.report(&mut errors);
+    if let Some((rec_num, read_name)) = without_sequence.first {
+        errors.push(format!(
+            "File contains {} primary record(s) without a sequence (SEQ '*'). First detected at record #{rec_num} ('{read_name}'). Only secondary and supplementary records may omit the sequence; the bases of the reads cannot be recovered from the file.",
+            without_sequence.count
+        ));
+    }
+    if let (Some((rec_num, read_name)), Some((read_length, sequence_len))) =
+        (cigar_mismatch.first, first_cigar_mismatch_lengths)
+    {
+        errors.push(format!(
+            "File contains {} record(s) whose CIGAR does not match the length of their sequence. First detected at record #{rec_num} ('{read_name}'): the CIGAR consumes {read_length} base(s) of the read, but SEQ has {sequence_len}. The file was likely corrupted or edited by hand.",
+            cigar_mismatch.count
+        ));
+    }
+    if let Some((rec_num, read_name)) = without_quality.first {
+        errors.push(format!(
+            "File contains {} primary record(s) without base quality scores (QUAL '*'). First detected at record #{rec_num} ('{read_name}'). The original base qualities are required; the file was likely written with qualities omitted, e.g. `--omit-quals`.",
+            without_quality.count
+        ));
+    }
+    errors.extend(sort_order.and_then(|sort_order| sort_order.error));
+    if let Some(tags) = tags {
+        tags.report(&mut errors, &mut warnings);
+    }
+
+    if let Some((rec_num, read_name)) = first_secondary_warning_details {
+        warnings.push(format!(
+            "File contains {secondary_alignment_count} secondary alignment(s). First detected at record #{rec_num} ('{read_name}')."
+        ));
+    }
+
+    if let Some((rec_num, read_name)) = first_hard_clip_warning_details {
+        warnings.push(format!(
+            "File contains {hard_clip_count} primary alignment(s) with hard-clipped bases. First detected at record #{rec_num} ('{read_name}')."
+        ));
+    }
+
+    Ok(CheckOutcome {
+        stats: Some(Stats {
+            num_records,
+            total_read_length: None,
+            modal_read_length: None,
+            min_read_length: None,
+            max_read_length: None,
+            gvcf: None,
+            adapter_reads: None,
+            poly_g_reads: None,
+            umi_reads: None,
+            duplicate_records: Some(duplicate_count),
+            mapped_records: Some(mapped_count),
+            bases: None,
+            quality: None,
+        }),
+        errors,
+        warnings,
+        skipped_checks,
     })
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct BamCheckJob {
     pub path: PathBuf,
+    pub species: Option<Species>,
+    /// Whether the file must be an unaligned BAM, given via `--ubam`.
+    pub unaligned: bool,
+    /// Sibling `.bai` or `.csi` index to cross-validate, given via `--check-index`.
+    pub index_path: Option<PathBuf>,
     pub size: u64,
 }
@@ -0,0 +1,252 @@
+use crate::checker::{FileReport, Stats};
+use crate::checks::common::{
+    CheckFailure, CheckOutcome, CheckSettings, Decompression, Occurrences, check_file,
+};
+use crate::checks::dependencies::Check;
+use indicatif::ProgressBar;
+use noodles::fasta::fai;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+const RECORDS_CHECK: &str = "bed.records";
+const SORT_ORDER_CHECK: &str = "bed.sort_order";
+const REFERENCE_NAMES_CHECK: &str = "bed.reference_names";
+
+/// Checks of BED files.
+pub const CHECKS: &[Check] = &[
+    Check::new(RECORDS_CHECK, &[]),
+    Check::new(SORT_ORDER_CHECK, &[RECORDS_CHECK]),
+    Check::new(REFERENCE_NAMES_CHECK, &[RECORDS_CHECK]),
+];
+
+/// Reads the sequence names of a reference, given either as a `.fai` index or as a FASTA file.
+/// For FASTA files, an index next to the file is used if present.
+fn read_reference_names(path: &Path) -> Result<HashSet<String>, String> {
+    let mut index_path = path.as_os_str().to_owned();
+    index_path.push(".fai");
+    let index_path = PathBuf::from(index_path);
+    let index_path = if path.extension().is_some_and(|ext| ext == "fai") {
+        Some(path)
+    } else if index_path.is_file() {
+        Some(index_path.as_path())
+    } else {
+        None
+    };
+
+    if let Some(index_path) = index_path {
+        let index = fai::fs::read(index_path).map_err(|e| e.to_string())?;
+        return Ok(index
+            .as_ref()
+            .iter()
+            .map(|record| record.name().to_string())
+            .collect());
+    }
+
+    let (reader, _) = niffler::from_path(path).map_err(|e| e.to_string())?;
+    let mut names = HashSet::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if let Some(definition) = line.strip_prefix('>') {
+            let name = definition.split_whitespace().next().unwrap_or_default();
+            names.insert(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+fn is_header_line(line: &str) -> bool {
+    line.is_empty()
+        || line.starts_with('#')
+        || line.starts_with("track")
+        || line.starts_with("browser")
+}
+
+pub fn check_bed(
+    path: &Path,
+    reference: Option<&Path>,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::Auto,
+        CHECKS,
+        |reader| {
+            let mut errors = Vec::new();
+            let mut warnings = Vec::new();
+            let mut skipped_checks = Vec::new();
+
+            let reference_names = match reference.map(|r| (r, read_reference_names(r))) {
+                Some((_, Ok(names))) => Some(names),
+                Some((reference, Err(e))) => {
+                    errors.push(format!(
+                        "Failed to read reference {}: {e}",
+                        reference.display()
+                    ));
+                    skipped_checks.push(REFERENCE_NAMES_CHECK);
+                    None
+                }
+                None => {
+                    skipped_checks.push(REFERENCE_NAMES_CHECK);
+                    None
+                }
+            };
+
+            let mut num_records: u64 = 0;
+            let mut total_length: u64 = 0;
+            let mut num_columns: Option<usize> = None;
+            let mut too_few_columns = Occurrences::default();
+            let mut inconsistent_columns = Occurrences::default();
+            let mut invalid_coordinates = Occurrences::default();
+            let mut start_not_before_end = Occurrences::default();
+            let mut unsorted = Occurrences::default();
+            let mut unknown_chromosomes = Occurrences::default();
+
+            let mut seen_chromosomes: HashSet<String> = HashSet::new();
+            let mut previous: Option<(String, u64)> = None;
+
+            for (i, line) in BufReader::new(reader).lines().enumerate() {
+                let line_number = i + 1;
+                let line = line.map_err(|e| {
+                    CheckFailure::new(
+                        RECORDS_CHECK,
+                        format!("Failed to read line {line_number}: {e}"),
+                    )
+                })?;
+                let line = line.strip_suffix('\r').unwrap_or(&line);
+                if is_header_line(line) {
+                    continue;
+                }
+                num_records += 1;
+
+                let columns: Vec<&str> = line.split('\t').collect();
+                if columns.len() < 3 {
+                    too_few_columns.add(|| format!("line {line_number}"));
+                    continue;
+                }
+                match num_columns {
+                    None => num_columns = Some(columns.len()),
+                    Some(n) if n != columns.len() => inconsistent_columns
+                        .add(|| format!("line {line_number} ({} instead of {n})", columns.len())),
+                    Some(_) => {}
+                }
+
+                let chromosome = columns[0];
+                let (Ok(start), Ok(end)) = (columns[1].parse::<u64>(), columns[2].parse::<u64>())
+                else {
+                    invalid_coordinates
+                        .add(|| format!("line {line_number} ('{}', '{}')", columns[1], columns[2]));
+                    continue;
+                };
+                if start >= end {
+                    start_not_before_end.add(|| format!("line {line_number} ({start}-{end})"));
+                } else {
+                    total_length += end - start;
+                }
+
+                if let Some(names) = &reference_names
+                    && !names.contains(chromosome)
+                {
+                    unknown_chromosomes.add(|| format!("line {line_number} ('{chromosome}')"));
+                }
+
+                match &mut previous {
+                    Some((previous_chromosome, previous_start))
+                        if previous_chromosome == chromosome =>
+                    {
+                        if start < *previous_start {
+                            unsorted.add(|| format!("line {line_number}"));
+                        }
+                        *previous_start = start;
+                    }
+                    _ => {
+                        if !seen_chromosomes.insert(chromosome.to_string()) {
+                            unsorted.add(|| format!("line {line_number}"));
+                        }
+                        previous = Some((chromosome.to_string(), start));
+                    }
+                }
+            }
+
+            if num_records == 0 {
+                return Ok(CheckOutcome {
+                    errors: vec!["File is empty. Expected at least one record.".to_string()],
+                    skipped_checks,
+                    ..Default::default()
+                });
+            }
+
+            if let Some(first) = too_few_columns.first {
+                errors.push(format!(
+                    "File contains {} record(s) with fewer than 3 columns. First detected at {first}.",
+                    too_few_columns.count
+                ));
+            }
+            if let Some(first) = inconsistent_columns.first {
+                errors.push(format!(
+                    "File contains {} record(s) with an inconsistent number of columns. First detected at {first}.",
+                    inconsistent_columns.count
+                ));
+            }
+            if let Some(first) = invalid_coordinates.first {
+                errors.push(format!(
+                    "File contains {} record(s) with invalid coordinates. First detected at {first}.",
+                    invalid_coordinates.count
+                ));
+            }
+            if let Some(first) = start_not_before_end.first {
+                errors.push(format!(
+                    "File contains {} record(s) whose start is not less than their end. First detected at {first}.",
+                    start_not_before_end.count
+                ));
+            }
+            if let Some(first) = unknown_chromosomes.first {
+                errors.push(format!(
+                    "File contains {} record(s) on chromosomes not present in the reference. First detected at {first}.",
+                    unknown_chromosomes.count
+                ));
+            }
+            if let Some(first) = unsorted.first {
+                warnings.push(format!(
+                    "File is not sorted by chromosome and start position ({} record(s) out of order). First detected at {first}.",
+                    unsorted.count
+                ));
+            }
+
+            Ok(CheckOutcome {
+                stats: Some(Stats {
+                    num_records,
+                    total_read_length: Some(total_length),
+                    modal_read_length: None,
+                    min_read_length: None,
+                    max_read_length: None,
+                    gvcf: None,
+                    adapter_reads: None,
+                    poly_g_reads: None,
+                    umi_reads: None,
+                    duplicate_records: None,
+                    mapped_records: None,
+                    bases: None,
+                    quality: None,
+                }),
+                errors,
+                warnings,
+                skipped_checks,
+            })
+        },
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BedCheckJob {
+    pub path: PathBuf,
+    /// FASTA file or `.fai` index whose sequence names the chromosomes must match.
+    pub reference: Option<PathBuf>,
+    pub size: u64,
+}
@@ -1,90 +1,415 @@
+use crate::archive;
 use crate::checker::{FileReport, Stats};
+use crate::checks::dependencies::{self, Check, READ_CHECK};
+use crate::checks::fastq::{AdapterScreening, PolyGScreening};
+use crate::checks::reference::Md5Reference;
+use crate::lab_data::ReadGroupMapping;
+use crate::line_format::ParseLeniency;
+use crate::md5_sidecar;
 use crate::progress::DualProgressReader;
-use crate::sha256::SharedHashingReader;
+use crate::sha256::{FileHasher, SharedHashingReader};
+use crate::systemd::Heartbeat;
+use crate::timing::{ReadTimers, TimedReader, Timings};
+use crate::umi::UmiCheck;
 use anyhow::Context;
 use indicatif::ProgressBar;
-use sha2::{Digest, Sha256};
-use std::fs;
-use std::io::{BufReader, Read};
+use noodles::bgzf;
+use serde::Serialize;
+use std::io::{self, BufRead, BufReader, Read};
+use std::num::NonZeroUsize;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
 #[derive(Debug, Default)]
 pub struct CheckOutcome {
     pub stats: Option<Stats>,
     pub errors: Vec<String>,
     pub warnings: Vec<String>,
+    /// Names of checks that were not performed on the file, e.g. because they were disabled
+    /// or are not applicable.
+    pub skipped_checks: Vec<&'static str>,
+}
+
+/// A failure that aborted checking a file.
+#[derive(Debug)]
+pub struct CheckFailure {
+    /// The check that failed.
+    pub check: &'static str,
+    pub message: String,
+}
+
+impl CheckFailure {
+    pub fn new(check: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            check,
+            message: message.into(),
+        }
+    }
+}
+
+/// Counts problems of one kind and remembers the first occurrence.
+#[derive(Debug, Default)]
+pub struct Occurrences {
+    pub count: u64,
+    pub first: Option<String>,
+}
+
+impl Occurrences {
+    pub fn add(&mut self, detail: impl FnOnce() -> String) {
+        self.count += 1;
+        if self.first.is_none() {
+            self.first = Some(detail());
+        }
+    }
+}
+
+/// Settings of a run that apply to the checks of every file, derived from the
+/// [`RunOptions`](crate::checker::RunOptions) of the run.
+#[derive(Debug, Clone, Default)]
+pub struct CheckSettings {
+    /// Compute MD5 digests of files with a sidecar to verify them, via `--verify-md5`.
+    pub verify_md5: bool,
+    /// Size of the chunks whose digests are reported, via `--chunk-size`.
+    pub chunk_size: Option<u64>,
+    /// Report Merkle-tree digests, with the roots of the subtrees of the given height, via
+    /// `--merkle`.
+    pub merkle: Option<Option<u32>>,
+    /// Inflate BGZF files with this many worker threads per file, via
+    /// `--inflate-backend parallel-bgzf`.
+    pub parallel_bgzf: Option<NonZeroUsize>,
+    pub parse_leniency: ParseLeniency,
+    /// Only allow `A`, `C`, `G`, `T` and `N` in FASTQ sequences, via `--strict-bases`.
+    pub strict_bases: bool,
+    /// Require FASTQ read names in Casava 1.8+ format, via `--strict-read-names`.
+    pub strict_read_names: bool,
+    pub adapter_screening: Option<AdapterScreening>,
+    pub poly_g_screening: Option<PolyGScreening>,
+    pub umi: Option<UmiCheck>,
+    /// Memory budget per file of the duplicate read-name check, via `--duplicate-read-names`.
+    pub duplicate_names_memory: Option<u64>,
+    /// Validate the tags of BAM and SAM records, via `--validate-tags`.
+    pub validate_tags: bool,
+    /// Reference that the sequences of BAM and SAM headers are verified against, via
+    /// `--bam-reference`. Shared by all files, so that its digests are computed once.
+    pub md5_reference: Option<Arc<Md5Reference>>,
+    pub read_group_mapping: Option<Arc<ReadGroupMapping>>,
+    /// Liveness signal for the systemd watchdog, beaten by every read.
+    pub heartbeat: Heartbeat,
+}
+
+/// How the bytes of a file are decompressed before being handed to the check logic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Decompression {
+    /// Pass the bytes through as-is.
+    None,
+    /// Detect the compression format from the magic bytes.
+    Auto,
+    /// Decompress as BGZF (e.g. BAM).
+    Bgzf,
 }
 
-type ReaderAndHasher = (Box<dyn Read>, Arc<Mutex<Sha256>>);
+/// Compression format of a file, as detected when setting up its reader.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Gzip,
+    Bgzf,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl From<niffler::compression::Format> for Compression {
+    fn from(format: niffler::compression::Format) -> Self {
+        match format {
+            niffler::compression::Format::Gzip => Compression::Gzip,
+            niffler::compression::Format::Bzip => Compression::Bzip2,
+            niffler::compression::Format::Lzma => Compression::Xz,
+            niffler::compression::Format::Zstd => Compression::Zstd,
+            niffler::compression::Format::No => Compression::None,
+        }
+    }
+}
+
+/// How gzip and BGZF streams are inflated, given via `--inflate-backend`.
+///
+/// The zlib implementation used by flate2 is chosen at build time: miniz_oxide by default, or
+/// the faster zlib-rs with the `zlib-rs` feature.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum InflateBackend {
+    /// Inflate with flate2 on the thread checking the file.
+    #[default]
+    Flate2,
+    /// Inflate the blocks of BGZF files on worker threads ahead of the check, e.g. of BAM files
+    /// and bgzip-compressed FASTQ files. Other gzip files are inflated with flate2.
+    ParallelBgzf,
+}
+
+/// zlib implementation flate2 was built with.
+pub const ZLIB_IMPLEMENTATION: &str = if cfg!(feature = "zlib-rs") {
+    "zlib-rs"
+} else {
+    "miniz_oxide"
+};
+
+/// Default number of worker threads per file of [`InflateBackend::ParallelBgzf`].
+pub const DEFAULT_INFLATE_THREADS: NonZeroUsize = NonZeroUsize::new(4).unwrap();
+
+/// Returns whether `header` starts with a gzip header with the `BC` extra subfield of BGZF.
+fn is_bgzf_header(header: &[u8]) -> bool {
+    header.len() >= 16
+        && header[..2] == [0x1f, 0x8b]
+        && header[3] & 4 != 0
+        && header[12..14] == *b"BC"
+}
+
+/// Tracks whether the compressed stream of a file ended inside a block, e.g. of a file that was
+/// only partially transferred, so that this is reported instead of the parse error it causes.
+#[derive(Debug, Clone, Default)]
+pub struct Truncation {
+    /// Number of compressed bytes read from the file.
+    compressed_bytes: Arc<AtomicU64>,
+    truncated: Arc<AtomicBool>,
+}
+
+impl Truncation {
+    /// Returns an error if the compressed stream was truncated.
+    pub fn error(&self) -> Option<String> {
+        self.truncated.load(Ordering::Relaxed).then(|| {
+            format!(
+                "Compressed stream truncated at byte {} (file likely incomplete). The file ends inside a compressed block; transfer it again.",
+                self.compressed_bytes.load(Ordering::Relaxed)
+            )
+        })
+    }
+}
+
+/// Counts the compressed bytes read by the decompressor.
+struct CountingReader<R: Read> {
+    inner: R,
+    truncation: Truncation,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        self.truncation
+            .compressed_bytes
+            .fetch_add(bytes_read as u64, Ordering::Relaxed);
+        Ok(bytes_read)
+    }
+}
+
+/// Records an unexpected end of the input reported by the decompressor. Such errors of the
+/// parsers on top of it do not pass through this reader.
+struct TruncationReader<R: Read> {
+    inner: R,
+    truncation: Truncation,
+}
+
+impl<R: Read> Read for TruncationReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf).inspect_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                self.truncation.truncated.store(true, Ordering::Relaxed);
+            }
+        })
+    }
+}
+
+type ReaderAndHasher = (
+    Box<dyn Read>,
+    Arc<Mutex<FileHasher>>,
+    ReadTimers,
+    Option<Compression>,
+    Truncation,
+);
 
 pub fn setup_file_reader(
     path: &Path,
+    settings: &CheckSettings,
     file_pb: &ProgressBar,
     global_pb: &ProgressBar,
-    decompress: bool,
+    decompression: Decompression,
 ) -> anyhow::Result<ReaderAndHasher> {
     file_pb.set_message(format!(
         "~ CHECK {}",
         path.file_name().unwrap_or_default().to_string_lossy()
     ));
 
-    let file = fs::File::open(path)
+    let file = archive::open(path)
         .with_context(|| format!("Failed to open file for reading: {}", path.display()))?;
 
-    let hasher = Arc::new(Mutex::new(Sha256::new()));
-    let hashing_reader = SharedHashingReader::new(BufReader::new(file), hasher.clone());
-    let progress_reader =
-        DualProgressReader::new(hashing_reader, file_pb.clone(), global_pb.clone());
-
-    let reader: Box<dyn Read> = if decompress {
-        let (decompressed_reader, _) = niffler::get_reader(Box::new(progress_reader))
-            .with_context(|| format!("Failed to decompress file: {}", path.display()))?;
-        decompressed_reader
-    } else {
-        Box::new(progress_reader)
+    let timers = ReadTimers::default();
+    let hasher = Arc::new(Mutex::new(FileHasher::new(
+        settings.verify_md5 && md5_sidecar::exists(path),
+        settings.chunk_size,
+        settings.merkle,
+    )));
+    let hashing_reader = SharedHashingReader::new(
+        BufReader::new(TimedReader::new(file, timers.read.clone())),
+        hasher.clone(),
+        timers.hashing.clone(),
+    );
+    let progress_reader = DualProgressReader::new(
+        hashing_reader,
+        file_pb.clone(),
+        global_pb.clone(),
+        settings.heartbeat.clone(),
+    );
+    let truncation = Truncation::default();
+    let counting_reader = CountingReader {
+        inner: progress_reader,
+        truncation: truncation.clone(),
     };
 
-    Ok((reader, hasher))
+    let parallel_bgzf = settings.parallel_bgzf;
+    let (reader, compression): (Box<dyn Read>, _) = match decompression {
+        Decompression::None => (Box::new(counting_reader), None),
+        Decompression::Auto => {
+            let mut buffered = BufReader::new(counting_reader);
+            let header = buffered
+                .fill_buf()
+                .with_context(|| format!("Failed to read file: {}", path.display()))?;
+            match parallel_bgzf {
+                // Reported as gzip like BGZF files inflated with flate2, so that the report
+                // does not depend on the backend.
+                Some(workers) if is_bgzf_header(header) => (
+                    Box::new(bgzf::io::MultithreadedReader::with_worker_count(
+                        workers, buffered,
+                    )),
+                    Some(Compression::Gzip),
+                ),
+                _ => {
+                    let (decompressed_reader, format) = niffler::get_reader(Box::new(buffered))
+                        .with_context(|| {
+                            format!("Failed to decompress file: {}", path.display())
+                        })?;
+                    (decompressed_reader, Some(format.into()))
+                }
+            }
+        }
+        Decompression::Bgzf => match parallel_bgzf {
+            Some(workers) => (
+                Box::new(bgzf::io::MultithreadedReader::with_worker_count(
+                    workers,
+                    counting_reader,
+                )),
+                Some(Compression::Bgzf),
+            ),
+            None => (
+                Box::new(bgzf::io::Reader::new(counting_reader)),
+                Some(Compression::Bgzf),
+            ),
+        },
+    };
+    let reader = TruncationReader {
+        inner: reader,
+        truncation: truncation.clone(),
+    };
+
+    Ok((
+        Box::new(TimedReader::new(reader, timers.total.clone())),
+        hasher,
+        timers,
+        compression,
+        truncation,
+    ))
 }
 
 pub fn check_file<F>(
     path: &Path,
+    settings: &CheckSettings,
     file_pb: &ProgressBar,
     global_pb: &ProgressBar,
-    decompress: bool,
+    decompression: Decompression,
+    checks: &[Check],
     logic: F,
 ) -> FileReport
 where
-    F: FnOnce(&mut dyn Read) -> Result<CheckOutcome, String>,
+    F: FnOnce(&mut dyn Read) -> Result<CheckOutcome, CheckFailure>,
 {
-    let (mut reader, hasher) = match setup_file_reader(path, file_pb, global_pb, decompress) {
-        Ok(setup) => setup,
-        Err(e) => return FileReport::new_with_error(path, e.to_string()),
-    };
+    let started = Instant::now();
+    let (mut reader, hasher, timers, compression, truncation) =
+        match setup_file_reader(path, settings, file_pb, global_pb, decompression) {
+            Ok(setup) => setup,
+            Err(e) => {
+                return FileReport::new_with_error(path, format!("{e:#}"))
+                    .with_not_evaluated(dependencies::propagate_failures(checks, &[READ_CHECK]));
+            }
+        };
 
-    let outcome = match logic(&mut reader) {
+    let mut outcome = match logic(&mut reader) {
         Ok(outcome) => outcome,
-        Err(error_msg) => {
-            return FileReport::new_with_error(path, error_msg);
+        // The parse error of a truncated stream is only a consequence of the truncation.
+        Err(_) if let Some(error) = truncation.error() => {
+            return FileReport::new_with_error(path, error)
+                .with_not_evaluated(dependencies::propagate_failures(checks, &[READ_CHECK]))
+                .with_compression(compression)
+                .with_timings(Timings::new(&timers, started));
+        }
+        Err(failure) => {
+            return FileReport::new_with_error(path, failure.message)
+                .with_not_evaluated(dependencies::propagate_failures(checks, &[failure.check]))
+                .with_compression(compression)
+                .with_timings(Timings::new(&timers, started));
         }
     };
+    dependencies::propagate_skips(checks, &mut outcome.skipped_checks);
+    if let Some(error) = truncation.error() {
+        outcome.errors.insert(0, error);
+    }
 
     // Ensure the reader is fully consumed, such that the hasher can finalize
     drop(reader);
+    let timings = Timings::new(&timers, started);
 
-    let checksum = match Arc::try_unwrap(hasher) {
-        Ok(mutex) => {
-            let final_hasher = mutex.into_inner().unwrap();
-            Some(format!("{:x}", final_hasher.finalize()))
-        }
+    let digests = match Arc::try_unwrap(hasher) {
+        Ok(mutex) => mutex.into_inner().unwrap().finalize(),
         Err(_) => {
-            let mut final_report = FileReport::new(path, outcome.stats, vec![], outcome.warnings);
+            let mut final_report = FileReport::new(path, outcome.stats, vec![], outcome.warnings)
+                .with_skipped_checks(outcome.skipped_checks)
+                .with_compression(compression);
             final_report
                 .errors
                 .push("Failed to finalize checksum: hasher is still in use.".to_string());
-            return final_report;
+            return final_report.with_timings(timings);
         }
     };
 
-    FileReport::new(path, outcome.stats, outcome.errors, outcome.warnings).with_sha256(checksum)
+    if let Some(md5) = &digests.md5
+        && let Some(error) = md5_sidecar::verify(path, md5)
+    {
+        outcome.errors.push(error);
+    }
+
+    FileReport::new(path, outcome.stats, outcome.errors, outcome.warnings)
+        .with_digests(Some(digests))
+        .with_skipped_checks(outcome.skipped_checks)
+        .with_compression(compression)
+        .with_timings(timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    #[test]
+    fn test_is_bgzf_header() -> anyhow::Result<()> {
+        let mut writer = bgzf::io::Writer::new(Vec::new());
+        writer.write_all(b"@read1\nACGT\n+\nIIII\n")?;
+        assert!(is_bgzf_header(&writer.finish()?));
+
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"@read1\nACGT\n+\nIIII\n")?;
+        assert!(!is_bgzf_header(&encoder.finish()?));
+        assert!(!is_bgzf_header(&[0x1f, 0x8b]));
+        Ok(())
+    }
 }
@@ -0,0 +1,115 @@
+//! Dependencies between the checks of a file type.
+//!
+//! Each file type declares its checks in dependency order. If a check fails or is skipped,
+//! all checks depending on it, directly or transitively, are not evaluated or skipped as well.
+
+use serde::Serialize;
+
+/// Implicit first check of every file type: opening and reading the file.
+pub const READ_CHECK: &str = "file.read";
+
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub depends_on: &'static [&'static str],
+}
+
+impl Check {
+    pub const fn new(name: &'static str, depends_on: &'static [&'static str]) -> Self {
+        Self { name, depends_on }
+    }
+}
+
+/// A check that was not evaluated because a check it depends on failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NotEvaluated {
+    pub check: &'static str,
+    /// The failed check that caused this one to not be evaluated.
+    pub because: &'static str,
+}
+
+/// Returns the failed root check that `check` depends on, if any.
+fn affected_by(check: &Check, roots: &[(&'static str, &'static str)]) -> Option<&'static str> {
+    std::iter::once(READ_CHECK)
+        .chain(check.depends_on.iter().copied())
+        .find_map(|dependency| {
+            roots
+                .iter()
+                .find(|(name, _)| *name == dependency)
+                .map(|&(_, root)| root)
+        })
+}
+
+/// Propagates `failed` checks to all checks depending on them.
+///
+/// Checks that failed themselves are not listed.
+pub fn propagate_failures(checks: &[Check], failed: &[&'static str]) -> Vec<NotEvaluated> {
+    let mut affected: Vec<(&'static str, &'static str)> =
+        failed.iter().map(|&name| (name, name)).collect();
+    let mut not_evaluated = Vec::new();
+    for check in checks {
+        if failed.contains(&check.name) {
+            continue;
+        }
+        if let Some(because) = affected_by(check, &affected) {
+            affected.push((check.name, because));
+            not_evaluated.push(NotEvaluated {
+                check: check.name,
+                because,
+            });
+        }
+    }
+    not_evaluated
+}
+
+/// Extends `skipped` with all checks depending on a skipped check.
+pub fn propagate_skips(checks: &[Check], skipped: &mut Vec<&'static str>) {
+    for check in checks {
+        if !skipped.contains(&check.name)
+            && check.depends_on.iter().any(|dep| skipped.contains(dep))
+        {
+            skipped.push(check.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECKS: &[Check] = &[
+        Check::new("header", &[]),
+        Check::new("records", &["header"]),
+        Check::new("thresholds", &["records"]),
+        Check::new("index", &[]),
+        Check::new("index_contents", &["index", "records"]),
+    ];
+
+    #[test]
+    fn test_failures_propagate_transitively() {
+        let not_evaluated = propagate_failures(CHECKS, &["header"]);
+        let names: Vec<_> = not_evaluated.iter().map(|n| (n.check, n.because)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("records", "header"),
+                ("thresholds", "header"),
+                ("index_contents", "header"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_failure_affects_all_checks() {
+        let not_evaluated = propagate_failures(CHECKS, &[READ_CHECK]);
+        assert_eq!(not_evaluated.len(), CHECKS.len());
+        assert!(not_evaluated.iter().all(|n| n.because == READ_CHECK));
+    }
+
+    #[test]
+    fn test_skips_propagate() {
+        let mut skipped = vec!["index"];
+        propagate_skips(CHECKS, &mut skipped);
+        assert_eq!(skipped, vec!["index", "index_contents"]);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::checker::{FileReport, Stats};
+use crate::checks::common::{
+    CheckFailure, CheckOutcome, CheckSettings, Decompression, Occurrences, check_file,
+};
+use crate::checks::dependencies::Check;
+use indicatif::ProgressBar;
+use noodles::fasta::fai;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+const RECORDS_CHECK: &str = "fasta.records";
+const INDEX_CHECK: &str = "fasta.index_cross_check";
+
+/// Checks of FASTA files.
+pub const CHECKS: &[Check] = &[
+    Check::new(RECORDS_CHECK, &[]),
+    Check::new(INDEX_CHECK, &[RECORDS_CHECK]),
+];
+
+/// Layout of a sequence in the file, i.e. what its `.fai` entry must look like.
+#[derive(Debug)]
+struct SequenceLayout {
+    name: String,
+    length: u64,
+    offset: u64,
+    line_bases: u64,
+    line_width: u64,
+    /// Whether all lines except the last one have the same length, as required for indexing.
+    uniform_lines: bool,
+}
+
+impl SequenceLayout {
+    fn new(name: String, offset: u64) -> Self {
+        Self {
+            name,
+            length: 0,
+            offset,
+            line_bases: 0,
+            line_width: 0,
+            uniform_lines: true,
+        }
+    }
+}
+
+fn is_valid_residue(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'*' || b == b'-'
+}
+
+/// Reads all sequences, validating the record structure along the way.
+fn read_layouts<R: BufRead>(
+    mut reader: R,
+    errors: &mut Vec<String>,
+) -> Result<Vec<SequenceLayout>, String> {
+    let mut layouts: Vec<SequenceLayout> = Vec::new();
+    let mut invalid_residues = Occurrences::default();
+    let mut position: u64 = 0;
+    let mut line_number: u64 = 0;
+    // Whether the current sequence had a line shorter than the others, which must be its last.
+    let mut seen_short_line = false;
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let n = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| format!("Failed to read line {}: {e}", line_number + 1))?;
+        if n == 0 {
+            break;
+        }
+        line_number += 1;
+        position += n as u64;
+
+        let content = line.strip_suffix(b"\n").unwrap_or(&line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+
+        if let Some(definition) = content.strip_prefix(b">") {
+            let name = definition
+                .split(|b| b.is_ascii_whitespace())
+                .next()
+                .unwrap_or_default();
+            if name.is_empty() {
+                return Err(format!("Empty sequence name at line {line_number}"));
+            }
+            layouts.push(SequenceLayout::new(
+                String::from_utf8_lossy(name).into_owned(),
+                position,
+            ));
+            seen_short_line = false;
+            continue;
+        }
+
+        let Some(layout) = layouts.last_mut() else {
+            if content.is_empty() {
+                continue;
+            }
+            return Err(format!(
+                "Sequence data before the first header at line {line_number}"
+            ));
+        };
+
+        let line_bases = content.len() as u64;
+        if layout.line_width == 0 {
+            layout.line_bases = line_bases;
+            layout.line_width = n as u64;
+        } else if seen_short_line || line_bases > layout.line_bases {
+            layout.uniform_lines = false;
+        } else if line_bases < layout.line_bases || n as u64 != layout.line_width {
+            seen_short_line = true;
+        }
+        layout.length += line_bases;
+
+        if let Some(i) = content.iter().position(|&b| !is_valid_residue(b)) {
+            let name = &layout.name;
+            invalid_residues.add(|| {
+                format!(
+                    "line {line_number}, column {} of '{name}': {:?}",
+                    i + 1,
+                    char::from(content[i])
+                )
+            });
+        }
+    }
+
+    if let Some(first) = invalid_residues.first {
+        errors.push(format!(
+            "File contains {} line(s) with invalid sequence characters. First detected at {first}.",
+            invalid_residues.count
+        ));
+    }
+    Ok(layouts)
+}
+
+/// Compares the entries of a `.fai` index with the actual layout of the sequences.
+fn cross_check_index(layouts: &[SequenceLayout], index: &fai::Index, errors: &mut Vec<String>) {
+    let entries: HashMap<String, &fai::Record> = index
+        .as_ref()
+        .iter()
+        .map(|record| (record.name().to_string(), record))
+        .collect();
+
+    let mut missing = Occurrences::default();
+    let mut mismatched = Occurrences::default();
+    for layout in layouts {
+        let Some(entry) = entries.get(&layout.name) else {
+            missing.add(|| format!("'{}'", layout.name));
+            continue;
+        };
+        let differences: Vec<String> = [
+            ("length", entry.length(), layout.length),
+            ("offset", entry.offset(), layout.offset),
+            ("line bases", entry.line_bases(), layout.line_bases),
+            ("line width", entry.line_width(), layout.line_width),
+        ]
+        .into_iter()
+        .filter(|(_, indexed, actual)| indexed != actual)
+        .map(|(field, indexed, actual)| format!("{field} {indexed} in index, {actual} in file"))
+        .collect();
+        if !differences.is_empty() {
+            mismatched.add(|| format!("'{}': {}", layout.name, differences.join(", ")));
+        }
+    }
+
+    let names: HashSet<&str> = layouts.iter().map(|l| l.name.as_str()).collect();
+    let mut extra = Occurrences::default();
+    for record in index.as_ref() {
+        let name = record.name().to_string();
+        if !names.contains(name.as_str()) {
+            extra.add(|| format!("'{name}'"));
+        }
+    }
+
+    if let Some(first) = missing.first {
+        errors.push(format!(
+            "Index is missing {} sequence(s) of the file. First missing: {first}.",
+            missing.count
+        ));
+    }
+    if let Some(first) = extra.first {
+        errors.push(format!(
+            "Index contains {} sequence(s) not present in the file. First detected: {first}.",
+            extra.count
+        ));
+    }
+    if let Some(first) = mismatched.first {
+        errors.push(format!(
+            "Index entries of {} sequence(s) do not match the file. First detected at {first}.",
+            mismatched.count
+        ));
+    }
+}
+
+pub fn check_fasta(
+    path: &Path,
+    index_path: Option<&Path>,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::Auto,
+        CHECKS,
+        |reader| {
+            let mut errors = Vec::new();
+            let mut warnings = Vec::new();
+
+            let layouts = read_layouts(BufReader::new(reader), &mut errors)
+                .map_err(|message| CheckFailure::new(RECORDS_CHECK, message))?;
+            if layouts.is_empty() {
+                return Ok(CheckOutcome {
+                    errors: vec!["File is empty. Expected at least one record.".to_string()],
+                    ..Default::default()
+                });
+            }
+
+            let mut seen = HashSet::new();
+            let mut duplicates = Occurrences::default();
+            let mut empty = Occurrences::default();
+            let mut non_uniform = Occurrences::default();
+            for (i, layout) in layouts.iter().enumerate() {
+                let describe = || format!("sequence #{} ('{}')", i + 1, layout.name);
+                if !seen.insert(layout.name.as_str()) {
+                    duplicates.add(describe);
+                }
+                if layout.length == 0 {
+                    empty.add(describe);
+                }
+                if !layout.uniform_lines {
+                    non_uniform.add(describe);
+                }
+            }
+
+            if let Some(first) = duplicates.first {
+                errors.push(format!(
+                    "File contains {} duplicate sequence name(s). First detected at {first}.",
+                    duplicates.count
+                ));
+            }
+            if let Some(first) = empty.first {
+                warnings.push(format!(
+                    "File contains {} empty sequence(s). First detected at {first}.",
+                    empty.count
+                ));
+            }
+            if let Some(first) = non_uniform.first {
+                warnings.push(format!(
+                "File contains {} sequence(s) with inconsistent line lengths, which cannot be indexed. First detected at {first}.",
+                non_uniform.count
+            ));
+            }
+
+            let mut skipped_checks = Vec::new();
+            match index_path.map(|index_path| (index_path, fai::fs::read(index_path))) {
+                Some((_, Ok(index))) => cross_check_index(&layouts, &index, &mut errors),
+                Some((index_path, Err(e))) => errors.push(format!(
+                    "Failed to read FASTA index {}: {e}",
+                    index_path.display()
+                )),
+                None => skipped_checks.push(INDEX_CHECK),
+            }
+
+            Ok(CheckOutcome {
+                stats: Some(Stats {
+                    num_records: layouts.len() as u64,
+                    total_read_length: Some(layouts.iter().map(|l| l.length).sum()),
+                    modal_read_length: None,
+                    min_read_length: None,
+                    max_read_length: None,
+                    gvcf: None,
+                    adapter_reads: None,
+                    poly_g_reads: None,
+                    umi_reads: None,
+                    duplicate_records: None,
+                    mapped_records: None,
+                    bases: None,
+                    quality: None,
+                }),
+                errors,
+                warnings,
+                skipped_checks,
+            })
+        },
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FastaCheckJob {
+    pub path: PathBuf,
+    /// `.fai` index to cross-check against the file, if present.
+    pub index_path: Option<PathBuf>,
+    pub size: u64,
+}
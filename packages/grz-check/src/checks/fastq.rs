@@ -1,47 +1,712 @@
 use crate::checker::{FileReport, Stats};
-use crate::checks::common::{CheckOutcome, check_file};
+use crate::checks::common::{CheckFailure, CheckOutcome, CheckSettings, Decompression, check_file};
+use crate::checks::dependencies::Check;
+use crate::line_format::LineFormat;
+use crate::progress::RecordProgress;
+use crate::read_names::{DuplicateNames, Duplicates};
+use crate::umi::{UmiCheck, UmiLocation};
 use indicatif::ProgressBar;
 use itertools::EitherOrBoth::{Both, Left, Right};
 use itertools::Itertools;
 use noodles::fastq;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::{BufReader, Read};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Copy, Clone)]
+pub const RECORDS_CHECK: &str = "fastq.records";
+const MEAN_READ_LENGTH_CHECK: &str = "fastq.mean_read_length";
+const DECLARED_READ_LENGTH_CHECK: &str = "fastq.declared_read_length";
+
+const MATE_PAIRING_CHECK: &str = "fastq.mate_pairing";
+
+/// Checks of a FASTQ file, for both single-end and paired-end data.
+pub const CHECKS: &[Check] = &[
+    Check::new(RECORDS_CHECK, &[]),
+    Check::new(MEAN_READ_LENGTH_CHECK, &[RECORDS_CHECK]),
+    Check::new(DECLARED_READ_LENGTH_CHECK, &[RECORDS_CHECK]),
+];
+
+/// Checks of an interleaved FASTQ file.
+const INTERLEAVED_CHECKS: &[Check] = &[
+    Check::new(RECORDS_CHECK, &[]),
+    Check::new(MEAN_READ_LENGTH_CHECK, &[RECORDS_CHECK]),
+    Check::new(DECLARED_READ_LENGTH_CHECK, &[RECORDS_CHECK]),
+    Check::new(MATE_PAIRING_CHECK, &[RECORDS_CHECK]),
+];
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ReadLengthCheck {
     Fixed(usize),
     Skip,
 }
 
-#[derive(Debug)]
+/// Read length declared in the submission metadata, which the modal read length of a file
+/// has to match within the given tolerance.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct DeclaredReadLength {
+    pub length: usize,
+    pub tolerance: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SingleFastqJob {
     pub path: PathBuf,
     pub length_check: ReadLengthCheck,
+    pub declared_read_length: Option<DeclaredReadLength>,
     pub size: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InterleavedFastqJob {
+    pub path: PathBuf,
+    pub length_check: ReadLengthCheck,
+    pub declared_read_length: Option<DeclaredReadLength>,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PairedFastqJob {
     pub fq1_path: PathBuf,
     pub fq2_path: PathBuf,
     pub length_check: ReadLengthCheck,
+    pub fq1_declared_read_length: Option<DeclaredReadLength>,
+    pub fq2_declared_read_length: Option<DeclaredReadLength>,
     pub fq1_size: u64,
     pub fq2_size: u64,
 }
 
-struct FastqCheckProcessor {
+/// A read pair with a FASTQ file of index reads (I1), e.g. of 10x or dual-index runs.
+///
+/// The index reads are not subject to the read length check.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TripleFastqJob {
+    #[serde(flatten)]
+    pub pair: PairedFastqJob,
+    pub index_path: PathBuf,
+    pub index_size: u64,
+}
+
+/// Default fraction by which the number of bases of a FASTQ file or pair may deviate from its
+/// expected yield.
+pub const DEFAULT_BASE_YIELD_TOLERANCE: f64 = 0.01;
+
+/// Decimal unit prefixes of base counts, as used for yields in lab manifests.
+const BASE_UNITS: &[(&str, f64)] = &[("k", 1e3), ("M", 1e6), ("G", 1e9), ("T", 1e12)];
+
+/// Parses a number of bases such as `1500000`, `12.5G` or `12.5Gb`.
+pub fn parse_base_count(s: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid number of bases '{s}'. Expected e.g. 1500000 or 12.5Gb.");
+    let number = s.trim();
+    let number = number.strip_suffix('b').unwrap_or(number);
+    let (number, multiplier) = BASE_UNITS
+        .iter()
+        .find_map(|&(unit, multiplier)| Some((number.strip_suffix(unit)?, multiplier)))
+        .unwrap_or((number, 1.0));
+    let bases = number.parse::<f64>().map_err(|_| invalid())? * multiplier;
+    if !bases.is_finite() || bases < 1.0 {
+        return Err(invalid());
+    }
+    Ok(bases.round() as u64)
+}
+
+/// Lowest quality character of Phred+64 encoded files (Q0).
+const PHRED64_MIN: u8 = b'@';
+/// Lowest quality character of Solexa encoded files (Q-5).
+const SOLEXA_MIN: u8 = b';';
+/// Highest quality character of Phred+33 encoded short reads (Q41). Files without a quality
+/// character below [`SOLEXA_MIN`] but with characters above this one are not Phred+33 encoded.
+const PHRED33_SHORT_READ_MAX: u8 = b'J';
+/// Legal quality characters of Phred+33 (Q0 to Q93), the printable ASCII characters.
+const PHRED33_RANGE: std::ops::RangeInclusive<u8> = b'!'..=b'~';
+
+/// Returns why a read name and its comment are not in Casava 1.8+ format, i.e.
+/// `instrument:run:flowcell:lane:tile:x:y` with an optional `read:filtered:control:index`
+/// comment.
+fn casava_name_error(name: &[u8], description: &[u8]) -> Option<String> {
+    let is_number = |field: &[u8]| !field.is_empty() && field.iter().all(u8::is_ascii_digit);
+    let fields: Vec<&[u8]> = name.split(|&b| b == b':').collect();
+    if fields.len() != 7 {
+        return Some(format!(
+            "expected 7 colon-separated fields, found {}",
+            fields.len()
+        ));
+    }
+    if fields[0].is_empty() || fields[2].is_empty() {
+        return Some("instrument and flowcell ID must not be empty".to_string());
+    }
+    if !std::iter::once(fields[1])
+        .chain(fields[3..].iter().copied())
+        .all(is_number)
+    {
+        return Some("run number, lane, tile and coordinates must be numbers".to_string());
+    }
+    if description.is_empty() {
+        return None;
+    }
+    let comment: Vec<&[u8]> = description.split(|&b| b == b':').collect();
+    let valid_comment = comment.len() == 4
+        && is_number(comment[0])
+        && matches!(comment[1], b"Y" | b"N")
+        && is_number(comment[2]);
+    (!valid_comment).then(|| "comment must be read:filtered:control:index".to_string())
+}
+
+/// Adapters screened for by default, as `(name, sequence)`.
+pub const DEFAULT_ADAPTERS: &[(&str, &str)] = &[
+    ("Illumina TruSeq", "AGATCGGAAGAGC"),
+    ("Nextera", "CTGTCTCTTATACACATCT"),
+];
+/// Default fraction of reads with an adapter above which a warning is reported.
+pub const DEFAULT_MAX_ADAPTER_FRACTION: f64 = 0.05;
+/// Number of leading bases of an adapter that a read has to contain to count as a hit, as in
+/// FastQC.
+const ADAPTER_PROBE_LEN: usize = 12;
+
+/// Screening of reads for adapter sequences, enabled via `--adapter-screening`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdapterScreening {
+    pub adapters: Vec<Adapter>,
+    /// Fraction of reads with an adapter above which a warning is reported.
+    pub max_fraction: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Adapter {
+    pub name: String,
+    pub sequence: String,
+}
+
+impl Adapter {
+    /// Leading bases of the adapter that are searched for in reads.
+    fn probe(&self) -> &[u8] {
+        let sequence = self.sequence.as_bytes();
+        &sequence[..sequence.len().min(ADAPTER_PROBE_LEN)]
+    }
+}
+
+/// Parses an adapter given as `NAME=SEQUENCE`.
+pub fn parse_adapter(s: &str) -> Result<Adapter, String> {
+    let (name, sequence) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid adapter '{s}'. Expected NAME=SEQUENCE."))?;
+    let sequence = sequence.trim().to_ascii_uppercase();
+    if sequence.is_empty() || !sequence.bytes().all(|base| b"ACGT".contains(&base)) {
+        return Err(format!(
+            "Invalid adapter sequence '{sequence}'. Expected only A, C, G and T."
+        ));
+    }
+    Ok(Adapter {
+        name: name.trim().to_string(),
+        sequence,
+    })
+}
+
+/// Default minimum length of the G homopolymer at the end of a read that counts as poly-G, as in
+/// `fastp`.
+pub const DEFAULT_POLY_G_LENGTH: NonZeroUsize = NonZeroUsize::new(10).unwrap();
+/// Default fraction of poly-G reads above which a warning is reported.
+pub const DEFAULT_MAX_POLY_G_FRACTION: f64 = 0.01;
+
+/// Screening of reads for poly-G tails, enabled via `--poly-g-screening`. On two-color
+/// instruments such as NovaSeq, a missing signal is called as G, so reads running past the end
+/// of their fragment or into a dark cluster end in long G stretches.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolyGScreening {
+    /// Number of trailing Gs from which a read counts as poly-G.
+    pub min_length: NonZeroUsize,
+    /// Fraction of poly-G reads above which a warning is reported.
+    pub max_fraction: f64,
+}
+
+/// Number of reads with a valid UMI in a file.
+#[derive(Debug)]
+struct UmiCounts<'a> {
+    check: &'a UmiCheck,
+    valid: u64,
+    /// Number and name of the first read without a valid UMI.
+    first_invalid: Option<(u64, String)>,
+}
+
+impl<'a> UmiCounts<'a> {
+    fn new(check: &'a UmiCheck) -> Self {
+        Self {
+            check,
+            valid: 0,
+            first_invalid: None,
+        }
+    }
+
+    fn add(&mut self, record_number: u64, record: &fastq::Record) {
+        if self
+            .check
+            .umi(record.name(), record.sequence())
+            .is_some_and(|umi| self.check.pattern.matches(umi))
+        {
+            self.valid += 1;
+        } else if self.first_invalid.is_none() {
+            self.first_invalid = Some((
+                record_number,
+                String::from_utf8_lossy(record.name()).into_owned(),
+            ));
+        }
+    }
+
+    /// Returns an error if the fraction of reads with a valid UMI is below the minimum.
+    fn error(&self, num_records: u64) -> Option<String> {
+        let fraction = self.valid as f64 / num_records as f64;
+        let (record_number, name) = self.first_invalid.as_ref()?;
+        if num_records == 0 || fraction >= self.check.min_fraction {
+            return None;
+        }
+        Some(format!(
+            "Only {:.2}% of reads ({} of {num_records}) have a valid UMI in the {} matching '{}', below the minimum of {:.2}%. First detected at record #{record_number} ('{name}').",
+            fraction * 100.0,
+            self.valid,
+            self.check.location,
+            self.check.pattern,
+            self.check.min_fraction * 100.0
+        ))
+    }
+}
+
+/// Number of reads ending in a poly-G tail in a file.
+#[derive(Debug)]
+struct PolyGCounts<'a> {
+    screening: &'a PolyGScreening,
+    reads: u64,
+}
+
+impl<'a> PolyGCounts<'a> {
+    fn new(screening: &'a PolyGScreening) -> Self {
+        Self {
+            screening,
+            reads: 0,
+        }
+    }
+
+    fn add(&mut self, sequence: &[u8]) {
+        let tail = sequence
+            .iter()
+            .rev()
+            .take_while(|&&base| base == b'G' || base == b'g')
+            .count();
+        if tail >= self.screening.min_length.get() {
+            self.reads += 1;
+        }
+    }
+
+    /// Returns a warning if the fraction of poly-G reads exceeds the maximum.
+    fn warning(&self, num_records: u64) -> Option<String> {
+        let fraction = self.reads as f64 / num_records as f64;
+        if num_records == 0 || fraction <= self.screening.max_fraction {
+            return None;
+        }
+        Some(format!(
+            "{:.2}% of reads ({} of {num_records}) end in a poly-G tail of at least {} bases, above the maximum of {:.2}%. This is typical of signal loss on two-color instruments such as NovaSeq.",
+            fraction * 100.0,
+            self.reads,
+            self.screening.min_length,
+            self.screening.max_fraction * 100.0
+        ))
+    }
+}
+
+/// Number of reads with adapter hits in a file.
+#[derive(Debug)]
+struct AdapterCounts<'a> {
+    screening: &'a AdapterScreening,
+    /// Reads containing any adapter.
+    reads: u64,
+    /// Reads containing each adapter.
+    hits: Vec<u64>,
+}
+
+impl<'a> AdapterCounts<'a> {
+    fn new(screening: &'a AdapterScreening) -> Self {
+        Self {
+            screening,
+            reads: 0,
+            hits: vec![0; screening.adapters.len()],
+        }
+    }
+
+    fn add(&mut self, sequence: &[u8]) {
+        let mut hit = false;
+        for (adapter, hits) in self.screening.adapters.iter().zip(&mut self.hits) {
+            let probe = adapter.probe();
+            if sequence.windows(probe.len()).any(|window| window == probe) {
+                *hits += 1;
+                hit = true;
+            }
+        }
+        if hit {
+            self.reads += 1;
+        }
+    }
+
+    /// Returns a warning if the fraction of reads with an adapter exceeds the maximum.
+    fn warning(&self, num_records: u64) -> Option<String> {
+        let fraction = self.reads as f64 / num_records as f64;
+        if num_records == 0 || fraction <= self.screening.max_fraction {
+            return None;
+        }
+        let (adapter, hits) = self
+            .screening
+            .adapters
+            .iter()
+            .zip(&self.hits)
+            .max_by_key(|&(_, hits)| hits)?;
+        Some(format!(
+            "{:.2}% of reads ({} of {num_records}) contain adapter sequences, above the maximum of {:.2}%. Most frequent adapter: {} ({hits} reads). The reads may not have been adapter-trimmed.",
+            fraction * 100.0,
+            self.reads,
+            self.screening.max_fraction * 100.0,
+            adapter.name
+        ))
+    }
+}
+
+/// Returns the `instrument:run:flowcell` prefix of an Illumina read name of the form
+/// `instrument:run:flowcell:lane:tile:x:y`.
+fn illumina_run_id(name: &[u8]) -> Option<&[u8]> {
+    let colons: Vec<usize> = name.iter().positions(|&b| b == b':').take(7).collect();
+    (colons.len() == 6).then(|| &name[..colons[2]])
+}
+
+/// Instrument, run and flowcell IDs of the reads of a file, to detect concatenated runs.
+#[derive(Debug, Default)]
+struct RunIds {
+    /// Distinct IDs in order of appearance, with the number and name of their first record.
+    ids: Vec<(Vec<u8>, u64, String)>,
+}
+
+impl RunIds {
+    /// Number of distinct IDs listed in the warning.
+    const MAX_LISTED: usize = 5;
+
+    fn add(&mut self, record_number: u64, name: &[u8]) {
+        let Some(id) = illumina_run_id(name) else {
+            return;
+        };
+        // IDs rarely change within a file, so the last one is compared first.
+        if self.ids.iter().rev().any(|(known, _, _)| known == id) {
+            return;
+        }
+        self.ids.push((
+            id.to_vec(),
+            record_number,
+            String::from_utf8_lossy(name).into_owned(),
+        ));
+    }
+
+    /// Returns a warning if the reads come from more than one instrument, run or flowcell.
+    fn warning(&self) -> Option<String> {
+        let (_, record_number, name) = self.ids.get(1)?;
+        let listed = self
+            .ids
+            .iter()
+            .take(Self::MAX_LISTED)
+            .map(|(id, _, _)| String::from_utf8_lossy(id))
+            .join(", ");
+        let more = if self.ids.len() > Self::MAX_LISTED {
+            ", ..."
+        } else {
+            ""
+        };
+        Some(format!(
+            "Reads come from {} different instrument:run:flowcell combinations ({listed}{more}), so the file may be a concatenation of several runs. First detected at record #{record_number} ('{name}').",
+            self.ids.len()
+        ))
+    }
+}
+
+/// Number of `A`, `C`, `G`, `T` and `N` bases of a file. Other characters, such as IUPAC
+/// ambiguity codes, are not counted.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BaseCounts {
+    pub a: u64,
+    pub c: u64,
+    pub g: u64,
+    pub t: u64,
+    pub n: u64,
+}
+
+impl BaseCounts {
+    fn add(&mut self, sequence: &[u8]) {
+        for base in sequence {
+            match base {
+                b'A' => self.a += 1,
+                b'C' => self.c += 1,
+                b'G' => self.g += 1,
+                b'T' => self.t += 1,
+                b'N' => self.n += 1,
+                _ => {}
+            }
+        }
+    }
+
+    /// Percentages of each base among `total` bases.
+    pub fn composition(self, total: u64) -> BaseComposition {
+        let percentage = |count: u64| count as f64 * 100.0 / total as f64;
+        BaseComposition {
+            a: percentage(self.a),
+            c: percentage(self.c),
+            g: percentage(self.g),
+            t: percentage(self.t),
+            n: percentage(self.n),
+        }
+    }
+}
+
+/// Percentages of `A`, `C`, `G`, `T` and `N` bases of a file, as reported.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct BaseComposition {
+    pub a: f64,
+    pub c: f64,
+    pub g: f64,
+    pub t: f64,
+    pub n: f64,
+}
+
+/// Builds a lookup table of the allowed sequence characters.
+const fn base_table(bases: &[u8]) -> [bool; 256] {
+    let mut table = [false; 256];
+    let mut i = 0;
+    while i < bases.len() {
+        table[bases[i] as usize] = true;
+        i += 1;
+    }
+    table
+}
+
+/// Upper-case IUPAC nucleotide codes.
+const IUPAC_BASES: [bool; 256] = base_table(b"ACGTURYSWKMBDHVN");
+const STRICT_BASES_TABLE: [bool; 256] = base_table(b"ACGTN");
+
+/// Returns the 0-based position and the character of the first base not in `table`.
+fn find_invalid_base(sequence: &[u8], table: &[bool; 256]) -> Option<(usize, u8)> {
+    sequence
+        .iter()
+        .find_position(|&&base| !table[usize::from(base)])
+        .map(|(position, &base)| (position, base))
+}
+
+/// Printable characters are quoted, others such as NUL bytes are shown in hex.
+fn display_byte(byte: u8) -> String {
+    if byte.is_ascii_graphic() {
+        format!("'{}'", byte as char)
+    } else {
+        format!("0x{byte:02X}")
+    }
+}
+
+/// Range of the quality characters of a file, used to detect illegal characters and legacy
+/// encodings.
+#[derive(Debug, Default)]
+struct QualityRange {
+    min: Option<u8>,
+    max: Option<u8>,
+    /// Number and name of the first record with a quality character above
+    /// [`PHRED33_SHORT_READ_MAX`].
+    first_high: Option<(u64, String)>,
+    /// Number and name of the first record with a character outside [`PHRED33_RANGE`], with the
+    /// 1-based position and the character.
+    first_illegal: Option<(u64, String, usize, u8)>,
+}
+
+/// Mean and median Phred quality of the bases of a file, assuming Phred+33.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub struct QualityStats {
+    pub mean: f64,
+    pub median: u8,
+}
+
+/// Number of bases with each quality character in [`PHRED33_RANGE`].
+#[derive(Debug)]
+struct QualityHistogram([u64; 94]);
+
+impl QualityHistogram {
+    fn add(&mut self, quality_scores: &[u8]) {
+        for &quality in quality_scores {
+            if let Some(count) = self.0.get_mut(usize::from(quality.wrapping_sub(b'!'))) {
+                *count += 1;
+            }
+        }
+    }
+
+    /// The median is the lower median of all bases.
+    fn stats(&self) -> Option<QualityStats> {
+        let total: u64 = self.0.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let sum: u64 = (0..)
+            .zip(self.0)
+            .map(|(quality, count)| quality * count)
+            .sum();
+        let mut seen = 0;
+        let median = (0..)
+            .zip(self.0)
+            .find(|&(_, count)| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .map(|(quality, _)| quality)?;
+        Some(QualityStats {
+            mean: sum as f64 / total as f64,
+            median,
+        })
+    }
+}
+
+impl QualityRange {
+    fn add(&mut self, record_number: u64, record: &fastq::Record) {
+        let Some((&min, &max)) = record.quality_scores().iter().minmax().into_option() else {
+            return;
+        };
+        self.min = Some(self.min.map_or(min, |m| m.min(min)));
+        self.max = Some(self.max.map_or(max, |m| m.max(max)));
+        if max > PHRED33_SHORT_READ_MAX && self.first_high.is_none() {
+            self.first_high = Some((
+                record_number,
+                String::from_utf8_lossy(record.name()).into_owned(),
+            ));
+        }
+        if !(PHRED33_RANGE.contains(&min) && PHRED33_RANGE.contains(&max))
+            && self.first_illegal.is_none()
+            && let Some((position, &quality)) = record
+                .quality_scores()
+                .iter()
+                .find_position(|quality| !PHRED33_RANGE.contains(quality))
+        {
+            self.first_illegal = Some((
+                record_number,
+                String::from_utf8_lossy(record.name()).into_owned(),
+                position + 1,
+                quality,
+            ));
+        }
+    }
+
+    /// Returns an error if a quality character is not printable ASCII, as in garbage from
+    /// corrupted streams.
+    fn illegal_character_error(&self) -> Option<String> {
+        let (record_number, name, position, quality) = self.first_illegal.as_ref()?;
+        Some(format!(
+            "Illegal quality character 0x{quality:02X} at position {position} of record #{record_number} ('{name}'). Quality characters must be printable ASCII from '{}' to '{}' (Phred+33).",
+            *PHRED33_RANGE.start() as char,
+            *PHRED33_RANGE.end() as char
+        ))
+    }
+
+    /// Returns an error if the quality characters indicate Phred+64 or Solexa encoding.
+    fn legacy_encoding_error(&self) -> Option<String> {
+        let (Some(min), Some(max), Some((record_number, name))) =
+            (self.min, self.max, &self.first_high)
+        else {
+            return None;
+        };
+        // The encoding of files with illegal characters is not meaningful.
+        if self.first_illegal.is_some() {
+            return None;
+        }
+        let encoding = match min {
+            PHRED64_MIN.. => "Phred+64",
+            SOLEXA_MIN.. => "Solexa",
+            _ => return None,
+        };
+        Some(format!(
+            "Quality scores appear to be {encoding} encoded (quality characters range from '{}' to '{}'), first detected at record #{record_number} ('{name}'). Expected Phred+33.",
+            min as char, max as char
+        ))
+    }
+}
+
+struct FastqCheckProcessor<'a> {
     length_check: ReadLengthCheck,
+    declared_read_length: Option<DeclaredReadLength>,
+    quality_range: QualityRange,
+    quality_histogram: QualityHistogram,
+    allowed_bases: &'static [bool; 256],
+    /// Number of records with a character that is not an allowed base.
+    invalid_base_records: u64,
+    /// Number and name of the first record with an invalid base, with its 1-based position and
+    /// the character.
+    first_invalid_base: Option<(u64, String, usize, u8)>,
+    /// Number of records with an empty sequence or quality string.
+    empty_records: u64,
+    /// Number and name of the first record with an empty sequence or quality string.
+    first_empty: Option<(u64, String)>,
+    /// Whether read names must be in Casava 1.8+ format.
+    strict_read_names: bool,
+    /// Number of records whose name is not in Casava 1.8+ format.
+    malformed_name_records: u64,
+    /// Number and name of the first such record, with the reason.
+    first_malformed_name: Option<(u64, String, String)>,
+    adapter_counts: Option<AdapterCounts<'a>>,
+    poly_g_counts: Option<PolyGCounts<'a>>,
+    umi_counts: Option<UmiCounts<'a>>,
+    run_ids: RunIds,
+    duplicate_names: Option<DuplicateNames>,
+    /// Whether both mates of each pair are in this file, so that only the names of the first
+    /// mates are checked for duplicates.
+    interleaved: bool,
+    bases: BaseCounts,
     num_records: u64,
     total_read_length: u64,
+    read_length_counts: HashMap<usize, u64>,
+    /// Findings about the raw lines of the file, if it is read through a [`LineInspector`].
+    ///
+    /// [`LineInspector`]: crate::line_format::LineInspector
+    line_format: Option<LineFormat>,
+    /// Record count of a file whose progress is counted in records instead of bytes.
+    record_progress: Option<RecordProgress>,
     errors: Vec<String>,
 }
 
-impl FastqCheckProcessor {
-    fn new(length_check: ReadLengthCheck) -> Self {
+impl<'a> FastqCheckProcessor<'a> {
+    fn new(
+        length_check: ReadLengthCheck,
+        declared_read_length: Option<DeclaredReadLength>,
+        settings: &'a CheckSettings,
+    ) -> Self {
         Self {
             length_check,
+            declared_read_length,
+            quality_range: QualityRange::default(),
+            quality_histogram: QualityHistogram([0; 94]),
+            allowed_bases: if settings.strict_bases {
+                &STRICT_BASES_TABLE
+            } else {
+                &IUPAC_BASES
+            },
+            invalid_base_records: 0,
+            first_invalid_base: None,
+            empty_records: 0,
+            first_empty: None,
+            strict_read_names: settings.strict_read_names,
+            malformed_name_records: 0,
+            first_malformed_name: None,
+            adapter_counts: settings.adapter_screening.as_ref().map(AdapterCounts::new),
+            poly_g_counts: settings.poly_g_screening.as_ref().map(PolyGCounts::new),
+            umi_counts: settings
+                .umi
+                .as_ref()
+                .filter(|check| check.location == UmiLocation::Name)
+                .map(UmiCounts::new),
+            run_ids: RunIds::default(),
+            duplicate_names: settings.duplicate_names_memory.map(DuplicateNames::new),
+            interleaved: false,
+            bases: BaseCounts::default(),
             num_records: 0,
             total_read_length: 0,
+            read_length_counts: HashMap::new(),
+            line_format: None,
+            record_progress: None,
             errors: Vec::new(),
         }
     }
@@ -54,28 +719,102 @@ impl FastqCheckProcessor {
         &mut self,
         record: Result<fastq::Record, std::io::Error>,
         file_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<fastq::Record, String> {
         self.num_records += 1;
+        if let Some(record_progress) = &mut self.record_progress {
+            record_progress.update(self.num_records);
+        }
 
         let record = record.map_err(|e| {
+            // A blank line fails as an invalid name prefix, which does not point to it.
+            let reason = self
+                .line_format
+                .as_ref()
+                .and_then(|format| format.blank_line_error(self.num_records))
+                .unwrap_or_else(|| e.to_string());
             format!(
                 "Failed to parse {} record #{}: {}",
-                file_id, self.num_records, e
+                file_id, self.num_records, reason
             )
         })?;
 
+        let read_length = record.sequence().len();
         self.total_read_length = self
             .total_read_length
             .checked_add(
-                u64::try_from(record.sequence().len())
-                    .expect("Single FASTQ record length should fit in u64"),
+                u64::try_from(read_length).expect("Single FASTQ record length should fit in u64"),
             )
             .expect("Total length of all reads should fit in u64");
+        *self.read_length_counts.entry(read_length).or_insert(0) += 1;
+        self.quality_range.add(self.num_records, &record);
+        self.quality_histogram.add(record.quality_scores());
+        self.bases.add(record.sequence());
+        if let Some(adapter_counts) = &mut self.adapter_counts {
+            adapter_counts.add(record.sequence());
+        }
+        if let Some(poly_g_counts) = &mut self.poly_g_counts {
+            poly_g_counts.add(record.sequence());
+        }
+        if let Some(umi_counts) = &mut self.umi_counts {
+            umi_counts.add(self.num_records, &record);
+        }
+        self.run_ids.add(self.num_records, record.name());
+        if let Some(duplicate_names) = &mut self.duplicate_names
+            && !(self.interleaved && self.num_records.is_multiple_of(2))
+            && let Err(e) = duplicate_names.add(self.num_records, mate_name(record.name()))
+        {
+            self.errors
+                .push(format!("Failed to check read names for duplicates: {e}"));
+            self.duplicate_names = None;
+        }
+        if record.sequence().is_empty() || record.quality_scores().is_empty() {
+            self.empty_records += 1;
+            if self.first_empty.is_none() {
+                self.first_empty = Some((
+                    self.num_records,
+                    String::from_utf8_lossy(record.name()).into_owned(),
+                ));
+            }
+        }
+        if self.strict_read_names
+            && let Some(reason) = casava_name_error(record.name(), record.description())
+        {
+            self.malformed_name_records += 1;
+            if self.first_malformed_name.is_none() {
+                self.first_malformed_name = Some((
+                    self.num_records,
+                    String::from_utf8_lossy(record.name()).into_owned(),
+                    reason,
+                ));
+            }
+        }
+        if let Some((position, base)) = find_invalid_base(record.sequence(), self.allowed_bases) {
+            self.invalid_base_records += 1;
+            if self.first_invalid_base.is_none() {
+                self.first_invalid_base = Some((
+                    self.num_records,
+                    String::from_utf8_lossy(record.name()).into_owned(),
+                    position + 1,
+                    base,
+                ));
+            }
+        }
+
+        Ok(record)
+    }
 
-        Ok(())
+    /// Most frequent read length; ties are resolved towards the longer read length.
+    fn modal_read_length(&self) -> Option<usize> {
+        self.read_length_counts
+            .iter()
+            .max_by_key(|&(&length, &count)| (count, length))
+            .map(|(&length, _)| length)
     }
 
     fn finalize(mut self) -> CheckOutcome {
+        if let Some(record_progress) = &mut self.record_progress {
+            record_progress.finish(self.num_records);
+        }
         if self.num_records == 0 && self.is_ok() {
             self.errors
                 .push("File is empty. Expected at least one record.".to_string());
@@ -98,17 +837,158 @@ impl FastqCheckProcessor {
             ReadLengthCheck::Skip => (),
         };
 
+        let mut skipped_checks = Vec::new();
+        if matches!(self.length_check, ReadLengthCheck::Skip) {
+            skipped_checks.push(MEAN_READ_LENGTH_CHECK);
+        }
+        if self.declared_read_length.is_none() {
+            skipped_checks.push(DECLARED_READ_LENGTH_CHECK);
+        }
+
+        // Trailing whitespace is reported as such instead of as an invalid base or quality
+        // character of its record.
+        let trailing_whitespace_record = self
+            .line_format
+            .as_ref()
+            .and_then(LineFormat::trailing_whitespace_record);
+        if let Some(error) = self
+            .line_format
+            .as_ref()
+            .and_then(LineFormat::trailing_whitespace_error)
+        {
+            self.errors.push(error);
+        }
+        if let Some(error) = self
+            .line_format
+            .as_ref()
+            .filter(|format| !format.is_tolerant())
+            .and_then(LineFormat::missing_newline)
+        {
+            self.errors.push(error);
+        }
+        if let Some((record_number, _, _, base)) = &self.first_invalid_base
+            && base.is_ascii_whitespace()
+            && Some(*record_number) == trailing_whitespace_record
+        {
+            self.first_invalid_base = None;
+        }
+        if let Some((record_number, _, _, quality)) = &self.quality_range.first_illegal
+            && quality.is_ascii_whitespace()
+            && Some(*record_number) == trailing_whitespace_record
+        {
+            self.quality_range.first_illegal = None;
+        }
+        if let Some((record_number, name, position, base)) = &self.first_invalid_base {
+            let allowed = if std::ptr::eq(self.allowed_bases, &STRICT_BASES_TABLE) {
+                "A, C, G, T and N"
+            } else {
+                "upper-case IUPAC nucleotide codes"
+            };
+            self.errors.push(format!(
+                "File contains {} record(s) with invalid sequence characters. First detected at position {position} of record #{record_number} ('{name}'): {}. Sequences must only contain {allowed}.",
+                self.invalid_base_records,
+                display_byte(*base)
+            ));
+        }
+        if let Some((record_number, name)) = &self.first_empty {
+            self.errors.push(format!(
+                "File contains {} record(s) with an empty sequence or quality string. First detected at record #{record_number} ('{name}').",
+                self.empty_records
+            ));
+        }
+        if let Some((record_number, name, reason)) = &self.first_malformed_name {
+            self.errors.push(format!(
+                "File contains {} record(s) with read names not in Casava 1.8 format. First detected at record #{record_number} ('{name}'): {reason}.",
+                self.malformed_name_records
+            ));
+        }
+        if let Some(duplicate_names) = self.duplicate_names.take() {
+            match duplicate_names.finish() {
+                Ok(Duplicates {
+                    count,
+                    first_record: Some(first_record),
+                }) => self.errors.push(format!(
+                    "File contains {count} record(s) with duplicate read names. First detected at record #{first_record}."
+                )),
+                Ok(_) => {}
+                Err(e) => self
+                    .errors
+                    .push(format!("Failed to check read names for duplicates: {e}")),
+            }
+        }
+        if let Some(error) = self
+            .umi_counts
+            .as_ref()
+            .and_then(|counts| counts.error(self.num_records))
+        {
+            self.errors.push(error);
+        }
+        if let Some(error) = self.quality_range.illegal_character_error() {
+            self.errors.push(error);
+        }
+        if let Some(error) = self.quality_range.legacy_encoding_error() {
+            self.errors.push(error);
+        }
+
+        let modal_read_length = self.modal_read_length();
+        if let (Some(declared), Some(modal)) = (self.declared_read_length, modal_read_length)
+            && modal.abs_diff(declared.length) > declared.tolerance
+        {
+            self.errors.push(format!(
+                "Modal read length ({}) deviates from declared read length ({}) by more than the tolerance of {} base(s)",
+                modal, declared.length, declared.tolerance
+            ));
+        }
+
+        let mut warnings = Vec::new();
+        if let Some(warning) = self
+            .adapter_counts
+            .as_ref()
+            .and_then(|counts| counts.warning(self.num_records))
+        {
+            warnings.push(warning);
+        }
+        if let Some(warning) = self
+            .poly_g_counts
+            .as_ref()
+            .and_then(|counts| counts.warning(self.num_records))
+        {
+            warnings.push(warning);
+        }
+        if let Some(format) = &self.line_format {
+            warnings.extend(format.crlf_warning());
+            if format.is_tolerant() {
+                warnings.extend(format.missing_newline());
+            }
+            warnings.extend(format.trailing_blank_lines_warning());
+        }
+        if let Some(warning) = self.run_ids.warning() {
+            warnings.push(warning);
+        }
+
         CheckOutcome {
             stats: if self.num_records > 0 {
                 Some(Stats {
                     num_records: self.num_records,
                     total_read_length: Some(self.total_read_length),
+                    modal_read_length: modal_read_length.map(|l| l as u64),
+                    min_read_length: self.read_length_counts.keys().min().map(|&l| l as u64),
+                    max_read_length: self.read_length_counts.keys().max().map(|&l| l as u64),
+                    gvcf: None,
+                    adapter_reads: self.adapter_counts.as_ref().map(|counts| counts.reads),
+                    poly_g_reads: self.poly_g_counts.as_ref().map(|counts| counts.reads),
+                    umi_reads: self.umi_counts.as_ref().map(|counts| counts.valid),
+                    duplicate_records: None,
+                    mapped_records: None,
+                    bases: Some(self.bases),
+                    quality: self.quality_histogram.stats(),
                 })
             } else {
                 None
             },
             errors: self.errors,
-            warnings: vec![],
+            warnings,
+            skipped_checks,
         }
     }
 }
@@ -116,45 +996,219 @@ impl FastqCheckProcessor {
 pub fn check_single_fastq(
     path: &Path,
     length_check: ReadLengthCheck,
+    declared_read_length: Option<DeclaredReadLength>,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_fastq_records(
+        path,
+        length_check,
+        declared_read_length,
+        false,
+        settings,
+        file_pb,
+        global_pb,
+    )
+}
+
+/// Checks the index reads (I1) of a read pair. They are not subject to the read length check,
+/// but carry the UMIs if these are located in the index read.
+pub fn check_index_fastq(
+    path: &Path,
+    settings: &CheckSettings,
     file_pb: &ProgressBar,
     global_pb: &ProgressBar,
 ) -> FileReport {
-    check_file(path, file_pb, global_pb, true, |reader| {
-        let mut fastq_reader = fastq::io::Reader::new(BufReader::new(reader));
-        let mut processor = FastqCheckProcessor::new(length_check);
+    check_fastq_records(
+        path,
+        ReadLengthCheck::Skip,
+        None,
+        true,
+        settings,
+        file_pb,
+        global_pb,
+    )
+}
 
-        for record_res in fastq_reader.records() {
-            processor.process_record(record_res, "record")?;
-            if !processor.is_ok() {
-                break;
+fn check_fastq_records(
+    path: &Path,
+    length_check: ReadLengthCheck,
+    declared_read_length: Option<DeclaredReadLength>,
+    index_reads: bool,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::Auto,
+        CHECKS,
+        |reader| {
+            let (reader, line_format) = LineFormat::inspect(reader, settings.parse_leniency);
+            let mut fastq_reader = fastq::io::Reader::new(BufReader::new(reader));
+            let mut processor =
+                FastqCheckProcessor::new(length_check, declared_read_length, settings);
+            processor.line_format = Some(line_format);
+            processor.record_progress = RecordProgress::of(file_pb, path);
+            if index_reads
+                && let Some(check) = settings
+                    .umi
+                    .as_ref()
+                    .filter(|check| check.location == UmiLocation::Index)
+            {
+                processor.umi_counts = Some(UmiCounts::new(check));
             }
-        }
 
-        Ok(processor.finalize())
-    })
+            for record_res in fastq_reader.records() {
+                processor
+                    .process_record(record_res, "record")
+                    .map_err(|message| CheckFailure::new(RECORDS_CHECK, message))?;
+                if !processor.is_ok() {
+                    break;
+                }
+            }
+
+            Ok(processor.finalize())
+        },
+    )
+}
+
+/// Read name without a trailing mate number (`/1` or `/2`).
+///
+/// Anything after the first space, e.g. the Casava comment `1:N:0:ATCACG`, is not part of the
+/// name of a record.
+fn mate_name(name: &[u8]) -> &[u8] {
+    name.strip_suffix(b"/1")
+        .or_else(|| name.strip_suffix(b"/2"))
+        .unwrap_or(name)
 }
 
+/// Checks a FASTQ file containing both mates of each pair in alternating records.
+///
+/// The read length check applies to all reads, regardless of the mate.
+pub fn check_interleaved_fastq(
+    path: &Path,
+    length_check: ReadLengthCheck,
+    declared_read_length: Option<DeclaredReadLength>,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::Auto,
+        INTERLEAVED_CHECKS,
+        |reader| {
+            let (reader, line_format) = LineFormat::inspect(reader, settings.parse_leniency);
+            let mut fastq_reader = fastq::io::Reader::new(BufReader::new(reader));
+            let mut processor =
+                FastqCheckProcessor::new(length_check, declared_read_length, settings);
+            processor.line_format = Some(line_format);
+            processor.record_progress = RecordProgress::of(file_pb, path);
+            processor.interleaved = true;
+
+            let mut first_mate: Option<(u64, Vec<u8>)> = None;
+            let mut mismatched_pairs: u64 = 0;
+            let mut first_mismatch_details: Option<(u64, String, String)> = None;
+
+            for record_res in fastq_reader.records() {
+                let record = processor
+                    .process_record(record_res, "record")
+                    .map_err(|message| CheckFailure::new(RECORDS_CHECK, message))?;
+                let name = mate_name(record.name()).to_vec();
+
+                match first_mate.take() {
+                    None => first_mate = Some((processor.num_records, name)),
+                    Some((first_record, first_name)) => {
+                        if first_name != name {
+                            mismatched_pairs += 1;
+                            if first_mismatch_details.is_none() {
+                                first_mismatch_details = Some((
+                                    first_record,
+                                    String::from_utf8_lossy(&first_name).into_owned(),
+                                    String::from_utf8_lossy(&name).into_owned(),
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+
+            let mut outcome = processor.finalize();
+
+            if let Some((rec_num, r1_name, r2_name)) = first_mismatch_details {
+                outcome.errors.push(format!(
+                    "File contains {mismatched_pairs} pair(s) with mismatched mate names. First detected at records #{rec_num} and #{} ('{r1_name}' vs '{r2_name}').",
+                    rec_num + 1
+                ));
+            }
+            if let Some((rec_num, name)) = first_mate {
+                outcome.errors.push(format!(
+                    "Mismatched read counts: the last record #{rec_num} ('{}') has no mate.",
+                    String::from_utf8_lossy(&name)
+                ));
+            }
+            Ok(outcome)
+        },
+    )
+}
+
+/// Start of the pair error about R1 and R2 containing the same reads.
+pub const IDENTICAL_MATES: &str = "R1 and R2 contain the same reads";
+
 pub fn process_paired_readers<R1, R2>(
     reader1: R1,
     reader2: R2,
     length_check: ReadLengthCheck,
+    declared_read_lengths: (Option<DeclaredReadLength>, Option<DeclaredReadLength>),
+    settings: &CheckSettings,
+    record_progress: (Option<RecordProgress>, Option<RecordProgress>),
 ) -> Result<(CheckOutcome, CheckOutcome, Vec<String>), String>
 where
     R1: Read,
     R2: Read,
 {
+    let (reader1, line_format1) = LineFormat::inspect(reader1, settings.parse_leniency);
+    let (reader2, line_format2) = LineFormat::inspect(reader2, settings.parse_leniency);
     let mut fq1_reader = fastq::io::Reader::new(BufReader::new(reader1));
     let mut fq2_reader = fastq::io::Reader::new(BufReader::new(reader2));
 
-    let mut fq1_processor = FastqCheckProcessor::new(length_check);
-    let mut fq2_processor = FastqCheckProcessor::new(length_check);
+    let mut fq1_processor =
+        FastqCheckProcessor::new(length_check, declared_read_lengths.0, settings);
+    let mut fq2_processor =
+        FastqCheckProcessor::new(length_check, declared_read_lengths.1, settings);
+    fq1_processor.line_format = Some(line_format1);
+    fq2_processor.line_format = Some(line_format2);
+    fq1_processor.record_progress = record_progress.0;
+    fq2_processor.record_progress = record_progress.1;
     let mut pair_errors = Vec::new();
+    // Pairs whose mates have the same sequence and quality scores, as when R1 is submitted as
+    // both mates.
+    let mut identical_pairs: u64 = 0;
 
     for result in fq1_reader.records().zip_longest(fq2_reader.records()) {
         match result {
             Both(r1_res, r2_res) => {
-                fq1_processor.process_record(r1_res, "R1")?;
-                fq2_processor.process_record(r2_res, "R2")?;
+                let r1 = fq1_processor.process_record(r1_res, "R1")?;
+                let r2 = fq2_processor.process_record(r2_res, "R2")?;
+                if mate_name(r1.name()) != mate_name(r2.name()) {
+                    pair_errors.push(format!(
+                        "R1 and R2 have mismatched mate names at record #{} ('{}' vs '{}'); the files may be shuffled or not belong together.",
+                        fq1_processor.num_records,
+                        String::from_utf8_lossy(r1.name()),
+                        String::from_utf8_lossy(r2.name())
+                    ));
+                }
+                if r1.sequence() == r2.sequence() && r1.quality_scores() == r2.quality_scores() {
+                    identical_pairs += 1;
+                }
             }
             Left(r1_res) => {
                 fq1_processor.process_record(r1_res, "R1")?;
@@ -172,8 +1226,237 @@ where
         }
     }
 
+    if pair_errors.is_empty()
+        && identical_pairs > 0
+        && identical_pairs == fq1_processor.num_records
+        && identical_pairs == fq2_processor.num_records
+    {
+        pair_errors.push(format!(
+            "{IDENTICAL_MATES}: all {identical_pairs} pair(s) have identical sequences and quality scores. The same file was likely submitted as both mates."
+        ));
+    }
+
     let outcome1 = fq1_processor.finalize();
     let outcome2 = fq2_processor.finalize();
 
     Ok((outcome1, outcome2, pair_errors))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_base_count() {
+        assert_eq!(parse_base_count("1500000"), Ok(1_500_000));
+        assert_eq!(parse_base_count("12.5G"), Ok(12_500_000_000));
+        assert_eq!(parse_base_count("12.5Gb"), Ok(12_500_000_000));
+        assert_eq!(parse_base_count("300Mb"), Ok(300_000_000));
+        assert!(parse_base_count("Gb").is_err());
+        assert!(parse_base_count("0").is_err());
+        assert!(parse_base_count("12.5GB").is_err());
+    }
+
+    #[test]
+    fn test_find_invalid_base() {
+        assert_eq!(find_invalid_base(b"ACGTNRYKM", &IUPAC_BASES), None);
+        assert_eq!(
+            find_invalid_base(b"ACGTNRY", &STRICT_BASES_TABLE),
+            Some((5, b'R'))
+        );
+        assert_eq!(find_invalid_base(b"ACgT", &IUPAC_BASES), Some((2, b'g')));
+        assert_eq!(find_invalid_base(b"AC\0T", &IUPAC_BASES), Some((2, 0)));
+        assert_eq!(display_byte(b'g'), "'g'");
+        assert_eq!(display_byte(0), "0x00");
+    }
+
+    #[test]
+    fn test_adapter_counts() {
+        let screening = AdapterScreening {
+            adapters: DEFAULT_ADAPTERS
+                .iter()
+                .map(|(name, sequence)| parse_adapter(&format!("{name}={sequence}")).unwrap())
+                .collect(),
+            max_fraction: 0.25,
+        };
+        let mut counts = AdapterCounts::new(&screening);
+        counts.add(b"ACGTACGTAGATCGGAAGAGCACAC");
+        counts.add(b"CTGTCTCTTATAAGATCGGAAGAG");
+        counts.add(b"ACGTACGTACGTACGTACGTACGT");
+        counts.add(b"AGATCGGAAGA");
+        assert_eq!((counts.reads, counts.hits.clone()), (2, vec![2, 1]));
+        assert_eq!(counts.warning(8), None);
+        assert_eq!(
+            counts.warning(4).as_deref(),
+            Some(
+                "50.00% of reads (2 of 4) contain adapter sequences, above the maximum of 25.00%. Most frequent adapter: Illumina TruSeq (2 reads). The reads may not have been adapter-trimmed."
+            )
+        );
+        assert!(parse_adapter("custom=ACGN").is_err());
+        assert!(parse_adapter("ACGT").is_err());
+    }
+
+    #[test]
+    fn test_poly_g_counts() {
+        let screening = PolyGScreening {
+            min_length: NonZeroUsize::new(5).unwrap(),
+            max_fraction: 0.25,
+        };
+        let mut counts = PolyGCounts::new(&screening);
+        counts.add(b"ACGTACGTGGGGG");
+        counts.add(b"ACGTggggggg");
+        counts.add(b"ACGTGGGGA");
+        counts.add(b"GGGGGGGGAC");
+        assert_eq!(counts.reads, 2);
+        assert_eq!(counts.warning(8), None);
+        assert_eq!(
+            counts.warning(4).as_deref(),
+            Some(
+                "50.00% of reads (2 of 4) end in a poly-G tail of at least 5 bases, above the maximum of 25.00%. This is typical of signal loss on two-color instruments such as NovaSeq."
+            )
+        );
+    }
+
+    #[test]
+    fn test_casava_name_error() {
+        assert_eq!(
+            casava_name_error(b"A00123:8:HXXXXDSXX:1:1101:1000:2000", b"1:N:0:ACGT+TGCA"),
+            None
+        );
+        assert_eq!(
+            casava_name_error(b"A00123:8:HXXXXDSXX:1:1101:1000:2000", b""),
+            None
+        );
+        assert_eq!(
+            casava_name_error(b"A00123_8_HXXXXDSXX_1_1101_1000_2000", b"").as_deref(),
+            Some("expected 7 colon-separated fields, found 1")
+        );
+        assert_eq!(
+            casava_name_error(b"A00123:8:HXXXXDSXX:1:1101:x:2000", b"").as_deref(),
+            Some("run number, lane, tile and coordinates must be numbers")
+        );
+        assert_eq!(
+            casava_name_error(b"A00123:8:HXXXXDSXX:1:1101:1000:2000", b"1:X:0:1").as_deref(),
+            Some("comment must be read:filtered:control:index")
+        );
+
+        let settings = CheckSettings {
+            strict_read_names: true,
+            ..Default::default()
+        };
+        let mut processor = FastqCheckProcessor::new(ReadLengthCheck::Skip, None, &settings);
+        for name in ["A00123:8:HXXXXDSXX:1:1101:1000:2000", "r2", "r3"] {
+            let record =
+                fastq::Record::new(fastq::record::Definition::new(name, ""), "ACGT", "FFFF");
+            processor.process_record(Ok(record), "record").unwrap();
+        }
+        assert_eq!(
+            processor.finalize().errors,
+            vec![
+                "File contains 2 record(s) with read names not in Casava 1.8 format. First detected at record #2 ('r2'): expected 7 colon-separated fields, found 1."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_ids() {
+        assert_eq!(
+            illumina_run_id(b"A00123:8:HXXXXDSXX:1:1101:1000:2000"),
+            Some(&b"A00123:8:HXXXXDSXX"[..])
+        );
+        assert_eq!(illumina_run_id(b"SRR123.1"), None);
+        assert_eq!(illumina_run_id(b"a:b:c:d:e:f:g:h"), None);
+
+        let mut run_ids = RunIds::default();
+        for (i, name) in [
+            "A00123:8:HXXXXDSXX:1:1101:1000:2000",
+            "A00123:8:HXXXXDSXX:2:1101:1000:2000",
+            "read_without_run",
+        ]
+        .iter()
+        .enumerate()
+        {
+            run_ids.add(i as u64 + 1, name.as_bytes());
+        }
+        assert_eq!(run_ids.warning(), None);
+        run_ids.add(4, b"A00123:9:HYYYYDSXX:1:1101:1000:2000");
+        run_ids.add(5, b"A00123:8:HXXXXDSXX:1:1101:1000:2000");
+        assert_eq!(
+            run_ids.warning().as_deref(),
+            Some(
+                "Reads come from 2 different instrument:run:flowcell combinations (A00123:8:HXXXXDSXX, A00123:9:HYYYYDSXX), so the file may be a concatenation of several runs. First detected at record #4 ('A00123:9:HYYYYDSXX:1:1101:1000:2000')."
+            )
+        );
+    }
+
+    #[test]
+    fn test_umi_counts() {
+        let check = UmiCheck {
+            location: UmiLocation::Name,
+            pattern: crate::umi::UmiPattern::parse("[ACGTN]{8}").unwrap(),
+            min_fraction: 0.75,
+        };
+        let mut counts = UmiCounts::new(&check);
+        for (i, name) in [
+            "A00123:8:HXXXXDSXX:1:1101:1000:2000:ACGTACGT",
+            "A00123:8:HXXXXDSXX:1:1101:1000:2001:ACGTNNNN",
+            "A00123:8:HXXXXDSXX:1:1101:1000:2002",
+            "A00123:8:HXXXXDSXX:1:1101:1000:2003:ACGTAC",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let record =
+                fastq::Record::new(fastq::record::Definition::new(name, ""), "ACGT", "FFFF");
+            counts.add(i as u64 + 1, &record);
+        }
+        assert_eq!(counts.valid, 2);
+        assert_eq!(
+            counts.error(4).as_deref(),
+            Some(
+                "Only 50.00% of reads (2 of 4) have a valid UMI in the read name matching '[ACGTN]{8}', below the minimum of 75.00%. First detected at record #3 ('A00123:8:HXXXXDSXX:1:1101:1000:2002')."
+            )
+        );
+    }
+
+    #[test]
+    fn test_duplicate_read_names() {
+        let check = |interleaved: bool, names: &[&str]| {
+            let settings = CheckSettings {
+                duplicate_names_memory: Some(48),
+                ..Default::default()
+            };
+            let mut processor = FastqCheckProcessor::new(ReadLengthCheck::Skip, None, &settings);
+            processor.interleaved = interleaved;
+            for name in names {
+                let record =
+                    fastq::Record::new(fastq::record::Definition::new(*name, ""), "ACGT", "FFFF");
+                processor.process_record(Ok(record), "record").unwrap();
+            }
+            processor.finalize().errors
+        };
+        assert_eq!(
+            check(false, &["r1/1", "r2/1", "r3/1", "r1/2", "r2"]),
+            vec![
+                "File contains 2 record(s) with duplicate read names. First detected at record #4."
+            ]
+        );
+        assert!(check(true, &["r1/1", "r1/2", "r2/1", "r2/2"]).is_empty());
+    }
+
+    #[test]
+    fn test_quality_histogram() {
+        let mut histogram = QualityHistogram([0; 94]);
+        assert_eq!(histogram.stats(), None);
+        histogram.add(b"!!+5");
+        histogram.add(b"5\x07");
+        // '!' = 0, '+' = 10, '5' = 20; illegal characters are ignored
+        assert_eq!(
+            histogram.stats(),
+            Some(QualityStats {
+                mean: 10.0,
+                median: 10,
+            })
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use crate::archive::{self, Section};
+use crate::checker::{FileReport, Stats};
+use crate::checks::common::{
+    CheckFailure, CheckOutcome, CheckSettings, Decompression, Occurrences, check_file,
+};
+use crate::checks::dependencies::Check;
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+const INDEX_CHECK: &str = "gzi.index";
+const OFFSETS_CHECK: &str = "gzi.offsets";
+
+/// Checks of `.gzi` indexes of bgzipped files.
+pub const CHECKS: &[Check] = &[
+    Check::new(INDEX_CHECK, &[]),
+    Check::new(OFFSETS_CHECK, &[INDEX_CHECK]),
+];
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const BGZF_HEADER_SIZE: u64 = 18;
+
+/// Parses a `.gzi` index: the number of entries, followed by the compressed and uncompressed
+/// offset of the start of each indexed block, all as little-endian 64-bit integers.
+fn read_index(data: &[u8]) -> Result<Vec<(u64, u64)>, String> {
+    let read_u64 = |chunk: &[u8]| u64::from_le_bytes(chunk.try_into().unwrap());
+    if data.len() < 8 {
+        return Err(format!(
+            "File is too short to be a GZI index ({} bytes).",
+            data.len()
+        ));
+    }
+    let num_entries = read_u64(&data[..8]);
+    let expected_len = num_entries
+        .checked_mul(16)
+        .and_then(|len| len.checked_add(8));
+    if expected_len != Some(data.len() as u64) {
+        return Err(format!(
+            "GZI index declares {num_entries} entries but has {} bytes of entries, so it is truncated or has trailing data.",
+            data.len() - 8
+        ));
+    }
+    Ok(data[8..]
+        .chunks_exact(16)
+        .map(|entry| (read_u64(&entry[..8]), read_u64(&entry[8..])))
+        .collect())
+}
+
+/// Returns the size of the BGZF block at `position` and its uncompressed size.
+fn read_block(file: &mut Section, position: u64) -> Result<(u64, u64), String> {
+    let invalid = |reason: &str| format!("invalid BGZF block at offset {position}: {reason}");
+    if position + BGZF_HEADER_SIZE > file.size() {
+        return Err(invalid("truncated header"));
+    }
+    let header = file
+        .read_at(position, BGZF_HEADER_SIZE as usize)
+        .map_err(|e| e.to_string())?;
+    // gzip header with FEXTRA set, followed by the `BC` subfield holding the block size
+    if header[..2] != GZIP_MAGIC || header[3] & 4 == 0 || header[12..14] != *b"BC" {
+        return Err(invalid("not a BGZF block header"));
+    }
+    let block_size = u64::from(u16::from_le_bytes([header[16], header[17]])) + 1;
+    if block_size < BGZF_HEADER_SIZE + 8 || position + block_size > file.size() {
+        return Err(invalid("truncated block"));
+    }
+    let trailer = file
+        .read_at(position + block_size - 4, 4)
+        .map_err(|e| e.to_string())?;
+    let uncompressed_size = u32::from_le_bytes(trailer.try_into().unwrap());
+    Ok((block_size, u64::from(uncompressed_size)))
+}
+
+/// Walks the BGZF blocks of the data file and checks that every indexed offset pair is the
+/// start of a block.
+fn check_offsets(
+    entries: &[(u64, u64)],
+    data_path: &Path,
+    errors: &mut Vec<String>,
+) -> Result<(), String> {
+    let mut file = archive::open_section(data_path).map_err(|e| e.to_string())?;
+    let size = file.size();
+
+    let mut unsorted = Occurrences::default();
+    for (i, window) in entries.windows(2).enumerate() {
+        let ((c1, u1), (c2, u2)) = (window[0], window[1]);
+        if c2 <= c1 || u2 < u1 {
+            unsorted.add(|| format!("entry #{} ({c2}:{u2} after {c1}:{u1})", i + 2));
+        }
+    }
+    if let Some(first) = unsorted.first {
+        errors.push(format!(
+            "Index contains {} entry(ies) that are not in increasing order of offsets. First detected at {first}.",
+            unsorted.count
+        ));
+        // the entries cannot be matched to blocks in one pass
+        return Ok(());
+    }
+
+    let mut misaligned = Occurrences::default();
+    let mut mismatched = Occurrences::default();
+    let mut out_of_bounds = Occurrences::default();
+    let mut remaining = entries.iter().enumerate().peekable();
+    let (mut compressed, mut uncompressed) = (0, 0);
+    while compressed < size {
+        let (block_size, uncompressed_size) = read_block(&mut file, compressed)?;
+        while let Some(&(i, &(c, u))) = remaining.peek()
+            && c <= compressed
+        {
+            if c < compressed {
+                misaligned.add(|| format!("entry #{} (compressed offset {c})", i + 1));
+            } else if u != uncompressed {
+                mismatched.add(|| {
+                    format!(
+                        "entry #{} (uncompressed offset {u}, block starts at {uncompressed})",
+                        i + 1
+                    )
+                });
+            }
+            remaining.next();
+        }
+        compressed += block_size;
+        uncompressed += uncompressed_size;
+    }
+    for (i, &(c, _)) in remaining {
+        out_of_bounds.add(|| format!("entry #{} (compressed offset {c})", i + 1));
+    }
+
+    if let Some(first) = out_of_bounds.first {
+        errors.push(format!(
+            "Index contains {} entry(ies) beyond the end of the data file ({size} bytes), so the index may be stale. First detected at {first}.",
+            out_of_bounds.count
+        ));
+    }
+    if let Some(first) = misaligned.first {
+        errors.push(format!(
+            "Index contains {} compressed offset(s) that are not the start of a BGZF block. First detected at {first}.",
+            misaligned.count
+        ));
+    }
+    if let Some(first) = mismatched.first {
+        errors.push(format!(
+            "Index contains {} uncompressed offset(s) that do not match the data preceding their block. First detected at {first}.",
+            mismatched.count
+        ));
+    }
+    Ok(())
+}
+
+pub fn check_gzi(
+    path: &Path,
+    data_path: &Path,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::None,
+        CHECKS,
+        |reader| {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).map_err(|e: io::Error| {
+                CheckFailure::new(INDEX_CHECK, format!("Failed to read GZI index: {e}"))
+            })?;
+            let entries =
+                read_index(&data).map_err(|message| CheckFailure::new(INDEX_CHECK, message))?;
+
+            let mut errors = Vec::new();
+            if let Err(e) = check_offsets(&entries, data_path, &mut errors) {
+                errors.push(format!(
+                    "Failed to walk BGZF blocks of data file {}: {e}",
+                    data_path.display()
+                ));
+            }
+
+            Ok(CheckOutcome {
+                stats: Some(Stats {
+                    num_records: entries.len() as u64,
+                    total_read_length: None,
+                    modal_read_length: None,
+                    min_read_length: None,
+                    max_read_length: None,
+                    gvcf: None,
+                    adapter_reads: None,
+                    poly_g_reads: None,
+                    umi_reads: None,
+                    duplicate_records: None,
+                    mapped_records: None,
+                    bases: None,
+                    quality: None,
+                }),
+                errors,
+                warnings: vec![],
+                skipped_checks: vec![],
+            })
+        },
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GziCheckJob {
+    /// Path of the `.gzi` index.
+    pub path: PathBuf,
+    /// Bgzipped file the index belongs to.
+    pub data_path: PathBuf,
+    pub size: u64,
+}
@@ -1,5 +1,14 @@
 pub mod bam;
+pub mod bed;
+pub mod dependencies;
+pub mod fasta;
 pub mod fastq;
+pub mod gzi;
 pub mod raw;
+pub mod reference;
+pub mod sam;
+pub mod signal;
+pub mod tabix;
+pub mod vcf;
 
 pub mod common;
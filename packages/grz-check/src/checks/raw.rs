@@ -1,20 +1,65 @@
 use crate::checker::FileReport;
-use crate::checks::common::{CheckOutcome, check_file};
+use crate::checks::common::{CheckFailure, CheckOutcome, CheckSettings, Decompression, check_file};
+use crate::checks::dependencies::{Check, READ_CHECK};
 use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
 use std::io;
 use std::path::{Path, PathBuf};
 
-pub fn check_raw(path: &Path, file_pb: &ProgressBar, global_pb: &ProgressBar) -> FileReport {
-    check_file(path, file_pb, global_pb, false, |reader| {
-        match io::copy(reader, &mut io::sink()) {
-            Ok(_) => Ok(CheckOutcome::default()),
-            Err(e) => Err(format!("Failed to read file: {e}")),
-        }
-    })
+/// Checks of raw files; the file format is never validated.
+pub const CHECKS: &[Check] = &[Check::new("format", &[])];
+
+pub fn check_raw(
+    path: &Path,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::None,
+        CHECKS,
+        |reader| match io::copy(reader, &mut io::sink()) {
+            Ok(_) => Ok(CheckOutcome {
+                skipped_checks: vec!["format"],
+                ..Default::default()
+            }),
+            Err(e) => Err(CheckFailure::new(
+                READ_CHECK,
+                format!("Failed to read file: {e}"),
+            )),
+        },
+    )
+}
+
+/// Checks a raw file listed in a checksum manifest against the digest given there.
+pub fn check_listed(
+    path: &Path,
+    expected_sha256: &str,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    let mut report = check_raw(path, settings, file_pb, global_pb);
+    report.expected_sha256 = Some(expected_sha256.to_string());
+    if let Some(sha256) = &report.sha256
+        && !sha256.eq_ignore_ascii_case(expected_sha256)
+    {
+        report.errors.push(format!(
+            "SHA-256 digest {sha256} differs from the digest {expected_sha256} listed in the manifest."
+        ));
+    }
+    report
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct RawJob {
     pub path: PathBuf,
     pub size: u64,
+    /// Digest listed in a checksum manifest, for jobs created by `verify`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_sha256: Option<String>,
 }
@@ -0,0 +1,420 @@
+use crate::checks::common::CheckOutcome;
+use md5::{Digest, Md5};
+use noodles::sam;
+use noodles::sam::header::record::value::map::reference_sequence::tag;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Species whose reference genome the aligned files of a submission are expected to use.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Species {
+    Human,
+    Mouse,
+}
+
+struct Assembly {
+    name: &'static str,
+    chr1_length: usize,
+}
+
+struct ReferenceProfile {
+    species: Species,
+    num_autosomes: usize,
+    assemblies: &'static [Assembly],
+    /// Plausible range of the summed length of all reference sequences of a whole-genome reference.
+    genome_length: (u64, u64),
+}
+
+/// Name of the reference plausibility check, as listed in skipped checks.
+pub const PLAUSIBILITY_CHECK: &str = "reference.plausibility";
+
+const PROFILES: &[ReferenceProfile] = &[
+    ReferenceProfile {
+        species: Species::Human,
+        num_autosomes: 22,
+        assemblies: &[
+            Assembly {
+                name: "GRCh37",
+                chr1_length: 249_250_621,
+            },
+            Assembly {
+                name: "GRCh38",
+                chr1_length: 248_956_422,
+            },
+            Assembly {
+                name: "T2T-CHM13",
+                chr1_length: 248_387_328,
+            },
+        ],
+        genome_length: (2_950_000_000, 3_600_000_000),
+    },
+    ReferenceProfile {
+        species: Species::Mouse,
+        num_autosomes: 19,
+        assemblies: &[
+            Assembly {
+                name: "GRCm38",
+                chr1_length: 195_471_971,
+            },
+            Assembly {
+                name: "GRCm39",
+                chr1_length: 195_154_279,
+            },
+        ],
+        genome_length: (2_500_000_000, 2_950_000_000),
+    },
+];
+
+impl Species {
+    fn profile(self) -> &'static ReferenceProfile {
+        PROFILES
+            .iter()
+            .find(|p| p.species == self)
+            .expect("Every species should have a reference profile")
+    }
+}
+
+impl fmt::Display for Species {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Species::Human => write!(f, "human"),
+            Species::Mouse => write!(f, "mouse"),
+        }
+    }
+}
+
+fn strip_chr_prefix(name: &str) -> &str {
+    match name.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("chr") => &name[3..],
+        _ => name,
+    }
+}
+
+/// Sanity-checks the reference sequence dictionary (@SQ lines) of a header against the expected species.
+///
+/// Headers without reference sequences (e.g. unaligned BAM) are not checked.
+pub fn check_reference_sequences(header: &sam::Header, species: Species) -> CheckOutcome {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let reference_sequences = header.reference_sequences();
+    if reference_sequences.is_empty() {
+        return CheckOutcome {
+            skipped_checks: vec![PLAUSIBILITY_CHECK],
+            ..Default::default()
+        };
+    }
+
+    let profile = species.profile();
+
+    let mut chr1_length = None;
+    let mut numbered_chromosomes = Vec::new();
+    let mut genome_length: u64 = 0;
+
+    for (name, reference_sequence) in reference_sequences {
+        let length = usize::from(reference_sequence.length());
+        genome_length += length as u64;
+
+        let name = String::from_utf8_lossy(name);
+        if let Ok(number) = strip_chr_prefix(&name).parse::<usize>()
+            && number > 0
+        {
+            numbered_chromosomes.push(number);
+            if number == 1 {
+                chr1_length = Some(length);
+            }
+        }
+    }
+    numbered_chromosomes.sort_unstable();
+    numbered_chromosomes.dedup();
+
+    let Some(&max_chromosome) = numbered_chromosomes.last() else {
+        warnings.push(format!(
+            "None of the {} reference sequence(s) in the header use {species} chromosome names (e.g. 'chr1' or '1'); skipping reference plausibility check.",
+            reference_sequences.len()
+        ));
+        return CheckOutcome {
+            warnings,
+            skipped_checks: vec![PLAUSIBILITY_CHECK],
+            ..Default::default()
+        };
+    };
+
+    let foreign: Vec<String> = numbered_chromosomes
+        .iter()
+        .filter(|&&n| n > profile.num_autosomes)
+        .map(|n| n.to_string())
+        .collect();
+    if !foreign.is_empty() {
+        errors.push(format!(
+            "Reference sequences do not look like a {species} reference: chromosome(s) {} do not exist in {species}.",
+            foreign.join(", ")
+        ));
+    }
+
+    // A reference containing exactly the autosomes of another species (e.g. chr1-chr19 of
+    // mouse when human is expected) is a strong hint for a mix-up.
+    let is_contiguous = numbered_chromosomes.len() == max_chromosome;
+    if is_contiguous
+        && max_chromosome < profile.num_autosomes
+        && let Some(other) = PROFILES
+            .iter()
+            .find(|p| p.species != species && p.num_autosomes == max_chromosome)
+    {
+        errors.push(format!(
+            "Reference sequences do not look like a {species} reference: header contains chromosomes 1-{max_chromosome} but not {}-{}, matching a {} reference.",
+            max_chromosome + 1,
+            profile.num_autosomes,
+            other.species
+        ));
+    }
+    let is_whole_genome = is_contiguous && max_chromosome == profile.num_autosomes;
+
+    if let Some(length) = chr1_length
+        && !profile.assemblies.iter().any(|a| a.chr1_length == length)
+    {
+        let known = profile
+            .assemblies
+            .iter()
+            .map(|a| format!("{}: {}", a.name, a.chr1_length))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let other_species = PROFILES
+            .iter()
+            .filter(|p| p.species != species)
+            .find_map(|p| {
+                p.assemblies
+                    .iter()
+                    .find(|a| a.chr1_length == length)
+                    .map(|a| format!(" It matches {} ({}).", p.species, a.name))
+            })
+            .unwrap_or_default();
+        errors.push(format!(
+            "Length of chromosome 1 ({length}) does not match any known {species} assembly ({known}).{other_species}"
+        ));
+    }
+
+    let (min_length, max_length) = profile.genome_length;
+    if is_whole_genome && !(min_length..=max_length).contains(&genome_length) {
+        errors.push(format!(
+            "Total length of reference sequences ({genome_length}) is outside the plausible range for a {species} genome ({min_length}-{max_length})."
+        ));
+    }
+
+    CheckOutcome {
+        errors,
+        warnings,
+        ..Default::default()
+    }
+}
+
+/// Length and MD5 digest of a sequence of the reference given via `--bam-reference`.
+#[derive(Debug)]
+struct SequenceDigest {
+    length: u64,
+    md5: String,
+}
+
+/// Reference given via `--bam-reference`, whose digests are computed by the first file that
+/// needs them.
+#[derive(Debug)]
+pub struct Md5Reference {
+    path: PathBuf,
+    digests: OnceLock<Result<HashMap<String, SequenceDigest>, String>>,
+}
+
+impl Md5Reference {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            digests: OnceLock::new(),
+        }
+    }
+
+    /// Returns errors if the reference sequences of a header do not match the reference: sequences
+    /// must be in the reference, and their `M5` tags, or their lengths if they have none, must
+    /// match the sequences of the reference.
+    pub fn errors(&self, header: &sam::Header) -> Vec<String> {
+        if header.reference_sequences().is_empty() {
+            return Vec::new();
+        }
+        match self
+            .digests
+            .get_or_init(|| read_sequence_digests(&self.path))
+        {
+            Ok(digests) => compare_sequences(header, &self.path, digests),
+            Err(e) => vec![format!(
+                "Failed to read reference {}: {e}",
+                self.path.display()
+            )],
+        }
+    }
+}
+
+/// Maximum number of reference sequences listed for each kind of mismatch.
+const MAX_MISMATCH_EXAMPLES: usize = 3;
+
+/// Computes the length and MD5 digest of each sequence of a FASTA file, optionally compressed.
+/// Like the `M5` tag of @SQ lines, digests are computed over the uppercased sequence without
+/// whitespace.
+fn read_sequence_digests(path: &Path) -> Result<HashMap<String, SequenceDigest>, String> {
+    let (reader, _) = niffler::from_path(path).map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(reader);
+    let mut digests = HashMap::new();
+    let mut current: Option<(String, u64, Md5)> = None;
+    let mut finish = |current: Option<(String, u64, Md5)>| {
+        if let Some((name, length, hasher)) = current {
+            let md5 = format!("{:x}", hasher.finalize());
+            digests.insert(name, SequenceDigest { length, md5 });
+        }
+    };
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| e.to_string())?
+            == 0
+        {
+            break;
+        }
+        if let Some(definition) = line.strip_prefix(b">") {
+            let definition = String::from_utf8_lossy(definition);
+            let name = definition.split_whitespace().next().unwrap_or_default();
+            finish(current.replace((name.to_string(), 0, Md5::new())));
+        } else if let Some((_, length, hasher)) = &mut current {
+            let bases: Vec<u8> = line
+                .iter()
+                .filter(|base| base.is_ascii_graphic())
+                .map(u8::to_ascii_uppercase)
+                .collect();
+            *length += bases.len() as u64;
+            hasher.update(&bases);
+        }
+    }
+    finish(current);
+    Ok(digests)
+}
+
+/// Formats the first few `examples` of a mismatch, noting how many are not listed.
+fn list_examples(examples: &[String]) -> String {
+    let mut listed = examples[..examples.len().min(MAX_MISMATCH_EXAMPLES)].join(", ");
+    if examples.len() > MAX_MISMATCH_EXAMPLES {
+        listed.push_str(&format!(
+            " and {} more",
+            examples.len() - MAX_MISMATCH_EXAMPLES
+        ));
+    }
+    listed
+}
+
+fn compare_sequences(
+    header: &sam::Header,
+    reference: &Path,
+    digests: &HashMap<String, SequenceDigest>,
+) -> Vec<String> {
+    let mut missing = Vec::new();
+    let mut different_md5 = Vec::new();
+    let mut different_length = Vec::new();
+    for (name, reference_sequence) in header.reference_sequences() {
+        let name = String::from_utf8_lossy(name);
+        let Some(digest) = digests.get(name.as_ref()) else {
+            missing.push(format!("'{name}'"));
+            continue;
+        };
+        let length = usize::from(reference_sequence.length()) as u64;
+        match reference_sequence.other_fields().get(&tag::MD5_CHECKSUM) {
+            Some(md5) if !md5.to_string().eq_ignore_ascii_case(&digest.md5) => {
+                different_md5.push(format!("'{name}' (M5 {md5}, reference {})", digest.md5));
+            }
+            Some(_) => {}
+            None if length != digest.length => {
+                different_length.push(format!(
+                    "'{name}' (LN {length}, reference {})",
+                    digest.length
+                ));
+            }
+            None => {}
+        }
+    }
+
+    let reference = reference.display();
+    let mut errors = Vec::new();
+    if !different_md5.is_empty() {
+        errors.push(format!(
+            "{} reference sequence(s) of the header have an M5 checksum different from the sequence in {reference}: {}. The file was likely aligned to another build of the reference.",
+            different_md5.len(),
+            list_examples(&different_md5)
+        ));
+    }
+    if !different_length.is_empty() {
+        errors.push(format!(
+            "{} reference sequence(s) of the header have a length different from the sequence in {reference}: {}. The file was likely aligned to another build of the reference.",
+            different_length.len(),
+            list_examples(&different_length)
+        ));
+    }
+    if !missing.is_empty() {
+        errors.push(format!(
+            "{} reference sequence(s) of the header are not in {reference}: {}. The file was likely aligned to another reference.",
+            missing.len(),
+            list_examples(&missing)
+        ));
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_reference_sequence_md5s() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let fasta = dir.path().join("ref.fa");
+        fs::write(&fasta, ">chr1 first\nACGT\nacgt\n>chr2\nNNNN\n")?;
+        let digests = read_sequence_digests(&fasta).map_err(anyhow::Error::msg)?;
+        assert_eq!(digests["chr1"].length, 8);
+        assert_eq!(
+            digests["chr1"].md5,
+            format!("{:x}", Md5::digest(b"ACGTACGT"))
+        );
+
+        let matching: sam::Header = format!(
+            "@SQ\tSN:chr1\tLN:8\tM5:{}\n@SQ\tSN:chr2\tLN:4\n",
+            digests["chr1"].md5.to_uppercase()
+        )
+        .parse()?;
+        assert!(compare_sequences(&matching, &fasta, &digests).is_empty());
+
+        let other_build: sam::Header = "@SQ\tSN:chr1\tLN:8\tM5:0123456789abcdef0123456789abcdef\n\
+             @SQ\tSN:chr2\tLN:5\n\
+             @SQ\tSN:chrM\tLN:16569\n"
+            .parse()?;
+        let errors = compare_sequences(&other_build, &fasta, &digests);
+        let reference = fasta.display();
+        assert_eq!(
+            errors,
+            vec![
+                format!(
+                    "1 reference sequence(s) of the header have an M5 checksum different from the sequence in {reference}: 'chr1' (M5 0123456789abcdef0123456789abcdef, reference {}). The file was likely aligned to another build of the reference.",
+                    digests["chr1"].md5
+                ),
+                format!(
+                    "1 reference sequence(s) of the header have a length different from the sequence in {reference}: 'chr2' (LN 5, reference 4). The file was likely aligned to another build of the reference."
+                ),
+                format!(
+                    "1 reference sequence(s) of the header are not in {reference}: 'chrM'. The file was likely aligned to another reference."
+                ),
+            ]
+        );
+        Ok(())
+    }
+}
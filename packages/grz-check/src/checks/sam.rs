@@ -0,0 +1,53 @@
+use crate::checker::FileReport;
+use crate::checks::bam::{self, check_alignments};
+use crate::checks::common::{CheckFailure, CheckSettings, Decompression, check_file};
+use crate::checks::reference::Species;
+use indicatif::ProgressBar;
+use noodles::sam;
+use serde::{Deserialize, Serialize};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+pub fn check_sam(
+    path: &Path,
+    species: Option<Species>,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::Auto,
+        bam::CHECKS,
+        |reader| {
+            let mut sam_reader = sam::io::Reader::new(BufReader::new(reader));
+            let header = match sam_reader.read_header() {
+                Ok(h) => h,
+                Err(e) => {
+                    return Err(CheckFailure::new(
+                        bam::HEADER_CHECK,
+                        format!("Failed to read SAM header: {e}"),
+                    ));
+                }
+            };
+            check_alignments(
+                &header,
+                sam_reader.records(),
+                species,
+                false,
+                settings,
+                "SAM",
+            )
+        },
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SamCheckJob {
+    pub path: PathBuf,
+    pub species: Option<Species>,
+    pub size: u64,
+}
@@ -0,0 +1,738 @@
+//! Structural checks of nanopore raw signal files (POD5 and FAST5).
+//!
+//! POD5 files are a signature, a section marker, several embedded Arrow IPC files (the reads,
+//! signal and run info tables) and a FlatBuffers footer listing them, followed by the footer
+//! length, the section marker and the signature again. The footers are read by seeking, so only
+//! a few kilobytes are read besides the checksummed stream.
+//!
+//! FAST5 files are HDF5 files. Only the superblock is validated; the read groups are not.
+
+use crate::archive::{self, Section};
+use crate::checker::{FileReport, Stats};
+use crate::checks::common::{CheckFailure, CheckOutcome, CheckSettings, Decompression, check_file};
+use crate::checks::dependencies::{Check, READ_CHECK};
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+const POD5_SIGNATURE_CHECK: &str = "pod5.signature";
+const POD5_FOOTER_CHECK: &str = "pod5.footer";
+const POD5_TABLES_CHECK: &str = "pod5.tables";
+const POD5_READ_COUNT_CHECK: &str = "pod5.read_count";
+
+/// Checks of POD5 files.
+pub const POD5_CHECKS: &[Check] = &[
+    Check::new(POD5_SIGNATURE_CHECK, &[]),
+    Check::new(POD5_FOOTER_CHECK, &[POD5_SIGNATURE_CHECK]),
+    Check::new(POD5_TABLES_CHECK, &[POD5_FOOTER_CHECK]),
+    Check::new(POD5_READ_COUNT_CHECK, &[POD5_TABLES_CHECK]),
+];
+
+const FAST5_SIGNATURE_CHECK: &str = "fast5.signature";
+const FAST5_SUPERBLOCK_CHECK: &str = "fast5.superblock";
+const FAST5_GROUPS_CHECK: &str = "fast5.groups";
+
+/// Checks of FAST5 files. The read groups are never validated.
+pub const FAST5_CHECKS: &[Check] = &[
+    Check::new(FAST5_SIGNATURE_CHECK, &[]),
+    Check::new(FAST5_SUPERBLOCK_CHECK, &[FAST5_SIGNATURE_CHECK]),
+    Check::new(FAST5_GROUPS_CHECK, &[FAST5_SIGNATURE_CHECK]),
+];
+
+const POD5_SIGNATURE: &[u8; 8] = b"\x8bPOD\r\n\x1a\n";
+const SECTION_MARKER_LEN: u64 = 16;
+const FOOTER_MAGIC: &[u8; 8] = b"FOOTER\0\0";
+const ARROW_MAGIC: &[u8; 6] = b"ARROW1";
+/// Signature, section marker, footer length, section marker and signature.
+const POD5_MIN_SIZE: u64 = 8 + SECTION_MARKER_LEN + 8 + SECTION_MARKER_LEN + 8;
+
+/// Content types of the tables embedded in a POD5 file, as declared in its footer.
+const READS_TABLE: i16 = 0;
+const SIGNAL_TABLE: i16 = 1;
+
+/// Type of the `RecordBatch` member of the Arrow `MessageHeader` union.
+const ARROW_RECORD_BATCH: u8 = 3;
+
+const HDF5_SIGNATURE: &[u8; 8] = b"\x89HDF\r\n\x1a\n";
+/// Address of undefined fields in an HDF5 superblock.
+const HDF5_UNDEFINED_ADDRESS: u64 = u64::MAX;
+
+fn read_u16(buf: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(buf.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(buf: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(buf.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn read_u64(buf: &[u8], pos: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(buf.get(pos..pos + 8)?.try_into().ok()?))
+}
+
+/// Minimal reader of FlatBuffers tables, enough to walk the POD5 and Arrow footers.
+///
+/// Absent scalar fields read as zero, their default value.
+#[derive(Clone, Copy)]
+struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Table<'a> {
+    fn root(buf: &'a [u8]) -> Option<Self> {
+        let pos = read_u32(buf, 0)? as usize;
+        Some(Self { buf, pos })
+    }
+
+    /// Follows the offset stored at `pos`.
+    fn at_offset(buf: &'a [u8], pos: usize) -> Option<Self> {
+        let pos = pos.checked_add(read_u32(buf, pos)? as usize)?;
+        Some(Self { buf, pos })
+    }
+
+    fn field_pos(&self, index: usize) -> Option<usize> {
+        let vtable_offset = read_u32(self.buf, self.pos)? as i32;
+        let vtable = usize::try_from(self.pos as i64 - i64::from(vtable_offset)).ok()?;
+        let entry = 4 + 2 * index;
+        if entry + 2 > read_u16(self.buf, vtable)? as usize {
+            return None;
+        }
+        match read_u16(self.buf, vtable + entry)? {
+            0 => None,
+            offset => Some(self.pos + offset as usize),
+        }
+    }
+
+    fn u8(&self, index: usize) -> u8 {
+        self.field_pos(index)
+            .and_then(|pos| self.buf.get(pos).copied())
+            .unwrap_or(0)
+    }
+
+    fn i16(&self, index: usize) -> i16 {
+        self.field_pos(index)
+            .and_then(|pos| read_u16(self.buf, pos))
+            .map_or(0, |value| value as i16)
+    }
+
+    fn i64(&self, index: usize) -> i64 {
+        self.field_pos(index)
+            .and_then(|pos| read_u64(self.buf, pos))
+            .map_or(0, |value| value as i64)
+    }
+
+    fn table(&self, index: usize) -> Option<Table<'a>> {
+        Self::at_offset(self.buf, self.field_pos(index)?)
+    }
+
+    /// Returns the start and the number of elements of a vector field.
+    fn vector(&self, index: usize, element_size: usize) -> Option<(usize, usize)> {
+        let vector = Self::at_offset(self.buf, self.field_pos(index)?)?.pos;
+        let len = read_u32(self.buf, vector)? as usize;
+        let start = vector + 4;
+        (start.checked_add(len.checked_mul(element_size)?)? <= self.buf.len())
+            .then_some((start, len))
+    }
+
+    fn tables(&self, index: usize) -> Option<Vec<Table<'a>>> {
+        let (start, len) = self.vector(index, 4)?;
+        (0..len)
+            .map(|i| Self::at_offset(self.buf, start + 4 * i))
+            .collect()
+    }
+}
+
+/// A table embedded in a POD5 file.
+#[derive(Debug, Clone, Copy)]
+struct EmbeddedTable {
+    offset: u64,
+    length: u64,
+    content_type: i16,
+}
+
+fn table_name(content_type: i16) -> &'static str {
+    match content_type {
+        READS_TABLE => "reads",
+        SIGNAL_TABLE => "signal",
+        2 => "read ID index",
+        4 => "run info",
+        _ => "other",
+    }
+}
+
+/// Number of rows of the reads and signal tables of a POD5 file.
+#[derive(Debug, PartialEq, Eq)]
+struct Pod5Counts {
+    reads: u64,
+    signal_rows: u64,
+}
+
+fn io_failure(check: &'static str) -> impl Fn(io::Error) -> CheckFailure {
+    move |e| CheckFailure::new(check, format!("Failed to read file: {e}"))
+}
+
+/// Reads the footer of a POD5 file, returning its position and the embedded tables.
+fn read_pod5_footer(file: &mut Section) -> Result<(u64, Vec<EmbeddedTable>), CheckFailure> {
+    let size = file.size();
+    if size < POD5_MIN_SIZE {
+        return Err(CheckFailure::new(
+            POD5_SIGNATURE_CHECK,
+            format!("File is too short to be a POD5 file ({size} bytes)."),
+        ));
+    }
+    let read_failure = io_failure(POD5_SIGNATURE_CHECK);
+    let head = file.read_at(0, 24).map_err(&read_failure)?;
+    let tail = file.read_at(size - 32, 32).map_err(&read_failure)?;
+    if &head[..8] != POD5_SIGNATURE {
+        return Err(CheckFailure::new(
+            POD5_SIGNATURE_CHECK,
+            "POD5 signature is missing at the start of the file.",
+        ));
+    }
+    if &tail[24..] != POD5_SIGNATURE {
+        return Err(CheckFailure::new(
+            POD5_SIGNATURE_CHECK,
+            "POD5 signature is missing at the end of the file; the file may be truncated.",
+        ));
+    }
+    if head[8..24] != tail[8..24] {
+        return Err(CheckFailure::new(
+            POD5_SIGNATURE_CHECK,
+            "POD5 section markers at the start and the end of the file differ.",
+        ));
+    }
+
+    // The footer is preceded by a magic and directly followed by its length, which may or may
+    // not include the padding to a multiple of 8 bytes.
+    let footer_end = size - 32;
+    let footer_length = read_u64(&tail, 0).unwrap_or_default();
+    let max_length = footer_end.saturating_sub(24 + FOOTER_MAGIC.len() as u64);
+    if footer_length == 0 || footer_length > max_length {
+        return Err(CheckFailure::new(
+            POD5_FOOTER_CHECK,
+            format!("POD5 footer length {footer_length} is out of range."),
+        ));
+    }
+    let mut footer_start = None;
+    for length in [footer_length, footer_length.next_multiple_of(8)] {
+        let Some(start) = footer_end.checked_sub(length).filter(|&start| start >= 32) else {
+            continue;
+        };
+        let magic = file
+            .read_at(start - 8, 8)
+            .map_err(io_failure(POD5_FOOTER_CHECK))?;
+        if magic == FOOTER_MAGIC {
+            footer_start = Some(start);
+            break;
+        }
+    }
+    let Some(footer_start) = footer_start else {
+        return Err(CheckFailure::new(
+            POD5_FOOTER_CHECK,
+            "POD5 footer not found before the footer length.",
+        ));
+    };
+    let footer = file
+        .read_at(footer_start, footer_length as usize)
+        .map_err(io_failure(POD5_FOOTER_CHECK))?;
+    let tables = Table::root(&footer)
+        .and_then(|footer| footer.tables(3))
+        .ok_or_else(|| {
+            CheckFailure::new(
+                POD5_FOOTER_CHECK,
+                "POD5 footer is malformed: the list of embedded tables is unreadable.",
+            )
+        })?
+        .into_iter()
+        .map(|table| EmbeddedTable {
+            offset: table.i64(0) as u64,
+            length: table.i64(1) as u64,
+            content_type: table.i16(3),
+        })
+        .collect();
+    Ok((footer_start - 8, tables))
+}
+
+/// Sums the lengths of the record batches of an embedded Arrow IPC file.
+fn count_rows(file: &mut Section, table: &EmbeddedTable) -> Result<u64, String> {
+    let end = table.offset + table.length;
+    let trailer = file.read_at(end - 10, 10).map_err(|e| e.to_string())?;
+    let footer_length = u64::from(read_u32(&trailer, 0).unwrap_or_default());
+    if footer_length + 10 + 8 > table.length {
+        return Err(format!("footer length {footer_length} is out of range"));
+    }
+    let footer = file
+        .read_at(end - 10 - footer_length, footer_length as usize)
+        .map_err(|e| e.to_string())?;
+
+    // Blocks are structs of the offset, the metadata length (padded to 8 bytes) and the body
+    // length of each record batch.
+    const BLOCK_SIZE: usize = 24;
+    let root = Table::root(&footer).ok_or("footer is unreadable")?;
+    let (start, len) = root
+        .vector(3, BLOCK_SIZE)
+        .ok_or("list of record batches is unreadable")?;
+    let mut rows = 0;
+    for i in 0..len {
+        let block = start + BLOCK_SIZE * i;
+        let offset = read_u64(&footer, block).unwrap_or_default();
+        let metadata_length = u64::from(read_u32(&footer, block + 8).unwrap_or_default());
+        if offset.saturating_add(metadata_length) > table.length {
+            return Err(format!("record batch {i} lies outside the table"));
+        }
+        let metadata = file
+            .read_at(table.offset + offset, metadata_length as usize)
+            .map_err(|e| e.to_string())?;
+        // Messages start with a continuation marker in current versions of the format.
+        let message = match read_u32(&metadata, 0) {
+            Some(u32::MAX) => metadata.get(8..),
+            _ => metadata.get(4..),
+        };
+        let batch = message
+            .and_then(Table::root)
+            .filter(|message| message.u8(1) == ARROW_RECORD_BATCH)
+            .and_then(|message| message.table(2))
+            .ok_or_else(|| format!("record batch {i} is unreadable"))?;
+        rows += batch.i64(0) as u64;
+    }
+    Ok(rows)
+}
+
+/// Validates the structure of a POD5 file and counts its reads.
+fn check_pod5_structure(path: &Path) -> Result<Pod5Counts, CheckFailure> {
+    let mut file = archive::open_section(path).map_err(io_failure(READ_CHECK))?;
+    let (footer_start, tables) = read_pod5_footer(&mut file)?;
+
+    for (i, table) in tables.iter().enumerate() {
+        let in_bounds = table.offset >= 24
+            && table.length >= 2 * ARROW_MAGIC.len() as u64
+            && table
+                .offset
+                .checked_add(table.length)
+                .is_some_and(|end| end <= footer_start);
+        if !in_bounds {
+            return Err(CheckFailure::new(
+                POD5_TABLES_CHECK,
+                format!(
+                    "POD5 table {i} at offset {} with length {} lies outside the file.",
+                    table.offset, table.length
+                ),
+            ));
+        }
+        let read_failure = io_failure(POD5_TABLES_CHECK);
+        let head = file
+            .read_at(table.offset, ARROW_MAGIC.len())
+            .map_err(&read_failure)?;
+        let tail = file
+            .read_at(table.offset + table.length - 6, ARROW_MAGIC.len())
+            .map_err(&read_failure)?;
+        if head != ARROW_MAGIC || tail != ARROW_MAGIC {
+            return Err(CheckFailure::new(
+                POD5_TABLES_CHECK,
+                format!(
+                    "POD5 table {i} ({}) at offset {} is not an Arrow IPC file.",
+                    table_name(table.content_type),
+                    table.offset
+                ),
+            ));
+        }
+    }
+
+    let mut count = |content_type| -> Result<u64, CheckFailure> {
+        let Some(table) = tables.iter().find(|t| t.content_type == content_type) else {
+            return Err(CheckFailure::new(
+                POD5_TABLES_CHECK,
+                format!("POD5 file has no {} table.", table_name(content_type)),
+            ));
+        };
+        count_rows(&mut file, table).map_err(|e| {
+            CheckFailure::new(
+                POD5_READ_COUNT_CHECK,
+                format!(
+                    "Failed to count the rows of the POD5 {} table: {e}.",
+                    table_name(content_type)
+                ),
+            )
+        })
+    };
+    Ok(Pod5Counts {
+        reads: count(READS_TABLE)?,
+        signal_rows: count(SIGNAL_TABLE)?,
+    })
+}
+
+/// Reads the rest of the file, so that it is checksummed.
+fn drain(reader: &mut dyn Read) -> Result<(), CheckFailure> {
+    io::copy(reader, &mut io::sink())
+        .map(|_| ())
+        .map_err(|e| CheckFailure::new(READ_CHECK, format!("Failed to read file: {e}")))
+}
+
+pub fn check_pod5(
+    path: &Path,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::None,
+        POD5_CHECKS,
+        |reader| {
+            let counts = check_pod5_structure(path)?;
+            drain(reader)?;
+
+            let mut errors = Vec::new();
+            if counts.reads == 0 {
+                errors.push("File contains no records.".to_string());
+            } else if counts.signal_rows == 0 {
+                errors.push(format!(
+                    "POD5 signal table is empty, although the reads table has {} record(s).",
+                    counts.reads
+                ));
+            }
+            Ok(CheckOutcome {
+                stats: Some(Stats {
+                    num_records: counts.reads,
+                    total_read_length: None,
+                    modal_read_length: None,
+                    min_read_length: None,
+                    max_read_length: None,
+                    gvcf: None,
+                    adapter_reads: None,
+                    poly_g_reads: None,
+                    umi_reads: None,
+                    duplicate_records: None,
+                    mapped_records: None,
+                    bases: None,
+                    quality: None,
+                }),
+                errors,
+                ..Default::default()
+            })
+        },
+    )
+}
+
+/// Validates the HDF5 superblock of a FAST5 file, including that the file is not shorter than
+/// the end-of-file address recorded in it.
+fn check_fast5_superblock(path: &Path) -> Result<(), CheckFailure> {
+    let mut file = archive::open_section(path).map_err(io_failure(READ_CHECK))?;
+    let size = file.size();
+    let head = file
+        .read_at(0, 12.min(size as usize))
+        .map_err(io_failure(READ_CHECK))?;
+    if head.get(..8) != Some(&HDF5_SIGNATURE[..]) {
+        return Err(CheckFailure::new(
+            FAST5_SIGNATURE_CHECK,
+            "File is not an HDF5 file (FAST5 files start with the HDF5 signature).",
+        ));
+    }
+
+    let version = head[8];
+    let (offsets_size_pos, addresses_pos) = match version {
+        0 => (13, 24),
+        1 => (13, 28),
+        2 | 3 => (9, 12),
+        _ => {
+            return Err(CheckFailure::new(
+                FAST5_SUPERBLOCK_CHECK,
+                format!("HDF5 superblock version {version} is not supported."),
+            ));
+        }
+    };
+    let superblock = file
+        .read_at(0, (addresses_pos + 3 * 8).min(size as usize))
+        .map_err(io_failure(FAST5_SUPERBLOCK_CHECK))?;
+    let offsets_size = superblock
+        .get(offsets_size_pos)
+        .copied()
+        .unwrap_or_default() as usize;
+    if offsets_size != 8 {
+        return Err(CheckFailure::new(
+            FAST5_SUPERBLOCK_CHECK,
+            format!("HDF5 superblock has an unsupported size of offsets ({offsets_size})."),
+        ));
+    }
+    // Base address, then the free-space (v0/1) or superblock extension (v2/3) address, then the
+    // end-of-file address.
+    let address = |i: usize| read_u64(&superblock, addresses_pos + 8 * i);
+    let (Some(base), Some(eof)) = (address(0), address(2)) else {
+        return Err(CheckFailure::new(
+            FAST5_SUPERBLOCK_CHECK,
+            "HDF5 superblock is truncated.",
+        ));
+    };
+    if eof != HDF5_UNDEFINED_ADDRESS && base.saturating_add(eof) > size {
+        return Err(CheckFailure::new(
+            FAST5_SUPERBLOCK_CHECK,
+            format!(
+                "File is truncated: the HDF5 end-of-file address is {}, but the file has {size} bytes.",
+                base.saturating_add(eof)
+            ),
+        ));
+    }
+    Ok(())
+}
+
+pub fn check_fast5(
+    path: &Path,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::None,
+        FAST5_CHECKS,
+        |reader| {
+            check_fast5_superblock(path)?;
+            drain(reader)?;
+            Ok(CheckOutcome {
+                skipped_checks: vec![FAST5_GROUPS_CHECK],
+                ..Default::default()
+            })
+        },
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignalJob {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    /// A serialized FlatBuffers object and the position of its table, relative to its start.
+    struct Object {
+        bytes: Vec<u8>,
+        entry: usize,
+    }
+
+    enum Field {
+        Scalar(Vec<u8>),
+        Child(Object),
+    }
+
+    /// Writes a table with its vtable in front and its children behind it.
+    fn table(fields: Vec<Option<Field>>) -> Object {
+        let mut inline = Vec::new();
+        let mut field_offsets = Vec::new();
+        let mut children = Vec::new();
+        for field in fields {
+            match field {
+                None => field_offsets.push(0u16),
+                Some(Field::Scalar(bytes)) => {
+                    field_offsets.push(4 + inline.len() as u16);
+                    inline.extend(bytes);
+                }
+                Some(Field::Child(child)) => {
+                    field_offsets.push(4 + inline.len() as u16);
+                    children.push((inline.len(), child));
+                    inline.extend([0; 4]);
+                }
+            }
+        }
+        let mut bytes = Vec::new();
+        bytes.extend((4 + 2 * field_offsets.len() as u16).to_le_bytes());
+        bytes.extend((4 + inline.len() as u16).to_le_bytes());
+        for offset in field_offsets {
+            bytes.extend(offset.to_le_bytes());
+        }
+        let entry = bytes.len();
+        bytes.extend((entry as i32).to_le_bytes());
+        bytes.extend(inline);
+        append_children(&mut bytes, entry + 4, children);
+        Object { bytes, entry }
+    }
+
+    /// Appends children and patches the offsets pointing to them.
+    fn append_children(bytes: &mut Vec<u8>, inline_start: usize, children: Vec<(usize, Object)>) {
+        for (offset_pos, child) in children {
+            let field_pos = inline_start + offset_pos;
+            let target = bytes.len() + child.entry;
+            bytes[field_pos..field_pos + 4]
+                .copy_from_slice(&((target - field_pos) as u32).to_le_bytes());
+            bytes.extend(child.bytes);
+        }
+    }
+
+    fn table_vector(elements: Vec<Object>) -> Object {
+        let mut bytes = (elements.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(vec![0; 4 * elements.len()]);
+        let children = elements
+            .into_iter()
+            .enumerate()
+            .map(|(i, e)| (4 * i, e))
+            .collect();
+        append_children(&mut bytes, 4, children);
+        Object { bytes, entry: 0 }
+    }
+
+    fn struct_vector(elements: Vec<Vec<u8>>) -> Object {
+        let mut bytes = (elements.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(elements.concat());
+        Object { bytes, entry: 0 }
+    }
+
+    fn finish(root: Object) -> Vec<u8> {
+        let mut bytes = ((4 + root.entry) as u32).to_le_bytes().to_vec();
+        bytes.extend(root.bytes);
+        bytes
+    }
+
+    fn pad(bytes: &mut Vec<u8>) {
+        bytes.resize(bytes.len().next_multiple_of(8), 0);
+    }
+
+    /// Writes an Arrow IPC file with empty record batches of the given lengths.
+    fn arrow_file(batches: &[u64]) -> Vec<u8> {
+        let mut bytes = b"ARROW1\0\0".to_vec();
+        let mut blocks = Vec::new();
+        for &length in batches {
+            let batch = table(vec![Some(Field::Scalar(length.to_le_bytes().to_vec()))]);
+            let mut message = finish(table(vec![
+                Some(Field::Scalar(4i16.to_le_bytes().to_vec())),
+                Some(Field::Scalar(vec![ARROW_RECORD_BATCH])),
+                Some(Field::Child(batch)),
+            ]));
+            pad(&mut message);
+            let offset = bytes.len() as u64;
+            bytes.extend(u32::MAX.to_le_bytes());
+            bytes.extend((message.len() as u32).to_le_bytes());
+            bytes.extend(&message);
+            let block = [
+                offset.to_le_bytes().as_slice(),
+                &(message.len() as u32 + 8).to_le_bytes(),
+                &[0; 4],
+                &0u64.to_le_bytes(),
+            ]
+            .concat();
+            blocks.push(block);
+        }
+        let footer = finish(table(vec![
+            Some(Field::Scalar(4i16.to_le_bytes().to_vec())),
+            None,
+            None,
+            Some(Field::Child(struct_vector(blocks))),
+        ]));
+        bytes.extend(&footer);
+        bytes.extend((footer.len() as u32).to_le_bytes());
+        bytes.extend(ARROW_MAGIC);
+        bytes
+    }
+
+    /// Writes a POD5 file with the given record batch lengths of its reads and signal tables.
+    fn pod5_file(reads: &[u64], signal: &[u64]) -> Vec<u8> {
+        let marker = [7u8; 16];
+        let mut bytes = POD5_SIGNATURE.to_vec();
+        bytes.extend(marker);
+        let mut tables = Vec::new();
+        for (content_type, batches) in [(READS_TABLE, reads), (SIGNAL_TABLE, signal)] {
+            let offset = bytes.len() as i64;
+            let arrow = arrow_file(batches);
+            bytes.extend(&arrow);
+            tables.push(table(vec![
+                Some(Field::Scalar(offset.to_le_bytes().to_vec())),
+                Some(Field::Scalar((arrow.len() as i64).to_le_bytes().to_vec())),
+                Some(Field::Scalar(0i16.to_le_bytes().to_vec())),
+                Some(Field::Scalar(content_type.to_le_bytes().to_vec())),
+            ]));
+            pad(&mut bytes);
+            bytes.extend(marker);
+        }
+        bytes.extend(FOOTER_MAGIC);
+        let mut footer = finish(table(vec![
+            None,
+            None,
+            None,
+            Some(Field::Child(table_vector(tables))),
+        ]));
+        pad(&mut footer);
+        bytes.extend(&footer);
+        bytes.extend((footer.len() as u64).to_le_bytes());
+        bytes.extend(marker);
+        bytes.extend(POD5_SIGNATURE);
+        bytes
+    }
+
+    #[test]
+    fn test_pod5_structure() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("reads.pod5");
+        let pb = ProgressBar::hidden();
+
+        fs::write(&path, pod5_file(&[3, 2], &[4, 1]))?;
+        let report = check_pod5(&path, &CheckSettings::default(), &pb, &pb);
+        assert_eq!(report.errors, Vec::<String>::new());
+        assert_eq!(report.stats.map(|s| s.num_records), Some(5));
+        assert!(report.sha256.is_some());
+
+        fs::write(&path, pod5_file(&[3], &[]))?;
+        let report = check_pod5(&path, &CheckSettings::default(), &pb, &pb);
+        assert_eq!(
+            report.errors,
+            vec!["POD5 signal table is empty, although the reads table has 3 record(s)."]
+        );
+
+        let mut truncated = pod5_file(&[3], &[3]);
+        truncated.truncate(truncated.len() - 100);
+        fs::write(&path, truncated)?;
+        let report = check_pod5(&path, &CheckSettings::default(), &pb, &pb);
+        assert_eq!(
+            report.errors,
+            vec!["POD5 signature is missing at the end of the file; the file may be truncated."]
+        );
+        assert_eq!(report.not_evaluated.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fast5_superblock() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("reads.fast5");
+        let pb = ProgressBar::hidden();
+
+        // Version 2 superblock: signature, version, sizes of offsets and lengths, flags, then
+        // the base, superblock extension, end-of-file and root group addresses.
+        let superblock = |eof: u64| {
+            let mut bytes = HDF5_SIGNATURE.to_vec();
+            bytes.extend([2, 8, 8, 0]);
+            for address in [0, HDF5_UNDEFINED_ADDRESS, eof, 48] {
+                bytes.extend(address.to_le_bytes());
+            }
+            bytes.resize(128, 0);
+            bytes
+        };
+        fs::write(&path, superblock(128))?;
+        let report = check_fast5(&path, &CheckSettings::default(), &pb, &pb);
+        assert!(report.errors.is_empty());
+        assert_eq!(report.skipped_checks, vec![FAST5_GROUPS_CHECK]);
+
+        fs::write(&path, superblock(4096))?;
+        let report = check_fast5(&path, &CheckSettings::default(), &pb, &pb);
+        assert_eq!(
+            report.errors,
+            vec![
+                "File is truncated: the HDF5 end-of-file address is 4096, but the file has 128 bytes."
+            ]
+        );
+
+        fs::write(&path, b"not an HDF5 file")?;
+        let report = check_fast5(&path, &CheckSettings::default(), &pb, &pb);
+        assert!(report.errors[0].contains("not an HDF5 file"));
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use crate::checker::{FileReport, Stats};
+use crate::checks::common::{
+    CheckFailure, CheckOutcome, CheckSettings, Decompression, Occurrences, check_file,
+};
+use crate::checks::dependencies::Check;
+use indicatif::ProgressBar;
+use noodles::bgzf;
+use noodles::core::region::Interval;
+use noodles::csi::BinningIndex;
+use noodles::csi::binning_index::index::Header;
+use noodles::{csi, tabix};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+const INDEX_CHECK: &str = "tabix.index";
+const SEQUENCE_NAMES_CHECK: &str = "tabix.sequence_names";
+const OFFSETS_CHECK: &str = "tabix.sampled_offsets";
+
+/// Checks of tabix and CSI indexes.
+pub const CHECKS: &[Check] = &[
+    Check::new(INDEX_CHECK, &[]),
+    Check::new(SEQUENCE_NAMES_CHECK, &[INDEX_CHECK]),
+    Check::new(OFFSETS_CHECK, &[INDEX_CHECK]),
+];
+
+/// Maximum number of reference sequences whose first indexed offset is resolved in the data file.
+const MAX_SAMPLED_OFFSETS: usize = 16;
+
+const TABIX_MAGIC_NUMBER: &[u8; 4] = b"TBI\x01";
+const CSI_MAGIC_NUMBER: &[u8; 4] = b"CSI\x01";
+
+/// Parses a BGZF-compressed tabix or CSI index, telling them apart by their magic number.
+fn read_index(data: &[u8]) -> Result<Box<dyn BinningIndex>, String> {
+    let mut magic_number = [0; 4];
+    bgzf::io::Reader::new(data)
+        .read_exact(&mut magic_number)
+        .map_err(|e| format!("Failed to read index: {e}"))?;
+    match &magic_number {
+        TABIX_MAGIC_NUMBER => tabix::io::Reader::new(data)
+            .read_index()
+            .map(|index| Box::new(index) as Box<dyn BinningIndex>)
+            .map_err(|e| format!("Failed to read tabix index: {e}")),
+        CSI_MAGIC_NUMBER => csi::io::Reader::new(data)
+            .read_index()
+            .map(|index| Box::new(index) as Box<dyn BinningIndex>)
+            .map_err(|e| format!("Failed to read CSI index: {e}")),
+        _ => Err(format!(
+            "File is not a tabix or CSI index (magic number {:?}).",
+            String::from_utf8_lossy(&magic_number)
+        )),
+    }
+}
+
+/// Returns the sequence name of a data line, or `None` for header lines.
+fn sequence_name<'a>(header: &Header, line: &'a str) -> Option<&'a str> {
+    if line.as_bytes().first() == Some(&header.line_comment_prefix()) {
+        return None;
+    }
+    line.split('\t').nth(header.reference_sequence_name_index())
+}
+
+/// Collects the sequence names of all records of the BGZF-compressed data file.
+fn read_sequence_names(data_path: &Path, header: &Header) -> Result<Vec<String>, String> {
+    let file = fs::File::open(data_path).map_err(|e| e.to_string())?;
+    let reader = BufReader::new(bgzf::io::Reader::new(file));
+    let mut seen = HashSet::new();
+    let mut names = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("line {}: {e}", i + 1))?;
+        if i < header.line_skip_count() as usize {
+            continue;
+        }
+        if let Some(name) = sequence_name(header, &line)
+            && seen.insert(name.to_string())
+        {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+fn cross_check_names(index_names: &[String], data_names: &[String], errors: &mut Vec<String>) {
+    let index_set: HashSet<&String> = index_names.iter().collect();
+    let data_set: HashSet<&String> = data_names.iter().collect();
+
+    let mut missing_from_data = Occurrences::default();
+    for name in index_names.iter().filter(|name| !data_set.contains(name)) {
+        missing_from_data.add(|| format!("'{name}'"));
+    }
+    let mut missing_from_index = Occurrences::default();
+    for name in data_names.iter().filter(|name| !index_set.contains(name)) {
+        missing_from_index.add(|| format!("'{name}'"));
+    }
+
+    if let Some(first) = missing_from_data.first {
+        errors.push(format!(
+            "Index contains {} sequence name(s) not present in the data file. First detected: {first}.",
+            missing_from_data.count
+        ));
+    }
+    if let Some(first) = missing_from_index.first {
+        errors.push(format!(
+            "Data file contains {} sequence name(s) missing from the index, which may be stale. First detected: {first}.",
+            missing_from_index.count
+        ));
+    }
+}
+
+/// Resolves the first indexed offset of up to [`MAX_SAMPLED_OFFSETS`] reference sequences,
+/// spread evenly over the index, and checks that each points to a record of that sequence.
+fn check_sampled_offsets(
+    index: &dyn BinningIndex,
+    header: &Header,
+    index_names: &[String],
+    data_path: &Path,
+    errors: &mut Vec<String>,
+) -> Result<(), String> {
+    let mut reader = fs::File::open(data_path)
+        .map(bgzf::io::Reader::new)
+        .map_err(|e| e.to_string())?;
+    let step = index_names.len().div_ceil(MAX_SAMPLED_OFFSETS).max(1);
+
+    let mut unresolved = Occurrences::default();
+    for (id, name) in index_names.iter().enumerate().step_by(step) {
+        let chunks = index
+            .query(id, Interval::from(..))
+            .map_err(|e| format!("Failed to query index for '{name}': {e}"))?;
+        let Some(chunk) = chunks.first() else {
+            continue;
+        };
+        let offset = chunk.start();
+        let describe = |reason: String| {
+            format!(
+                "offset {}:{} of '{name}' ({reason})",
+                offset.compressed(),
+                offset.uncompressed()
+            )
+        };
+
+        let mut line = String::new();
+        let result = reader
+            .seek(offset)
+            .and_then(|_| reader.read_line(&mut line));
+        match result {
+            Err(e) => unresolved.add(|| describe(e.to_string())),
+            Ok(0) => unresolved.add(|| describe("end of file".to_string())),
+            Ok(_) => {
+                let line = line.trim_end_matches(['\n', '\r']);
+                match sequence_name(header, line) {
+                    Some(found) if found == name => {}
+                    Some(found) => unresolved.add(|| describe(format!("record of '{found}'"))),
+                    None => unresolved.add(|| describe("not a record".to_string())),
+                }
+            }
+        }
+    }
+
+    if let Some(first) = unresolved.first {
+        errors.push(format!(
+            "Index contains {} sampled offset(s) that do not resolve to a record of their sequence, so the index may be stale or truncated. First detected at {first}.",
+            unresolved.count
+        ));
+    }
+    Ok(())
+}
+
+pub fn check_tabix(
+    path: &Path,
+    data_path: &Path,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::None,
+        CHECKS,
+        |reader| {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).map_err(|e| {
+                CheckFailure::new(INDEX_CHECK, format!("Failed to read index: {e}"))
+            })?;
+            let index =
+                read_index(&data).map_err(|message| CheckFailure::new(INDEX_CHECK, message))?;
+
+            let mut errors = Vec::new();
+            let mut skipped_checks = Vec::new();
+            let num_sequences;
+            match index.header() {
+                Some(header) => {
+                    let index_names: Vec<String> = header
+                        .reference_sequence_names()
+                        .iter()
+                        .map(|name| name.to_string())
+                        .collect();
+                    num_sequences = index_names.len();
+
+                    match read_sequence_names(data_path, header) {
+                        Ok(data_names) => {
+                            cross_check_names(&index_names, &data_names, &mut errors);
+                        }
+                        Err(e) => errors.push(format!(
+                            "Failed to read data file {}: {e}",
+                            data_path.display()
+                        )),
+                    }
+                    if let Err(e) = check_sampled_offsets(
+                        index.as_ref(),
+                        header,
+                        &index_names,
+                        data_path,
+                        &mut errors,
+                    ) {
+                        errors.push(format!(
+                            "Failed to resolve offsets in data file {}: {e}",
+                            data_path.display()
+                        ));
+                    }
+                }
+                // CSI indexes of BCF and BAM files store no sequence names; these are only
+                // known from the header of the data file.
+                None => {
+                    num_sequences = index.reference_sequences().count();
+                    skipped_checks.extend([SEQUENCE_NAMES_CHECK, OFFSETS_CHECK]);
+                }
+            }
+
+            Ok(CheckOutcome {
+                stats: Some(Stats {
+                    num_records: num_sequences as u64,
+                    total_read_length: None,
+                    modal_read_length: None,
+                    min_read_length: None,
+                    max_read_length: None,
+                    gvcf: None,
+                    adapter_reads: None,
+                    poly_g_reads: None,
+                    umi_reads: None,
+                    duplicate_records: None,
+                    mapped_records: None,
+                    bases: None,
+                    quality: None,
+                }),
+                errors,
+                warnings: vec![],
+                skipped_checks,
+            })
+        },
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TabixCheckJob {
+    /// Path of the `.tbi` or `.csi` index.
+    pub path: PathBuf,
+    /// BGZF-compressed file the index belongs to.
+    pub data_path: PathBuf,
+    pub size: u64,
+}
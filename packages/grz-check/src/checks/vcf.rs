@@ -0,0 +1,321 @@
+use crate::checker::{FileReport, Stats};
+use crate::checks::common::{CheckFailure, CheckOutcome, CheckSettings, Decompression, check_file};
+use crate::checks::dependencies::{Check, READ_CHECK};
+use indicatif::ProgressBar;
+use noodles::vcf::variant::record::info::field::{Value, key};
+use noodles::vcf::variant::record::{AlternateBases as _, Info as _, Samples as _};
+use noodles::{bcf, vcf};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+const HEADER_CHECK: &str = "vcf.header";
+const RECORDS_CHECK: &str = "vcf.records";
+const CONTIG_CHECK: &str = "vcf.contig_consistency";
+const GVCF_CHECK: &str = "vcf.gvcf_blocks";
+
+/// Checks of VCF and BCF files.
+pub const CHECKS: &[Check] = &[
+    Check::new(HEADER_CHECK, &[]),
+    Check::new(RECORDS_CHECK, &[HEADER_CHECK]),
+    Check::new(CONTIG_CHECK, &[RECORDS_CHECK]),
+];
+
+/// Checks of gVCF files, which additionally validate the block structure.
+pub const GVCF_CHECKS: &[Check] = &[
+    Check::new(HEADER_CHECK, &[]),
+    Check::new(RECORDS_CHECK, &[HEADER_CHECK]),
+    Check::new(CONTIG_CHECK, &[RECORDS_CHECK]),
+    Check::new(GVCF_CHECK, &[RECORDS_CHECK]),
+];
+
+/// Alternate alleles marking a reference block of a gVCF.
+const NON_REF_ALLELES: &[&str] = &["<NON_REF>", "<*>"];
+
+/// Counts records with a specific consistency problem and remembers the first one.
+#[derive(Default)]
+struct Occurrences {
+    count: u64,
+    first: Option<(u64, String)>,
+}
+
+impl Occurrences {
+    fn add(&mut self, record_number: u64, detail: impl FnOnce() -> String) {
+        self.count += 1;
+        if self.first.is_none() {
+            self.first = Some((record_number, detail()));
+        }
+    }
+
+    fn report(&self, errors: &mut Vec<String>, description: &str) {
+        if let Some((record_number, detail)) = &self.first {
+            errors.push(format!(
+                "File contains {} record(s) {description}. First detected at record #{record_number} ({detail}).",
+                self.count
+            ));
+        }
+    }
+}
+
+/// Statistics of the blocks of a gVCF.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize)]
+pub struct GvcfStats {
+    pub reference_blocks: u64,
+    pub variant_records: u64,
+    /// Number of positions covered by reference blocks.
+    pub reference_block_bases: u64,
+    pub max_reference_block_length: u64,
+}
+
+/// Validates the block structure of a gVCF, one record at a time.
+#[derive(Default)]
+struct GvcfBlocks {
+    stats: GvcfStats,
+    missing_end: Occurrences,
+    invalid_end: Occurrences,
+    overlapping: Occurrences,
+    /// Contig and last position of the preceding record.
+    previous: Option<(String, usize)>,
+}
+
+impl GvcfBlocks {
+    fn add<R: vcf::variant::Record>(
+        &mut self,
+        header: &vcf::Header,
+        record: &R,
+        record_number: u64,
+        contig: &str,
+        start: usize,
+    ) -> Result<(), String> {
+        let alternate_bases = record.alternate_bases();
+        let mut is_reference_block = !alternate_bases.is_empty();
+        for allele in alternate_bases.iter() {
+            let allele = allele
+                .map_err(|e| format!("Failed to parse ALT of record #{record_number}: {e}"))?;
+            is_reference_block &= NON_REF_ALLELES.contains(&allele);
+        }
+
+        let end = match record.info().get(header, key::END_POSITION) {
+            Some(Ok(Some(Value::Integer(end)))) => Some(end),
+            Some(Ok(_)) | None => None,
+            Some(Err(e)) => {
+                return Err(format!(
+                    "Failed to parse END of record #{record_number}: {e}"
+                ));
+            }
+        };
+        let has_end = end.is_some();
+        let end = match end.map(usize::try_from) {
+            Some(Ok(end)) if end >= start => Some(end),
+            Some(_) => {
+                self.invalid_end.add(record_number, || {
+                    format!("'{contig}:{start}' with END={}", end.unwrap_or_default())
+                });
+                None
+            }
+            None => None,
+        };
+
+        if is_reference_block {
+            self.stats.reference_blocks += 1;
+            if let Some(end) = end {
+                let length = (end - start + 1) as u64;
+                self.stats.reference_block_bases += length;
+                self.stats.max_reference_block_length =
+                    self.stats.max_reference_block_length.max(length);
+            } else if !has_end {
+                self.missing_end
+                    .add(record_number, || format!("'{contig}:{start}'"));
+            }
+        } else {
+            self.stats.variant_records += 1;
+        }
+
+        let last = end.unwrap_or(start);
+        match &mut self.previous {
+            Some((previous_contig, previous_last)) if previous_contig == contig => {
+                if start <= *previous_last {
+                    let previous_last = *previous_last;
+                    self.overlapping.add(record_number, || {
+                        format!("'{contig}:{start}' overlaps a block ending at {previous_last}")
+                    });
+                }
+                *previous_last = (*previous_last).max(last);
+            }
+            _ => self.previous = Some((contig.to_string(), last)),
+        }
+        Ok(())
+    }
+
+    fn report(self, errors: &mut Vec<String>, warnings: &mut Vec<String>) -> GvcfStats {
+        self.missing_end
+            .report(errors, "that are reference blocks without an END tag");
+        self.invalid_end
+            .report(errors, "with an END before their position");
+        self.overlapping
+            .report(errors, "overlapping the preceding block");
+        if self.stats.reference_blocks == 0 && self.stats.variant_records > 0 {
+            warnings.push(
+                "File contains no reference blocks (ALT <NON_REF> or <*>); it may not be a gVCF."
+                    .to_string(),
+            );
+        }
+        self.stats
+    }
+}
+
+fn check_records<R, I>(header: &vcf::Header, records: I, gvcf: bool) -> Result<CheckOutcome, String>
+where
+    R: vcf::variant::Record,
+    I: Iterator<Item = io::Result<R>>,
+{
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let mut num_records: u64 = 0;
+    let mut undeclared_contigs = Occurrences::default();
+    let mut undeclared_info = Occurrences::default();
+    let mut undeclared_format = Occurrences::default();
+    let mut sample_count_mismatches = Occurrences::default();
+    let num_samples = header.sample_names().len();
+    let mut gvcf_blocks = gvcf.then(GvcfBlocks::default);
+
+    let mut skipped_checks = Vec::new();
+    if header.contigs().is_empty() {
+        skipped_checks.push(CONTIG_CHECK);
+        warnings.push(
+            "Header declares no contigs (##contig); skipping contig consistency check.".to_string(),
+        );
+    }
+
+    for result in records {
+        num_records += 1;
+        let record = result.map_err(|e| format!("Failed to parse record #{num_records}: {e}"))?;
+
+        let contig = record
+            .reference_sequence_name(header)
+            .map_err(|e| format!("Failed to parse CHROM of record #{num_records}: {e}"))?;
+        if !header.contigs().is_empty() && !header.contigs().contains_key(contig) {
+            undeclared_contigs.add(num_records, || format!("'{contig}'"));
+        }
+
+        let start = match record.variant_start() {
+            Some(Ok(position)) => usize::from(position),
+            Some(Err(e)) => {
+                return Err(format!("Failed to parse POS of record #{num_records}: {e}"));
+            }
+            None => 0,
+        };
+        if let Some(gvcf_blocks) = &mut gvcf_blocks {
+            gvcf_blocks.add(header, &record, num_records, contig, start)?;
+        }
+
+        let info = record.info();
+        for field in info.iter(header) {
+            let (key, _) =
+                field.map_err(|e| format!("Failed to parse INFO of record #{num_records}: {e}"))?;
+            if !header.infos().contains_key(key) {
+                undeclared_info.add(num_records, || format!("'{key}'"));
+            }
+        }
+
+        let samples = record
+            .samples()
+            .map_err(|e| format!("Failed to parse samples of record #{num_records}: {e}"))?;
+        for key in samples.column_names(header) {
+            let key =
+                key.map_err(|e| format!("Failed to parse FORMAT of record #{num_records}: {e}"))?;
+            if !header.formats().contains_key(key) {
+                undeclared_format.add(num_records, || format!("'{key}'"));
+            }
+        }
+        let record_num_samples = samples.len();
+        if record_num_samples != num_samples {
+            sample_count_mismatches.add(num_records, || {
+                format!("{record_num_samples} sample(s) instead of {num_samples}")
+            });
+        }
+    }
+
+    undeclared_contigs.report(&mut errors, "on contigs not declared in the header");
+    undeclared_info.report(&mut errors, "with INFO fields not declared in the header");
+    undeclared_format.report(&mut errors, "with FORMAT fields not declared in the header");
+    sample_count_mismatches.report(
+        &mut errors,
+        "whose number of samples does not match the header",
+    );
+
+    let gvcf_stats = gvcf_blocks.map(|blocks| blocks.report(&mut errors, &mut warnings));
+
+    if num_records == 0 {
+        warnings.push("File contains no records.".to_string());
+    }
+
+    Ok(CheckOutcome {
+        stats: Some(Stats {
+            num_records,
+            total_read_length: None,
+            modal_read_length: None,
+            min_read_length: None,
+            max_read_length: None,
+            gvcf: gvcf_stats,
+            adapter_reads: None,
+            poly_g_reads: None,
+            umi_reads: None,
+            duplicate_records: None,
+            mapped_records: None,
+            bases: None,
+            quality: None,
+        }),
+        errors,
+        warnings,
+        skipped_checks,
+    })
+}
+
+pub fn check_vcf(
+    path: &Path,
+    gvcf: bool,
+    settings: &CheckSettings,
+    file_pb: &ProgressBar,
+    global_pb: &ProgressBar,
+) -> FileReport {
+    check_file(
+        path,
+        settings,
+        file_pb,
+        global_pb,
+        Decompression::Auto,
+        if gvcf { GVCF_CHECKS } else { CHECKS },
+        |reader| {
+            let mut reader = BufReader::new(reader);
+            let is_bcf = reader
+                .fill_buf()
+                .map_err(|e| CheckFailure::new(READ_CHECK, format!("Failed to read file: {e}")))?
+                .starts_with(b"BCF");
+
+            let outcome = if is_bcf {
+                let mut bcf_reader = bcf::io::Reader::from(reader);
+                let header = bcf_reader.read_header().map_err(|e| {
+                    CheckFailure::new(HEADER_CHECK, format!("Failed to read BCF header: {e}"))
+                })?;
+                check_records(&header, bcf_reader.records(), gvcf)
+            } else {
+                let mut vcf_reader = vcf::io::Reader::new(reader);
+                let header = vcf_reader.read_header().map_err(|e| {
+                    CheckFailure::new(HEADER_CHECK, format!("Failed to read VCF header: {e}"))
+                })?;
+                check_records(&header, vcf_reader.records(), gvcf)
+            };
+            outcome.map_err(|message| CheckFailure::new(RECORDS_CHECK, message))
+        },
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VcfCheckJob {
+    pub path: PathBuf,
+    /// Whether the file is a gVCF, given via `--gvcf`.
+    pub gvcf: bool,
+    pub size: u64,
+}
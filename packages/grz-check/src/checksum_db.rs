@@ -0,0 +1,139 @@
+//! SQLite database of the checksums of previously checked submissions, given via `--checksum-db`.
+//!
+//! Files identical to a file of a different submission usually indicate a sample-handling error,
+//! e.g. the same FASTQ delivered for two donors.
+
+use anyhow::Context;
+use chrono::{SecondsFormat, Utc};
+use rusqlite::{Connection, params};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS files (
+    checksum TEXT NOT NULL,
+    path TEXT NOT NULL,
+    submission_id TEXT NOT NULL,
+    recorded_at TEXT NOT NULL,
+    PRIMARY KEY (checksum, path, submission_id)
+)";
+
+/// A previously recorded file with the same checksum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedFile {
+    pub path: String,
+    pub submission_id: String,
+}
+
+#[derive(Debug)]
+pub struct ChecksumDb {
+    connection: Mutex<Connection>,
+    submission_id: String,
+}
+
+impl ChecksumDb {
+    pub fn open(path: &Path, submission_id: &str) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open checksum database {}", path.display()))?;
+        // Other runs may record their checksums at the same time.
+        connection.busy_timeout(Duration::from_secs(30))?;
+        connection
+            .execute(SCHEMA, [])
+            .with_context(|| format!("Failed to set up checksum database {}", path.display()))?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            submission_id: submission_id.to_string(),
+        })
+    }
+
+    /// Returns the files of other submissions with the given checksum.
+    pub fn lookup(&self, checksum: &str) -> rusqlite::Result<Vec<RecordedFile>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare_cached(
+            "SELECT path, submission_id FROM files
+             WHERE checksum = ?1 AND submission_id != ?2
+             ORDER BY recorded_at, path",
+        )?;
+        statement
+            .query_map(params![checksum, self.submission_id], |row| {
+                Ok(RecordedFile {
+                    path: row.get(0)?,
+                    submission_id: row.get(1)?,
+                })
+            })?
+            .collect()
+    }
+
+    /// Records a file of the current submission.
+    pub fn record(&self, path: &Path, checksum: &str) -> rusqlite::Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT OR IGNORE INTO files (checksum, path, submission_id, recorded_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                checksum,
+                path.to_string_lossy(),
+                self.submission_id,
+                Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Looks up the file in the database and records it, returning a warning if identical files
+    /// of other submissions exist.
+    pub fn check_and_record(
+        &self,
+        path: &Path,
+        checksum: &str,
+    ) -> rusqlite::Result<Option<String>> {
+        let previous = self.lookup(checksum)?;
+        self.record(path, checksum)?;
+        Ok(previous.first().map(|first| {
+            format!(
+                "File is identical to {} file(s) of other submissions. First recorded: '{}' of submission '{}'.",
+                previous.len(),
+                first.path,
+                first.submission_id
+            )
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_identical_files_of_other_submissions() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("checksums.sqlite");
+
+        let first = ChecksumDb::open(&db_path, "submission-1")?;
+        assert_eq!(
+            first.check_and_record(Path::new("a.fastq.gz"), "abc")?,
+            None
+        );
+        // Files of the same submission are not reported, e.g. when checking it again.
+        assert_eq!(
+            first.check_and_record(Path::new("a.fastq.gz"), "abc")?,
+            None
+        );
+        drop(first);
+
+        let second = ChecksumDb::open(&db_path, "submission-2")?;
+        assert_eq!(
+            second.check_and_record(Path::new("b.fastq.gz"), "def")?,
+            None
+        );
+        let warning = second.check_and_record(Path::new("c.fastq.gz"), "abc")?;
+        assert_eq!(
+            warning.as_deref(),
+            Some(
+                "File is identical to 1 file(s) of other submissions. First recorded: 'a.fastq.gz' of submission 'submission-1'."
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+//! External registry of the expected SHA-256 digests of files, given by the
+//! `[checksum_registry]` table of the config file.
+//!
+//! Data hubs that keep a central fixity database can drive verification with it instead of
+//! generating a checksum manifest for each run. Files are looked up by their logical ID, their
+//! path relative to the `root` of the registry, either by running a command, e.g. one querying
+//! an HTTP endpoint with `curl`, or in a local SQLite database.
+
+use crate::checker::FileReport;
+use crate::hooks::shell_quote;
+use anyhow::Context;
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+/// Placeholder in command templates that is replaced by the shell-quoted logical ID.
+pub const ID_PLACEHOLDER: &str = "{id}";
+
+/// Query of the database backend unless the config file gives another one.
+pub const DEFAULT_QUERY: &str = "SELECT sha256 FROM checksums WHERE id = ?1";
+
+/// Source of expected digests by logical ID.
+pub trait Registry: Send + Sync + fmt::Debug {
+    /// Returns the digest recorded for `id`, or `None` if the registry does not know the ID.
+    fn lookup(&self, id: &str) -> Result<Option<String>, String>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum Backend {
+    /// Shell command template printing the digest of `{id}`, and nothing for unknown IDs, e.g.
+    /// `curl -fsS https://hub.example/fixity/{id}`.
+    Command { command: String },
+    /// SQLite database with a query selecting the digest of the ID bound to `?1`.
+    Database { path: PathBuf, query: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChecksumRegistry {
+    pub backend: Backend,
+    /// Directory the logical IDs are relative to; files outside of it are not looked up.
+    pub root: PathBuf,
+}
+
+impl ChecksumRegistry {
+    /// Logical ID of `path`, or `None` if it is outside of the root.
+    pub fn logical_id(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        Some(relative.to_string_lossy().into_owned())
+    }
+
+    /// Connects to the backend, failing early if it cannot be used.
+    pub fn connect(&self) -> anyhow::Result<Box<dyn Registry>> {
+        Ok(match &self.backend {
+            Backend::Command { command } => Box::new(CommandRegistry {
+                command: command.clone(),
+            }),
+            Backend::Database { path, query } => Box::new(DatabaseRegistry::open(path, query)?),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CommandRegistry {
+    command: String,
+}
+
+impl Registry for CommandRegistry {
+    fn lookup(&self, id: &str) -> Result<Option<String>, String> {
+        let command = self.command.replace(ID_PLACEHOLDER, &shell_quote(id));
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run command `{command}`: {e}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Command `{command}` failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.split_whitespace().next().map(str::to_string))
+    }
+}
+
+#[derive(Debug)]
+struct DatabaseRegistry {
+    connection: Mutex<Connection>,
+    query: String,
+}
+
+impl DatabaseRegistry {
+    fn open(path: &Path, query: &str) -> anyhow::Result<Self> {
+        // The registry is maintained elsewhere and never written to.
+        let connection =
+            Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .with_context(|| format!("Failed to open checksum registry {}", path.display()))?;
+        connection.prepare(query).with_context(|| {
+            format!(
+                "Invalid query `{query}` of checksum registry {}",
+                path.display()
+            )
+        })?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            query: query.to_string(),
+        })
+    }
+}
+
+impl Registry for DatabaseRegistry {
+    fn lookup(&self, id: &str) -> Result<Option<String>, String> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .prepare_cached(&self.query)
+            .and_then(|mut statement| {
+                statement
+                    .query_row([id], |row| row.get::<_, String>(0))
+                    .optional()
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Verifies the digest of a checked file against the digest recorded for its logical ID,
+/// reporting an error if it differs or cannot be looked up and a warning if the registry does not
+/// know the file.
+///
+/// Files that do not exist are only given the recorded digest as the one they are expected to
+/// have.
+pub fn verify(registry: &dyn Registry, id: &str, report: &mut FileReport) {
+    let Some(sha256) = &report.sha256 else {
+        if report.missing
+            && let Ok(Some(expected)) = registry.lookup(id)
+            && is_sha256(&expected)
+        {
+            report.expected_sha256 = Some(expected.to_ascii_lowercase());
+        }
+        return;
+    };
+    match registry.lookup(id) {
+        Ok(Some(expected)) if is_sha256(&expected) => {
+            let expected = expected.to_ascii_lowercase();
+            if !sha256.eq_ignore_ascii_case(&expected) {
+                report.errors.push(format!(
+                    "SHA-256 digest {sha256} differs from the digest {expected} recorded for '{id}' in the checksum registry."
+                ));
+            }
+            report.expected_sha256 = Some(expected);
+        }
+        Ok(Some(other)) => report.errors.push(format!(
+            "Checksum registry returned '{other}' for '{id}', which is not a SHA-256 digest."
+        )),
+        Ok(None) => report.warnings.push(format!(
+            "File '{id}' is not recorded in the checksum registry, so its digest was not verified."
+        )),
+        Err(e) => report.errors.push(format!(
+            "Failed to look up '{id}' in the checksum registry: {e}"
+        )),
+    }
+}
+
+fn is_sha256(digest: &str) -> bool {
+    digest.len() == 64 && digest.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const DIGEST: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    fn verify_digest(registry: &dyn Registry, id: &str, sha256: &str) -> FileReport {
+        let mut report = FileReport::new(Path::new(id), None, vec![], vec![]);
+        report.sha256 = Some(sha256.to_string());
+        verify(registry, id, &mut report);
+        report
+    }
+
+    #[test]
+    fn test_database_registry() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("fixity.sqlite");
+        let connection = Connection::open(&path)?;
+        connection.execute_batch(&format!(
+            "CREATE TABLE checksums (id TEXT PRIMARY KEY, sha256 TEXT NOT NULL);
+             INSERT INTO checksums VALUES ('donor1/a.fastq.gz', '{}');
+             INSERT INTO checksums VALUES ('donor1/b.fastq.gz', 'unknown');",
+            DIGEST.to_uppercase()
+        ))?;
+        drop(connection);
+
+        let registry = ChecksumRegistry {
+            backend: Backend::Database {
+                path,
+                query: DEFAULT_QUERY.to_string(),
+            },
+            root: PathBuf::from("/data/submission"),
+        };
+        assert_eq!(
+            registry.logical_id(Path::new("/data/submission/donor1/a.fastq.gz")),
+            Some("donor1/a.fastq.gz".to_string())
+        );
+        assert_eq!(registry.logical_id(Path::new("/tmp/a.fastq.gz")), None);
+        let registry = registry.connect()?;
+
+        let report = verify_digest(registry.as_ref(), "donor1/a.fastq.gz", DIGEST);
+        assert!(report.errors.is_empty() && report.warnings.is_empty());
+        assert_eq!(report.expected_sha256.as_deref(), Some(DIGEST));
+
+        let report = verify_digest(registry.as_ref(), "donor1/a.fastq.gz", &"0".repeat(64));
+        assert_eq!(
+            report.errors,
+            vec![format!(
+                "SHA-256 digest {} differs from the digest {DIGEST} recorded for 'donor1/a.fastq.gz' in the checksum registry.",
+                "0".repeat(64)
+            )]
+        );
+
+        let report = verify_digest(registry.as_ref(), "donor1/b.fastq.gz", DIGEST);
+        assert_eq!(
+            report.errors,
+            vec![
+                "Checksum registry returned 'unknown' for 'donor1/b.fastq.gz', which is not a SHA-256 digest."
+            ]
+        );
+
+        let report = verify_digest(registry.as_ref(), "donor1/c.fastq.gz", DIGEST);
+        assert!(report.errors.is_empty());
+        assert_eq!(
+            report.warnings,
+            vec![
+                "File 'donor1/c.fastq.gz' is not recorded in the checksum registry, so its digest was not verified."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_registry() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let listing = dir.path().join("fixity.txt");
+        std::fs::write(&listing, format!("{DIGEST}  it's.bam\n"))?;
+
+        let registry = ChecksumRegistry {
+            backend: Backend::Command {
+                command: format!(
+                    "awk -v id={ID_PLACEHOLDER} '$2 == id {{ print $1 }}' {}",
+                    listing.display()
+                ),
+            },
+            root: dir.path().to_path_buf(),
+        }
+        .connect()?;
+        assert_eq!(
+            registry.lookup("it's.bam").unwrap(),
+            Some(DIGEST.to_string())
+        );
+        assert_eq!(registry.lookup("other.bam").unwrap(), None);
+
+        let failing = ChecksumRegistry {
+            backend: Backend::Command {
+                command: "echo unreachable >&2; exit 7 # {id}".to_string(),
+            },
+            root: dir.path().to_path_buf(),
+        }
+        .connect()?;
+        let report = verify_digest(failing.as_ref(), "a.bam", DIGEST);
+        assert_eq!(report.errors.len(), 1);
+        assert!(
+            report.errors[0]
+                .starts_with("Failed to look up 'a.bam' in the checksum registry: Command")
+                && report.errors[0].ends_with("unreachable"),
+            "{:?}",
+            report.errors
+        );
+        Ok(())
+    }
+}
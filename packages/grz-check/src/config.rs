@@ -0,0 +1,326 @@
+//! Configuration file given via `--config`.
+//!
+//! ```toml
+//! [[suppress]]
+//! code = "alignment.hard_clip"
+//! path = "legacy_pipeline/**/*.bam"
+//!
+//! [[mount]]
+//! path = "/mnt/tape"
+//! concurrency = 1
+//!
+//! [staging]
+//! command = "dmget {path}"
+//!
+//! [post_check]
+//! command = "[ {status} = OK ] && mv {path} ready/"
+//! digest_encoding = "s3"
+//!
+//! [[external_check]]
+//! name = "site"
+//! command = "site-validate --json {path}"
+//! check_types = ["fastq", "bam"]
+//!
+//! [[consent_category]]
+//! name = "research VCFs"
+//! path = "**/research/*.vcf.gz"
+//! requires = "research"
+//! expected = true
+//!
+//! [path_constraints]
+//! root = "/data/submission"
+//! max_length = 1024
+//! allowed_characters = "A-Za-z0-9._/-"
+//!
+//! [checksum_registry]
+//! root = "/data/submission"
+//! command = "curl -fsS https://hub.example/fixity/{id}"
+//!
+//! [options]
+//! continue_on_error = true
+//! show_progress = false
+//! ```
+
+use crate::checksum_registry::{self, Backend, ChecksumRegistry};
+use crate::consent::{self, Scope};
+use crate::external::ExternalCheck;
+use crate::hooks::{self, PostCheckHook, StagingHook};
+use crate::mounts::MountLimit;
+use crate::options::Settings;
+use crate::path_constraints::{CharacterSet, PathConstraints};
+use crate::sha256::DigestEncoding;
+use crate::suppress::Suppression;
+use anyhow::Context;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Findings to suppress, equivalent to `--suppress CODE:GLOB`.
+    #[serde(default)]
+    suppress: Vec<SuppressionEntry>,
+    /// Limits on the number of files checked and directories scanned concurrently per storage
+    /// mount.
+    #[serde(default)]
+    mount: Vec<MountEntry>,
+    /// Command run before the files of a job are opened.
+    staging: Option<HookEntry>,
+    /// Command run after each file has been checked.
+    post_check: Option<PostCheckEntry>,
+    /// External validators run on each checked file.
+    #[serde(default)]
+    external_check: Vec<ExternalCheckEntry>,
+    /// File categories checked against the consent scopes given via `--consent-scope`.
+    #[serde(default)]
+    consent_category: Vec<ConsentCategoryEntry>,
+    /// Constraints of the inbox on file paths.
+    path_constraints: Option<PathConstraintsEntry>,
+    /// External registry of the expected digests of files.
+    checksum_registry: Option<ChecksumRegistryEntry>,
+    /// Defaults of command-line flags, see [`crate::options`].
+    #[serde(default)]
+    options: Settings,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SuppressionEntry {
+    code: String,
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MountEntry {
+    path: PathBuf,
+    concurrency: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HookEntry {
+    command: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PostCheckEntry {
+    command: String,
+    /// Encoding of `{digest}`: `hex` (default), `base64` or `s3`.
+    #[serde(default)]
+    digest_encoding: DigestEncoding,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExternalCheckEntry {
+    name: String,
+    command: String,
+    #[serde(default)]
+    check_types: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConsentCategoryEntry {
+    name: String,
+    path: String,
+    #[serde(default = "research_scope")]
+    requires: Scope,
+    #[serde(default)]
+    expected: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathConstraintsEntry {
+    root: Option<PathBuf>,
+    max_length: Option<usize>,
+    allowed_characters: Option<String>,
+}
+
+/// A `[checksum_registry]` table with either a `command` or a `database` and, for the latter,
+/// an optional `query`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChecksumRegistryEntry {
+    root: PathBuf,
+    command: Option<String>,
+    database: Option<PathBuf>,
+    query: Option<String>,
+}
+
+fn research_scope() -> Scope {
+    Scope::Research
+}
+
+/// Validates the command of a hook table.
+fn hook_command(command: Option<&String>, table: &str) -> anyhow::Result<Option<String>> {
+    let Some(command) = command else {
+        return Ok(None);
+    };
+    if !command.contains(hooks::PATH_PLACEHOLDER) {
+        anyhow::bail!(
+            "Command '{command}' of [{table}] must contain the placeholder {}",
+            hooks::PATH_PLACEHOLDER
+        );
+    }
+    Ok(Some(command.clone()))
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.options
+    }
+
+    pub fn suppressions(&self) -> anyhow::Result<Vec<Suppression>> {
+        self.suppress
+            .iter()
+            .map(|entry| Suppression::new(&entry.code, &entry.path).map_err(anyhow::Error::msg))
+            .collect()
+    }
+
+    pub fn mount_limits(&self) -> anyhow::Result<Vec<MountLimit>> {
+        self.mount
+            .iter()
+            .map(|entry| {
+                if entry.concurrency == 0 {
+                    anyhow::bail!(
+                        "Concurrency of mount {} must be at least 1",
+                        entry.path.display()
+                    );
+                }
+                Ok(MountLimit {
+                    path: entry.path.clone(),
+                    concurrency: entry.concurrency,
+                })
+            })
+            .collect()
+    }
+
+    pub fn staging_hook(&self) -> anyhow::Result<Option<StagingHook>> {
+        Ok(
+            hook_command(self.staging.as_ref().map(|entry| &entry.command), "staging")?
+                .map(|command| StagingHook { command }),
+        )
+    }
+
+    pub fn external_checks(&self) -> anyhow::Result<Vec<ExternalCheck>> {
+        let mut checks: Vec<ExternalCheck> = Vec::new();
+        for entry in &self.external_check {
+            if entry.name.is_empty()
+                || !entry
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                anyhow::bail!(
+                    "Name '{}' of [[external_check]] must only contain letters, digits and underscores",
+                    entry.name
+                );
+            }
+            if checks.iter().any(|check| check.name == entry.name) {
+                anyhow::bail!("Name '{}' of [[external_check]] is not unique", entry.name);
+            }
+            let command =
+                hook_command(Some(&entry.command), "external_check")?.expect("a command is given");
+            checks.push(ExternalCheck {
+                name: entry.name.clone(),
+                command,
+                check_types: entry.check_types.clone(),
+            });
+        }
+        Ok(checks)
+    }
+
+    pub fn consent_categories(&self) -> anyhow::Result<Vec<consent::Category>> {
+        self.consent_category
+            .iter()
+            .map(|entry| {
+                let path = glob::Pattern::new(&entry.path).with_context(|| {
+                    format!(
+                        "Invalid path pattern '{}' of [[consent_category]] '{}'",
+                        entry.path, entry.name
+                    )
+                })?;
+                Ok(consent::Category {
+                    name: entry.name.clone(),
+                    path,
+                    requires: entry.requires,
+                    expected: entry.expected,
+                })
+            })
+            .collect()
+    }
+
+    pub fn path_constraints(&self) -> anyhow::Result<Option<PathConstraints>> {
+        let Some(entry) = &self.path_constraints else {
+            return Ok(None);
+        };
+        let allowed_characters = entry
+            .allowed_characters
+            .as_deref()
+            .map(CharacterSet::parse)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("{e} in [path_constraints]"))?;
+        Ok(Some(PathConstraints {
+            root: entry.root.clone(),
+            max_length: entry.max_length,
+            allowed_characters,
+        }))
+    }
+
+    pub fn checksum_registry(&self) -> anyhow::Result<Option<ChecksumRegistry>> {
+        let Some(entry) = &self.checksum_registry else {
+            return Ok(None);
+        };
+        let backend = match (&entry.command, &entry.database, &entry.query) {
+            (Some(command), None, None) => {
+                if !command.contains(checksum_registry::ID_PLACEHOLDER) {
+                    anyhow::bail!(
+                        "Command '{command}' of [checksum_registry] must contain the placeholder {}",
+                        checksum_registry::ID_PLACEHOLDER
+                    );
+                }
+                Backend::Command {
+                    command: command.clone(),
+                }
+            }
+            (None, Some(path), query) => Backend::Database {
+                path: path.clone(),
+                query: query
+                    .clone()
+                    .unwrap_or_else(|| checksum_registry::DEFAULT_QUERY.to_string()),
+            },
+            (Some(_), None, Some(_)) => {
+                anyhow::bail!("[checksum_registry] only takes a query with a database")
+            }
+            _ => anyhow::bail!("[checksum_registry] requires either a command or a database"),
+        };
+        Ok(Some(ChecksumRegistry {
+            backend,
+            root: entry.root.clone(),
+        }))
+    }
+
+    pub fn post_check_hook(&self) -> anyhow::Result<Option<PostCheckHook>> {
+        let Some(entry) = &self.post_check else {
+            return Ok(None);
+        };
+        Ok(
+            hook_command(Some(&entry.command), "post_check")?.map(|command| PostCheckHook {
+                command,
+                digest_encoding: entry.digest_encoding,
+            }),
+        )
+    }
+}
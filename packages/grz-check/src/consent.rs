@@ -0,0 +1,166 @@
+//! Cross-check of the submitted files against the consent scope of each donor, given via
+//! `--consent-scope`, and the file categories of the `[[consent_category]]` tables of the
+//! config file.
+//!
+//! Files of a category that requires a broader scope than a donor consented to must not be
+//! submitted for that donor. Categories marked as `expected` must be present for every donor
+//! whose scope covers them. The findings concern the submission as a whole and are reported in
+//! the run-level entry.
+
+use glob::Pattern;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Consent scope declared in the metadata, ordered from narrowest to broadest.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Use of the data for the care of the patient only.
+    Care,
+    /// Use of the data for research in addition to care.
+    Research,
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Scope::Care => "care",
+            Scope::Research => "research",
+        })
+    }
+}
+
+fn serialize_pattern<S: Serializer>(pattern: &Pattern, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(pattern)
+}
+
+/// Consent scope of a donor, whose files are those matching `path`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DonorConsent {
+    pub donor: String,
+    #[serde(serialize_with = "serialize_pattern")]
+    pub path: Pattern,
+    pub scope: Scope,
+}
+
+/// Files whose submission depends on the consent scope, e.g. research-only VCFs.
+#[derive(Debug, Clone, Serialize)]
+pub struct Category {
+    pub name: String,
+    #[serde(serialize_with = "serialize_pattern")]
+    pub path: Pattern,
+    /// Narrowest scope that allows files of the category.
+    pub requires: Scope,
+    /// Whether files of the category must be present if the scope allows them.
+    pub expected: bool,
+}
+
+/// Parses a `DONOR GLOB SCOPE` triple of `--consent-scope`.
+pub fn parse_donor_consent(donor: &str, path: &str, scope: &str) -> Result<DonorConsent, String> {
+    let path = Pattern::new(path)
+        .map_err(|e| format!("Invalid path pattern '{path}' of donor {donor}: {e}"))?;
+    let scope = <Scope as clap::ValueEnum>::from_str(scope, true).map_err(|_| {
+        format!("Invalid consent scope '{scope}' of donor {donor}. Expected care or research.")
+    })?;
+    Ok(DonorConsent {
+        donor: donor.to_string(),
+        path,
+        scope,
+    })
+}
+
+/// Errors and warnings about the files of all donors.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Findings {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Checks the submitted `paths` of each donor against the categories.
+pub fn check(donors: &[DonorConsent], categories: &[Category], paths: &[&Path]) -> Findings {
+    let mut findings = Findings::default();
+    for donor in donors {
+        let donor_paths: Vec<&Path> = paths
+            .iter()
+            .copied()
+            .filter(|path| donor.path.matches_path(path))
+            .collect();
+        for category in categories {
+            let category_paths: Vec<PathBuf> = donor_paths
+                .iter()
+                .filter(|path| category.path.matches_path(path))
+                .map(|path| path.to_path_buf())
+                .sorted()
+                .dedup()
+                .collect();
+            if donor.scope < category.requires && !category_paths.is_empty() {
+                findings.errors.push(format!(
+                    "Consent scope of donor {} is {}, but {} file(s) of category '{}' requiring {} consent are submitted: {}",
+                    donor.donor,
+                    donor.scope,
+                    category_paths.len(),
+                    category.name,
+                    category.requires,
+                    category_paths.iter().map(|path| path.display()).join(", ")
+                ));
+            } else if donor.scope >= category.requires
+                && category.expected
+                && category_paths.is_empty()
+            {
+                findings.warnings.push(format!(
+                    "Consent scope of donor {} is {}, but no file of the expected category '{}' is submitted",
+                    donor.donor, donor.scope, category.name
+                ));
+            }
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consent_scope() {
+        let donors = [
+            parse_donor_consent("index", "files/index/**", "care").unwrap(),
+            parse_donor_consent("mother", "files/mother/**", "Research").unwrap(),
+        ];
+        let categories = [
+            Category {
+                name: "research VCFs".to_string(),
+                path: Pattern::new("**/research/*.vcf.gz").unwrap(),
+                requires: Scope::Research,
+                expected: true,
+            },
+            Category {
+                name: "reads".to_string(),
+                path: Pattern::new("**/*.fastq.gz").unwrap(),
+                requires: Scope::Care,
+                expected: true,
+            },
+        ];
+        let paths = [
+            Path::new("files/index/reads_R1.fastq.gz"),
+            Path::new("files/index/research/calls.vcf.gz"),
+            Path::new("files/mother/reads_R1.fastq.gz"),
+        ];
+        assert_eq!(
+            check(&donors, &categories, &paths),
+            Findings {
+                errors: vec![
+                    "Consent scope of donor index is care, but 1 file(s) of category 'research VCFs' requiring research consent are submitted: files/index/research/calls.vcf.gz".to_string()
+                ],
+                warnings: vec![
+                    "Consent scope of donor mother is research, but no file of the expected category 'research VCFs' is submitted".to_string()
+                ],
+            }
+        );
+        assert!(parse_donor_consent("index", "**", "broad").is_err());
+    }
+}
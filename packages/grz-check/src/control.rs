@@ -0,0 +1,329 @@
+//! A tiny line-based control protocol served over a Unix domain socket.
+//!
+//! Each request is a single line containing one of the commands below, and each response is a
+//! single line of JSON:
+//!
+//! - `status`: overall progress of the run
+//! - `jobs`: all jobs with their paths and current state
+//! - `report`: the latest report entries written so far, at most [`REPORT_TAIL`] of them, with
+//!   the number of earlier entries that were omitted
+//! - `cancel`: gracefully stop the run, like Ctrl+C
+
+use crate::logging;
+use anyhow::Context;
+use indicatif::ProgressBar;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Number of report entries kept for the `report` command. Older entries are only in the report
+/// file, so that the memory used by the control socket does not grow with the run.
+pub const REPORT_TAIL: usize = 1000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Pending,
+    Running,
+    Ok,
+    Error,
+    NotChecked,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobEntry {
+    pub id: usize,
+    pub check_type: &'static str,
+    pub paths: Vec<PathBuf>,
+    pub state: JobState,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusResponse {
+    cancelled: bool,
+    jobs_total: usize,
+    jobs_pending: usize,
+    jobs_running: usize,
+    jobs_ok: usize,
+    jobs_error: usize,
+    jobs_not_checked: usize,
+    bytes_processed: u64,
+    bytes_total: u64,
+}
+
+/// Shared state of a run, updated by the workers and queried by control connections.
+pub struct ControlState {
+    jobs: Mutex<Vec<JobEntry>>,
+    report_lines: Mutex<ReportTail>,
+    progress: ProgressBar,
+    shutdown_flag: Arc<AtomicBool>,
+}
+
+impl ControlState {
+    pub fn new(jobs: Vec<JobEntry>, progress: ProgressBar, shutdown_flag: Arc<AtomicBool>) -> Self {
+        Self {
+            jobs: Mutex::new(jobs),
+            report_lines: Mutex::new(ReportTail::default()),
+            progress,
+            shutdown_flag,
+        }
+    }
+
+    pub fn set_job_state(&self, id: usize, state: JobState) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            job.state = state;
+        }
+    }
+
+    /// Records the JSONL lines written to the report for a job.
+    pub fn add_report_lines(&self, lines: &[u8]) {
+        let mut report_lines = self.report_lines.lock().unwrap();
+        for line in String::from_utf8_lossy(lines).lines() {
+            if !line.is_empty() {
+                report_lines.push(line.to_string());
+            }
+        }
+    }
+
+    fn status(&self) -> StatusResponse {
+        let jobs = self.jobs.lock().unwrap();
+        let count = |state| jobs.iter().filter(|j| j.state == state).count();
+        StatusResponse {
+            cancelled: self.shutdown_flag.load(Ordering::Relaxed),
+            jobs_total: jobs.len(),
+            jobs_pending: count(JobState::Pending),
+            jobs_running: count(JobState::Running),
+            jobs_ok: count(JobState::Ok),
+            jobs_error: count(JobState::Error),
+            jobs_not_checked: count(JobState::NotChecked),
+            bytes_processed: self.progress.position(),
+            bytes_total: self.progress.length().unwrap_or_default(),
+        }
+    }
+
+    fn respond(&self, command: &str) -> String {
+        match command {
+            "status" => serde_json::to_string(&self.status()).expect("Status should serialize"),
+            "jobs" => serde_json::json!({ "jobs": *self.jobs.lock().unwrap() }).to_string(),
+            "report" => {
+                let report_lines = self.report_lines.lock().unwrap();
+                let entries: Vec<&str> = report_lines.lines.iter().map(String::as_str).collect();
+                format!(
+                    "{{\"entries\":[{}],\"omitted\":{}}}",
+                    entries.join(","),
+                    report_lines.omitted
+                )
+            }
+            "cancel" => {
+                self.shutdown_flag.store(true, Ordering::SeqCst);
+                serde_json::json!({ "ok": true }).to_string()
+            }
+            other => serde_json::json!({
+                "error": format!("Unknown command '{other}'. Expected one of: status, jobs, report, cancel."),
+            })
+            .to_string(),
+        }
+    }
+}
+
+/// The latest report entries, with the number of entries dropped to stay within [`REPORT_TAIL`].
+#[derive(Debug, Default)]
+struct ReportTail {
+    lines: VecDeque<String>,
+    omitted: usize,
+}
+
+impl ReportTail {
+    fn push(&mut self, line: String) {
+        if self.lines.len() == REPORT_TAIL {
+            self.lines.pop_front();
+            self.omitted += 1;
+        }
+        self.lines.push_back(line);
+    }
+}
+
+fn handle_connection(stream: UnixStream, state: &ControlState) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        let command = line.trim();
+        if command.is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}", state.respond(command))?;
+    }
+    Ok(())
+}
+
+/// Serves the control protocol on a background thread until stopped.
+pub struct ControlServer {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl ControlServer {
+    pub fn start(path: &Path, state: Arc<ControlState>) -> anyhow::Result<Self> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                anyhow::bail!(
+                    "Control socket {} is already in use by another process",
+                    path.display()
+                );
+            }
+            std::fs::remove_file(path).with_context(|| {
+                format!("Failed to remove stale control socket {}", path.display())
+            })?;
+        }
+
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("Failed to bind control socket at {}", path.display()))?;
+        listener.set_nonblocking(true)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let state = state.clone();
+                            thread::spawn(move || {
+                                if let Err(e) = handle_connection(stream, &state) {
+                                    logging::warn(format!("Control socket connection failed: {e}"));
+                                }
+                            });
+                        }
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                            thread::sleep(Duration::from_millis(50));
+                        }
+                        Err(e) => {
+                            logging::error(format!(
+                                "Control socket stopped accepting connections: {e}"
+                            ));
+                            break;
+                        }
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            stop,
+            handle,
+        })
+    }
+
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use tempfile::tempdir;
+
+    fn test_state() -> ControlState {
+        let jobs = vec![
+            JobEntry {
+                id: 0,
+                check_type: "raw",
+                paths: vec![PathBuf::from("a.txt")],
+                state: JobState::Pending,
+            },
+            JobEntry {
+                id: 1,
+                check_type: "raw",
+                paths: vec![PathBuf::from("b.txt")],
+                state: JobState::Pending,
+            },
+        ];
+        let progress = ProgressBar::hidden();
+        progress.set_length(100);
+        ControlState::new(jobs, progress, Arc::new(AtomicBool::new(false)))
+    }
+
+    fn respond(state: &ControlState, command: &str) -> Value {
+        serde_json::from_str(&state.respond(command)).expect("Responses should be JSON")
+    }
+
+    #[test]
+    fn test_status_and_cancel() {
+        let state = test_state();
+        state.set_job_state(0, JobState::Ok);
+        state.set_job_state(1, JobState::Running);
+        state.progress.set_position(40);
+
+        let status = respond(&state, "status");
+        assert_eq!(status["cancelled"], false);
+        assert_eq!(status["jobs_total"], 2);
+        assert_eq!(status["jobs_ok"], 1);
+        assert_eq!(status["jobs_running"], 1);
+        assert_eq!(status["jobs_pending"], 0);
+        assert_eq!(status["bytes_processed"], 40);
+        assert_eq!(status["bytes_total"], 100);
+
+        assert_eq!(respond(&state, "cancel")["ok"], true);
+        assert!(state.shutdown_flag.load(Ordering::Relaxed));
+        assert_eq!(respond(&state, "status")["cancelled"], true);
+
+        let unknown = respond(&state, "pause");
+        assert!(unknown["error"].as_str().unwrap().contains("'pause'"));
+    }
+
+    #[test]
+    fn test_report_keeps_the_latest_entries() {
+        let state = test_state();
+        state.add_report_lines(b"{\"n\":0}\n\n{\"n\":1}\n");
+        let report = respond(&state, "report");
+        assert_eq!(
+            report["entries"],
+            serde_json::json!([{ "n": 0 }, { "n": 1 }])
+        );
+        assert_eq!(report["omitted"], 0);
+
+        for n in 2..REPORT_TAIL + 5 {
+            state.add_report_lines(format!("{{\"n\":{n}}}\n").as_bytes());
+        }
+        let report = respond(&state, "report");
+        let entries = report["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), REPORT_TAIL);
+        assert_eq!(entries[0]["n"], 5);
+        assert_eq!(entries[REPORT_TAIL - 1]["n"], REPORT_TAIL + 4);
+        assert_eq!(report["omitted"], 5);
+    }
+
+    #[test]
+    fn test_server_answers_and_removes_its_socket() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("control.sock");
+        let server = ControlServer::start(&path, Arc::new(test_state()))?;
+        assert!(path.exists());
+
+        let stream = UnixStream::connect(&path)?;
+        writeln!(&stream, "jobs")?;
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        let jobs: Value = serde_json::from_str(&line)?;
+        assert_eq!(jobs["jobs"][1]["paths"][0], "b.txt");
+        assert_eq!(jobs["jobs"][1]["state"], "pending");
+        drop(stream);
+
+        server.stop();
+        assert!(!path.exists());
+        Ok(())
+    }
+}
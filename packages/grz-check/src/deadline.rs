@@ -0,0 +1,155 @@
+//! Overall wall-clock budget of a run, given via `--deadline`.
+//!
+//! Once the deadline has passed, no further jobs are started and the remaining jobs are
+//! reported as `NOT_CHECKED`. Running jobs are allowed to finish, unless a grace period is
+//! given via `--deadline-grace`, after which they are reported as `NOT_CHECKED` as well and the
+//! run is aborted.
+
+use crate::logging;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+const DURATION_UNITS: &[(char, u64)] = &[('d', 86400), ('h', 3600), ('m', 60), ('s', 1)];
+
+/// Parses a duration of one or more numbers with a unit, e.g. `90s`, `45m` or `1h30m`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration '{s}'. Expected e.g. 90s, 45m or 1h30m.");
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let &(_, multiplier) = DURATION_UNITS
+            .iter()
+            .find(|&&(unit, _)| unit == c)
+            .ok_or_else(invalid)?;
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        seconds = value
+            .checked_mul(multiplier)
+            .and_then(|value| seconds.checked_add(value))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+    if !number.is_empty() || seconds == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Parses a deadline given as an RFC 3339 timestamp, e.g. `2025-06-01T22:00:00+02:00`, or as
+/// a duration from now.
+pub fn parse_deadline(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    let duration = parse_duration(s).map_err(|_| {
+        format!("Invalid deadline '{s}'. Expected a duration like 8h or an RFC 3339 timestamp.")
+    })?;
+    let duration = chrono::Duration::from_std(duration).map_err(|e| e.to_string())?;
+    Ok(Utc::now() + duration)
+}
+
+/// Check type and paths of a job that has not finished yet.
+pub type PendingJob = (&'static str, Vec<PathBuf>);
+
+#[derive(Debug)]
+pub struct Deadline {
+    pub at: DateTime<Utc>,
+    pub grace: Option<Duration>,
+    reached: AtomicBool,
+    /// Jobs that have not been reported yet, by ID.
+    unfinished: Mutex<BTreeMap<usize, PendingJob>>,
+    not_checked: AtomicUsize,
+}
+
+impl Deadline {
+    pub fn new(
+        at: DateTime<Utc>,
+        grace: Option<Duration>,
+        jobs: BTreeMap<usize, PendingJob>,
+    ) -> Self {
+        Self {
+            at,
+            grace,
+            reached: AtomicBool::new(false),
+            unfinished: Mutex::new(jobs),
+            not_checked: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns whether the deadline has passed, logging when it is first noticed.
+    pub fn is_reached(&self) -> bool {
+        if Utc::now() < self.at {
+            return false;
+        }
+        if !self.reached.swap(true, Ordering::SeqCst) {
+            logging::warn(format!(
+                "Deadline {} reached; no further jobs are started",
+                self.at.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ));
+        }
+        true
+    }
+
+    /// Time until the end of the grace period, if any.
+    pub fn until_cancellation(&self) -> Option<Duration> {
+        let end = self.at + chrono::Duration::from_std(self.grace?).ok()?;
+        Some((end - Utc::now()).to_std().unwrap_or_default())
+    }
+
+    /// Marks a job as reported.
+    pub fn finish(&self, id: usize) {
+        self.unfinished.lock().unwrap().remove(&id);
+    }
+
+    /// Marks a job as not checked, returning its check type and paths.
+    pub fn skip(&self, id: usize) -> Option<PendingJob> {
+        let job = self.unfinished.lock().unwrap().remove(&id)?;
+        self.not_checked.fetch_add(1, Ordering::SeqCst);
+        Some(job)
+    }
+
+    /// Marks all unfinished jobs as not checked, returning them by ID.
+    pub fn skip_all(&self) -> Vec<(usize, PendingJob)> {
+        let jobs: Vec<_> = std::mem::take(&mut *self.unfinished.lock().unwrap())
+            .into_iter()
+            .collect();
+        self.not_checked.fetch_add(jobs.len(), Ordering::SeqCst);
+        jobs
+    }
+
+    /// Number of jobs reported as not checked.
+    pub fn num_not_checked(&self) -> usize {
+        self.not_checked.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_deadline() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::from_secs(172_800)));
+        assert!(parse_duration("90").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("5w").is_err());
+
+        assert_eq!(
+            parse_deadline("2025-06-01T22:00:00+02:00").unwrap(),
+            DateTime::parse_from_rfc3339("2025-06-01T20:00:00Z").unwrap()
+        );
+        let deadline = parse_deadline("1h").unwrap();
+        assert!(deadline > Utc::now() + chrono::Duration::minutes(59));
+        assert!(parse_deadline("tomorrow").is_err());
+    }
+}
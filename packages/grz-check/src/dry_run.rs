@@ -0,0 +1,546 @@
+//! Planning output of `--dry-run`: the jobs that would be run, with their size on disk and their
+//! estimated decompressed size, which is the amount of data parsed by the checks.
+//!
+//! Decompressed sizes are read from compression metadata where it is reliable: the sizes stored
+//! in the blocks of BGZF files, or the trailer of small gzip files. Other compressed files are
+//! estimated from the compression ratio of their beginning.
+//!
+//! With `--previous-report`, the jobs are also listed one by one with their status and number
+//! of findings in an earlier report, and with a runtime estimated from the throughput per check
+//! type recorded in it. Throughput is only known from reports written with `--stats full`, and is
+//! computed from the current sizes of the previously checked files.
+
+use crate::archive::{self, Section};
+use crate::checker::Job;
+use crate::quota;
+use anyhow::Context;
+use indicatif::{HumanBytes, HumanDuration};
+use itertools::Itertools;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Number of BGZF blocks whose sizes are summed before extrapolating to the rest of the file.
+const MAX_BGZF_BLOCKS: u64 = 4096;
+
+/// Largest gzip file whose trailer is trusted. The trailer stores the decompressed size modulo
+/// 4 GiB, so it is only meaningful for files that are unlikely to decompress to more.
+const MAX_GZIP_TRAILER_SIZE: u64 = 128 * 1024 * 1024;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Sums the decompressed sizes stored in the blocks of a BGZF file.
+///
+/// Returns `None` if the file is not BGZF-compressed.
+fn bgzf_size(file: &mut Section) -> io::Result<Option<u64>> {
+    let size = file.size();
+    let mut position = 0;
+    let mut total = 0;
+    for _ in 0..MAX_BGZF_BLOCKS {
+        if position >= size {
+            return Ok(Some(total));
+        }
+        let header = file.read_at(position, 18)?;
+        // gzip header with FEXTRA set, followed by the `BC` subfield holding the block size
+        if header[..2] != GZIP_MAGIC || header[3] & 4 == 0 || header[12..14] != *b"BC" {
+            return Ok(None);
+        }
+        let block_size = u64::from(u16::from_le_bytes([header[16], header[17]])) + 1;
+        let trailer = file.read_at(position + block_size - 4, 4)?;
+        total += u64::from(u32::from_le_bytes(trailer.try_into().unwrap()));
+        position += block_size;
+    }
+    Ok(Some((total as f64 * size as f64 / position as f64) as u64))
+}
+
+/// Estimates the number of bytes the checks of a file parse.
+fn decompressed_size(path: &Path, decompressed: bool) -> anyhow::Result<u64> {
+    let mut file = match archive::open_section(path) {
+        Ok(file) => file,
+        // Compressed members of zip archives can only be read from the start.
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            return match decompressed {
+                true => quota::estimate_decompressed_size(path),
+                false => Ok(archive::size(path)?),
+            };
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let size = file.size();
+    if !decompressed || size < 18 || file.read_at(0, 2)? != GZIP_MAGIC {
+        if decompressed {
+            return quota::estimate_decompressed_size(path);
+        }
+        return Ok(size);
+    }
+    if let Some(bgzf_size) = bgzf_size(&mut file)? {
+        return Ok(bgzf_size);
+    }
+    if size <= MAX_GZIP_TRAILER_SIZE {
+        let trailer = file.read_at(size - 4, 4)?;
+        return Ok(u64::from(u32::from_le_bytes(trailer.try_into().unwrap())));
+    }
+    quota::estimate_decompressed_size(path)
+}
+
+/// Writes the jobs that would be run, grouped by check type.
+pub fn write_plan(jobs: &[Job], writer: &mut impl Write) -> anyhow::Result<()> {
+    writeln!(
+        writer,
+        "Dry run: {} job(s) would be run; no file was checked.\n",
+        jobs.len()
+    )?;
+    writeln!(
+        writer,
+        "{:<10} {:>6} {:>14} {:>18}",
+        "CHECK", "FILES", "SIZE ON DISK", "EST. DECOMPRESSED"
+    )?;
+    let mut total = (0, 0, 0);
+    // Files whose size could not be determined, e.g. truncated ones, with the error.
+    let mut unreadable = Vec::new();
+    let by_type = jobs.iter().into_group_map_by(|job| job.check_type());
+    for (check_type, jobs) in by_type
+        .into_iter()
+        .sorted_by_key(|(check_type, _)| *check_type)
+    {
+        // Raw and signal files are read as they are.
+        let decompressed = !matches!(check_type, "raw" | "gzi" | "pod5" | "fast5");
+        let (mut files, mut on_disk, mut parsed) = (0, 0, 0);
+        for path in jobs.iter().flat_map(|job| job.paths()) {
+            files += 1;
+            match archive::size(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|size| Ok((size, decompressed_size(&path, decompressed)?)))
+            {
+                Ok((size, decompressed_size)) => {
+                    on_disk += size;
+                    parsed += decompressed_size;
+                }
+                Err(e) => unreadable.push((path, e)),
+            }
+        }
+        writeln!(
+            writer,
+            "{check_type:<10} {files:>6} {:>14} {:>18}",
+            HumanBytes(on_disk).to_string(),
+            HumanBytes(parsed).to_string()
+        )?;
+        total = (total.0 + files, total.1 + on_disk, total.2 + parsed);
+    }
+    writeln!(
+        writer,
+        "{:<10} {:>6} {:>14} {:>18}",
+        "total",
+        total.0,
+        HumanBytes(total.1).to_string(),
+        HumanBytes(total.2).to_string()
+    )?;
+    if !unreadable.is_empty() {
+        writeln!(
+            writer,
+            "\nThe sizes of {} file(s) could not be determined and are not included:",
+            unreadable.len()
+        )?;
+        for (path, e) in unreadable {
+            writeln!(writer, "  {}: {e:#}", path.display())?;
+        }
+    }
+    Ok(())
+}
+
+/// Entry of a file in a previous report.
+#[derive(Debug, Clone, PartialEq)]
+struct PreviousEntry {
+    status: String,
+    findings: usize,
+    /// Total processing time, from reports written with `--stats full` only.
+    seconds: Option<f64>,
+    /// Length of the JSONL line of the entry.
+    report_bytes: usize,
+}
+
+/// File entries of a previous report, by check type and path.
+#[derive(Debug, Default)]
+pub struct PreviousReport {
+    entries: HashMap<(String, PathBuf), PreviousEntry>,
+}
+
+impl PreviousReport {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open previous report {}", path.display()))?;
+        Self::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to read previous report {}", path.display()))
+    }
+
+    fn from_reader(reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut report = Self::default();
+        let mut pairs = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Value = serde_json::from_str(&line)
+                .with_context(|| format!("Line {} is not valid JSON", i + 1))?;
+            let (Some(check_type), Some(data)) = (entry["check_type"].as_str(), entry.get("data"))
+            else {
+                continue;
+            };
+            if check_type == "entity" {
+                if data["kind"] == "pair" {
+                    pairs.push((data.clone(), line.len() + 1));
+                }
+                continue;
+            }
+            // Jobs that were not checked are listed with the check type of the job.
+            let (check_type, paths) = match check_type {
+                "not_checked" => (
+                    data["check"].as_str().unwrap_or_default(),
+                    data["paths"].as_array().cloned().unwrap_or_default(),
+                ),
+                _ => (check_type, vec![data["path"].clone()]),
+            };
+            let previous = PreviousEntry {
+                status: data["status"].as_str().unwrap_or("UNKNOWN").to_string(),
+                findings: data["findings"].as_array().map_or(0, Vec::len),
+                seconds: data["timings"]["total_seconds"].as_f64(),
+                report_bytes: line.len() + 1,
+            };
+            for path in paths.iter().filter_map(Value::as_str) {
+                report.entries.insert(
+                    (check_type.to_string(), PathBuf::from(path)),
+                    previous.clone(),
+                );
+            }
+        }
+        // The findings concerning a pair are in the entry of the pair rather than of its files,
+        // so they are attributed to its first file, and its status to all of them.
+        for (pair, report_bytes) in pairs {
+            let members = pair["members"].as_array().cloned().unwrap_or_default();
+            for (i, member) in members.iter().filter_map(Value::as_str).enumerate() {
+                let key = ("fastq".to_string(), PathBuf::from(member));
+                let Some(entry) = report.entries.get_mut(&key) else {
+                    continue;
+                };
+                if pair["status"] == "ERROR" && entry.status == "OK" {
+                    entry.status = "ERROR".to_string();
+                }
+                if i == 0 {
+                    entry.findings += pair["findings"].as_array().map_or(0, Vec::len);
+                    entry.report_bytes += report_bytes;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    fn get(&self, check_type: &str, path: &Path) -> Option<&PreviousEntry> {
+        self.entries
+            .get(&(check_type.to_string(), path.to_path_buf()))
+    }
+
+    /// Bytes processed per second for each check type, from the timed entries whose files still
+    /// exist.
+    fn throughput(&self) -> HashMap<&str, f64> {
+        let mut totals: HashMap<&str, (u64, f64)> = HashMap::new();
+        for ((check_type, path), entry) in &self.entries {
+            if let (Some(seconds), Ok(size)) = (entry.seconds, archive::size(path)) {
+                let total = totals.entry(check_type).or_default();
+                *total = (total.0 + size, total.1 + seconds);
+            }
+        }
+        totals
+            .into_iter()
+            .filter(|&(_, (_, seconds))| seconds > 0.0)
+            .map(|(check_type, (size, seconds))| (check_type, size as f64 / seconds))
+            .collect()
+    }
+
+    /// Mean length of the report entries of each check type.
+    fn mean_report_bytes(&self) -> HashMap<&str, usize> {
+        self.entries
+            .iter()
+            .into_group_map_by(|((check_type, _), _)| check_type.as_str())
+            .into_iter()
+            .map(|(check_type, entries)| {
+                let total: usize = entries.iter().map(|(_, entry)| entry.report_bytes).sum();
+                (check_type, total / entries.len())
+            })
+            .collect()
+    }
+}
+
+/// Status of a job in a previous report: the worst status of its files, or `NEW` if none of
+/// them was reported.
+fn previous_status(entries: &[&PreviousEntry]) -> &'static str {
+    let has = |status: &str| entries.iter().any(|entry| entry.status == status);
+    if entries.is_empty() {
+        "NEW"
+    } else if has("ERROR") || has("MISSING") {
+        "ERROR"
+    } else if has("NOT_CHECKED") {
+        "NOT_CHECKED"
+    } else {
+        "OK"
+    }
+}
+
+/// Writes each job with its status and findings in a previous report and an estimate of its
+/// runtime, followed by the expected size of the report and the total runtime.
+pub fn write_estimates(
+    jobs: &[Job],
+    previous: &PreviousReport,
+    writer: &mut impl Write,
+) -> anyhow::Result<()> {
+    let throughput = previous.throughput();
+    let mean_report_bytes = previous.mean_report_bytes();
+    writeln!(
+        writer,
+        "\n{:<10} {:<12} {:>8} {:>10}  PATH",
+        "CHECK", "PREVIOUS", "FINDINGS", "EST. TIME"
+    )?;
+    let (mut total_seconds, mut unknown_time, mut report_bytes) = (0.0, 0, 0);
+    let (mut failed, mut new) = (0, 0);
+    for job in jobs {
+        let check_type = job.check_type();
+        let paths = job.paths();
+        let entries: Vec<&PreviousEntry> = paths
+            .iter()
+            .filter_map(|path| previous.get(check_type, path))
+            .collect();
+        let status = previous_status(&entries);
+        match status {
+            "NEW" => new += 1,
+            "ERROR" | "NOT_CHECKED" => failed += 1,
+            _ => {}
+        }
+        report_bytes += paths
+            .iter()
+            .map(|path| match previous.get(check_type, path) {
+                Some(entry) => entry.report_bytes,
+                None => mean_report_bytes.get(check_type).copied().unwrap_or(0),
+            })
+            .sum::<usize>();
+
+        let size: u64 = paths
+            .iter()
+            .map(|path| archive::size(path).unwrap_or(0))
+            .sum();
+        let seconds = throughput
+            .get(check_type)
+            .map(|bytes_per_second| size as f64 / bytes_per_second);
+        match seconds {
+            Some(seconds) => total_seconds += seconds,
+            None => unknown_time += 1,
+        }
+        writeln!(
+            writer,
+            "{check_type:<10} {status:<12} {:>8} {:>10}  {}",
+            entries.iter().map(|entry| entry.findings).sum::<usize>(),
+            seconds.map_or("-".to_string(), |seconds| {
+                HumanDuration(Duration::from_secs_f64(seconds)).to_string()
+            }),
+            paths.iter().map(|path| path.display()).join(", ")
+        )?;
+    }
+
+    writeln!(
+        writer,
+        "\n{failed} job(s) failed or were not checked previously, {new} job(s) are new."
+    )?;
+    writeln!(
+        writer,
+        "Estimated report size: {}",
+        HumanBytes(report_bytes as u64)
+    )?;
+    write!(
+        writer,
+        "Estimated check time: {} in total, before parallelization",
+        HumanDuration(Duration::from_secs_f64(total_seconds))
+    )?;
+    if unknown_time > 0 {
+        write!(
+            writer,
+            " ({unknown_time} job(s) without timings of their check type in the previous report)"
+        )?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::raw::RawJob;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use noodles::bgzf;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_decompressed_sizes_from_metadata() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let content = "@r\nACGT\n+\nIIII\n".repeat(10_000);
+
+        let gz_path = dir.path().join("reads.fastq.gz");
+        let mut encoder = GzEncoder::new(fs::File::create(&gz_path)?, Compression::default());
+        encoder.write_all(content.as_bytes())?;
+        encoder.finish()?;
+        assert_eq!(decompressed_size(&gz_path, true)?, content.len() as u64);
+
+        let bgzf_path = dir.path().join("reads.bgz");
+        let mut writer = bgzf::io::Writer::new(fs::File::create(&bgzf_path)?);
+        writer.write_all(content.as_bytes())?;
+        writer.finish()?;
+        let mut file = archive::open_section(&bgzf_path)?;
+        assert_eq!(bgzf_size(&mut file)?, Some(content.len() as u64));
+        assert_eq!(bgzf_size(&mut archive::open_section(&gz_path)?)?, None);
+
+        let jobs = vec![Job::Raw(RawJob {
+            path: gz_path.clone(),
+            size: fs::metadata(&gz_path)?.len(),
+            expected_sha256: None,
+        })];
+        let mut plan = Vec::new();
+        write_plan(&jobs, &mut plan)?;
+        let plan = String::from_utf8(plan)?;
+        assert!(plan.starts_with("Dry run: 1 job(s)"), "{plan}");
+        assert!(plan.lines().any(|line| line.starts_with("raw ")), "{plan}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_lists_files_of_unknown_size() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let content = "@r\nACGT\n+\nIIII\n".repeat(10_000);
+        let mut writer = bgzf::io::Writer::new(Vec::new());
+        writer.write_all(content.as_bytes())?;
+        let bgzf = writer.finish()?;
+        let truncated_path = dir.path().join("truncated.fastq.gz");
+        fs::write(&truncated_path, &bgzf[..bgzf.len() / 2])?;
+        let complete_path = dir.path().join("complete.txt");
+        fs::write(&complete_path, "contents")?;
+
+        let jobs: Vec<Job> = [&truncated_path, &complete_path]
+            .into_iter()
+            .map(|path| {
+                Job::Raw(RawJob {
+                    path: path.clone(),
+                    size: 0,
+                    expected_sha256: None,
+                })
+            })
+            .chain(std::iter::once(Job::SingleFastq(
+                crate::checks::fastq::SingleFastqJob {
+                    path: truncated_path.clone(),
+                    length_check: crate::checks::fastq::ReadLengthCheck::Skip,
+                    declared_read_length: None,
+                    size: 0,
+                },
+            )))
+            .collect();
+        let mut plan = Vec::new();
+        write_plan(&jobs, &mut plan)?;
+        let plan = String::from_utf8(plan)?;
+        assert!(
+            plan.contains("The sizes of 1 file(s) could not be determined"),
+            "{plan}"
+        );
+        assert!(
+            plan.contains(&format!("  {}: ", truncated_path.display())),
+            "{plan}"
+        );
+        assert!(plan.lines().any(|line| line.starts_with("raw ")), "{plan}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_dry_run_does_not_require_an_output() {
+        use clap::Parser;
+
+        let args = crate::Args::try_parse_from(["grz-check", "--scan", "data", "--dry-run"]);
+        assert!(args.is_ok_and(|args| args.output.is_none()));
+        let args = crate::Args::try_parse_from(["grz-check", "--scan", "data"]);
+        assert!(args.is_err());
+    }
+
+    #[test]
+    fn test_estimates_from_previous_report() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let raw_job = |name: &str, size: usize| -> anyhow::Result<Job> {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![b'x'; size])?;
+            Ok(Job::Raw(RawJob {
+                path,
+                size: size as u64,
+                expected_sha256: None,
+            }))
+        };
+        let jobs = vec![
+            raw_job("ok.bin", 1000)?,
+            raw_job("failed.bin", 3000)?,
+            raw_job("new.bin", 2000)?,
+        ];
+        let entry = |name: &str, status: &str, findings: &str| {
+            format!(
+                r#"{{"schema_version":2,"check_type":"raw","data":{{"path":"{}","status":"{status}","findings":[{findings}],"timings":{{"total_seconds":2.0}}}}}}"#,
+                dir.path().join(name).display()
+            )
+        };
+        let report = [
+            entry("ok.bin", "OK", ""),
+            entry("failed.bin", "ERROR", r#"{"code":"file.unreadable"}"#),
+            r#"{"schema_version":2,"check_type":"run","data":{"errors":[]}}"#.to_string(),
+        ]
+        .join("\n");
+        let previous = PreviousReport::from_reader(report.as_bytes())?;
+        // 4000 bytes in 4 seconds
+        assert_eq!(previous.throughput().get("raw").copied(), Some(1000.0));
+
+        let mut output = Vec::new();
+        write_estimates(&jobs, &previous, &mut output)?;
+        let output = String::from_utf8(output)?;
+        let row = |name: &str| {
+            output
+                .lines()
+                .find(|line| line.ends_with(name))
+                .unwrap_or_default()
+                .split_whitespace()
+                .take(4)
+                .join(" ")
+        };
+        assert_eq!(row("ok.bin"), "raw OK 0 1");
+        assert_eq!(row("failed.bin"), "raw ERROR 1 3");
+        assert_eq!(row("new.bin"), "raw NEW 0 2");
+        assert!(
+            output.contains("1 job(s) failed or were not checked previously, 1 job(s) are new."),
+            "{output}"
+        );
+        assert!(
+            output.contains("Estimated check time: 6 seconds"),
+            "{output}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pair_findings_from_entity_entries() -> anyhow::Result<()> {
+        let report = [
+            r#"{"schema_version":4,"check_type":"fastq","data":{"path":"s_R1.fastq.gz","status":"OK","findings":[]}}"#,
+            r#"{"schema_version":4,"check_type":"fastq","data":{"path":"s_R2.fastq.gz","status":"OK","findings":[]}}"#,
+            r#"{"schema_version":4,"check_type":"entity","data":{"kind":"pair","id":"s_R1.fastq.gz","status":"ERROR","members":["s_R1.fastq.gz","s_R2.fastq.gz"],"failed_members":[],"findings":[{"code":"fastq.pair_record_count"}]}}"#,
+        ]
+        .join("\n");
+        let previous = PreviousReport::from_reader(report.as_bytes())?;
+        let fq1 = previous.get("fastq", Path::new("s_R1.fastq.gz")).unwrap();
+        let fq2 = previous.get("fastq", Path::new("s_R2.fastq.gz")).unwrap();
+        assert_eq!((fq1.status.as_str(), fq1.findings), ("ERROR", 1));
+        assert_eq!((fq2.status.as_str(), fq2.findings), ("ERROR", 0));
+        Ok(())
+    }
+}
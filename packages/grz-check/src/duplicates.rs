@@ -0,0 +1,136 @@
+//! Detection of files that are checked more than once within a run: paths reaching the same
+//! file, e.g. through symlinks or bind mounts, and files with identical content.
+
+use crate::checker::Job;
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Identity of a file, shared by all paths reaching it.
+pub type FileId = (u64, u64);
+
+#[cfg(unix)]
+pub fn metadata_id(metadata: &fs::Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+pub fn metadata_id(_metadata: &fs::Metadata) -> Option<FileId> {
+    None
+}
+
+pub fn file_id(path: &Path) -> Option<FileId> {
+    fs::metadata(path).ok().and_then(|m| metadata_id(&m))
+}
+
+/// Other paths of checked files, by the path they were checked under.
+#[derive(Debug, Default)]
+pub struct Aliases(HashMap<PathBuf, Vec<PathBuf>>);
+
+impl Aliases {
+    pub fn of(&self, path: &Path) -> &[PathBuf] {
+        self.0.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Files of the jobs kept so far, to recognize later jobs reaching them through other paths.
+#[derive(Debug, Default)]
+pub struct Deduper {
+    seen: HashMap<(&'static str, Vec<FileId>), Vec<PathBuf>>,
+}
+
+impl Deduper {
+    /// Returns the job unless its files are the files of an earlier job of the same type reached
+    /// through other paths, in which case the pairs of the checked path and the dropped path are
+    /// returned instead. Jobs whose files cannot be identified, e.g. members of archives, are
+    /// kept.
+    pub fn keep(&mut self, job: Job) -> Result<Job, Vec<(PathBuf, PathBuf)>> {
+        let paths = job.paths();
+        let Some(ids) = paths
+            .iter()
+            .map(|path| file_id(path))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(job);
+        };
+        match self.seen.get(&(job.check_type(), ids.clone())) {
+            // The same paths given twice are not aliases, and are checked twice.
+            Some(checked_paths) if *checked_paths != paths => {
+                Err(checked_paths.iter().cloned().zip(paths).collect())
+            }
+            Some(_) => Ok(job),
+            None => {
+                self.seen.insert((job.check_type(), ids), paths);
+                Ok(job)
+            }
+        }
+    }
+}
+
+/// Drops jobs whose files are the files of an earlier job of the same type reached through other
+/// paths, so that their bytes are only read once. Returns the remaining jobs, the number of
+/// bytes of the dropped files, the paths of the dropped files by the paths they are checked
+/// under, and the state for deduplicating further jobs.
+pub fn dedupe_jobs(jobs: Vec<Job>) -> (Vec<Job>, u64, Aliases, Deduper) {
+    let mut deduper = Deduper::default();
+    let mut aliases = Aliases::default();
+    let mut dropped_bytes = 0;
+    let mut kept = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        match deduper.keep(job) {
+            Ok(job) => kept.push(job),
+            Err(dropped) => {
+                for (checked_path, path) in dropped {
+                    dropped_bytes += fs::metadata(&path).map_or(0, |m| m.len());
+                    if checked_path != path {
+                        aliases.0.entry(checked_path).or_default().push(path);
+                    }
+                }
+            }
+        }
+    }
+    (kept, dropped_bytes, aliases, deduper)
+}
+
+/// Paths of all checked files by checksum.
+#[derive(Debug, Default)]
+pub struct DigestIndex {
+    paths: Mutex<BTreeMap<String, Vec<PathBuf>>>,
+}
+
+impl DigestIndex {
+    pub fn add(&self, path: &Path, checksum: &str) {
+        let mut paths = self.paths.lock().unwrap();
+        let entry = paths.entry(checksum.to_string()).or_default();
+        if !entry.iter().any(|p| p == path) {
+            entry.push(path.to_path_buf());
+        }
+    }
+
+    /// Describes each group of differently named files with identical content.
+    pub fn duplicates(&self) -> Vec<String> {
+        self.paths
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(checksum, paths)| {
+                format!(
+                    "Files with identical content (SHA256 {checksum}): {}",
+                    paths.iter().sorted().map(|p| p.display()).join(", ")
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,104 @@
+//! Logical entities made of several checked files: FASTQ pairs, samples (lab data given via
+//! `--lab-datum`) and the submission as a whole.
+//!
+//! Findings concerning an entity rather than any of its files, e.g. mates that do not match, are
+//! attached to the entity instead of being repeated in the entry of each file. The status of an
+//! entity rolls up its own findings and the statuses of its members: a failed file fails its
+//! pair, a failed pair its sample, and any failed entry the submission.
+
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    /// A FASTQ pair, identified by the path of its first read, including the index reads of a
+    /// `--fastq-triple`.
+    Pair,
+    /// A lab datum, identified by its ID, with its FASTQ pairs as members.
+    Sample,
+    /// All entries of the report.
+    Submission,
+}
+
+impl fmt::Display for EntityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntityKind::Pair => write!(f, "pair"),
+            EntityKind::Sample => write!(f, "sample"),
+            EntityKind::Submission => write!(f, "submission"),
+        }
+    }
+}
+
+/// An entity with its own findings and its members, given by their paths or the IDs of their
+/// entities.
+#[derive(Debug, Clone)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub id: String,
+    pub members: Vec<String>,
+    /// Members whose status is not OK, in the order they were added.
+    pub failed_members: Vec<String>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl Entity {
+    pub fn new(kind: EntityKind, id: impl Into<String>) -> Self {
+        Entity {
+            kind,
+            id: id.into(),
+            members: Vec::new(),
+            failed_members: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn add_member(&mut self, member: impl Into<String>, is_ok: bool) {
+        let member = member.into();
+        if !is_ok {
+            self.failed_members.push(member.clone());
+        }
+        self.members.push(member);
+    }
+
+    /// Whether a member has been added as failed.
+    pub fn has_failed(&self, member: &str) -> bool {
+        self.failed_members.iter().any(|failed| failed == member)
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty() && self.failed_members.is_empty()
+    }
+
+    pub fn status(&self) -> &'static str {
+        if self.is_ok() { "OK" } else { "ERROR" }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_rolls_up_members_and_own_findings() {
+        let mut pair = Entity::new(EntityKind::Pair, "s_R1.fastq.gz");
+        pair.add_member("s_R1.fastq.gz", true);
+        pair.add_member("s_R2.fastq.gz", true);
+        pair.warnings.push("warning".to_string());
+        assert_eq!(pair.status(), "OK");
+
+        pair.errors.push("Mates do not match".to_string());
+        assert_eq!(pair.status(), "ERROR");
+
+        let mut sample = Entity::new(EntityKind::Sample, "tumor");
+        sample.add_member(pair.id.clone(), pair.is_ok());
+        sample.add_member("t_R1.fastq.gz", true);
+        assert_eq!(sample.status(), "ERROR");
+        assert!(sample.has_failed("s_R1.fastq.gz"));
+        assert_eq!(sample.failed_members, vec!["s_R1.fastq.gz"]);
+        assert_eq!(sample.members.len(), 2);
+    }
+}
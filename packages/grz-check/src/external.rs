@@ -0,0 +1,149 @@
+//! External validators from the `[[external_check]]` tables of the config file, e.g.
+//! site-specific validators, which are run on each checked file after the built-in checks.
+//!
+//! The command may print a JSON object with optional `errors` and `warnings` arrays of messages
+//! to stdout. A non-zero exit code is an error of its own. Messages are added to the report of
+//! the file, prefixed with the namespaced check name `external.<name>`, so that they can be
+//! suppressed and assessed like the findings of the built-in checks.
+
+use crate::archive;
+use crate::hooks::{self, PATH_PLACEHOLDER};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExternalCheck {
+    /// Name of the check, which is namespaced as `external.<name>` in the report.
+    pub name: String,
+    /// Shell command template, e.g. `site-validate --json {path}`.
+    pub command: String,
+    /// Check types (e.g. `fastq`, `bam`) of the files to run the command on; all if empty.
+    pub check_types: Vec<String>,
+}
+
+/// Output of an external check on stdout.
+#[derive(Debug, Default, Deserialize)]
+struct Output {
+    #[serde(default)]
+    errors: Vec<String>,
+    #[serde(default)]
+    warnings: Vec<String>,
+}
+
+/// Errors and warnings of an external check on a file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Findings {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl ExternalCheck {
+    pub fn check_name(&self) -> String {
+        format!("external.{}", self.name)
+    }
+
+    pub fn applies_to(&self, check_type: &str, path: &Path) -> bool {
+        // Members of archives cannot be passed to external commands.
+        archive::split(path).is_none()
+            && (self.check_types.is_empty() || self.check_types.iter().any(|t| t == check_type))
+    }
+
+    /// Runs the command for a checked file and waits for it to finish.
+    pub fn run(&self, path: &Path) -> Findings {
+        let check_name = self.check_name();
+        let command = self.command.replace(
+            PATH_PLACEHOLDER,
+            &hooks::shell_quote(&path.to_string_lossy()),
+        );
+        let output = match Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .stdin(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) => {
+                return Findings {
+                    errors: vec![format!(
+                        "External check {check_name} could not be run (`{command}`): {e}"
+                    )],
+                    warnings: vec![],
+                };
+            }
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let parsed = match stdout.trim() {
+            "" => Ok(Output::default()),
+            stdout => serde_json::from_str::<Output>(stdout),
+        };
+        let prefix = |message: String| format!("[{check_name}] {message}");
+        let mut findings = Findings::default();
+        match parsed {
+            Ok(parsed) => {
+                findings
+                    .errors
+                    .extend(parsed.errors.into_iter().map(prefix));
+                findings
+                    .warnings
+                    .extend(parsed.warnings.into_iter().map(prefix));
+            }
+            Err(e) => findings.errors.push(format!(
+                "External check {check_name} printed invalid JSON output: {e}"
+            )),
+        }
+        if !output.status.success() {
+            findings.errors.push(format!(
+                "External check {check_name} failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn external_check(command: &str) -> ExternalCheck {
+        ExternalCheck {
+            name: "site".to_string(),
+            command: command.to_string(),
+            check_types: vec!["fastq".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_external_check_output() {
+        let path = Path::new("sample.fastq.gz");
+        let check = external_check(
+            r#"echo '{"errors": ["bad header"], "warnings": ["odd name: {path}"]}'"#,
+        );
+        assert!(check.applies_to("fastq", path));
+        assert!(!check.applies_to("bam", path));
+        assert_eq!(
+            check.run(path),
+            Findings {
+                errors: vec!["[external.site] bad header".to_string()],
+                warnings: vec!["[external.site] odd name: sample.fastq.gz".to_string()],
+            }
+        );
+
+        let findings = external_check("echo not json; echo oops >&2; exit 3 # {path}").run(path);
+        assert_eq!(findings.warnings, Vec::<String>::new());
+        assert_eq!(findings.errors.len(), 2);
+        assert!(
+            findings.errors[0]
+                .starts_with("External check external.site printed invalid JSON output: ")
+        );
+        assert_eq!(
+            findings.errors[1],
+            "External check external.site failed (exit status: 3): oops"
+        );
+
+        assert_eq!(external_check("true {path}").run(path), Findings::default());
+    }
+}
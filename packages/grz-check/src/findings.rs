@@ -0,0 +1,1388 @@
+//! Stable, machine-readable codes for the errors and warnings in a report.
+//!
+//! Messages are meant for humans and may be reworded between releases, whereas codes are part
+//! of the report schema and must not change once released.
+
+use serde::Serialize;
+
+/// Code of findings that do not match any known message.
+pub const UNKNOWN_CODE: &str = "other";
+
+/// Known messages, identified by a distinctive fragment, and their codes. The first matching
+/// fragment wins, so more specific fragments must come first.
+const CODES: &[(&str, &str)] = &[
+    // External checks, whose messages may contain any of the fragments below
+    ("[external.", "external.finding"),
+    ("External check external.", "external.check_failed"),
+    // I/O
+    ("File was replaced after it was scanned", "io.replaced"),
+    ("Failed to open file", "io.open"),
+    ("given as an input, but does not exist", "io.missing_file"),
+    ("another file of its job does not exist", "io.missing_mate"),
+    ("Failed to decompress file", "io.decompress"),
+    ("Compressed stream truncated", "io.truncated_stream"),
+    ("BGZF EOF marker", "io.missing_eof"),
+    ("Failed to read file", "io.read"),
+    ("Failed to read line", "io.read"),
+    ("Failed to run staging command", "io.staging"),
+    ("Staging command", "io.staging"),
+    ("Failed to run post-check command", "io.post_check"),
+    ("Failed to quarantine", "io.quarantine"),
+    ("Post-check command", "io.post_check"),
+    ("Failed to finalize checksum", "checksum.finalize"),
+    ("file(s) of other submissions", "checksum.other_submission"),
+    (
+        "Failed to read MD5 sidecar",
+        "checksum.md5_sidecar_unreadable",
+    ),
+    ("does not match the digest", "checksum.md5_mismatch"),
+    (
+        "is not recorded in the checksum registry",
+        "checksum.registry_unknown_file",
+    ),
+    ("Failed to look up '", "checksum.registry_unavailable"),
+    ("which is not a SHA-256 digest", "checksum.registry_invalid"),
+    ("in the checksum registry", "checksum.registry_mismatch"),
+    (
+        "listed in the manifest, but does not exist",
+        "manifest.missing_file",
+    ),
+    ("listed in the manifest", "manifest.checksum_mismatch"),
+    // Structure
+    ("Failed to read BAM header", "header.unreadable"),
+    ("Failed to read SAM header", "header.unreadable"),
+    ("Failed to read VCF header", "header.unreadable"),
+    ("Failed to read BCF header", "header.unreadable"),
+    ("failed to parse; check aborted", "fastq.mate_unparsable"),
+    (
+        "Parsing error during paired fastq check",
+        "fastq.pair_unparsable",
+    ),
+    ("blank line at line", "fastq.blank_line"),
+    ("Failed to parse", "record.unparsable"),
+    ("File is empty", "file.empty"),
+    ("File contains no records", "file.empty"),
+    (
+        "File extension is not accepted",
+        "file.extension_not_accepted",
+    ),
+    ("above the maximum path length", "file.path_too_long"),
+    (
+        "contains characters not allowed by the inbox",
+        "file.path_characters",
+    ),
+    // FASTQ
+    ("Mean read length", "fastq.mean_read_length"),
+    (
+        "deviates from declared read length",
+        "fastq.declared_read_length",
+    ),
+    ("Mismatched read counts", "fastq.pair_read_count_mismatch"),
+    ("Quality scores appear to be", "fastq.quality_encoding"),
+    (
+        "Illegal quality character",
+        "fastq.illegal_quality_character",
+    ),
+    (
+        "record(s) with invalid sequence characters",
+        "fastq.invalid_base",
+    ),
+    ("Trailing whitespace", "fastq.trailing_whitespace"),
+    ("(CRLF) line endings", "fastq.crlf"),
+    ("does not end with a newline", "fastq.missing_newline"),
+    ("blank line(s) after line", "fastq.trailing_blank_lines"),
+    (
+        "with an empty sequence or quality string",
+        "fastq.empty_record",
+    ),
+    ("instrument:run:flowcell combinations", "fastq.mixed_runs"),
+    ("contain adapter sequences", "fastq.adapter_content"),
+    ("end in a poly-G tail", "fastq.poly_g"),
+    ("have a valid UMI", "fastq.umi_missing"),
+    ("Fraction of N bases", "fastq.n_fraction"),
+    (
+        "does not match the expected number of records",
+        "fastq.record_count_mismatch",
+    ),
+    (
+        "deviates from the expected yield",
+        "fastq.base_yield_mismatch",
+    ),
+    ("with duplicate read names", "fastq.duplicate_name"),
+    (
+        "Failed to check read names for duplicates",
+        "fastq.duplicate_name_check_failed",
+    ),
+    ("mismatched mate names", "fastq.mate_name_mismatch"),
+    ("R1 and R2 are byte-identical", "fastq.identical_mates"),
+    ("R1 and R2 contain the same reads", "fastq.identical_mates"),
+    ("not in Casava 1.8 format", "fastq.malformed_read_name"),
+    // Consent
+    ("consent are submitted", "consent.scope_violation"),
+    (
+        "no file of the expected category",
+        "consent.expected_category_missing",
+    ),
+    // Alignments
+    ("Detected a header in", "alignment.header_present"),
+    ("secondary alignment(s)", "alignment.secondary"),
+    ("hard-clipped bases", "alignment.hard_clip"),
+    ("without base quality scores", "alignment.missing_quality"),
+    ("without a sequence (SEQ '*')", "alignment.missing_sequence"),
+    (
+        "beyond the end of their reference sequence",
+        "alignment.out_of_bounds",
+    ),
+    (
+        "CIGAR does not match the length of their sequence",
+        "alignment.cigar_sequence_mismatch",
+    ),
+    (
+        "flagged as PCR or optical duplicates",
+        "alignment.duplicate_fraction",
+    ),
+    (
+        "mapped record(s), which are not allowed in unaligned BAM",
+        "alignment.ubam_mapped",
+    ),
+    (
+        "record(s) with reference coordinates",
+        "alignment.ubam_coordinates",
+    ),
+    ("record(s) with CIGAR operations", "alignment.ubam_cigar"),
+    ("Header declares SO:", "alignment.unsorted"),
+    (
+        "have an M5 checksum different from the sequence in",
+        "reference.md5_mismatch",
+    ),
+    (
+        "have a length different from the sequence in",
+        "reference.length_mismatch",
+    ),
+    (
+        "reference sequence(s) of the header are not in",
+        "reference.sequence_missing",
+    ),
+    (
+        "which is not assigned to this file",
+        "alignment.read_group_foreign_lab_datum",
+    ),
+    (
+        "maps to no declared lab datum",
+        "alignment.read_group_unmapped",
+    ),
+    (
+        "but no read group has it",
+        "alignment.lab_datum_without_read_group",
+    ),
+    (
+        "not declared by an @RG header line",
+        "alignment.read_group_undeclared",
+    ),
+    (
+        "without a read group (RG tag)",
+        "alignment.read_group_missing",
+    ),
+    (
+        "without the reference sequence or position of the mate",
+        "alignment.mate_fields_missing",
+    ),
+    ("but not flagged as paired", "alignment.mate_flags_unpaired"),
+    ("(read 1 and read 2)", "alignment.read1_and_read2"),
+    (
+        "flagged as properly paired",
+        "alignment.proper_pair_unmapped",
+    ),
+    ("malformed auxiliary fields", "alignment.malformed_tags"),
+    ("as large as the reads themselves", "alignment.tag_bloat"),
+    // BAM indexes
+    (
+        "but the BAM header declares",
+        "alignment.index_reference_count",
+    ),
+    (
+        "bin(s) with an ID out of range",
+        "alignment.index_invalid_bin",
+    ),
+    (
+        "bin(s) beyond the length",
+        "alignment.index_bin_beyond_length",
+    ),
+    (
+        "chunk(s) ending before they start",
+        "alignment.index_invalid_chunk",
+    ),
+    (
+        "beyond the end of the file",
+        "alignment.index_offset_beyond_eof",
+    ),
+    (
+        "sampled offset(s) without a matching record",
+        "alignment.index_stale",
+    ),
+    ("Failed to read BAM index", "alignment.index_unreadable"),
+    // Reference plausibility
+    (
+        "chromosome names (e.g. 'chr1' or '1')",
+        "reference.unrecognized_names",
+    ),
+    ("reference: chromosome(s)", "reference.foreign_chromosomes"),
+    (
+        "reference: header contains chromosomes",
+        "reference.species_mismatch",
+    ),
+    ("Length of chromosome 1", "reference.chr1_length"),
+    (
+        "Total length of reference sequences",
+        "reference.genome_length",
+    ),
+    // FASTA
+    ("Empty sequence name", "fasta.empty_name"),
+    (
+        "Sequence data before the first header",
+        "fasta.missing_header",
+    ),
+    (
+        "line(s) with invalid sequence characters",
+        "fasta.invalid_residues",
+    ),
+    ("duplicate sequence name(s)", "fasta.duplicate_name"),
+    ("empty sequence(s)", "fasta.empty_sequence"),
+    ("inconsistent line lengths", "fasta.non_uniform_lines"),
+    ("Failed to read FASTA index", "fasta.index_unreadable"),
+    ("Index is missing", "fasta.index_missing_sequence"),
+    ("not present in the file", "fasta.index_extra_sequence"),
+    ("Index entries of", "fasta.index_mismatch"),
+    // BED
+    ("fewer than 3 columns", "bed.too_few_columns"),
+    ("inconsistent number of columns", "bed.inconsistent_columns"),
+    ("with invalid coordinates", "bed.invalid_coordinates"),
+    (
+        "start is not less than their end",
+        "bed.start_not_before_end",
+    ),
+    ("not present in the reference", "bed.unknown_chromosome"),
+    ("Failed to read reference", "bed.reference_unreadable"),
+    ("not sorted by chromosome", "bed.unsorted"),
+    // Nanopore raw signal
+    ("File is too short to be a POD5 file", "pod5.truncated"),
+    ("POD5 signature is missing", "pod5.signature"),
+    ("POD5 section markers", "pod5.signature"),
+    ("POD5 footer", "pod5.footer"),
+    ("POD5 file has no", "pod5.missing_table"),
+    ("POD5 signal table is empty", "pod5.empty_signal"),
+    ("rows of the POD5", "pod5.record_batches"),
+    ("POD5 table", "pod5.table"),
+    ("not an HDF5 file", "fast5.signature"),
+    ("HDF5 end-of-file address", "fast5.truncated"),
+    ("HDF5 superblock", "fast5.superblock"),
+    // GZI indexes
+    ("to be a GZI index", "gzi.invalid"),
+    ("GZI index declares", "gzi.invalid"),
+    ("Failed to read GZI index", "gzi.unreadable"),
+    ("not in increasing order of offsets", "gzi.unsorted"),
+    ("beyond the end of the data file", "gzi.out_of_bounds"),
+    ("not the start of a BGZF block", "gzi.misaligned_offset"),
+    (
+        "do not match the data preceding their block",
+        "gzi.uncompressed_offset",
+    ),
+    ("Failed to walk BGZF blocks", "gzi.data_unreadable"),
+    // Tabix/CSI indexes
+    ("is not a tabix or CSI index", "tabix.invalid_magic"),
+    ("Failed to read tabix index", "tabix.unreadable"),
+    ("Failed to read CSI index", "tabix.unreadable"),
+    ("Failed to read index", "tabix.unreadable"),
+    ("Failed to query index for", "tabix.unreadable"),
+    ("not present in the data file", "tabix.extra_sequence"),
+    ("missing from the index", "tabix.missing_sequence"),
+    (
+        "sampled offset(s) that do not resolve to a record",
+        "tabix.unresolved_offset",
+    ),
+    ("Failed to resolve offsets", "tabix.data_unreadable"),
+    ("Failed to read data file", "tabix.data_unreadable"),
+    // gVCF
+    (
+        "reference blocks without an END tag",
+        "vcf.gvcf_missing_end",
+    ),
+    ("with an END before their position", "vcf.gvcf_invalid_end"),
+    ("overlapping the preceding block", "vcf.gvcf_overlap"),
+    (
+        "contains no reference blocks",
+        "vcf.gvcf_no_reference_blocks",
+    ),
+    // Lab data
+    (
+        "Total number of reads across",
+        "lab_datum.read_count_mismatch",
+    ),
+    ("Combined yield across", "lab_datum.yield_mismatch"),
+    ("Totals were not computed", "lab_datum.incomplete"),
+    // Run
+    ("Files with identical content", "run.duplicate_content"),
+    // VCF
+    ("declares no contigs", "vcf.no_contigs"),
+    ("contigs not declared", "vcf.undeclared_contig"),
+    ("INFO fields not declared", "vcf.undeclared_info"),
+    ("FORMAT fields not declared", "vcf.undeclared_format"),
+    (
+        "number of samples does not match",
+        "vcf.sample_count_mismatch",
+    ),
+];
+
+/// Returns the code of a finding message.
+pub fn code_for(message: &str) -> &'static str {
+    CODES
+        .iter()
+        .find(|(fragment, _)| message.contains(fragment))
+        .map_or(UNKNOWN_CODE, |&(_, code)| code)
+}
+
+/// Remediation hints for common findings, which are forwarded to the submitting lab.
+const HINTS: &[(&str, &str)] = &[
+    (
+        "io.replaced",
+        "Wait until the upload of the file has finished and check it again.",
+    ),
+    (
+        "io.missing_file",
+        "Check the path of the file, or transfer the file if it is still missing.",
+    ),
+    (
+        "io.decompress",
+        "The file may be truncated or corrupt; transfer it again or recompress it from the original.",
+    ),
+    (
+        "io.truncated_stream",
+        "Compare the file size with the source and transfer the file again.",
+    ),
+    (
+        "io.missing_eof",
+        "Compare the file size with the source and transfer the file again; `samtools quickcheck` reports the same defect.",
+    ),
+    (
+        "checksum.md5_mismatch",
+        "The file may have been corrupted in transfer; transfer it again.",
+    ),
+    (
+        "manifest.checksum_mismatch",
+        "The file may have been corrupted in transfer; transfer it again.",
+    ),
+    (
+        "checksum.registry_mismatch",
+        "The file may have been corrupted in transfer or replaced after it was registered; transfer it again or correct the registry.",
+    ),
+    (
+        "checksum.registry_unknown_file",
+        "Register the file in the checksum registry, or check that the `root` of [checksum_registry] matches the layout of the registry.",
+    ),
+    (
+        "checksum.registry_unavailable",
+        "Check that the registry command or database of [checksum_registry] is reachable and run the check again.",
+    ),
+    (
+        "fastq.quality_encoding",
+        "Convert the quality scores to Phred+33, e.g. with `seqtk seq -V -Q64`.",
+    ),
+    (
+        "fastq.illegal_quality_character",
+        "The file may be corrupt; recompress it from the original and transfer it again.",
+    ),
+    (
+        "fastq.n_fraction",
+        "Check the run for failed cycles and trim or exclude the affected reads.",
+    ),
+    (
+        "fastq.record_count_mismatch",
+        "The file may have been truncated in transfer; transfer it again or correct the metadata.",
+    ),
+    (
+        "fastq.base_yield_mismatch",
+        "The file may have been truncated in transfer; transfer it again or correct the yield in the lab manifest.",
+    ),
+    (
+        "fastq.identical_mates",
+        "Upload the R2 file of the pair; the R1 file was uploaded in its place.",
+    ),
+    (
+        "fastq.blank_line",
+        "Remove blank lines from the file, e.g. with `sed '/^$/d'`, and check that no record lost a line.",
+    ),
+    (
+        "fastq.trailing_whitespace",
+        "Remove trailing whitespace, e.g. with `sed 's/[ \\t]*$//'`; the file was likely edited by hand.",
+    ),
+    (
+        "fastq.crlf",
+        "Convert the file to Unix line endings, e.g. with `dos2unix`.",
+    ),
+    (
+        "fastq.missing_newline",
+        "Append a newline, e.g. with `echo >> FILE`, after checking that the last record is complete.",
+    ),
+    (
+        "fastq.trailing_blank_lines",
+        "Remove the blank lines at the end of the file, e.g. with `sed '/^$/d'`.",
+    ),
+    (
+        "fastq.poly_g",
+        "Trim poly-G tails before submitting, e.g. with `fastp --trim_poly_g`.",
+    ),
+    (
+        "fastq.adapter_content",
+        "Trim adapters before submitting, e.g. with `cutadapt` or `fastp`.",
+    ),
+    (
+        "fastq.empty_record",
+        "Remove empty reads after trimming, e.g. with `cutadapt --minimum-length 1`.",
+    ),
+    (
+        "fastq.malformed_read_name",
+        "Export the reads with their original headers, e.g. with `bcl2fastq` or `bcl-convert`.",
+    ),
+    (
+        "fastq.umi_missing",
+        "Keep the UMIs when demultiplexing, e.g. with the `TrimUMI` and `CreateFastqForIndexReads` settings of `bcl-convert`.",
+    ),
+    (
+        "fastq.mixed_runs",
+        "Submit the reads of each run in a separate file instead of concatenating them.",
+    ),
+    (
+        "fastq.duplicate_name",
+        "The file may contain the reads of a lane twice; re-export it from the original data.",
+    ),
+    (
+        "file.path_too_long",
+        "Shorten the directory and file names of the submission.",
+    ),
+    (
+        "file.path_characters",
+        "Rename the file, e.g. replace spaces with underscores and umlauts with ASCII letters.",
+    ),
+    (
+        "consent.scope_violation",
+        "Remove the files from the submission or correct the consent scope in the metadata.",
+    ),
+    (
+        "file.extension_not_accepted",
+        "Convert the file to an accepted format, e.g. compress FASTQ files with `gzip -n`.",
+    ),
+    (
+        "alignment.missing_quality",
+        "Regenerate the file from the original reads without dropping the base qualities.",
+    ),
+    (
+        "alignment.missing_sequence",
+        "Regenerate the file from the original reads without dropping the sequences of primary records.",
+    ),
+    (
+        "alignment.cigar_sequence_mismatch",
+        "Regenerate the file from the original reads; records whose CIGAR and sequence disagree cannot be interpreted.",
+    ),
+    (
+        "alignment.out_of_bounds",
+        "Realign the reads against the reference declared in the header; the file was likely corrupted or its @SQ lines replaced by those of another reference.",
+    ),
+    (
+        "alignment.duplicate_fraction",
+        "Check the library preparation; if duplicates are expected, raise --max-duplicate-fraction.",
+    ),
+    (
+        "alignment.mate_fields_missing",
+        "Regenerate the file from the original reads; it was likely modified by a tool that does not keep the flags of mates consistent. `samtools fixmate` corrects the mate fields of name-sorted files.",
+    ),
+    (
+        "alignment.mate_flags_unpaired",
+        "Regenerate the file from the original reads; it was likely modified by a tool that does not keep the flags of mates consistent. `samtools fixmate` corrects the mate fields of name-sorted files.",
+    ),
+    (
+        "alignment.read1_and_read2",
+        "Regenerate the file from the original reads; it was likely modified by a tool that does not keep the flags of mates consistent. `samtools fixmate` corrects the mate fields of name-sorted files.",
+    ),
+    (
+        "alignment.proper_pair_unmapped",
+        "Regenerate the file from the original reads; it was likely modified by a tool that does not keep the flags of mates consistent. `samtools fixmate` corrects the mate fields of name-sorted files.",
+    ),
+    (
+        "alignment.read_group_foreign_lab_datum",
+        "Check that the BAM file was not merged from the wrong sample, or correct the lab data declared in the metadata.",
+    ),
+    (
+        "alignment.read_group_unmapped",
+        "Set the SM or LB of the read group to the name of its lab datum, e.g. with `samtools addreplacerg`, or declare the lab datum in the metadata.",
+    ),
+    (
+        "alignment.lab_datum_without_read_group",
+        "Check that the BAM file contains the reads of all lab data declared for it.",
+    ),
+    (
+        "alignment.read_group_undeclared",
+        "Add the missing @RG lines to the header, e.g. with `samtools addreplacerg` or by merging with `samtools merge` instead of concatenating the records.",
+    ),
+    (
+        "alignment.read_group_missing",
+        "Assign a read group to every record, e.g. with `samtools addreplacerg -m orphan_only`.",
+    ),
+    (
+        "alignment.tag_bloat",
+        "Remove the tags before submitting, e.g. with `samtools view -b --remove-tag OQ,BI,BD`.",
+    ),
+    (
+        "reference.md5_mismatch",
+        "Realign the reads to the reference declared in the metadata, or correct the declared reference.",
+    ),
+    (
+        "reference.length_mismatch",
+        "Realign the reads to the reference declared in the metadata, or correct the declared reference.",
+    ),
+    (
+        "reference.sequence_missing",
+        "Realign the reads to the reference declared in the metadata, or correct the declared reference.",
+    ),
+    (
+        "alignment.unsorted",
+        "Sort the file with `samtools sort` (by coordinate) or `samtools sort -n` (by read name), or correct the SO tag of the @HD header line.",
+    ),
+    (
+        "alignment.index_reference_count",
+        "Regenerate the index with `samtools index`.",
+    ),
+    (
+        "alignment.index_invalid_bin",
+        "Regenerate the index with `samtools index`.",
+    ),
+    (
+        "alignment.index_bin_beyond_length",
+        "Regenerate the index with `samtools index`.",
+    ),
+    (
+        "alignment.index_invalid_chunk",
+        "Regenerate the index with `samtools index`.",
+    ),
+    (
+        "alignment.index_offset_beyond_eof",
+        "Regenerate the index with `samtools index`.",
+    ),
+    (
+        "alignment.index_stale",
+        "Regenerate the index with `samtools index` from the submitted BAM file.",
+    ),
+    (
+        "alignment.index_unreadable",
+        "Regenerate the index with `samtools index`.",
+    ),
+    (
+        "fasta.index_missing_sequence",
+        "Regenerate the index with `samtools faidx`.",
+    ),
+    (
+        "fasta.index_extra_sequence",
+        "Regenerate the index with `samtools faidx`.",
+    ),
+    (
+        "fasta.index_mismatch",
+        "Regenerate the index with `samtools faidx`.",
+    ),
+    (
+        "fasta.index_unreadable",
+        "Regenerate the index with `samtools faidx`.",
+    ),
+    (
+        "pod5.truncated",
+        "The file may be truncated; transfer it again.",
+    ),
+    (
+        "pod5.signature",
+        "The file may be truncated; transfer it again.",
+    ),
+    (
+        "fast5.truncated",
+        "The file may be truncated; transfer it again.",
+    ),
+    ("bed.unsorted", "Sort the file with `sort -k1,1 -k2,2n`."),
+    ("gzi.invalid", "Regenerate the index with `bgzip -r`."),
+    ("gzi.unreadable", "Regenerate the index with `bgzip -r`."),
+    ("gzi.unsorted", "Regenerate the index with `bgzip -r`."),
+    ("gzi.out_of_bounds", "Regenerate the index with `bgzip -r`."),
+    (
+        "gzi.misaligned_offset",
+        "Regenerate the index with `bgzip -r`.",
+    ),
+    (
+        "gzi.uncompressed_offset",
+        "Regenerate the index with `bgzip -r`.",
+    ),
+    (
+        "gzi.data_unreadable",
+        "Re-compress the data file with `bgzip`, then regenerate the index with `bgzip -r`.",
+    ),
+    ("tabix.invalid_magic", "Regenerate the index with `tabix`."),
+    ("tabix.unreadable", "Regenerate the index with `tabix`."),
+    ("tabix.extra_sequence", "Regenerate the index with `tabix`."),
+    (
+        "tabix.missing_sequence",
+        "Regenerate the index with `tabix`.",
+    ),
+    (
+        "tabix.unresolved_offset",
+        "Regenerate the index with `tabix`.",
+    ),
+    (
+        "tabix.data_unreadable",
+        "Re-gzip the data file with `bgzip`, then regenerate the index with `tabix`.",
+    ),
+    (
+        "vcf.no_contigs",
+        "Add ##contig lines to the header, e.g. with `bcftools reheader --fai`.",
+    ),
+    (
+        "vcf.undeclared_contig",
+        "Add ##contig lines to the header, e.g. with `bcftools reheader --fai`.",
+    ),
+];
+
+/// Returns the remediation hint for a finding code, if there is one.
+pub fn hint_for(code: &str) -> Option<&'static str> {
+    HINTS
+        .iter()
+        .find(|(hint_code, _)| *hint_code == code)
+        .map(|&(_, hint)| hint)
+}
+
+/// Codes of findings about thresholds rather than the integrity of the data, which are reported
+/// as warnings in `--assess` mode.
+const THRESHOLD_CODES: &[&str] = &[
+    "fastq.mean_read_length",
+    "fastq.declared_read_length",
+    "fastq.n_fraction",
+    "fastq.record_count_mismatch",
+    "fastq.base_yield_mismatch",
+    "lab_datum.read_count_mismatch",
+    "lab_datum.yield_mismatch",
+];
+
+/// Code of findings about legacy FASTQ quality encodings, which are reported as warnings if
+/// `--quality-encoding-severity warning` is given.
+pub const QUALITY_ENCODING_CODE: &str = "fastq.quality_encoding";
+
+/// Code of findings about FASTQ records with an empty sequence or quality string, which are
+/// reported as warnings if `--empty-record-severity warning` is given.
+pub const EMPTY_RECORD_CODE: &str = "fastq.empty_record";
+
+/// Code of findings about primary alignment records without base qualities, which are reported
+/// as warnings if `--missing-quality-severity warning` is given.
+pub const MISSING_QUALITY_CODE: &str = "alignment.missing_quality";
+
+/// Moves errors with one of `codes` to the warnings.
+pub fn demote(codes: &[&str], errors: &mut Vec<String>, warnings: &mut Vec<String>) {
+    let (demoted, others): (Vec<String>, Vec<String>) = errors
+        .drain(..)
+        .partition(|message| codes.contains(&code_for(message)));
+    *errors = others;
+    warnings.extend(demoted);
+}
+
+/// Moves errors about threshold violations to the warnings.
+pub fn demote_threshold_violations(errors: &mut Vec<String>, warnings: &mut Vec<String>) {
+    demote(THRESHOLD_CODES, errors, warnings);
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding<'a> {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<&'static str>,
+}
+
+/// Builds the coded findings for the errors and warnings of a report entry.
+pub fn collect<'a>(errors: &'a [String], warnings: &'a [String]) -> Vec<Finding<'a>> {
+    let finding = |severity| {
+        move |message: &'a String| {
+            let code = code_for(message);
+            Finding {
+                code,
+                severity,
+                message,
+                hint: hint_for(code),
+            }
+        }
+    };
+    errors
+        .iter()
+        .map(finding(Severity::Error))
+        .chain(warnings.iter().map(finding(Severity::Warning)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One message of every format emitted by the checks, as rendered, with its intended code.
+    const MESSAGES: &[(&str, &str)] = &[
+        ("[external.site] bad header", "external.finding"),
+        (
+            "External check external.site failed (exit status: 3): oops",
+            "external.check_failed",
+        ),
+        (
+            "External check external.site printed invalid JSON output: expected value",
+            "external.check_failed",
+        ),
+        (
+            "External check external.site could not be run (`site-check a.bam`): No such file or directory (os error 2)",
+            "external.check_failed",
+        ),
+        (
+            "File was replaced after it was scanned (device 1, inode 2 when scanned; device 1, inode 3 when opened). It was likely overwritten by an upload that was still in progress.",
+            "io.replaced",
+        ),
+        ("Failed to open file for reading: a.fastq.gz", "io.open"),
+        (
+            "File is given as an input, but does not exist.",
+            "io.missing_file",
+        ),
+        (
+            "File was not checked, because another file of its job does not exist.",
+            "io.missing_mate",
+        ),
+        ("Failed to decompress file: a.fastq.gz", "io.decompress"),
+        (
+            "Compressed stream truncated at byte 1024 (file likely incomplete). The file ends inside a compressed block; transfer it again.",
+            "io.truncated_stream",
+        ),
+        (
+            "File does not end with the BGZF EOF marker block (file likely incomplete). The transfer was probably interrupted at a block boundary; transfer it again.",
+            "io.missing_eof",
+        ),
+        ("Failed to read file: unexpected end of file", "io.read"),
+        (
+            "Failed to read line 3: stream did not contain valid UTF-8",
+            "io.read",
+        ),
+        (
+            "Failed to run staging command `dmget 'a.bam'`: No such file or directory (os error 2)",
+            "io.staging",
+        ),
+        (
+            "Staging command `dmget 'a.bam'` failed (exit status: 1): offline",
+            "io.staging",
+        ),
+        (
+            "Failed to run post-check command `mv 'a.bam' done/`: No such file or directory (os error 2)",
+            "io.post_check",
+        ),
+        (
+            "Post-check command `mv 'a.bam' done/` failed (exit status: 1): denied",
+            "io.post_check",
+        ),
+        (
+            "Failed to quarantine a.bam: Permission denied (os error 13)",
+            "io.quarantine",
+        ),
+        (
+            "Failed to finalize checksum: hasher is still in use.",
+            "checksum.finalize",
+        ),
+        (
+            "File is identical to 1 file(s) of other submissions. First recorded: 'a.fastq.gz' of submission 'submission-1'.",
+            "checksum.other_submission",
+        ),
+        (
+            "Failed to read MD5 sidecar a.bam.md5: invalid digest",
+            "checksum.md5_sidecar_unreadable",
+        ),
+        (
+            "MD5 digest 0123456789abcdef0123456789abcdef does not match the digest fedcba9876543210fedcba9876543210 given in a.bam.md5.",
+            "checksum.md5_mismatch",
+        ),
+        (
+            "File 'donor1/c.fastq.gz' is not recorded in the checksum registry, so its digest was not verified.",
+            "checksum.registry_unknown_file",
+        ),
+        (
+            "Failed to look up 'a.bam' in the checksum registry: Command `lookup a.bam` failed (exit status: 1): timeout",
+            "checksum.registry_unavailable",
+        ),
+        (
+            "Checksum registry returned 'unknown' for 'donor1/b.fastq.gz', which is not a SHA-256 digest.",
+            "checksum.registry_invalid",
+        ),
+        (
+            "SHA-256 digest 0123 differs from the digest 4567 recorded for 'donor1/a.fastq.gz' in the checksum registry.",
+            "checksum.registry_mismatch",
+        ),
+        (
+            "File is listed in the manifest, but does not exist.",
+            "manifest.missing_file",
+        ),
+        (
+            "SHA-256 digest 0123 differs from the digest 4567 listed in the manifest.",
+            "manifest.checksum_mismatch",
+        ),
+        (
+            "Failed to read BAM header: invalid magic number",
+            "header.unreadable",
+        ),
+        (
+            "Failed to read SAM header: invalid record",
+            "header.unreadable",
+        ),
+        (
+            "Failed to read VCF header: missing fileformat",
+            "header.unreadable",
+        ),
+        (
+            "Failed to read BCF header: invalid magic number",
+            "header.unreadable",
+        ),
+        (
+            "R1 (\"a_R1.fastq.gz\") failed to parse; check aborted.",
+            "fastq.mate_unparsable",
+        ),
+        (
+            "Parsing error during paired fastq check.",
+            "fastq.pair_unparsable",
+        ),
+        (
+            "Failed to parse record record #2: blank line at line 5, where the record should start. FASTQ records consist of exactly four lines, without blank lines between or after them.",
+            "fastq.blank_line",
+        ),
+        (
+            "Failed to parse record #3: invalid flags",
+            "record.unparsable",
+        ),
+        ("File is empty. Expected at least one record.", "file.empty"),
+        ("File contains no records.", "file.empty"),
+        (
+            "File extension is not accepted for GRZ submissions. Expected one of: .bam, .vcf.gz.",
+            "file.extension_not_accepted",
+        ),
+        (
+            "Path 'files/Müller R1.fastq.gz' is 25 bytes long, above the maximum path length of 20 bytes of the inbox.",
+            "file.path_too_long",
+        ),
+        (
+            "Path 'files/Müller R1.fastq.gz' contains characters not allowed by the inbox: 'ü', ' '. Allowed characters: A-Za-z0-9._/-.",
+            "file.path_characters",
+        ),
+        (
+            "Mean read length (4) is not greater than minimum required (10)",
+            "fastq.mean_read_length",
+        ),
+        (
+            "Modal read length (5) deviates from declared read length (4) by more than the tolerance of 0 base(s)",
+            "fastq.declared_read_length",
+        ),
+        (
+            "Mismatched read counts: I1 has 3 records, but R1 has 4.",
+            "fastq.pair_read_count_mismatch",
+        ),
+        (
+            "Quality scores appear to be Phred+64 encoded (quality characters range from '@' to 'h'), first detected at record #2 ('SEQ2'). Expected Phred+33.",
+            "fastq.quality_encoding",
+        ),
+        (
+            "Illegal quality character 0x07 at position 3 of record #2 ('SEQ2'). Quality characters must be printable ASCII from '!' to '~' (Phred+33).",
+            "fastq.illegal_quality_character",
+        ),
+        (
+            "File contains 2 record(s) with invalid sequence characters. First detected at position 3 of record #2 ('SEQ2'): 'g'. Sequences must only contain upper-case IUPAC codes.",
+            "fastq.invalid_base",
+        ),
+        (
+            "Trailing whitespace (space) at the end of line 6, the sequence line of record #2. Sequence and quality lines must not contain whitespace.",
+            "fastq.trailing_whitespace",
+        ),
+        (
+            "File has Windows (CRLF) line endings, first at line 1 (record #1). Many tools expect Unix (LF) line endings.",
+            "fastq.crlf",
+        ),
+        (
+            "Last line (line 4) does not end with a newline, which FASTQ files require. The file may have been cut off or concatenated without line breaks.",
+            "fastq.missing_newline",
+        ),
+        (
+            "File ends with 2 blank line(s) after line 4, which were ignored.",
+            "fastq.trailing_blank_lines",
+        ),
+        (
+            "File contains 2 record(s) with an empty sequence or quality string. First detected at record #2 ('SEQ2').",
+            "fastq.empty_record",
+        ),
+        (
+            "Reads come from 2 different instrument:run:flowcell combinations (A00123:8:HXXXXDSXX, A00123:9:HYYYYDSXX), so the file may be a concatenation of several runs.",
+            "fastq.mixed_runs",
+        ),
+        (
+            "50.00% of reads (2 of 4) contain adapter sequences, above the maximum of 25.00%. Most frequent adapter: Illumina TruSeq (2 reads).",
+            "fastq.adapter_content",
+        ),
+        (
+            "50.00% of reads (2 of 4) end in a poly-G tail of at least 5 bases, above the maximum of 25.00%.",
+            "fastq.poly_g",
+        ),
+        (
+            "Only 50.00% of reads (2 of 4) have a valid UMI in the read name matching '[ACGTN]{8}', below the minimum of 75.00%. First detected at record #3 ('r3').",
+            "fastq.umi_missing",
+        ),
+        (
+            "Fraction of N bases (0.2000, 4 of 20 bases) exceeds the maximum of 0.1; failed sequencing cycles may have produced all-N reads or tails.",
+            "fastq.n_fraction",
+        ),
+        (
+            "Number of records (2) does not match the expected number of records (3); the file may be truncated or incomplete.",
+            "fastq.record_count_mismatch",
+        ),
+        (
+            "Total number of bases of R1 and R2 (14) deviates from the expected yield (20) by 30.00%, more than the tolerance of 10.00%; the file may be truncated or incomplete.",
+            "fastq.base_yield_mismatch",
+        ),
+        (
+            "File contains 2 record(s) with duplicate read names. First detected at record #4.",
+            "fastq.duplicate_name",
+        ),
+        (
+            "Failed to check read names for duplicates: No space left on device (os error 28)",
+            "fastq.duplicate_name_check_failed",
+        ),
+        (
+            "R1 and R2 have mismatched mate names at record #2 ('SEQ2/1' vs 'SEQ3/2'); the files may be shuffled or not belong together.",
+            "fastq.mate_name_mismatch",
+        ),
+        (
+            "File contains 1 pair(s) with mismatched mate names. First detected at records #1 and #2 ('SEQ1' vs 'SEQ2').",
+            "fastq.mate_name_mismatch",
+        ),
+        (
+            "R1 and R2 are byte-identical (SHA-256 0123). The same file was submitted as both mates.",
+            "fastq.identical_mates",
+        ),
+        (
+            "R1 and R2 contain the same reads: all 2 pair(s) have identical sequences and quality scores.",
+            "fastq.identical_mates",
+        ),
+        (
+            "File contains 2 record(s) with read names not in Casava 1.8 format. First detected at record #2 ('r2'): expected 7 colon-separated fields, found 1.",
+            "fastq.malformed_read_name",
+        ),
+        (
+            "Consent scope of donor index is care, but 1 file(s) of category 'research VCFs' requiring research consent are submitted: files/index/research/calls.vcf.gz",
+            "consent.scope_violation",
+        ),
+        (
+            "Consent scope of donor mother is research, but no file of the expected category 'research VCFs' is submitted",
+            "consent.expected_category_missing",
+        ),
+        (
+            "Detected a header in BAM file, ensure it contains no private information!",
+            "alignment.header_present",
+        ),
+        (
+            "File contains 2 secondary alignment(s). First detected at record #2 ('rec2_secondary').",
+            "alignment.secondary",
+        ),
+        (
+            "File contains 2 primary alignment(s) with hard-clipped bases. First detected at record #1 ('rec1_hardclip').",
+            "alignment.hard_clip",
+        ),
+        (
+            "File contains 2 primary record(s) without base quality scores (QUAL '*'). First detected at record #2 ('r2').",
+            "alignment.missing_quality",
+        ),
+        (
+            "File contains 2 primary record(s) without a sequence (SEQ '*'). First detected at record #2 ('r2').",
+            "alignment.missing_sequence",
+        ),
+        (
+            "File contains 2 mapped record(s) extending beyond the end of their reference sequence. First detected at record #2 ('deleted'), aligned to chr1:95-102.",
+            "alignment.out_of_bounds",
+        ),
+        (
+            "File contains 2 record(s) whose CIGAR does not match the length of their sequence. First detected at record #3 ('long').",
+            "alignment.cigar_sequence_mismatch",
+        ),
+        (
+            "50.00% of records (2 of 4) are flagged as PCR or optical duplicates, above the maximum of 30.00%.",
+            "alignment.duplicate_fraction",
+        ),
+        (
+            "File contains 1 mapped record(s), which are not allowed in unaligned BAM. First detected at record #2 ('r1_mapped').",
+            "alignment.ubam_mapped",
+        ),
+        (
+            "File contains 2 record(s) with reference coordinates, which are not allowed in unaligned BAM. First detected at record #2 ('r1_mapped').",
+            "alignment.ubam_coordinates",
+        ),
+        (
+            "File contains 1 record(s) with CIGAR operations, which are not allowed in unaligned BAM. First detected at record #2 ('r1_mapped').",
+            "alignment.ubam_cigar",
+        ),
+        (
+            "Header declares SO:coordinate, but record #2 ('r2') at chr1:100 comes after a record at chr2:5. The file is not sorted by coordinate, so it cannot be indexed.",
+            "alignment.unsorted",
+        ),
+        (
+            "1 reference sequence(s) of the header have an M5 checksum different from the sequence in ref.fa: 'chr1'. The file was likely aligned to another build of the reference.",
+            "reference.md5_mismatch",
+        ),
+        (
+            "1 reference sequence(s) of the header have a length different from the sequence in ref.fa: 'chr2' (LN 5, reference 4). The file was likely aligned to another build of the reference.",
+            "reference.length_mismatch",
+        ),
+        (
+            "1 reference sequence(s) of the header are not in ref.fa: 'chrM'. The file was likely aligned to another reference.",
+            "reference.sequence_missing",
+        ),
+        (
+            "Read group 'rg2' (SM 'normal', LB 'lib2') belongs to lab datum 'normal', which is not assigned to this file. The file may have been merged with the reads of another sample.",
+            "alignment.read_group_foreign_lab_datum",
+        ),
+        (
+            "Read group 'rg3' (SM 'unknown', LB 'lib3') maps to no declared lab datum; its ID, SM or LB must be one of: tumor, germline.",
+            "alignment.read_group_unmapped",
+        ),
+        (
+            "Lab datum 'germline' is assigned to this file, but no read group has it as ID, SM or LB.",
+            "alignment.lab_datum_without_read_group",
+        ),
+        (
+            "File contains 3 record(s) with a read group that is not declared by an @RG header line: 'rg2' (2 record(s)). First detected at record #2 ('r2').",
+            "alignment.read_group_undeclared",
+        ),
+        (
+            "File contains 1 record(s) without a read group (RG tag), although the file uses read groups. First detected at record #3 ('r3').",
+            "alignment.read_group_missing",
+        ),
+        (
+            "File contains 1 record(s) flagged as paired with a mapped mate, but without the reference sequence or position of the mate. First detected at record(s) #3 ('nomate').",
+            "alignment.mate_fields_missing",
+        ),
+        (
+            "File contains 1 record(s) with flags about their mate (mate unmapped or mate reverse strand), but not flagged as paired. First detected at record(s) #4 ('unpaired').",
+            "alignment.mate_flags_unpaired",
+        ),
+        (
+            "File contains 1 record(s) flagged as both the first and the last segment of their template (read 1 and read 2). First detected at record(s) #5 ('both').",
+            "alignment.read1_and_read2",
+        ),
+        (
+            "File contains 2 record(s) flagged as properly paired, although they are unpaired or they or their mate are unmapped. First detected at record(s) #6 ('proper1').",
+            "alignment.proper_pair_unmapped",
+        ),
+        (
+            "File contains 2 record(s) with malformed auxiliary fields (tags). First detected at record #2 ('r2'): hex string is not an even number of hexadecimal digits.",
+            "alignment.malformed_tags",
+        ),
+        (
+            "Auxiliary tag(s) OQ (12 bytes per record) are as large as the reads themselves.",
+            "alignment.tag_bloat",
+        ),
+        (
+            "Index contains 3 reference sequence(s), but the BAM header declares 2.",
+            "alignment.index_reference_count",
+        ),
+        (
+            "Index contains 1 bin(s) with an ID out of range. First detected: bin 40000 of 'chr1'.",
+            "alignment.index_invalid_bin",
+        ),
+        (
+            "Index contains 1 bin(s) beyond the length of their reference sequence in the BAM header. First detected: bin 4681 of 'chr1'.",
+            "alignment.index_bin_beyond_length",
+        ),
+        (
+            "Index contains 1 chunk(s) ending before they start. First detected in bin 0 of 'chr1'.",
+            "alignment.index_invalid_chunk",
+        ),
+        (
+            "Index refers to offset 123456 beyond the end of the file (1000 bytes); it may belong to a different or truncated BAM file.",
+            "alignment.index_offset_beyond_eof",
+        ),
+        (
+            "Index points to 1 of 1 sampled offset(s) without a matching record; it was likely generated for a different version of the file. First detected: offset 0/12 of 'chr1'.",
+            "alignment.index_stale",
+        ),
+        (
+            "Failed to read BAM index a.bam.bai: invalid magic number",
+            "alignment.index_unreadable",
+        ),
+        (
+            "None of the 2 reference sequence(s) in the header use human chromosome names (e.g. 'chr1' or '1'); skipping reference plausibility check.",
+            "reference.unrecognized_names",
+        ),
+        (
+            "Reference sequences do not look like a human reference: chromosome(s) 23, 24 do not exist in human.",
+            "reference.foreign_chromosomes",
+        ),
+        (
+            "Reference sequences do not look like a human reference: header contains chromosomes 1-19 but not 20-22, matching a mouse reference.",
+            "reference.species_mismatch",
+        ),
+        (
+            "Length of chromosome 1 (195471971) does not match any known human assembly (GRCh37: 249250621, GRCh38: 248956422). It matches mouse (GRCm39).",
+            "reference.chr1_length",
+        ),
+        (
+            "Total length of reference sequences (1000) is outside the plausible range for a human genome (2900000000-3300000000).",
+            "reference.genome_length",
+        ),
+        ("Empty sequence name at line 3", "fasta.empty_name"),
+        (
+            "Sequence data before the first header at line 1",
+            "fasta.missing_header",
+        ),
+        (
+            "File contains 2 line(s) with invalid sequence characters. First detected at line 2 of sequence #1 ('chr1').",
+            "fasta.invalid_residues",
+        ),
+        (
+            "File contains 1 duplicate sequence name(s). First detected at sequence #2 ('chr1').",
+            "fasta.duplicate_name",
+        ),
+        (
+            "File contains 1 empty sequence(s). First detected at sequence #2 ('chr2').",
+            "fasta.empty_sequence",
+        ),
+        (
+            "File contains 1 sequence(s) with inconsistent line lengths, which cannot be indexed. First detected at sequence #1 ('chr1').",
+            "fasta.non_uniform_lines",
+        ),
+        (
+            "Failed to read FASTA index ref.fa.fai: invalid length",
+            "fasta.index_unreadable",
+        ),
+        (
+            "Index is missing 1 sequence(s) of the file. First missing: 'chr2'.",
+            "fasta.index_missing_sequence",
+        ),
+        (
+            "Index contains 1 sequence(s) not present in the file. First detected: 'chrM'.",
+            "fasta.index_extra_sequence",
+        ),
+        (
+            "Index entries of 1 sequence(s) do not match the file. First detected at 'chr1' (length 10 in index, 12 in file).",
+            "fasta.index_mismatch",
+        ),
+        (
+            "File contains 1 record(s) with fewer than 3 columns. First detected at line 5.",
+            "bed.too_few_columns",
+        ),
+        (
+            "File contains 1 record(s) with an inconsistent number of columns. First detected at line 6 (4 instead of 3).",
+            "bed.inconsistent_columns",
+        ),
+        (
+            "File contains 1 record(s) with invalid coordinates. First detected at line 4 ('-1', '4').",
+            "bed.invalid_coordinates",
+        ),
+        (
+            "File contains 1 record(s) whose start is not less than their end. First detected at line 2 (5-5).",
+            "bed.start_not_before_end",
+        ),
+        (
+            "File contains 1 record(s) on chromosomes not present in the reference. First detected at line 3 ('chr2').",
+            "bed.unknown_chromosome",
+        ),
+        (
+            "Failed to read reference ref.fa.fai: No such file or directory (os error 2)",
+            "bed.reference_unreadable",
+        ),
+        (
+            "File is not sorted by chromosome and start position (2 record(s) out of order). First detected at line 2.",
+            "bed.unsorted",
+        ),
+        (
+            "File is too short to be a POD5 file (12 bytes).",
+            "pod5.truncated",
+        ),
+        (
+            "POD5 signature is missing at the end of the file; the file may be truncated.",
+            "pod5.signature",
+        ),
+        (
+            "POD5 section markers at the start and the end of the file differ.",
+            "pod5.signature",
+        ),
+        (
+            "POD5 footer not found before the footer length.",
+            "pod5.footer",
+        ),
+        ("POD5 file has no reads table.", "pod5.missing_table"),
+        (
+            "POD5 signal table is empty, although the reads table has 3 record(s).",
+            "pod5.empty_signal",
+        ),
+        (
+            "Failed to count the rows of the POD5 reads table: invalid IPC file.",
+            "pod5.record_batches",
+        ),
+        (
+            "POD5 table 1 at offset 24 with length 4096 lies outside the file.",
+            "pod5.table",
+        ),
+        (
+            "File is not an HDF5 file (FAST5 files start with the HDF5 signature).",
+            "fast5.signature",
+        ),
+        (
+            "File is truncated: the HDF5 end-of-file address is 4096, but the file has 128 bytes.",
+            "fast5.truncated",
+        ),
+        (
+            "HDF5 superblock version 9 is not supported.",
+            "fast5.superblock",
+        ),
+        (
+            "File is too short to be a GZI index (4 bytes).",
+            "gzi.invalid",
+        ),
+        (
+            "GZI index declares 2 entries but has 0 bytes of entries, so it is truncated or has trailing data.",
+            "gzi.invalid",
+        ),
+        (
+            "Failed to read GZI index: unexpected end of file",
+            "gzi.unreadable",
+        ),
+        (
+            "Index contains 1 entry(ies) that are not in increasing order of offsets. First detected at entry #2 (100:200 after 300:400).",
+            "gzi.unsorted",
+        ),
+        (
+            "Index contains 1 entry(ies) beyond the end of the data file (1000 bytes), so the index may be stale. First detected at entry #3.",
+            "gzi.out_of_bounds",
+        ),
+        (
+            "Index contains 1 compressed offset(s) that are not the start of a BGZF block. First detected at entry #1.",
+            "gzi.misaligned_offset",
+        ),
+        (
+            "Index contains 1 uncompressed offset(s) that do not match the data preceding their block. First detected at entry #1.",
+            "gzi.uncompressed_offset",
+        ),
+        (
+            "Failed to walk BGZF blocks of data file a.fa.gz: invalid block header",
+            "gzi.data_unreadable",
+        ),
+        (
+            "File is not a tabix or CSI index (magic number \"not \").",
+            "tabix.invalid_magic",
+        ),
+        (
+            "Failed to read tabix index: unexpected end of file",
+            "tabix.unreadable",
+        ),
+        (
+            "Failed to read CSI index: unexpected end of file",
+            "tabix.unreadable",
+        ),
+        (
+            "Failed to read index: invalid BGZF header",
+            "tabix.unreadable",
+        ),
+        (
+            "Failed to query index for 'chr1': invalid reference sequence ID",
+            "tabix.unreadable",
+        ),
+        (
+            "Index contains 1 sequence name(s) not present in the data file. First detected: 'chrM'.",
+            "tabix.extra_sequence",
+        ),
+        (
+            "Data file contains 1 sequence name(s) missing from the index, which may be stale. First detected: 'chr2'.",
+            "tabix.missing_sequence",
+        ),
+        (
+            "Index contains 1 sampled offset(s) that do not resolve to a record of their sequence, so the index may be stale or truncated. First detected at 'chr1'.",
+            "tabix.unresolved_offset",
+        ),
+        (
+            "Failed to resolve offsets in data file a.vcf.gz: invalid BGZF header",
+            "tabix.data_unreadable",
+        ),
+        (
+            "Failed to read data file a.vcf.gz: No such file or directory (os error 2)",
+            "tabix.data_unreadable",
+        ),
+        (
+            "File contains 1 record(s) that are reference blocks without an END tag. First detected at record #1 ('chr1:1').",
+            "vcf.gvcf_missing_end",
+        ),
+        (
+            "File contains 1 record(s) with an END before their position. First detected at record #4 ('chr1:60' with END=55).",
+            "vcf.gvcf_invalid_end",
+        ),
+        (
+            "File contains 1 record(s) overlapping the preceding block. First detected at record #3 ('chr1:40' overlaps a block ending at 50).",
+            "vcf.gvcf_overlap",
+        ),
+        (
+            "File contains no reference blocks (ALT <NON_REF> or <*>); it may not be a gVCF.",
+            "vcf.gvcf_no_reference_blocks",
+        ),
+        (
+            "Total number of reads across 2 FASTQ pair(s) is 34, but 40 are declared.",
+            "lab_datum.read_count_mismatch",
+        ),
+        (
+            "Combined yield across 2 FASTQ pair(s) is 34 bases, but 40 are declared.",
+            "lab_datum.yield_mismatch",
+        ),
+        (
+            "Totals were not computed, since 1 of 2 FASTQ pair(s) have no statistics.",
+            "lab_datum.incomplete",
+        ),
+        (
+            "Files with identical content (SHA256 0123): a.fastq.gz, b.fastq.gz",
+            "run.duplicate_content",
+        ),
+        (
+            "Header declares no contigs (##contig); skipping contig consistency check.",
+            "vcf.no_contigs",
+        ),
+        (
+            "File contains 1 record(s) on contigs not declared in the header. First detected at record #2 ('chr2').",
+            "vcf.undeclared_contig",
+        ),
+        (
+            "File contains 1 record(s) with INFO fields not declared in the header. First detected at record #2 ('AF').",
+            "vcf.undeclared_info",
+        ),
+        (
+            "File contains 1 record(s) with FORMAT fields not declared in the header. First detected at record #2 ('AD').",
+            "vcf.undeclared_format",
+        ),
+        (
+            "File contains 1 record(s) whose number of samples does not match the header. First detected at record #3 (1 sample(s) instead of 2).",
+            "vcf.sample_count_mismatch",
+        ),
+    ];
+
+    #[test]
+    fn test_codes_of_emitted_messages() {
+        for (message, code) in MESSAGES {
+            assert_eq!(code_for(message), *code, "{message}");
+        }
+        // Every code is attached to at least one emitted message, so the list above is complete.
+        for (_, code) in CODES {
+            assert!(
+                MESSAGES.iter().any(|(_, c)| c == code),
+                "No message of code {code}"
+            );
+        }
+    }
+}
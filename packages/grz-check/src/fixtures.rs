@@ -0,0 +1,361 @@
+//! Synthetic example submissions with selectable defects, written via
+//! `grz-check generate-fixtures`, e.g. to train lab staff on interpreting reports.
+//!
+//! A submission consists of a FASTQ pair and an unaligned BAM file below `files/`, a
+//! `SHA256SUMS` manifest of them and a `metadata/metadata.json` with the subset of the GRZ
+//! metadata schema that describes the files. The metadata always matches the generated reads,
+//! except for defects that are about the metadata itself. The writers are shared with the
+//! self-test.
+
+use anyhow::Context;
+use flate2::write::GzEncoder;
+use noodles::bam;
+use noodles::sam::Header;
+use noodles::sam::alignment::io::Write as _;
+use noodles::sam::alignment::record::Flags;
+use noodles::sam::alignment::record_buf::{self, QualityScores};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// A defect of a generated submission.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum Defect {
+    /// R1 ends inside a gzip block, as after an interrupted transfer.
+    TruncatedGzip,
+    /// A read of R1 contains a character that is not a base.
+    InvalidBases,
+    /// R2 lacks the mate of the last read of R1.
+    MissingMate,
+    /// R1 is submitted as both mates.
+    IdenticalMates,
+    /// R1 has Windows (CRLF) line endings, which are reported as a warning.
+    CrlfLineEndings,
+    /// The BAM file lacks the BGZF EOF marker block.
+    MissingBamEof,
+    /// The metadata declares a wrong checksum for R1.
+    WrongChecksum,
+    /// The metadata declares a read length that differs from the reads.
+    WrongReadLength,
+}
+
+/// Default number of read pairs of a generated submission.
+pub const DEFAULT_NUM_READS: usize = 1000;
+/// Default read length of a generated submission.
+pub const DEFAULT_READ_LENGTH: usize = 150;
+
+#[derive(Debug, Clone)]
+pub struct FixtureOptions {
+    pub num_reads: usize,
+    pub read_length: usize,
+    /// Seed of the generated reads, so that fixtures can be reproduced.
+    pub seed: u64,
+    pub defects: Vec<Defect>,
+}
+
+/// A synthetic read.
+pub struct Read {
+    pub name: String,
+    pub sequence: Vec<u8>,
+    pub quality: Vec<u8>,
+}
+
+/// xorshift64* generator, which is sufficient for synthetic reads.
+struct Random(u64);
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        // The state must not be zero.
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+/// Generates reads with random bases and Phred qualities from 20 to 40 in Illumina naming.
+pub fn reads(num_reads: usize, read_length: usize, seed: u64) -> Vec<Read> {
+    let mut random = Random::new(seed);
+    (0..num_reads)
+        .map(|i| Read {
+            name: format!(
+                "FIXTURE:1:FC0001:1:1101:{}:{}",
+                1000 + i / 100,
+                1000 + i % 100
+            ),
+            sequence: (0..read_length)
+                .map(|_| b"ACGT"[(random.next() % 4) as usize])
+                .collect(),
+            quality: (0..read_length)
+                .map(|_| 20 + (random.next() % 21) as u8)
+                .collect(),
+        })
+        .collect()
+}
+
+/// Formats reads as FASTQ records of the given mate (1 or 2).
+pub fn fastq(reads: &[Read], mate: u8) -> String {
+    reads
+        .iter()
+        .map(|read| {
+            let quality: String = read.quality.iter().map(|&q| char::from(q + 33)).collect();
+            format!(
+                "@{} {mate}:N:0:ATCACG\n{}\n+\n{quality}\n",
+                read.name,
+                String::from_utf8_lossy(&read.sequence)
+            )
+        })
+        .collect()
+}
+
+pub fn write_gzipped(path: &Path, content: &[u8]) -> anyhow::Result<()> {
+    let mut encoder = GzEncoder::new(fs::File::create(path)?, flate2::Compression::default());
+    encoder.write_all(content)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Cuts a file in half, so that it ends inside a compressed block.
+pub fn truncate(path: &Path) -> anyhow::Result<()> {
+    let content = fs::read(path)?;
+    fs::write(path, &content[..content.len() / 2])?;
+    Ok(())
+}
+
+/// Removes the BGZF EOF marker block at the end of a file, as after a transfer interrupted
+/// between blocks.
+pub fn remove_eof_marker(path: &Path) -> anyhow::Result<()> {
+    let content = fs::read(path)?;
+    fs::write(path, &content[..content.len().saturating_sub(28)])?;
+    Ok(())
+}
+
+/// Writes reads as an unaligned BAM file.
+pub fn write_bam(path: &Path, reads: &[Read]) -> anyhow::Result<()> {
+    let header = Header::default();
+    let mut writer = bam::io::Writer::new(fs::File::create(path)?);
+    writer.write_header(&header)?;
+    for read in reads {
+        let record = record_buf::Builder::default()
+            .set_name(read.name.as_str())
+            .set_flags(Flags::UNMAPPED)
+            .set_sequence(read.sequence.clone().into())
+            .set_quality_scores(QualityScores::from(read.quality.clone()))
+            .build();
+        writer.write_alignment_record(&header, &record)?;
+    }
+    // Finishing consumes the writer, which would otherwise write the EOF block again on drop.
+    writer.into_inner().finish()?;
+    Ok(())
+}
+
+/// A generated file with what the metadata declares about it.
+struct FileEntry {
+    name: String,
+    file_type: &'static str,
+    checksum: String,
+    size: u64,
+    read_order: Option<&'static str>,
+    read_length: Option<usize>,
+}
+
+fn file_entry(path: &Path, file_type: &'static str) -> anyhow::Result<FileEntry> {
+    let content = fs::read(path)?;
+    Ok(FileEntry {
+        name: path
+            .file_name()
+            .context("Fixture path should have a file name")?
+            .to_string_lossy()
+            .into_owned(),
+        file_type,
+        checksum: format!("{:x}", Sha256::digest(&content)),
+        size: content.len() as u64,
+        read_order: None,
+        read_length: None,
+    })
+}
+
+fn metadata(entries: &[FileEntry]) -> serde_json::Value {
+    let files: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let mut file = json!({
+                "filePath": entry.name,
+                "fileType": entry.file_type,
+                "fileChecksum": entry.checksum,
+                "fileSizeInBytes": entry.size,
+            });
+            if let Some(read_order) = entry.read_order {
+                file["readOrder"] = json!(read_order);
+            }
+            if let Some(read_length) = entry.read_length {
+                file["readLength"] = json!(read_length);
+            }
+            file
+        })
+        .collect();
+    json!({
+        "submission": {
+            "submissionType": "test",
+            "localCaseId": "fixture",
+            "genomicStudyType": "single",
+            "genomicStudySubtype": "germline-only",
+        },
+        "donors": [{
+            "donorPseudonym": "index",
+            "relation": "index",
+            "labData": [{
+                "labDataName": "Blood DNA normal",
+                "sequenceType": "dna",
+                "sequenceSubtype": "germline",
+                "libraryType": "wgs",
+                "sequencingLayout": "paired-end",
+                "sequenceData": {
+                    "referenceGenome": "GRCh38",
+                    "files": files,
+                },
+            }],
+        }],
+    })
+}
+
+/// Writes a submission with the given defects to `dir` and prints how to check it.
+pub fn generate(dir: &Path, options: &FixtureOptions) -> anyhow::Result<()> {
+    let has = |defect| options.defects.contains(&defect);
+    let files_dir = dir.join("files");
+    let metadata_dir = dir.join("metadata");
+    fs::create_dir_all(&files_dir)
+        .with_context(|| format!("Failed to create {}", files_dir.display()))?;
+    fs::create_dir_all(&metadata_dir)
+        .with_context(|| format!("Failed to create {}", metadata_dir.display()))?;
+
+    let mates = [1, 2].map(|mate| {
+        reads(
+            options.num_reads,
+            options.read_length,
+            options.seed.wrapping_add(mate),
+        )
+    });
+    let mut r1 = fastq(&mates[0], 1);
+    let mut r2 = fastq(&mates[1], 2);
+    if has(Defect::IdenticalMates) {
+        r2 = r1.clone();
+    }
+    if has(Defect::MissingMate)
+        && let Some(last_record) = r2.trim_end().rfind("\n@")
+    {
+        r2.truncate(last_record + 1);
+    }
+    if has(Defect::InvalidBases)
+        && let Some(base) = r1.lines().nth(1).map(|sequence| sequence.len() / 2)
+    {
+        // The middle base of the first read.
+        let position = r1.find('\n').map_or(0, |end| end + 1) + base;
+        r1.replace_range(position..=position, "X");
+    }
+    if has(Defect::CrlfLineEndings) {
+        r1 = r1.replace('\n', "\r\n");
+    }
+
+    let r1_path = files_dir.join("fixture.read1.fastq.gz");
+    let r2_path = files_dir.join("fixture.read2.fastq.gz");
+    let bam_path = files_dir.join("fixture.bam");
+    write_gzipped(&r1_path, r1.as_bytes())?;
+    write_gzipped(&r2_path, r2.as_bytes())?;
+    // The metadata describes the files as they were before the transfer.
+    let mut entries = vec![
+        file_entry(&r1_path, "fastq")?,
+        file_entry(&r2_path, "fastq")?,
+    ];
+    if has(Defect::TruncatedGzip) {
+        truncate(&r1_path)?;
+    }
+    write_bam(&bam_path, &mates[0])?;
+    entries.push(file_entry(&bam_path, "bam")?);
+    if has(Defect::MissingBamEof) {
+        remove_eof_marker(&bam_path)?;
+    }
+
+    let declared_read_length = if has(Defect::WrongReadLength) {
+        options.read_length + 1
+    } else {
+        options.read_length
+    };
+    for (entry, read_order) in entries.iter_mut().zip(["R1", "R2"]) {
+        entry.read_order = Some(read_order);
+        entry.read_length = Some(declared_read_length);
+    }
+    if has(Defect::WrongChecksum) {
+        entries[0].checksum = format!("{:x}", Sha256::digest(b"a different upload"));
+    }
+
+    let manifest: String = entries
+        .iter()
+        .map(|entry| format!("{}  {}\n", entry.checksum, entry.name))
+        .collect();
+    fs::write(files_dir.join("SHA256SUMS"), manifest)?;
+    fs::write(
+        metadata_dir.join("metadata.json"),
+        serde_json::to_string_pretty(&metadata(&entries))? + "\n",
+    )?;
+
+    let (r1, r2, bam) = (r1_path.display(), r2_path.display(), bam_path.display());
+    println!("Wrote a submission to {}. Check it with:", dir.display());
+    println!(
+        "  grz-check --fastq-paired {r1} {r2} {} --declared-read-length {r1} {declared_read_length} --declared-read-length {r2} {declared_read_length} --expected-records {r1} {n} --expected-records {r2} {n} --ubam {bam} --continue-on-error --output report.jsonl",
+        options.read_length / 2,
+        n = options.num_reads
+    );
+    println!("and verify the checksums declared in the metadata with:");
+    println!(
+        "  grz-check verify {} --output verify.jsonl",
+        files_dir.join("SHA256SUMS").display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_generate_fixtures() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let options = FixtureOptions {
+            num_reads: 10,
+            read_length: 20,
+            seed: 1,
+            defects: vec![Defect::MissingMate, Defect::InvalidBases],
+        };
+        generate(dir.path(), &options)?;
+
+        let metadata: serde_json::Value =
+            serde_json::from_slice(&fs::read(dir.path().join("metadata/metadata.json"))?)?;
+        let files = &metadata["donors"][0]["labData"][0]["sequenceData"]["files"];
+        assert_eq!(files.as_array().map(Vec::len), Some(3));
+        assert_eq!(files[0]["readLength"], 20);
+        assert_eq!(files[2]["fileType"], "bam");
+
+        let r1 = niffler::from_path(dir.path().join("files/fixture.read1.fastq.gz"))?.0;
+        let r1 = std::io::read_to_string(r1)?;
+        let r2 = niffler::from_path(dir.path().join("files/fixture.read2.fastq.gz"))?.0;
+        let r2 = std::io::read_to_string(r2)?;
+        assert_eq!(r1.lines().count(), 40);
+        assert_eq!(r2.lines().count(), 36);
+        assert_eq!(
+            r1.lines().nth(1).map(|sequence| &sequence[10..11]),
+            Some("X")
+        );
+
+        // The same seed reproduces the same reads.
+        assert_eq!(fastq(&reads(3, 20, 7), 1), fastq(&reads(3, 20, 7), 1));
+        Ok(())
+    }
+}
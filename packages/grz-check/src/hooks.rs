@@ -0,0 +1,103 @@
+//! Hook commands from the config file: the `[staging]` command is run before the files of a job
+//! are opened, e.g. to recall them from tape or a hierarchical storage management system, and
+//! the `[post_check]` command after each file has been checked, e.g. to move passed files into
+//! a `ready/` directory.
+
+use crate::archive;
+use crate::sha256::DigestEncoding;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Placeholder in command templates that is replaced by the shell-quoted path.
+pub const PATH_PLACEHOLDER: &str = "{path}";
+/// Placeholder in post-check command templates that is replaced by `OK` or `ERROR`.
+const STATUS_PLACEHOLDER: &str = "{status}";
+/// Placeholder in post-check command templates that is replaced by the SHA-256 digest, which is
+/// empty if it was not computed.
+const DIGEST_PLACEHOLDER: &str = "{digest}";
+
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Runs `command` with `sh` and waits for it to finish.
+pub fn run(kind: &str, command: &str) -> Result<(), String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| {
+            format!(
+                "Failed to run {} command `{command}`: {e}",
+                kind.to_lowercase()
+            )
+        })?;
+    if !output.status.success() {
+        return Err(format!(
+            "{kind} command `{command}` failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StagingHook {
+    /// Shell command template, e.g. `dmget {path}`.
+    pub command: String,
+}
+
+/// Outcome of staging a file: the time it took, or why it failed.
+pub type Staged = (PathBuf, Result<Duration, String>);
+
+impl StagingHook {
+    /// Runs the command for `path` and waits for it to finish.
+    ///
+    /// Members of archives are staged by staging the archive.
+    fn stage(&self, path: &Path) -> Result<Duration, String> {
+        let started = Instant::now();
+        let target = archive::split(path).map_or_else(|| path.to_path_buf(), |(tar, _)| tar);
+        let command = self
+            .command
+            .replace(PATH_PLACEHOLDER, &shell_quote(&target.to_string_lossy()));
+        run("Staging", &command)?;
+        Ok(started.elapsed())
+    }
+
+    /// Stages all files of a job, one after the other.
+    pub fn stage_all(&self, paths: &[PathBuf]) -> Vec<Staged> {
+        paths
+            .iter()
+            .map(|path| (path.clone(), self.stage(path)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PostCheckHook {
+    /// Shell command template, e.g. `[ {status} = OK ] && mv {path} ready/`.
+    pub command: String,
+    /// Encoding of the digest that replaces `{digest}`.
+    pub digest_encoding: DigestEncoding,
+}
+
+impl PostCheckHook {
+    /// Runs the command for a checked file and waits for it to finish.
+    pub fn run(&self, path: &Path, status: &str, digest: Option<&str>) -> Result<(), String> {
+        let command = self
+            .command
+            .replace(PATH_PLACEHOLDER, &shell_quote(&path.to_string_lossy()))
+            .replace(STATUS_PLACEHOLDER, status)
+            .replace(
+                DIGEST_PLACEHOLDER,
+                &digest
+                    .map(|digest| self.digest_encoding.encode(digest))
+                    .unwrap_or_default(),
+            );
+        run("Post-check", &command)
+    }
+}
@@ -0,0 +1,217 @@
+//! Lab data consisting of several paired FASTQ files given via `--lab-datum`, e.g. a library
+//! sequenced on multiple flowcells or lanes.
+//!
+//! The statistics of all pairs of a lab datum are aggregated and compared against the totals
+//! declared in the metadata. BAM files assigned to lab data via `--lab-datum-bam` are checked
+//! for read groups mapping to them instead.
+
+use crate::checker::PairReport;
+use noodles::sam;
+use noodles::sam::header::record::value::map::read_group::tag;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FastqPair {
+    pub fq1: PathBuf,
+    pub fq2: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LabDatum {
+    pub id: String,
+    /// FASTQ pairs in the order given on the command line.
+    pub pairs: Vec<FastqPair>,
+    /// Total number of reads of all pairs declared in the metadata.
+    pub declared_num_records: Option<u64>,
+    /// Total number of bases of all pairs declared in the metadata.
+    pub declared_total_bases: Option<u64>,
+}
+
+/// Number of reads and bases of a checked FASTQ pair.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+struct PairTotals {
+    num_records: u64,
+    total_bases: u64,
+}
+
+/// Aggregated statistics of a lab datum.
+#[derive(Debug)]
+pub struct Summary<'a> {
+    pub lab_datum: &'a LabDatum,
+    /// Totals over all pairs, or `None` if some pair has no statistics.
+    pub num_records: Option<u64>,
+    pub total_bases: Option<u64>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Collects the statistics of the FASTQ pairs belonging to lab data while checking.
+#[derive(Debug, Default)]
+pub struct LabDataTotals {
+    lab_data: Vec<LabDatum>,
+    pairs: Mutex<HashMap<PathBuf, PairTotals>>,
+}
+
+impl LabDataTotals {
+    pub fn new(lab_data: &[LabDatum]) -> Self {
+        Self {
+            lab_data: lab_data.to_vec(),
+            pairs: Mutex::default(),
+        }
+    }
+
+    /// Records the statistics of a checked pair, if it belongs to a lab datum.
+    pub fn add(&self, report: &PairReport) {
+        let belongs_to_lab_datum = self.lab_data.iter().any(|lab_datum| {
+            lab_datum
+                .pairs
+                .iter()
+                .any(|pair| pair.fq1 == report.fq1_report.path)
+        });
+        if !belongs_to_lab_datum {
+            return;
+        }
+
+        let mut totals = PairTotals::default();
+        for file_report in [&report.fq1_report, &report.fq2_report] {
+            let Some(stats) = file_report.stats else {
+                return;
+            };
+            totals.num_records += stats.num_records;
+            totals.total_bases += stats.total_read_length.unwrap_or_default();
+        }
+        self.pairs
+            .lock()
+            .unwrap()
+            .insert(report.fq1_report.path.clone(), totals);
+    }
+
+    /// Aggregates the statistics of each lab datum and compares them against the declared totals.
+    pub fn summaries(&self) -> Vec<Summary<'_>> {
+        let pairs = self.pairs.lock().unwrap();
+        self.lab_data
+            .iter()
+            .map(|lab_datum| {
+                let mut errors = Vec::new();
+                let mut warnings = Vec::new();
+                let num_pairs = lab_datum.pairs.len();
+
+                let totals: Option<Vec<PairTotals>> = lab_datum
+                    .pairs
+                    .iter()
+                    .map(|pair| pairs.get(&pair.fq1).copied())
+                    .collect();
+                let Some(totals) = totals else {
+                    let missing = lab_datum
+                        .pairs
+                        .iter()
+                        .filter(|pair| !pairs.contains_key(&pair.fq1))
+                        .count();
+                    warnings.push(format!(
+                        "Totals were not computed, since {missing} of {num_pairs} FASTQ pair(s) have no statistics."
+                    ));
+                    return Summary {
+                        lab_datum,
+                        num_records: None,
+                        total_bases: None,
+                        errors,
+                        warnings,
+                    };
+                };
+
+                let num_records = totals.iter().map(|t| t.num_records).sum();
+                let total_bases = totals.iter().map(|t| t.total_bases).sum();
+                if let Some(declared) = lab_datum.declared_num_records
+                    && declared != num_records
+                {
+                    errors.push(format!(
+                        "Total number of reads across {num_pairs} FASTQ pair(s) is {num_records}, but {declared} are declared."
+                    ));
+                }
+                if let Some(declared) = lab_datum.declared_total_bases
+                    && declared != total_bases
+                {
+                    errors.push(format!(
+                        "Combined yield across {num_pairs} FASTQ pair(s) is {total_bases} bases, but {declared} are declared."
+                    ));
+                }
+                Summary {
+                    lab_datum,
+                    num_records: Some(num_records),
+                    total_bases: Some(total_bases),
+                    errors,
+                    warnings,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Lab data assigned to BAM files, and all lab data declared in the metadata.
+#[derive(Debug, Clone, Default)]
+pub struct ReadGroupMapping {
+    /// IDs of the lab data assigned to each BAM file.
+    pub assigned: BTreeMap<PathBuf, Vec<String>>,
+    /// IDs of all declared lab data, of FASTQ pairs and BAM files.
+    pub declared: Vec<String>,
+}
+
+/// Returns errors if a read group of a BAM file assigned to lab data maps to no lab datum of
+/// the file by its ID, SM or LB, or if a lab datum of the file has no read group, e.g. for a
+/// BAM file merged with the reads of another sample.
+pub fn read_group_errors(
+    path: &Path,
+    header: &sam::Header,
+    mapping: &ReadGroupMapping,
+) -> Vec<String> {
+    let Some(assigned) = mapping.assigned.get(path) else {
+        return Vec::new();
+    };
+    let mut errors = Vec::new();
+    let mut mapped = vec![false; assigned.len()];
+    for (id, read_group) in header.read_groups() {
+        let fields = read_group.other_fields();
+        let (sample, library) = (fields.get(&tag::SAMPLE), fields.get(&tag::LIBRARY));
+        let names: Vec<String> = [Some(id), sample, library]
+            .into_iter()
+            .flatten()
+            .map(ToString::to_string)
+            .collect();
+        let maps_to = |lab_datum: &String| names.contains(lab_datum);
+        let mut is_mapped = false;
+        for (lab_datum, mapped) in assigned.iter().zip(&mut mapped) {
+            if maps_to(lab_datum) {
+                *mapped = true;
+                is_mapped = true;
+            }
+        }
+        if is_mapped {
+            continue;
+        }
+        let described = format!(
+            "Read group '{id}' (SM '{}', LB '{}')",
+            sample.map(ToString::to_string).unwrap_or_default(),
+            library.map(ToString::to_string).unwrap_or_default()
+        );
+        match mapping.declared.iter().find(|lab_datum| maps_to(lab_datum)) {
+            Some(other) => errors.push(format!(
+                "{described} belongs to lab datum '{other}', which is not assigned to this file. The file may have been merged with the reads of another sample."
+            )),
+            None => errors.push(format!(
+                "{described} maps to no declared lab datum; its ID, SM or LB must be one of: {}.",
+                assigned.join(", ")
+            )),
+        }
+    }
+    for (lab_datum, mapped) in assigned.iter().zip(mapped) {
+        if !mapped {
+            errors.push(format!(
+                "Lab datum '{lab_datum}' is assigned to this file, but no read group has it as ID, SM or LB."
+            ));
+        }
+    }
+    errors
+}
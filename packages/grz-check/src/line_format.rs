@@ -0,0 +1,381 @@
+//! Inspection of the raw lines of FASTQ files for Windows line endings, trailing whitespace and
+//! blank lines, e.g. of files edited by hand on Windows.
+//!
+//! The FASTQ parser silently accepts CRLF line endings and reports blank lines and trailing
+//! whitespace only as generic parse errors or invalid characters, so the lines are inspected
+//! below the parser to report the exact line instead. Line numbers are only meaningful up to the
+//! first blank line, which ends the check of a file anyway.
+//!
+//! With `--parse-leniency tolerant`, blank lines at the end of a file are held back from the
+//! parser and reported as a warning, like a last line without a newline, which is an error in
+//! strict mode.
+
+use serde::Serialize;
+use std::cell::RefCell;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+/// How recoverable defects of FASTQ files are reported, as data hubs interpret the format
+/// differently.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ParseLeniency {
+    /// Blank lines at the end of a file and a last line without a newline are errors.
+    #[default]
+    Strict,
+    /// Blank lines at the end of a file and a last line without a newline are warnings.
+    Tolerant,
+}
+
+/// Findings about the lines of a file, up to the bytes read so far.
+#[derive(Debug, Default)]
+struct Lines {
+    /// Number of completed lines.
+    completed: u64,
+    /// Number of bytes of the current line.
+    length: u64,
+    /// Last byte of the current line, and the byte before it.
+    last: [u8; 2],
+    first_crlf: Option<u64>,
+    /// Line number and character of the first sequence or quality line ending in whitespace.
+    first_trailing_whitespace: Option<(u64, u8)>,
+    first_blank: Option<u64>,
+    /// Whether the end of the file was reached.
+    finished: bool,
+    /// Line number of a last line without a newline.
+    unterminated: Option<u64>,
+    /// Number of blank lines at the end of the file that were held back from the parser.
+    trailing_blank: u64,
+}
+
+/// Record number of a 1-based line number, and the 0-based index of the line in the record.
+fn record_of(line: u64) -> (u64, u64) {
+    ((line - 1) / 4 + 1, (line - 1) % 4)
+}
+
+impl Lines {
+    fn add(&mut self, bytes: &[u8]) {
+        let mut rest = bytes;
+        while let Some(newline) = rest.iter().position(|&b| b == b'\n') {
+            self.extend(&rest[..newline]);
+            self.end_line();
+            rest = &rest[newline + 1..];
+        }
+        self.extend(rest);
+    }
+
+    fn extend(&mut self, bytes: &[u8]) {
+        match bytes {
+            [] => {}
+            [byte] => self.last = [self.last[1], *byte],
+            [.., before, last] => self.last = [*before, *last],
+        }
+        self.length += bytes.len() as u64;
+    }
+
+    fn end_line(&mut self) {
+        self.completed += 1;
+        let line = self.completed;
+        // A line of only a carriage return is blank with CRLF line endings.
+        if self.length == 0 || (self.length == 1 && self.last[1] == b'\r') {
+            self.first_blank.get_or_insert(line);
+        } else {
+            let mut end = self.last[1];
+            if end == b'\r' {
+                self.first_crlf.get_or_insert(line);
+                end = self.last[0];
+            }
+            let (_, index) = record_of(line);
+            if matches!(end, b' ' | b'\t')
+                && matches!(index, 1 | 3)
+                && self.first_trailing_whitespace.is_none()
+            {
+                self.first_trailing_whitespace = Some((line, end));
+            }
+        }
+        self.length = 0;
+        self.last = [0; 2];
+    }
+
+    fn finish(&mut self) {
+        if !self.finished && self.length > 0 {
+            self.unterminated = Some(self.completed + 1);
+        }
+        self.finished = true;
+    }
+}
+
+/// Blank lines held back from the parser until a line with content follows them.
+#[derive(Default)]
+struct HeldBlankLines {
+    /// Whether only line breaks were read since the last line with content.
+    in_blank_lines: bool,
+    held: Vec<u8>,
+    chunk: Vec<u8>,
+    /// Bytes passed on to the parser, from `position` on.
+    pending: Vec<u8>,
+    position: usize,
+}
+
+impl HeldBlankLines {
+    fn new() -> Self {
+        Self {
+            in_blank_lines: true,
+            ..Default::default()
+        }
+    }
+
+    /// Reads a chunk from `inner`, holding back line breaks at the start of lines. Returns
+    /// `false` at the end of the file.
+    fn fill(&mut self, inner: &mut impl Read, size: usize, lines: &mut Lines) -> io::Result<bool> {
+        self.chunk.resize(size.max(1), 0);
+        let n = inner.read(&mut self.chunk)?;
+        if n == 0 {
+            lines.trailing_blank = self.held.iter().filter(|&&b| b == b'\n').count() as u64;
+            self.held.clear();
+            return Ok(false);
+        }
+        self.pending.clear();
+        self.position = 0;
+        for &byte in &self.chunk[..n] {
+            if self.in_blank_lines && matches!(byte, b'\n' | b'\r') {
+                self.held.push(byte);
+                continue;
+            }
+            self.pending.append(&mut self.held);
+            self.pending.push(byte);
+            self.in_blank_lines = byte == b'\n';
+        }
+        lines.add(&self.pending);
+        Ok(true)
+    }
+}
+
+/// Reader that inspects the lines passing through it.
+pub struct LineInspector<R> {
+    inner: R,
+    lines: Rc<RefCell<Lines>>,
+    /// Set if blank lines at the end of the file are tolerated.
+    held: Option<HeldBlankLines>,
+}
+
+impl<R: Read> Read for LineInspector<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let Some(held) = &mut self.held else {
+            let n = self.inner.read(buf)?;
+            let mut lines = self.lines.borrow_mut();
+            lines.add(&buf[..n]);
+            if n == 0 {
+                lines.finish();
+            }
+            return Ok(n);
+        };
+        while held.position == held.pending.len() {
+            let mut lines = self.lines.borrow_mut();
+            if !held.fill(&mut self.inner, buf.len(), &mut lines)? {
+                lines.finish();
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(held.pending.len() - held.position);
+        buf[..n].copy_from_slice(&held.pending[held.position..held.position + n]);
+        held.position += n;
+        Ok(n)
+    }
+}
+
+/// Handle to the findings of a [`LineInspector`].
+#[derive(Debug, Clone, Default)]
+pub struct LineFormat {
+    lines: Rc<RefCell<Lines>>,
+    tolerant: bool,
+}
+
+impl LineFormat {
+    /// Wraps `reader` to inspect its lines, holding back blank lines at the end of the file from
+    /// the parser if `leniency` is tolerant.
+    pub fn inspect<R: Read>(reader: R, leniency: ParseLeniency) -> (LineInspector<R>, Self) {
+        let format = Self {
+            tolerant: leniency == ParseLeniency::Tolerant,
+            ..Default::default()
+        };
+        let inspector = LineInspector {
+            inner: reader,
+            lines: format.lines.clone(),
+            held: format.tolerant.then(HeldBlankLines::new),
+        };
+        (inspector, format)
+    }
+
+    /// Whether recoverable defects are reported as warnings instead of errors.
+    pub fn is_tolerant(&self) -> bool {
+        self.tolerant
+    }
+
+    /// Returns the reason a record failed to parse if a blank line comes before or inside it.
+    pub fn blank_line_error(&self, record_number: u64) -> Option<String> {
+        let line = self.lines.borrow().first_blank?;
+        let (record, index) = record_of(line);
+        if record > record_number {
+            return None;
+        }
+        let position = if index == 0 {
+            "where the record should start"
+        } else {
+            "inside the record"
+        };
+        Some(format!(
+            "blank line at line {line}, {position}. FASTQ records consist of exactly four lines, without blank lines between or after them."
+        ))
+    }
+
+    /// Record number of the first sequence or quality line ending in whitespace.
+    pub fn trailing_whitespace_record(&self) -> Option<u64> {
+        let (line, _) = self.lines.borrow().first_trailing_whitespace?;
+        Some(record_of(line).0)
+    }
+
+    /// Returns an error if a sequence or quality line ends in whitespace.
+    pub fn trailing_whitespace_error(&self) -> Option<String> {
+        let (line, byte) = self.lines.borrow().first_trailing_whitespace?;
+        let (record, index) = record_of(line);
+        Some(format!(
+            "Trailing whitespace ({}) at the end of line {line}, the {} line of record #{record}. Sequence and quality lines must not contain whitespace.",
+            if byte == b'\t' { "tab" } else { "space" },
+            if index == 1 { "sequence" } else { "quality" }
+        ))
+    }
+
+    /// Returns a finding if the last line does not end with a newline, an error unless
+    /// [`is_tolerant`](Self::is_tolerant).
+    pub fn missing_newline(&self) -> Option<String> {
+        let line = self.lines.borrow().unterminated?;
+        Some(format!(
+            "Last line (line {line}) does not end with a newline, which FASTQ files require. The file may have been cut off or concatenated without line breaks."
+        ))
+    }
+
+    /// Returns a warning if blank lines at the end of the file were held back from the parser.
+    pub fn trailing_blank_lines_warning(&self) -> Option<String> {
+        let lines = self.lines.borrow();
+        (lines.trailing_blank > 0).then(|| {
+            format!(
+                "File ends with {} blank line(s) after line {}, which were ignored.",
+                lines.trailing_blank, lines.completed
+            )
+        })
+    }
+
+    /// Returns a warning if the file has Windows (CRLF) line endings.
+    pub fn crlf_warning(&self) -> Option<String> {
+        let line = self.lines.borrow().first_crlf?;
+        Some(format!(
+            "File has Windows (CRLF) line endings, first at line {line} (record #{}). Many tools expect Unix (LF) line endings.",
+            record_of(line).0
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inspect(data: &[u8]) -> LineFormat {
+        let (mut inspector, format) = LineFormat::inspect(data, ParseLeniency::Strict);
+        // Small reads split lines and CRLF pairs across calls.
+        let mut buf = [0; 3];
+        while inspector.read(&mut buf).unwrap() > 0 {}
+        format
+    }
+
+    /// Reads `data` in tolerant mode and returns what the parser sees.
+    fn inspect_tolerant(data: &[u8]) -> (Vec<u8>, LineFormat) {
+        let (mut inspector, format) = LineFormat::inspect(data, ParseLeniency::Tolerant);
+        let mut parsed = Vec::new();
+        let mut buf = [0; 3];
+        loop {
+            let n = inspector.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            parsed.extend_from_slice(&buf[..n]);
+        }
+        (parsed, format)
+    }
+
+    #[test]
+    fn test_line_format() {
+        let format = inspect(b"@r1\nACGT\n+\nIIII\n@r2\nACGT\n+\nIIII\n");
+        assert_eq!(format.crlf_warning(), None);
+        assert_eq!(format.trailing_whitespace_error(), None);
+        assert_eq!(format.blank_line_error(3), None);
+
+        let format = inspect(b"@r1 \nACGT\n+\nIIII\r\n@r2\nACGT \r\n+\nIIII\n");
+        assert_eq!(
+            format.crlf_warning().as_deref(),
+            Some(
+                "File has Windows (CRLF) line endings, first at line 4 (record #1). Many tools expect Unix (LF) line endings."
+            )
+        );
+        assert_eq!(format.trailing_whitespace_record(), Some(2));
+        assert_eq!(
+            format.trailing_whitespace_error().as_deref(),
+            Some(
+                "Trailing whitespace (space) at the end of line 6, the sequence line of record #2. Sequence and quality lines must not contain whitespace."
+            )
+        );
+
+        let format = inspect(b"@r1\nACGT\n+\nIIII\n\n@r2\nACGT\n+\nIIII\n");
+        assert_eq!(format.blank_line_error(1), None);
+        assert_eq!(
+            format.blank_line_error(2).as_deref(),
+            Some(
+                "blank line at line 5, where the record should start. FASTQ records consist of exactly four lines, without blank lines between or after them."
+            )
+        );
+        let format = inspect(b"@r1\nACGT\n\n+\nIIII\n");
+        assert!(
+            format
+                .blank_line_error(1)
+                .is_some_and(|error| error.contains("line 3, inside the record"))
+        );
+    }
+
+    #[test]
+    fn test_parse_leniency() {
+        let format = inspect(b"@r1\nACGT\n+\nIIII");
+        assert_eq!(
+            format.missing_newline().as_deref(),
+            Some(
+                "Last line (line 4) does not end with a newline, which FASTQ files require. The file may have been cut off or concatenated without line breaks."
+            )
+        );
+        let format = inspect(b"@r1\nACGT\n+\nIIII\n\n");
+        assert_eq!(format.missing_newline(), None);
+        assert_eq!(format.trailing_blank_lines_warning(), None);
+        assert!(format.blank_line_error(2).is_some());
+
+        let (parsed, format) = inspect_tolerant(b"@r1\nACGT\n+\nIIII\r\n\r\n\n");
+        assert_eq!(parsed, b"@r1\nACGT\n+\nIIII\r\n");
+        assert_eq!(
+            format.trailing_blank_lines_warning().as_deref(),
+            Some("File ends with 2 blank line(s) after line 4, which were ignored.")
+        );
+        assert_eq!(format.blank_line_error(2), None);
+
+        // Blank lines between records are passed on to the parser.
+        let data = b"@r1\nACGT\n+\nIIII\n\n\n@r2\nACGT\n+\nIIII";
+        let (parsed, format) = inspect_tolerant(data);
+        assert_eq!(parsed, data);
+        assert_eq!(format.trailing_blank_lines_warning(), None);
+        assert!(format.blank_line_error(2).is_some());
+        assert!(
+            format
+                .missing_newline()
+                .is_some_and(|m| m.contains("line 10"))
+        );
+    }
+}
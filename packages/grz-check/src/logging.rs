@@ -0,0 +1,149 @@
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable messages on stderr alongside the progress bars.
+    #[default]
+    Text,
+    /// One JSON object per log event on stderr; progress bars are suppressed.
+    Json,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Serialize)]
+struct LogEvent<'a> {
+    timestamp: String,
+    level: Level,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    progress: Option<ProgressEvent<'a>>,
+}
+
+/// Progress of a file counted in records, for files whose byte progress is meaningless.
+#[derive(Serialize)]
+struct ProgressEvent<'a> {
+    path: &'a Path,
+    records: u64,
+    records_per_sec: f64,
+}
+
+static FORMAT: OnceLock<LogFormat> = OnceLock::new();
+
+pub fn init(format: LogFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> LogFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Writes a log event to stderr.
+///
+/// In text mode, info events are not printed, since the progress bars already convey them.
+pub fn log(level: Level, message: impl Display) {
+    match format() {
+        LogFormat::Text => {
+            if level > Level::Info {
+                eprintln!("{message}");
+            }
+        }
+        LogFormat::Json => {
+            if let Some(line) = json_line(level, message.to_string().trim(), None) {
+                eprintln!("{line}");
+            }
+        }
+    }
+}
+
+/// Formats a log event as a line of JSON.
+fn json_line(level: Level, message: &str, progress: Option<ProgressEvent>) -> Option<String> {
+    let event = LogEvent {
+        timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        level,
+        message,
+        progress,
+    };
+    serde_json::to_string(&event).ok()
+}
+
+pub fn info(message: impl Display) {
+    log(Level::Info, message);
+}
+
+pub fn warn(message: impl Display) {
+    log(Level::Warn, message);
+}
+
+pub fn error(message: impl Display) {
+    log(Level::Error, message);
+}
+
+/// Writes a progress event with the number of records of a file read so far to stderr, in JSON
+/// format only.
+pub fn progress(path: &Path, records: u64, records_per_sec: f64) {
+    if format() != LogFormat::Json {
+        return;
+    }
+    let message = format!(
+        "Read {records} records of {} ({records_per_sec:.0} records/s)",
+        path.display()
+    );
+    let progress = ProgressEvent {
+        path,
+        records,
+        records_per_sec,
+    };
+    if let Some(line) = json_line(Level::Info, &message, Some(progress)) {
+        eprintln!("{line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn test_json_line() {
+        let message = "Invalid record \"r1\" in C:\\data\ttab\nnext line";
+        let line = json_line(Level::Warn, message, None).unwrap();
+        assert!(!line.contains('\n'), "{line}");
+        let event: Value = serde_json::from_str(&line).unwrap();
+        assert!(DateTime::parse_from_rfc3339(event["timestamp"].as_str().unwrap()).is_ok());
+        assert_eq!(event["level"], "warn");
+        assert_eq!(event["message"], message);
+        // Only the fields above are written for plain messages.
+        assert_eq!(event.as_object().unwrap().len(), 3);
+
+        let progress = ProgressEvent {
+            path: Path::new("dir/reads \"1\".bam"),
+            records: 1000,
+            records_per_sec: 250.5,
+        };
+        let line = json_line(Level::Info, "Read 1000 records", Some(progress)).unwrap();
+        let event: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(event["level"], "info");
+        assert_eq!(event["message"], "Read 1000 records");
+        assert_eq!(
+            event["progress"],
+            json!({
+                "path": "dir/reads \"1\".bam",
+                "records": 1000,
+                "records_per_sec": 250.5,
+            })
+        );
+    }
+}
@@ -1,38 +1,130 @@
 use anyhow::{Context, Result};
-use clap::{ArgGroup, Parser};
-use std::fs;
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use clap::{ArgGroup, Parser, Subcommand};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
 
-use crate::checker::Job;
-use crate::checks::bam::BamCheckJob;
-use crate::checks::fastq::{PairedFastqJob, ReadLengthCheck, SingleFastqJob};
+use crate::checker::{Job, RunOptions, StatsLevel};
+use crate::checks::bam::{self, BamCheckJob};
+use crate::checks::bed::BedCheckJob;
+use crate::checks::common::{self, InflateBackend};
+use crate::checks::fasta::FastaCheckJob;
+use crate::checks::fastq::{
+    self, Adapter, AdapterScreening, DeclaredReadLength, InterleavedFastqJob, PairedFastqJob,
+    PolyGScreening, ReadLengthCheck, SingleFastqJob, TripleFastqJob,
+};
+use crate::checks::gzi::GziCheckJob;
 use crate::checks::raw::RawJob;
+use crate::checks::reference::Species;
+use crate::checks::sam::SamCheckJob;
+use crate::checks::signal::SignalJob;
+use crate::checks::tabix::TabixCheckJob;
+use crate::checks::vcf::VcfCheckJob;
+use crate::config::Config;
+use crate::findings::Severity;
+use crate::fixtures::{Defect, FixtureOptions};
+use crate::hooks::StagingHook;
+use crate::lab_data::{FastqPair, LabDatum};
+use crate::line_format::ParseLeniency;
+use crate::logging::LogFormat;
+use crate::options::Settings;
+use crate::pipeline::Pipeline;
+use crate::quarantine::{Quarantine, QuarantineMode};
+use crate::schedule::Schedule;
+use crate::sha256::DigestEncoding;
+use crate::suppress::Suppression;
+use crate::systemd::Heartbeat;
+use crate::umi::{UmiCheck, UmiLocation, UmiPattern};
 
+mod archive;
 mod checker;
 mod checks;
+mod checksum_db;
+mod checksum_registry;
+mod config;
+mod consent;
+mod control;
+mod deadline;
+mod dry_run;
+mod duplicates;
+mod entity;
+mod external;
+mod findings;
+mod fixtures;
+mod hooks;
+mod lab_data;
+mod line_format;
+mod logging;
+mod manifest;
+mod md5_sidecar;
+mod merkle;
+mod mounts;
+mod options;
+mod path_constraints;
+mod pipeline;
 mod progress;
+mod quarantine;
+mod quota;
+mod read_names;
+mod report;
+mod rerun;
+mod samplesheet;
+mod scan;
+mod schedule;
+mod self_test;
 mod sha256;
+mod suppress;
+mod systemd;
+mod timing;
+mod umi;
+mod watch;
+mod zip;
 
-/// Checks integrity of sequencing files (FASTQ, BAM).
+/// Checks integrity of sequencing files (FASTQ, BAM, SAM, VCF, FASTA, BED, POD5, FAST5).
 ///
 /// Use --fastq-paired for paired-end FASTQ, --fastq-single for single-end FASTQ,
-/// --bam for BAM files, or --raw for only calculating checksums of any file.
-/// These flags can be used multiple times.
+/// --fastq-interleaved for interleaved paired-end FASTQ, --bam for BAM files,
+/// --ubam for unaligned BAM files, --sam for SAM files, --vcf for VCF/BCF files, --fasta for
+/// reference sequences, --bed for target regions, --tabix for tabix/CSI indexes, --gzi for
+/// indexes of bgzipped files, --pod5 and --fast5 for nanopore raw signal files, or --raw for
+/// only calculating checksums of any file.
+/// These flags can be used multiple times. FASTQ files may be uncompressed or compressed with
+/// gzip, bzip2, xz or zstd.
 ///
 /// By default, the tool will exit immediately after the first error is found.
 /// Use --continue-on-error to check all files regardless of errors.
 #[derive(Debug, clap::Parser)]
 #[command(author, version, about)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 #[command(group(
     ArgGroup::new("input_files")
         .required(true)
         .multiple(true)
 ))]
 struct Args {
-    /// Flag to show progress bars during processing.
-    #[arg(long, global = true)]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Flag to show progress bars during processing. Given without a value, progress bars are
+    /// shown. Defaults to `show_progress` of the `[options]` table of the config file, then to
+    /// GRZ_CHECK_SHOW_PROGRESS, then to showing them on a terminal.
+    #[arg(
+        long,
+        global = true,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        overrides_with = "no_progress"
+    )]
     show_progress: Option<bool>,
 
+    /// Hide progress bars, even if the config file or GRZ_CHECK_SHOW_PROGRESS enables them.
+    #[arg(long, global = true, overrides_with = "show_progress")]
+    no_progress: bool,
+
     /// A paired-end FASTQ sample. Provide FQ1, FQ2, and minimum mean read length.
     /// Read Length: >0 for fixed, <0 to skip length check.
     #[arg(
@@ -45,6 +137,20 @@ struct Args {
     )]
     fastq_paired: Vec<String>,
 
+    /// A paired-end FASTQ sample with a FASTQ file of index reads, e.g. of 10x or dual-index
+    /// runs. Provide FQ1, FQ2, the index reads (I1) and minimum mean read length, which does
+    /// not apply to the index reads. All three files must have the same number of records.
+    /// Read Length: >0 for fixed, <0 to skip length check.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        allow_hyphen_values = true,
+        num_args = 4,
+        value_names = ["FQ1_PATH", "FQ2_PATH", "I1_PATH", "MIN_MEAN_READ_LEN"],
+        group = "input_files"
+    )]
+    fastq_triple: Vec<String>,
+
     /// A single-end FASTQ sample. Provide the file path and minimum mean read length.
     /// Read Length: >0 for fixed, <0 to skip length check.
     #[arg(
@@ -57,6 +163,19 @@ struct Args {
     )]
     fastq_single: Vec<String>,
 
+    /// An interleaved paired-end FASTQ file, with the mates of each pair in consecutive
+    /// records. Provide the file path and minimum mean read length.
+    /// Read Length: >0 for fixed, <0 to skip length check.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        allow_hyphen_values = true,
+        num_args = 2,
+        value_names = ["FQ_PATH", "MIN_MEAN_READ_LEN"],
+        group = "input_files"
+    )]
+    fastq_interleaved: Vec<String>,
+
     /// A single BAM file to validate.
     #[arg(
         long,
@@ -67,6 +186,142 @@ struct Args {
     )]
     bam: Vec<PathBuf>,
 
+    /// Cross-validate the index next to each --bam file (FILE.bam.bai, FILE.bai or
+    /// FILE.bam.csi), if any: its number of reference sequences and its bins must match the BAM
+    /// header, it must not refer to offsets beyond the end of the file, and a sample of its
+    /// offsets must point to records within their bins.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    check_index: bool,
+
+    /// Validate the auxiliary fields (tags) of BAM and SAM records: types must be known and
+    /// array and hex values well-formed. Warns about tags as large as the reads, e.g. leftover
+    /// OQ or BI/BD tags of base recalibration that inflate the submission.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    validate_tags: bool,
+
+    /// FASTA file of the reference the --bam and --sam files were aligned to, optionally
+    /// compressed. The M5 checksums of their @SQ lines must match the sequences of the reference,
+    /// and @SQ lines without M5 tag must match their length. Sequences not in the reference are
+    /// errors.
+    #[arg(long, value_name = "FASTA_PATH")]
+    bam_reference: Option<PathBuf>,
+
+    /// A single unaligned BAM file to validate. In addition to the BAM checks, any record that
+    /// is mapped, has reference coordinates or carries CIGAR operations is an error.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["UBAM_PATH"],
+        group = "input_files"
+    )]
+    ubam: Vec<PathBuf>,
+
+    /// A single SAM file to validate, either uncompressed or gzipped. SAM files get the same
+    /// checks as BAM files.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["SAM_PATH"],
+        group = "input_files"
+    )]
+    sam: Vec<PathBuf>,
+
+    /// A single VCF or BCF file to validate. VCF files may be uncompressed or bgzipped.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["VCF_PATH"],
+        group = "input_files"
+    )]
+    vcf: Vec<PathBuf>,
+
+    /// A single gVCF file to validate. In addition to the VCF checks, the block structure is
+    /// validated: reference blocks (ALT <NON_REF> or <*>) need an END tag, and blocks must not
+    /// overlap. Block statistics are added to the report.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["GVCF_PATH"],
+        group = "input_files"
+    )]
+    gvcf: Vec<PathBuf>,
+
+    /// A single FASTA file to validate, either uncompressed or gzipped. If an index exists
+    /// next to it (FASTA_PATH.fai), it is cross-checked against the file.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["FASTA_PATH"],
+        group = "input_files"
+    )]
+    fasta: Vec<PathBuf>,
+
+    /// A single BED file to validate, either uncompressed or gzipped. Coordinates, column
+    /// counts and sort order are checked.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["BED_PATH"],
+        group = "input_files"
+    )]
+    bed: Vec<PathBuf>,
+
+    /// FASTA file or .fai index of the reference. If given, the chromosome names of --bed files
+    /// must be sequence names of the reference.
+    #[arg(long, value_name = "PATH")]
+    bed_reference: Option<PathBuf>,
+
+    /// A tabix (.tbi) or CSI (.csi) index to validate against its BGZF-compressed data file.
+    /// The sequence names of the index must match those of the data file, and the indexed
+    /// offsets of a sample of sequences must point to records of these sequences.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 2,
+        value_names = ["DATA_PATH", "INDEX_PATH"],
+        group = "input_files"
+    )]
+    tabix: Vec<PathBuf>,
+
+    /// A .gzi index to validate against its bgzipped data file, e.g. a FASTA or FASTQ file.
+    /// Every indexed offset pair must be the start of a BGZF block of the data file.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 2,
+        value_names = ["DATA_PATH", "INDEX_PATH"],
+        group = "input_files"
+    )]
+    gzi: Vec<PathBuf>,
+
+    /// A POD5 file of nanopore raw signal data. Its signature, footer and embedded tables are
+    /// validated and its reads are counted.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["FILE_PATH"],
+        group = "input_files"
+    )]
+    pod5: Vec<PathBuf>,
+
+    /// A FAST5 file of nanopore raw signal data. Its HDF5 superblock is validated, including
+    /// that the file is not truncated; the read groups are not.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["FILE_PATH"],
+        group = "input_files"
+    )]
+    fast5: Vec<PathBuf>,
+
     /// A file for which to only calculate the SHA256 checksum, skipping all other validation.
     #[arg(
         long,
@@ -77,28 +332,722 @@ struct Args {
     )]
     raw: Vec<PathBuf>,
 
-    /// Path to write the output JSONL report.
-    #[arg(long, required = true)]
-    output: PathBuf,
+    /// A directory to scan recursively for input files, which are checked according to their
+    /// extension. FASTQ files are checked without a read length check, as paired-end files if
+    /// their names only differ in the read marker (e.g. `_R1`/`_R2` or `_1`/`_2`) and as
+    /// single-end files otherwise; files of unknown type only get a checksum.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["DIR"],
+        group = "input_files"
+    )]
+    scan: Vec<PathBuf>,
+
+    /// Start checking the files found by --scan while the directories are still being walked,
+    /// instead of after the walk. Cannot be combined with options that need the complete list of
+    /// files up front.
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        requires = "scan",
+        conflicts_with_all = [
+            "emit_samplesheet",
+            "dry_run",
+            "quota",
+            "deadline",
+            "control_socket",
+            "emit_rerun_bundle",
+            "lab_datum",
+            "lab_datum_bam",
+            "expected_records",
+            "expected_bases",
+            "consent_scope",
+            "declared_read_length",
+        ]
+    )]
+    stream_scan: bool,
 
-    /// Continue processing all files even if an error is found.
+    /// An uncompressed tar archive or a zip archive whose members are checked according to their
+    /// extension, like with --scan, without extracting them. Single members can also be given to
+    /// any other input option as ARCHIVE.tar::MEMBER_PATH.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["ARCHIVE"],
+        group = "input_files"
+    )]
+    tar: Vec<PathBuf>,
+
+    /// An nf-core style samplesheet (`sample,fastq_1,fastq_2,...`), e.g. of an existing pipeline
+    /// run. Rows with `fastq_2` are checked as paired-end samples, all others as single-end
+    /// samples, without a read length check. Relative paths are resolved against the directory
+    /// of the samplesheet.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 1,
+        value_names = ["CSV_PATH"],
+        group = "input_files"
+    )]
+    samplesheet: Vec<PathBuf>,
+
+    /// Write the FASTQ files found by --scan and --tar as an nf-core style samplesheet to this
+    /// path before the checks run, to review how they were paired.
+    #[arg(long, value_name = "CSV_PATH")]
+    emit_samplesheet: Option<PathBuf>,
+
+    /// Report an error for every input file whose extension is not accepted in GRZ
+    /// submissions, e.g. stray backups or uncompressed FASTQ files. The error lists the accepted
+    /// extensions.
     #[arg(long, action = clap::ArgAction::SetTrue)]
+    strict_extensions: bool,
+
+    /// Verify the MD5 digest of every input file that has a sidecar FILE.md5 next to it, as
+    /// shipped by many sequencing providers. A mismatch is reported as an error.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    verify_md5: bool,
+
+    /// Read length declared in the metadata for a FASTQ file given via --fastq-paired,
+    /// --fastq-triple, --fastq-single or --fastq-interleaved. The modal read length of the file must match it within
+    /// --read-length-tolerance.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 2,
+        value_names = ["FQ_PATH", "READ_LEN"]
+    )]
+    declared_read_length: Vec<String>,
+
+    /// Number of records (reads) expected in a FASTQ file given via --fastq-paired,
+    /// --fastq-triple, --fastq-single or --fastq-interleaved, e.g. as declared in the metadata.
+    /// A different number is reported as an error, e.g. for files truncated at a gzip member
+    /// boundary.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 2,
+        value_names = ["FQ_PATH", "NUM_RECORDS"]
+    )]
+    expected_records: Vec<String>,
+
+    /// Number of bases (yield) expected in a FASTQ file, e.g. as declared in the lab manifest,
+    /// as a number or with a decimal unit like 12.5Gb. For inputs of --fastq-paired or
+    /// --fastq-triple, give the path of R1 and the yield of R1 and R2 together. A deviation above
+    /// --base-yield-tolerance is reported as an error, e.g. for partially transferred files.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 2,
+        value_names = ["FQ_PATH", "NUM_BASES"]
+    )]
+    expected_bases: Vec<String>,
+
+    /// Fraction by which the number of bases may deviate from --expected-bases, e.g. to allow
+    /// for yields rounded in the lab manifest.
+    #[arg(long, value_name = "FRACTION", default_value_t = fastq::DEFAULT_BASE_YIELD_TOLERANCE, requires = "expected_bases")]
+    base_yield_tolerance: f64,
+
+    /// Assigns the paired FASTQ file given via --fastq-paired or --fastq-triple with R1 FQ1_PATH
+    /// to the lab datum ID. A lab datum may consist of several pairs, e.g. from multiple
+    /// flowcells or lanes; its pairs are ordered as given. The total number of reads and bases of each lab datum are
+    /// reported in an entry with check type `lab_datum`.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 2,
+        value_names = ["ID", "FQ1_PATH"]
+    )]
+    lab_datum: Vec<String>,
+
+    /// Total number of reads declared in the metadata for a lab datum given via --lab-datum.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 2,
+        value_names = ["ID", "NUM_READS"]
+    )]
+    declared_reads: Vec<String>,
+
+    /// Total number of bases (yield) declared in the metadata for a lab datum given via
+    /// --lab-datum.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 2,
+        value_names = ["ID", "NUM_BASES"]
+    )]
+    declared_bases: Vec<String>,
+
+    /// Assigns the BAM file given via --bam or --ubam to the lab datum ID declared in the
+    /// metadata. Every read group of the file must map to one of its lab data by its ID, SM or
+    /// LB, and every lab datum of the file must have a read group. Read groups of lab data
+    /// declared for other files, via --lab-datum or --lab-datum-bam, are reported as such.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 2,
+        value_names = ["ID", "BAM_PATH"]
+    )]
+    lab_datum_bam: Vec<String>,
+
+    /// Maximum allowed difference in bases between the declared and the modal read length.
+    #[arg(long, default_value_t = 0)]
+    read_length_tolerance: usize,
+
+    /// Expected species of the reference genome. If given, the reference sequences in BAM
+    /// headers are checked for plausibility against it (contig naming, chromosome 1 length,
+    /// total genome length).
+    #[arg(long, value_enum)]
+    species: Option<Species>,
+
+    /// Path to write the output JSONL report. Required unless `--dry-run` is given.
+    #[arg(long, required_unless_present = "dry_run")]
+    output: Option<PathBuf>,
+
+    /// Storage quota of the submission, e.g. `500G` or `2TB`. If the files to check are larger
+    /// in total, the run fails before any file is read.
+    #[arg(long, value_name = "BYTES", value_parser = quota::parse_size)]
+    quota: Option<u64>,
+
+    /// Account the estimated decompressed size of compressed files against --quota instead of
+    /// their size on disk. The estimate is based on the compression ratio of the first 16 MiB.
+    #[arg(long, action = clap::ArgAction::SetTrue, requires = "quota")]
+    quota_decompressed: bool,
+
+    /// Print the jobs that would be run, with the size on disk and the estimated decompressed
+    /// size per check type, without checking any file. Decompressed sizes are read from BGZF
+    /// block metadata and gzip trailers where possible.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    dry_run: bool,
+
+    /// Report of an earlier run of the same files for --dry-run. Each job is listed with its
+    /// status and number of findings in that report and a runtime estimated from the throughput
+    /// recorded with --stats full, followed by the expected report size.
+    #[arg(long, value_name = "JSONL_PATH", requires = "dry_run")]
+    previous_report: Option<PathBuf>,
+
+    /// Continue processing all files even if an error is found. Defaults to
+    /// `continue_on_error` of the `[options]` table of the config file, then to
+    /// GRZ_CHECK_CONTINUE_ON_ERROR.
+    #[arg(long, action = clap::ArgAction::SetTrue, overrides_with = "no_continue_on_error")]
     continue_on_error: bool,
 
+    /// Stop at the first error, even if the config file or GRZ_CHECK_CONTINUE_ON_ERROR enables
+    /// --continue-on-error.
+    #[arg(long, overrides_with = "continue_on_error", conflicts_with = "assess")]
+    no_continue_on_error: bool,
+
+    /// Order in which files are checked. `balanced` starts the largest files first to avoid a
+    /// long tail, but runs at most half of the threads on huge files, so that small files keep
+    /// being reported while they are checked.
+    #[arg(long, value_enum, default_value_t = Schedule::Input)]
+    schedule: Schedule,
+
+    /// Preserve each file that fails validation below this directory, mirroring its path, with
+    /// the report entries of its job next to it as `<name>.report.jsonl`, so that it can be
+    /// inspected even after the lab re-uploaded over the original.
+    #[arg(long, value_name = "DIR")]
+    quarantine: Option<PathBuf>,
+
+    /// Whether --quarantine copies or hard-links failed files.
+    #[arg(long, value_enum, default_value_t = QuarantineMode::Copy, requires = "quarantine")]
+    quarantine_mode: QuarantineMode,
+
+    /// Overall wall-clock budget of the run, as a duration (e.g. 8h or 1h30m) or an RFC 3339
+    /// timestamp (e.g. 2025-06-01T22:00:00+02:00). Once it has passed, no further jobs are
+    /// started, running jobs are allowed to finish and the remaining jobs are reported as
+    /// NOT_CHECKED; the run then fails.
+    #[arg(long, value_name = "DURATION|TIMESTAMP", value_parser = deadline::parse_deadline)]
+    deadline: Option<DateTime<Utc>>,
+
+    /// Cancel the jobs still running this long after --deadline, e.g. 10m, and report them as
+    /// NOT_CHECKED as well.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = deadline::parse_duration,
+        requires = "deadline"
+    )]
+    deadline_grace: Option<Duration>,
+
+    /// Compute and report all statistics without failing on threshold violations, e.g. to see
+    /// whether data would pass before submitting it. Violations of minimum and declared read
+    /// lengths, declared lab datum totals, --expected-records, --max-n-fraction and --quota are
+    /// reported as warnings; structural problems are still errors. Implies --continue-on-error.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    assess: bool,
+
+    /// Severity of findings about FASTQ files whose quality scores appear to be Phred+64 or
+    /// Solexa encoded instead of Phred+33.
+    #[arg(long, value_enum, default_value_t = Severity::Error)]
+    quality_encoding_severity: Severity,
+
+    /// Severity of findings about FASTQ records with an empty sequence or quality string, as
+    /// emitted by some trimming pipelines and rejected by many aligners.
+    #[arg(long, value_enum, default_value_t = Severity::Error)]
+    empty_record_severity: Severity,
+
+    /// Severity of findings about primary BAM or SAM records without base quality scores
+    /// (QUAL `*`), e.g. from files written with `--omit-quals`.
+    #[arg(long, value_enum, default_value_t = Severity::Error)]
+    missing_quality_severity: Severity,
+
+    /// Only allow A, C, G, T and N in FASTQ sequences instead of all upper-case IUPAC nucleotide
+    /// codes.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    strict_bases: bool,
+
+    /// Require FASTQ read names in Casava 1.8+ format (`instrument:run:flowcell:lane:tile:x:y`,
+    /// optionally followed by a `read:filtered:control:index` comment), as needed to extract
+    /// the lane and flowcell later.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    strict_read_names: bool,
+
+    /// How recoverable FASTQ defects are reported: blank lines at the end of a file and a last
+    /// line without a newline are errors in strict mode and warnings in tolerant mode, as data
+    /// hubs interpret the format differently. Blank lines between records are always errors.
+    #[arg(long, value_enum, default_value_t = ParseLeniency::Strict)]
+    parse_leniency: ParseLeniency,
+
+    /// Encoding of the SHA-256 digests in the report: lower-case hex, URL-safe base64 without
+    /// padding, or padded base64 as expected by S3. The post-check hook has its own setting in
+    /// the config file.
+    #[arg(long, value_enum, default_value_t = DigestEncoding::Hex)]
+    report_digest_encoding: DigestEncoding,
+
+    /// Also report the SHA-256 digests of consecutive chunks of this size (e.g. 64M) of every
+    /// file, so that a partial re-upload can later be verified by re-reading only the replaced
+    /// byte ranges.
+    #[arg(long, value_name = "BYTES", value_parser = quota::parse_nonzero_size)]
+    chunk_size: Option<u64>,
+
+    /// Maximum fraction of N bases of a FASTQ file, e.g. 0.05. Files with more are reported as
+    /// an error, e.g. if failed sequencing cycles produced all-N tails.
+    #[arg(long, value_name = "FRACTION")]
+    max_n_fraction: Option<f64>,
+
+    /// Maximum fraction of records of a BAM or SAM file flagged as PCR or optical duplicates,
+    /// e.g. 0.3. Files with more are reported with a warning. The fraction is always reported.
+    #[arg(long, value_name = "FRACTION")]
+    max_duplicate_fraction: Option<f64>,
+
+    /// Screen FASTQ reads for adapter sequences and warn if more than --max-adapter-fraction of
+    /// the reads of a file contain one, e.g. for deliveries that were not adapter-trimmed. By
+    /// default, the Illumina TruSeq and Nextera adapters are screened for.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    adapter_screening: bool,
+
+    /// Check that the read names of each FASTQ file are unique, ignoring mate numbers (/1, /2).
+    /// Files too large for --duplicate-read-names-memory are checked in two passes over
+    /// temporary files.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    duplicate_read_names: bool,
+
+    /// Memory budget per file of --duplicate-read-names, e.g. 512M. Name hashes beyond it are
+    /// sorted and spilled to temporary files, which take 24 bytes per read.
+    #[arg(
+        long,
+        value_name = "BYTES",
+        value_parser = quota::parse_nonzero_size,
+        default_value = "1G",
+        requires = "duplicate_read_names"
+    )]
+    duplicate_read_names_memory: u64,
+
+    /// Also report a Merkle-tree digest of every file, over SHA-256 digests of 1 MiB leaves, for
+    /// later incremental verification of byte ranges.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    merkle: bool,
+
+    /// Also report the roots of the Merkle subtrees of this height, i.e. of 2^HEIGHT leaves each,
+    /// from left to right.
+    #[arg(long, value_name = "HEIGHT", requires = "merkle")]
+    merkle_subtree_height: Option<u32>,
+
+    /// An adapter to screen for instead of the defaults, as NAME=SEQUENCE. Reads containing the
+    /// first 12 bases of an adapter count as hits. Can be given multiple times.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        value_name = "NAME=SEQUENCE",
+        value_parser = fastq::parse_adapter,
+        requires = "adapter_screening"
+    )]
+    adapter: Vec<Adapter>,
+
+    /// Fraction of reads with an adapter above which --adapter-screening warns.
+    #[arg(long, value_name = "FRACTION", default_value_t = fastq::DEFAULT_MAX_ADAPTER_FRACTION, requires = "adapter_screening")]
+    max_adapter_fraction: f64,
+
+    /// Count FASTQ reads ending in a long G homopolymer and warn if more than
+    /// --max-poly-g-fraction of the reads of a file do, e.g. for NovaSeq runs with poly-G
+    /// artifacts of two-color chemistry.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    poly_g_screening: bool,
+
+    /// Number of trailing Gs from which --poly-g-screening counts a read as poly-G.
+    #[arg(long, value_name = "NUM", default_value_t = fastq::DEFAULT_POLY_G_LENGTH, requires = "poly_g_screening")]
+    poly_g_length: NonZeroUsize,
+
+    /// Fraction of poly-G reads above which --poly-g-screening warns.
+    #[arg(long, value_name = "FRACTION", default_value_t = fastq::DEFAULT_MAX_POLY_G_FRACTION, requires = "poly_g_screening")]
+    max_poly_g_fraction: f64,
+
+    /// Check that FASTQ reads carry a UMI matching this pattern, e.g. `[ACGTN]{8}`, and report
+    /// the fraction of reads with a valid UMI. The pattern is a regular expression that must
+    /// match the whole UMI.
+    #[arg(long, value_name = "PATTERN", value_parser = UmiPattern::parse)]
+    umi: Option<UmiPattern>,
+
+    /// Where the UMI of a read is found: the last colon-separated field of the read name, or the
+    /// index read (I1) of --fastq-triple.
+    #[arg(long, value_enum, default_value_t = UmiLocation::Name, requires = "umi")]
+    umi_location: UmiLocation,
+
+    /// Fraction of reads with a valid UMI below which --umi reports an error.
+    #[arg(long, value_name = "FRACTION", default_value_t = umi::DEFAULT_MIN_UMI_FRACTION, requires = "umi")]
+    min_umi_fraction: f64,
+
     /// Number of threads to use for processing.
     #[arg(long)]
     threads: Option<usize>,
+
+    /// How gzip and BGZF streams are inflated. `parallel-bgzf` inflates the blocks of BAM and
+    /// bgzip-compressed files on --inflate-threads worker threads per file, which helps when
+    /// fewer files than threads are checked. Builds with the `zlib-rs` feature inflate faster
+    /// with either backend.
+    #[arg(long, value_enum, default_value_t = InflateBackend::Flate2)]
+    inflate_backend: InflateBackend,
+
+    /// Number of worker threads per file of --inflate-backend parallel-bgzf.
+    #[arg(long, value_name = "NUM", default_value_t = common::DEFAULT_INFLATE_THREADS)]
+    inflate_threads: NonZeroUsize,
+
+    /// Level of detail of the statistics in the report. `full` adds a per-file breakdown of the
+    /// time spent reading, decompressing, hashing and checking.
+    #[arg(long, value_enum, default_value_t = StatsLevel::Basic)]
+    stats: StatsLevel,
+
+    /// Path of a Unix domain socket on which to serve a line-based control protocol while
+    /// checking. Supported commands: status, jobs, report, cancel.
+    #[arg(long, value_name = "PATH")]
+    control_socket: Option<PathBuf>,
+
+    /// Format of log messages on stderr. `json` emits one JSON object per event with level and
+    /// timestamp and suppresses progress bars.
+    #[arg(long, value_enum, default_value_t = LogFormat::Text, global = true)]
+    log_format: LogFormat,
+
+    /// Write a JSON file capturing the resolved jobs, configuration, tool version and
+    /// environment of this run, so that it can be reproduced later.
+    #[arg(long, value_name = "PATH")]
+    emit_rerun_bundle: Option<PathBuf>,
+
+    /// Suppress findings with a code matching CODE in files with a path matching GLOB, e.g.
+    /// `alignment.hard_clip:legacy/*.bam`. Both are glob patterns. Suppressed findings do not
+    /// affect the status of a file, but are listed under `suppressed_findings` in the report
+    /// and counted in a summary at the end of the run.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        value_name = "CODE:GLOB",
+        value_parser = suppress::parse_suppression
+    )]
+    suppress: Vec<Suppression>,
+
+    /// Path of an SQLite database of checksums. Files identical to a file recorded for a
+    /// different submission get a warning, and the checksums of all checked files are recorded
+    /// under --submission-id. The database is created if it does not exist.
+    #[arg(long, value_name = "PATH", requires = "submission_id")]
+    checksum_db: Option<PathBuf>,
+
+    /// Identifier of the checked submission, under which checksums are recorded in
+    /// --checksum-db.
+    #[arg(long, value_name = "ID")]
+    submission_id: Option<String>,
+
+    /// Consent scope (care or research) declared in the metadata for the donor whose files match
+    /// GLOB. Files of the `[[consent_category]]` tables of the config file that require a broader
+    /// scope are reported as errors in the run-level entry.
+    #[arg(
+        long,
+        action = clap::ArgAction::Append,
+        num_args = 3,
+        value_names = ["DONOR", "GLOB", "SCOPE"]
+    )]
+    consent_scope: Vec<String>,
+
+    /// Path of a TOML configuration file with suppressions, concurrency limits per mount, hooks,
+    /// external checks, consent categories, path constraints, a checksum registry and defaults of
+    /// flags. See the Configuration file section of the README for its tables. The `[options]`
+    /// table overrides the environment, but not the command line.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
 }
 
-fn create_jobs(
-    paired_raw: &[String],
-    single_raw: &[String],
-    bam_raw: &[PathBuf],
-    raw: &[PathBuf],
-) -> Result<(Vec<Job>, u64)> {
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Work with existing JSONL reports.
+    #[command(subcommand)]
+    Report(ReportCommand),
+    /// Verify files against a SHA256SUMS-style manifest, as written by `sha256sum`. All listed
+    /// files are hashed in parallel and each match, mismatch or missing file is written to a
+    /// JSONL report.
+    Verify {
+        /// Path of the manifest. Relative paths in it are resolved against its directory.
+        manifest: PathBuf,
+
+        /// Path to write the JSONL report to.
+        #[arg(long)]
+        output: PathBuf,
+
+        /// Number of threads to use for hashing.
+        #[arg(long)]
+        threads: Option<usize>,
+    },
+    /// Check small built-in fixtures, valid and with known defects, with the regular pipeline
+    /// and verify the expected findings, to confirm that an installation (e.g. a container or
+    /// an environment module) is functional before trusting it with a large run.
+    SelfTest,
+    /// Write a small synthetic submission (a FASTQ pair, an unaligned BAM file, a SHA256SUMS
+    /// manifest and a matching metadata.json) with the selected defects, e.g. to train lab
+    /// staff on interpreting reports. Prints the commands to check it.
+    GenerateFixtures {
+        /// Directory to write the submission to. It is created if missing.
+        dir: PathBuf,
+
+        /// Defect to introduce. Can be given multiple times; without it, all files are valid.
+        #[arg(long, value_enum)]
+        defect: Vec<Defect>,
+
+        /// Number of read pairs.
+        #[arg(long, default_value_t = fixtures::DEFAULT_NUM_READS)]
+        reads: usize,
+
+        /// Length of the reads.
+        #[arg(long, default_value_t = fixtures::DEFAULT_READ_LENGTH)]
+        read_length: usize,
+
+        /// Seed of the generated reads. The same seed yields the same files.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+    /// Run a declarative pre-submission pipeline with the stages stage_in, check, encrypt, move
+    /// and notify. See the documentation of the pipeline module for the file format.
+    Run {
+        /// Path of the pipeline TOML file.
+        pipeline: PathBuf,
+    },
+    /// Watch an inbox directory and check each submission directory moved into it, like
+    /// `--scan`, writing its report to `REPORTS/NAME.jsonl`. Submissions with a report are not
+    /// checked again. Runs until interrupted, e.g. as a systemd service.
+    Watch {
+        /// Directory that complete submissions are moved into.
+        inbox: PathBuf,
+
+        /// Directory to write the reports to. It is created if missing.
+        #[arg(long)]
+        reports: PathBuf,
+
+        /// Seconds between listings of the inbox.
+        #[arg(long, default_value_t = 30)]
+        interval: u64,
+
+        /// Number of threads to use for the checks.
+        #[arg(long)]
+        threads: Option<usize>,
+
+        /// Further arguments of the checks, e.g. `-- --strict-extensions --verify-md5`.
+        #[arg(last = true)]
+        check_args: Vec<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ReportCommand {
+    /// Validate a JSONL report against the known report schema versions, listing malformed
+    /// lines, unknown fields and fields of the wrong type.
+    Lint {
+        /// Path of the JSONL report to validate.
+        file: PathBuf,
+    },
+    /// Rewrite a JSONL report into a newer schema version, e.g. to keep archived reports in a
+    /// single consumable format. Error and warning messages are mapped to finding codes.
+    Upgrade {
+        /// Path of the JSONL report to upgrade.
+        file: PathBuf,
+
+        /// Schema version to upgrade to, e.g. `v2`. Defaults to the current version.
+        #[arg(long, value_name = "VERSION", value_parser = report::parse_schema_version)]
+        to: Option<u64>,
+
+        /// Path to write the upgraded report to. Defaults to stdout.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Write a rerun bundle with only the jobs of a bundle written by `--emit-rerun-bundle`
+    /// that failed in the report of its run, to repeat just those after fixing the files.
+    FailedJobs {
+        /// Path of the rerun bundle of the run.
+        bundle: PathBuf,
+
+        /// Path of the JSONL report of the run.
+        #[arg(long)]
+        report: PathBuf,
+
+        /// Path to write the bundle of the failed jobs to.
+        #[arg(long)]
+        output: PathBuf,
+    },
+}
+
+fn run_pipeline(path: &Path, heartbeat: &Heartbeat) -> Result<()> {
+    let pipeline = Pipeline::load(path)?;
+    let args = Args::try_parse_from(pipeline.check_args())
+        .with_context(|| format!("Invalid [check] arguments in {}", path.display()))?;
+    if args.command.is_some() {
+        anyhow::bail!(
+            "The [check] stage of {} must not run a subcommand",
+            path.display()
+        );
+    }
+    let report = args
+        .output
+        .clone()
+        .with_context(|| format!("The [check] stage of {} must set --output", path.display()))?;
+    let checked = run(args, pipeline.staging_hook(), heartbeat);
+    pipeline.finish(&report, checked)
+}
+
+fn run_watch(
+    inbox: &Path,
+    reports: &Path,
+    interval: Duration,
+    check_args: &[String],
+    heartbeat: &Heartbeat,
+) -> Result<()> {
+    let parse_check_args = |submission: &Path, report: &Path| {
+        let args = std::iter::once(env!("CARGO_PKG_NAME").into())
+            .chain(check_args.iter().map(Into::into))
+            .chain(["--scan".into(), submission.into()])
+            .chain(["--output".into(), report.into()]);
+        Args::try_parse_from::<_, std::ffi::OsString>(args)
+            .context("Invalid arguments of the checks")
+    };
+    // Reject unusable arguments up front rather than for each submission.
+    let args = parse_check_args(inbox, reports)?;
+    if args.command.is_some() || args.threads.is_some() || args.dry_run {
+        anyhow::bail!(
+            "The arguments of the checks must not run a subcommand, set --threads or --dry-run"
+        );
+    }
+    let shutdown_flag = checker::shutdown_flag()?;
+    let mut inbox = watch::Inbox::new(inbox, reports)?;
+    inbox.watch(interval, heartbeat, &shutdown_flag, |submission, report| {
+        run(parse_check_args(submission, report)?, None, heartbeat)
+    })
+}
+
+fn init_thread_pool(threads: Option<usize>) -> Result<()> {
+    if let Some(num_threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build_global()
+            .context("Failed to set up Rayon thread pool")?;
+    }
+    Ok(())
+}
+
+/// Input files given on the command line, in samplesheets or found by --scan, grouped by the
+/// check to apply.
+#[derive(Debug, Default)]
+struct Inputs {
+    /// `R1 R2 READ_LENGTH` of --fastq-paired.
+    fastq_paired: Vec<String>,
+    /// `R1 R2 I1 READ_LENGTH` of --fastq-triple.
+    fastq_triple: Vec<String>,
+    /// `PATH READ_LENGTH` of --fastq-single.
+    fastq_single: Vec<String>,
+    /// `PATH READ_LENGTH` of --fastq-interleaved.
+    fastq_interleaved: Vec<String>,
+    bam: Vec<PathBuf>,
+    ubam: Vec<PathBuf>,
+    sam: Vec<PathBuf>,
+    vcf: Vec<PathBuf>,
+    gvcf: Vec<PathBuf>,
+    fasta: Vec<PathBuf>,
+    bed: Vec<PathBuf>,
+    /// `DATA INDEX` of --tabix.
+    tabix: Vec<PathBuf>,
+    /// `DATA INDEX` of --gzi.
+    gzi: Vec<PathBuf>,
+    pod5: Vec<PathBuf>,
+    fast5: Vec<PathBuf>,
+    raw: Vec<PathBuf>,
+    /// `PATH LENGTH` of --declared-read-length.
+    declared_read_length: Vec<String>,
+}
+
+impl Inputs {
+    /// Adds the files found by --scan, whose FASTQ files skip the read length check.
+    fn add_scanned(&mut self, scanned: scan::ScannedFiles) -> Result<()> {
+        for pair in scanned.fastq_pairs {
+            self.fastq_paired.extend([
+                fastq_path_string(pair.fq1_path)?,
+                fastq_path_string(pair.fq2_path)?,
+                "-1".to_string(),
+            ]);
+        }
+        for path in scanned.fastq {
+            self.fastq_single
+                .extend([fastq_path_string(path)?, "-1".to_string()]);
+        }
+        self.bam.extend(scanned.bam);
+        self.sam.extend(scanned.sam);
+        self.vcf.extend(scanned.vcf);
+        self.fasta.extend(scanned.fasta);
+        self.bed.extend(scanned.bed);
+        self.pod5.extend(scanned.pod5);
+        self.fast5.extend(scanned.fast5);
+        self.raw.extend(scanned.raw);
+        Ok(())
+    }
+}
+
+/// Settings of the jobs that are not specific to a single input.
+#[derive(Debug)]
+struct JobOptions {
+    check_index: bool,
+    bed_reference: Option<PathBuf>,
+    read_length_tolerance: usize,
+    species: Option<Species>,
+}
+
+fn create_jobs(inputs: &Inputs, options: &JobOptions) -> Result<(Vec<Job>, u64)> {
     let mut jobs = Vec::new();
     let mut total_bytes: u64 = 0;
 
+    let mut declared_read_lengths = HashMap::new();
+    for chunk in inputs.declared_read_length.chunks_exact(2) {
+        let length: usize = chunk[1].parse().with_context(|| {
+            format!(
+                "Invalid declared read length '{}' for file '{}'",
+                &chunk[1], &chunk[0]
+            )
+        })?;
+        declared_read_lengths.insert(
+            PathBuf::from(&chunk[0]),
+            DeclaredReadLength {
+                length,
+                tolerance: options.read_length_tolerance,
+            },
+        );
+    }
+    let mut take_declared = |path: &PathBuf| declared_read_lengths.remove(path);
+
     let parse_len = |len_str: &str| -> Result<ReadLengthCheck> {
         let len_val: i64 = len_str
             .parse()
@@ -109,15 +1058,17 @@ fn create_jobs(
         })
     };
 
-    for chunk in paired_raw.chunks_exact(3) {
+    for chunk in inputs.fastq_paired.chunks_exact(3) {
         let fq1_path = PathBuf::from(&chunk[0]);
         let fq2_path = PathBuf::from(&chunk[1]);
         let length_check =
             parse_len(&chunk[2]).with_context(|| format!("Invalid read length '{}'", &chunk[2]))?;
-        let fq1_size = fs::metadata(&fq1_path)?.len();
-        let fq2_size = fs::metadata(&fq2_path)?.len();
+        let fq1_size = input_size(&fq1_path)?;
+        let fq2_size = input_size(&fq2_path)?;
         total_bytes += fq1_size + fq2_size;
         jobs.push(Job::PairedFastq(PairedFastqJob {
+            fq1_declared_read_length: take_declared(&fq1_path),
+            fq2_declared_read_length: take_declared(&fq2_path),
             fq1_path,
             fq2_path,
             length_check,
@@ -126,7 +1077,32 @@ fn create_jobs(
         }));
     }
 
-    for chunk in single_raw.chunks_exact(2) {
+    for chunk in inputs.fastq_triple.chunks_exact(4) {
+        let fq1_path = PathBuf::from(&chunk[0]);
+        let fq2_path = PathBuf::from(&chunk[1]);
+        let index_path = PathBuf::from(&chunk[2]);
+        let length_check =
+            parse_len(&chunk[3]).with_context(|| format!("Invalid read length '{}'", &chunk[3]))?;
+        let fq1_size = input_size(&fq1_path)?;
+        let fq2_size = input_size(&fq2_path)?;
+        let index_size = input_size(&index_path)?;
+        total_bytes += fq1_size + fq2_size + index_size;
+        jobs.push(Job::TripleFastq(TripleFastqJob {
+            pair: PairedFastqJob {
+                fq1_declared_read_length: take_declared(&fq1_path),
+                fq2_declared_read_length: take_declared(&fq2_path),
+                fq1_path,
+                fq2_path,
+                length_check,
+                fq1_size,
+                fq2_size,
+            },
+            index_path,
+            index_size,
+        }));
+    }
+
+    for chunk in inputs.fastq_single.chunks_exact(2) {
         let path = PathBuf::from(&chunk[0]);
         let length_check = parse_len(&chunk[1]).with_context(|| {
             format!(
@@ -134,58 +1110,820 @@ fn create_jobs(
                 &chunk[1], &chunk[0]
             )
         })?;
-        let size = fs::metadata(&path)?.len();
+        let size = input_size(&path)?;
         total_bytes += size;
         jobs.push(Job::SingleFastq(SingleFastqJob {
+            declared_read_length: take_declared(&path),
+            path,
+            length_check,
+            size,
+        }));
+    }
+
+    for chunk in inputs.fastq_interleaved.chunks_exact(2) {
+        let path = PathBuf::from(&chunk[0]);
+        let length_check = parse_len(&chunk[1]).with_context(|| {
+            format!(
+                "Invalid read length '{}' for file '{}'",
+                &chunk[1], &chunk[0]
+            )
+        })?;
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::InterleavedFastq(InterleavedFastqJob {
+            declared_read_length: take_declared(&path),
             path,
             length_check,
             size,
         }));
     }
 
-    for path_str in bam_raw {
+    if let Some(path) = declared_read_lengths.keys().next() {
+        anyhow::bail!(
+            "Read length declared for '{}', which is not given as a FASTQ input",
+            path.display()
+        );
+    }
+
+    for path_str in &inputs.bam {
+        let path = PathBuf::from(path_str);
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::Bam(BamCheckJob {
+            index_path: if options.check_index {
+                bam::sibling_index(&path)
+            } else {
+                None
+            },
+            path,
+            species: options.species,
+            unaligned: false,
+            size,
+        }));
+    }
+
+    for path_str in &inputs.ubam {
+        let path = PathBuf::from(path_str);
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::Bam(BamCheckJob {
+            path,
+            species: None,
+            unaligned: true,
+            index_path: None,
+            size,
+        }));
+    }
+
+    for path_str in &inputs.sam {
+        let path = PathBuf::from(path_str);
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::Sam(SamCheckJob {
+            path,
+            species: options.species,
+            size,
+        }));
+    }
+
+    for path_str in &inputs.vcf {
         let path = PathBuf::from(path_str);
-        let size = fs::metadata(&path)?.len();
+        let size = input_size(&path)?;
         total_bytes += size;
-        jobs.push(Job::Bam(BamCheckJob { path, size }));
+        jobs.push(Job::Vcf(VcfCheckJob {
+            path,
+            gvcf: false,
+            size,
+        }));
     }
 
-    for path_str in raw {
+    for path_str in &inputs.gvcf {
         let path = PathBuf::from(path_str);
-        let size = fs::metadata(&path)
-            .with_context(|| format!("Could not get metadata for {}", path.display()))?
-            .len();
+        let size = input_size(&path)?;
         total_bytes += size;
-        jobs.push(Job::Raw(RawJob { path, size }));
+        jobs.push(Job::Vcf(VcfCheckJob {
+            path,
+            gvcf: true,
+            size,
+        }));
+    }
+
+    for path_str in &inputs.fasta {
+        let path = PathBuf::from(path_str);
+        let size = input_size(&path)?;
+        total_bytes += size;
+        let mut index_path = path.clone().into_os_string();
+        index_path.push(".fai");
+        let index_path = PathBuf::from(index_path);
+        jobs.push(Job::Fasta(FastaCheckJob {
+            index_path: index_path.is_file().then_some(index_path),
+            path,
+            size,
+        }));
+    }
+
+    for path_str in &inputs.bed {
+        let path = PathBuf::from(path_str);
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::Bed(BedCheckJob {
+            path,
+            reference: options.bed_reference.clone(),
+            size,
+        }));
+    }
+
+    for chunk in inputs.tabix.chunks_exact(2) {
+        let data_path = chunk[0].clone();
+        let path = chunk[1].clone();
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::Tabix(TabixCheckJob {
+            path,
+            data_path,
+            size,
+        }));
+    }
+
+    for chunk in inputs.gzi.chunks_exact(2) {
+        let data_path = chunk[0].clone();
+        let path = chunk[1].clone();
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::Gzi(GziCheckJob {
+            path,
+            data_path,
+            size,
+        }));
+    }
+
+    for path_str in &inputs.pod5 {
+        let path = PathBuf::from(path_str);
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::Pod5(SignalJob { path, size }));
+    }
+
+    for path_str in &inputs.fast5 {
+        let path = PathBuf::from(path_str);
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::Fast5(SignalJob { path, size }));
+    }
+
+    for path_str in &inputs.raw {
+        let path = PathBuf::from(path_str);
+        let size = input_size(&path)?;
+        total_bytes += size;
+        jobs.push(Job::Raw(RawJob {
+            path,
+            size,
+            expected_sha256: None,
+        }));
     }
 
     Ok((jobs, total_bytes))
 }
 
+/// Size of an input file, or 0 if it does not exist. Its job is created regardless, so that it is
+/// reported as missing instead of failing the run.
+fn input_size(path: &Path) -> Result<u64> {
+    match archive::size(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        size => size.with_context(|| format!("Could not get metadata for {}", path.display())),
+    }
+}
+
+/// Groups the paired FASTQ jobs into lab data and attaches the declared totals.
+fn create_lab_data(
+    lab_datum_raw: &[String],
+    declared_reads_raw: &[String],
+    declared_bases_raw: &[String],
+    jobs: &[Job],
+) -> Result<Vec<LabDatum>> {
+    let mut lab_data: Vec<LabDatum> = Vec::new();
+    let mut assigned: HashMap<PathBuf, String> = HashMap::new();
+    for chunk in lab_datum_raw.chunks_exact(2) {
+        let id = &chunk[0];
+        let fq1 = PathBuf::from(&chunk[1]);
+        let fq2 = jobs
+            .iter()
+            .find_map(|job| match job {
+                Job::PairedFastq(job) | Job::TripleFastq(TripleFastqJob { pair: job, .. })
+                    if job.fq1_path == fq1 =>
+                {
+                    Some(job.fq2_path.clone())
+                }
+                _ => None,
+            })
+            .with_context(|| {
+                format!(
+                    "Lab datum '{id}' refers to '{}', which is not given as R1 of a --fastq-paired or --fastq-triple input",
+                    fq1.display()
+                )
+            })?;
+        if let Some(other) = assigned.insert(fq1.clone(), id.clone()) {
+            anyhow::bail!(
+                "FASTQ pair '{}' is assigned to both lab datum '{other}' and '{id}'",
+                fq1.display()
+            );
+        }
+
+        let pair = FastqPair { fq1, fq2 };
+        match lab_data.iter_mut().find(|lab_datum| &lab_datum.id == id) {
+            Some(lab_datum) => lab_datum.pairs.push(pair),
+            None => lab_data.push(LabDatum {
+                id: id.clone(),
+                pairs: vec![pair],
+                ..Default::default()
+            }),
+        }
+    }
+
+    for (declared_raw, what) in [(declared_reads_raw, "reads"), (declared_bases_raw, "bases")] {
+        for chunk in declared_raw.chunks_exact(2) {
+            let id = &chunk[0];
+            let count: u64 = chunk[1].parse().with_context(|| {
+                format!(
+                    "Invalid declared number of {what} '{}' for lab datum '{id}'",
+                    &chunk[1]
+                )
+            })?;
+            let lab_datum = lab_data
+                .iter_mut()
+                .find(|lab_datum| &lab_datum.id == id)
+                .with_context(|| {
+                    format!(
+                        "Number of {what} declared for '{id}', which is not given via --lab-datum"
+                    )
+                })?;
+            match what {
+                "reads" => lab_datum.declared_num_records = Some(count),
+                _ => lab_datum.declared_total_bases = Some(count),
+            }
+        }
+    }
+    Ok(lab_data)
+}
+
+/// Parses the lab data assigned to BAM files, which must be inputs of BAM jobs.
+fn create_lab_datum_bams(
+    lab_datum_bam_raw: &[String],
+    jobs: &[Job],
+) -> Result<BTreeMap<PathBuf, Vec<String>>> {
+    let mut lab_datum_bams: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for chunk in lab_datum_bam_raw.chunks_exact(2) {
+        let id = &chunk[0];
+        let path = PathBuf::from(&chunk[1]);
+        if !jobs
+            .iter()
+            .any(|job| matches!(job, Job::Bam(job) if job.path == path))
+        {
+            anyhow::bail!(
+                "Lab datum '{id}' refers to '{}', which is not given via --bam or --ubam",
+                path.display()
+            );
+        }
+        let ids = lab_datum_bams.entry(path).or_default();
+        if !ids.contains(id) {
+            ids.push(id.clone());
+        }
+    }
+    Ok(lab_datum_bams)
+}
+
+/// Parses the expected number of records of FASTQ files, which must be inputs of FASTQ jobs.
+fn create_expected_records(
+    expected_records_raw: &[String],
+    jobs: &[Job],
+) -> Result<BTreeMap<PathBuf, u64>> {
+    let mut expected_records = BTreeMap::new();
+    for chunk in expected_records_raw.chunks_exact(2) {
+        let path = PathBuf::from(&chunk[0]);
+        let count: u64 = chunk[1].parse().with_context(|| {
+            format!(
+                "Invalid expected number of records '{}' for file '{}'",
+                &chunk[1], &chunk[0]
+            )
+        })?;
+        if !jobs
+            .iter()
+            .any(|job| job.check_type() == "fastq" && job.paths().contains(&path))
+        {
+            anyhow::bail!(
+                "Number of records expected for '{}', which is not given as a FASTQ input",
+                path.display()
+            );
+        }
+        expected_records.insert(path, count);
+    }
+    Ok(expected_records)
+}
+
+/// Parses the expected number of bases of FASTQ files, which must be inputs of FASTQ jobs. Pairs
+/// are given by the path of R1.
+fn create_expected_bases(
+    expected_bases_raw: &[String],
+    jobs: &[Job],
+) -> Result<BTreeMap<PathBuf, u64>> {
+    let mut expected_bases = BTreeMap::new();
+    for chunk in expected_bases_raw.chunks_exact(2) {
+        let path = PathBuf::from(&chunk[0]);
+        let count = fastq::parse_base_count(&chunk[1])
+            .map_err(|e| anyhow::anyhow!("{e} Given for file '{}'.", &chunk[0]))?;
+        match jobs
+            .iter()
+            .find(|job| job.check_type() == "fastq" && job.paths().contains(&path))
+        {
+            Some(Job::PairedFastq(job) | Job::TripleFastq(TripleFastqJob { pair: job, .. }))
+                if job.fq2_path == path =>
+            {
+                anyhow::bail!(
+                    "Number of bases expected for '{}', which is R2 of a pair. Give the yield of the pair for R1 '{}'.",
+                    path.display(),
+                    job.fq1_path.display()
+                );
+            }
+            Some(Job::TripleFastq(job)) if job.index_path == path => {
+                anyhow::bail!(
+                    "Number of bases expected for '{}', which holds the index reads of a --fastq-triple input",
+                    path.display()
+                );
+            }
+            Some(_) => {}
+            None => anyhow::bail!(
+                "Number of bases expected for '{}', which is not given as a FASTQ input",
+                path.display()
+            ),
+        }
+        expected_bases.insert(path, count);
+    }
+    Ok(expected_bases)
+}
+
+/// Converts a FASTQ path found by --scan or in a samplesheet to an argument of the FASTQ
+/// input options.
+fn fastq_path_string(path: PathBuf) -> Result<String> {
+    path.into_os_string()
+        .into_string()
+        .map_err(|path| anyhow::anyhow!("FASTQ path {} is not valid UTF-8", path.display()))
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
+    logging::init(args.log_format);
 
+    let service = systemd::Service::start();
+    let result = run(args, None, service.heartbeat());
+    service.stop();
+    if let Err(e) = &result
+        && logging::format() == LogFormat::Json
+    {
+        logging::error(format!("{e:#}"));
+        std::process::exit(1);
+    }
+    result
+}
+
+/// Runs the checks given by `args`. `stage_in` is the staging hook of a pipeline, which takes the
+/// place of the `[staging]` hook of the config file. The workers beat `heartbeat`.
+fn run(args: Args, stage_in: Option<StagingHook>, heartbeat: &Heartbeat) -> Result<()> {
     let Args {
+        command,
         fastq_paired,
+        fastq_triple,
         fastq_single,
+        fastq_interleaved,
         bam,
+        check_index,
+        ubam,
+        sam,
+        vcf,
+        gvcf,
+        fasta,
+        bed,
+        bed_reference,
+        tabix,
+        gzi,
+        pod5,
+        fast5,
         raw,
+        scan: scan_dirs,
+        stream_scan,
+        tar: tar_archives,
+        samplesheet: samplesheets,
+        emit_samplesheet,
+        strict_extensions,
+        verify_md5,
+        declared_read_length,
+        expected_records,
+        expected_bases,
+        base_yield_tolerance,
+        lab_datum,
+        declared_reads,
+        declared_bases,
+        lab_datum_bam,
+        read_length_tolerance,
+        species,
         output,
+        quota,
+        quota_decompressed,
+        dry_run,
+        previous_report,
         threads,
+        inflate_backend,
+        inflate_threads,
         continue_on_error,
+        no_continue_on_error,
+        schedule,
+        quarantine,
+        quarantine_mode,
+        deadline,
+        deadline_grace,
+        assess,
+        quality_encoding_severity,
+        empty_record_severity,
+        missing_quality_severity,
+        strict_bases,
+        strict_read_names,
+        parse_leniency,
+        validate_tags,
+        bam_reference,
+        report_digest_encoding,
+        chunk_size,
+        adapter_screening,
+        adapter: adapters,
+        max_adapter_fraction,
+        poly_g_screening,
+        poly_g_length,
+        max_poly_g_fraction,
+        umi,
+        umi_location,
+        min_umi_fraction,
+        max_n_fraction,
+        max_duplicate_fraction,
+        duplicate_read_names,
+        duplicate_read_names_memory,
+        merkle,
+        merkle_subtree_height,
         show_progress,
+        no_progress,
+        stats,
+        control_socket,
+        log_format,
+        emit_rerun_bundle,
+        suppress: mut suppressions,
+        consent_scope,
+        config,
+        checksum_db,
+        submission_id,
     } = args;
 
-    if let Some(num_threads) = threads {
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build_global()
-            .context("Failed to set up Rayon thread pool")?;
+    let config = match config {
+        Some(path) => Config::load(&path)?,
+        None => Config::default(),
+    };
+
+    match command {
+        Some(Command::Report(ReportCommand::Lint { file })) => return report::lint(&file),
+        Some(Command::Report(ReportCommand::Upgrade { file, to, output })) => {
+            let target = to.unwrap_or(report::CURRENT_SCHEMA_VERSION);
+            return report::upgrade(&file, target, output.as_deref());
+        }
+        Some(Command::Report(ReportCommand::FailedJobs {
+            bundle,
+            report,
+            output,
+        })) => return rerun::write_failed_bundle(&bundle, &report, &output),
+        Some(Command::Verify {
+            manifest: manifest_path,
+            output,
+            threads,
+        }) => {
+            init_thread_pool(threads)?;
+            let (jobs, total_bytes) = manifest::create_jobs(&manifest_path)?;
+            let settings = options::resolve(
+                Settings::from_flags((false, false), show_progress, no_progress),
+                config.settings(),
+                Settings::from_env(std::env::var)?,
+            );
+            let options = RunOptions {
+                continue_on_error: true,
+                show_progress: settings.show_progress,
+                heartbeat: heartbeat.clone(),
+                ..Default::default()
+            };
+            return checker::run_check(jobs, total_bytes, &output, &options);
+        }
+        Some(Command::Run { pipeline }) => return run_pipeline(&pipeline, heartbeat),
+        Some(Command::Watch {
+            inbox,
+            reports,
+            interval,
+            threads,
+            check_args,
+        }) => {
+            init_thread_pool(threads)?;
+            return run_watch(
+                &inbox,
+                &reports,
+                Duration::from_secs(interval),
+                &check_args,
+                heartbeat,
+            );
+        }
+        Some(Command::SelfTest) => return self_test::run(),
+        Some(Command::GenerateFixtures {
+            dir,
+            defect,
+            reads,
+            read_length,
+            seed,
+        }) => {
+            let options = FixtureOptions {
+                num_reads: reads,
+                read_length,
+                seed,
+                defects: defect,
+            };
+            return fixtures::generate(&dir, &options);
+        }
+        None => {}
+    }
+
+    let settings = options::resolve(
+        Settings::from_flags(
+            (continue_on_error, no_continue_on_error),
+            show_progress,
+            no_progress,
+        ),
+        config.settings(),
+        Settings::from_env(std::env::var)?,
+    );
+    suppressions.extend(config.suppressions()?);
+    let mount_limits = config.mount_limits()?;
+    let staging_hook = match (stage_in, config.staging_hook()?) {
+        (Some(_), Some(_)) => {
+            anyhow::bail!(
+                "The [staging] hook of the config file conflicts with [stage_in] of the pipeline"
+            )
+        }
+        (stage_in, staging_hook) => stage_in.or(staging_hook),
+    };
+    let post_check_hook = config.post_check_hook()?;
+    let checksum_registry = config.checksum_registry()?;
+    let external_checks = config.external_checks()?;
+    let consent_categories = config.consent_categories()?;
+    let path_constraints = config.path_constraints()?;
+    let donor_consents = consent_scope
+        .chunks_exact(3)
+        .map(|chunk| consent::parse_donor_consent(&chunk[0], &chunk[1], &chunk[2]))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(anyhow::Error::msg)?;
+    if !donor_consents.is_empty() && consent_categories.is_empty() {
+        anyhow::bail!("--consent-scope requires [[consent_category]] tables in the config file");
     }
 
-    let (jobs, total_bytes) = create_jobs(&fastq_paired, &fastq_single, &bam, &raw)?;
+    init_thread_pool(threads)?;
+
+    let mut inputs = Inputs {
+        fastq_paired,
+        fastq_triple,
+        fastq_single,
+        fastq_interleaved,
+        bam,
+        ubam,
+        sam,
+        vcf,
+        gvcf,
+        fasta,
+        bed,
+        tabix,
+        gzi,
+        pod5,
+        fast5,
+        raw,
+        declared_read_length,
+    };
+    if emit_samplesheet.is_some() && scan_dirs.is_empty() && tar_archives.is_empty() {
+        anyhow::bail!("--emit-samplesheet requires --scan or --tar");
+    }
+    if !scan_dirs.is_empty() || !tar_archives.is_empty() {
+        let scanned = if stream_scan {
+            scan::scan_archives(&tar_archives)?
+        } else {
+            scan::scan(
+                &scan_dirs,
+                &tar_archives,
+                &mount_limits,
+                rayon::current_num_threads(),
+            )?
+        };
+        if let Some(path) = &emit_samplesheet {
+            let rows: Vec<samplesheet::Row> = scanned
+                .fastq_pairs
+                .iter()
+                .map(|pair| samplesheet::Row {
+                    sample: pair.sample.clone(),
+                    fastq_1: pair.fq1_path.clone(),
+                    fastq_2: Some(pair.fq2_path.clone()),
+                })
+                .chain(scanned.fastq.iter().map(|path| samplesheet::Row {
+                    sample: scan::fastq_stem(path),
+                    fastq_1: path.clone(),
+                    fastq_2: None,
+                }))
+                .collect();
+            samplesheet::write(path, &rows)?;
+            logging::info(format!(
+                "Wrote {} pair(s) and {} single FASTQ file(s) to samplesheet {}",
+                scanned.fastq_pairs.len(),
+                scanned.fastq.len(),
+                path.display()
+            ));
+        }
+        inputs.add_scanned(scanned)?;
+    }
 
-    checker::run_check(jobs, total_bytes, &output, continue_on_error, show_progress)?;
+    for samplesheet in &samplesheets {
+        let rows = samplesheet::read(samplesheet)?;
+        logging::info(format!(
+            "Read {} row(s) of {} sample(s) from samplesheet {}",
+            rows.len(),
+            rows.iter()
+                .map(|row| &row.sample)
+                .collect::<HashSet<_>>()
+                .len(),
+            samplesheet.display()
+        ));
+        for row in rows {
+            let fastq_1 = fastq_path_string(row.fastq_1)?;
+            match row.fastq_2 {
+                Some(fastq_2) => inputs.fastq_paired.extend([
+                    fastq_1,
+                    fastq_path_string(fastq_2)?,
+                    "-1".to_string(),
+                ]),
+                None => inputs.fastq_single.extend([fastq_1, "-1".to_string()]),
+            }
+        }
+    }
 
-    Ok(())
+    let job_options = JobOptions {
+        check_index,
+        bed_reference,
+        read_length_tolerance,
+        species,
+    };
+    let (jobs, total_bytes) = create_jobs(&inputs, &job_options)?;
+
+    let lab_data = create_lab_data(&lab_datum, &declared_reads, &declared_bases, &jobs)?;
+    let lab_datum_bams = create_lab_datum_bams(&lab_datum_bam, &jobs)?;
+    let expected_records = create_expected_records(&expected_records, &jobs)?;
+    let expected_bases = create_expected_bases(&expected_bases, &jobs)?;
+
+    if dry_run {
+        let mut stdout = std::io::stdout().lock();
+        dry_run::write_plan(&jobs, &mut stdout)?;
+        if let Some(previous_report) = previous_report {
+            let previous = dry_run::PreviousReport::read(&previous_report)?;
+            dry_run::write_estimates(&jobs, &previous, &mut stdout)?;
+        }
+        return Ok(());
+    }
+    let output = output.context("--output is required")?;
+
+    if let Some(quota) = quota
+        && let Err(e) = quota::check(&jobs, total_bytes, quota, quota_decompressed)
+    {
+        if !assess {
+            return Err(e);
+        }
+        logging::warn(format!("{e:#}"));
+    }
+
+    let options = RunOptions {
+        continue_on_error: settings.continue_on_error || assess,
+        schedule,
+        deadline,
+        deadline_grace,
+        show_progress: settings.show_progress,
+        stats,
+        control_socket,
+        suppressions,
+        strict_extensions,
+        verify_md5,
+        checksum_db,
+        submission_id,
+        lab_data,
+        lab_datum_bams,
+        expected_records,
+        expected_bases,
+        base_yield_tolerance,
+        assess,
+        quality_encoding_severity,
+        empty_record_severity,
+        missing_quality_severity,
+        strict_bases,
+        strict_read_names,
+        parse_leniency,
+        validate_tags,
+        bam_reference,
+        report_digest_encoding,
+        chunk_size,
+        max_n_fraction,
+        max_duplicate_fraction,
+        adapter_screening: adapter_screening.then(|| AdapterScreening {
+            adapters: if adapters.is_empty() {
+                fastq::DEFAULT_ADAPTERS
+                    .iter()
+                    .map(|&(name, sequence)| Adapter {
+                        name: name.to_string(),
+                        sequence: sequence.to_string(),
+                    })
+                    .collect()
+            } else {
+                adapters
+            },
+            max_fraction: max_adapter_fraction,
+        }),
+        poly_g_screening: poly_g_screening.then_some(PolyGScreening {
+            min_length: poly_g_length,
+            max_fraction: max_poly_g_fraction,
+        }),
+        umi: umi.map(|pattern| UmiCheck {
+            location: umi_location,
+            pattern,
+            min_fraction: min_umi_fraction,
+        }),
+        duplicate_names_memory: duplicate_read_names.then_some(duplicate_read_names_memory),
+        merkle: merkle.then_some(merkle_subtree_height),
+        mount_limits,
+        staging_hook,
+        post_check_hook,
+        checksum_registry,
+        quarantine: quarantine.map(|dir| Quarantine {
+            dir,
+            mode: quarantine_mode,
+        }),
+        inflate_backend,
+        inflate_threads: inflate_threads.get(),
+        external_checks,
+        path_constraints,
+        donor_consents,
+        consent_categories,
+        heartbeat: heartbeat.clone(),
+    };
+
+    if let Some(bundle_path) = emit_rerun_bundle {
+        rerun::write_rerun_bundle(
+            &bundle_path,
+            &jobs,
+            total_bytes,
+            &output,
+            threads,
+            log_format,
+            &options,
+        )?;
+    }
+
+    if !stream_scan {
+        return checker::run_check(jobs, total_bytes, &output, &options);
+    }
+    let (sender, discovered) = mpsc::channel();
+    let (walked, checked) = std::thread::scope(|scope| {
+        let (scan_dirs, mount_limits) = (&scan_dirs, &options.mount_limits);
+        let job_options = &job_options;
+        let walker = scope.spawn(move || {
+            let on_files = |scanned: scan::ScannedFiles| {
+                let jobs = scanned_jobs(scanned, job_options)?;
+                // Sending only fails once the checks have stopped early.
+                jobs.into_iter()
+                    .for_each(|job| sender.send(job).unwrap_or(()));
+                Ok(())
+            };
+            // The sender is dropped once the walk has finished, which ends the checks. A batch
+            // whose jobs cannot be created stops the walk.
+            scan::walk(
+                scan_dirs,
+                mount_limits,
+                rayon::current_num_threads(),
+                &on_files,
+            )
+        });
+        let checked =
+            checker::run_check_streaming(jobs, total_bytes, discovered, &output, &options);
+        (walker.join().expect("Directory scan panicked"), checked)
+    });
+    walked?;
+    checked
+}
+
+/// Creates the jobs of files found by a streaming --scan, like they would be created if the
+/// files had been collected before the checks.
+fn scanned_jobs(scanned: scan::ScannedFiles, job_options: &JobOptions) -> Result<Vec<Job>> {
+    let mut inputs = Inputs::default();
+    inputs.add_scanned(scanned)?;
+    let (jobs, _) = create_jobs(&inputs, job_options)?;
+    Ok(jobs)
 }
@@ -0,0 +1,112 @@
+//! `SHA256SUMS`-style checksum manifests, as written by `sha256sum`, for the `verify` subcommand.
+//!
+//! Each line holds a hex-encoded SHA-256 digest, a space, a mode character (space for text,
+//! `*` for binary) and the path of the file. Relative paths are resolved against the directory
+//! of the manifest.
+
+use crate::archive;
+use crate::checker::Job;
+use crate::checks::raw::RawJob;
+use anyhow::Context;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A file listed in a manifest with its expected digest.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+/// Parses a single manifest line. Returns `None` for lines not of the form `DIGEST  PATH`.
+fn parse_line(line: &str, base: &Path) -> Option<Entry> {
+    let (digest, rest) = line.split_at_checked(64)?;
+    if !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let path = rest
+        .strip_prefix("  ")
+        .or_else(|| rest.strip_prefix(" *"))?;
+    if path.is_empty() {
+        return None;
+    }
+    Some(Entry {
+        path: base.join(path),
+        sha256: digest.to_ascii_lowercase(),
+    })
+}
+
+/// Reads all entries of a manifest. Empty lines and lines starting with `#` are skipped.
+pub fn read(path: &Path) -> anyhow::Result<Vec<Entry>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read manifest {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            parse_line(line.trim_end_matches('\r'), base).with_context(|| {
+                format!(
+                    "Invalid line {} in manifest {}: expected a SHA-256 digest followed by two spaces and a path",
+                    i + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect()
+}
+
+/// Creates a raw check job for every entry of a manifest. Files that do not exist are still
+/// included, so that they are reported as missing.
+pub fn create_jobs(path: &Path) -> anyhow::Result<(Vec<Job>, u64)> {
+    let mut total_bytes = 0;
+    let jobs = read(path)?
+        .into_iter()
+        .map(|entry| {
+            let size = archive::size(&entry.path).unwrap_or(0);
+            total_bytes += size;
+            Job::Raw(RawJob {
+                path: entry.path,
+                size,
+                expected_sha256: Some(entry.sha256),
+            })
+        })
+        .collect();
+    Ok((jobs, total_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_manifest() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let manifest = dir.path().join("SHA256SUMS");
+        let digest = "AB".repeat(32);
+        fs::write(
+            &manifest,
+            format!("# comment\n{digest}  reads.fastq.gz\n\n{digest} */data/calls.vcf\r\n"),
+        )?;
+        assert_eq!(
+            read(&manifest)?,
+            vec![
+                Entry {
+                    path: dir.path().join("reads.fastq.gz"),
+                    sha256: "ab".repeat(32),
+                },
+                Entry {
+                    path: PathBuf::from("/data/calls.vcf"),
+                    sha256: "ab".repeat(32),
+                },
+            ]
+        );
+
+        fs::write(&manifest, format!("{digest}\treads.fastq.gz\n"))?;
+        let error = read(&manifest).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid line 1"), "{error}");
+        Ok(())
+    }
+}
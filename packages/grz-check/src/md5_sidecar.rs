@@ -0,0 +1,49 @@
+//! Verification of the MD5 sidecar (`FILE.md5`) shipped next to an input file, enabled via
+//! `--verify-md5`.
+
+use crate::archive;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Path of the sidecar of `path`, which may also be a member of an archive.
+fn sidecar_path(path: &Path) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".md5");
+    PathBuf::from(sidecar)
+}
+
+/// Whether `path` has a sidecar, so that its MD5 digest needs to be computed for verification.
+pub fn exists(path: &Path) -> bool {
+    archive::size(&sidecar_path(path)).is_ok()
+}
+
+/// Reads the expected digest from a sidecar in `md5sum` format, i.e. the hex digest optionally
+/// followed by the file name.
+fn read_expected(sidecar: &Path) -> Result<String, String> {
+    let mut line = String::new();
+    BufReader::new(archive::open(sidecar).map_err(|e| e.to_string())?)
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    let digest = line.split_whitespace().next().unwrap_or_default();
+    if digest.len() == 32 && digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+        Ok(digest.to_ascii_lowercase())
+    } else {
+        Err(format!("'{}' is not an MD5 digest", line.trim_end()))
+    }
+}
+
+/// Compares the MD5 digest of `path` against its sidecar, returning an error on mismatch.
+pub fn verify(path: &Path, md5: &str) -> Option<String> {
+    let sidecar = sidecar_path(path);
+    match read_expected(&sidecar) {
+        Ok(expected) if expected == md5 => None,
+        Ok(expected) => Some(format!(
+            "MD5 digest {md5} does not match the digest {expected} given in {}.",
+            sidecar.display()
+        )),
+        Err(e) => Some(format!(
+            "Failed to read MD5 sidecar {}: {e}",
+            sidecar.display()
+        )),
+    }
+}
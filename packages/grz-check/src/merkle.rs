@@ -0,0 +1,182 @@
+//! Merkle-tree digests of files, enabled via `--merkle`, for incremental verification and proofs
+//! that a byte range belongs to a file without reading all of it.
+//!
+//! Like BLAKE3, the tree is built over fixed-size leaves and is left-balanced: the left subtree
+//! of every node holds the largest power of two of leaves that is smaller than the number of
+//! leaves below the node. Nodes are hashed with SHA-256, with a domain-separating prefix for
+//! leaves (`0x00`) and inner nodes (`0x01`), as in RFC 6962. Empty files consist of one empty
+//! leaf.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Number of bytes of a file covered by a leaf.
+pub const LEAF_SIZE: u64 = 1 << 20;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+type Hash = [u8; 32];
+
+/// Hex-encoded Merkle root of a file, with the roots of its subtrees of a given height from left
+/// to right. The last subtree may have fewer leaves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MerkleDigests {
+    pub leaf_size: u64,
+    pub root: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtree_height: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subtrees: Vec<String>,
+}
+
+fn new_leaf() -> Sha256 {
+    let mut leaf = Sha256::new();
+    leaf.update([LEAF_PREFIX]);
+    leaf
+}
+
+fn parent(left: &Hash, right: &Hash) -> Hash {
+    let mut node = Sha256::new();
+    node.update([NODE_PREFIX]);
+    node.update(left);
+    node.update(right);
+    node.finalize().into()
+}
+
+fn hex(hash: &Hash) -> String {
+    hash.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub struct MerkleHasher {
+    leaf_size: u64,
+    leaf: Sha256,
+    /// Number of bytes hashed of the current leaf.
+    filled: u64,
+    num_leaves: u64,
+    /// Roots of the complete subtrees not yet merged, from left to right, with their height.
+    /// Heights are strictly decreasing.
+    stack: Vec<(u32, Hash)>,
+    subtree_height: Option<u32>,
+    subtrees: Vec<String>,
+}
+
+impl MerkleHasher {
+    pub fn new(leaf_size: u64, subtree_height: Option<u32>) -> Self {
+        Self {
+            leaf_size,
+            leaf: new_leaf(),
+            filled: 0,
+            num_leaves: 0,
+            stack: Vec::new(),
+            subtree_height,
+            subtrees: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let remaining = self.leaf_size - self.filled;
+            let len = usize::try_from(remaining).map_or(data.len(), |r| r.min(data.len()));
+            self.leaf.update(&data[..len]);
+            self.filled += len as u64;
+            data = &data[len..];
+            if self.filled == self.leaf_size {
+                self.finish_leaf();
+            }
+        }
+    }
+
+    fn finish_leaf(&mut self) {
+        let leaf = std::mem::replace(&mut self.leaf, new_leaf());
+        self.filled = 0;
+        self.num_leaves += 1;
+        let mut node = (0, leaf.finalize().into());
+        self.record(node);
+        // merge complete subtrees of equal height, as in a binary counter
+        while let Some(&(height, left)) = self.stack.last()
+            && height == node.0
+        {
+            self.stack.pop();
+            node = (height + 1, parent(&left, &node.1));
+            self.record(node);
+        }
+        self.stack.push(node);
+    }
+
+    fn record(&mut self, (height, hash): (u32, Hash)) {
+        if self.subtree_height == Some(height) {
+            self.subtrees.push(hex(&hash));
+        }
+    }
+
+    pub fn finalize(mut self) -> MerkleDigests {
+        if self.filled > 0 || self.num_leaves == 0 {
+            self.finish_leaf();
+        }
+        // Merging from the right yields the left-balanced tree. Subtrees lower than the
+        // requested height at the right end form the last, incomplete subtree.
+        let mut root: Option<Hash> = None;
+        let mut incomplete_recorded = false;
+        while let Some((height, left)) = self.stack.pop() {
+            if !incomplete_recorded && self.subtree_height.is_some_and(|h| height >= h) {
+                if let Some(incomplete) = root {
+                    self.subtrees.push(hex(&incomplete));
+                }
+                incomplete_recorded = true;
+            }
+            root = Some(match root {
+                None => left,
+                Some(right) => parent(&left, &right),
+            });
+        }
+        let root = root.expect("the tree has at least one leaf");
+        if self.subtree_height.is_some() && !incomplete_recorded {
+            self.subtrees.push(hex(&root));
+        }
+        MerkleDigests {
+            leaf_size: self.leaf_size,
+            root: hex(&root),
+            subtree_height: self.subtree_height,
+            subtrees: self.subtrees,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(data: &[u8]) -> Hash {
+        let mut leaf = new_leaf();
+        leaf.update(data);
+        leaf.finalize().into()
+    }
+
+    #[test]
+    fn test_left_balanced_tree() {
+        let (a, b, c) = (leaf(b"abcd"), leaf(b"efgh"), leaf(b"ij"));
+
+        let mut hasher = MerkleHasher::new(4, Some(1));
+        hasher.update(b"abc");
+        hasher.update(b"defghij");
+        let digests = hasher.finalize();
+        assert_eq!(digests.root, hex(&parent(&parent(&a, &b), &c)));
+        assert_eq!(digests.subtrees, vec![hex(&parent(&a, &b)), hex(&c)]);
+
+        let mut hasher = MerkleHasher::new(4, Some(0));
+        hasher.update(b"abcdefghij");
+        assert_eq!(hasher.finalize().subtrees, vec![hex(&a), hex(&b), hex(&c)]);
+
+        let mut hasher = MerkleHasher::new(4, Some(2));
+        hasher.update(b"abcdefghij");
+        assert_eq!(
+            hasher.finalize().subtrees,
+            vec![hex(&parent(&parent(&a, &b), &c))]
+        );
+
+        let empty = MerkleHasher::new(4, None).finalize();
+        assert_eq!(empty.root, hex(&leaf(b"")));
+        assert!(empty.subtrees.is_empty());
+    }
+}
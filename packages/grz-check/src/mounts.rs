@@ -0,0 +1,152 @@
+//! Limits on the number of files checked concurrently per storage mount, configured via
+//! `[[mount]]` entries of the config file.
+//!
+//! A job touching a mount whose limit is reached waits until another job on that mount has
+//! finished, so slow storage tiers are not overloaded while fast ones use all threads.
+
+use serde::Serialize;
+use std::path::{self, Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MountLimit {
+    /// Path prefix of the mount, e.g. `/mnt/tape`.
+    pub path: PathBuf,
+    /// Maximum number of files below `path` that are checked at the same time.
+    pub concurrency: usize,
+}
+
+#[derive(Debug)]
+struct Slots {
+    limit: usize,
+    in_use: Mutex<usize>,
+    released: Condvar,
+}
+
+/// Slots of all mounts with a concurrency limit.
+#[derive(Debug, Default)]
+pub struct Mounts {
+    mounts: Vec<(PathBuf, Slots)>,
+}
+
+impl Mounts {
+    pub fn new(limits: &[MountLimit]) -> Self {
+        Self {
+            mounts: limits
+                .iter()
+                .map(|limit| {
+                    let slots = Slots {
+                        limit: limit.concurrency,
+                        in_use: Mutex::new(0),
+                        released: Condvar::new(),
+                    };
+                    (absolute(&limit.path), slots)
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the index of the most specific mount containing `path`.
+    fn mount_of(&self, path: &Path) -> Option<usize> {
+        let path = absolute(path);
+        self.mounts
+            .iter()
+            .enumerate()
+            .filter(|(_, (prefix, _))| path.starts_with(prefix))
+            .max_by_key(|(_, (prefix, _))| prefix.components().count())
+            .map(|(i, _)| i)
+    }
+
+    /// Waits for a slot on every mount touched by `paths`.
+    ///
+    /// Slots are taken in a fixed order, so that jobs spanning several mounts cannot deadlock.
+    pub fn acquire(&self, paths: &[PathBuf]) -> Permit<'_> {
+        let mut indices: Vec<usize> = paths.iter().filter_map(|p| self.mount_of(p)).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        for &i in &indices {
+            let slots = &self.mounts[i].1;
+            let mut in_use = slots
+                .released
+                .wait_while(slots.in_use.lock().unwrap(), |in_use| {
+                    *in_use >= slots.limit
+                })
+                .unwrap();
+            *in_use += 1;
+        }
+        Permit {
+            mounts: self,
+            indices,
+        }
+    }
+}
+
+/// Slots taken by a job, which are released when it is dropped.
+pub struct Permit<'a> {
+    mounts: &'a Mounts,
+    indices: Vec<usize>,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        for &i in &self.indices {
+            let slots = &self.mounts.mounts[i].1;
+            *slots.in_use.lock().unwrap() -= 1;
+            slots.released.notify_one();
+        }
+    }
+}
+
+/// Makes relative paths absolute without resolving symbolic links, which would defeat matching
+/// mount prefixes.
+fn absolute(path: &Path) -> PathBuf {
+    path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_concurrency_is_limited_per_mount() {
+        let mounts = Mounts::new(&[
+            MountLimit {
+                path: PathBuf::from("/mnt/tape"),
+                concurrency: 1,
+            },
+            MountLimit {
+                path: PathBuf::from("/mnt/tape/staging"),
+                concurrency: 2,
+            },
+        ]);
+        assert_eq!(mounts.mount_of(Path::new("/mnt/tape/a.bam")), Some(0));
+        assert_eq!(
+            mounts.mount_of(Path::new("/mnt/tape/staging/a.bam")),
+            Some(1)
+        );
+        assert_eq!(mounts.mount_of(Path::new("/mnt/tapes/a.bam")), None);
+
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for i in 0..4 {
+                let (mounts, running, max_running) = (&mounts, &running, &max_running);
+                scope.spawn(move || {
+                    let paths = [
+                        PathBuf::from(format!("/mnt/tape/{i}_R1.fastq.gz")),
+                        PathBuf::from(format!("/mnt/tape/{i}_R2.fastq.gz")),
+                    ];
+                    let _permit = mounts.acquire(&paths);
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(max_running.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,218 @@
+//! Resolution of the settings that can be given on the command line, in the `[options]` table of
+//! the config file and via environment variables.
+//!
+//! The command line takes precedence over the config file, which takes precedence over the
+//! environment. A negated flag, e.g. `--no-continue-on-error`, therefore disables a setting that
+//! is enabled by the config file or the environment, and of a flag given together with its
+//! negation the last one wins.
+
+use anyhow::bail;
+use serde::Deserialize;
+
+pub const CONTINUE_ON_ERROR_ENV: &str = "GRZ_CHECK_CONTINUE_ON_ERROR";
+pub const SHOW_PROGRESS_ENV: &str = "GRZ_CHECK_SHOW_PROGRESS";
+
+/// Settings given by one source, `None` where the source leaves them unset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub continue_on_error: Option<bool>,
+    pub show_progress: Option<bool>,
+}
+
+impl Settings {
+    /// Settings of the command line, given a flag and its negation for each of them.
+    pub fn from_flags(
+        (continue_on_error, no_continue_on_error): (bool, bool),
+        show_progress: Option<bool>,
+        no_progress: bool,
+    ) -> Self {
+        Settings {
+            continue_on_error: flag(continue_on_error, no_continue_on_error),
+            show_progress: if no_progress {
+                Some(false)
+            } else {
+                show_progress
+            },
+        }
+    }
+
+    /// Settings of the environment, looked up with `var`, e.g. [`std::env::var`].
+    pub fn from_env<E>(var: impl Fn(&'static str) -> Result<String, E>) -> anyhow::Result<Self> {
+        let parse = |name: &'static str| {
+            var(name)
+                .ok()
+                .map(|value| parse_bool(name, &value))
+                .transpose()
+        };
+        Ok(Settings {
+            continue_on_error: parse(CONTINUE_ON_ERROR_ENV)?,
+            show_progress: parse(SHOW_PROGRESS_ENV)?,
+        })
+    }
+
+    /// Fills the settings left unset with those of a source of lower precedence.
+    pub fn or(self, lower: Settings) -> Self {
+        Settings {
+            continue_on_error: self.continue_on_error.or(lower.continue_on_error),
+            show_progress: self.show_progress.or(lower.show_progress),
+        }
+    }
+}
+
+/// Settings of a run after applying the precedence of their sources and the defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolved {
+    pub continue_on_error: bool,
+    /// `None` leaves the progress bars to the terminal detection.
+    pub show_progress: Option<bool>,
+}
+
+pub fn resolve(cli: Settings, config: Settings, env: Settings) -> Resolved {
+    let settings = cli.or(config).or(env);
+    Resolved {
+        continue_on_error: settings.continue_on_error.unwrap_or(false),
+        show_progress: settings.show_progress,
+    }
+}
+
+/// A boolean flag given as `--flag`, `--no-flag` or neither. clap clears the flag that was
+/// overridden by the other, so at most one of them is set.
+fn flag(enabled: bool, disabled: bool) -> Option<bool> {
+    match (enabled, disabled) {
+        (true, _) => Some(true),
+        (false, true) => Some(false),
+        (false, false) => None,
+    }
+}
+
+fn parse_bool(name: &str, value: &str) -> anyhow::Result<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" | "" => Ok(false),
+        _ => bail!("Invalid value '{value}' of {name}; expected true or false"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> anyhow::Result<Settings> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        Settings::from_env(|name| vars.get(name).map(|value| value.to_string()).ok_or(()))
+    }
+
+    #[test]
+    fn test_command_line_over_config_over_environment() -> anyhow::Result<()> {
+        let env = env(&[(CONTINUE_ON_ERROR_ENV, "1"), (SHOW_PROGRESS_ENV, "true")])?;
+        let config = Settings {
+            continue_on_error: None,
+            show_progress: Some(false),
+        };
+
+        let resolved = resolve(Settings::default(), config, env);
+        assert_eq!(
+            resolved,
+            Resolved {
+                continue_on_error: true,
+                show_progress: Some(false)
+            }
+        );
+
+        // Negated flags override the config file and the environment.
+        let cli = Settings::from_flags((false, true), None, true);
+        assert_eq!(
+            resolve(cli, config, env),
+            Resolved {
+                continue_on_error: false,
+                show_progress: Some(false)
+            }
+        );
+
+        let cli = Settings::from_flags((true, false), Some(true), false);
+        assert_eq!(
+            resolve(cli, config, env),
+            Resolved {
+                continue_on_error: true,
+                show_progress: Some(true)
+            }
+        );
+
+        assert_eq!(
+            resolve(
+                Settings::default(),
+                Settings::default(),
+                Settings::default()
+            ),
+            Resolved {
+                continue_on_error: false,
+                show_progress: None
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_of_flag_and_negation_wins() {
+        use clap::Parser;
+
+        let parse = |flags: &[&str]| {
+            let args = ["grz-check", "--output", "report.jsonl", "--raw", "a.bin"];
+            let args = crate::Args::try_parse_from(args.iter().chain(flags)).unwrap();
+            Settings::from_flags(
+                (args.continue_on_error, args.no_continue_on_error),
+                args.show_progress,
+                args.no_progress,
+            )
+        };
+        assert_eq!(parse(&[]), Settings::default());
+        assert_eq!(
+            parse(&[
+                "--continue-on-error",
+                "--no-continue-on-error",
+                "--show-progress"
+            ]),
+            Settings {
+                continue_on_error: Some(false),
+                show_progress: Some(true)
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "--no-continue-on-error",
+                "--continue-on-error",
+                "--show-progress",
+                "--no-progress"
+            ]),
+            Settings {
+                continue_on_error: Some(true),
+                show_progress: Some(false)
+            }
+        );
+        assert_eq!(
+            parse(&["--show-progress", "false"]).show_progress,
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_environment_values() -> anyhow::Result<()> {
+        assert_eq!(env(&[])?, Settings::default());
+        assert_eq!(
+            env(&[(CONTINUE_ON_ERROR_ENV, "Off"), (SHOW_PROGRESS_ENV, "yes")])?,
+            Settings {
+                continue_on_error: Some(false),
+                show_progress: Some(true)
+            }
+        );
+        assert_eq!(
+            env(&[(CONTINUE_ON_ERROR_ENV, "maybe")])
+                .unwrap_err()
+                .to_string(),
+            "Invalid value 'maybe' of GRZ_CHECK_CONTINUE_ON_ERROR; expected true or false"
+        );
+        Ok(())
+    }
+}
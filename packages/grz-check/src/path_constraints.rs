@@ -0,0 +1,137 @@
+//! Constraints of the inbox on file paths from the `[path_constraints]` table of the config
+//! file, e.g. the maximum length and the characters allowed in S3 keys.
+//!
+//! Paths below `root` are checked relative to it, as they appear as keys in the inbox. Members
+//! of archives are checked by the path of their archive, which is the uploaded file.
+
+use crate::archive;
+use itertools::Itertools;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Characters given as a list of single characters and `a-z` style ranges, e.g.
+/// `A-Za-z0-9._/-`. A `-` at the start or end is a literal `-`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(into = "String")]
+pub struct CharacterSet {
+    spec: String,
+    ranges: Vec<(char, char)>,
+}
+
+impl From<CharacterSet> for String {
+    fn from(set: CharacterSet) -> Self {
+        set.spec
+    }
+}
+
+impl CharacterSet {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let chars: Vec<char> = spec.chars().collect();
+        let mut ranges = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            if i + 2 < chars.len() && chars[i + 1] == '-' {
+                let (start, end) = (chars[i], chars[i + 2]);
+                if start > end {
+                    return Err(format!(
+                        "Invalid character range '{start}-{end}' in '{spec}'"
+                    ));
+                }
+                ranges.push((start, end));
+                i += 3;
+            } else {
+                ranges.push((chars[i], chars[i]));
+                i += 1;
+            }
+        }
+        if ranges.is_empty() {
+            return Err("The set of allowed characters must not be empty".to_string());
+        }
+        Ok(Self {
+            spec: spec.to_string(),
+            ranges,
+        })
+    }
+
+    fn contains(&self, c: char) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| (start..=end).contains(&c))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PathConstraints {
+    /// Directory the keys in the inbox are relative to.
+    pub root: Option<PathBuf>,
+    /// Maximum length of a path in bytes.
+    pub max_length: Option<usize>,
+    /// Characters allowed in paths.
+    pub allowed_characters: Option<CharacterSet>,
+}
+
+impl PathConstraints {
+    /// Returns an error for each constraint the path of a checked file violates.
+    pub fn check(&self, path: &Path) -> Vec<String> {
+        let path = archive::split(path).map_or_else(|| path.to_path_buf(), |(path, _)| path);
+        let path = self
+            .root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(&path)
+            .to_string_lossy();
+
+        let mut errors = Vec::new();
+        if let Some(max_length) = self.max_length
+            && path.len() > max_length
+        {
+            errors.push(format!(
+                "Path '{path}' is {} bytes long, above the maximum path length of {max_length} bytes of the inbox.",
+                path.len()
+            ));
+        }
+        if let Some(allowed) = &self.allowed_characters {
+            let invalid = path
+                .chars()
+                .filter(|&c| !allowed.contains(c))
+                .unique()
+                .map(|c| format!("{c:?}"))
+                .join(", ");
+            if !invalid.is_empty() {
+                errors.push(format!(
+                    "Path '{path}' contains characters not allowed by the inbox: {invalid}. Allowed characters: {}.",
+                    allowed.spec
+                ));
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_constraints() {
+        let constraints = PathConstraints {
+            root: Some(PathBuf::from("/data/submission")),
+            max_length: Some(20),
+            allowed_characters: Some(CharacterSet::parse("A-Za-z0-9._/-").unwrap()),
+        };
+        assert!(
+            constraints
+                .check(Path::new("/data/submission/files/a_R1.fastq.gz"))
+                .is_empty()
+        );
+        assert_eq!(
+            constraints.check(Path::new("/data/submission/files/Müller R1.fastq.gz")),
+            vec![
+                "Path 'files/Müller R1.fastq.gz' is 25 bytes long, above the maximum path length of 20 bytes of the inbox.",
+                "Path 'files/Müller R1.fastq.gz' contains characters not allowed by the inbox: 'ü', ' '. Allowed characters: A-Za-z0-9._/-.",
+            ]
+        );
+        assert!(CharacterSet::parse("z-a").is_err());
+        assert!(CharacterSet::parse("").is_err());
+    }
+}
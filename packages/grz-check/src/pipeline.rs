@@ -0,0 +1,248 @@
+//! Declarative pre-submission pipelines run via `grz-check run PIPELINE.toml`.
+//!
+//! ```toml
+//! [stage_in]
+//! command = "dmget {path}"
+//!
+//! [check]
+//! args = ["--fastq-paired", "R1.fastq.gz", "R2.fastq.gz", "100", "--output", "report.jsonl"]
+//!
+//! [encrypt]
+//! command = "crypt4gh encrypt --recipient_pk grz.pub < {path} > {path}.c4gh"
+//!
+//! [move]
+//! command = "mv {path}.c4gh outbox/"
+//!
+//! [notify]
+//! command = "mail -s 'Submission check: {status}' ops@example.org < {report}"
+//! ```
+//!
+//! Stages run in the order above. `stage_in` is run before each file is opened, like the
+//! `[staging]` hook of the config file. `encrypt` and `move` are run for every checked file, but
+//! only if all checks passed. `notify` is always run last, with `{status}` replaced by `OK` or
+//! `ERROR` and `{report}` by the path of the report.
+
+use crate::hooks::{self, StagingHook};
+use crate::logging;
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Placeholder in the notify command that is replaced by `OK` or `ERROR`.
+const STATUS_PLACEHOLDER: &str = "{status}";
+/// Placeholder in the notify command that is replaced by the shell-quoted path of the report.
+const REPORT_PLACEHOLDER: &str = "{report}";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pipeline {
+    stage_in: Option<StageEntry>,
+    check: CheckEntry,
+    encrypt: Option<StageEntry>,
+    #[serde(rename = "move")]
+    move_to: Option<StageEntry>,
+    notify: Option<StageEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StageEntry {
+    command: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CheckEntry {
+    /// Command-line arguments of the check, as passed to `grz-check`.
+    args: Vec<String>,
+}
+
+/// Checked files and whether all checks passed, as read back from the report.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Outcome {
+    paths: Vec<PathBuf>,
+    passed: bool,
+}
+
+impl Pipeline {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read pipeline file {}", path.display()))?;
+        let pipeline: Self = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse pipeline file {}", path.display()))?;
+        for (stage, entry) in [
+            ("stage_in", &pipeline.stage_in),
+            ("encrypt", &pipeline.encrypt),
+            ("move", &pipeline.move_to),
+        ] {
+            if let Some(entry) = entry
+                && !entry.command.contains(hooks::PATH_PLACEHOLDER)
+            {
+                anyhow::bail!(
+                    "Command '{}' of [{stage}] must contain the placeholder {}",
+                    entry.command,
+                    hooks::PATH_PLACEHOLDER
+                );
+            }
+        }
+        Ok(pipeline)
+    }
+
+    /// Arguments of the check stage, including the program name.
+    pub fn check_args(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(env!("CARGO_PKG_NAME")).chain(self.check.args.iter().map(String::as_str))
+    }
+
+    pub fn staging_hook(&self) -> Option<StagingHook> {
+        self.stage_in.as_ref().map(|entry| StagingHook {
+            command: entry.command.clone(),
+        })
+    }
+
+    /// Runs the stages after the check, given the result of the check stage and its report.
+    pub fn finish(&self, report: &Path, checked: anyhow::Result<()>) -> anyhow::Result<()> {
+        let outcome = match &checked {
+            Ok(()) => read_outcome(report)?,
+            Err(_) => Outcome::default(),
+        };
+        let mut result = checked;
+        if result.is_ok() && !outcome.passed {
+            result = Err(anyhow::anyhow!(
+                "At least one check failed, so no files were encrypted or moved. See report: {}",
+                report.display()
+            ));
+        }
+        if result.is_ok() {
+            result = self.run_file_stages(&outcome.paths);
+        }
+
+        if let Some(notify) = &self.notify {
+            let status = if result.is_ok() { "OK" } else { "ERROR" };
+            let command = notify.command.replace(STATUS_PLACEHOLDER, status).replace(
+                REPORT_PLACEHOLDER,
+                &hooks::shell_quote(&report.to_string_lossy()),
+            );
+            if let Err(e) = hooks::run("Notify", &command) {
+                logging::warn(e.clone());
+                result = result.and(Err(anyhow::anyhow!(e)));
+            }
+        }
+        result
+    }
+
+    /// Runs the encrypt and move stages for every checked file, stopping at the first failure.
+    fn run_file_stages(&self, paths: &[PathBuf]) -> anyhow::Result<()> {
+        for (kind, entry) in [("Encrypt", &self.encrypt), ("Move", &self.move_to)] {
+            let Some(entry) = entry else {
+                continue;
+            };
+            logging::info(format!("{kind} stage: {} file(s)", paths.len()));
+            for path in paths {
+                let command = entry.command.replace(
+                    hooks::PATH_PLACEHOLDER,
+                    &hooks::shell_quote(&path.to_string_lossy()),
+                );
+                hooks::run(kind, &command).map_err(anyhow::Error::msg)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the checked files from a report. The checks passed if no entry has an error.
+fn read_outcome(report: &Path) -> anyhow::Result<Outcome> {
+    let file = fs::File::open(report)
+        .with_context(|| format!("Failed to open report {}", report.display()))?;
+    let mut outcome = Outcome {
+        paths: vec![],
+        passed: true,
+    };
+    for line in BufReader::new(file).lines() {
+        let line = line.with_context(|| format!("Failed to read report {}", report.display()))?;
+        let entry: Value = serde_json::from_str(&line)
+            .with_context(|| format!("Malformed line in report {}", report.display()))?;
+        let data = &entry["data"];
+        if data["status"] == "ERROR"
+            || data["errors"]
+                .as_array()
+                .is_some_and(|errors| !errors.is_empty())
+        {
+            outcome.passed = false;
+        }
+        if let Some(path) = data["path"].as_str() {
+            outcome.paths.push(PathBuf::from(path));
+        }
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_pipeline(dir: &Path, checked: &[&str]) -> anyhow::Result<Pipeline> {
+        let toml = format!(
+            r#"
+            [check]
+            args = []
+
+            [encrypt]
+            command = "cp {{path}} {{path}}.enc"
+
+            [move]
+            command = "mv {{path}}.enc {outbox}/"
+
+            [notify]
+            command = "echo {{status}} > {outbox}/status"
+            "#,
+            outbox = dir.join("outbox").display()
+        );
+        fs::create_dir(dir.join("outbox"))?;
+        let report = checked
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        fs::write(dir.join("report.jsonl"), report)?;
+        Ok(toml::from_str(&toml)?)
+    }
+
+    #[test]
+    fn test_files_are_moved_only_if_all_checks_passed() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("raw.txt");
+        fs::write(&file, "contents")?;
+        let ok = format!(
+            r#"{{"check_type":"raw","data":{{"path":"{}","status":"OK","errors":[]}}}}"#,
+            file.display()
+        );
+        let pipeline = write_pipeline(
+            dir.path(),
+            &[&ok, r#"{"check_type":"run","data":{"errors":[]}}"#],
+        )?;
+        pipeline.finish(&dir.path().join("report.jsonl"), Ok(()))?;
+        assert!(dir.path().join("outbox/raw.txt.enc").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("outbox/status"))?,
+            "OK\n"
+        );
+
+        let dir = tempdir()?;
+        let failed = r#"{"check_type":"run","data":{"errors":["Files with identical content"]}}"#;
+        let pipeline = write_pipeline(dir.path(), &[&ok, failed])?;
+        assert!(
+            pipeline
+                .finish(&dir.path().join("report.jsonl"), Ok(()))
+                .is_err()
+        );
+        assert!(!dir.path().join("outbox/raw.txt.enc").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("outbox/status"))?,
+            "ERROR\n"
+        );
+        Ok(())
+    }
+}
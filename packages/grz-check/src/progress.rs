@@ -1,17 +1,24 @@
-use indicatif::ProgressBar;
+use crate::archive;
+use crate::logging::{self, LogFormat};
+use crate::systemd::Heartbeat;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 pub(crate) struct DualProgressReader<R: std::io::Read> {
     inner: R,
     specific_pb: ProgressBar,
     global_pb: ProgressBar,
+    heartbeat: Heartbeat,
 }
 
 impl<R: std::io::Read> DualProgressReader<R> {
-    pub fn new(inner: R, pb1: ProgressBar, pb2: ProgressBar) -> Self {
+    pub fn new(inner: R, pb1: ProgressBar, pb2: ProgressBar, heartbeat: Heartbeat) -> Self {
         Self {
             inner,
             specific_pb: pb1,
             global_pb: pb2,
+            heartbeat,
         }
     }
 }
@@ -19,11 +26,119 @@ impl<R: std::io::Read> DualProgressReader<R> {
 impl<R: std::io::Read> std::io::Read for DualProgressReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let bytes_read = self.inner.read(buf)?;
+        self.heartbeat.beat();
         if bytes_read > 0 {
             let n = bytes_read as u64;
-            self.specific_pb.inc(n);
+            // Bars without length count records instead, see `file_bar`.
+            if self.specific_pb.length().is_some() {
+                self.specific_pb.inc(n);
+            }
             self.global_pb.inc(n);
         }
         Ok(bytes_read)
     }
 }
+
+/// Number of records between updates of a [`RecordProgress`].
+const RECORD_BATCH: u64 = 1024;
+/// Interval between progress events in JSON log format.
+const EVENT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Creates the progress bar of a file of records. Files whose size is unknown until they are
+/// read, such as a pipe from stdin or from a download, get a bar without length that counts
+/// records instead of bytes, since their byte progress is meaningless.
+pub(crate) fn file_bar(size: u64, path: &Path, byte_style: &ProgressStyle) -> ProgressBar {
+    if archive::is_stream(path) {
+        let pb = ProgressBar::no_length();
+        pb.set_style(
+            ProgressStyle::with_template(
+                "{prefix:8.bold} {spinner:.cyan} {human_pos:>10} records ({per_sec:>12}) {wide_msg}",
+            )
+            .expect("Record progress template should be valid"),
+        );
+        pb
+    } else {
+        let pb = ProgressBar::new(size);
+        pb.set_style(byte_style.clone());
+        pb
+    }
+}
+
+/// Record count of a file whose progress bar has no length, see [`file_bar`].
+#[derive(Debug)]
+pub(crate) struct RecordProgress {
+    pb: ProgressBar,
+    path: PathBuf,
+    started: Instant,
+    last_event: Instant,
+}
+
+impl RecordProgress {
+    /// Returns the record progress of `pb` if it counts records.
+    pub fn of(pb: &ProgressBar, path: &Path) -> Option<Self> {
+        let now = Instant::now();
+        pb.length().is_none().then(|| Self {
+            pb: pb.clone(),
+            path: path.to_path_buf(),
+            started: now,
+            last_event: now,
+        })
+    }
+
+    /// Updates the bar with the number of records read so far, in batches.
+    pub fn update(&mut self, records: u64) {
+        if records.is_multiple_of(RECORD_BATCH) {
+            self.set(records);
+        }
+    }
+
+    /// Updates the bar with the final number of records.
+    pub fn finish(&mut self, records: u64) {
+        self.set(records);
+    }
+
+    fn set(&mut self, records: u64) {
+        self.pb.set_position(records);
+        // The bars are hidden in JSON log format, so the progress is logged instead.
+        let now = Instant::now();
+        if logging::format() == LogFormat::Json
+            && now.duration_since(self.last_event) >= EVENT_INTERVAL
+        {
+            self.last_event = now;
+            let seconds = now.duration_since(self.started).as_secs_f64();
+            logging::progress(&self.path, records, records as f64 / seconds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_record_progress_of_streams() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let style = ProgressStyle::default_bar();
+        let file = dir.path().join("reads.fastq");
+        fs::write(&file, "@r1\nACGT\n+\nIIII\n")?;
+        let pb = file_bar(16, &file, &style);
+        assert_eq!(pb.length(), Some(16));
+        assert!(RecordProgress::of(&pb, &file).is_none());
+
+        let fifo = dir.path().join("stdin.fastq");
+        assert!(Command::new("mkfifo").arg(&fifo).status()?.success());
+        let pb = file_bar(0, &fifo, &style);
+        assert_eq!(pb.length(), None);
+        let mut record_progress = RecordProgress::of(&pb, &fifo).expect("Bar counts records");
+        record_progress.update(RECORD_BATCH - 1);
+        assert_eq!(pb.position(), 0);
+        record_progress.update(RECORD_BATCH);
+        assert_eq!(pb.position(), RECORD_BATCH);
+        record_progress.finish(RECORD_BATCH + 1);
+        assert_eq!(pb.position(), RECORD_BATCH + 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+//! Preservation of failed files for later inspection, given via `--quarantine`.
+//!
+//! Each file that fails validation is copied or hard-linked below the quarantine directory,
+//! mirroring its original path, and the report entries of its job are written next to it as
+//! `<name>.report.jsonl`. Members of archives are copied to the directory of their archive. If a
+//! file of the same path was quarantined before, e.g. by an earlier run, a numbered suffix is
+//! added instead of overwriting it.
+
+use crate::archive;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum QuarantineMode {
+    /// Copy the file, which preserves it even if the original is overwritten in place.
+    #[default]
+    Copy,
+    /// Hard-link the file, falling back to a copy across file systems and for members of
+    /// archives. The link shares the content of the original, so it is only preserved if
+    /// re-uploads replace the file instead of writing into it.
+    Hardlink,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Quarantine {
+    pub dir: PathBuf,
+    pub mode: QuarantineMode,
+}
+
+/// Path below the quarantine directory that mirrors `path`, without root or parent components.
+fn mirrored(path: &Path) -> PathBuf {
+    let (path, member) = archive::split(path)
+        .map_or((path.to_path_buf(), None), |(archive, member)| {
+            (archive, Some(member))
+        });
+    path.components()
+        .chain(member.iter().flat_map(|member| member.components()))
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns `target`, or the first of `target.1`, `target.2`, ... that does not exist yet.
+fn unused(target: PathBuf) -> PathBuf {
+    if !target.exists() {
+        return target;
+    }
+    (1..)
+        .map(|n| {
+            let mut name = target.clone().into_os_string();
+            name.push(format!(".{n}"));
+            PathBuf::from(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("Some numbered path should be unused")
+}
+
+impl Quarantine {
+    /// Preserves a failed file together with the report entries of its job, returning the path
+    /// of the preserved copy.
+    pub fn preserve(&self, path: &Path, report_entries: &[u8]) -> Result<PathBuf, String> {
+        let failed = |e: io::Error| format!("Failed to quarantine {}: {e}", path.display());
+        let target = unused(self.dir.join(mirrored(path)));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(failed)?;
+        }
+        let linked = self.mode == QuarantineMode::Hardlink
+            && archive::split(path).is_none()
+            && fs::hard_link(path, &target).is_ok();
+        if !linked {
+            let mut source = archive::open(path).map_err(failed)?;
+            let mut file = fs::File::create(&target).map_err(failed)?;
+            io::copy(&mut source, &mut file).map_err(failed)?;
+        }
+        let mut entries_path = target.clone().into_os_string();
+        entries_path.push(".report.jsonl");
+        fs::write(entries_path, report_entries).map_err(failed)?;
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_preserve_failed_files() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("upload").join("reads.fastq.gz");
+        fs::create_dir(dir.path().join("upload"))?;
+        fs::write(&path, "first upload")?;
+
+        for mode in [QuarantineMode::Copy, QuarantineMode::Hardlink] {
+            let quarantine = Quarantine {
+                dir: dir.path().join(format!("quarantine-{mode:?}")),
+                mode,
+            };
+            let first = quarantine
+                .preserve(&path, b"{\"status\":\"ERROR\"}\n")
+                .map_err(anyhow::Error::msg)?;
+            assert_eq!(first, quarantine.dir.join(mirrored(&path)));
+            assert_eq!(fs::read_to_string(&first)?, "first upload");
+            let mut entries = first.clone().into_os_string();
+            entries.push(".report.jsonl");
+            assert_eq!(fs::read_to_string(entries)?, "{\"status\":\"ERROR\"}\n");
+
+            // A later failure of the same path does not replace the earlier copy.
+            let second = quarantine
+                .preserve(&path, b"")
+                .map_err(anyhow::Error::msg)?;
+            assert_eq!(second.file_name().unwrap(), "reads.fastq.gz.1");
+        }
+        Ok(())
+    }
+}
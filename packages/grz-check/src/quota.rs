@@ -0,0 +1,172 @@
+//! Accounting of the submission size against a storage quota given via `--quota`.
+
+use crate::archive;
+use crate::checker::Job;
+use crate::logging;
+use anyhow::Context;
+use indicatif::HumanBytes;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of decompressed bytes read from each compressed file to estimate its compression ratio.
+const SAMPLE_SIZE: u64 = 16 * 1024 * 1024;
+
+const UNITS: &[(&str, u64)] = &[
+    ("KiB", 1 << 10),
+    ("MiB", 1 << 20),
+    ("GiB", 1 << 30),
+    ("TiB", 1 << 40),
+    ("PiB", 1 << 50),
+    ("KB", 1_000),
+    ("MB", 1_000_000),
+    ("GB", 1_000_000_000),
+    ("TB", 1_000_000_000_000),
+    ("PB", 1_000_000_000_000_000),
+    ("K", 1 << 10),
+    ("M", 1 << 20),
+    ("G", 1 << 30),
+    ("T", 1 << 40),
+    ("P", 1 << 50),
+    ("B", 1),
+];
+
+/// Parses a size in bytes with an optional unit, e.g. `500G`, `2TB` or `1024`.
+///
+/// Units with an `i` (`KiB`, `GiB`, …) and single-letter units are binary, `KB`, `GB`, … are
+/// decimal.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (number, multiplier) = UNITS
+        .iter()
+        .find_map(|&(unit, multiplier)| {
+            s.strip_suffix(unit)
+                .map(|number| (number.trim_end(), multiplier))
+        })
+        .unwrap_or((s, 1));
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid size '{s}'. Expected e.g. 1024, 500G or 2TB."))
+}
+
+/// Parses a size like [`parse_size`], rejecting zero, e.g. for `--chunk-size`.
+pub fn parse_nonzero_size(s: &str) -> Result<u64, String> {
+    match parse_size(s)? {
+        0 => Err(format!(
+            "Invalid size '{s}'. Expected a size greater than zero."
+        )),
+        size => Ok(size),
+    }
+}
+
+/// Counts the bytes read from the inner reader.
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+/// Estimates the decompressed size of a file from the compression ratio of its beginning.
+///
+/// Uncompressed files and compressed files smaller than the sample are measured exactly.
+pub fn estimate_decompressed_size(path: &Path) -> anyhow::Result<u64> {
+    let file = archive::open(path)
+        .with_context(|| format!("Failed to open file for reading: {}", path.display()))?;
+    let size = archive::size(path)?;
+    let consumed = Arc::new(AtomicU64::new(0));
+    let reader = CountingReader {
+        inner: file,
+        count: consumed.clone(),
+    };
+    let (reader, format) = niffler::get_reader(Box::new(reader))
+        .with_context(|| format!("Failed to decompress file: {}", path.display()))?;
+    if format == niffler::compression::Format::No {
+        return Ok(size);
+    }
+
+    let decompressed = io::copy(&mut reader.take(SAMPLE_SIZE), &mut io::sink())
+        .with_context(|| format!("Failed to decompress file: {}", path.display()))?;
+    let consumed = consumed.load(Ordering::Relaxed);
+    if decompressed < SAMPLE_SIZE || consumed >= size {
+        return Ok(decompressed);
+    }
+    let ratio = decompressed as f64 / consumed.max(1) as f64;
+    Ok((size as f64 * ratio) as u64)
+}
+
+/// Fails if the size of all files of `jobs` exceeds `quota`.
+///
+/// If `decompressed` is set, the estimated decompressed sizes are accounted instead of the sizes
+/// on disk.
+pub fn check(jobs: &[Job], total_bytes: u64, quota: u64, decompressed: bool) -> anyhow::Result<()> {
+    let (size, description) = if decompressed {
+        let mut size: u64 = 0;
+        for path in jobs.iter().flat_map(Job::paths) {
+            size += estimate_decompressed_size(&path)?;
+        }
+        (size, "Estimated decompressed submission size")
+    } else {
+        (total_bytes, "Submission size")
+    };
+
+    if size > quota {
+        anyhow::bail!(
+            "{description} of {} ({size} bytes) exceeds the quota of {} ({quota} bytes) by {}.",
+            HumanBytes(size),
+            HumanBytes(quota),
+            HumanBytes(size - quota)
+        );
+    }
+    logging::info(format!(
+        "{description} of {} is within the quota of {}",
+        HumanBytes(size),
+        HumanBytes(quota)
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("2K"), Ok(2048));
+        assert_eq!(parse_size("3 GiB"), Ok(3 << 30));
+        assert_eq!(parse_size("2TB"), Ok(2_000_000_000_000));
+        assert!(parse_size("1.5T").is_err());
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("99999999P").is_err());
+    }
+
+    #[test]
+    fn test_estimate_decompressed_size() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let plain_path = dir.path().join("plain.txt");
+        fs::write(&plain_path, "ACGT".repeat(100))?;
+        assert_eq!(estimate_decompressed_size(&plain_path)?, 400);
+
+        let gz_path = dir.path().join("reads.gz");
+        let mut encoder = GzEncoder::new(fs::File::create(&gz_path)?, Compression::default());
+        encoder.write_all("ACGT".repeat(1000).as_bytes())?;
+        encoder.finish()?;
+        assert_eq!(estimate_decompressed_size(&gz_path)?, 4000);
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! Detection of duplicate read names within a memory budget, enabled via
+//! `--duplicate-read-names`.
+//!
+//! Names are reduced to 128-bit hashes, which are collected with their record number. Once the
+//! budget is used up, the collected hashes are sorted and spilled to a temporary file. At the end
+//! of the file, the sorted runs are merged in a second pass, in which equal adjacent hashes are
+//! duplicates. With 128-bit hashes, false positives are negligible even for billions of reads.
+
+use crate::logging;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+/// A name hash and the number of its record.
+type Entry = (u128, u64);
+
+const ENTRY_SIZE: u64 = 24;
+
+fn hash_name(name: &[u8]) -> u128 {
+    let half = |seed: u8| {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        name.hash(&mut hasher);
+        hasher.finish()
+    };
+    (u128::from(half(0)) << 64) | u128::from(half(1))
+}
+
+fn write_entry(writer: &mut impl Write, (hash, record): Entry) -> io::Result<()> {
+    writer.write_all(&hash.to_le_bytes())?;
+    writer.write_all(&record.to_le_bytes())
+}
+
+fn read_entry(reader: &mut impl Read) -> io::Result<Option<Entry>> {
+    let mut buf = [0; ENTRY_SIZE as usize];
+    match reader.read_exact(&mut buf) {
+        Ok(()) => Ok(Some((
+            u128::from_le_bytes(buf[..16].try_into().unwrap()),
+            u64::from_le_bytes(buf[16..].try_into().unwrap()),
+        ))),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Duplicate read names of a file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Duplicates {
+    /// Number of records whose name occurs in an earlier record.
+    pub count: u64,
+    /// Lowest number of such a record.
+    pub first_record: Option<u64>,
+}
+
+impl Duplicates {
+    fn add(&mut self, record: u64) {
+        self.count += 1;
+        self.first_record = Some(self.first_record.map_or(record, |first| first.min(record)));
+    }
+}
+
+#[derive(Debug)]
+pub struct DuplicateNames {
+    entries: Vec<Entry>,
+    capacity: usize,
+    runs: Vec<fs::File>,
+    spilled_bytes: u64,
+}
+
+impl DuplicateNames {
+    pub fn new(memory: u64) -> Self {
+        let capacity = usize::try_from(memory / ENTRY_SIZE)
+            .unwrap_or(usize::MAX)
+            .max(1);
+        Self {
+            entries: Vec::new(),
+            capacity,
+            runs: Vec::new(),
+            spilled_bytes: 0,
+        }
+    }
+
+    pub fn add(&mut self, record: u64, name: &[u8]) -> io::Result<()> {
+        if self.entries.len() == self.capacity {
+            self.spill()?;
+        }
+        self.entries.push((hash_name(name), record));
+        Ok(())
+    }
+
+    /// Writes the collected entries as a sorted run to a temporary file.
+    fn spill(&mut self) -> io::Result<()> {
+        self.entries.sort_unstable();
+        let mut writer = BufWriter::new(tempfile::tempfile()?);
+        for &entry in &self.entries {
+            write_entry(&mut writer, entry)?;
+        }
+        let mut file = writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        file.seek(SeekFrom::Start(0))?;
+        self.spilled_bytes += self.entries.len() as u64 * ENTRY_SIZE;
+        self.runs.push(file);
+        self.entries.clear();
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<Duplicates> {
+        let mut duplicates = Duplicates::default();
+        if self.runs.is_empty() {
+            self.entries.sort_unstable();
+            for pair in self.entries.windows(2) {
+                if pair[0].0 == pair[1].0 {
+                    duplicates.add(pair[1].1);
+                }
+            }
+            return Ok(duplicates);
+        }
+
+        self.spill()?;
+        logging::info(format!(
+            "Merging {} spilled run(s) of read-name hashes ({} bytes) to find duplicate read names",
+            self.runs.len(),
+            self.spilled_bytes
+        ));
+        let mut readers: Vec<BufReader<fs::File>> =
+            self.runs.into_iter().map(BufReader::new).collect();
+        let mut heap = BinaryHeap::new();
+        for (i, reader) in readers.iter_mut().enumerate() {
+            if let Some(entry) = read_entry(reader)? {
+                heap.push(Reverse((entry, i)));
+            }
+        }
+        let mut previous = None;
+        while let Some(Reverse(((hash, record), i))) = heap.pop() {
+            if previous == Some(hash) {
+                duplicates.add(record);
+            }
+            previous = Some(hash);
+            if let Some(entry) = read_entry(&mut readers[i])? {
+                heap.push(Reverse((entry, i)));
+            }
+        }
+        Ok(duplicates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_duplicates(memory: u64, names: &[&str]) -> Duplicates {
+        let mut duplicate_names = DuplicateNames::new(memory);
+        for (i, name) in names.iter().enumerate() {
+            duplicate_names.add(i as u64 + 1, name.as_bytes()).unwrap();
+        }
+        duplicate_names.finish().unwrap()
+    }
+
+    #[test]
+    fn test_spilled_runs_match_in_memory() {
+        let names = ["r1", "r2", "r3", "r1", "r4", "r2", "r5", "r1"];
+        let expected = Duplicates {
+            count: 3,
+            first_record: Some(4),
+        };
+        assert_eq!(find_duplicates(1 << 20, &names), expected);
+        // two entries per run
+        assert_eq!(find_duplicates(2 * ENTRY_SIZE, &names), expected);
+        assert_eq!(find_duplicates(0, &names), expected);
+        assert_eq!(
+            find_duplicates(2 * ENTRY_SIZE, &["r1", "r2", "r3"]),
+            Duplicates::default()
+        );
+    }
+}
@@ -0,0 +1,813 @@
+//! Tools for working with existing JSONL reports, e.g. archived reports from earlier versions.
+
+use crate::findings;
+use anyhow::Context;
+use chrono::DateTime;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Schema version assumed for report entries without a `schema_version` field.
+const LEGACY_SCHEMA_VERSION: u64 = 1;
+
+/// Schema version of the reports written by this build.
+pub const CURRENT_SCHEMA_VERSION: u64 = 4;
+
+/// Report schema versions known to this build.
+///
+/// - 1: original format without a `schema_version` field
+/// - 2: adds `schema_version` and coded `findings` alongside the `errors` and `warnings`
+/// - 3: adds the RFC 3339 timestamps `created_at` and `completed_at` and the monotonic
+///   `duration_seconds` to each entry
+/// - 4: adds `entity` entries of FASTQ pairs, which carry the findings concerning both files
+///   instead of the entries of the files; the status of lab data and of the `run` entry, now
+///   always written, rolls up their members, listed in `failed_members` if not OK
+pub const KNOWN_SCHEMA_VERSIONS: &[u64] = &[1, 2, 3, 4];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum FieldType {
+    Path,
+    Status,
+    Count,
+    Number,
+    Checksum,
+    Compression,
+    Messages,
+    Timings,
+    Counts,
+    Findings,
+    NotEvaluated,
+    FastqPairs,
+    EntityKind,
+    AlignmentStatus,
+    ChunkChecksums,
+    Merkle,
+    BaseComposition,
+}
+
+impl FieldType {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            FieldType::Path => value.is_string(),
+            FieldType::Status => matches!(
+                value.as_str(),
+                Some("OK" | "ERROR" | "MISSING" | "NOT_CHECKED")
+            ),
+            FieldType::Count => value.is_null() || value.is_u64(),
+            FieldType::Number => value.is_null() || value.is_number(),
+            FieldType::Checksum => value.is_null() || value.is_string(),
+            FieldType::Compression => matches!(
+                value.as_str(),
+                Some("none" | "gzip" | "bgzf" | "bzip2" | "xz" | "zstd")
+            ),
+            FieldType::Messages => value
+                .as_array()
+                .is_some_and(|messages| messages.iter().all(Value::is_string)),
+            FieldType::Timings => value
+                .as_object()
+                .is_some_and(|timings| timings.values().all(Value::is_number)),
+            FieldType::Counts => value
+                .as_object()
+                .is_some_and(|counts| counts.values().all(Value::is_u64)),
+            FieldType::Findings => value.as_array().is_some_and(|findings| {
+                findings.iter().all(|finding| {
+                    finding.get("code").is_some_and(Value::is_string)
+                        && matches!(
+                            finding.get("severity").and_then(Value::as_str),
+                            Some("error" | "warning")
+                        )
+                        && finding.get("message").is_some_and(Value::is_string)
+                        && finding.get("hint").is_none_or(Value::is_string)
+                })
+            }),
+            FieldType::EntityKind => {
+                matches!(value.as_str(), Some("pair" | "sample" | "submission"))
+            }
+            FieldType::AlignmentStatus => {
+                value.is_null() || matches!(value.as_str(), Some("aligned" | "unaligned" | "mixed"))
+            }
+            FieldType::FastqPairs => value.as_array().is_some_and(|pairs| {
+                pairs.iter().all(|pair| {
+                    pair.get("fq1").is_some_and(Value::is_string)
+                        && pair.get("fq2").is_some_and(Value::is_string)
+                })
+            }),
+            FieldType::ChunkChecksums => {
+                value.get("chunk_size").is_some_and(Value::is_u64)
+                    && value
+                        .get("sha256")
+                        .and_then(Value::as_array)
+                        .is_some_and(|digests| digests.iter().all(Value::is_string))
+            }
+            FieldType::Merkle => {
+                value.get("leaf_size").is_some_and(Value::is_u64)
+                    && value.get("root").is_some_and(Value::is_string)
+                    && value
+                        .get("subtrees")
+                        .is_none_or(|subtrees| FieldType::Messages.matches(subtrees))
+            }
+            FieldType::BaseComposition => {
+                value.is_null()
+                    || ["A", "C", "G", "T", "N"]
+                        .iter()
+                        .all(|base| value.get(base).is_some_and(Value::is_number))
+            }
+            FieldType::NotEvaluated => value.as_array().is_some_and(|entries| {
+                entries.iter().all(|entry| {
+                    entry.get("check").is_some_and(Value::is_string)
+                        && entry.get("because").is_some_and(Value::is_string)
+                })
+            }),
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            FieldType::Path => "a string",
+            FieldType::Status => "\"OK\", \"ERROR\", \"MISSING\" or \"NOT_CHECKED\"",
+            FieldType::Count => "a non-negative integer or null",
+            FieldType::Number => "a number or null",
+            FieldType::Checksum => "a string or null",
+            FieldType::Compression => {
+                "one of \"none\", \"gzip\", \"bgzf\", \"bzip2\", \"xz\" or \"zstd\""
+            }
+            FieldType::Messages => "an array of strings",
+            FieldType::Timings => "an object of numbers",
+            FieldType::Counts => "an object of non-negative integers",
+            FieldType::Findings => {
+                "an array of objects with code, severity (\"error\" or \"warning\"), message and an optional hint"
+            }
+            FieldType::NotEvaluated => "an array of objects with check and because",
+            FieldType::FastqPairs => "an array of objects with fq1 and fq2",
+            FieldType::EntityKind => "\"pair\", \"sample\" or \"submission\"",
+            FieldType::AlignmentStatus => "null, \"aligned\", \"unaligned\" or \"mixed\"",
+            FieldType::ChunkChecksums => "an object with chunk_size and an array of sha256 digests",
+            FieldType::Merkle => "an object with leaf_size, root and optional subtrees",
+            FieldType::BaseComposition => "null or an object with numbers A, C, G, T and N",
+        }
+    }
+}
+
+struct Field {
+    name: &'static str,
+    field_type: FieldType,
+    /// Whether the field must be present. Fields added after a schema version was first released
+    /// are optional, since older reports of the same version do not contain them.
+    required: bool,
+}
+
+const fn field(name: &'static str, field_type: FieldType) -> Field {
+    Field {
+        name,
+        field_type,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, field_type: FieldType) -> Field {
+    Field {
+        name,
+        field_type,
+        required: false,
+    }
+}
+
+const V1_FASTQ_FIELDS: &[Field] = &[
+    field("path", FieldType::Path),
+    field("status", FieldType::Status),
+    field("num_records", FieldType::Count),
+    optional("num_pairs", FieldType::Count),
+    field("mean_read_length", FieldType::Number),
+    optional("modal_read_length", FieldType::Count),
+    field("checksum", FieldType::Checksum),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+    optional("timings", FieldType::Timings),
+];
+
+const V1_RECORD_FIELDS: &[Field] = &[
+    field("path", FieldType::Path),
+    field("status", FieldType::Status),
+    field("num_records", FieldType::Count),
+    field("checksum", FieldType::Checksum),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+    optional("timings", FieldType::Timings),
+];
+
+/// Fields of the check types added in version 2 that report a total length (FASTA, BED).
+const V2_LENGTH_FIELDS: &[Field] = &[
+    field("path", FieldType::Path),
+    field("status", FieldType::Status),
+    field("num_records", FieldType::Count),
+    field("total_length", FieldType::Count),
+    field("checksum", FieldType::Checksum),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+    optional("timings", FieldType::Timings),
+];
+
+/// Fields of tabix and CSI index checks, added in version 2.
+const V2_TABIX_FIELDS: &[Field] = &[
+    field("path", FieldType::Path),
+    field("status", FieldType::Status),
+    field("num_sequences", FieldType::Count),
+    field("checksum", FieldType::Checksum),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+    optional("timings", FieldType::Timings),
+];
+
+/// Fields of GZI index checks, added in version 2.
+const V2_GZI_FIELDS: &[Field] = &[
+    field("path", FieldType::Path),
+    field("status", FieldType::Status),
+    field("num_entries", FieldType::Count),
+    field("checksum", FieldType::Checksum),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+    optional("timings", FieldType::Timings),
+];
+
+/// Fields of nanopore raw signal checks (POD5, FAST5), added in version 2.
+const V2_SIGNAL_FIELDS: &[Field] = &[
+    field("path", FieldType::Path),
+    field("status", FieldType::Status),
+    field("num_records", FieldType::Count),
+    field("checksum", FieldType::Checksum),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+    optional("timings", FieldType::Timings),
+];
+
+const V1_RAW_FIELDS: &[Field] = &[
+    field("path", FieldType::Path),
+    field("status", FieldType::Status),
+    field("checksum", FieldType::Checksum),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+    optional("timings", FieldType::Timings),
+];
+
+/// Fields of the run-level entry added in version 2, which concerns no single file.
+const V2_RUN_FIELDS: &[Field] = &[
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+];
+
+/// Fields of the entries of jobs that were not checked, e.g. after `--deadline`, added in
+/// version 2.
+const V2_NOT_CHECKED_FIELDS: &[Field] = &[
+    field("check", FieldType::Path),
+    field("paths", FieldType::Messages),
+    field("status", FieldType::Status),
+    field("reason", FieldType::Path),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+];
+
+/// Fields of the lab datum entries added in version 2, which aggregate several FASTQ pairs.
+const V2_LAB_DATUM_FIELDS: &[Field] = &[
+    field("id", FieldType::Path),
+    field("status", FieldType::Status),
+    field("pairs", FieldType::FastqPairs),
+    field("num_records", FieldType::Count),
+    field("total_bases", FieldType::Count),
+    field("declared_num_records", FieldType::Count),
+    field("declared_total_bases", FieldType::Count),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+];
+
+/// Fields of the entity entries added in version 4, which concern several files.
+const V4_ENTITY_FIELDS: &[Field] = &[
+    field("kind", FieldType::EntityKind),
+    field("id", FieldType::Path),
+    field("status", FieldType::Status),
+    field("members", FieldType::Messages),
+    field("failed_members", FieldType::Messages),
+    field("errors", FieldType::Messages),
+    field("warnings", FieldType::Messages),
+    optional("suppressed_findings", FieldType::Findings),
+];
+
+/// Fields added to the run-level entry in version 4. They are optional, because the members of
+/// entries upgraded from earlier versions are unknown.
+const V4_RUN_FIELDS: &[Field] = &[
+    optional("status", FieldType::Status),
+    optional("failed_members", FieldType::Messages),
+];
+
+/// Fields added to the lab datum entries in version 4.
+const V4_LAB_DATUM_FIELDS: &[Field] = &[optional("failed_members", FieldType::Messages)];
+
+/// Fields added to the data of raw checks in version 2.
+const V2_RAW_FIELDS: &[Field] = &[optional("expected_checksum", FieldType::Checksum)];
+
+/// Fields added to the data of FASTQ checks in version 2.
+const V2_FASTQ_FIELDS: &[Field] = &[
+    optional("min_read_length", FieldType::Count),
+    optional("max_read_length", FieldType::Count),
+    optional("n_fraction", FieldType::Number),
+    optional("gc_content", FieldType::Number),
+    optional("base_composition", FieldType::BaseComposition),
+    optional("mean_quality", FieldType::Number),
+    optional("median_quality", FieldType::Count),
+    optional("adapter_fraction", FieldType::Number),
+    optional("poly_g_fraction", FieldType::Number),
+    optional("umi_fraction", FieldType::Number),
+];
+
+/// Fields added to the data of BAM and SAM checks in version 2.
+const V2_ALIGNMENT_FIELDS: &[Field] = &[
+    optional("num_duplicates", FieldType::Count),
+    optional("duplicate_fraction", FieldType::Number),
+    optional("num_mapped", FieldType::Count),
+    optional("mapped_fraction", FieldType::Number),
+    optional("alignment_status", FieldType::AlignmentStatus),
+];
+
+/// Fields added to the data of VCF checks in version 2.
+const V2_VCF_FIELDS: &[Field] = &[optional("gvcf_blocks", FieldType::Counts)];
+
+/// Fields added to the data of every check type in version 2.
+const V2_ADDED_FIELDS: &[Field] = &[
+    field("findings", FieldType::Findings),
+    optional("skipped_checks", FieldType::Messages),
+    optional("not_evaluated", FieldType::NotEvaluated),
+    optional("suppressed_findings", FieldType::Findings),
+    optional("compression", FieldType::Compression),
+    optional("staging_seconds", FieldType::Number),
+    optional("chunk_checksums", FieldType::ChunkChecksums),
+    optional("merkle", FieldType::Merkle),
+    optional("aliases", FieldType::Messages),
+];
+
+fn data_fields(schema_version: u64, check_type: &str) -> Option<Vec<&'static Field>> {
+    let v1_fields = match (check_type, schema_version) {
+        ("fastq", _) => V1_FASTQ_FIELDS,
+        ("bam" | "sam" | "vcf", _) => V1_RECORD_FIELDS,
+        ("raw", _) => V1_RAW_FIELDS,
+        ("fasta" | "bed", 2..) => V2_LENGTH_FIELDS,
+        ("tabix", 2..) => V2_TABIX_FIELDS,
+        ("gzi", 2..) => V2_GZI_FIELDS,
+        ("pod5" | "fast5", 2..) => V2_SIGNAL_FIELDS,
+        ("run", 2..) => V2_RUN_FIELDS,
+        ("lab_datum", 2..) => V2_LAB_DATUM_FIELDS,
+        ("not_checked", 2..) => V2_NOT_CHECKED_FIELDS,
+        ("entity", 4..) => V4_ENTITY_FIELDS,
+        _ => return None,
+    };
+    let added_fields = match schema_version {
+        1 => &[][..],
+        2..=4 => V2_ADDED_FIELDS,
+        _ => return None,
+    };
+    let type_fields = match (check_type, schema_version) {
+        ("run", 4..) => V4_RUN_FIELDS,
+        ("lab_datum", 4..) => V4_LAB_DATUM_FIELDS,
+        ("vcf", 2..) => V2_VCF_FIELDS,
+        ("fastq", 2..) => V2_FASTQ_FIELDS,
+        ("raw", 2..) => V2_RAW_FIELDS,
+        ("bam" | "sam", 2..) => V2_ALIGNMENT_FIELDS,
+        _ => &[][..],
+    };
+    Some(
+        v1_fields
+            .iter()
+            .chain(added_fields)
+            .chain(type_fields)
+            .collect(),
+    )
+}
+
+const ENTRY_FIELDS: &[&str] = &["schema_version", "check_type", "data"];
+
+/// Entry fields added in schema version 3. They are optional, because entries upgraded from
+/// earlier versions have no timestamps.
+const V3_TIMESTAMP_FIELDS: &[&str] = &["created_at", "completed_at"];
+const V3_DURATION_FIELD: &str = "duration_seconds";
+
+/// Checks the timestamps of a report entry, which must be RFC 3339 with an explicit timezone so
+/// that entries written on machines with different local times can be ordered.
+fn lint_times(entry: &Map<String, Value>, issues: &mut Vec<String>) {
+    for name in V3_TIMESTAMP_FIELDS {
+        match entry.get(*name) {
+            Some(Value::String(value)) if DateTime::parse_from_rfc3339(value).is_ok() => {}
+            Some(value) => issues.push(format!(
+                "Field '{name}' must be an RFC 3339 timestamp with a timezone, found {value}"
+            )),
+            None => {}
+        }
+    }
+    if let Some(value) = entry.get(V3_DURATION_FIELD)
+        && !value.as_f64().is_some_and(|seconds| seconds >= 0.0)
+    {
+        issues.push(format!(
+            "Field '{V3_DURATION_FIELD}' must be a non-negative number, found {value}"
+        ));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn lint_entry(entry: &Map<String, Value>, issues: &mut Vec<String>) {
+    let schema_version = match entry.get("schema_version") {
+        None => LEGACY_SCHEMA_VERSION,
+        Some(value) => match value.as_u64() {
+            Some(version) if KNOWN_SCHEMA_VERSIONS.contains(&version) => version,
+            _ => {
+                issues.push(format!(
+                    "Unknown schema version {value}. Known versions: {KNOWN_SCHEMA_VERSIONS:?}"
+                ));
+                return;
+            }
+        },
+    };
+    for key in entry.keys() {
+        let known = ENTRY_FIELDS.contains(&key.as_str())
+            || (schema_version >= 3
+                && (V3_TIMESTAMP_FIELDS.contains(&key.as_str()) || key == V3_DURATION_FIELD));
+        if !known {
+            issues.push(format!("Unknown field '{key}'"));
+        }
+    }
+    if schema_version >= 3 {
+        lint_times(entry, issues);
+    }
+
+    let Some(check_type) = entry.get("check_type") else {
+        issues.push("Missing field 'check_type'".to_string());
+        return;
+    };
+    let Some(fields) = check_type
+        .as_str()
+        .and_then(|check_type| data_fields(schema_version, check_type))
+    else {
+        issues.push(format!(
+            "Unknown check type {check_type} for schema version {schema_version}"
+        ));
+        return;
+    };
+
+    let Some(data) = entry.get("data") else {
+        issues.push("Missing field 'data'".to_string());
+        return;
+    };
+    let Some(data) = data.as_object() else {
+        issues.push("Field 'data' must be an object".to_string());
+        return;
+    };
+
+    for field in &fields {
+        match data.get(field.name) {
+            Some(value) if !field.field_type.matches(value) => issues.push(format!(
+                "Field 'data.{}' must be {}, found {value}",
+                field.name,
+                field.field_type.description()
+            )),
+            None if field.required => {
+                issues.push(format!("Missing field 'data.{}'", field.name));
+            }
+            _ => {}
+        }
+    }
+    for key in data.keys() {
+        if !fields.iter().any(|field| field.name == key) {
+            issues.push(format!("Unknown field 'data.{key}'"));
+        }
+    }
+}
+
+/// Checks each line of a JSONL report against the known report schemas.
+pub fn lint_report<R: BufRead>(reader: R) -> anyhow::Result<Vec<LintIssue>> {
+    let mut issues = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.with_context(|| format!("Failed to read line {line_number}"))?;
+
+        let mut messages = Vec::new();
+        match serde_json::from_str::<Value>(&line) {
+            Ok(Value::Object(entry)) => lint_entry(&entry, &mut messages),
+            Ok(_) => messages.push("Entry must be a JSON object".to_string()),
+            Err(e) => messages.push(format!("Malformed JSON: {e}")),
+        }
+        issues.extend(messages.into_iter().map(|message| LintIssue {
+            line: line_number,
+            message,
+        }));
+    }
+    Ok(issues)
+}
+
+/// Lints the report at `path`, printing all issues to stdout.
+///
+/// Fails if the report contains any issues.
+pub fn lint(path: &Path) -> anyhow::Result<()> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to open report {}", path.display()))?;
+    let issues = lint_report(BufReader::new(file))?;
+
+    for issue in &issues {
+        println!("{}: {issue}", path.display());
+    }
+    if !issues.is_empty() {
+        anyhow::bail!(
+            "Report {} does not match the known schema: found {} issue(s)",
+            path.display(),
+            issues.len()
+        );
+    }
+    println!("{}: OK", path.display());
+    Ok(())
+}
+
+/// Parses a schema version given as `vN` or `N`.
+pub fn parse_schema_version(value: &str) -> Result<u64, String> {
+    let version: u64 = value
+        .strip_prefix('v')
+        .unwrap_or(value)
+        .parse()
+        .map_err(|_| format!("Invalid schema version '{value}', expected e.g. 'v2'"))?;
+    if !KNOWN_SCHEMA_VERSIONS.contains(&version) {
+        return Err(format!(
+            "Unknown schema version {version}. Known versions: {KNOWN_SCHEMA_VERSIONS:?}"
+        ));
+    }
+    Ok(version)
+}
+
+fn data_mut(entry: &mut Map<String, Value>) -> Result<&mut Map<String, Value>, String> {
+    entry
+        .get_mut("data")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| "Missing object field 'data'".to_string())
+}
+
+fn messages(data: &Map<String, Value>, field: &str) -> Result<Vec<String>, String> {
+    serde_json::from_value(data.get(field).cloned().unwrap_or(Value::Array(vec![])))
+        .map_err(|_| format!("Field 'data.{field}' must be an array of strings"))
+}
+
+/// Adds coded findings derived from the error and warning messages.
+fn upgrade_v1_to_v2(entry: &mut Map<String, Value>) -> Result<(), String> {
+    let data = data_mut(entry)?;
+    let errors = messages(data, "errors")?;
+    let warnings = messages(data, "warnings")?;
+    let findings = serde_json::to_value(findings::collect(&errors, &warnings))
+        .expect("Findings should serialize");
+    data.insert("findings".to_string(), findings);
+    Ok(())
+}
+
+/// Only bumps the version: the timestamps of entries written before version 3 are unknown.
+fn upgrade_v2_to_v3(_entry: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+/// Only bumps the version: the findings of a pair in earlier versions stay in the entries of
+/// both files, which do not tell which files form a pair.
+fn upgrade_v3_to_v4(_entry: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+type Upgrade = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// Migrations from each schema version to the next one.
+const UPGRADES: &[(u64, Upgrade)] = &[
+    (1, upgrade_v1_to_v2),
+    (2, upgrade_v2_to_v3),
+    (3, upgrade_v3_to_v4),
+];
+
+/// Rewrites a single report entry into the schema version `target`.
+fn upgrade_entry(entry: &mut Map<String, Value>, target: u64) -> Result<(), String> {
+    let mut version = match entry.get("schema_version") {
+        None => LEGACY_SCHEMA_VERSION,
+        Some(value) => value
+            .as_u64()
+            .filter(|version| KNOWN_SCHEMA_VERSIONS.contains(version))
+            .ok_or_else(|| format!("Unknown schema version {value}"))?,
+    };
+    if version > target {
+        return Err(format!(
+            "Entry has schema version {version}, which is newer than the target version {target}"
+        ));
+    }
+
+    while version < target {
+        let (_, upgrade) = UPGRADES
+            .iter()
+            .find(|(from, _)| *from == version)
+            .ok_or_else(|| format!("No upgrade from schema version {version}"))?;
+        upgrade(entry)?;
+        version += 1;
+    }
+    if version > LEGACY_SCHEMA_VERSION {
+        entry.insert("schema_version".to_string(), Value::from(version));
+    }
+    Ok(())
+}
+
+/// Rewrites all entries of a JSONL report into the schema version `target`.
+pub fn upgrade_report<R: BufRead, W: Write>(
+    reader: R,
+    writer: &mut W,
+    target: u64,
+) -> anyhow::Result<()> {
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.with_context(|| format!("Failed to read line {line_number}"))?;
+        let mut entry: Map<String, Value> = serde_json::from_str(&line)
+            .with_context(|| format!("Line {line_number} is not a JSON object"))?;
+        upgrade_entry(&mut entry, target)
+            .map_err(|e| anyhow::anyhow!("Failed to upgrade line {line_number}: {e}"))?;
+        serde_json::to_writer(&mut *writer, &entry)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Upgrades the report at `path` to schema version `target`, writing the result to `output`, or
+/// to stdout if no output is given.
+pub fn upgrade(path: &Path, target: u64, output: Option<&Path>) -> anyhow::Result<()> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to open report {}", path.display()))?;
+    let reader = BufReader::new(file);
+
+    match output {
+        Some(output) => {
+            let mut writer = BufWriter::new(fs::File::create(output).with_context(|| {
+                format!("Failed to create report file at {}", output.display())
+            })?);
+            upgrade_report(reader, &mut writer, target)?;
+            writer.flush()?;
+        }
+        None => {
+            let mut writer = io::stdout().lock();
+            upgrade_report(reader, &mut writer, target)?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::{Job, RunOptions, run_check};
+    use crate::checks::raw::RawJob;
+
+    fn lint_str(report: &str) -> Vec<LintIssue> {
+        lint_report(report.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_lint_accepts_report_written_by_current_version() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("raw.txt");
+        fs::write(&file_path, "hello")?;
+        let output = dir.path().join("report.jsonl");
+        let jobs = vec![Job::Raw(RawJob {
+            path: file_path,
+            size: 5,
+            expected_sha256: None,
+        })];
+        let options = RunOptions {
+            show_progress: Some(false),
+            ..Default::default()
+        };
+        run_check(jobs, 5, &output, &options)?;
+
+        let issues = lint_report(BufReader::new(fs::File::open(&output)?))?;
+        assert!(issues.is_empty(), "{issues:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_entries_carry_timestamps() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("raw.txt");
+        fs::write(&file_path, "hello")?;
+        let output = dir.path().join("report.jsonl");
+        let jobs = vec![Job::Raw(RawJob {
+            path: file_path,
+            size: 5,
+            expected_sha256: None,
+        })];
+        let options = RunOptions {
+            show_progress: Some(false),
+            ..Default::default()
+        };
+        run_check(jobs, 5, &output, &options)?;
+
+        let entry: Value = serde_json::from_str(
+            fs::read_to_string(&output)?
+                .lines()
+                .next()
+                .unwrap_or_default(),
+        )?;
+        let timestamp = |name: &str| {
+            let value = entry[name].as_str().unwrap_or_default();
+            assert!(value.ends_with('Z'), "{name}: {value}");
+            DateTime::parse_from_rfc3339(value).unwrap()
+        };
+        assert!(timestamp("created_at") <= timestamp("completed_at"));
+        assert!(entry["duration_seconds"].as_f64().is_some_and(|s| s >= 0.0));
+
+        let report = concat!(
+            r#"{"schema_version":3,"check_type":"run","created_at":"2025-01-01T10:00:00+02:00","completed_at":"2025-01-01 09:00:00","duration_seconds":-1,"data":{"errors":[],"warnings":[],"findings":[]}}"#,
+            "\n",
+            r#"{"schema_version":2,"check_type":"run","created_at":"2025-01-01T08:00:00Z","data":{"errors":[],"warnings":[],"findings":[]}}"#,
+            "\n",
+        );
+        let issues = lint_str(report);
+        let messages: Vec<(usize, &str)> = issues
+            .iter()
+            .map(|issue| (issue.line, issue.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    1,
+                    "Field 'completed_at' must be an RFC 3339 timestamp with a timezone, found \"2025-01-01 09:00:00\""
+                ),
+                (
+                    1,
+                    "Field 'duration_seconds' must be a non-negative number, found -1"
+                ),
+                (2, "Unknown field 'created_at'"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_reports_malformed_lines_and_unknown_fields() {
+        let report = concat!(
+            r#"{"check_type":"bam","data":{"path":"a.bam","status":"OK","num_records":1,"checksum":null,"errors":[],"warnings":[]}}"#,
+            "\n",
+            "{not json\n",
+            r#"{"check_type":"raw","data":{"path":"b","status":"FINE","checksum":null,"errors":[],"warnings":[],"extra":1}}"#,
+            "\n",
+            r#"{"schema_version":99,"check_type":"raw","data":{}}"#,
+            "\n",
+            r#"{"schema_version":2,"check_type":"raw","data":{"path":"c","status":"OK","checksum":"ab","chunk_checksums":{"chunk_size":4,"sha256":["ab"]},"errors":[],"warnings":[],"findings":[]}}"#,
+            "\n",
+            r#"{"schema_version":2,"check_type":"raw","data":{"path":"d","status":"OK","checksum":"ab","chunk_checksums":["ab"],"errors":[],"warnings":[],"findings":[]}}"#,
+            "\n",
+        );
+        let issues = lint_str(report);
+        let lines: Vec<usize> = issues.iter().map(|issue| issue.line).collect();
+        assert_eq!(lines, vec![2, 3, 3, 4, 6]);
+        assert!(issues[0].message.starts_with("Malformed JSON"));
+        assert!(issues[1].message.contains("'data.status'"));
+        assert_eq!(issues[2].message, "Unknown field 'data.extra'");
+        assert!(issues[3].message.contains("Unknown schema version 99"));
+    }
+
+    #[test]
+    fn test_upgrade_v1_report_to_current_version() -> anyhow::Result<()> {
+        let report = concat!(
+            r#"{"check_type":"bam","data":{"path":"a.bam","status":"OK","num_records":1,"checksum":null,"errors":[],"warnings":["File contains 3 secondary alignment(s). First detected at record #1 ('r0')."]}}"#,
+            "\n",
+            r#"{"check_type":"fastq","data":{"path":"a.fq","status":"ERROR","num_records":0,"mean_read_length":null,"checksum":null,"errors":["File is empty. Expected at least one record."],"warnings":[]}}"#,
+            "\n",
+        );
+
+        let mut upgraded = Vec::new();
+        upgrade_report(report.as_bytes(), &mut upgraded, CURRENT_SCHEMA_VERSION)?;
+        assert!(lint_report(upgraded.as_slice())?.is_empty());
+
+        let entries: Vec<Value> = upgraded
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(entries[0]["schema_version"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(
+            entries[0]["data"]["findings"][0]["code"],
+            "alignment.secondary"
+        );
+        assert_eq!(entries[0]["data"]["findings"][0]["severity"], "warning");
+        assert_eq!(entries[1]["data"]["findings"][0]["code"], "file.empty");
+        assert_eq!(entries[1]["data"]["findings"][0]["severity"], "error");
+
+        // Downgrades are not supported
+        let mut downgraded = Vec::new();
+        assert!(upgrade_report(upgraded.as_slice(), &mut downgraded, 1).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use crate::checker::{Job, RunOptions};
+use crate::logging::LogFormat;
+use crate::options;
+use anyhow::Context;
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Environment variables that influence how a run behaves.
+const RELEVANT_ENV_VARS: &[&str] = &[
+    "RAYON_NUM_THREADS",
+    "NOTIFY_SOCKET",
+    "WATCHDOG_USEC",
+    options::CONTINUE_ON_ERROR_ENV,
+    options::SHOW_PROGRESS_ENV,
+];
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+    arguments: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Environment {
+    os: &'static str,
+    arch: &'static str,
+    current_dir: Option<PathBuf>,
+    num_threads: usize,
+    variables: BTreeMap<&'static str, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Configuration<'a> {
+    output: &'a Path,
+    threads: Option<usize>,
+    log_format: LogFormat,
+    #[serde(flatten)]
+    options: &'a RunOptions,
+}
+
+/// Everything needed to reproduce a run: the resolved jobs, the configuration, the tool version
+/// and the relevant parts of the environment.
+#[derive(Debug, Serialize)]
+struct RerunBundle<'a> {
+    created_at: String,
+    tool: Tool,
+    environment: Environment,
+    configuration: Configuration<'a>,
+    total_bytes: u64,
+    jobs: &'a [Job],
+}
+
+pub fn write_rerun_bundle(
+    path: &Path,
+    jobs: &[Job],
+    total_bytes: u64,
+    output: &Path,
+    threads: Option<usize>,
+    log_format: LogFormat,
+    options: &RunOptions,
+) -> anyhow::Result<()> {
+    let bundle = RerunBundle {
+        created_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        tool: Tool {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            arguments: std::env::args().collect(),
+        },
+        environment: Environment {
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            current_dir: std::env::current_dir().ok(),
+            num_threads: rayon::current_num_threads(),
+            variables: RELEVANT_ENV_VARS
+                .iter()
+                .filter_map(|&name| std::env::var(name).ok().map(|value| (name, value)))
+                .collect(),
+        },
+        configuration: Configuration {
+            output,
+            threads,
+            log_format,
+            options,
+        },
+        total_bytes,
+        jobs,
+    };
+
+    write_bundle(path, &bundle)
+}
+
+fn write_bundle(path: &Path, bundle: &impl Serialize) -> anyhow::Result<()> {
+    let file = fs::File::create(path)
+        .with_context(|| format!("Failed to create rerun bundle at {}", path.display()))?;
+    serde_json::to_writer_pretty(file, bundle)
+        .with_context(|| format!("Failed to write rerun bundle to {}", path.display()))?;
+    Ok(())
+}
+
+/// A rerun bundle read back from disk. Only the jobs are parsed; everything else is kept as it
+/// was written.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedBundle {
+    created_at: String,
+    tool: Value,
+    environment: Value,
+    configuration: Value,
+    total_bytes: u64,
+    jobs: Vec<Job>,
+}
+
+impl SavedBundle {
+    /// Keeps only the jobs with a file among `failed`.
+    pub fn retain_failed(&mut self, failed: &HashSet<PathBuf>) {
+        self.jobs
+            .retain(|job| job.paths().iter().any(|path| failed.contains(path)));
+        self.total_bytes = self.jobs.iter().map(Job::size).sum();
+    }
+}
+
+pub fn read_rerun_bundle(path: &Path) -> anyhow::Result<SavedBundle> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to open rerun bundle {}", path.display()))?;
+    serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Failed to parse rerun bundle {}", path.display()))
+}
+
+/// Paths of the files with an `ERROR` entry in the report at `path`, including the first files
+/// of the entities, e.g. FASTQ pairs, with errors concerning the entity as a whole.
+pub fn failed_paths(path: &Path) -> anyhow::Result<HashSet<PathBuf>> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to open report {}", path.display()))?;
+    let mut failed = HashSet::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let entry: Value = serde_json::from_str(&line?)
+            .with_context(|| format!("Failed to parse line {} of {}", i + 1, path.display()))?;
+        let data = &entry["data"];
+        if data["status"] != "ERROR" {
+            continue;
+        }
+        let id = (entry["check_type"] == "entity").then(|| &data["id"]);
+        let paths = data["paths"].as_array().into_iter().flatten();
+        failed.extend(
+            [&data["path"]]
+                .into_iter()
+                .chain(id)
+                .chain(paths)
+                .filter_map(Value::as_str)
+                .map(PathBuf::from),
+        );
+    }
+    Ok(failed)
+}
+
+/// Writes the jobs of the rerun bundle at `bundle` that failed in `report` to a new bundle at
+/// `output`, to repeat only those after the files have been fixed.
+pub fn write_failed_bundle(bundle: &Path, report: &Path, output: &Path) -> anyhow::Result<()> {
+    let mut saved = read_rerun_bundle(bundle)?;
+    saved.retain_failed(&failed_paths(report)?);
+    write_bundle(output, &saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::run_check;
+    use crate::checks::fastq::{ReadLengthCheck, SingleFastqJob};
+    use crate::checks::raw::RawJob;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_bundle_round_trip() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let raw_path = dir.path().join("notes.txt");
+        fs::write(&raw_path, "hello")?;
+        let fastq_path = dir.path().join("reads.fastq");
+        // The quality string is shorter than the sequence.
+        fs::write(&fastq_path, "@r1\nACGT\n+\nFF\n")?;
+        let jobs = vec![
+            Job::Raw(RawJob {
+                path: raw_path.clone(),
+                size: 5,
+                expected_sha256: None,
+            }),
+            Job::SingleFastq(SingleFastqJob {
+                path: fastq_path.clone(),
+                length_check: ReadLengthCheck::Fixed(4),
+                declared_read_length: None,
+                size: 15,
+            }),
+        ];
+        let options = RunOptions {
+            continue_on_error: true,
+            show_progress: Some(false),
+            ..Default::default()
+        };
+        let bundle_path = dir.path().join("bundle.json");
+        let report_path = dir.path().join("report.jsonl");
+        write_rerun_bundle(
+            &bundle_path,
+            &jobs,
+            20,
+            &report_path,
+            Some(2),
+            LogFormat::Json,
+            &options,
+        )?;
+
+        let saved = read_rerun_bundle(&bundle_path)?;
+        assert_eq!(saved.total_bytes, 20);
+        assert_eq!(
+            serde_json::to_value(&saved.jobs)?,
+            serde_json::to_value(&jobs)?
+        );
+        assert_eq!(saved.configuration["threads"], 2);
+        assert_eq!(saved.configuration["log_format"], "json");
+
+        run_check(jobs, 20, &report_path, &options)?;
+        let failed_bundle_path = dir.path().join("failed.json");
+        write_failed_bundle(&bundle_path, &report_path, &failed_bundle_path)?;
+        let failed = read_rerun_bundle(&failed_bundle_path)?;
+        let failed_paths: Vec<_> = failed.jobs.iter().flat_map(Job::paths).collect();
+        assert_eq!(failed_paths, [fastq_path]);
+        assert_eq!(failed.total_bytes, 15);
+        // Everything but the jobs is kept as it was.
+        assert_eq!(failed.created_at, saved.created_at);
+        assert_eq!(failed.configuration, saved.configuration);
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+//! nf-core style samplesheets given via `--samplesheet`.
+//!
+//! A samplesheet is a CSV file with a header line naming at least the `sample` and `fastq_1`
+//! columns, e.g. `sample,fastq_1,fastq_2,strandedness`. Rows with a `fastq_2` are paired-end
+//! samples, all others single-end ones. Other columns are ignored. Relative paths are resolved
+//! against the directory of the samplesheet.
+//!
+//! `--emit-samplesheet` writes the FASTQ files found by `--scan` and `--tar` in this format, so
+//! that the pairing of the files can be reviewed before the checks run.
+
+use anyhow::Context;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// FASTQ files of a row of a samplesheet.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Row {
+    pub sample: String,
+    pub fastq_1: PathBuf,
+    pub fastq_2: Option<PathBuf>,
+}
+
+/// Reads all rows of a samplesheet. Empty lines are skipped.
+pub fn read(path: &Path) -> anyhow::Result<Vec<Row>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read samplesheet {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    let mut lines = content
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let (_, header) = lines
+        .next()
+        .with_context(|| format!("Samplesheet {} is empty", path.display()))?;
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    let column = |name: &str| columns.iter().position(|column| *column == name);
+    let (Some(sample_column), Some(fastq_1_column)) = (column("sample"), column("fastq_1")) else {
+        anyhow::bail!(
+            "Header of samplesheet {} must contain the columns 'sample' and 'fastq_1'",
+            path.display()
+        );
+    };
+    let fastq_2_column = column("fastq_2");
+
+    lines
+        .map(|(i, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != columns.len() {
+                anyhow::bail!(
+                    "Invalid line {} in samplesheet {}: expected {} fields, found {}",
+                    i + 1,
+                    path.display(),
+                    columns.len(),
+                    fields.len()
+                );
+            }
+            let (sample, fastq_1) = (fields[sample_column], fields[fastq_1_column]);
+            if sample.is_empty() || fastq_1.is_empty() {
+                anyhow::bail!(
+                    "Invalid line {} in samplesheet {}: 'sample' and 'fastq_1' must not be empty",
+                    i + 1,
+                    path.display()
+                );
+            }
+            Ok(Row {
+                sample: sample.to_string(),
+                fastq_1: base.join(fastq_1),
+                fastq_2: fastq_2_column
+                    .map(|column| fields[column])
+                    .filter(|fastq_2| !fastq_2.is_empty())
+                    .map(|fastq_2| base.join(fastq_2)),
+            })
+        })
+        .collect()
+}
+
+/// Writes a samplesheet with the columns `sample`, `fastq_1` and `fastq_2`. Paths are made
+/// absolute, so that the samplesheet can be read from any directory.
+pub fn write(path: &Path, rows: &[Row]) -> anyhow::Result<()> {
+    let absolute = |path: &Path| -> anyhow::Result<String> {
+        let path = std::path::absolute(path)?;
+        let path = path.to_string_lossy();
+        if path.contains(',') {
+            anyhow::bail!(
+                "FASTQ path {path} contains a comma, which cannot be written to a samplesheet"
+            );
+        }
+        Ok(path.into_owned())
+    };
+    let mut content = String::from("sample,fastq_1,fastq_2\n");
+    for row in rows {
+        content.push_str(&format!(
+            "{},{},{}\n",
+            row.sample,
+            absolute(&row.fastq_1)?,
+            row.fastq_2
+                .as_deref()
+                .map(absolute)
+                .transpose()?
+                .unwrap_or_default()
+        ));
+    }
+    fs::write(path, content)
+        .with_context(|| format!("Failed to write samplesheet {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_samplesheet() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let samplesheet = dir.path().join("samplesheet.csv");
+        fs::write(
+            &samplesheet,
+            "sample,fastq_1,fastq_2,strandedness\r\n\
+             tumor,tumor_R1.fastq.gz,tumor_R2.fastq.gz,auto\r\n\
+             \n\
+             normal,/data/normal.fastq.gz,,auto\n",
+        )?;
+        assert_eq!(
+            read(&samplesheet)?,
+            vec![
+                Row {
+                    sample: "tumor".to_string(),
+                    fastq_1: dir.path().join("tumor_R1.fastq.gz"),
+                    fastq_2: Some(dir.path().join("tumor_R2.fastq.gz")),
+                },
+                Row {
+                    sample: "normal".to_string(),
+                    fastq_1: PathBuf::from("/data/normal.fastq.gz"),
+                    fastq_2: None,
+                },
+            ]
+        );
+
+        fs::write(&samplesheet, "sample,fastq_1\ntumor,a.fq.gz,b.fq.gz\n")?;
+        let error = read(&samplesheet).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid line 2"), "{error}");
+
+        fs::write(&samplesheet, "sample,bam\ntumor,a.bam\n")?;
+        let error = read(&samplesheet).unwrap_err().to_string();
+        assert!(error.contains("'fastq_1'"), "{error}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_samplesheet_round_trip() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let samplesheet = dir.path().join("out").join("samplesheet.csv");
+        fs::create_dir(dir.path().join("out"))?;
+        let rows = vec![
+            Row {
+                sample: "tumor".to_string(),
+                fastq_1: dir.path().join("tumor_R1.fastq.gz"),
+                fastq_2: Some(dir.path().join("tumor_R2.fastq.gz")),
+            },
+            Row {
+                sample: "single".to_string(),
+                fastq_1: dir.path().join("single.fastq.gz"),
+                fastq_2: None,
+            },
+        ];
+        write(&samplesheet, &rows)?;
+        assert_eq!(read(&samplesheet)?, rows);
+        Ok(())
+    }
+}
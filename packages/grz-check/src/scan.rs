@@ -0,0 +1,560 @@
+//! Discovery of input files in directories given via `--scan` and archives given via `--tar`.
+//!
+//! Directories are listed in parallel on a dedicated thread pool, so that a walk streaming its
+//! files into the checks cannot be starved by them. Listings below a mount with a `[[mount]]`
+//! limit take a slot of that mount, like the checks of its files.
+//!
+//! The device and inode of each file found by the walk are recorded, and verified again when the
+//! file is opened for its check, so that a file replaced in the meantime, e.g. by an upload that
+//! is still in progress, is reported instead of the digest of a file of the same name.
+
+use crate::archive;
+use crate::duplicates::{self, FileId};
+use crate::mounts::{MountLimit, Mounts};
+use anyhow::Context;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+/// Extensions of files accepted in GRZ submissions.
+pub const ACCEPTED_EXTENSIONS: &[&str] = &[
+    ".bam",
+    ".bed",
+    ".bed.gz",
+    ".fast5",
+    ".fastq.gz",
+    ".fq.gz",
+    ".pod5",
+    ".vcf",
+    ".vcf.gz",
+];
+
+const FASTQ_EXTENSIONS: &[&str] = &[
+    ".fastq.gz",
+    ".fq.gz",
+    ".fastq.zst",
+    ".fq.zst",
+    ".fastq.xz",
+    ".fq.xz",
+    ".fastq.bz2",
+    ".fq.bz2",
+    ".fastq",
+    ".fq",
+];
+const BAM_EXTENSIONS: &[&str] = &[".bam"];
+const SAM_EXTENSIONS: &[&str] = &[".sam", ".sam.gz"];
+const VCF_EXTENSIONS: &[&str] = &[".vcf", ".vcf.gz", ".vcf.bgz", ".bcf"];
+const BED_EXTENSIONS: &[&str] = &[".bed", ".bed.gz"];
+const POD5_EXTENSIONS: &[&str] = &[".pod5"];
+const FAST5_EXTENSIONS: &[&str] = &[".fast5"];
+const FASTA_EXTENSIONS: &[&str] = &[".fa", ".fa.gz", ".fasta", ".fasta.gz", ".fna", ".fna.gz"];
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    extensions.iter().any(|extension| name.ends_with(extension))
+}
+
+/// Returns an error message if the extension of `path` is not accepted in GRZ submissions.
+pub fn check_extension(path: &Path) -> Option<String> {
+    if has_extension(path, ACCEPTED_EXTENSIONS) {
+        None
+    } else {
+        Some(format!(
+            "File extension is not accepted for GRZ submissions. Expected one of: {}.",
+            ACCEPTED_EXTENSIONS.join(", ")
+        ))
+    }
+}
+
+/// Files found in scanned directories, grouped by the check to apply.
+#[derive(Debug, Default)]
+pub struct ScannedFiles {
+    /// FASTQ files without a mate.
+    pub fastq: Vec<PathBuf>,
+    /// Mates of paired-end FASTQ files, see [`FastqPair`].
+    pub fastq_pairs: Vec<FastqPair>,
+    pub bam: Vec<PathBuf>,
+    pub sam: Vec<PathBuf>,
+    pub vcf: Vec<PathBuf>,
+    pub fasta: Vec<PathBuf>,
+    pub bed: Vec<PathBuf>,
+    pub pod5: Vec<PathBuf>,
+    pub fast5: Vec<PathBuf>,
+    pub raw: Vec<PathBuf>,
+}
+
+impl ScannedFiles {
+    /// Moves all files of `other` to the end of these files.
+    fn append(&mut self, mut other: ScannedFiles) {
+        self.fastq.append(&mut other.fastq);
+        self.fastq_pairs.append(&mut other.fastq_pairs);
+        self.bam.append(&mut other.bam);
+        self.sam.append(&mut other.sam);
+        self.vcf.append(&mut other.vcf);
+        self.fasta.append(&mut other.fasta);
+        self.bed.append(&mut other.bed);
+        self.pod5.append(&mut other.pod5);
+        self.fast5.append(&mut other.fast5);
+        self.raw.append(&mut other.raw);
+    }
+
+    /// Sorts the files of each kind by path, which is the order of a sequential walk visiting
+    /// the entries of each directory in sorted order.
+    fn sort(&mut self) {
+        self.fastq.sort();
+        self.fastq_pairs.sort_by(|a, b| a.fq1_path.cmp(&b.fq1_path));
+        self.bam.sort();
+        self.sam.sort();
+        self.vcf.sort();
+        self.fasta.sort();
+        self.bed.sort();
+        self.pod5.sort();
+        self.fast5.sort();
+        self.raw.sort();
+    }
+
+    fn add(&mut self, path: PathBuf) {
+        let files = if has_extension(&path, FASTQ_EXTENSIONS) {
+            &mut self.fastq
+        } else if has_extension(&path, BAM_EXTENSIONS) {
+            &mut self.bam
+        } else if has_extension(&path, SAM_EXTENSIONS) {
+            &mut self.sam
+        } else if has_extension(&path, VCF_EXTENSIONS) {
+            &mut self.vcf
+        } else if has_extension(&path, FASTA_EXTENSIONS) {
+            &mut self.fasta
+        } else if has_extension(&path, BED_EXTENSIONS) {
+            &mut self.bed
+        } else if has_extension(&path, POD5_EXTENSIONS) {
+            &mut self.pod5
+        } else if has_extension(&path, FAST5_EXTENSIONS) {
+            &mut self.fast5
+        } else {
+            &mut self.raw
+        };
+        files.push(path);
+    }
+}
+
+/// Markers of the first read in FASTQ file names, each with the marker of the second read.
+/// `_R1_` matches Illumina names like `NA12878_S1_L001_R1_001.fastq.gz`.
+const READ_MARKERS: &[(&str, &str)] = &[("_R1_", "_R2_"), ("_R1", "_R2"), ("_1", "_2")];
+
+/// A pair of scanned FASTQ files whose names only differ in the read marker, e.g.
+/// `tumor_R1.fastq.gz` and `tumor_R2.fastq.gz`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FastqPair {
+    /// Part of the file name before the read marker.
+    pub sample: String,
+    pub fq1_path: PathBuf,
+    pub fq2_path: PathBuf,
+}
+
+/// Returns the file name of a FASTQ file without its extension.
+pub fn fastq_stem(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    FASTQ_EXTENSIONS
+        .iter()
+        .find(|extension| name.to_lowercase().ends_with(*extension))
+        .and_then(|extension| name.get(..name.len() - extension.len()))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// Returns the sample name and the path of the second read if `path` is named like the first
+/// read of a pair. Markers other than `_R1_` must directly precede the extension.
+fn mate_of(path: &Path) -> Option<(String, PathBuf)> {
+    let name = path.file_name()?.to_str()?;
+    let extension = FASTQ_EXTENSIONS
+        .iter()
+        .find(|extension| name.to_lowercase().ends_with(*extension))?;
+    let stem = name.get(..name.len() - extension.len())?;
+    READ_MARKERS.iter().find_map(|(first, second)| {
+        let position = if first.ends_with('_') {
+            stem.rfind(first)?
+        } else {
+            stem.strip_suffix(first)?.len()
+        };
+        let sample = &stem[..position];
+        let mate = format!(
+            "{sample}{second}{}{}",
+            &stem[position + first.len()..],
+            &name[stem.len()..]
+        );
+        Some((sample.to_string(), path.with_file_name(mate)))
+    })
+}
+
+impl ScannedFiles {
+    /// Moves FASTQ files whose mate was also found to [`ScannedFiles::fastq_pairs`].
+    fn pair_fastq(&mut self) {
+        let fastq = std::mem::take(&mut self.fastq);
+        let found: HashSet<&PathBuf> = fastq.iter().collect();
+        let mut paired = HashSet::new();
+        for path in &fastq {
+            if paired.contains(path) {
+                continue;
+            }
+            if let Some((sample, fq2_path)) = mate_of(path)
+                && found.contains(&fq2_path)
+                && !paired.contains(&fq2_path)
+            {
+                paired.insert(path.clone());
+                paired.insert(fq2_path.clone());
+                self.fastq_pairs.push(FastqPair {
+                    sample,
+                    fq1_path: path.clone(),
+                    fq2_path,
+                });
+            }
+        }
+        self.fastq = fastq
+            .into_iter()
+            .filter(|path| !paired.contains(path))
+            .collect();
+    }
+}
+
+/// Identities of the files found by walks, by their path.
+static DISCOVERED: LazyLock<Mutex<HashMap<PathBuf, FileId>>> = LazyLock::new(Default::default);
+
+/// Returns an error if `file`, opened from `path`, is not the file that a walk found at `path`.
+/// Files that were not found by a walk are not verified.
+pub fn verify_identity(path: &Path, file: &fs::File) -> io::Result<()> {
+    let Some(&discovered) = DISCOVERED.lock().unwrap().get(path) else {
+        return Ok(());
+    };
+    match duplicates::metadata_id(&file.metadata()?) {
+        Some(opened) if opened != discovered => Err(io::Error::other(format!(
+            "File was replaced after it was scanned (device {}, inode {} when scanned; device {}, inode {} when opened). It was likely overwritten by an upload that was still in progress",
+            discovered.0, discovered.1, opened.0, opened.1
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// State shared by the tasks of a parallel walk.
+struct Walk<'a> {
+    mounts: Mounts,
+    on_files: &'a (dyn Fn(ScannedFiles) -> anyhow::Result<()> + Sync),
+    error: Mutex<Option<anyhow::Error>>,
+    /// Identities of the directories listed so far, so that directories reached again through
+    /// symbolic links, e.g. a link to a parent, are listed only once.
+    visited: Mutex<HashSet<FileId>>,
+}
+
+impl Walk<'_> {
+    /// Lists `dir`, passing its files to `on_files` and walking its subdirectories in further
+    /// tasks. The walk stops listing directories after the first error.
+    fn visit<'scope>(&'scope self, scope: &rayon::Scope<'scope>, dir: PathBuf) {
+        if self.error.lock().unwrap().is_some() {
+            return;
+        }
+        if let Some(id) = duplicates::file_id(&dir)
+            && !self.visited.lock().unwrap().insert(id)
+        {
+            return;
+        }
+        let entries = {
+            let _permit = self.mounts.acquire(std::slice::from_ref(&dir));
+            fs::read_dir(&dir)
+                .and_then(|entries| {
+                    entries
+                        .map(|entry| entry.map(|entry| entry.path()))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .with_context(|| format!("Failed to read directory {}", dir.display()))
+        };
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.error.lock().unwrap().get_or_insert(e);
+                return;
+            }
+        };
+
+        let mut files = ScannedFiles::default();
+        let mut ids = Vec::new();
+        for path in entries {
+            if path.is_dir() {
+                scope.spawn(move |scope| self.visit(scope, path));
+            } else {
+                if let Some(id) = duplicates::file_id(&path) {
+                    ids.push((path.clone(), id));
+                }
+                files.add(path);
+            }
+        }
+        DISCOVERED.lock().unwrap().extend(ids);
+        // Both mates of a pair are in the same directory.
+        files.pair_fastq();
+        files.sort();
+        if let Err(e) = (self.on_files)(files) {
+            self.error.lock().unwrap().get_or_insert(e);
+        }
+    }
+}
+
+/// Recursively walks `dirs` with `threads` threads, passing the files of each directory to
+/// `on_files` as soon as it has been listed. At most the configured number of directories below
+/// each mount of `mount_limits` are listed at the same time.
+pub fn walk(
+    dirs: &[PathBuf],
+    mount_limits: &[MountLimit],
+    threads: usize,
+    on_files: &(dyn Fn(ScannedFiles) -> anyhow::Result<()> + Sync),
+) -> anyhow::Result<()> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("scan-{i}"))
+        .build()
+        .context("Failed to set up the thread pool of the directory scan")?;
+    let walk = Walk {
+        mounts: Mounts::new(mount_limits),
+        on_files,
+        error: Mutex::new(None),
+        visited: Mutex::default(),
+    };
+    pool.scope(|scope| {
+        for dir in dirs {
+            let walk = &walk;
+            let dir = dir.clone();
+            scope.spawn(move |scope| walk.visit(scope, dir));
+        }
+    });
+    match walk.error.into_inner().unwrap() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Collects all members of `archives`, classified by their extension.
+pub fn scan_archives(archives: &[PathBuf]) -> anyhow::Result<ScannedFiles> {
+    let mut files = ScannedFiles::default();
+    for archive in archives {
+        let members = archive::members(archive)
+            .with_context(|| format!("Failed to read archive {}", archive.display()))?;
+        for member in members {
+            files.add(member);
+        }
+    }
+    files.pair_fastq();
+    Ok(files)
+}
+
+/// Recursively collects all files below `dirs` and all members of `archives`, classified by
+/// their extension. FASTQ files are paired by the read markers in their names.
+pub fn scan(
+    dirs: &[PathBuf],
+    archives: &[PathBuf],
+    mount_limits: &[MountLimit],
+    threads: usize,
+) -> anyhow::Result<ScannedFiles> {
+    let mut files = ScannedFiles::default();
+    for dir in dirs {
+        let found = Mutex::new(ScannedFiles::default());
+        walk(std::slice::from_ref(dir), mount_limits, threads, &|files| {
+            found.lock().unwrap().append(files);
+            Ok(())
+        })?;
+        let mut found = found.into_inner().unwrap();
+        found.sort();
+        files.append(found);
+    }
+    files.append(scan_archives(archives)?);
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_scan_classifies_files_recursively() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join("files"))?;
+        for name in [
+            "a.fastq.gz",
+            "b.BAM",
+            "c.vcf.gz",
+            "ref.fa",
+            "ref.fa.fai",
+            "notes.bak",
+            "reads.pod5",
+        ] {
+            fs::write(dir.path().join("files").join(name), "")?;
+        }
+        fs::write(dir.path().join("metadata.json"), "{}")?;
+
+        let files = scan(&[dir.path().to_path_buf()], &[], &[], 4)?;
+        let names = |paths: &[PathBuf]| -> Vec<String> {
+            paths
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+        assert_eq!(names(&files.fastq), vec!["a.fastq.gz"]);
+        assert_eq!(names(&files.bam), vec!["b.BAM"]);
+        assert_eq!(names(&files.vcf), vec!["c.vcf.gz"]);
+        assert_eq!(names(&files.fasta), vec!["ref.fa"]);
+        assert_eq!(names(&files.pod5), vec!["reads.pod5"]);
+        assert_eq!(
+            names(&files.raw),
+            vec!["notes.bak", "ref.fa.fai", "metadata.json"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_pairs_fastq_files() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        for name in [
+            "NA12878_S1_L001_R1_001.fastq.gz",
+            "NA12878_S1_L001_R2_001.fastq.gz",
+            "normal_2.fq.gz",
+            "normal_1.fq.gz",
+            "orphan_R1.fastq.gz",
+            "single.fastq.gz",
+        ] {
+            fs::write(dir.path().join(name), "")?;
+        }
+
+        let files = scan(&[dir.path().to_path_buf()], &[], &[], 4)?;
+        assert_eq!(
+            files.fastq_pairs,
+            vec![
+                FastqPair {
+                    sample: "NA12878_S1_L001".to_string(),
+                    fq1_path: dir.path().join("NA12878_S1_L001_R1_001.fastq.gz"),
+                    fq2_path: dir.path().join("NA12878_S1_L001_R2_001.fastq.gz"),
+                },
+                FastqPair {
+                    sample: "normal".to_string(),
+                    fq1_path: dir.path().join("normal_1.fq.gz"),
+                    fq2_path: dir.path().join("normal_2.fq.gz"),
+                },
+            ]
+        );
+        assert_eq!(
+            files.fastq,
+            vec![
+                dir.path().join("orphan_R1.fastq.gz"),
+                dir.path().join("single.fastq.gz"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk_passes_files_per_directory() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        for sub in ["a", "a/b", "c"] {
+            fs::create_dir(dir.path().join(sub))?;
+        }
+        for name in ["a/x_R1.fastq.gz", "a/x_R2.fastq.gz", "a/b/y.bam", "c/z.txt"] {
+            fs::write(dir.path().join(name), "")?;
+        }
+        let mount_limits = [MountLimit {
+            path: dir.path().to_path_buf(),
+            concurrency: 1,
+        }];
+
+        let batches = Mutex::new(Vec::new());
+        walk(&[dir.path().to_path_buf()], &mount_limits, 4, &|files| {
+            batches.lock().unwrap().push(files);
+            Ok(())
+        })?;
+        let batches = batches.into_inner().unwrap();
+        // One batch for each directory, including the scanned one
+        assert_eq!(batches.len(), 4);
+        // The mates of a pair are paired within their directory.
+        assert!(batches.iter().all(|files| files.fastq.is_empty()));
+        assert_eq!(
+            batches
+                .iter()
+                .flat_map(|files| &files.fastq_pairs)
+                .map(|pair| &pair.sample)
+                .collect::<Vec<_>>(),
+            vec!["x"]
+        );
+        assert!(
+            batches
+                .iter()
+                .any(|files| files.bam == vec![dir.path().join("a/b/y.bam")])
+        );
+        assert!(
+            batches
+                .iter()
+                .any(|files| files.raw == vec![dir.path().join("c/z.txt")])
+        );
+
+        let missing = dir.path().join("missing");
+        assert!(walk(&[missing], &[], 2, &|_| Ok(())).is_err());
+        // A batch that cannot be handled stops the walk with its error.
+        let error = walk(&[dir.path().to_path_buf()], &[], 2, &|_| {
+            anyhow::bail!("Unusable batch")
+        })
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Unusable batch");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_directories_are_listed_once() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir_all(dir.path().join("a/b"))?;
+        fs::write(dir.path().join("a/b/y.bam"), "")?;
+        // A loop back to the scanned directory and a second path to a/b.
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("a/b/loop"))?;
+        std::os::unix::fs::symlink(dir.path().join("a/b"), dir.path().join("b"))?;
+
+        let files = scan(&[dir.path().to_path_buf()], &[], &[], 4)?;
+        assert_eq!(files.bam.len(), 1, "{:?}", files.bam);
+        assert!(files.raw.is_empty(), "{:?}", files.raw);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replaced_files_fail_to_open() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let kept = dir.path().join("kept.bam");
+        let replaced = dir.path().join("replaced.bam");
+        fs::write(&kept, "kept")?;
+        fs::write(&replaced, "partial")?;
+        scan(&[dir.path().to_path_buf()], &[], &[], 2)?;
+
+        // Uploads typically write to a temporary file and rename it over the target.
+        let upload = dir.path().join("replaced.bam.part");
+        fs::write(&upload, "complete")?;
+        fs::rename(&upload, &replaced)?;
+
+        assert!(archive::open(&kept).is_ok());
+        let error = archive::open(&replaced).err().unwrap();
+        assert!(
+            error
+                .to_string()
+                .starts_with("File was replaced after it was scanned")
+        );
+        assert!(archive::open_section(&replaced).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_extension() {
+        assert!(check_extension(Path::new("x/sample_R1.fastq.gz")).is_none());
+        assert!(check_extension(Path::new("x/sample.vcf")).is_none());
+        assert!(check_extension(Path::new("x/sample_R1.fastq")).is_some());
+        assert!(check_extension(Path::new("x/sample.bam.bak")).is_some());
+        assert!(check_extension(Path::new("x/table.xlsx")).is_some());
+    }
+}
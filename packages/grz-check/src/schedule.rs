@@ -0,0 +1,159 @@
+//! Order in which jobs are started, selected via `--schedule`.
+//!
+//! Jobs are handed to the worker threads one at a time from a [`Queue`], so the order of the
+//! queue is the order in which they start. The balanced schedule starts huge files first to avoid
+//! a long tail at the end of the run, but runs at most half of the threads on them, so that the
+//! other threads keep working through the small files and the report keeps growing.
+//!
+//! Jobs discovered while the run is in progress, e.g. by a streaming `--scan`, are started after
+//! the initial jobs in the order they are discovered.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Schedule {
+    /// Start the jobs in the order they were given.
+    #[default]
+    Input,
+    /// Start the largest jobs first, interleaving a bounded number of huge jobs with the small
+    /// ones.
+    Balanced,
+}
+
+/// Slot of a running huge job, which is released when it is dropped.
+#[derive(Debug)]
+pub struct HugeSlot(Arc<AtomicUsize>);
+
+impl Drop for HugeSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Jobs by their index in the input, in the order they are started.
+pub struct Queue<'a, T> {
+    huge: VecDeque<(usize, T)>,
+    small: VecDeque<(usize, T)>,
+    running_huge: Arc<AtomicUsize>,
+    max_huge: usize,
+    /// Jobs discovered later, which are waited for once all other jobs have been started.
+    discovered: Option<Box<dyn Iterator<Item = T> + Send + 'a>>,
+    next_id: usize,
+}
+
+impl<'a, T> Queue<'a, T> {
+    /// Creates the queue of `jobs` for `threads` worker threads.
+    ///
+    /// With the balanced schedule, a job is huge if it is larger than the share of all bytes of a
+    /// single thread.
+    pub fn new(schedule: Schedule, jobs: Vec<T>, size: impl Fn(&T) -> u64, threads: usize) -> Self {
+        let threads = threads.max(1);
+        let next_id = jobs.len();
+        let mut jobs: Vec<(usize, T)> = jobs.into_iter().enumerate().collect();
+        let mut huge = Vec::new();
+        if schedule == Schedule::Balanced {
+            let total: u64 = jobs.iter().map(|(_, job)| size(job)).sum();
+            let fair_share = total / threads as u64;
+            jobs.sort_by_key(|(id, job)| (Reverse(size(job)), *id));
+            let num_huge = jobs
+                .iter()
+                .take_while(|(_, job)| size(job) > fair_share)
+                .count();
+            huge = jobs.drain(..num_huge).collect();
+        }
+        Queue {
+            huge: huge.into(),
+            small: jobs.into(),
+            running_huge: Arc::new(AtomicUsize::new(0)),
+            max_huge: (threads / 2).max(1),
+            discovered: None,
+            next_id,
+        }
+    }
+
+    /// Appends the jobs of `discovered`, numbered after the initial jobs.
+    pub fn followed_by(mut self, discovered: impl Iterator<Item = T> + Send + 'a) -> Self {
+        self.discovered = Some(Box::new(discovered));
+        self
+    }
+}
+
+impl<T> Iterator for Queue<'_, T> {
+    /// A job with its index and, for huge jobs, the slot held while it runs.
+    type Item = (usize, T, Option<HugeSlot>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Huge jobs are started while slots are free, or once no small jobs are left to keep the
+        // threads busy.
+        let start_huge = !self.huge.is_empty()
+            && (self.small.is_empty() || self.running_huge.load(Ordering::SeqCst) < self.max_huge);
+        if start_huge {
+            let (id, job) = self.huge.pop_front()?;
+            self.running_huge.fetch_add(1, Ordering::SeqCst);
+            return Some((id, job, Some(HugeSlot(self.running_huge.clone()))));
+        }
+        if let Some((id, job)) = self.small.pop_front() {
+            return Some((id, job, None));
+        }
+        let job = self.discovered.as_mut()?.next()?;
+        self.next_id += 1;
+        Some((self.next_id - 1, job, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_schedule_keeps_order() {
+        let queue = Queue::new(Schedule::Input, vec![1, 500, 2, 400], |&size| size, 4);
+        let order: Vec<usize> = queue
+            .map(|(id, _, slot)| {
+                assert!(slot.is_none());
+                id
+            })
+            .collect();
+        assert_eq!(order, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_discovered_jobs_follow_initial_jobs() {
+        let queue = Queue::new(Schedule::Balanced, vec![1, 500], |&size| size, 4)
+            .followed_by([7, 8].into_iter());
+        let jobs: Vec<(usize, u64)> = queue.map(|(id, job, _)| (id, job)).collect();
+        assert_eq!(jobs, vec![(1, 500), (0, 1), (2, 7), (3, 8)]);
+    }
+
+    #[test]
+    fn test_balanced_schedule_bounds_running_huge_jobs() {
+        // 1000 bytes on 4 threads: jobs larger than 250 bytes are huge, at most 2 run at a time.
+        let sizes = vec![10, 300, 20, 5, 320, 310, 15, 20];
+        let mut queue = Queue::new(Schedule::Balanced, sizes, |&size| size, 4);
+
+        let (first, _, first_slot) = queue.next().unwrap();
+        let (second, _, second_slot) = queue.next().unwrap();
+        assert_eq!((first, second), (4, 5));
+        assert!(first_slot.is_some() && second_slot.is_some());
+
+        // Both slots are taken, so the small jobs are started, largest first.
+        let (id, _, slot) = queue.next().unwrap();
+        assert_eq!((id, slot.is_none()), (2, true));
+        let (id, _, _) = queue.next().unwrap();
+        assert_eq!(id, 7);
+
+        // Once a huge job has finished, the next one is started.
+        drop(first_slot);
+        let (id, _, slot) = queue.next().unwrap();
+        assert_eq!((id, slot.is_some()), (1, true));
+
+        let rest: Vec<usize> = queue.map(|(id, _, _)| id).collect();
+        assert_eq!(rest, vec![6, 0, 3]);
+        drop(second_slot);
+    }
+}
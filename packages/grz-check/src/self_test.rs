@@ -0,0 +1,226 @@
+//! Self-test of the installation, run via `grz-check self-test`.
+//!
+//! Small fixtures with known defects are written to a temporary directory and checked with the
+//! regular pipeline, and the status and finding codes of each report entry are compared with the
+//! expected ones. This confirms that a deployment, e.g. a container or an environment module,
+//! decompresses, parses and reports correctly before it is trusted with a large submission.
+
+use crate::checker::{self, Job, RunOptions};
+use crate::checks::bam::BamCheckJob;
+use crate::checks::fastq::{PairedFastqJob, ReadLengthCheck, SingleFastqJob};
+use crate::fixtures::{self, remove_eof_marker, truncate, write_gzipped};
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+const READS_R1: &str = "@SEQ1/1\nACGTACGTAC\n+\nFFFFFFFFFF\n@SEQ2/1\nTTGCATTGCA\n+\nFFFFFFFFFF\n";
+const READS_R2: &str = "@SEQ1/2\nGGCATGCATG\n+\nFFFFFFFFFF\n@SEQ2/2\nCATGCAAGTC\n+\nFFFFFFFFFF\n";
+
+/// A fixture with the status and finding code its report entry is expected to have.
+struct Case {
+    description: &'static str,
+    file_name: &'static str,
+    status: &'static str,
+    code: Option<&'static str>,
+}
+
+const CASES: &[Case] = &[
+    Case {
+        description: "valid FASTQ pair (R1)",
+        file_name: "valid_r1.fastq.gz",
+        status: "OK",
+        code: None,
+    },
+    Case {
+        description: "valid FASTQ pair (R2)",
+        file_name: "valid_r2.fastq.gz",
+        status: "OK",
+        code: None,
+    },
+    Case {
+        description: "FASTQ with invalid bases",
+        file_name: "invalid_bases.fastq.gz",
+        status: "ERROR",
+        code: Some("fastq.invalid_base"),
+    },
+    Case {
+        description: "truncated FASTQ",
+        file_name: "truncated.fastq.gz",
+        status: "ERROR",
+        code: Some("io.truncated_stream"),
+    },
+    Case {
+        description: "valid BAM",
+        file_name: "valid.bam",
+        status: "OK",
+        code: None,
+    },
+    Case {
+        description: "BAM without EOF marker",
+        file_name: "no_eof.bam",
+        status: "ERROR",
+        code: Some("io.missing_eof"),
+    },
+    Case {
+        description: "truncated BAM",
+        file_name: "truncated.bam",
+        status: "ERROR",
+        code: Some("io.truncated_stream"),
+    },
+];
+
+/// Writes the fixtures of [`CASES`] to `dir` and returns their jobs.
+fn write_fixtures(dir: &Path) -> anyhow::Result<Vec<Job>> {
+    write_gzipped(&dir.join("valid_r1.fastq.gz"), READS_R1.as_bytes())?;
+    write_gzipped(&dir.join("valid_r2.fastq.gz"), READS_R2.as_bytes())?;
+    write_gzipped(
+        &dir.join("invalid_bases.fastq.gz"),
+        READS_R1.replace("ACGTACGTAC", "ACGTXCGTAC").as_bytes(),
+    )?;
+    write_gzipped(
+        &dir.join("truncated.fastq.gz"),
+        READS_R1.repeat(1000).as_bytes(),
+    )?;
+    truncate(&dir.join("truncated.fastq.gz"))?;
+    let reads = fixtures::reads(1000, 10, 0);
+    fixtures::write_bam(&dir.join("valid.bam"), &reads)?;
+    fixtures::write_bam(&dir.join("no_eof.bam"), &reads)?;
+    remove_eof_marker(&dir.join("no_eof.bam"))?;
+    fixtures::write_bam(&dir.join("truncated.bam"), &reads)?;
+    truncate(&dir.join("truncated.bam"))?;
+
+    let size = |name: &str| fs::metadata(dir.join(name)).map(|metadata| metadata.len());
+    let single = |name: &str| -> anyhow::Result<Job> {
+        Ok(Job::SingleFastq(SingleFastqJob {
+            path: dir.join(name),
+            length_check: ReadLengthCheck::Skip,
+            declared_read_length: None,
+            size: size(name)?,
+        }))
+    };
+    let bam = |name: &str| -> anyhow::Result<Job> {
+        Ok(Job::Bam(BamCheckJob {
+            path: dir.join(name),
+            species: None,
+            unaligned: false,
+            index_path: None,
+            size: size(name)?,
+        }))
+    };
+    Ok(vec![
+        Job::PairedFastq(PairedFastqJob {
+            fq1_path: dir.join("valid_r1.fastq.gz"),
+            fq2_path: dir.join("valid_r2.fastq.gz"),
+            length_check: ReadLengthCheck::Fixed(5),
+            fq1_declared_read_length: None,
+            fq2_declared_read_length: None,
+            fq1_size: size("valid_r1.fastq.gz")?,
+            fq2_size: size("valid_r2.fastq.gz")?,
+        }),
+        single("invalid_bases.fastq.gz")?,
+        single("truncated.fastq.gz")?,
+        bam("valid.bam")?,
+        bam("no_eof.bam")?,
+        bam("truncated.bam")?,
+    ])
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    data: EntryData,
+}
+
+#[derive(Deserialize)]
+struct EntryData {
+    path: Option<PathBuf>,
+    status: Option<String>,
+    #[serde(default)]
+    findings: Vec<EntryFinding>,
+}
+
+#[derive(Deserialize)]
+struct EntryFinding {
+    code: String,
+}
+
+/// Reads the status and finding codes of each checked file from a report.
+fn read_results(report: &Path) -> anyhow::Result<BTreeMap<PathBuf, (String, Vec<String>)>> {
+    let mut results = BTreeMap::new();
+    for line in BufReader::new(fs::File::open(report)?).lines() {
+        let entry: Entry = serde_json::from_str(&line?)?;
+        if let (Some(path), Some(status)) = (entry.data.path, entry.data.status) {
+            let codes = entry.data.findings.into_iter().map(|f| f.code).collect();
+            results.insert(path, (status, codes));
+        }
+    }
+    Ok(results)
+}
+
+/// Checks the fixtures and prints the result of each case, failing if any case does not
+/// yield the expected status and finding.
+pub fn run() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir().context("Failed to create a directory for the fixtures")?;
+    let jobs = write_fixtures(dir.path()).context("Failed to write the fixtures")?;
+    let total_bytes = CASES
+        .iter()
+        .map(|case| fs::metadata(dir.path().join(case.file_name)).map(|m| m.len()))
+        .sum::<Result<u64, _>>()?;
+    let report = dir.path().join("report.jsonl");
+    let options = RunOptions {
+        continue_on_error: true,
+        show_progress: Some(false),
+        ..Default::default()
+    };
+    checker::run_check(jobs, total_bytes, &report, &options)?;
+    let results = read_results(&report)?;
+
+    let mut failed = 0;
+    for case in CASES {
+        let outcome = match results.get(&dir.path().join(case.file_name)) {
+            None => Err("no report entry".to_string()),
+            Some((status, codes)) => {
+                let code_found = case.code.is_none_or(|code| codes.iter().any(|c| c == code));
+                if status == case.status && code_found {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "expected {}{}, got {status} [{}]",
+                        case.status,
+                        case.code
+                            .map(|code| format!(" ({code})"))
+                            .unwrap_or_default(),
+                        codes.join(", ")
+                    ))
+                }
+            }
+        };
+        match outcome {
+            Ok(()) => println!("PASS  {}", case.description),
+            Err(reason) => {
+                failed += 1;
+                println!("FAIL  {}: {reason}", case.description);
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!(
+            "{failed} of {} self-test case(s) failed; this installation is not functional",
+            CASES.len()
+        );
+    }
+    println!("All {} self-test cases passed.", CASES.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_passes() -> anyhow::Result<()> {
+        run()
+    }
+}
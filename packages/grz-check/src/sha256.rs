@@ -1,15 +1,183 @@
+use crate::merkle::{self, MerkleDigests, MerkleHasher};
+use crate::timing::Timer;
+use md5::Md5;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::io::{self, Read};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Hashes the bytes of a file with SHA-256 and, if requested, MD5, fixed-size chunks and a
+/// Merkle tree.
+pub struct FileHasher {
+    sha256: Sha256,
+    md5: Option<Md5>,
+    chunks: Option<ChunkHasher>,
+    merkle: Option<MerkleHasher>,
+}
+
+/// Hex-encoded digests of a file.
+pub struct FileDigests {
+    pub sha256: String,
+    pub md5: Option<String>,
+    pub chunks: Option<ChunkDigests>,
+    pub merkle: Option<MerkleDigests>,
+}
+
+/// SHA-256 digests of consecutive chunks of a file, so that a partial re-upload can be verified
+/// by re-reading only the replaced byte ranges. The last chunk may be shorter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChunkDigests {
+    pub chunk_size: u64,
+    pub sha256: Vec<String>,
+}
+
+struct ChunkHasher {
+    chunk_size: u64,
+    sha256: Sha256,
+    /// Number of bytes hashed of the current chunk.
+    filled: u64,
+    digests: Vec<String>,
+}
+
+impl ChunkHasher {
+    fn new(chunk_size: u64) -> Self {
+        Self {
+            chunk_size,
+            sha256: Sha256::new(),
+            filled: 0,
+            digests: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let remaining = self.chunk_size - self.filled;
+            let len = usize::try_from(remaining).map_or(data.len(), |r| r.min(data.len()));
+            self.sha256.update(&data[..len]);
+            self.filled += len as u64;
+            data = &data[len..];
+            if self.filled == self.chunk_size {
+                self.digests
+                    .push(format!("{:x}", self.sha256.finalize_reset()));
+                self.filled = 0;
+            }
+        }
+    }
+
+    fn finalize(mut self) -> ChunkDigests {
+        if self.filled > 0 {
+            self.digests.push(format!("{:x}", self.sha256.finalize()));
+        }
+        ChunkDigests {
+            chunk_size: self.chunk_size,
+            sha256: self.digests,
+        }
+    }
+}
+
+/// Encoding of the digests written to a sink, i.e. the report or the post-check hook.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestEncoding {
+    /// Lower-case hex, as written by `sha256sum`.
+    #[default]
+    Hex,
+    /// Base64 of the binary digest, URL-safe and without padding (RFC 4648, section 5).
+    Base64,
+    /// Base64 of the binary digest with padding, as expected by S3 in `x-amz-checksum-sha256`.
+    S3,
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+            bits | u32::from(byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(alphabet[(bits >> (18 - 6 * i)) as usize & 63]));
+            } else if pad {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+impl DigestEncoding {
+    /// Re-encodes a hex-encoded digest. Digests that are not valid hex are returned unchanged.
+    pub fn encode(self, hex: &str) -> String {
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|b| u8::from_str_radix(b, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>();
+        match (self, bytes) {
+            (DigestEncoding::Base64, Some(bytes)) => base64(&bytes, BASE64_URL_ALPHABET, false),
+            (DigestEncoding::S3, Some(bytes)) => base64(&bytes, BASE64_ALPHABET, true),
+            _ => hex.to_string(),
+        }
+    }
+}
+
+impl FileHasher {
+    /// Creates a hasher of chunks of `chunk_size` bytes if given, and of a Merkle tree whose
+    /// subtrees of the given height are reported if `merkle` is given.
+    pub fn new(md5: bool, chunk_size: Option<u64>, merkle: Option<Option<u32>>) -> Self {
+        Self {
+            sha256: Sha256::new(),
+            md5: md5.then(Md5::new),
+            chunks: chunk_size.filter(|&size| size > 0).map(ChunkHasher::new),
+            merkle: merkle
+                .map(|subtree_height| MerkleHasher::new(merkle::LEAF_SIZE, subtree_height)),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.sha256.update(data);
+        if let Some(md5) = &mut self.md5 {
+            md5.update(data);
+        }
+        if let Some(chunks) = &mut self.chunks {
+            chunks.update(data);
+        }
+        if let Some(merkle) = &mut self.merkle {
+            merkle.update(data);
+        }
+    }
+
+    pub fn finalize(self) -> FileDigests {
+        FileDigests {
+            sha256: format!("{:x}", self.sha256.finalize()),
+            md5: self.md5.map(|md5| format!("{:x}", md5.finalize())),
+            chunks: self.chunks.map(ChunkHasher::finalize),
+            merkle: self.merkle.map(MerkleHasher::finalize),
+        }
+    }
+}
 
 pub struct SharedHashingReader<R: Read> {
     inner: R,
-    hasher: Arc<Mutex<Sha256>>,
+    hasher: Arc<Mutex<FileHasher>>,
+    timer: Timer,
 }
 
 impl<R: Read> SharedHashingReader<R> {
-    pub fn new(inner: R, hasher: Arc<Mutex<Sha256>>) -> Self {
-        Self { inner, hasher }
+    pub fn new(inner: R, hasher: Arc<Mutex<FileHasher>>, timer: Timer) -> Self {
+        Self {
+            inner,
+            hasher,
+            timer,
+        }
     }
 }
 
@@ -17,8 +185,48 @@ impl<R: Read> Read for SharedHashingReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let bytes_read = self.inner.read(buf)?;
         if bytes_read > 0 {
+            let start = Instant::now();
             self.hasher.lock().unwrap().update(&buf[..bytes_read]);
+            self.timer.add(start.elapsed());
         }
         Ok(bytes_read)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_encodings() {
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(DigestEncoding::Hex.encode(empty), empty);
+        assert_eq!(
+            DigestEncoding::S3.encode(empty),
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+        assert_eq!(
+            DigestEncoding::Base64.encode(empty),
+            "47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU"
+        );
+        assert_eq!(
+            DigestEncoding::S3.encode("d41d8cd98f00b204e9800998ecf8427e"),
+            "1B2M2Y8AsgTpgAmY7PhCfg=="
+        );
+        assert_eq!(DigestEncoding::S3.encode("not hex"), "not hex");
+    }
+
+    #[test]
+    fn test_chunk_digests() {
+        let mut chunks = ChunkHasher::new(4);
+        chunks.update(b"abc");
+        chunks.update(b"defghij");
+        let digests = chunks.finalize();
+        let sha256 = |data: &[u8]| format!("{:x}", Sha256::digest(data));
+        assert_eq!(
+            digests.sha256,
+            vec![sha256(b"abcd"), sha256(b"efgh"), sha256(b"ij")]
+        );
+        assert_eq!(ChunkHasher::new(4).finalize().sha256, Vec::<String>::new());
+    }
+}
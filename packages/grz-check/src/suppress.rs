@@ -0,0 +1,108 @@
+//! Suppression of known, accepted findings per path pattern.
+//!
+//! Suppressed findings are removed from the errors and warnings of a report entry, so they no
+//! longer affect its status, but are still listed under `suppressed_findings` and counted in the
+//! summary at the end of a run.
+
+use crate::findings;
+use glob::Pattern;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Suppresses findings whose code matches `code` in files whose path matches `path`.
+#[derive(Debug, Clone)]
+pub struct Suppression {
+    code: Pattern,
+    path: Pattern,
+}
+
+impl Suppression {
+    pub fn new(code: &str, path: &str) -> Result<Self, String> {
+        let code = Pattern::new(code).map_err(|e| format!("Invalid code pattern '{code}': {e}"))?;
+        let path = Pattern::new(path).map_err(|e| format!("Invalid path pattern '{path}': {e}"))?;
+        Ok(Self { code, path })
+    }
+
+    fn matches(&self, code: &str, path: &Path) -> bool {
+        self.code.matches(code) && self.path.matches_path(path)
+    }
+}
+
+impl fmt::Display for Suppression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.code, self.path)
+    }
+}
+
+impl Serialize for Suppression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parses a `CODE:GLOB` command line argument.
+pub fn parse_suppression(s: &str) -> Result<Suppression, String> {
+    let (code, path) = s
+        .split_once(':')
+        .ok_or_else(|| format!("Expected CODE:GLOB, got '{s}'"))?;
+    Suppression::new(code, path)
+}
+
+/// Moves the messages of `messages` matching any of `rules` for `paths` into `suppressed`,
+/// returning their codes.
+pub fn apply(
+    rules: &[Suppression],
+    paths: &[&Path],
+    messages: &mut Vec<String>,
+    suppressed: &mut Vec<String>,
+) -> Vec<&'static str> {
+    if rules.is_empty() {
+        return vec![];
+    }
+    let mut codes = Vec::new();
+    messages.retain(|message| {
+        let code = findings::code_for(message);
+        let is_suppressed = rules
+            .iter()
+            .any(|rule| paths.iter().any(|path| rule.matches(code, path)));
+        if is_suppressed {
+            codes.push(code);
+            suppressed.push(message.clone());
+        }
+        !is_suppressed
+    });
+    codes
+}
+
+/// Number of suppressed findings per code over a whole run.
+#[derive(Debug, Default)]
+pub struct Summary {
+    counts: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl Summary {
+    pub fn add(&self, codes: &[&'static str]) {
+        let mut counts = self.counts.lock().unwrap();
+        for &code in codes {
+            *counts.entry(code).or_default() += 1;
+        }
+    }
+
+    /// Describes the suppressed findings, or `None` if nothing was suppressed.
+    pub fn describe(&self) -> Option<String> {
+        let counts = self.counts.lock().unwrap();
+        if counts.is_empty() {
+            return None;
+        }
+        let total: u64 = counts.values().sum();
+        let by_code = counts
+            .iter()
+            .map(|(code, count)| format!("{code} ({count})"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Some(format!("Suppressed {total} finding(s): {by_code}"))
+    }
+}
@@ -0,0 +1,219 @@
+//! Minimal `sd_notify` support, so grz-check can run as a `Type=notify` systemd service with
+//! `WatchdogSec=` set, e.g. in watch mode. All functions are no-ops when not started by systemd.
+
+use crate::logging;
+use std::env;
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Sends a state update (e.g. `READY=1`) to the service manager.
+///
+/// Returns `Ok(false)` if no service manager is listening, i.e. `NOTIFY_SOCKET` is unset.
+pub fn notify(state: &str) -> io::Result<bool> {
+    let Some(socket_path) = env::var_os("NOTIFY_SOCKET") else {
+        return Ok(false);
+    };
+
+    let socket = UnixDatagram::unbound()?;
+    let socket_path = socket_path.to_string_lossy();
+    if let Some(abstract_name) = socket_path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(abstract_name)?;
+            socket.send_to_addr(state.as_bytes(), &addr)?;
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = abstract_name;
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Abstract notify sockets are only supported on Linux",
+            ));
+        }
+    } else {
+        socket.send_to(state.as_bytes(), socket_path.as_ref())?;
+    }
+    Ok(true)
+}
+
+/// Watchdog interval requested by the service manager via `WATCHDOG_USEC`, if it is meant for
+/// this process.
+fn watchdog_interval() -> Option<Duration> {
+    parse_watchdog_interval(
+        env::var("WATCHDOG_USEC").ok().as_deref(),
+        env::var("WATCHDOG_PID").ok().as_deref(),
+        std::process::id(),
+    )
+}
+
+/// Parses the values of `WATCHDOG_USEC` and `WATCHDOG_PID`. The interval only applies if
+/// `WATCHDOG_PID` is unset or the ID of this process, `own_pid`.
+fn parse_watchdog_interval(
+    usec: Option<&str>,
+    pid: Option<&str>,
+    own_pid: u32,
+) -> Option<Duration> {
+    if let Some(pid) = pid
+        && pid.parse::<u32>().ok() != Some(own_pid)
+    {
+        return None;
+    }
+    let usec: u64 = usec?.parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+/// Liveness signal of the worker threads of a check run, for the [`Watchdog`].
+///
+/// Workers beat whenever they read from a file and when they start or finish a job. During a
+/// run, see [`Heartbeat::run`], the watchdog only sends keepalives while the workers beat, such
+/// that a hung worker gets the service restarted. Outside of a run, e.g. while waiting for
+/// submissions in watch mode, the service is kept alive.
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeat {
+    beats: Arc<AtomicU64>,
+    runs: Arc<AtomicUsize>,
+}
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        self.beats.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Expects beats of the workers until the returned guard is dropped.
+    pub fn run(&self) -> Run<'_> {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        Run(self)
+    }
+
+    /// Returns whether the service is alive, given the number of beats at the previous call.
+    fn is_alive(&self, last_beats: &mut u64) -> bool {
+        let beats = self.beats.load(Ordering::Relaxed);
+        let advanced = beats != std::mem::replace(last_beats, beats);
+        advanced || self.runs.load(Ordering::Relaxed) == 0
+    }
+}
+
+/// A check run of a [`Heartbeat`], see [`Heartbeat::run`].
+pub struct Run<'a>(&'a Heartbeat);
+
+impl Drop for Run<'_> {
+    fn drop(&mut self) {
+        self.0.runs.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Sends watchdog keepalives while the [`Heartbeat`] shows the service to be alive.
+pub struct Watchdog {
+    stop: Arc<AtomicBool>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl Watchdog {
+    pub fn start(heartbeat: Heartbeat) -> Option<Self> {
+        let interval = watchdog_interval()? / 2;
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let mut last_beats = 0;
+                let mut last_ping = Instant::now();
+                while !stop.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(100).min(interval));
+                    if last_ping.elapsed() < interval {
+                        continue;
+                    }
+                    if heartbeat.is_alive(&mut last_beats)
+                        && let Err(e) = notify("WATCHDOG=1")
+                    {
+                        logging::warn(format!("Failed to send watchdog keepalive: {e}"));
+                    }
+                    last_ping = Instant::now();
+                }
+            })
+        };
+        Some(Self { stop, handle })
+    }
+
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+    }
+}
+
+/// Supervision of the process by the service manager: readiness once started, watchdog
+/// keepalives while alive and a stopping notification when done.
+pub struct Service {
+    heartbeat: Heartbeat,
+    watchdog: Option<Watchdog>,
+}
+
+impl Service {
+    pub fn start() -> Self {
+        if let Err(e) = notify("READY=1") {
+            logging::warn(format!("Failed to notify service manager: {e}"));
+        }
+        let heartbeat = Heartbeat::default();
+        let watchdog = Watchdog::start(heartbeat.clone());
+        Self {
+            heartbeat,
+            watchdog,
+        }
+    }
+
+    pub fn heartbeat(&self) -> &Heartbeat {
+        &self.heartbeat
+    }
+
+    pub fn stop(self) {
+        if let Some(watchdog) = self.watchdog {
+            watchdog.stop();
+        }
+        if let Err(e) = notify("STOPPING=1") {
+            logging::warn(format!("Failed to notify service manager: {e}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_interval() {
+        let interval = |usec, pid| parse_watchdog_interval(usec, pid, 42);
+        assert_eq!(
+            interval(Some("5000000"), None),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            interval(Some("5000000"), Some("42")),
+            Some(Duration::from_secs(5))
+        );
+        // The watchdog of another process, e.g. of a parent that did not unset the variables.
+        assert_eq!(interval(Some("5000000"), Some("41")), None);
+        assert_eq!(interval(Some("5000000"), Some("self")), None);
+        assert_eq!(interval(Some("0"), None), None);
+        assert_eq!(interval(Some("5s"), None), None);
+        assert_eq!(interval(None, Some("42")), None);
+    }
+
+    #[test]
+    fn test_heartbeat_during_runs() {
+        let heartbeat = Heartbeat::default();
+        let mut last_beats = 0;
+        assert!(heartbeat.is_alive(&mut last_beats));
+
+        let run = heartbeat.run();
+        assert!(!heartbeat.is_alive(&mut last_beats));
+        heartbeat.beat();
+        assert!(heartbeat.is_alive(&mut last_beats));
+        assert!(!heartbeat.is_alive(&mut last_beats));
+        drop(run);
+        assert!(heartbeat.is_alive(&mut last_beats));
+    }
+}
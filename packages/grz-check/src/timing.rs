@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Cumulative time counter that can be shared between the layers of a reader stack.
+#[derive(Debug, Clone, Default)]
+pub struct Timer(Arc<AtomicU64>);
+
+impl Timer {
+    pub fn add(&self, elapsed: Duration) {
+        self.0.fetch_add(
+            u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// Timers for the reader stack of a single file.
+#[derive(Debug, Clone, Default)]
+pub struct ReadTimers {
+    /// Time spent reading raw bytes from disk.
+    pub read: Timer,
+    /// Time spent updating the checksum.
+    pub hashing: Timer,
+    /// Time spent in the outermost reader, i.e. reading, hashing and decompressing.
+    pub total: Timer,
+}
+
+pub struct TimedReader<R: Read> {
+    inner: R,
+    timer: Timer,
+}
+
+impl<R: Read> TimedReader<R> {
+    pub fn new(inner: R, timer: Timer) -> Self {
+        Self { inner, timer }
+    }
+}
+
+impl<R: Read> Read for TimedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let start = Instant::now();
+        let result = self.inner.read(buf);
+        self.timer.add(start.elapsed());
+        result
+    }
+}
+
+/// Breakdown of where the processing time of a file was spent, in seconds.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub struct Timings {
+    pub read_seconds: f64,
+    pub decompression_seconds: f64,
+    pub hashing_seconds: f64,
+    pub check_seconds: f64,
+    pub total_seconds: f64,
+}
+
+impl Timings {
+    /// Splits the wall time since `started` into the time spent in the reader stack and
+    /// the remaining time spent in the check logic itself.
+    pub fn new(timers: &ReadTimers, started: Instant) -> Self {
+        Self::with_check_time(timers, started.elapsed(), timers.total.elapsed())
+    }
+
+    /// Like [`Timings::new`], but for files processed together with others in one loop
+    /// (e.g. paired FASTQ), where `shared_read_time` is the reader time of all files.
+    pub fn with_check_time(
+        timers: &ReadTimers,
+        wall_time: Duration,
+        shared_read_time: Duration,
+    ) -> Self {
+        let read = timers.read.elapsed();
+        let hashing = timers.hashing.elapsed();
+        let decompression = timers.total.elapsed().saturating_sub(read + hashing);
+        Self {
+            read_seconds: read.as_secs_f64(),
+            decompression_seconds: decompression.as_secs_f64(),
+            hashing_seconds: hashing.as_secs_f64(),
+            check_seconds: wall_time.saturating_sub(shared_read_time).as_secs_f64(),
+            total_seconds: wall_time.as_secs_f64(),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! UMI check of FASTQ files, given via `--umi`.
+//!
+//! The UMI of a read is either the last colon-separated field of its name, as written by
+//! `bcl-convert` with `OverrideCycles` UMI settings, or the sequence of the corresponding read of
+//! the index file (I1) of `--fastq-triple`. It has to match a regular expression as a whole.
+
+use regex::bytes::Regex;
+use serde::Serialize;
+use std::fmt;
+
+/// Where the UMI of a read is found.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum UmiLocation {
+    /// Last colon-separated field of the read name.
+    Name,
+    /// Sequence of the index read (I1) of `--fastq-triple`.
+    Index,
+}
+
+impl fmt::Display for UmiLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UmiLocation::Name => "read name",
+            UmiLocation::Index => "index read",
+        })
+    }
+}
+
+/// Default fraction of reads that must have a valid UMI.
+pub const DEFAULT_MIN_UMI_FRACTION: f64 = 1.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UmiCheck {
+    pub location: UmiLocation,
+    pub pattern: UmiPattern,
+    /// Fraction of reads with a valid UMI below which an error is reported.
+    pub min_fraction: f64,
+}
+
+impl UmiCheck {
+    /// Returns the UMI of a read, if its location holds one.
+    pub fn umi<'a>(&self, name: &'a [u8], sequence: &'a [u8]) -> Option<&'a [u8]> {
+        match self.location {
+            UmiLocation::Name => name
+                .iter()
+                .rposition(|&b| b == b':')
+                .map(|colon| &name[colon + 1..]),
+            UmiLocation::Index => Some(sequence),
+        }
+    }
+}
+
+/// Pattern a UMI has to match as a whole.
+#[derive(Debug, Clone, Serialize)]
+#[serde(into = "String")]
+pub struct UmiPattern {
+    spec: String,
+    regex: Regex,
+}
+
+impl From<UmiPattern> for String {
+    fn from(pattern: UmiPattern) -> Self {
+        pattern.spec
+    }
+}
+
+impl fmt::Display for UmiPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+impl UmiPattern {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if spec.is_empty() {
+            return Err("Invalid UMI pattern '': the pattern must not be empty".to_string());
+        }
+        let regex = Regex::new(&format!("^(?:{spec})$"))
+            .map_err(|e| format!("Invalid UMI pattern '{spec}': {e}"))?;
+        Ok(Self {
+            spec: spec.to_string(),
+            regex,
+        })
+    }
+
+    /// Returns whether the UMI matches the pattern as a whole.
+    pub fn matches(&self, umi: &[u8]) -> bool {
+        self.regex.is_match(umi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_umi_pattern() {
+        let pattern = UmiPattern::parse("[ACGTN]{8}").unwrap();
+        assert!(pattern.matches(b"ACGTACGN"));
+        assert!(!pattern.matches(b"ACGTACG"));
+        assert!(!pattern.matches(b"ACGTACGTA"));
+        assert!(!pattern.matches(b"ACGTACGX"));
+
+        let pattern = UmiPattern::parse(r"^[ACGT]{4,6}\+[^N]+$").unwrap();
+        assert!(pattern.matches(b"ACGTA+TTTT"));
+        assert!(!pattern.matches(b"ACG+TTTT"));
+        assert!(!pattern.matches(b"ACGT+TTNT"));
+        assert!(!pattern.matches(b"ACGT+"));
+
+        let pattern = UmiPattern::parse("A*C?.G").unwrap();
+        assert!(pattern.matches(b"AAAAXG"));
+        assert!(pattern.matches(b"CTG"));
+        assert!(!pattern.matches(b"G"));
+
+        // Alternatives match the whole UMI, not just a prefix or suffix.
+        let pattern = UmiPattern::parse("AC|(GT)+").unwrap();
+        assert!(pattern.matches(b"AC"));
+        assert!(pattern.matches(b"GTGT"));
+        assert!(!pattern.matches(b"ACGT"));
+
+        let pattern = UmiPattern::parse(r"\d{2}[\-_]\w").unwrap();
+        assert!(pattern.matches(b"12-a"));
+        assert!(pattern.matches(b"12_a"));
+        assert!(!pattern.matches(b"12.a"));
+        assert!(!pattern.matches(b"dd-a"));
+
+        assert!(UmiPattern::parse("").is_err());
+        assert!(UmiPattern::parse("[ACGT").is_err());
+        assert!(UmiPattern::parse("[T-A]").is_err());
+        assert!(UmiPattern::parse("N{8,4}").is_err());
+        assert!(UmiPattern::parse("+N").is_err());
+    }
+
+    #[test]
+    fn test_umi_location() {
+        let check = |location| UmiCheck {
+            location,
+            pattern: UmiPattern::parse("[ACGT]+").unwrap(),
+            min_fraction: DEFAULT_MIN_UMI_FRACTION,
+        };
+        let name = b"A00123:8:HXXXXDSXX:1:1101:1000:2000:ACGTACGT";
+        assert_eq!(
+            check(UmiLocation::Name).umi(name, b"NNNN"),
+            Some(&b"ACGTACGT"[..])
+        );
+        assert_eq!(check(UmiLocation::Name).umi(b"read1", b"NNNN"), None);
+        assert_eq!(
+            check(UmiLocation::Index).umi(name, b"TTGCA"),
+            Some(&b"TTGCA"[..])
+        );
+    }
+}
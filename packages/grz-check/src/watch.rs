@@ -0,0 +1,207 @@
+//! Watch mode, `grz-check watch INBOX --reports DIR`, for running grz-check as a long-lived
+//! service that checks every submission moved into an inbox directory.
+//!
+//! Uploads are expected to be moved into the inbox once complete, such that each directory in
+//! the inbox is a complete submission. Its report is written to `<DIR>/<NAME>.jsonl` once its
+//! check has finished, and submissions with a report are not checked again, also after a
+//! restart. A check that was interrupted, e.g. by the watchdog of the service manager, leaves
+//! no report behind and is repeated.
+
+use crate::logging;
+use crate::systemd::{self, Heartbeat};
+use anyhow::Context;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Extension of the reports of checks that are still running.
+const PARTIAL_EXTENSION: &str = "partial";
+
+#[derive(Debug)]
+pub struct Inbox {
+    dir: PathBuf,
+    reports: PathBuf,
+    /// Submissions whose check failed to run, which are not retried until a restart.
+    failed: HashSet<PathBuf>,
+}
+
+impl Inbox {
+    pub fn new(dir: &Path, reports: &Path) -> anyhow::Result<Self> {
+        if !dir.is_dir() {
+            anyhow::bail!("Inbox {} is not a directory", dir.display());
+        }
+        fs::create_dir_all(reports)
+            .with_context(|| format!("Failed to create report directory {}", reports.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            reports: reports.to_path_buf(),
+            failed: HashSet::new(),
+        })
+    }
+
+    fn report_path(&self, name: &str) -> PathBuf {
+        self.reports.join(format!("{name}.jsonl"))
+    }
+
+    /// Submissions in the inbox without a report, in the order of their names.
+    fn pending(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let entries = fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to list inbox {}", self.dir.display()))?;
+        let mut pending = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if path.is_dir() && !self.report_path(name).exists() && !self.failed.contains(&path) {
+                pending.push(path);
+            }
+        }
+        pending.sort();
+        Ok(pending)
+    }
+
+    /// Checks the pending submissions with `check`, which is given the submission and the path
+    /// to write its report to. Returns the number of submissions checked.
+    pub fn check_pending(
+        &mut self,
+        heartbeat: &Heartbeat,
+        check: &mut impl FnMut(&Path, &Path) -> anyhow::Result<()>,
+    ) -> anyhow::Result<usize> {
+        let pending = self.pending()?;
+        for submission in &pending {
+            heartbeat.beat();
+            let name = submission
+                .file_name()
+                .expect("Listed submissions have a name")
+                .to_string_lossy();
+            if let Err(e) = systemd::notify(&format!("STATUS=Checking submission {name}")) {
+                logging::warn(format!("Failed to notify service manager: {e}"));
+            }
+            let report = self.report_path(&name);
+            let partial = report.with_extension(PARTIAL_EXTENSION);
+            logging::info(format!("Checking submission {}", submission.display()));
+            let result = check(submission, &partial);
+            if partial.exists() {
+                fs::rename(&partial, &report)
+                    .with_context(|| format!("Failed to move report to {}", report.display()))?;
+            }
+            match result {
+                Ok(()) => logging::info(format!("Submission {name} passed all checks")),
+                Err(e) if report.exists() => {
+                    logging::warn(format!("Submission {name} failed its checks: {e:#}"));
+                }
+                Err(e) => {
+                    logging::error(format!(
+                        "Failed to check submission {name}, skipping it until a restart: {e:#}"
+                    ));
+                    self.failed.insert(submission.clone());
+                }
+            }
+        }
+        Ok(pending.len())
+    }
+
+    /// Checks the pending submissions every `interval` until `shutdown_flag` is set.
+    pub fn watch(
+        &mut self,
+        interval: Duration,
+        heartbeat: &Heartbeat,
+        shutdown_flag: &AtomicBool,
+        mut check: impl FnMut(&Path, &Path) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        logging::info(format!(
+            "Watching inbox {} for submissions",
+            self.dir.display()
+        ));
+        let interrupted = || shutdown_flag.load(Ordering::Relaxed);
+        while !interrupted() {
+            self.check_pending(heartbeat, &mut check)?;
+            if let Err(e) = systemd::notify(&format!(
+                "STATUS=Waiting for submissions in {}",
+                self.dir.display()
+            )) {
+                logging::warn(format!("Failed to notify service manager: {e}"));
+            }
+            let listed = Instant::now();
+            while listed.elapsed() < interval && !interrupted() {
+                heartbeat.beat();
+                thread::sleep(Duration::from_millis(100).min(interval));
+            }
+        }
+        logging::warn("Stopped watching the inbox.");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_submissions_are_checked_once() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let inbox_dir = dir.path().join("inbox");
+        let reports = dir.path().join("reports");
+        for name in ["b", "a", "c", "unusable"] {
+            fs::create_dir_all(inbox_dir.join(name))?;
+        }
+        fs::write(inbox_dir.join("upload.tmp"), "not a submission")?;
+        let mut inbox = Inbox::new(&inbox_dir, &reports)?;
+        fs::write(reports.join("c.jsonl"), "")?;
+
+        let mut checked = Vec::new();
+        let mut check = |submission: &Path, report: &Path| {
+            let name = submission
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+            checked.push(name.clone());
+            match name.as_str() {
+                "unusable" => anyhow::bail!("Invalid arguments"),
+                "b" => {
+                    fs::write(report, "{}\n")?;
+                    anyhow::bail!("A validation error occurred")
+                }
+                _ => Ok(fs::write(report, "{}\n")?),
+            }
+        };
+        let heartbeat = Heartbeat::default();
+        assert_eq!(inbox.check_pending(&heartbeat, &mut check)?, 3);
+        assert_eq!(inbox.check_pending(&heartbeat, &mut check)?, 0);
+        assert_eq!(checked, ["a", "b", "unusable"]);
+
+        assert!(reports.join("a.jsonl").exists());
+        // Failed checks are reported like passed ones.
+        assert!(reports.join("b.jsonl").exists());
+        assert!(!reports.join("unusable.jsonl").exists());
+        assert!(!reports.join("a.partial").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupted_checks_are_repeated() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let inbox_dir = dir.path().join("inbox");
+        let reports = dir.path().join("reports");
+        fs::create_dir_all(inbox_dir.join("a"))?;
+        // Left behind by a check that was killed.
+        fs::create_dir_all(&reports)?;
+        fs::write(reports.join("a.partial"), "{}\n")?;
+
+        let mut inbox = Inbox::new(&inbox_dir, &reports)?;
+        let mut num_checked = 0;
+        inbox.check_pending(&Heartbeat::default(), &mut |_: &Path, report: &Path| {
+            num_checked += 1;
+            Ok(fs::write(report, "{}\n{}\n")?)
+        })?;
+        assert_eq!(num_checked, 1);
+        assert_eq!(fs::read_to_string(reports.join("a.jsonl"))?, "{}\n{}\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,488 @@
+//! Reading members of zip archives, including zip64 archives with members larger than 4 GiB, as
+//! some instruments export run archives this way.
+//!
+//! Members are decompressed while they are read, and their CRC-32 and size are validated against
+//! the central directory when the end of the member is reached, so that truncated or corrupt
+//! members fail the checks instead of being read partially.
+
+use std::fs;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const LOCAL_HEADER_SIGNATURE: [u8; 4] = *b"PK\x03\x04";
+const CENTRAL_HEADER_SIGNATURE: [u8; 4] = *b"PK\x01\x02";
+const EOCD_SIGNATURE: [u8; 4] = *b"PK\x05\x06";
+const ZIP64_EOCD_SIGNATURE: [u8; 4] = *b"PK\x06\x06";
+const ZIP64_LOCATOR_SIGNATURE: [u8; 4] = *b"PK\x06\x07";
+
+const LOCAL_HEADER_SIZE: usize = 30;
+const CENTRAL_HEADER_SIZE: usize = 46;
+const EOCD_SIZE: usize = 22;
+const ZIP64_EOCD_SIZE: usize = 56;
+const ZIP64_LOCATOR_SIZE: usize = 20;
+/// Longest comment at the end of an archive, which precedes the search for the EOCD record.
+const MAX_COMMENT_SIZE: usize = u16::MAX as usize;
+
+/// Header ID of the extra field holding the 64-bit sizes and offset of zip64 members.
+const ZIP64_EXTRA_FIELD: u16 = 0x0001;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+const FLAG_ENCRYPTED: u16 = 1;
+
+/// A member of a zip archive, as listed in the central directory.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    /// Decompressed size.
+    pub size: u64,
+    compressed_size: u64,
+    crc32: u32,
+    method: u16,
+    flags: u16,
+    header_offset: u64,
+}
+
+impl Entry {
+    pub fn is_file(&self) -> bool {
+        !self.name.ends_with('/')
+    }
+}
+
+fn invalid(archive: &Path, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid zip archive {}: {message}", archive.display()),
+    )
+}
+
+fn u16_at(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_at(file: &mut fs::File, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; len];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Returns whether a file starts like a zip archive.
+pub fn is_zip(path: &Path) -> io::Result<bool> {
+    let mut magic = [0; 4];
+    let mut file = fs::File::open(path)?;
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(magic == LOCAL_HEADER_SIGNATURE || magic == EOCD_SIGNATURE),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Reads the number of entries and the offset and size of the central directory, from the zip64
+/// records if the end of central directory record has fields saturated.
+fn central_directory(file: &mut fs::File, archive: &Path) -> io::Result<(u64, u64, u64)> {
+    let len = file.metadata()?.len();
+    let tail_len = len.min((EOCD_SIZE + MAX_COMMENT_SIZE) as u64);
+    let tail_start = len - tail_len;
+    let tail = read_at(file, tail_start, tail_len as usize)?;
+    let eocd = tail
+        .windows(EOCD_SIGNATURE.len())
+        .rposition(|window| window == EOCD_SIGNATURE)
+        .filter(|&position| position + EOCD_SIZE <= tail.len())
+        .ok_or_else(|| invalid(archive, "end of central directory record not found"))?;
+    let record = &tail[eocd..eocd + EOCD_SIZE];
+    let num_entries = u64::from(u16_at(record, 10));
+    let cd_size = u64::from(u32_at(record, 12));
+    let cd_offset = u64::from(u32_at(record, 16));
+    if num_entries != u64::from(u16::MAX)
+        && cd_size != u64::from(u32::MAX)
+        && cd_offset != u64::from(u32::MAX)
+    {
+        return Ok((num_entries, cd_offset, cd_size));
+    }
+
+    let locator_offset = (tail_start + eocd as u64)
+        .checked_sub(ZIP64_LOCATOR_SIZE as u64)
+        .ok_or_else(|| invalid(archive, "zip64 end of central directory locator not found"))?;
+    let locator = read_at(file, locator_offset, ZIP64_LOCATOR_SIZE)?;
+    if locator[..4] != ZIP64_LOCATOR_SIGNATURE {
+        return Err(invalid(
+            archive,
+            "zip64 end of central directory locator not found",
+        ));
+    }
+    let record = read_at(file, u64_at(&locator, 8), ZIP64_EOCD_SIZE)?;
+    if record[..4] != ZIP64_EOCD_SIGNATURE {
+        return Err(invalid(
+            archive,
+            "zip64 end of central directory record not found",
+        ));
+    }
+    Ok((
+        u64_at(&record, 32),
+        u64_at(&record, 48),
+        u64_at(&record, 40),
+    ))
+}
+
+/// Replaces saturated sizes and the offset of a central directory header by the values of the
+/// zip64 extra field, which only holds the saturated ones, in this order.
+fn apply_zip64_extra(entry: &mut Entry, mut extra: &[u8], archive: &Path) -> io::Result<()> {
+    while extra.len() >= 4 {
+        let (id, len) = (u16_at(extra, 0), usize::from(u16_at(extra, 2)));
+        let data = extra
+            .get(4..4 + len)
+            .ok_or_else(|| invalid(archive, format!("truncated extra field of {}", entry.name)))?;
+        if id == ZIP64_EXTRA_FIELD {
+            let mut values = data.chunks_exact(8).map(|value| u64_at(value, 0));
+            for field in [
+                &mut entry.size,
+                &mut entry.compressed_size,
+                &mut entry.header_offset,
+            ] {
+                if *field == u64::from(u32::MAX) {
+                    *field = values.next().ok_or_else(|| {
+                        invalid(archive, format!("incomplete zip64 field of {}", entry.name))
+                    })?;
+                }
+            }
+            return Ok(());
+        }
+        extra = &extra[4 + len..];
+    }
+    Ok(())
+}
+
+/// Lists all entries of a zip archive, including directories.
+pub fn entries(archive: &Path) -> io::Result<Vec<Entry>> {
+    let mut file = fs::File::open(archive)?;
+    let (num_entries, cd_offset, cd_size) = central_directory(&mut file, archive)?;
+    let cd_size = usize::try_from(cd_size)
+        .map_err(|_| invalid(archive, "central directory does not fit into memory"))?;
+    let cd = read_at(&mut file, cd_offset, cd_size)?;
+
+    let mut entries = Vec::new();
+    let mut position = 0;
+    for _ in 0..num_entries {
+        let header = cd
+            .get(position..position + CENTRAL_HEADER_SIZE)
+            .filter(|header| header[..4] == CENTRAL_HEADER_SIGNATURE)
+            .ok_or_else(|| invalid(archive, "malformed central directory"))?;
+        let name_len = usize::from(u16_at(header, 28));
+        let extra_len = usize::from(u16_at(header, 30));
+        let comment_len = usize::from(u16_at(header, 32));
+        let name_start = position + CENTRAL_HEADER_SIZE;
+        let extra_start = name_start + name_len;
+        let extra = cd
+            .get(extra_start..extra_start + extra_len)
+            .ok_or_else(|| invalid(archive, "malformed central directory"))?;
+        let mut entry = Entry {
+            name: String::from_utf8_lossy(&cd[name_start..extra_start]).into_owned(),
+            size: u64::from(u32_at(header, 24)),
+            compressed_size: u64::from(u32_at(header, 20)),
+            crc32: u32_at(header, 16),
+            method: u16_at(header, 10),
+            flags: u16_at(header, 8),
+            header_offset: u64::from(u32_at(header, 42)),
+        };
+        apply_zip64_extra(&mut entry, extra, archive)?;
+        entries.push(entry);
+        position = extra_start + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// Returns the offset of the data of an entry, which follows its local header.
+fn data_offset(file: &mut fs::File, entry: &Entry, archive: &Path) -> io::Result<u64> {
+    let header = read_at(file, entry.header_offset, LOCAL_HEADER_SIZE)?;
+    if header[..4] != LOCAL_HEADER_SIGNATURE {
+        return Err(invalid(
+            archive,
+            format!("local header of {} not found", entry.name),
+        ));
+    }
+    let name_len = u64::from(u16_at(&header, 26));
+    let extra_len = u64::from(u16_at(&header, 28));
+    Ok(entry.header_offset + LOCAL_HEADER_SIZE as u64 + name_len + extra_len)
+}
+
+fn check_supported(entry: &Entry) -> io::Result<()> {
+    if entry.flags & FLAG_ENCRYPTED != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Zip member {} is encrypted", entry.name),
+        ));
+    }
+    if !matches!(entry.method, METHOD_STORED | METHOD_DEFLATED) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "Zip member {} uses unsupported compression method {}",
+                entry.name, entry.method
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Opens an entry for reading its decompressed content, which is validated against the CRC-32
+/// and size of the central directory at its end.
+pub fn open(archive: &Path, entry: &Entry) -> io::Result<Box<dyn Read + Send>> {
+    check_supported(entry)?;
+    let mut file = fs::File::open(archive)?;
+    let offset = data_offset(&mut file, entry, archive)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let data = file.take(entry.compressed_size);
+    let reader: Box<dyn Read + Send> = if entry.method == METHOD_DEFLATED {
+        Box::new(flate2::read::DeflateDecoder::new(BufReader::new(data)))
+    } else {
+        Box::new(data)
+    };
+    Ok(Box::new(VerifyingReader {
+        inner: reader,
+        hasher: crc32fast::Hasher::new(),
+        len: 0,
+        entry: entry.clone(),
+    }))
+}
+
+/// Returns the offset and size of the data of an entry stored without compression, for random
+/// access.
+pub fn stored_range(archive: &Path, entry: &Entry) -> io::Result<(u64, u64)> {
+    check_supported(entry)?;
+    if entry.method != METHOD_STORED {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "Zip member {} is compressed and cannot be read at random positions; store it without compression",
+                entry.name
+            ),
+        ));
+    }
+    let mut file = fs::File::open(archive)?;
+    Ok((data_offset(&mut file, entry, archive)?, entry.size))
+}
+
+/// Reader of the content of an entry that fails at the end if its CRC-32 or size differ from
+/// the central directory.
+struct VerifyingReader {
+    inner: Box<dyn Read + Send>,
+    hasher: crc32fast::Hasher,
+    len: u64,
+    entry: Entry,
+}
+
+impl VerifyingReader {
+    fn verify(&self) -> io::Result<()> {
+        if self.len != self.entry.size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Zip member {} has {} bytes, but {} bytes are declared in the central directory",
+                    self.entry.name, self.len, self.entry.size
+                ),
+            ));
+        }
+        let crc32 = self.hasher.clone().finalize();
+        if crc32 != self.entry.crc32 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Zip member {} has CRC-32 {crc32:08x}, but {:08x} is declared in the central directory",
+                    self.entry.name, self.entry.crc32
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Read for VerifyingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            self.verify()?;
+        }
+        self.hasher.update(&buf[..n]);
+        self.len += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::DeflateEncoder;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    /// Writes a zip archive of `(name, content, deflate)` members. With `zip64`, all sizes and
+    /// offsets are stored in zip64 fields.
+    fn write_zip(path: &Path, members: &[(&str, &[u8], bool)], zip64: bool) -> io::Result<()> {
+        let saturate = |value: u64| if zip64 { u32::MAX } else { value as u32 };
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for &(name, content, deflate) in members {
+            let data = if deflate {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(content)?;
+                encoder.finish()?
+            } else {
+                content.to_vec()
+            };
+            let crc32 = crc32fast::hash(content);
+            let method = if deflate {
+                METHOD_DEFLATED
+            } else {
+                METHOD_STORED
+            };
+            let offset = out.len() as u64;
+            let mut local_extra = Vec::new();
+            let mut central_extra = Vec::new();
+            if zip64 {
+                local_extra.extend(ZIP64_EXTRA_FIELD.to_le_bytes());
+                local_extra.extend(16u16.to_le_bytes());
+                local_extra.extend((content.len() as u64).to_le_bytes());
+                local_extra.extend((data.len() as u64).to_le_bytes());
+                central_extra.extend(ZIP64_EXTRA_FIELD.to_le_bytes());
+                central_extra.extend(24u16.to_le_bytes());
+                central_extra.extend((content.len() as u64).to_le_bytes());
+                central_extra.extend((data.len() as u64).to_le_bytes());
+                central_extra.extend(offset.to_le_bytes());
+            }
+
+            out.extend(LOCAL_HEADER_SIGNATURE);
+            out.extend(45u16.to_le_bytes());
+            out.extend(0u16.to_le_bytes());
+            out.extend(method.to_le_bytes());
+            out.extend([0; 4]);
+            out.extend(crc32.to_le_bytes());
+            out.extend(saturate(data.len() as u64).to_le_bytes());
+            out.extend(saturate(content.len() as u64).to_le_bytes());
+            out.extend((name.len() as u16).to_le_bytes());
+            out.extend((local_extra.len() as u16).to_le_bytes());
+            out.extend(name.as_bytes());
+            out.extend(&local_extra);
+            out.extend(&data);
+
+            central.extend(CENTRAL_HEADER_SIGNATURE);
+            central.extend(45u16.to_le_bytes());
+            central.extend(45u16.to_le_bytes());
+            central.extend(0u16.to_le_bytes());
+            central.extend(method.to_le_bytes());
+            central.extend([0; 4]);
+            central.extend(crc32.to_le_bytes());
+            central.extend(saturate(data.len() as u64).to_le_bytes());
+            central.extend(saturate(content.len() as u64).to_le_bytes());
+            central.extend((name.len() as u16).to_le_bytes());
+            central.extend((central_extra.len() as u16).to_le_bytes());
+            central.extend([0; 6]);
+            central.extend([0; 4]);
+            central.extend(saturate(offset).to_le_bytes());
+            central.extend(name.as_bytes());
+            central.extend(&central_extra);
+        }
+
+        let cd_offset = out.len() as u64;
+        let cd_size = central.len() as u64;
+        let num_entries = members.len() as u64;
+        out.extend(central);
+        if zip64 {
+            let record_offset = out.len() as u64;
+            out.extend(ZIP64_EOCD_SIGNATURE);
+            out.extend((ZIP64_EOCD_SIZE as u64 - 12).to_le_bytes());
+            out.extend(45u16.to_le_bytes());
+            out.extend(45u16.to_le_bytes());
+            out.extend([0; 8]);
+            out.extend(num_entries.to_le_bytes());
+            out.extend(num_entries.to_le_bytes());
+            out.extend(cd_size.to_le_bytes());
+            out.extend(cd_offset.to_le_bytes());
+            out.extend(ZIP64_LOCATOR_SIGNATURE);
+            out.extend(0u32.to_le_bytes());
+            out.extend(record_offset.to_le_bytes());
+            out.extend(1u32.to_le_bytes());
+        }
+        let entries_field = if zip64 { u16::MAX } else { num_entries as u16 };
+        out.extend(EOCD_SIGNATURE);
+        out.extend([0; 4]);
+        out.extend(entries_field.to_le_bytes());
+        out.extend(entries_field.to_le_bytes());
+        out.extend(saturate(cd_size).to_le_bytes());
+        out.extend(saturate(cd_offset).to_le_bytes());
+        out.extend(0u16.to_le_bytes());
+        fs::write(path, out)
+    }
+
+    fn read_member(archive: &Path, entry: &Entry) -> io::Result<Vec<u8>> {
+        let mut content = Vec::new();
+        open(archive, entry)?.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    #[test]
+    fn test_zip64_members_are_validated() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let content = b"@r\nACGT\n+\nIIII\n".repeat(1000);
+        for zip64 in [false, true] {
+            let archive = dir.path().join("run.zip");
+            write_zip(
+                &archive,
+                &[
+                    ("run/", b"", false),
+                    ("run/reads.fastq", &content, true),
+                    ("run/stored.txt", b"stored", false),
+                ],
+                zip64,
+            )?;
+            assert!(is_zip(&archive)?);
+
+            let entries = entries(&archive)?;
+            assert_eq!(entries.len(), 3);
+            assert!(!entries[0].is_file());
+            assert_eq!(entries[1].size, content.len() as u64);
+            assert_eq!(read_member(&archive, &entries[1])?, content);
+            assert_eq!(read_member(&archive, &entries[2])?, b"stored");
+            let (offset, size) = stored_range(&archive, &entries[2])?;
+            assert_eq!(size, 6);
+            assert_eq!(
+                read_at(&mut fs::File::open(&archive)?, offset, 6)?,
+                b"stored"
+            );
+            assert!(stored_range(&archive, &entries[1]).is_err());
+
+            let members = crate::archive::members(&archive)?;
+            assert_eq!(members.len(), 2);
+            assert_eq!(crate::archive::size(&members[0])?, content.len() as u64);
+            let mut read = Vec::new();
+            crate::archive::open(&members[0])?.read_to_end(&mut read)?;
+            assert_eq!(read, content);
+            assert_eq!(
+                crate::archive::open_section(&members[1])?.read_at(0, 6)?,
+                b"stored"
+            );
+
+            // a member declared larger than its data, as after truncation at 4 GiB
+            let mut truncated = entries[2].clone();
+            truncated.size += u64::from(u32::MAX) + 1;
+            let error = read_member(&archive, &truncated).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert!(error.to_string().contains("bytes are declared"), "{error}");
+
+            let mut corrupt = entries[1].clone();
+            corrupt.crc32 ^= 1;
+            let error = read_member(&archive, &corrupt).unwrap_err();
+            assert!(error.to_string().contains("CRC-32"), "{error}");
+        }
+        Ok(())
+    }
+}